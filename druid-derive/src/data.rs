@@ -16,8 +16,8 @@
 
 use crate::attr::{Field, FieldKind, Fields};
 
-use quote::{quote, quote_spanned};
-use syn::{spanned::Spanned, Data, DataEnum, DataStruct};
+use quote::quote;
+use syn::{parse_quote, spanned::Spanned, Data, DataEnum, DataStruct};
 
 pub(crate) fn derive_data_impl(
     input: syn::DeriveInput,
@@ -36,8 +36,8 @@ fn derive_struct(
     input: &syn::DeriveInput,
     s: &DataStruct,
 ) -> Result<proc_macro2::TokenStream, syn::Error> {
-    let generics_bounds = generics_bounds(&input.generics);
-    let generics = &input.generics;
+    let generics = generics_with_data_bounds(&input.generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     let ty = &input.ident;
     let fields = Fields::parse_ast(&s.fields)?;
@@ -54,7 +54,7 @@ fn derive_struct(
     };
 
     let res = quote! {
-        impl<#generics_bounds> druid::Data for #ty #generics {
+        impl #impl_generics ::druid::Data for #ty #ty_generics #where_clause {
             fn same(&self, other: &Self) -> bool {
                 #diff
             }
@@ -81,14 +81,17 @@ fn derive_enum(
     s: &DataEnum,
 ) -> Result<proc_macro2::TokenStream, syn::Error> {
     let ty = &input.ident;
+    let generics = generics_with_data_bounds(&input.generics);
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     if is_c_style_enum(&s) {
-        let generics_bounds = generics_bounds(&input.generics);
-        let generics = &input.generics;
-
+        // the variants carry no data, so they are the same if they are the
+        // same variant.
         let res = quote! {
-            impl<#generics_bounds> ::druid::Data for #ty #generics {
-                fn same(&self, other: &Self) -> bool { self == other }
+            impl #impl_generics ::druid::Data for #ty #ty_generics #where_clause {
+                fn same(&self, other: &Self) -> bool {
+                    ::std::mem::discriminant(self) == ::std::mem::discriminant(other)
+                }
             }
         };
         return Ok(res);
@@ -161,15 +164,19 @@ fn derive_enum(
         })
         .collect::<Result<Vec<proc_macro2::TokenStream>, syn::Error>>()?;
 
-    let generics_bounds = generics_bounds(&input.generics);
-    let generics = &input.generics;
+    // with a single variant, a catch-all arm would be unreachable.
+    let fallback = if s.variants.len() > 1 {
+        quote!(_ => false,)
+    } else {
+        quote!()
+    };
 
     let res = quote! {
-        impl<#generics_bounds> ::druid::Data for #ty #generics {
+        impl #impl_generics ::druid::Data for #ty #ty_generics #where_clause {
             fn same(&self, other: &Self) -> bool {
                 match (self, other) {
-                    #( #cases ),*
-                    _ => false,
+                    #( #cases, )*
+                    #fallback
                 }
             }
         }
@@ -178,15 +185,11 @@ fn derive_enum(
     Ok(res)
 }
 
-fn generics_bounds(generics: &syn::Generics) -> proc_macro2::TokenStream {
-    let res = generics.params.iter().map(|gp| {
-        use syn::GenericParam::*;
-        match gp {
-            Type(ty) => quote_spanned!(ty.span()=> #ty : ::druid::Data),
-            Lifetime(lf) => quote!(#lf),
-            Const(cst) => quote!(#cst),
-        }
-    });
-
-    quote!( #( #res, )* )
+/// The input's generics, with a `Data` bound added to every type parameter.
+fn generics_with_data_bounds(generics: &syn::Generics) -> syn::Generics {
+    let mut generics = generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::druid::Data));
+    }
+    generics
 }
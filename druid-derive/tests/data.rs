@@ -71,3 +71,45 @@ fn test_data_derive_same() {
         })
     );
 }
+
+// not `PartialEq`; variants are compared by discriminant.
+#[derive(Data, Clone)]
+enum CStyleEnum {
+    A,
+    B,
+}
+
+#[derive(Data, Clone)]
+enum SingleVariantEnum {
+    Only { a: i64 },
+}
+
+#[derive(Data, Clone)]
+enum GenericEnum<T: Clone, U>
+where
+    U: Clone,
+{
+    Named { a: T, b: bool },
+    Tuple(U, String),
+    Unit,
+}
+
+#[test]
+fn test_data_derive_same_enums() {
+    assert!(CStyleEnum::A.same(&CStyleEnum::A));
+    assert!(!CStyleEnum::A.same(&CStyleEnum::B));
+
+    let single = SingleVariantEnum::Only { a: 1 };
+    assert!(single.same(&single.clone()));
+    assert!(!single.same(&SingleVariantEnum::Only { a: 2 }));
+
+    let named: GenericEnum<i64, f64> = GenericEnum::Named { a: 1, b: true };
+    let tuple: GenericEnum<i64, f64> = GenericEnum::Tuple(1.0, "one".to_string());
+    assert!(named.same(&named.clone()));
+    assert!(!named.same(&GenericEnum::Named { a: 2, b: true }));
+    assert!(tuple.same(&tuple.clone()));
+    assert!(!tuple.same(&GenericEnum::Tuple(1.0, "two".to_string())));
+    assert!(!named.same(&tuple));
+    assert!(GenericEnum::<i64, f64>::Unit.same(&GenericEnum::Unit));
+    assert!(!named.same(&GenericEnum::Unit));
+}
@@ -37,7 +37,7 @@ impl WinHandler for PerfTest {
         self.handle = handle.clone();
     }
 
-    fn paint(&mut self, piet: &mut Piet, _ctx: &mut dyn WinCtx) -> bool {
+    fn paint(&mut self, piet: &mut Piet, _: Rect, _ctx: &mut dyn WinCtx) -> bool {
         let (width, height) = self.size;
         let rect = Rect::new(0.0, 0.0, width, height);
         piet.fill(rect, &BG_COLOR);
@@ -19,7 +19,8 @@ use druid_shell::piet::{Color, RenderContext};
 
 use druid_shell::{
     Application, Cursor, FileDialogOptions, FileSpec, HotKey, KeyEvent, KeyModifiers, Menu,
-    MouseEvent, RunLoop, SysMods, TimerToken, WinCtx, WinHandler, WindowBuilder, WindowHandle,
+    MouseEvent, RunLoop, SysMods, TimerToken, WheelPhase, WheelUnit, WinCtx, WinHandler,
+    WindowBuilder, WindowHandle,
 };
 
 const BG_COLOR: Color = Color::rgb8(0x27, 0x28, 0x22);
@@ -36,7 +37,7 @@ impl WinHandler for HelloState {
         self.handle = handle.clone();
     }
 
-    fn paint(&mut self, piet: &mut piet_common::Piet, _ctx: &mut dyn WinCtx) -> bool {
+    fn paint(&mut self, piet: &mut piet_common::Piet, _: Rect, _ctx: &mut dyn WinCtx) -> bool {
         let (width, height) = self.size;
         let rect = Rect::new(0.0, 0.0, width, height);
         piet.fill(rect, &BG_COLOR);
@@ -70,8 +71,15 @@ impl WinHandler for HelloState {
         false
     }
 
-    fn wheel(&mut self, delta: Vec2, mods: KeyModifiers, _ctx: &mut dyn WinCtx) {
-        println!("mouse_wheel {:?} {:?}", delta, mods);
+    fn wheel(
+        &mut self,
+        delta: Vec2,
+        unit: WheelUnit,
+        phase: WheelPhase,
+        mods: KeyModifiers,
+        _ctx: &mut dyn WinCtx,
+    ) {
+        println!("mouse_wheel {:?} {:?} {:?} {:?}", delta, unit, phase, mods);
     }
 
     fn mouse_move(&mut self, event: &MouseEvent, ctx: &mut dyn WinCtx) {
@@ -33,6 +33,19 @@ impl Application {
         platform::Application::quit()
     }
 
+    /// Block until the next platform event arrives, and dispatch it.
+    ///
+    /// This is the building block for nested (modal) event loops, which run
+    /// inside a handler callback while the main run loop is suspended. The
+    /// handlers of windows that are still on the stack cannot be re-entered,
+    /// so the platform drops events for those windows until they return.
+    ///
+    /// Returns `false` if the application is quitting; the caller should then
+    /// stop pumping and return to the main run loop.
+    pub fn pump_events() -> bool {
+        platform::Application::pump_events()
+    }
+
     // TODO: do these two go in some kind of PlatformExt trait?
     /// Hide the application this window belongs to. (cmd+H)
     pub fn hide() {
@@ -15,6 +15,8 @@
 //! Interacting with the system pasteboard/clipboard.
 pub use crate::platform::clipboard as platform;
 
+use std::sync::{Arc, Mutex};
+
 /// A handle to the system clipboard.
 ///
 /// To get access to the global clipboard, call [`Application::clipboard()`].
@@ -118,7 +120,22 @@ pub use crate::platform::clipboard as platform;
 /// # fn do_something_with_data(_: &str, _: Vec<u8>) {}
 /// ```
 ///
+/// ## Inspecting the clipboard
+///
+/// [`Clipboard::available_formats`] lists the identifiers of everything that
+/// is currently on the clipboard, in the order of preference of whoever put it
+/// there. Note that on Windows the standard formats are reported by their
+/// `CF_` names, rather than by the identifiers used to put them.
+///
+/// ## Testing
+///
+/// A clipboard created with [`Clipboard::in_memory`] is not shared with the
+/// system, and behaves the same everywhere; it can be used to test copy and
+/// paste without touching the user's clipboard.
+///
 /// [`Application::clipboard()`]: struct.Application.html#method.clipboard
+/// [`Clipboard::available_formats`]: struct.Clipboard.html#method.available_formats
+/// [`Clipboard::in_memory`]: struct.Clipboard.html#method.in_memory
 /// [`Clipboard::put_string`]: struct.Clipboard.html#method.put_string
 /// [`Clipboard::get_string`]: struct.Clipboard.html#method.get_string
 /// [`FormatId`]: type.FormatId.html
@@ -126,28 +143,66 @@ pub use crate::platform::clipboard as platform;
 /// [MIME types]: https://developer.mozilla.org/en-US/docs/Web/HTTP/Basics_of_HTTP/MIME_types
 /// [`ClipboardFormat`]: struct.ClipboardFormat.html
 #[derive(Debug, Clone)]
-pub struct Clipboard(platform::Clipboard);
+pub struct Clipboard(Inner);
+
+#[derive(Debug, Clone)]
+enum Inner {
+    System(platform::Clipboard),
+    /// The formats most recently put on the clipboard, in order of preference.
+    Memory(Arc<Mutex<Vec<ClipboardFormat>>>),
+}
 
 impl Clipboard {
+    /// Create a clipboard that is private to this process.
+    ///
+    /// Its contents are shared between clones, but never with the system
+    /// clipboard. This is mostly useful for testing.
+    pub fn in_memory() -> Clipboard {
+        Clipboard(Inner::Memory(Default::default()))
+    }
+
     /// Put a string onto the system clipboard.
     pub fn put_string(&mut self, s: impl AsRef<str>) {
-        self.0.put_string(s);
+        if let Inner::System(clipboard) = &mut self.0 {
+            clipboard.put_string(s);
+        } else {
+            self.put_formats(&[s.as_ref().into()]);
+        }
     }
 
     /// Put multi-format data on the system clipboard.
+    ///
+    /// This replaces everything that was on the clipboard. The formats should
+    /// be in order of preference, the most specific first.
     pub fn put_formats(&mut self, formats: &[ClipboardFormat]) {
-        self.0.put_formats(formats)
+        match &mut self.0 {
+            Inner::System(clipboard) => clipboard.put_formats(formats),
+            Inner::Memory(contents) => *contents.lock().unwrap() = formats.to_owned(),
+        }
     }
 
     /// Get a string from the system clipboard, if one is available.
     pub fn get_string(&self) -> Option<String> {
-        self.0.get_string()
+        match &self.0 {
+            Inner::System(clipboard) => clipboard.get_string(),
+            Inner::Memory(_) => self
+                .get_format(ClipboardFormat::TEXT)
+                .and_then(|data| String::from_utf8(data).ok()),
+        }
     }
 
     /// Given a list of supported clipboard types, returns the supported type which has
     /// highest priority on the system clipboard, or `None` if no types are supported.
     pub fn preferred_format(&self, formats: &[FormatId]) -> Option<FormatId> {
-        self.0.preferred_format(formats)
+        match &self.0 {
+            Inner::System(clipboard) => clipboard.preferred_format(formats),
+            Inner::Memory(contents) => contents
+                .lock()
+                .unwrap()
+                .iter()
+                .find_map(|fmt| formats.iter().find(|id| **id == fmt.identifier))
+                .copied(),
+        }
     }
 
     /// Return data in a given format, if available.
@@ -158,14 +213,44 @@ impl Clipboard {
     /// [`Clipboard::preferred_format`]: struct.Clipboard.html#method.preferred_format
     /// [`FormatId`]: type.FormatId.html
     pub fn get_format(&self, format: FormatId) -> Option<Vec<u8>> {
-        self.0.get_format(format)
+        match &self.0 {
+            Inner::System(clipboard) => clipboard.get_format(format),
+            Inner::Memory(contents) => contents
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|fmt| fmt.identifier == format)
+                .map(|fmt| fmt.data.clone()),
+        }
+    }
+
+    /// The identifiers of every format currently on the clipboard, in order
+    /// of preference.
+    ///
+    /// This is useful for deciding whether to offer a paste at all; to pick
+    /// between the formats you support, use [`Clipboard::preferred_format`].
+    ///
+    /// [`Clipboard::preferred_format`]: struct.Clipboard.html#method.preferred_format
+    pub fn available_formats(&self) -> Vec<String> {
+        match &self.0 {
+            Inner::System(clipboard) => clipboard.available_formats(),
+            Inner::Memory(contents) => contents
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|fmt| fmt.identifier.to_string())
+                .collect(),
+        }
     }
 
     /// For debugging: print the resolved identifiers for each type currently
     /// on the clipboard.
     #[doc(hidden)]
     pub fn available_type_names(&self) -> Vec<String> {
-        self.0.available_type_names()
+        match &self.0 {
+            Inner::System(clipboard) => clipboard.available_type_names(),
+            Inner::Memory(_) => self.available_formats(),
+        }
     }
 }
 
@@ -210,7 +295,7 @@ impl From<&str> for ClipboardFormat {
 
 impl From<platform::Clipboard> for Clipboard {
     fn from(src: platform::Clipboard) -> Clipboard {
-        Clipboard(src)
+        Clipboard(Inner::System(src))
     }
 }
 
@@ -220,6 +305,9 @@ cfg_if::cfg_if! {
             pub const PDF: &'static str = "com.adobe.pdf";
             pub const TEXT: &'static str = "public.utf8-plain-text";
             pub const SVG: &'static str = "public.svg-image";
+            pub const HTML: &'static str = "public.html";
+            pub const RTF: &'static str = "public.rtf";
+            pub const PNG: &'static str = "public.png";
         }
     } else {
         impl ClipboardFormat {
@@ -233,6 +321,48 @@ cfg_if::cfg_if! {
             }
             pub const PDF: &'static str = "application/pdf";
             pub const SVG: &'static str = "image/svg+xml";
+            pub const HTML: &'static str = "text/html";
+            pub const RTF: &'static str = "text/rtf";
+            pub const PNG: &'static str = "image/png";
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_formats() {
+        const CUSTOM: FormatId = "io.xieditor.path-clipboard-type";
+        let html = "<b>bold</b>";
+
+        let mut clipboard = Clipboard::in_memory();
+        clipboard.put_formats(&[
+            ClipboardFormat::new(CUSTOM, vec![1, 2, 3]),
+            ClipboardFormat::new(ClipboardFormat::HTML, html),
+            ClipboardFormat::from("bold"),
+        ]);
+
+        // a paste target sees everything, and gets the best format it supports.
+        let pasted = clipboard.clone();
+        assert_eq!(
+            pasted.available_formats(),
+            vec![CUSTOM, ClipboardFormat::HTML, ClipboardFormat::TEXT]
+        );
+        let supported = [ClipboardFormat::TEXT, ClipboardFormat::HTML];
+        assert_eq!(
+            pasted.preferred_format(&supported),
+            Some(ClipboardFormat::HTML)
+        );
+        assert_eq!(pasted.get_format(ClipboardFormat::HTML), Some(html.into()));
+        assert_eq!(pasted.get_format(CUSTOM), Some(vec![1, 2, 3]));
+        assert_eq!(pasted.get_string().as_deref(), Some("bold"));
+        assert_eq!(pasted.get_format(ClipboardFormat::PDF), None);
+
+        // putting a string replaces everything else.
+        clipboard.put_string("plain");
+        assert_eq!(pasted.preferred_format(&[CUSTOM]), None);
+        assert_eq!(pasted.get_string().as_deref(), Some("plain"));
+    }
+}
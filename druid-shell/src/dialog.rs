@@ -16,13 +16,34 @@
 
 use std::path::{Path, PathBuf};
 
+use crate::common_util::Counter;
+
 /// Information about a file to be opened or saved.
 #[derive(Debug, Clone)]
 pub struct FileInfo {
     pub(crate) path: PathBuf,
 }
 
+/// A token that identifies a file dialog that is still open.
+///
+/// It is returned by [`WinCtx::open_file`] and [`WinCtx::save_as`], and is
+/// passed back to the handler with the result of the dialog.
+///
+/// [`WinCtx::open_file`]: trait.WinCtx.html#tymethod.open_file
+/// [`WinCtx::save_as`]: trait.WinCtx.html#tymethod.save_as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Hash)]
+pub struct FileDialogToken(u64);
+
+impl FileDialogToken {
+    /// Create a new token.
+    pub fn next() -> FileDialogToken {
+        static DIALOG_COUNTER: Counter = Counter::new();
+        FileDialogToken(DIALOG_COUNTER.next())
+    }
+}
+
 /// Type of file dialog.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum FileDialogType {
     /// File open dialog.
     Open,
@@ -68,6 +89,11 @@ pub struct FileSpec {
 }
 
 impl FileInfo {
+    /// Information about the file at this path.
+    pub fn new(path: impl Into<PathBuf>) -> FileInfo {
+        FileInfo { path: path.into() }
+    }
+
     /// The file's path.
     pub fn path(&self) -> &Path {
         &self.path
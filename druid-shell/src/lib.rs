@@ -23,6 +23,8 @@
 
 pub use piet_common as piet;
 pub use piet_common::kurbo;
+#[cfg(feature = "raw-win-handle")]
+pub use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
 
 #[cfg(target_os = "windows")]
 #[macro_use]
@@ -50,19 +52,22 @@ mod mouse;
 //a bunch of compiler warnings, so let's revisit that later.
 pub mod platform;
 mod runloop;
+mod screen;
 mod window;
 
 pub use application::Application;
 pub use clipboard::{Clipboard, ClipboardFormat, FormatId};
 pub use common_util::Counter;
-pub use dialog::{FileDialogOptions, FileInfo, FileSpec};
+pub use dialog::{FileDialogOptions, FileDialogToken, FileInfo, FileSpec};
 pub use error::Error;
 pub use hotkey::{HotKey, KeyCompare, RawMods, SysMods};
 pub use keyboard::{KeyEvent, KeyModifiers};
 pub use keycodes::KeyCode;
 pub use menu::Menu;
-pub use mouse::{Cursor, MouseButton, MouseEvent};
+pub use mouse::{Cursor, MouseButton, MouseEvent, WheelPhase, WheelUnit};
 pub use runloop::RunLoop;
+pub use screen::{Monitor, Screen};
 pub use window::{
-    IdleHandle, IdleToken, Text, TimerToken, WinCtx, WinHandler, WindowBuilder, WindowHandle,
+    IdleHandle, IdleToken, PowerEvent, Text, TimerToken, WinCtx, WinHandler, WindowBuilder,
+    WindowHandle,
 };
@@ -64,11 +64,44 @@ impl MouseButton {
     }
 }
 
+/// The unit of the delta of a wheel event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WheelUnit {
+    /// Display points, as reported by precise devices such as trackpads.
+    Pixels,
+    /// Lines of text, as reported by notched mouse wheels.
+    Lines,
+    /// Whole pages, for mouse wheels the user has set to scroll by page.
+    Pages,
+}
+
+/// Where a wheel event falls in a scrolling gesture.
+///
+/// Trackpads on some platforms report a gesture from when the fingers
+/// touch down to when they lift, followed by events of inertial
+/// "momentum" scrolling. A mouse wheel, or a platform that doesn't report
+/// phases, has no gesture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WheelPhase {
+    /// The event is not part of a gesture.
+    None,
+    /// The first event of a gesture.
+    Began,
+    /// An event in the middle of a gesture.
+    Changed,
+    /// The last event while the fingers are down; momentum may follow.
+    Ended,
+    /// An event of momentum scrolling, after the fingers have lifted.
+    Momentum,
+    /// The last event of momentum scrolling, which ends the gesture.
+    MomentumEnded,
+}
+
 //NOTE: this currently only contains cursors that are included by default on
 //both Windows and macOS. We may want to provide polyfills for various additional cursors,
 //and we will also want to add some mechanism for adding custom cursors.
 /// Mouse cursors.
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Cursor {
     /// The default arrow cursor.
     Arrow,
@@ -42,6 +42,12 @@ impl Application {
         });
     }
 
+    pub fn pump_events() -> bool {
+        util::assert_main_thread();
+        // `main_iteration` returns `true` once `main_quit` has been called.
+        !gtk::main_iteration()
+    }
+
     pub fn clipboard() -> Clipboard {
         Clipboard
     }
@@ -95,17 +95,29 @@ impl Clipboard {
             .map(|data| data.get_data())
     }
 
+    /// The identifiers of every format currently on the clipboard.
+    pub fn available_formats(&self) -> Vec<String> {
+        available_targets()
+            .iter()
+            .map(|atom| atom.name().to_string())
+            .collect()
+    }
+
     pub fn available_type_names(&self) -> Vec<String> {
-        let display = gdk::Display::get_default().unwrap();
-        let clipboard = gtk::Clipboard::get_default(&display).unwrap();
-        let targets = clipboard.wait_for_targets().unwrap_or_default();
-        targets
+        available_targets()
             .iter()
             .map(|atom| unsafe { format!("{} ({})", atom.name(), atom.value()) })
             .collect()
     }
 }
 
+/// The targets currently on the clipboard.
+fn available_targets() -> Vec<Atom> {
+    let display = gdk::Display::get_default().unwrap();
+    let clipboard = gtk::Clipboard::get_default(&display).unwrap();
+    clipboard.wait_for_targets().unwrap_or_default()
+}
+
 fn make_entries(formats: &[ClipboardFormat]) -> Vec<TargetEntry> {
     formats
         .iter()
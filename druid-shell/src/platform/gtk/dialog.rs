@@ -14,10 +14,13 @@
 
 //! File open/save dialogs, GTK implementation.
 
+use std::cell::RefCell;
 use std::ffi::OsString;
 
 use crate::dialog::{FileDialogOptions, FileDialogType};
-use gtk::{FileChooserAction, FileChooserExt, NativeDialogExt, ResponseType, Window};
+use gtk::{
+    FileChooserAction, FileChooserExt, FileChooserNative, NativeDialogExt, ResponseType, Window,
+};
 
 use crate::Error;
 
@@ -26,6 +29,40 @@ pub(crate) fn get_file_dialog_path(
     ty: FileDialogType,
     options: FileDialogOptions,
 ) -> Result<OsString, Error> {
+    let dialog = build_dialog(window, ty, options);
+    let result = dialog.run();
+    let result = dialog_result(&dialog, result);
+    dialog.destroy();
+    result
+}
+
+/// Show a file dialog without blocking; `callback` is called with the
+/// result once the dialog is dismissed.
+pub(crate) fn show_file_dialog(
+    window: &Window,
+    ty: FileDialogType,
+    options: FileDialogOptions,
+    callback: impl FnOnce(Result<OsString, Error>) + 'static,
+) {
+    let dialog = build_dialog(window, ty, options);
+    // the signal handler owns the only reference that keeps the dialog
+    // alive while it is shown; it lets go of it once the dialog is done.
+    let state = RefCell::new(Some((dialog.clone(), callback)));
+    dialog.connect_response(move |dialog, response| {
+        if let Some((_dialog, callback)) = state.borrow_mut().take() {
+            let result = dialog_result(dialog, response);
+            dialog.destroy();
+            callback(result);
+        }
+    });
+    dialog.show();
+}
+
+fn build_dialog(
+    window: &Window,
+    ty: FileDialogType,
+    options: FileDialogOptions,
+) -> FileChooserNative {
     // TODO: support message localization
     let (title, action) = match ty {
         FileDialogType::Open => ("Open File", FileChooserAction::Open),
@@ -41,9 +78,12 @@ pub(crate) fn get_file_dialog_path(
 
     dialog.set_show_hidden(options.show_hidden);
 
-    let result = dialog.run();
+    dialog
+}
 
-    let result = match result {
+fn dialog_result(dialog: &FileChooserNative, result: ResponseType) -> Result<OsString, Error> {
+    // TODO properly handle errors into the Error type
+    match result {
         ResponseType::Accept => match dialog.get_filename() {
             Some(path) => Ok(path.into_os_string()),
             None => Err(Error::Other("No path received for filename")),
@@ -53,11 +93,5 @@ pub(crate) fn get_file_dialog_path(
             log::warn!("Unhandled dialog result: {:?}", result);
             Err(Error::Other("Unhandled dialog result"))
         }
-    };
-
-    // TODO properly handle errors into the Error type
-
-    dialog.destroy();
-
-    result
+    }
 }
@@ -21,5 +21,6 @@ pub mod error;
 pub mod keycodes;
 pub mod menu;
 pub mod runloop;
+pub mod screen;
 pub mod util;
 pub mod window;
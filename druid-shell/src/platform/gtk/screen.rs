@@ -0,0 +1,51 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! GTK monitors.
+
+use gdk::Rectangle;
+
+use crate::kurbo::Rect;
+use crate::screen::Monitor;
+
+fn to_rect(rect: Rectangle) -> Rect {
+    Rect::new(
+        rect.x as f64,
+        rect.y as f64,
+        (rect.x + rect.width) as f64,
+        (rect.y + rect.height) as f64,
+    )
+}
+
+pub(crate) fn get_monitors() -> Vec<Monitor> {
+    let screen = match gdk::Screen::get_default() {
+        Some(screen) => screen,
+        None => {
+            log::warn!("no default screen");
+            return Vec::new();
+        }
+    };
+    let primary = screen.get_primary_monitor();
+    // GDK's coordinates are already in px, as are the window's.
+    (0..screen.get_n_monitors())
+        .map(|i| {
+            Monitor::new(
+                i == primary,
+                to_rect(screen.get_monitor_geometry(i)),
+                to_rect(screen.get_monitor_workarea(i)),
+                screen.get_monitor_scale_factor(i) as f64,
+            )
+        })
+        .collect()
+}
@@ -25,12 +25,19 @@ use std::slice;
 use std::sync::{Arc, Mutex, Weak};
 use std::time::Instant;
 
-use gdk::{EventKey, EventMask, ModifierType, ScrollDirection, WindowExt};
+use gdk::{
+    EventKey, EventMask, EventScroll, InputSource, ModifierType, ScrollDirection, WindowExt,
+};
 use gio::ApplicationExt;
 use gtk::prelude::*;
 use gtk::{AccelGroup, ApplicationWindow};
 
-use crate::kurbo::{Point, Size, Vec2};
+#[cfg(feature = "raw-win-handle")]
+use glib::translate::{from_glib, ToGlibPtr};
+#[cfg(feature = "raw-win-handle")]
+use raw_window_handle::{unix::XlibHandle, RawWindowHandle};
+
+use crate::kurbo::{Point, Rect, Size, Vec2};
 use crate::piet::{Piet, RenderContext};
 
 use super::dialog;
@@ -39,12 +46,19 @@ use super::runloop::with_application;
 use super::util::assert_main_thread;
 
 use crate::common_util::IdleCallback;
-use crate::dialog::{FileDialogOptions, FileDialogType, FileInfo};
+use crate::dialog::{FileDialogOptions, FileDialogToken, FileDialogType, FileInfo};
 use crate::keyboard;
-use crate::mouse::{Cursor, MouseButton, MouseEvent};
+use crate::mouse::{Cursor, MouseButton, MouseEvent, WheelPhase, WheelUnit};
 use crate::window::{IdleToken, Text, TimerToken, WinCtx, WinHandler};
 use crate::Error;
 
+/// The number of lines scrolled by one notch of a mouse wheel.
+const LINES_PER_NOTCH: f64 = 3.0;
+
+/// The number of px in a unit of a touchpad's smooth scroll delta; GDK
+/// divides the pixel deltas it is given by this.
+const PIXELS_PER_SMOOTH_UNIT: f64 = 10.0;
+
 /// Taken from https://gtk-rs.org/docs-src/tutorial/closures
 /// It is used to reduce the boilerplate of setting up gtk callbacks
 /// Example:
@@ -86,6 +100,9 @@ pub struct WindowBuilder {
     title: String,
     menu: Option<Menu>,
     size: Size,
+    resizable: bool,
+    always_on_top: bool,
+    transparent: bool,
 }
 
 #[derive(Clone)]
@@ -102,9 +119,22 @@ enum IdleKind {
 
 pub(crate) struct WindowState {
     window: ApplicationWindow,
+    /// The area the handler paints, below the menu bar.
+    drawing_area: gtk::DrawingArea,
     pub(crate) handler: RefCell<Box<dyn WinHandler>>,
     idle_queue: Arc<Mutex<Vec<IdleKind>>>,
     current_keyval: RefCell<Option<u32>>,
+    /// The files of the drag over this window, once they have been received.
+    drag_files: RefCell<Option<Vec<FileInfo>>>,
+    /// Set when files were dropped, and we are waiting for their data.
+    pending_drop: Cell<bool>,
+    im_context: gtk::IMMulticontext,
+    /// The key being filtered by the input method.
+    pending_key: RefCell<Option<keyboard::KeyEvent>>,
+    /// Set while the input method has text under composition.
+    composing: Cell<bool>,
+    /// Set during a touchpad scroll gesture, until its stop event.
+    scroll_gesture: Cell<bool>,
 }
 
 pub(crate) struct WinCtxImpl<'a> {
@@ -119,6 +149,9 @@ impl WindowBuilder {
             title: String::new(),
             menu: None,
             size: Size::new(500.0, 400.0),
+            resizable: true,
+            always_on_top: false,
+            transparent: false,
         }
     }
 
@@ -138,6 +171,18 @@ impl WindowBuilder {
         self.menu = Some(menu);
     }
 
+    pub fn set_resizable(&mut self, resizable: bool) {
+        self.resizable = resizable;
+    }
+
+    pub fn set_always_on_top(&mut self, always_on_top: bool) {
+        self.always_on_top = always_on_top;
+    }
+
+    pub fn set_transparent(&mut self, transparent: bool) {
+        self.transparent = transparent;
+    }
+
     pub fn build(self) -> Result<WindowHandle, Error> {
         assert_main_thread();
 
@@ -148,6 +193,19 @@ impl WindowBuilder {
         let window = with_application(|app| ApplicationWindow::new(&app));
 
         window.set_title(&self.title);
+        window.set_resizable(self.resizable);
+        window.set_keep_above(self.always_on_top);
+        if self.transparent {
+            // an RGBA visual needs a compositing window manager.
+            let visual = window.get_screen().and_then(|s| s.get_rgba_visual());
+            match visual {
+                Some(visual) => {
+                    window.set_visual(Some(&visual));
+                    window.set_app_paintable(true);
+                }
+                None => log::warn!("transparent windows are not supported on this screen"),
+            }
+        }
 
         let dpi_scale = window
             .get_display()
@@ -166,11 +224,20 @@ impl WindowBuilder {
         let vbox = gtk::Box::new(gtk::Orientation::Vertical, 0);
         window.add(&vbox);
 
+        let drawing_area = gtk::DrawingArea::new();
+
         let win_state = Arc::new(WindowState {
             window,
+            drawing_area: drawing_area.clone(),
             handler: RefCell::new(handler),
             idle_queue: Arc::new(Mutex::new(vec![])),
             current_keyval: RefCell::new(None),
+            drag_files: RefCell::new(None),
+            pending_drop: Cell::new(false),
+            im_context: gtk::IMMulticontext::new(),
+            pending_key: RefCell::new(None),
+            composing: Cell::new(false),
+            scroll_gesture: Cell::new(false),
         });
 
         with_application(|app| {
@@ -191,8 +258,6 @@ impl WindowBuilder {
             vbox.pack_start(&menu, false, false, 0);
         }
 
-        let drawing_area = gtk::DrawingArea::new();
-
         drawing_area.set_events(
             EventMask::EXPOSURE_MASK
                 | EventMask::POINTER_MOTION_MASK
@@ -200,6 +265,7 @@ impl WindowBuilder {
                 | EventMask::BUTTON_RELEASE_MASK
                 | EventMask::KEY_PRESS_MASK
                 | EventMask::ENTER_NOTIFY_MASK
+                | EventMask::LEAVE_NOTIFY_MASK
                 | EventMask::KEY_RELEASE_MASK
                 | EventMask::SCROLL_MASK
                 | EventMask::SMOOTH_SCROLL_MASK,
@@ -215,23 +281,35 @@ impl WindowBuilder {
         });
 
         let last_size = Cell::new((0, 0));
+        let last_scale = Cell::new(0.0);
 
         drawing_area.connect_draw(clone!(handle => move |widget, context| {
             if let Some(state) = handle.state.upgrade() {
                 let mut ctx = WinCtxImpl::from(&handle);
 
                 let extents = context.clip_extents();
+                let invalid_rect = Rect::new(extents.0, extents.1, extents.2, extents.3);
                 let dpi_scale = state.window.get_window()
                     .map(|w| w.get_display().get_default_screen().get_resolution())
                     .unwrap_or(96.0) / 96.0;
+                // the clip only covers the invalid part of the window.
                 let size = (
-                    ((extents.2 - extents.0) * dpi_scale) as u32,
-                    ((extents.3 - extents.1) * dpi_scale) as u32,
+                    (f64::from(widget.get_allocated_width()) * dpi_scale) as u32,
+                    (f64::from(widget.get_allocated_height()) * dpi_scale) as u32,
                 );
 
+                if last_scale.get() != dpi_scale {
+                    if let Ok(mut handler) = state.handler.try_borrow_mut() {
+                        last_scale.set(dpi_scale);
+                        handler.scale(dpi_scale, &mut ctx);
+                    }
+                }
+
                 if last_size.get() != size {
-                    last_size.set(size);
-                    state.handler.borrow_mut().size(size.0, size.1, &mut ctx);
+                    if let Ok(mut handler) = state.handler.try_borrow_mut() {
+                        last_size.set(size);
+                        handler.size(size.0, size.1, &mut ctx);
+                    }
                 }
 
                 // For some reason piet needs a mutable context, so give it one I guess.
@@ -240,7 +318,7 @@ impl WindowBuilder {
 
                 if let Ok(mut handler_borrow) = state.handler.try_borrow_mut() {
                     let anim = handler_borrow
-                        .paint(&mut piet_context, &mut ctx);
+                        .paint(&mut piet_context, invalid_rect, &mut ctx);
                     if let Err(e) = piet_context.finish() {
                         eprintln!("piet error on render: {:?}", e);
                     }
@@ -256,18 +334,27 @@ impl WindowBuilder {
         }));
 
         drawing_area.connect_button_press_event(clone!(handle => move |_widget, button| {
+            // GTK follows the second and third presses of a multi-click with
+            // an extra event of its own; the press itself has been reported.
+            if button.get_event_type() != gdk::EventType::ButtonPress {
+                return Inhibit(true);
+            }
             if let Some(state) = handle.state.upgrade() {
                 let mut ctx = WinCtxImpl::from(&handle);
 
-                state.handler.borrow_mut().mouse_down(
-                    &MouseEvent {
-                        pos: Point::from(button.get_position()),
-                        count: get_mouse_click_count(button.get_event_type()),
-                        mods: get_modifiers(button.get_state()),
-                        button: get_mouse_button(button.get_button()),
-                    },
-                    &mut ctx,
-                );
+                // The handler is already borrowed if it is running a nested
+                // event loop; input for its window is dropped until it returns.
+                if let Ok(mut handler) = state.handler.try_borrow_mut() {
+                    handler.mouse_down(
+                        &MouseEvent {
+                            pos: Point::from(button.get_position()),
+                            count: 1,
+                            mods: get_modifiers(button.get_state()),
+                            button: get_mouse_button(button.get_button()),
+                        },
+                        &mut ctx,
+                    );
+                }
             }
 
             Inhibit(true)
@@ -277,15 +364,17 @@ impl WindowBuilder {
             if let Some(state) = handle.state.upgrade() {
                 let mut ctx = WinCtxImpl::from(&handle);
 
-                state.handler.borrow_mut().mouse_up(
-                    &MouseEvent {
-                        pos: Point::from(button.get_position()),
-                        mods: get_modifiers(button.get_state()),
-                        count: 0,
-                        button: get_mouse_button(button.get_button()),
-                    },
-                    &mut ctx,
-                );
+                if let Ok(mut handler) = state.handler.try_borrow_mut() {
+                    handler.mouse_up(
+                        &MouseEvent {
+                            pos: Point::from(button.get_position()),
+                            mods: get_modifiers(button.get_state()),
+                            count: 0,
+                            button: get_mouse_button(button.get_button()),
+                        },
+                        &mut ctx,
+                    );
+                }
             }
 
             Inhibit(true)
@@ -303,51 +392,73 @@ impl WindowBuilder {
                     button: get_mouse_button_from_modifiers(motion.get_state()),
                 };
 
-                state
-                    .handler
-                    .borrow_mut()
-                    .mouse_move(&mouse_event, &mut ctx);
+                if let Ok(mut handler) = state.handler.try_borrow_mut() {
+                    handler.mouse_move(&mouse_event, &mut ctx);
+                }
             }
 
             Inhibit(true)
         }));
 
+        drawing_area.connect_leave_notify_event(clone!(handle => move |_widget, crossing| {
+            // grabs and ungrabs also cause crossing events; only a real
+            // departure of the pointer counts.
+            if crossing.get_mode() != gdk::CrossingMode::Normal {
+                return Inhibit(true);
+            }
+            if let Some(state) = handle.state.upgrade() {
+                let mut ctx = WinCtxImpl::from(&handle);
+                if let Ok(mut handler) = state.handler.try_borrow_mut() {
+                    handler.mouse_leave(&mut ctx);
+                }
+            }
+            Inhibit(true)
+        }));
+
         drawing_area.connect_scroll_event(clone!(handle => move |_widget, scroll| {
             if let Some(state) = handle.state.upgrade() {
                 let mut ctx = WinCtxImpl::from(&handle);
 
                 let modifiers = get_modifiers(scroll.get_state());
 
-                // The magic "120"s are from Microsoft's documentation for WM_MOUSEWHEEL.
-                // They claim that one "tick" on a scroll wheel should be 120 units.
-                let mut handler = state.handler.borrow_mut();
-                match scroll.get_direction() {
-                    ScrollDirection::Up => {
-                        handler.wheel(Vec2::from((0.0, -120.0)), modifiers, &mut ctx);
-                    }
-                    ScrollDirection::Down => {
-                        handler.wheel(Vec2::from((0.0, 120.0)), modifiers, &mut ctx);
-                    }
-                    ScrollDirection::Left => {
-                        handler.wheel(Vec2::from((-120.0, 0.0)), modifiers, &mut ctx);
-                    }
-                    ScrollDirection::Right => {
-                        handler.wheel(Vec2::from((120.0, 0.0)), modifiers, &mut ctx);
+                let mut handler = match state.handler.try_borrow_mut() {
+                    Ok(handler) => handler,
+                    Err(_) => return Inhibit(true),
+                };
+                let notches = match scroll.get_direction() {
+                    ScrollDirection::Up => Vec2::new(0.0, -1.0),
+                    ScrollDirection::Down => Vec2::new(0.0, 1.0),
+                    ScrollDirection::Left => Vec2::new(-1.0, 0.0),
+                    ScrollDirection::Right => Vec2::new(1.0, 0.0),
+                    ScrollDirection::Smooth if !is_precise(scroll) => {
+                        // a wheel that scrolls smoothly is still in notches.
+                        Vec2::from(scroll.get_delta())
                     }
                     ScrollDirection::Smooth => {
-                        //TODO: Look at how gtk's scroll containers implements it
-                        let (mut delta_x, mut delta_y) = scroll.get_delta();
-                        delta_x *= 120.;
-                        delta_y *= 120.;
-                        handler.wheel(Vec2::from((delta_x, delta_y)), modifiers, &mut ctx)
+                        // a touchpad scrolls in gestures, which end with a
+                        // zero-delta stop event.
+                        let phase = if scroll.get_is_stop() {
+                            state.scroll_gesture.set(false);
+                            WheelPhase::Ended
+                        } else if state.scroll_gesture.replace(true) {
+                            WheelPhase::Changed
+                        } else {
+                            WheelPhase::Began
+                        };
+                        let delta = Vec2::from(scroll.get_delta()) * PIXELS_PER_SMOOTH_UNIT;
+                        handler.wheel(delta, WheelUnit::Pixels, phase, modifiers, &mut ctx);
+                        return Inhibit(true);
                     }
                     e => {
                         eprintln!(
                             "Warning: the Druid widget got some whacky scroll direction {:?}",
                             e
                         );
+                        return Inhibit(true);
                     }
-                }
+                };
+                let delta = notches * LINES_PER_NOTCH;
+                handler.wheel(delta, WheelUnit::Lines, WheelPhase::None, modifiers, &mut ctx);
             }
 
             Inhibit(true)
@@ -363,12 +474,67 @@ impl WindowBuilder {
                 *current_keyval = Some(key.get_keyval());
 
                 let key_event = make_key_event(key, repeat);
-                state.handler.borrow_mut().key_down(key_event, &mut ctx);
+                drop(current_keyval);
+
+                // the input method sees every key first. A key it simply
+                // commits is delivered by the commit handler, as a key event;
+                // a key it uses for composing is not delivered at all.
+                *state.pending_key.borrow_mut() = Some(key_event);
+                let filtered = state.im_context.filter_keypress(key);
+                let key_event = state.pending_key.borrow_mut().take();
+                if let Some(key_event) = key_event.filter(|_| !filtered) {
+                    if let Ok(mut handler) = state.handler.try_borrow_mut() {
+                        handler.key_down(key_event, &mut ctx);
+                    }
+                }
             }
 
             Inhibit(true)
         }));
 
+        drawing_area.connect_realize(clone!(handle => move |widget| {
+            if let Some(state) = handle.state.upgrade() {
+                state.im_context.set_client_window(widget.get_window().as_ref());
+            }
+        }));
+
+        win_state
+            .im_context
+            .connect_commit(clone!(handle => move |_im_context, text| {
+                if let Some(state) = handle.state.upgrade() {
+                    let mut ctx = WinCtxImpl::from(&handle);
+                    let key_event = state.pending_key.borrow_mut().take();
+                    let composing = state.composing.replace(false);
+                    if let Ok(mut handler) = state.handler.try_borrow_mut() {
+                        match key_event {
+                            Some(key_event) if !composing && key_event.text() == Some(text) => {
+                                handler.key_down(key_event, &mut ctx);
+                            }
+                            _ => handler.ime_commit(text, &mut ctx),
+                        }
+                    }
+                }
+            }));
+
+        win_state
+            .im_context
+            .connect_preedit_changed(clone!(handle => move |im_context| {
+                if let Some(state) = handle.state.upgrade() {
+                    let mut ctx = WinCtxImpl::from(&handle);
+                    let (text, _attrs, cursor) = im_context.get_preedit_string();
+                    // gtk counts the cursor in chars
+                    let cursor = text
+                        .char_indices()
+                        .nth(cursor as usize)
+                        .map(|(idx, _)| idx)
+                        .unwrap_or_else(|| text.len());
+                    state.composing.set(!text.is_empty());
+                    if let Ok(mut handler) = state.handler.try_borrow_mut() {
+                        handler.ime_composition(&text, cursor, &mut ctx);
+                    }
+                }
+            }));
+
         drawing_area.connect_key_release_event(clone!(handle => move |_widget, key| {
             if let Some(state) = handle.state.upgrade() {
                 let mut ctx = WinCtxImpl::from(&handle);
@@ -376,12 +542,96 @@ impl WindowBuilder {
                 *(state.current_keyval.borrow_mut()) = None;
 
                 let key_event = make_key_event(key, false);
-                state.handler.borrow_mut().key_up(key_event, &mut ctx);
+                if let Ok(mut handler) = state.handler.try_borrow_mut() {
+                    handler.key_up(key_event, &mut ctx);
+                }
             }
 
             Inhibit(true)
         }));
 
+        // files dragged from other applications arrive as a list of uris. The
+        // data is only available on request, so the first motion of a drag
+        // asks for it, and the hover is reported once it is received.
+        let uri_list = gdk::Atom::intern("text/uri-list");
+        let targets = [gtk::TargetEntry::new(
+            "text/uri-list",
+            gtk::TargetFlags::OTHER_APP,
+            0,
+        )];
+        drawing_area.drag_dest_set(gtk::DestDefaults::empty(), &targets, gdk::DragAction::COPY);
+
+        drawing_area.connect_drag_motion(
+            clone!(handle, uri_list => move |widget, context, x, y, time| {
+                if let Some(state) = handle.state.upgrade() {
+                    if state.drag_files.borrow().is_none() {
+                        widget.drag_get_data(context, &uri_list, time);
+                        return Inhibit(true);
+                    }
+                    let mut ctx = WinCtxImpl::from(&handle);
+                    let pos = Point::new(f64::from(x), f64::from(y));
+                    let accepted = match state.handler.try_borrow_mut() {
+                        Ok(mut handler) => handler.drag_over(pos, &mut ctx),
+                        Err(_) => false,
+                    };
+                    context.drag_status(drag_action(accepted), time);
+                }
+                Inhibit(true)
+            }),
+        );
+
+        drawing_area.connect_drag_leave(clone!(handle => move |_widget, _context, _time| {
+            if let Some(state) = handle.state.upgrade() {
+                if state.drag_files.borrow_mut().take().is_some() {
+                    let mut ctx = WinCtxImpl::from(&handle);
+                    if let Ok(mut handler) = state.handler.try_borrow_mut() {
+                        handler.drag_leave(&mut ctx);
+                    }
+                }
+            }
+        }));
+
+        drawing_area.connect_drag_drop(
+            clone!(handle, uri_list => move |widget, context, _x, _y, time| {
+                if let Some(state) = handle.state.upgrade() {
+                    state.pending_drop.set(true);
+                    widget.drag_get_data(context, &uri_list, time);
+                }
+                Inhibit(true)
+            }),
+        );
+
+        drawing_area.connect_drag_data_received(
+            clone!(handle => move |_widget, context, x, y, data, _info, time| {
+                if let Some(state) = handle.state.upgrade() {
+                    let files: Vec<FileInfo> = data
+                        .get_uris()
+                        .iter()
+                        .filter_map(|uri| glib::filename_from_uri(uri).ok())
+                        .map(|(path, _)| FileInfo::new(path))
+                        .collect();
+                    let mut ctx = WinCtxImpl::from(&handle);
+                    let pos = Point::new(f64::from(x), f64::from(y));
+                    let mut handler = match state.handler.try_borrow_mut() {
+                        Ok(handler) => handler,
+                        Err(_) => {
+                            context.drag_status(gdk::DragAction::empty(), time);
+                            return;
+                        }
+                    };
+                    if state.pending_drop.replace(false) {
+                        // gtk sends drag-leave before drag-drop, so this is the end.
+                        let accepted = handler.drop_files(&files, pos, &mut ctx);
+                        context.drag_finish(accepted, false, time);
+                    } else {
+                        let accepted = handler.drag_enter(&files, pos, &mut ctx);
+                        state.drag_files.replace(Some(files));
+                        context.drag_status(drag_action(accepted), time);
+                    }
+                }
+            }),
+        );
+
         drawing_area.connect_destroy(clone!(handle => move |_widget| {
             if let Some(state) = handle.state.upgrade() {
                 let mut ctx = WinCtxImpl::from(&handle);
@@ -389,6 +639,64 @@ impl WindowBuilder {
             }
         }));
 
+        win_state
+            .window
+            .connect_delete_event(clone!(handle => move |_widget, _| {
+                if let Some(state) = handle.state.upgrade() {
+                    let mut ctx = WinCtxImpl::from(&handle);
+                    // a window whose handler is busy (for instance, running a
+                    // nested event loop) cannot agree to close.
+                    let should_close = match state.handler.try_borrow_mut() {
+                        Ok(mut handler) => handler.request_close(&mut ctx),
+                        Err(_) => false,
+                    };
+                    Inhibit(!should_close)
+                } else {
+                    Inhibit(false)
+                }
+            }));
+
+        win_state
+            .window
+            .connect_configure_event(clone!(handle => move |_widget, _event| {
+                if let Some(state) = handle.state.upgrade() {
+                    if let Ok(mut handler) = state.handler.try_borrow_mut() {
+                        let mut ctx = WinCtxImpl::from(&handle);
+                        // the event's position is that of the client area;
+                        // this is the frame's, as for `set_position`.
+                        let (x, y) = state.window.get_position();
+                        handler.position(Point::new(f64::from(x), f64::from(y)), &mut ctx);
+                    }
+                }
+                false
+            }));
+
+        win_state
+            .window
+            .connect_focus_in_event(clone!(handle => move |_widget, _| {
+                if let Some(state) = handle.state.upgrade() {
+                    state.im_context.focus_in();
+                    if let Ok(mut handler) = state.handler.try_borrow_mut() {
+                        let mut ctx = WinCtxImpl::from(&handle);
+                        handler.got_focus(&mut ctx);
+                    }
+                }
+                Inhibit(false)
+            }));
+
+        win_state
+            .window
+            .connect_focus_out_event(clone!(handle => move |_widget, _| {
+                if let Some(state) = handle.state.upgrade() {
+                    state.im_context.focus_out();
+                    if let Ok(mut handler) = state.handler.try_borrow_mut() {
+                        let mut ctx = WinCtxImpl::from(&handle);
+                        handler.lost_focus(&mut ctx);
+                    }
+                }
+                Inhibit(false)
+            }));
+
         vbox.pack_end(&drawing_area, true, true, 0);
 
         win_state
@@ -404,6 +712,14 @@ impl WindowBuilder {
 }
 
 impl WindowHandle {
+    pub fn with_context<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut dyn WinCtx) -> R,
+    {
+        let mut ctx = WinCtxImpl::from(self);
+        Some(f(&mut ctx))
+    }
+
     pub fn show(&self) {
         if let Some(state) = self.state.upgrade() {
             state.window.show_all();
@@ -419,18 +735,78 @@ impl WindowHandle {
         }
     }
 
+    /// Hide or show the window.
+    pub fn set_visible(&self, visible: bool) {
+        if let Some(state) = self.state.upgrade() {
+            if visible {
+                state.window.show_all();
+            } else {
+                state.window.hide();
+            }
+        }
+    }
+
     /// Bring this window to the front of the window stack and give it focus.
     pub fn bring_to_front_and_focus(&self) {
         //FIXME: implementation goes here
         log::warn!("bring_to_front_and_focus not yet implemented for gtk");
     }
 
+    /// Set the size of the window's frame, in px.
+    pub fn set_size(&self, size: Size) {
+        if let Some(state) = self.state.upgrade() {
+            state.window.resize(size.width as i32, size.height as i32);
+        }
+    }
+
+    /// Move the window's frame so that its top-left corner is at `position`,
+    /// in screen coordinates.
+    pub fn set_position(&self, position: Point) {
+        if let Some(state) = self.state.upgrade() {
+            state.window.move_(position.x as i32, position.y as i32);
+        }
+    }
+
+    pub fn minimize(&self) {
+        if let Some(state) = self.state.upgrade() {
+            state.window.iconify();
+        }
+    }
+
+    pub fn maximize(&self) {
+        if let Some(state) = self.state.upgrade() {
+            state.window.maximize();
+        }
+    }
+
+    pub fn restore(&self) {
+        if let Some(state) = self.state.upgrade() {
+            state.window.deiconify();
+            state.window.unmaximize();
+        }
+    }
+
     // Request invalidation of the entire window contents.
     pub fn invalidate(&self) {
         if let Some(state) = self.state.upgrade() {
             state.window.queue_draw();
         }
     }
+
+    /// Request invalidation of part of the window contents.
+    pub fn invalidate_rect(&self, rect: Rect) {
+        if let Some(state) = self.state.upgrade() {
+            // the rect is relative to the drawing area, not the window.
+            let (x0, y0) = (rect.x0.floor(), rect.y0.floor());
+            let (x1, y1) = (rect.x1.ceil(), rect.y1.ceil());
+            state.drawing_area.queue_draw_area(
+                x0 as i32,
+                y0 as i32,
+                (x1 - x0) as i32,
+                (y1 - y0) as i32,
+            );
+        }
+    }
     /// Get a handle that can be used to schedule an idle task.
     pub fn get_idle_handle(&self) -> Option<IdleHandle> {
         self.state.upgrade().map(|s| IdleHandle {
@@ -439,6 +815,30 @@ impl WindowHandle {
         })
     }
 
+    /// The X11 window and display of the window.
+    ///
+    /// Only the X11 backend of GDK is supported; on other backends, or once
+    /// the window is closed, the handle is empty.
+    #[cfg(feature = "raw-win-handle")]
+    pub fn raw_window_handle(&self) -> RawWindowHandle {
+        let window = match self.state.upgrade().and_then(|s| s.window.get_window()) {
+            Some(window) => window,
+            None => return RawWindowHandle::Xlib(XlibHandle::empty()),
+        };
+        let x11_type: glib::Type = unsafe { from_glib(gdk_x11_window_get_type()) };
+        if !window.get_type().is_a(&x11_type) {
+            log::warn!("raw_window_handle is only supported on X11");
+            return RawWindowHandle::Xlib(XlibHandle::empty());
+        }
+        unsafe {
+            RawWindowHandle::Xlib(XlibHandle {
+                window: gdk_x11_window_get_xid(window.to_glib_none().0),
+                display: gdk_x11_display_get_xdisplay(window.get_display().to_glib_none().0),
+                ..XlibHandle::empty()
+            })
+        }
+    }
+
     /// Get the dpi of the window.
     ///
     /// TODO: we want to migrate this from dpi (with 96 as nominal) to a scale
@@ -531,6 +931,41 @@ impl WindowHandle {
             ))
         }
     }
+
+    /// Show a file dialog without blocking, and deliver its result to the
+    /// handler.
+    fn file_dialog_async(&self, ty: FileDialogType, options: FileDialogOptions) -> FileDialogToken {
+        let token = FileDialogToken::next();
+        if let Some(state) = self.state.upgrade() {
+            let handle = self.clone();
+            dialog::show_file_dialog(state.window.upcast_ref(), ty, options, move |result| {
+                let file = result.ok().map(|s| FileInfo { path: s.into() });
+                // the handler may be busy, for instance running a modal loop;
+                // keep trying until it is free.
+                let file = Mutex::new(Some(file));
+                gdk::threads_add_idle(move || {
+                    if let Some(state) = handle.state.upgrade() {
+                        if let Ok(mut handler_borrow) = state.handler.try_borrow_mut() {
+                            let file = file.lock().unwrap().take().flatten();
+                            let mut ctx = WinCtxImpl::from(&handle);
+                            match ty {
+                                FileDialogType::Open => {
+                                    handler_borrow.open_file(token, file, &mut ctx)
+                                }
+                                FileDialogType::Save => {
+                                    handler_borrow.save_as(token, file, &mut ctx)
+                                }
+                            }
+                            return false;
+                        }
+                        return true;
+                    }
+                    false
+                });
+            });
+        }
+        token
+    }
 }
 
 unsafe impl Send for IdleHandle {}
@@ -615,6 +1050,18 @@ impl<'a> WinCtx<'a> for WinCtxImpl<'a> {
         }
     }
 
+    fn set_ime_cursor_area(&mut self, rect: Rect) {
+        if let Some(state) = self.handle.state.upgrade() {
+            let rect = rect.round();
+            state.im_context.set_cursor_location(&gdk::Rectangle {
+                x: rect.x0 as i32,
+                y: rect.y0 as i32,
+                width: rect.width() as i32,
+                height: rect.height() as i32,
+            });
+        }
+    }
+
     fn open_file_sync(&mut self, options: FileDialogOptions) -> Option<FileInfo> {
         self.handle
             .file_dialog(FileDialogType::Open, options)
@@ -629,6 +1076,14 @@ impl<'a> WinCtx<'a> for WinCtxImpl<'a> {
             .map(|s| FileInfo { path: s.into() })
     }
 
+    fn open_file(&mut self, options: FileDialogOptions) -> Option<FileDialogToken> {
+        Some(self.handle.file_dialog_async(FileDialogType::Open, options))
+    }
+
+    fn save_as(&mut self, options: FileDialogOptions) -> Option<FileDialogToken> {
+        Some(self.handle.file_dialog_async(FileDialogType::Save, options))
+    }
+
     fn request_timer(&mut self, deadline: Instant) -> TimerToken {
         let interval = deadline
             .checked_duration_since(Instant::now())
@@ -669,6 +1124,14 @@ impl<'a> From<&'a WindowHandle> for WinCtxImpl<'a> {
     }
 }
 
+fn drag_action(accepted: bool) -> gdk::DragAction {
+    if accepted {
+        gdk::DragAction::COPY
+    } else {
+        gdk::DragAction::empty()
+    }
+}
+
 fn make_gdk_cursor(cursor: &Cursor, gdk_window: &gdk::Window) -> Option<gdk::Cursor> {
     gdk::Cursor::new_from_name(
         &gdk_window.get_display(),
@@ -710,13 +1173,16 @@ fn get_mouse_button_from_modifiers(modifiers: gdk::ModifierType) -> MouseButton
     }
 }
 
-fn get_mouse_click_count(event_type: gdk::EventType) -> u32 {
-    match event_type {
-        gdk::EventType::ButtonPress => 1,
-        gdk::EventType::DoubleButtonPress => 2,
-        gdk::EventType::TripleButtonPress => 3,
-        _ => 0,
-    }
+/// Returns `true` if the scroll comes from a device that scrolls by px,
+/// such as a touchpad, rather than by the notches of a wheel.
+fn is_precise(scroll: &EventScroll) -> bool {
+    let source = scroll.get_source_device().map(|device| device.get_source());
+    matches!(
+        source,
+        Some(InputSource::Touchpad)
+            | Some(InputSource::Trackpoint)
+            | Some(InputSource::Touchscreen)
+    )
 }
 
 fn get_modifiers(modifiers: gdk::ModifierType) -> keyboard::KeyModifiers {
@@ -780,3 +1246,11 @@ fn hardware_keycode_to_keyval(keycode: u16) -> Option<u32> {
         }
     }
 }
+
+// The X11 backend of GDK, which gdk-sys doesn't bind.
+#[cfg(feature = "raw-win-handle")]
+extern "C" {
+    fn gdk_x11_window_get_type() -> glib_sys::GType;
+    fn gdk_x11_window_get_xid(window: *mut gdk_sys::GdkWindow) -> std::os::raw::c_ulong;
+    fn gdk_x11_display_get_xdisplay(display: *mut gdk_sys::GdkDisplay) -> *mut c_void;
+}
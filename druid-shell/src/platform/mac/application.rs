@@ -19,8 +19,9 @@
 use super::clipboard::Clipboard;
 use super::util;
 
-use cocoa::appkit::NSApp;
+use cocoa::appkit::{NSApp, NSApplication, NSEventMask};
 use cocoa::base::{id, nil, YES};
+use cocoa::foundation::{NSDefaultRunLoopMode, NSUInteger};
 use objc::declare::ClassDecl;
 use objc::runtime::{Class, Object, Sel};
 
@@ -41,6 +42,23 @@ impl Application {
         }
     }
 
+    pub fn pump_events() -> bool {
+        unsafe {
+            let app = NSApp();
+            let until: id = msg_send![class!(NSDate), distantFuture];
+            let event = app.nextEventMatchingMask_untilDate_inMode_dequeue_(
+                NSEventMask::NSAnyEventMask.bits() as NSUInteger,
+                until,
+                NSDefaultRunLoopMode,
+                YES,
+            );
+            if event != nil {
+                app.sendEvent_(event);
+            }
+            true
+        }
+    }
+
     /// Hide the application this window belongs to. (cmd+H)
     pub fn hide() {
         unsafe {
@@ -126,7 +126,8 @@ impl Clipboard {
         }
     }
 
-    pub fn available_type_names(&self) -> Vec<String> {
+    /// The identifiers of every format currently on the clipboard.
+    pub fn available_formats(&self) -> Vec<String> {
         unsafe {
             let pasteboard: id = msg_send![class!(NSPasteboard), generalPasteboard];
             let types: id = msg_send![pasteboard, types];
@@ -136,4 +137,9 @@ impl Clipboard {
                 .collect()
         }
     }
+
+    /// On macOS the identifiers are the type names.
+    pub fn available_type_names(&self) -> Vec<String> {
+        self.available_formats()
+    }
 }
@@ -16,8 +16,10 @@
 
 #![allow(non_upper_case_globals)]
 
+use std::cell::Cell;
 use std::ffi::OsString;
 
+use block::ConcreteBlock;
 use cocoa::base::{id, nil, YES};
 use cocoa::foundation::{NSArray, NSInteger};
 
@@ -27,46 +29,94 @@ use crate::dialog::{FileDialogOptions, FileDialogType};
 const NSModalResponseOK: NSInteger = 1;
 const NSModalResponseCancel: NSInteger = 0;
 
+/// Show a file dialog, and block until the user dismisses it.
+///
+/// Returns the chosen path, or `None` if the dialog was cancelled.
 pub(crate) fn get_file_dialog_path(
     ty: FileDialogType,
     options: FileDialogOptions,
 ) -> Option<OsString> {
     unsafe {
-        let panel: id = match ty {
-            FileDialogType::Open => msg_send![class!(NSOpenPanel), openPanel],
-            FileDialogType::Save => msg_send![class!(NSSavePanel), savePanel],
-        };
-
-        // set options
-        if options.show_hidden {
-            let () = msg_send![panel, setShowsHiddenFiles: YES];
-        }
-
-        // A vector of NSStrings. this must outlive `nsarray_allowed_types`.
-        let allowed_types = options.allowed_types.as_ref().map(|specs| {
-            specs
-                .iter()
-                .flat_map(|spec| spec.extensions.iter().map(|s| make_nsstring(s)))
-                .collect::<Vec<_>>()
-        });
-
-        let nsarray_allowed_types = allowed_types
-            .as_ref()
-            .map(|types| NSArray::arrayWithObjects(nil, types.as_slice()));
-        if let Some(nsarray) = nsarray_allowed_types {
-            let () = msg_send![panel, setAllowedFileTypes: nsarray];
-        }
-
+        let panel = make_panel(ty, options);
         let result: NSInteger = msg_send![panel, runModal];
         match result {
-            NSModalResponseOK => {
-                let url: id = msg_send![panel, URL];
-                let path: id = msg_send![url, path];
-                let path: OsString = from_nsstring(path).into();
-                Some(path)
-            }
+            NSModalResponseOK => Some(panel_path(panel)),
             NSModalResponseCancel => None,
             _ => unreachable!(),
         }
     }
 }
+
+/// Show a file dialog as a sheet on `window`, and return without waiting
+/// for the user.
+///
+/// Once the dialog is dismissed, `completion` is called from the run loop
+/// with the chosen path, or `None` if the dialog was cancelled. If `window`
+/// is `nil`, the dialog gets a window of its own.
+pub(crate) fn show_file_dialog_sheet(
+    window: id,
+    ty: FileDialogType,
+    options: FileDialogOptions,
+    completion: impl FnOnce(Option<OsString>) + 'static,
+) {
+    unsafe {
+        let panel = make_panel(ty, options);
+        // the panel is released by the completion handler.
+        let () = msg_send![panel, retain];
+        // blocks must be `Fn`, but the handler is only called once.
+        let completion = Cell::new(Some(completion));
+        let handler = ConcreteBlock::new(move |result: NSInteger| {
+            let path = match result {
+                NSModalResponseOK => Some(panel_path(panel)),
+                _ => None,
+            };
+            let () = msg_send![panel, release];
+            if let Some(completion) = completion.take() {
+                completion(path);
+            }
+        });
+        let handler = handler.copy();
+        if window == nil {
+            let () = msg_send![panel, beginWithCompletionHandler: &*handler];
+        } else {
+            let () = msg_send![panel, beginSheetModalForWindow: window
+                completionHandler: &*handler];
+        }
+    }
+}
+
+/// Create an open or save panel, with `options` applied.
+unsafe fn make_panel(ty: FileDialogType, options: FileDialogOptions) -> id {
+    let panel: id = match ty {
+        FileDialogType::Open => msg_send![class!(NSOpenPanel), openPanel],
+        FileDialogType::Save => msg_send![class!(NSSavePanel), savePanel],
+    };
+
+    // set options
+    if options.show_hidden {
+        let () = msg_send![panel, setShowsHiddenFiles: YES];
+    }
+
+    // A vector of NSStrings. this must outlive `nsarray_allowed_types`.
+    let allowed_types = options.allowed_types.as_ref().map(|specs| {
+        specs
+            .iter()
+            .flat_map(|spec| spec.extensions.iter().map(|s| make_nsstring(s)))
+            .collect::<Vec<_>>()
+    });
+
+    let nsarray_allowed_types = allowed_types
+        .as_ref()
+        .map(|types| NSArray::arrayWithObjects(nil, types.as_slice()));
+    if let Some(nsarray) = nsarray_allowed_types {
+        let () = msg_send![panel, setAllowedFileTypes: nsarray];
+    }
+    panel
+}
+
+/// The path the user chose in `panel`.
+unsafe fn panel_path(panel: id) -> OsString {
+    let url: id = msg_send![panel, URL];
+    let path: id = msg_send![url, path];
+    from_nsstring(path).into()
+}
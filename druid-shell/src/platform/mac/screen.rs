@@ -0,0 +1,67 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! macOS monitors.
+
+use cocoa::appkit::NSScreen;
+use cocoa::base::{id, nil};
+use cocoa::foundation::{NSArray, NSRect};
+
+use crate::kurbo::Rect;
+use crate::screen::{Monitor, ScreenCoords};
+
+/// Cocoa's screen coordinates, whose origin is the bottom-left corner of the
+/// primary screen, which is the first.
+pub(crate) fn screen_coords() -> ScreenCoords {
+    unsafe {
+        let screens = NSScreen::screens(nil);
+        let flip_height = if screens.count() > 0 {
+            Some(NSScreen::frame(screens.objectAtIndex(0)).size.height)
+        } else {
+            None
+        };
+        ScreenCoords {
+            scale: 1.0,
+            flip_height,
+        }
+    }
+}
+
+pub(crate) fn to_rect(rect: NSRect) -> Rect {
+    Rect::new(
+        rect.origin.x,
+        rect.origin.y,
+        rect.origin.x + rect.size.width,
+        rect.origin.y + rect.size.height,
+    )
+}
+
+pub(crate) fn get_monitors() -> Vec<Monitor> {
+    let coords = screen_coords();
+    unsafe {
+        let screens = NSScreen::screens(nil);
+        let count = screens.count();
+        (0..count)
+            .map(|i| {
+                let screen: id = screens.objectAtIndex(i);
+                Monitor::new(
+                    i == 0,
+                    coords.to_px(to_rect(NSScreen::frame(screen))),
+                    coords.to_px(to_rect(NSScreen::visibleFrame(screen))),
+                    NSScreen::backingScaleFactor(screen),
+                )
+            })
+            .collect()
+    }
+}
@@ -17,41 +17,66 @@
 #![allow(non_snake_case)]
 
 use std::any::Any;
-use std::ffi::c_void;
+use std::ffi::{c_void, OsString};
 use std::mem;
 use std::sync::{Arc, Mutex, Weak};
 use std::time::Instant;
 
 use cocoa::appkit::{
     CGFloat, NSApp, NSApplication, NSAutoresizingMaskOptions, NSBackingStoreBuffered, NSEvent,
-    NSEventModifierFlags, NSView, NSViewHeightSizable, NSViewWidthSizable, NSWindow,
+    NSEventModifierFlags, NSEventPhase, NSView, NSViewHeightSizable, NSViewWidthSizable, NSWindow,
     NSWindowStyleMask,
 };
 use cocoa::base::{id, nil, BOOL, NO, YES};
-use cocoa::foundation::{NSAutoreleasePool, NSPoint, NSRect, NSSize, NSString};
+use cocoa::foundation::{
+    NSArray, NSAutoreleasePool, NSInteger, NSPoint, NSRange, NSRect, NSSize, NSString, NSUInteger,
+};
 use objc::declare::ClassDecl;
 use objc::rc::WeakPtr;
-use objc::runtime::{Class, Object, Sel};
+use objc::runtime::{Class, Object, Protocol, Sel};
 
 use cairo::{Context, QuartzSurface};
 use log::{error, info};
+#[cfg(feature = "raw-win-handle")]
+use raw_window_handle::{macos::MacOSHandle, RawWindowHandle};
 
-use crate::kurbo::{Point, Size, Vec2};
+use crate::kurbo::{Point, Rect, Size, Vec2};
 use crate::piet::{Piet, RenderContext};
 
 use super::dialog;
 use super::menu::Menu;
-use super::util::{assert_main_thread, make_nsstring};
+use super::screen;
+use super::util::{assert_main_thread, from_nsstring, make_nsstring};
 use crate::common_util::IdleCallback;
-use crate::dialog::{FileDialogOptions, FileDialogType, FileInfo};
+use crate::dialog::{FileDialogOptions, FileDialogToken, FileDialogType, FileInfo};
 use crate::keyboard::{KeyEvent, KeyModifiers};
 use crate::keycodes::KeyCode;
-use crate::mouse::{Cursor, MouseButton, MouseEvent};
-use crate::window::{IdleToken, Text, TimerToken, WinCtx, WinHandler};
+use crate::mouse::{Cursor, MouseButton, MouseEvent, WheelPhase, WheelUnit};
+use crate::window::{IdleToken, PowerEvent, Text, TimerToken, WinCtx, WinHandler};
 use crate::Error;
 
 #[allow(non_upper_case_globals)]
 const NSWindowDidBecomeKeyNotification: &str = "NSWindowDidBecomeKeyNotification";
+#[allow(non_upper_case_globals)]
+const NSWindowDidMoveNotification: &str = "NSWindowDidMoveNotification";
+#[allow(non_upper_case_globals)]
+const NSFilenamesPboardType: &str = "NSFilenamesPboardType";
+#[allow(non_upper_case_globals)]
+const NSTrackingMouseEnteredAndExited: NSUInteger = 0x01;
+#[allow(non_upper_case_globals)]
+const NSTrackingActiveAlways: NSUInteger = 0x80;
+#[allow(non_upper_case_globals)]
+const NSTrackingInVisibleRect: NSUInteger = 0x200;
+#[allow(non_upper_case_globals)]
+const NSDragOperationNone: NSUInteger = 0;
+#[allow(non_upper_case_globals)]
+const NSDragOperationCopy: NSUInteger = 1;
+#[allow(non_upper_case_globals)]
+const NSWindowDidResignKeyNotification: &str = "NSWindowDidResignKeyNotification";
+#[allow(non_upper_case_globals)]
+const NSWorkspaceWillSleepNotification: &str = "NSWorkspaceWillSleepNotification";
+#[allow(non_upper_case_globals)]
+const NSWorkspaceDidWakeNotification: &str = "NSWorkspaceDidWakeNotification";
 
 #[derive(Clone)]
 pub(crate) struct WindowHandle {
@@ -76,6 +101,9 @@ pub(crate) struct WindowBuilder {
     title: String,
     menu: Option<Menu>,
     size: Size,
+    resizable: bool,
+    always_on_top: bool,
+    transparent: bool,
 }
 
 #[derive(Clone)]
@@ -88,6 +116,8 @@ pub(crate) struct IdleHandle {
 enum IdleKind {
     Callback(Box<dyn IdleCallback>),
     Token(IdleToken),
+    /// A file dialog requested with `WinCtx::open_file` or `WinCtx::save_as`.
+    FileDialog(FileDialogType, FileDialogOptions, FileDialogToken),
 }
 
 /// This is the state associated with our custom NSView.
@@ -96,6 +126,13 @@ struct ViewState {
     handler: Box<dyn WinHandler>,
     idle_queue: Arc<Mutex<Vec<IdleKind>>>,
     last_mods: KeyModifiers,
+    /// The key down event the input method is looking at, until it either
+    /// uses the key, or leaves it to us.
+    pending_key: Option<KeyEvent>,
+    /// The text under composition; empty if the input method isn't composing.
+    composition: String,
+    /// Where the text under composition is, in view coordinates.
+    ime_cursor_area: Rect,
 }
 
 struct WinCtxImpl<'a> {
@@ -110,6 +147,9 @@ impl WindowBuilder {
             title: String::new(),
             menu: None,
             size: Size::new(500.0, 400.0),
+            resizable: true,
+            always_on_top: false,
+            transparent: false,
         }
     }
 
@@ -129,13 +169,27 @@ impl WindowBuilder {
         self.menu = Some(menu);
     }
 
+    pub fn set_resizable(&mut self, resizable: bool) {
+        self.resizable = resizable;
+    }
+
+    pub fn set_always_on_top(&mut self, always_on_top: bool) {
+        self.always_on_top = always_on_top;
+    }
+
+    pub fn set_transparent(&mut self, transparent: bool) {
+        self.transparent = transparent;
+    }
+
     pub fn build(self) -> Result<WindowHandle, Error> {
         assert_main_thread();
         unsafe {
-            let style_mask = NSWindowStyleMask::NSTitledWindowMask
+            let mut style_mask = NSWindowStyleMask::NSTitledWindowMask
                 | NSWindowStyleMask::NSClosableWindowMask
-                | NSWindowStyleMask::NSMiniaturizableWindowMask
-                | NSWindowStyleMask::NSResizableWindowMask;
+                | NSWindowStyleMask::NSMiniaturizableWindowMask;
+            if self.resizable {
+                style_mask |= NSWindowStyleMask::NSResizableWindowMask;
+            }
             let rect = NSRect::new(
                 NSPoint::new(0., 0.),
                 NSSize::new(self.size.width, self.size.height),
@@ -152,6 +206,15 @@ impl WindowBuilder {
             window.setTitle_(make_nsstring(&self.title));
             // TODO: this should probably be a tracking area instead
             window.setAcceptsMouseMovedEvents_(YES);
+            if self.always_on_top {
+                // NSFloatingWindowLevel
+                let () = msg_send![window, setLevel: 3 as NSInteger];
+            }
+            if self.transparent {
+                let clear: id = msg_send![class!(NSColor), clearColor];
+                let () = msg_send![window, setOpaque: NO];
+                let () = msg_send![window, setBackgroundColor: clear];
+            }
 
             let (view, idle_queue) = make_view(self.handler.expect("view"));
             let content_view = window.contentView();
@@ -160,6 +223,18 @@ impl WindowBuilder {
 
             let () = msg_send![window, setDelegate: view];
 
+            // ask to be told when the mouse leaves the view.
+            let tracking_options: NSUInteger =
+                NSTrackingMouseEnteredAndExited | NSTrackingActiveAlways | NSTrackingInVisibleRect;
+            let tracking_area: id = msg_send![class!(NSTrackingArea), alloc];
+            let tracking_area: id = msg_send![tracking_area, initWithRect: NSRect::new(NSPoint::new(0., 0.), NSSize::new(0., 0.)) options: tracking_options owner: view userInfo: nil];
+            let () = msg_send![view, addTrackingArea: tracking_area];
+            let () = msg_send![tracking_area, release];
+
+            let drag_types =
+                NSArray::arrayWithObjects(nil, &[make_nsstring(NSFilenamesPboardType)]);
+            let () = msg_send![view, registerForDraggedTypes: drag_types];
+
             if let Some(menu) = self.menu {
                 NSApp().setMainMenu_(menu.menu);
             }
@@ -222,6 +297,30 @@ lazy_static! {
             sel!(windowDidBecomeKey:),
             window_did_become_key as extern "C" fn(&mut Object, Sel, id),
         );
+        decl.add_method(
+            sel!(windowDidResignKey:),
+            window_did_resign_key as extern "C" fn(&mut Object, Sel, id),
+        );
+        decl.add_method(
+            sel!(windowDidMove:),
+            window_did_move as extern "C" fn(&mut Object, Sel, id),
+        );
+        decl.add_method(
+            sel!(draggingEntered:),
+            dragging_entered as extern "C" fn(&mut Object, Sel, id) -> NSUInteger,
+        );
+        decl.add_method(
+            sel!(draggingUpdated:),
+            dragging_updated as extern "C" fn(&mut Object, Sel, id) -> NSUInteger,
+        );
+        decl.add_method(
+            sel!(draggingExited:),
+            dragging_exited as extern "C" fn(&mut Object, Sel, id),
+        );
+        decl.add_method(
+            sel!(performDragOperation:),
+            perform_drag_operation as extern "C" fn(&mut Object, Sel, id) -> BOOL,
+        );
         decl.add_method(
             sel!(setFrameSize:),
             set_frame_size as extern "C" fn(&mut Object, Sel, NSSize),
@@ -242,6 +341,10 @@ lazy_static! {
             sel!(rightMouseUp:),
             mouse_up_right as extern "C" fn(&mut Object, Sel, id),
         );
+        decl.add_method(
+            sel!(mouseExited:),
+            mouse_exited as extern "C" fn(&mut Object, Sel, id),
+        );
         decl.add_method(
             sel!(mouseMoved:),
             mouse_move as extern "C" fn(&mut Object, Sel, id),
@@ -263,6 +366,54 @@ lazy_static! {
             key_down as extern "C" fn(&mut Object, Sel, id),
         );
         decl.add_method(sel!(keyUp:), key_up as extern "C" fn(&mut Object, Sel, id));
+
+        // input methods talk to the view through `NSTextInputClient`.
+        if let Some(protocol) = Protocol::get("NSTextInputClient") {
+            decl.add_protocol(protocol);
+        }
+        decl.add_method(
+            sel!(insertText:replacementRange:),
+            insert_text as extern "C" fn(&mut Object, Sel, id, NSRange),
+        );
+        decl.add_method(
+            sel!(setMarkedText:selectedRange:replacementRange:),
+            set_marked_text as extern "C" fn(&mut Object, Sel, id, NSRange, NSRange),
+        );
+        decl.add_method(sel!(unmarkText), unmark_text as extern "C" fn(&mut Object, Sel));
+        decl.add_method(
+            sel!(hasMarkedText),
+            has_marked_text as extern "C" fn(&mut Object, Sel) -> BOOL,
+        );
+        decl.add_method(
+            sel!(markedRange),
+            marked_range as extern "C" fn(&mut Object, Sel) -> NSRange,
+        );
+        decl.add_method(
+            sel!(selectedRange),
+            selected_range as extern "C" fn(&mut Object, Sel) -> NSRange,
+        );
+        decl.add_method(
+            sel!(validAttributesForMarkedText),
+            valid_attributes_for_marked_text as extern "C" fn(&mut Object, Sel) -> id,
+        );
+        decl.add_method(
+            sel!(attributedSubstringForProposedRange:actualRange:),
+            attributed_substring_for_proposed_range
+                as extern "C" fn(&mut Object, Sel, NSRange, *mut c_void) -> id,
+        );
+        decl.add_method(
+            sel!(characterIndexForPoint:),
+            character_index_for_point as extern "C" fn(&mut Object, Sel, NSPoint) -> NSUInteger,
+        );
+        decl.add_method(
+            sel!(firstRectForCharacterRange:actualRange:),
+            first_rect_for_character_range
+                as extern "C" fn(&mut Object, Sel, NSRange, *mut c_void) -> NSRect,
+        );
+        decl.add_method(
+            sel!(doCommandBySelector:),
+            do_command_by_selector as extern "C" fn(&mut Object, Sel, Sel),
+        );
         decl.add_method(
             sel!(flagsChanged:),
             mods_changed as extern "C" fn(&mut Object, Sel, id),
@@ -285,10 +436,22 @@ lazy_static! {
             sel!(showContextMenu:),
             show_context_menu as extern "C" fn(&mut Object, Sel, id),
         );
+        decl.add_method(
+            sel!(windowShouldClose:),
+            window_should_close as extern "C" fn(&mut Object, Sel, id) -> BOOL,
+        );
         decl.add_method(
             sel!(windowWillClose:),
             window_will_close as extern "C" fn(&mut Object, Sel, id),
         );
+        decl.add_method(
+            sel!(workspaceWillSleep:),
+            workspace_will_sleep as extern "C" fn(&mut Object, Sel, id),
+        );
+        decl.add_method(
+            sel!(workspaceDidWake:),
+            workspace_did_wake as extern "C" fn(&mut Object, Sel, id),
+        );
         ViewClass(decl.register())
     };
 }
@@ -304,6 +467,9 @@ fn make_view(handler: Box<dyn WinHandler>) -> (id, Weak<Mutex<Vec<IdleKind>>>) {
             handler,
             idle_queue,
             last_mods: KeyModifiers::default(),
+            pending_key: None,
+            composition: String::new(),
+            ime_cursor_area: Rect::ZERO,
         };
         let state_ptr = Box::into_raw(Box::new(state));
         (*view).set_ivar("viewState", state_ptr as *mut c_void);
@@ -423,19 +589,31 @@ extern "C" fn mouse_move(this: &mut Object, _: Sel, nsevent: id) {
     }
 }
 
+extern "C" fn mouse_exited(this: &mut Object, _: Sel, _nsevent: id) {
+    unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        let view_state = &mut *(view_state as *mut ViewState);
+        let mut ctx = WinCtxImpl {
+            nsview: &(*view_state).nsview,
+            text: Text::new(),
+        };
+        (*view_state).handler.mouse_leave(&mut ctx);
+    }
+}
+
 extern "C" fn scroll_wheel(this: &mut Object, _: Sel, nsevent: id) {
     unsafe {
         let view_state: *mut c_void = *this.get_ivar("viewState");
         let view_state = &mut *(view_state as *mut ViewState);
-        let (dx, dy) = {
-            let dx = -nsevent.scrollingDeltaX() as f64;
-            let dy = -nsevent.scrollingDeltaY() as f64;
-            if nsevent.hasPreciseScrollingDeltas() == cocoa::base::YES {
-                (dx, dy)
-            } else {
-                (dx * 32.0, dy * 32.0)
-            }
+        let dx = -nsevent.scrollingDeltaX() as f64;
+        let dy = -nsevent.scrollingDeltaY() as f64;
+        // without precise deltas, the deltas are in lines.
+        let unit = if nsevent.hasPreciseScrollingDeltas() == cocoa::base::YES {
+            WheelUnit::Pixels
+        } else {
+            WheelUnit::Lines
         };
+        let phase = make_wheel_phase(nsevent.phase(), nsevent.momentumPhase());
         let mods = nsevent.modifierFlags();
         let mods = make_modifiers(mods);
 
@@ -444,7 +622,9 @@ extern "C" fn scroll_wheel(this: &mut Object, _: Sel, nsevent: id) {
             nsview: &(*view_state).nsview,
             text: Text::new(),
         };
-        (*view_state).handler.wheel(delta, mods, &mut ctx);
+        (*view_state)
+            .handler
+            .wheel(delta, unit, phase, mods, &mut ctx);
     }
 }
 
@@ -470,12 +650,22 @@ extern "C" fn key_down(this: &mut Object, _: Sel, nsevent: id) {
         let view_state: *mut c_void = *this.get_ivar("viewState");
         &mut *(view_state as *mut ViewState)
     };
-    let mut ctx = WinCtxImpl {
-        nsview: &(*view_state).nsview,
-        text: Text::new(),
-    };
-    (*view_state).handler.key_down(event, &mut ctx);
     view_state.last_mods = event.mods;
+    // the input method sees the key first; it calls back with the text the
+    // key produces, or with the command it stands for.
+    view_state.pending_key = Some(event);
+    unsafe {
+        let events: id = msg_send![class!(NSArray), arrayWithObject: nsevent];
+        let () = msg_send![this as *const _, interpretKeyEvents: events];
+    }
+    // a key that didn't go into the text, such as an arrow or a shortcut.
+    if let Some(event) = view_state.pending_key.take() {
+        let mut ctx = WinCtxImpl {
+            nsview: &(*view_state).nsview,
+            text: Text::new(),
+        };
+        (*view_state).handler.key_down(event, &mut ctx);
+    }
 }
 
 extern "C" fn key_up(this: &mut Object, _: Sel, nsevent: id) {
@@ -492,6 +682,144 @@ extern "C" fn key_up(this: &mut Object, _: Sel, nsevent: id) {
     view_state.last_mods = event.mods;
 }
 
+/// `NSNotFound`, for ranges that don't exist.
+const NS_NOT_FOUND: NSUInteger = NSInteger::max_value() as NSUInteger;
+
+extern "C" fn insert_text(this: &mut Object, _: Sel, text: id, _replacement_range: NSRange) {
+    let text = string_from_text_input(text);
+    let view_state = unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        &mut *(view_state as *mut ViewState)
+    };
+    let was_composing = !view_state.composition.is_empty();
+    view_state.composition.clear();
+    let mut ctx = WinCtxImpl {
+        nsview: &(*view_state).nsview,
+        text: Text::new(),
+    };
+    // a key that just types its own text is a plain key press.
+    match view_state.pending_key.take() {
+        Some(event) if !was_composing && event.text() == Some(text.as_str()) => {
+            (*view_state).handler.key_down(event, &mut ctx);
+        }
+        _ => (*view_state).handler.ime_commit(&text, &mut ctx),
+    }
+}
+
+extern "C" fn set_marked_text(
+    this: &mut Object,
+    _: Sel,
+    text: id,
+    selected_range: NSRange,
+    _replacement_range: NSRange,
+) {
+    let text = string_from_text_input(text);
+    let view_state = unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        &mut *(view_state as *mut ViewState)
+    };
+    // the key went into the composition.
+    view_state.pending_key = None;
+    let cursor = utf8_offset(&text, selected_range.location as usize);
+    view_state.composition = text;
+    let mut ctx = WinCtxImpl {
+        nsview: &(*view_state).nsview,
+        text: Text::new(),
+    };
+    (*view_state)
+        .handler
+        .ime_composition(&view_state.composition, cursor, &mut ctx);
+}
+
+extern "C" fn unmark_text(this: &mut Object, _: Sel) {
+    let view_state = unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        &mut *(view_state as *mut ViewState)
+    };
+    let text = mem::replace(&mut view_state.composition, String::new());
+    if !text.is_empty() {
+        let mut ctx = WinCtxImpl {
+            nsview: &(*view_state).nsview,
+            text: Text::new(),
+        };
+        (*view_state).handler.ime_commit(&text, &mut ctx);
+    }
+}
+
+extern "C" fn has_marked_text(this: &mut Object, _: Sel) -> BOOL {
+    let view_state = unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        &*(view_state as *mut ViewState)
+    };
+    if view_state.composition.is_empty() {
+        NO
+    } else {
+        YES
+    }
+}
+
+extern "C" fn marked_range(this: &mut Object, _: Sel) -> NSRange {
+    let view_state = unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        &*(view_state as *mut ViewState)
+    };
+    let len = view_state.composition.encode_utf16().count();
+    if len == 0 {
+        NSRange::new(NS_NOT_FOUND, 0)
+    } else {
+        NSRange::new(0, len as NSUInteger)
+    }
+}
+
+extern "C" fn selected_range(_this: &mut Object, _: Sel) -> NSRange {
+    // the text outside of the composition belongs to the widgets.
+    NSRange::new(NS_NOT_FOUND, 0)
+}
+
+extern "C" fn valid_attributes_for_marked_text(_this: &mut Object, _: Sel) -> id {
+    unsafe { msg_send![class!(NSArray), array] }
+}
+
+extern "C" fn attributed_substring_for_proposed_range(
+    _this: &mut Object,
+    _: Sel,
+    _range: NSRange,
+    _actual_range: *mut c_void,
+) -> id {
+    nil
+}
+
+extern "C" fn character_index_for_point(_this: &mut Object, _: Sel, _point: NSPoint) -> NSUInteger {
+    NS_NOT_FOUND
+}
+
+extern "C" fn first_rect_for_character_range(
+    this: &mut Object,
+    _: Sel,
+    _range: NSRange,
+    _actual_range: *mut c_void,
+) -> NSRect {
+    let view_state = unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        &*(view_state as *mut ViewState)
+    };
+    let area = view_state.ime_cursor_area;
+    let rect = NSRect::new(
+        NSPoint::new(area.x0, area.y0),
+        NSSize::new(area.width(), area.height()),
+    );
+    // the input method wants screen coordinates.
+    unsafe {
+        let rect: NSRect = msg_send![this as *const _, convertRect: rect toView: nil];
+        let window: id = msg_send![this as *const _, window];
+        msg_send![window, convertRectToScreen: rect]
+    }
+}
+
+extern "C" fn do_command_by_selector(_this: &mut Object, _: Sel, _command: Sel) {
+    // the key is left pending, and `key_down` hands it to the handler.
+}
+
 extern "C" fn mods_changed(this: &mut Object, _: Sel, nsevent: id) {
     let view_state = unsafe {
         let view_state: *mut c_void = *this.get_ivar("viewState");
@@ -532,7 +860,13 @@ extern "C" fn draw_rect(this: &mut Object, _: Sel, dirtyRect: NSRect) {
             nsview: &(*view_state).nsview,
             text: Text::new(),
         };
-        let anim = (*view_state).handler.paint(&mut piet_ctx, &mut ctx);
+        let invalid_rect = Rect::from_origin_size(
+            (dirtyRect.origin.x, dirtyRect.origin.y),
+            (dirtyRect.size.width, dirtyRect.size.height),
+        );
+        let anim = (*view_state)
+            .handler
+            .paint(&mut piet_ctx, invalid_rect, &mut ctx);
         if let Err(e) = piet_ctx.finish() {
             error!("{}", e)
         }
@@ -568,7 +902,41 @@ extern "C" fn run_idle(this: &mut Object, _: Sel) {
                 };
                 view_state.handler.as_mut().idle(it, &mut ctx);
             }
+            IdleKind::FileDialog(ty, options, token) => {
+                let nsview = view_state.nsview.clone();
+                let window: id = unsafe { msg_send![this as *const _, window] };
+                dialog::show_file_dialog_sheet(window, ty, options, move |path| {
+                    file_dialog_done(&nsview, ty, token, path)
+                });
+            }
+        }
+    }
+}
+
+/// Deliver the result of a file dialog to the handler of the view that
+/// asked for it, unless the view has gone away in the meantime.
+fn file_dialog_done(
+    nsview: &WeakPtr,
+    ty: FileDialogType,
+    token: FileDialogToken,
+    path: Option<OsString>,
+) {
+    let view_state = unsafe {
+        let view = nsview.load();
+        if view.is_null() {
+            return;
         }
+        let view_state: *mut c_void = *(**view).get_ivar("viewState");
+        &mut *(view_state as *mut ViewState)
+    };
+    let file = path.map(|s| FileInfo { path: s.into() });
+    let mut ctx = WinCtxImpl {
+        nsview,
+        text: Text::new(),
+    };
+    match ty {
+        FileDialogType::Open => view_state.handler.open_file(token, file, &mut ctx),
+        FileDialogType::Save => view_state.handler.save_as(token, file, &mut ctx),
     }
 }
 
@@ -632,8 +1000,164 @@ extern "C" fn window_did_become_key(this: &mut Object, _: Sel, _notification: id
     }
 }
 
+extern "C" fn window_did_move(this: &mut Object, _: Sel, _notification: id) {
+    unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        let view_state = &mut *(view_state as *mut ViewState);
+        let window: id = msg_send![this as *const _, window];
+        let frame: NSRect = msg_send![window, frame];
+        let frame = screen::screen_coords().to_px(screen::to_rect(frame));
+        let mut ctx = WinCtxImpl {
+            nsview: &(*view_state).nsview,
+            text: Text::new(),
+        };
+        (*view_state).handler.position(frame.origin(), &mut ctx);
+    }
+}
+
+/// The files being dragged, and their position in the view.
+fn dragged_files(view: &Object, sender: id) -> (Vec<FileInfo>, Point) {
+    unsafe {
+        let pasteboard: id = msg_send![sender, draggingPasteboard];
+        let paths: id =
+            msg_send![pasteboard, propertyListForType: make_nsstring(NSFilenamesPboardType)];
+        let files = if paths == nil {
+            Vec::new()
+        } else {
+            (0..paths.count())
+                .map(|i| FileInfo::new(from_nsstring(paths.objectAtIndex(i))))
+                .collect()
+        };
+        (files, dragging_location(view, sender))
+    }
+}
+
+fn dragging_location(view: &Object, sender: id) -> Point {
+    unsafe {
+        let location: NSPoint = msg_send![sender, draggingLocation];
+        let point: NSPoint = msg_send![view, convertPoint: location fromView: nil];
+        Point::new(point.x, point.y)
+    }
+}
+
+fn drag_operation(accepted: bool) -> NSUInteger {
+    if accepted {
+        NSDragOperationCopy
+    } else {
+        NSDragOperationNone
+    }
+}
+
+extern "C" fn dragging_entered(this: &mut Object, _: Sel, sender: id) -> NSUInteger {
+    unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        let view_state = &mut *(view_state as *mut ViewState);
+        let (files, pos) = dragged_files(this, sender);
+        let mut ctx = WinCtxImpl {
+            nsview: &(*view_state).nsview,
+            text: Text::new(),
+        };
+        drag_operation((*view_state).handler.drag_enter(&files, pos, &mut ctx))
+    }
+}
+
+extern "C" fn dragging_updated(this: &mut Object, _: Sel, sender: id) -> NSUInteger {
+    unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        let view_state = &mut *(view_state as *mut ViewState);
+        let pos = dragging_location(this, sender);
+        let mut ctx = WinCtxImpl {
+            nsview: &(*view_state).nsview,
+            text: Text::new(),
+        };
+        drag_operation((*view_state).handler.drag_over(pos, &mut ctx))
+    }
+}
+
+extern "C" fn dragging_exited(this: &mut Object, _: Sel, _sender: id) {
+    unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        let view_state = &mut *(view_state as *mut ViewState);
+        let mut ctx = WinCtxImpl {
+            nsview: &(*view_state).nsview,
+            text: Text::new(),
+        };
+        (*view_state).handler.drag_leave(&mut ctx);
+    }
+}
+
+extern "C" fn perform_drag_operation(this: &mut Object, _: Sel, sender: id) -> BOOL {
+    unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        let view_state = &mut *(view_state as *mut ViewState);
+        let (files, pos) = dragged_files(this, sender);
+        let mut ctx = WinCtxImpl {
+            nsview: &(*view_state).nsview,
+            text: Text::new(),
+        };
+        if (*view_state).handler.drop_files(&files, pos, &mut ctx) {
+            YES
+        } else {
+            NO
+        }
+    }
+}
+
+extern "C" fn window_did_resign_key(this: &mut Object, _: Sel, _notification: id) {
+    unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        let view_state = &mut *(view_state as *mut ViewState);
+        let mut ctx = WinCtxImpl {
+            nsview: &(*view_state).nsview,
+            text: Text::new(),
+        };
+        (*view_state).handler.lost_focus(&mut ctx);
+    }
+}
+
+extern "C" fn workspace_will_sleep(this: &mut Object, _: Sel, _notification: id) {
+    send_power_event(this, PowerEvent::WillSleep);
+}
+
+extern "C" fn workspace_did_wake(this: &mut Object, _: Sel, _notification: id) {
+    send_power_event(this, PowerEvent::DidWake);
+}
+
+fn send_power_event(this: &mut Object, event: PowerEvent) {
+    unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        let view_state = &mut *(view_state as *mut ViewState);
+        let mut ctx = WinCtxImpl {
+            nsview: &(*view_state).nsview,
+            text: Text::new(),
+        };
+        (*view_state).handler.power_event(event, &mut ctx);
+    }
+}
+
+extern "C" fn window_should_close(this: &mut Object, _: Sel, _sender: id) -> BOOL {
+    unsafe {
+        let view_state: *mut c_void = *this.get_ivar("viewState");
+        let view_state = &mut *(view_state as *mut ViewState);
+        let mut ctx = WinCtxImpl {
+            nsview: &(*view_state).nsview,
+            text: Text::new(),
+        };
+        if (*view_state).handler.request_close(&mut ctx) {
+            YES
+        } else {
+            NO
+        }
+    }
+}
+
 extern "C" fn window_will_close(this: &mut Object, _: Sel, _window: id) {
     unsafe {
+        // stop listening for power events before the view goes away.
+        let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+        let workspace_center: id = msg_send![workspace, notificationCenter];
+        let () = msg_send![workspace_center, removeObserver: this as *mut Object];
+
         let view_state: *mut c_void = *this.get_ivar("viewState");
         let view_state = &mut *(view_state as *mut ViewState);
         let mut ctx = WinCtxImpl {
@@ -645,6 +1169,17 @@ extern "C" fn window_will_close(this: &mut Object, _: Sel, _window: id) {
 }
 
 impl WindowHandle {
+    pub fn with_context<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut dyn WinCtx) -> R,
+    {
+        let mut ctx = WinCtxImpl {
+            nsview: &self.nsview,
+            text: Text::new(),
+        };
+        Some(f(&mut ctx))
+    }
+
     pub fn show(&self) {
         unsafe {
             let window: id = msg_send![*self.nsview.load(), window];
@@ -655,6 +1190,26 @@ impl WindowHandle {
                 .autorelease();
             let notif_center: id = msg_send![notif_center_class, defaultCenter];
             let () = msg_send![notif_center, addObserver:*self.nsview.load() selector: sel!(windowDidBecomeKey:) name: notif_string object: window];
+            let resign_string = NSString::alloc(nil)
+                .init_str(NSWindowDidResignKeyNotification)
+                .autorelease();
+            let () = msg_send![notif_center, addObserver:*self.nsview.load() selector: sel!(windowDidResignKey:) name: resign_string object: window];
+            let move_string = NSString::alloc(nil)
+                .init_str(NSWindowDidMoveNotification)
+                .autorelease();
+            let () = msg_send![notif_center, addObserver:*self.nsview.load() selector: sel!(windowDidMove:) name: move_string object: window];
+
+            // sleep and wake are posted to the workspace's own notification center.
+            let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let workspace_center: id = msg_send![workspace, notificationCenter];
+            let sleep_string = NSString::alloc(nil)
+                .init_str(NSWorkspaceWillSleepNotification)
+                .autorelease();
+            let () = msg_send![workspace_center, addObserver:*self.nsview.load() selector: sel!(workspaceWillSleep:) name: sleep_string object: nil];
+            let wake_string = NSString::alloc(nil)
+                .init_str(NSWorkspaceDidWakeNotification)
+                .autorelease();
+            let () = msg_send![workspace_center, addObserver:*self.nsview.load() selector: sel!(workspaceDidWake:) name: wake_string object: nil];
             window.makeKeyAndOrderFront_(nil)
         }
     }
@@ -667,6 +1222,18 @@ impl WindowHandle {
         }
     }
 
+    /// Hide or show the window.
+    pub fn set_visible(&self, visible: bool) {
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            if visible {
+                let () = msg_send![window, orderFront: nil];
+            } else {
+                let () = msg_send![window, orderOut: nil];
+            }
+        }
+    }
+
     /// Bring this window to the front of the window stack and give it focus.
     pub fn bring_to_front_and_focus(&self) {
         unsafe {
@@ -675,6 +1242,62 @@ impl WindowHandle {
         }
     }
 
+    /// Set the size of the window's frame, in px.
+    pub fn set_size(&self, size: Size) {
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            let mut frame: NSRect = msg_send![window, frame];
+            // AppKit's origin is the bottom-left corner; keep the top edge fixed.
+            frame.origin.y += frame.size.height - size.height;
+            frame.size = NSSize::new(size.width, size.height);
+            let () = msg_send![window, setFrame: frame display: YES];
+        }
+    }
+
+    /// Move the window's frame so that its top-left corner is at `position`,
+    /// in screen coordinates.
+    pub fn set_position(&self, position: Point) {
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            // the same coordinates as the monitors, and `window_did_move`.
+            let corner = Rect::from_origin_size(position, Size::ZERO);
+            let corner = screen::screen_coords().to_platform(corner);
+            let top_left = NSPoint::new(corner.x0, corner.y1);
+            let () = msg_send![window, setFrameTopLeftPoint: top_left];
+        }
+    }
+
+    pub fn minimize(&self) {
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            let () = msg_send![window, miniaturize: nil];
+        }
+    }
+
+    pub fn maximize(&self) {
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            let zoomed: BOOL = msg_send![window, isZoomed];
+            if zoomed == NO {
+                let () = msg_send![window, zoom: nil];
+            }
+        }
+    }
+
+    pub fn restore(&self) {
+        unsafe {
+            let window: id = msg_send![*self.nsview.load(), window];
+            let miniaturized: BOOL = msg_send![window, isMiniaturized];
+            if miniaturized == YES {
+                let () = msg_send![window, deminiaturize: nil];
+            }
+            let zoomed: BOOL = msg_send![window, isZoomed];
+            if zoomed == YES {
+                let () = msg_send![window, zoom: nil];
+            }
+        }
+    }
+
     // Request invalidation of the entire window contents.
     pub fn invalidate(&self) {
         unsafe {
@@ -683,6 +1306,18 @@ impl WindowHandle {
         }
     }
 
+    /// Request invalidation of part of the window contents.
+    pub fn invalidate_rect(&self, rect: Rect) {
+        let rect = NSRect::new(
+            NSPoint::new(rect.x0, rect.y0),
+            NSSize::new(rect.width(), rect.height()),
+        );
+        unsafe {
+            // the view is flipped, so this is in the same coordinates as `rect`.
+            let () = msg_send![*self.nsview.load(), setNeedsDisplayInRect: rect];
+        }
+    }
+
     /// Set the title for this menu.
     pub fn set_title(&self, title: &str) {
         unsafe {
@@ -719,6 +1354,21 @@ impl WindowHandle {
         }
     }
 
+    /// The `NSView` of the window, and the `NSWindow` that contains it.
+    #[cfg(feature = "raw-win-handle")]
+    pub fn raw_window_handle(&self) -> RawWindowHandle {
+        let nsview = self.nsview.load();
+        if nsview.is_null() {
+            return RawWindowHandle::MacOS(MacOSHandle::empty());
+        }
+        let ns_window: id = unsafe { msg_send![*nsview, window] };
+        RawWindowHandle::MacOS(MacOSHandle {
+            ns_window: ns_window as *mut c_void,
+            ns_view: *nsview as *mut c_void,
+            ..MacOSHandle::empty()
+        })
+    }
+
     /// Get the dpi of the window.
     ///
     /// TODO: we want to migrate this from dpi (with 96 as nominal) to a scale
@@ -757,6 +1407,10 @@ impl IdleHandle {
     }
 
     pub fn add_idle_token(&self, token: IdleToken) {
+        self.push(IdleKind::Token(token));
+    }
+
+    fn push(&self, kind: IdleKind) {
         if let Some(queue) = self.idle_queue.upgrade() {
             let mut queue = queue.lock().expect("queue lock");
             if queue.is_empty() {
@@ -767,7 +1421,7 @@ impl IdleHandle {
                         withObject: nil waitUntilDone: NO);
                 }
             }
-            queue.push(IdleKind::Token(token));
+            queue.push(kind);
         }
     }
 }
@@ -799,6 +1453,21 @@ impl<'a> WinCtx<'a> for WinCtxImpl<'a> {
         }
     }
 
+    fn set_ime_cursor_area(&mut self, rect: Rect) {
+        unsafe {
+            let view = self.nsview.load();
+            if view.is_null() {
+                return;
+            }
+            let view_state: *mut c_void = *(**view).get_ivar("viewState");
+            let view_state = &mut *(view_state as *mut ViewState);
+            view_state.ime_cursor_area = rect;
+            // ask the input method to move its candidate window.
+            let input_context: id = msg_send![*view, inputContext];
+            let () = msg_send![input_context, invalidateCharacterCoordinates];
+        }
+    }
+
     fn request_timer(&mut self, deadline: std::time::Instant) -> TimerToken {
         let ti = time_interval_from_deadline(deadline);
         let token = TimerToken::next();
@@ -822,6 +1491,40 @@ impl<'a> WinCtx<'a> for WinCtxImpl<'a> {
         dialog::get_file_dialog_path(FileDialogType::Save, options)
             .map(|s| FileInfo { path: s.into() })
     }
+
+    fn open_file(&mut self, options: FileDialogOptions) -> Option<FileDialogToken> {
+        self.file_dialog_async(FileDialogType::Open, options)
+    }
+
+    fn save_as(&mut self, options: FileDialogOptions) -> Option<FileDialogToken> {
+        self.file_dialog_async(FileDialogType::Save, options)
+    }
+}
+
+impl WinCtxImpl<'_> {
+    /// Queue a file dialog to be shown from the idle handler, once the
+    /// current event has been handled; `None` if the view is gone.
+    fn file_dialog_async(
+        &self,
+        ty: FileDialogType,
+        options: FileDialogOptions,
+    ) -> Option<FileDialogToken> {
+        let token = FileDialogToken::next();
+        unsafe {
+            let view = self.nsview.load();
+            if view.is_null() {
+                return None;
+            }
+            let view_state: *mut c_void = *(**view).get_ivar("viewState");
+            let view_state = &*(view_state as *mut ViewState);
+            let idle = IdleHandle {
+                nsview: self.nsview.clone(),
+                idle_queue: Arc::downgrade(&view_state.idle_queue),
+            };
+            idle.push(IdleKind::FileDialog(ty, options, token));
+        }
+        Some(token)
+    }
 }
 
 /// Convert an `Instant` into an NSTimeInterval, i.e. a fractional number
@@ -840,6 +1543,32 @@ fn time_interval_from_deadline(deadline: std::time::Instant) -> f64 {
     }
 }
 
+/// The text passed to an `NSTextInputClient` method, which may be an
+/// `NSString` or an `NSAttributedString`.
+fn string_from_text_input(text: id) -> String {
+    unsafe {
+        let is_attributed: BOOL = msg_send![text, isKindOfClass: class!(NSAttributedString)];
+        if is_attributed == YES {
+            from_nsstring(msg_send![text, string])
+        } else {
+            from_nsstring(text)
+        }
+    }
+}
+
+/// Convert an offset in UTF-16 code units, as Cocoa counts them, into a byte
+/// offset in `text`.
+fn utf8_offset(text: &str, utf16_offset: usize) -> usize {
+    let mut utf16 = 0;
+    for (idx, c) in text.char_indices() {
+        if utf16 >= utf16_offset {
+            return idx;
+        }
+        utf16 += c.len_utf16();
+    }
+    text.len()
+}
+
 fn make_key_event(event: id) -> KeyEvent {
     unsafe {
         let chars = event.characters();
@@ -880,6 +1609,23 @@ fn mods_changed_key_event(prev: KeyModifiers, event: id) -> (bool, KeyEvent) {
     }
 }
 
+fn make_wheel_phase(phase: NSEventPhase, momentum: NSEventPhase) -> WheelPhase {
+    let ends = NSEventPhase::NSEventPhaseEnded | NSEventPhase::NSEventPhaseCancelled;
+    if momentum.intersects(ends) {
+        WheelPhase::MomentumEnded
+    } else if !momentum.is_empty() {
+        WheelPhase::Momentum
+    } else if phase.contains(NSEventPhase::NSEventPhaseBegan) {
+        WheelPhase::Began
+    } else if phase.intersects(ends) {
+        WheelPhase::Ended
+    } else if !phase.is_empty() {
+        WheelPhase::Changed
+    } else {
+        WheelPhase::None
+    }
+}
+
 fn make_modifiers(raw: NSEventModifierFlags) -> KeyModifiers {
     KeyModifiers {
         shift: raw.contains(NSEventModifierFlags::NSShiftKeyMask),
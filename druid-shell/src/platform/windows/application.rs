@@ -14,12 +14,18 @@
 
 //! Windows implementation of features at the application scope.
 
+use std::mem;
+use std::ptr::null_mut;
+
 use winapi::shared::minwindef::HINSTANCE;
 use winapi::shared::ntdef::LPCWSTR;
 use winapi::shared::windef::HCURSOR;
 use winapi::um::shellscalingapi::PROCESS_SYSTEM_DPI_AWARE;
 use winapi::um::wingdi::CreateSolidBrush;
-use winapi::um::winuser::{LoadIconW, PostQuitMessage, RegisterClassW, IDI_APPLICATION, WNDCLASSW};
+use winapi::um::winuser::{
+    DispatchMessageW, GetMessageW, LoadIconW, PostQuitMessage, RegisterClassW, TranslateMessage,
+    IDI_APPLICATION, MSG, WNDCLASSW,
+};
 
 use super::clipboard::Clipboard;
 use super::util::{self, ToWide, CLASS_NAME, OPTIONAL_FUNCTIONS};
@@ -67,6 +73,27 @@ impl Application {
         }
     }
 
+    /// Note: unlike the main run loop, this does not translate accelerators
+    /// or service the run loop's handle listeners.
+    pub fn pump_events() -> bool {
+        unsafe {
+            let mut msg = mem::MaybeUninit::uninit();
+            let res = GetMessageW(msg.as_mut_ptr(), null_mut(), 0, 0);
+            if res <= 0 {
+                if res == 0 {
+                    // put WM_QUIT back, so that the main run loop sees it too.
+                    let msg: MSG = msg.assume_init();
+                    PostQuitMessage(msg.wParam as i32);
+                }
+                return false;
+            }
+            let msg: MSG = msg.assume_init();
+            TranslateMessage(&msg);
+            DispatchMessageW(&msg);
+            true
+        }
+    }
+
     pub fn clipboard() -> Clipboard {
         Clipboard
     }
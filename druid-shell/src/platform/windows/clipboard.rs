@@ -151,20 +151,33 @@ impl Clipboard {
         }
     }
 
+    /// The identifiers of every format currently on the clipboard.
+    ///
+    /// Standard formats are reported by their `CF_` names.
+    pub fn available_formats(&self) -> Vec<String> {
+        available_types().into_iter().map(get_format_name).collect()
+    }
+
     pub fn available_type_names(&self) -> Vec<String> {
-        unsafe {
-            if OpenClipboard(ptr::null_mut()) == FALSE {
-                return vec![];
-            }
-        }
-        let res = iter_clipboard_types()
+        available_types()
+            .into_iter()
             .map(|id| format!("{}: {}", get_format_name(id), id))
-            .collect();
-        unsafe {
-            CloseClipboard();
+            .collect()
+    }
+}
+
+/// The ids of the formats currently on the clipboard.
+fn available_types() -> Vec<UINT> {
+    unsafe {
+        if OpenClipboard(ptr::null_mut()) == FALSE {
+            return vec![];
         }
-        res
     }
+    let res = iter_clipboard_types().collect();
+    unsafe {
+        CloseClipboard();
+    }
+    res
 }
 
 unsafe fn make_handle(format: &ClipboardFormat) -> HANDLE {
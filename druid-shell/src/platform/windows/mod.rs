@@ -23,6 +23,7 @@ pub mod keycodes;
 pub mod menu;
 pub mod paint;
 pub mod runloop;
+pub mod screen;
 mod timers;
 pub mod util;
 pub mod window;
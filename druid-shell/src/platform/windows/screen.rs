@@ -0,0 +1,96 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Windows monitors.
+
+use std::mem;
+use std::ptr::null_mut;
+
+use winapi::shared::minwindef::*;
+use winapi::shared::windef::*;
+use winapi::um::shellscalingapi::MDT_EFFECTIVE_DPI;
+use winapi::um::winuser::*;
+
+use super::util::{system_scale, OPTIONAL_FUNCTIONS};
+use crate::kurbo::Rect;
+use crate::screen::{Monitor, ScreenCoords};
+
+/// Screen coordinates span monitors that may each have their own dpi, so
+/// they are converted to and from px at the system's scale, whatever
+/// monitor they are on.
+pub(crate) fn screen_coords() -> ScreenCoords {
+    ScreenCoords {
+        scale: system_scale(),
+        flip_height: None,
+    }
+}
+
+pub(crate) fn to_rect(rect: RECT) -> Rect {
+    Rect::new(
+        rect.left as f64,
+        rect.top as f64,
+        rect.right as f64,
+        rect.bottom as f64,
+    )
+}
+
+/// The scale factor of a monitor, where `1.0` is 96 dpi.
+fn monitor_scale(hmonitor: HMONITOR) -> f64 {
+    if let Some(func) = OPTIONAL_FUNCTIONS.GetDpiForMonitor {
+        let (mut dpi_x, mut dpi_y) = (0, 0);
+        unsafe { func(hmonitor, MDT_EFFECTIVE_DPI, &mut dpi_x, &mut dpi_y) };
+        if dpi_x > 0 {
+            return dpi_x as f64 / 96.0;
+        }
+    }
+    // only supported on windows 8.1 and up.
+    1.0
+}
+
+unsafe extern "system" fn monitor_enum_proc(
+    hmonitor: HMONITOR,
+    _hdc: HDC,
+    _rect: LPRECT,
+    data: LPARAM,
+) -> BOOL {
+    let monitors = &mut *(data as *mut Vec<Monitor>);
+    let mut info: MONITORINFO = mem::zeroed();
+    info.cbSize = mem::size_of::<MONITORINFO>() as DWORD;
+    if GetMonitorInfoW(hmonitor, &mut info) == FALSE {
+        log::warn!("failed to get monitor info");
+        return TRUE;
+    }
+    // every rect is converted at the same scale, whatever the monitor's own
+    // is, so that they don't overlap.
+    let coords = screen_coords();
+    let to_px = |rect: RECT| coords.to_px(to_rect(rect));
+    monitors.push(Monitor::new(
+        info.dwFlags & MONITORINFOF_PRIMARY != 0,
+        to_px(info.rcMonitor),
+        to_px(info.rcWork),
+        monitor_scale(hmonitor),
+    ));
+    TRUE
+}
+
+pub(crate) fn get_monitors() -> Vec<Monitor> {
+    let mut monitors = Vec::new();
+    unsafe {
+        let data = &mut monitors as *mut Vec<Monitor> as LPARAM;
+        if EnumDisplayMonitors(null_mut(), null_mut(), Some(monitor_enum_proc), data) == FALSE {
+            log::warn!("failed to enumerate monitors");
+        }
+    }
+    monitors
+}
@@ -212,6 +212,19 @@ lazy_static! {
     pub static ref OPTIONAL_FUNCTIONS: OptionalFunctions = load_optional_functions();
 }
 
+/// The scale factor of the system, where `1.0` is 96 dpi.
+///
+/// Screen coordinates span monitors that may each have their own dpi, so
+/// they are converted to and from px at this one scale, whatever monitor
+/// they are on.
+pub(crate) fn system_scale() -> f64 {
+    match OPTIONAL_FUNCTIONS.GetDpiForSystem {
+        // only supported on windows 10 and up.
+        Some(func) => f64::from(unsafe { func() }) / 96.0,
+        None => 1.0,
+    }
+}
+
 pub(crate) const CLASS_NAME: &str = "druid";
 
 /// Convenience macro for defining accelerator tables.
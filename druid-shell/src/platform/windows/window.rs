@@ -34,16 +34,21 @@ use winapi::shared::minwindef::*;
 use winapi::shared::windef::*;
 use winapi::shared::winerror::*;
 use winapi::um::d2d1::*;
+use winapi::um::imm::*;
+use winapi::um::shellapi::{DragAcceptFiles, DragFinish, DragQueryFileW, DragQueryPoint, HDROP};
 use winapi::um::unknwnbase::*;
+use winapi::um::winbase::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
 use winapi::um::winnt::*;
 use winapi::um::winuser::*;
 
 use piet_common::d2d::{D2DFactory, DeviceContext};
 use piet_common::dwrite::DwriteFactory;
+#[cfg(feature = "raw-win-handle")]
+use raw_window_handle::{windows::WindowsHandle, RawWindowHandle};
 
 use crate::platform::windows::HwndRenderTarget;
 
-use crate::kurbo::{Point, Size, Vec2};
+use crate::kurbo::{Point, Rect, Size, Vec2};
 use crate::piet::{Piet, RenderContext};
 
 use super::dcomp::{D3D11Device, DCompositionDevice, DCompositionTarget, DCompositionVisual};
@@ -51,15 +56,16 @@ use super::dialog::get_file_dialog_path;
 use super::error::Error;
 use super::menu::Menu;
 use super::paint;
+use super::screen;
 use super::timers::TimerSlots;
 use super::util::{as_result, FromWide, ToWide, OPTIONAL_FUNCTIONS};
 
 use crate::common_util::IdleCallback;
-use crate::dialog::{FileDialogOptions, FileDialogType, FileInfo};
+use crate::dialog::{FileDialogOptions, FileDialogToken, FileDialogType, FileInfo};
 use crate::keyboard::{KeyEvent, KeyModifiers};
 use crate::keycodes::KeyCode;
-use crate::mouse::{Cursor, MouseButton, MouseEvent};
-use crate::window::{IdleToken, Text, TimerToken, WinCtx, WinHandler};
+use crate::mouse::{Cursor, MouseButton, MouseEvent, WheelPhase, WheelUnit};
+use crate::window::{IdleToken, PowerEvent, Text, TimerToken, WinCtx, WinHandler};
 
 extern "system" {
     pub fn DwmFlush();
@@ -73,6 +79,7 @@ pub struct WindowBuilder {
     menu: Option<Menu>,
     present_strategy: PresentStrategy,
     size: Size,
+    always_on_top: bool,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
@@ -123,6 +130,8 @@ pub struct IdleHandle {
 enum IdleKind {
     Callback(Box<dyn IdleCallback>),
     Token(IdleToken),
+    /// A file dialog requested with `WinCtx::open_file` or `WinCtx::save_as`.
+    FileDialog(FileDialogType, FileDialogOptions, FileDialogToken),
 }
 
 /// This is the low level window state. All mutable contents are protected
@@ -168,6 +177,13 @@ struct WndState {
     /// The `char` of the last `WM_CHAR` event, if there has not already been
     /// a `WM_KEYUP` event.
     stashed_char: Option<char>,
+    /// Whether we have asked to be told when the mouse leaves the window.
+    tracking_mouse_leave: bool,
+    /// The number of mouse buttons held down; the mouse is captured while
+    /// this is nonzero, so that we see the releases outside the window.
+    buttons_down: u32,
+    /// Whether the battery was low at the last power status change.
+    battery_low: bool,
     //TODO: track surrogate orphan
 }
 
@@ -236,6 +252,43 @@ fn get_mod_state() -> KeyModifiers {
     }
 }
 
+/// Convert the raw delta of a wheel message to the amount the user has
+/// set the wheel to scroll, from the `SPI_GETWHEELSCROLLLINES` (or, for the
+/// horizontal wheel, `SPI_GETWHEELSCROLLCHARS`) setting.
+fn wheel_delta(raw: i16, setting: UINT) -> (f64, WheelUnit) {
+    let notches = raw as f64 / WHEEL_DELTA as f64;
+    let mut lines: UINT = 3;
+    unsafe {
+        SystemParametersInfoW(setting, 0, &mut lines as *mut UINT as *mut c_void, 0);
+    }
+    if lines == WHEEL_PAGESCROLL {
+        (notches, WheelUnit::Pages)
+    } else {
+        (notches * lines as f64, WheelUnit::Lines)
+    }
+}
+
+// not yet in winapi's imm module
+const GCS_COMPSTR: DWORD = 0x0008;
+const GCS_CURSORPOS: DWORD = 0x0080;
+const GCS_RESULTSTR: DWORD = 0x0800;
+
+extern "system" {
+    fn ImmGetCompositionStringW(himc: HIMC, index: DWORD, buf: LPVOID, len: DWORD) -> LONG;
+}
+
+/// Read one of the strings of an input method composition.
+unsafe fn get_composition_string(himc: HIMC, index: DWORD) -> String {
+    let len = ImmGetCompositionStringW(himc, index, null_mut(), 0);
+    if len <= 0 {
+        return String::new();
+    }
+    // the length is in bytes
+    let mut buf = vec![0u16; len as usize / 2];
+    ImmGetCompositionStringW(himc, index, buf.as_mut_ptr() as LPVOID, len as DWORD);
+    String::from_utf16_lossy(&buf)
+}
+
 impl WndState {
     fn rebuild_render_target(&mut self, d2d: &D2DFactory) {
         unsafe {
@@ -254,12 +307,27 @@ impl WndState {
         handle: &RefCell<WindowHandle>,
         c: &mut WinCtxOwner,
     ) {
+        // the swap chain is always redrawn in full, so all of it is invalid.
+        let invalid_rect = {
+            let handle = handle.borrow();
+            let mut rect: RECT = unsafe { mem::zeroed() };
+            if let Some(hwnd) = handle.get_hwnd() {
+                unsafe {
+                    GetClientRect(hwnd, &mut rect);
+                }
+            }
+            let (width, height) =
+                handle.pixels_to_px_xy(rect.right - rect.left, rect.bottom - rect.top);
+            Rect::new(0.0, 0.0, width.into(), height.into())
+        };
         let rt = self.render_target.as_mut().unwrap();
         rt.begin_draw();
         let anim;
         {
             let mut piet_ctx = Piet::new(d2d, dw, rt);
-            anim = self.handler.paint(&mut piet_ctx, &mut c.ctx());
+            anim = self
+                .handler
+                .paint(&mut piet_ctx, invalid_rect, &mut c.ctx());
             if let Err(e) = piet_ctx.finish() {
                 error!("piet error on render: {:?}", e);
             }
@@ -340,6 +408,16 @@ impl WndProc for MyWndProc {
                 }
                 Some(0)
             }
+            WM_KILLFOCUS => {
+                if let Ok(mut s) = self.state.try_borrow_mut() {
+                    let s = s.as_mut().unwrap();
+                    let mut c = WinCtxOwner::new(self.handle.borrow(), &self.dwrite_factory);
+                    s.handler.lost_focus(&mut c.ctx());
+                } else {
+                    self.log_dropped_msg(hwnd, msg, wparam, lparam);
+                }
+                Some(0)
+            }
             WM_PAINT => unsafe {
                 if let Ok(mut s) = self.state.try_borrow_mut() {
                     let s = s.as_mut().unwrap();
@@ -363,6 +441,10 @@ impl WndProc for MyWndProc {
                     }
                     ValidateRect(hwnd, null_mut());
                 } else {
+                    // The window stays invalid until it is validated, so without
+                    // this a window whose handler is running a nested event loop
+                    // would be sent WM_PAINT over and over.
+                    ValidateRect(hwnd, null_mut());
                     self.log_dropped_msg(hwnd, msg, wparam, lparam);
                 }
                 Some(0)
@@ -447,6 +529,76 @@ impl WndProc for MyWndProc {
                 }
                 None
             },
+            WM_DPICHANGED => unsafe {
+                let dpi = HIWORD(wparam as u32) as f32;
+                if let Some(w) = self.handle.borrow().state.upgrade() {
+                    w.dpi.set(dpi);
+                }
+                if let Ok(mut s) = self.state.try_borrow_mut() {
+                    let s = s.as_mut().unwrap();
+                    let mut c = WinCtxOwner::new(self.handle.borrow(), &self.dwrite_factory);
+                    s.handler.scale(f64::from(dpi) / 96.0, &mut c.ctx());
+                } else {
+                    self.log_dropped_msg(hwnd, msg, wparam, lparam);
+                }
+                // the system suggests a new frame that keeps the window at
+                // the same apparent size on its new monitor.
+                let rect = &*(lparam as *const RECT);
+                SetWindowPos(
+                    hwnd,
+                    null_mut(),
+                    rect.left,
+                    rect.top,
+                    rect.right - rect.left,
+                    rect.bottom - rect.top,
+                    SWP_NOZORDER | SWP_NOACTIVATE,
+                );
+                Some(0)
+            },
+            WM_DROPFILES => unsafe {
+                let hdrop = wparam as HDROP;
+                if let Ok(mut s) = self.state.try_borrow_mut() {
+                    let s = s.as_mut().unwrap();
+                    let count = DragQueryFileW(hdrop, 0xFFFF_FFFF, null_mut(), 0);
+                    let files = (0..count)
+                        .map(|i| {
+                            let len = DragQueryFileW(hdrop, i, null_mut(), 0) + 1;
+                            let mut buf = vec![0u16; len as usize];
+                            DragQueryFileW(hdrop, i, buf.as_mut_ptr(), len);
+                            buf.truncate(len as usize - 1);
+                            FileInfo::new(buf.to_os_string())
+                        })
+                        .collect::<Vec<_>>();
+                    let mut point = POINT { x: 0, y: 0 };
+                    DragQueryPoint(hdrop, &mut point);
+                    let (px, py) = self.handle.borrow().pixels_to_px_xy(point.x, point.y);
+                    let pos = Point::new(px as f64, py as f64);
+                    let mut c = WinCtxOwner::new(self.handle.borrow(), &self.dwrite_factory);
+                    s.handler.drop_files(&files, pos, &mut c.ctx());
+                } else {
+                    self.log_dropped_msg(hwnd, msg, wparam, lparam);
+                }
+                DragFinish(hdrop);
+                Some(0)
+            },
+            WM_MOVE => unsafe {
+                if let Ok(mut s) = self.state.try_borrow_mut() {
+                    let s = s.as_mut().unwrap();
+                    // `lparam` is the origin of the client area; the position
+                    // is that of the frame, as for `set_position`.
+                    let mut rect: RECT = mem::zeroed();
+                    if GetWindowRect(hwnd, &mut rect) == FALSE {
+                        warn!("failed to get window rect");
+                        return Some(0);
+                    }
+                    let frame = screen::screen_coords().to_px(screen::to_rect(rect));
+                    let mut c = WinCtxOwner::new(self.handle.borrow(), &self.dwrite_factory);
+                    s.handler.position(frame.origin(), &mut c.ctx());
+                } else {
+                    self.log_dropped_msg(hwnd, msg, wparam, lparam);
+                }
+                Some(0)
+            },
             WM_SIZE => unsafe {
                 if let Ok(mut s) = self.state.try_borrow_mut() {
                     let s = s.as_mut().unwrap();
@@ -546,6 +698,55 @@ impl WndProc for MyWndProc {
                     None
                 }
             }
+            // we draw the composition ourselves, so the default composition
+            // window is suppressed.
+            WM_IME_STARTCOMPOSITION => Some(0),
+            WM_IME_COMPOSITION => {
+                if let Ok(mut s) = self.state.try_borrow_mut() {
+                    let s = s.as_mut().unwrap();
+                    let mut c = WinCtxOwner::new(self.handle.borrow(), &self.dwrite_factory);
+                    unsafe {
+                        let himc = ImmGetContext(hwnd);
+                        if lparam as DWORD & GCS_RESULTSTR != 0 {
+                            let text = get_composition_string(himc, GCS_RESULTSTR);
+                            s.handler.ime_commit(&text, &mut c.ctx());
+                        }
+                        if lparam as DWORD & GCS_COMPSTR != 0 {
+                            let text = get_composition_string(himc, GCS_COMPSTR);
+                            let cursor =
+                                ImmGetCompositionStringW(himc, GCS_CURSORPOS, null_mut(), 0);
+                            // the cursor is counted in utf-16 code units
+                            let cursor = text
+                                .char_indices()
+                                .scan(0, |units, (idx, ch)| {
+                                    let here = *units;
+                                    *units += ch.len_utf16() as i32;
+                                    Some((idx, here))
+                                })
+                                .find(|&(_, units)| units >= cursor)
+                                .map(|(idx, _)| idx)
+                                .unwrap_or_else(|| text.len());
+                            s.handler.ime_composition(&text, cursor, &mut c.ctx());
+                        }
+                        ImmReleaseContext(hwnd, himc);
+                    }
+                } else {
+                    self.log_dropped_msg(hwnd, msg, wparam, lparam);
+                }
+                Some(0)
+            }
+            WM_IME_ENDCOMPOSITION => {
+                // sent after the result, or when the composition is cancelled;
+                // either way, nothing is being composed anymore.
+                if let Ok(mut s) = self.state.try_borrow_mut() {
+                    let s = s.as_mut().unwrap();
+                    let mut c = WinCtxOwner::new(self.handle.borrow(), &self.dwrite_factory);
+                    s.handler.ime_composition("", 0, &mut c.ctx());
+                } else {
+                    self.log_dropped_msg(hwnd, msg, wparam, lparam);
+                }
+                Some(0)
+            }
             WM_KEYDOWN | WM_SYSKEYDOWN => {
                 if let Ok(mut s) = self.state.try_borrow_mut() {
                     let s = s.as_mut().unwrap();
@@ -591,15 +792,15 @@ impl WndProc for MyWndProc {
             }
             //TODO: WM_SYSCOMMAND
             WM_MOUSEWHEEL => {
-                // TODO: apply mouse sensitivity based on
-                // SPI_GETWHEELSCROLLLINES setting.
                 if let Ok(mut s) = self.state.try_borrow_mut() {
                     let s = s.as_mut().unwrap();
-                    let delta_y = HIWORD(wparam as u32) as i16 as f64;
+                    let raw = HIWORD(wparam as u32) as i16;
+                    let (delta_y, unit) = wheel_delta(raw, SPI_GETWHEELSCROLLLINES);
                     let delta = Vec2::new(0.0, -delta_y);
                     let mods = get_mod_state();
                     let mut c = WinCtxOwner::new(self.handle.borrow(), &self.dwrite_factory);
-                    s.handler.wheel(delta, mods, &mut c.ctx());
+                    s.handler
+                        .wheel(delta, unit, WheelPhase::None, mods, &mut c.ctx());
                 } else {
                     self.log_dropped_msg(hwnd, msg, wparam, lparam);
                 }
@@ -608,11 +809,13 @@ impl WndProc for MyWndProc {
             WM_MOUSEHWHEEL => {
                 if let Ok(mut s) = self.state.try_borrow_mut() {
                     let s = s.as_mut().unwrap();
-                    let delta_x = HIWORD(wparam as u32) as i16 as f64;
+                    let raw = HIWORD(wparam as u32) as i16;
+                    let (delta_x, unit) = wheel_delta(raw, SPI_GETWHEELSCROLLCHARS);
                     let delta = Vec2::new(delta_x, 0.0);
                     let mods = get_mod_state();
                     let mut c = WinCtxOwner::new(self.handle.borrow(), &self.dwrite_factory);
-                    s.handler.wheel(delta, mods, &mut c.ctx());
+                    s.handler
+                        .wheel(delta, unit, WheelPhase::None, mods, &mut c.ctx());
                 } else {
                     self.log_dropped_msg(hwnd, msg, wparam, lparam);
                 }
@@ -642,6 +845,18 @@ impl WndProc for MyWndProc {
                         button,
                         count: 0,
                     };
+                    if !s.tracking_mouse_leave {
+                        let mut desc = TRACKMOUSEEVENT {
+                            cbSize: mem::size_of::<TRACKMOUSEEVENT>() as DWORD,
+                            dwFlags: TME_LEAVE,
+                            hwndTrack: hwnd,
+                            dwHoverTime: HOVER_DEFAULT,
+                        };
+                        unsafe {
+                            TrackMouseEvent(&mut desc);
+                        }
+                        s.tracking_mouse_leave = true;
+                    }
                     let mut c = WinCtxOwner::new(self.handle.borrow(), &self.dwrite_factory);
                     s.handler.mouse_move(&event, &mut c.ctx());
                 } else {
@@ -649,6 +864,24 @@ impl WndProc for MyWndProc {
                 }
                 Some(0)
             }
+            WM_MOUSELEAVE => {
+                if let Ok(mut s) = self.state.try_borrow_mut() {
+                    let s = s.as_mut().unwrap();
+                    s.tracking_mouse_leave = false;
+                    let mut c = WinCtxOwner::new(self.handle.borrow(), &self.dwrite_factory);
+                    s.handler.mouse_leave(&mut c.ctx());
+                } else {
+                    self.log_dropped_msg(hwnd, msg, wparam, lparam);
+                }
+                Some(0)
+            }
+            WM_CAPTURECHANGED => {
+                // the capture was taken away, so we won't see the releases.
+                if let Ok(mut s) = self.state.try_borrow_mut() {
+                    s.as_mut().unwrap().buttons_down = 0;
+                }
+                None
+            }
             // TODO: not clear where double-click processing should happen. Currently disabled
             // because CS_DBLCLKS is not set
             WM_LBUTTONDBLCLK | WM_LBUTTONDOWN | WM_LBUTTONUP | WM_MBUTTONDBLCLK
@@ -692,8 +925,20 @@ impl WndProc for MyWndProc {
                     };
                     let mut c = WinCtxOwner::new(self.handle.borrow(), &self.dwrite_factory);
                     if count > 0 {
+                        if s.buttons_down == 0 {
+                            unsafe {
+                                SetCapture(hwnd);
+                            }
+                        }
+                        s.buttons_down += 1;
                         s.handler.mouse_down(&event, &mut c.ctx());
                     } else {
+                        s.buttons_down = s.buttons_down.saturating_sub(1);
+                        if s.buttons_down == 0 {
+                            unsafe {
+                                ReleaseCapture();
+                            }
+                        }
                         s.handler.mouse_up(&event, &mut c.ctx());
                     }
                 } else {
@@ -701,6 +946,21 @@ impl WndProc for MyWndProc {
                 }
                 Some(0)
             }
+            WM_CLOSE => {
+                // returning `None` lets the default window proc destroy the window.
+                if let Ok(mut s) = self.state.try_borrow_mut() {
+                    let s = s.as_mut().unwrap();
+                    let mut c = WinCtxOwner::new(self.handle.borrow(), &self.dwrite_factory);
+                    if s.handler.request_close(&mut c.ctx()) {
+                        None
+                    } else {
+                        Some(0)
+                    }
+                } else {
+                    self.log_dropped_msg(hwnd, msg, wparam, lparam);
+                    None
+                }
+            }
             XI_REQUEST_DESTROY => {
                 unsafe {
                     DestroyWindow(hwnd);
@@ -731,7 +991,35 @@ impl WndProc for MyWndProc {
                 }
                 Some(1)
             }
+            WM_POWERBROADCAST => {
+                if let Ok(mut s) = self.state.try_borrow_mut() {
+                    let s = s.as_mut().unwrap();
+                    let event = match wparam {
+                        PBT_APMSUSPEND => Some(PowerEvent::WillSleep),
+                        PBT_APMRESUMEAUTOMATIC => Some(PowerEvent::DidWake),
+                        // the status changes with every percent of charge;
+                        // only tell the handler when the battery becomes low.
+                        PBT_APMPOWERSTATUSCHANGE => {
+                            let was_low = mem::replace(&mut s.battery_low, battery_is_low());
+                            if s.battery_low && !was_low {
+                                Some(PowerEvent::BatteryLow)
+                            } else {
+                                None
+                            }
+                        }
+                        _ => None,
+                    };
+                    if let Some(event) = event {
+                        let mut c = WinCtxOwner::new(self.handle.borrow(), &self.dwrite_factory);
+                        s.handler.power_event(event, &mut c.ctx());
+                    }
+                } else {
+                    self.log_dropped_msg(hwnd, msg, wparam, lparam);
+                }
+                Some(TRUE as LRESULT)
+            }
             XI_RUN_IDLE => {
+                let mut dialogs = Vec::new();
                 if let Ok(mut s) = self.state.try_borrow_mut() {
                     let s = s.as_mut().unwrap();
                     let queue = self.handle.borrow().take_idle_queue();
@@ -740,18 +1028,56 @@ impl WndProc for MyWndProc {
                         match callback {
                             IdleKind::Callback(it) => it.call(s.handler.as_any()),
                             IdleKind::Token(token) => s.handler.idle(token, &mut c.ctx()),
+                            IdleKind::FileDialog(ty, options, token) => {
+                                dialogs.push((ty, options, token))
+                            }
                         }
                     }
-                    Some(0)
                 } else {
-                    None
+                    return None;
+                }
+                // a file dialog runs a message loop of its own until it is
+                // dismissed. We show it without holding the state, so that
+                // the loop can still deliver our messages, and the window
+                // keeps painting and handling timers and commands meanwhile.
+                for (ty, options, token) in dialogs {
+                    let file = unsafe { get_file_dialog_path(hwnd, ty, options) };
+                    let file = file.ok().map(|path| FileInfo { path: path.into() });
+                    if let Ok(mut s) = self.state.try_borrow_mut() {
+                        let s = s.as_mut().unwrap();
+                        let mut c = WinCtxOwner::new(self.handle.borrow(), &self.dwrite_factory);
+                        match ty {
+                            FileDialogType::Open => s.handler.open_file(token, file, &mut c.ctx()),
+                            FileDialogType::Save => s.handler.save_as(token, file, &mut c.ctx()),
+                        }
+                    } else {
+                        error!("dropped the result of file dialog {:?}", token);
+                    }
                 }
+                Some(0)
             }
             _ => None,
         }
     }
 }
 
+/// Returns `true` if we are running on a battery that is low or critical.
+fn battery_is_low() -> bool {
+    // BatteryFlag bits: 2 is low, 4 is critical, 128 is "no battery".
+    const LOW_OR_CRITICAL: BYTE = 2 | 4;
+    const NO_BATTERY: BYTE = 128;
+    unsafe {
+        let mut status: SYSTEM_POWER_STATUS = mem::zeroed();
+        if GetSystemPowerStatus(&mut status) == 0 {
+            return false;
+        }
+        // ACLineStatus is 0 when we are running on battery.
+        status.ACLineStatus == 0
+            && status.BatteryFlag & NO_BATTERY == 0
+            && status.BatteryFlag & LOW_OR_CRITICAL != 0
+    }
+}
+
 // Note: there's a clone method in 0.3.0-alpha4. We work around
 // the lack in 0.1.2 by calling the low-level unsafe operations.
 fn clone_dwrite(dwrite: &DwriteFactory) -> DwriteFactory {
@@ -770,6 +1096,7 @@ impl WindowBuilder {
             menu: None,
             present_strategy: Default::default(),
             size: Size::new(500.0, 400.0),
+            always_on_top: false,
         }
     }
 
@@ -800,6 +1127,24 @@ impl WindowBuilder {
         self.menu = Some(menu);
     }
 
+    pub fn set_resizable(&mut self, resizable: bool) {
+        if resizable {
+            self.dwStyle |= WS_THICKFRAME | WS_MAXIMIZEBOX;
+        } else {
+            self.dwStyle &= !(WS_THICKFRAME | WS_MAXIMIZEBOX);
+        }
+    }
+
+    pub fn set_always_on_top(&mut self, always_on_top: bool) {
+        self.always_on_top = always_on_top;
+    }
+
+    pub fn set_transparent(&mut self, transparent: bool) {
+        if transparent {
+            warn!("transparent windows are not yet supported on windows");
+        }
+    }
+
     pub fn set_present_strategy(&mut self, present_strategy: PresentStrategy) {
         self.present_strategy = present_strategy;
     }
@@ -853,6 +1198,9 @@ impl WindowBuilder {
             if self.present_strategy == PresentStrategy::Flip {
                 dwExStyle |= WS_EX_NOREDIRECTIONBITMAP;
             }
+            if self.always_on_top {
+                dwExStyle |= WS_EX_TOPMOST;
+            }
             let hwnd = create_window(
                 dwExStyle,
                 class_name.as_ptr(),
@@ -870,6 +1218,8 @@ impl WindowBuilder {
             if hwnd.is_null() {
                 return Err(Error::NullHwnd);
             }
+            // WM_DROPFILES has no hover phase, so `drag_enter` is never called.
+            DragAcceptFiles(hwnd, TRUE);
 
             let dcomp_state = create_dcomp_state(self.present_strategy, hwnd).unwrap_or_else(|e| {
                 warn!("Creating swapchain failed, falling back to hwnd: {:?}", e);
@@ -884,6 +1234,9 @@ impl WindowBuilder {
                 dpi,
                 stashed_key_code: KeyCode::Unknown(0),
                 stashed_char: None,
+                tracking_mouse_leave: false,
+                buttons_down: 0,
+                battery_low: false,
             };
             win.wndproc.connect(&handle, state);
             mem::drop(win);
@@ -1092,6 +1445,27 @@ impl Clone for WindowHandle {
 }
 
 impl WindowHandle {
+    pub fn with_context<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut dyn WinCtx) -> R,
+    {
+        let dwrite = match self.dwrite_factory.as_ref() {
+            Some(dwrite) => clone_dwrite(dwrite),
+            None => match DwriteFactory::new() {
+                Ok(dwrite) => dwrite,
+                Err(e) => {
+                    warn!("failed to create a text factory for a WinCtx: {:?}", e);
+                    return None;
+                }
+            },
+        };
+        let mut ctx = WinCtxImpl {
+            handle: self,
+            text: Text::new(&dwrite),
+        };
+        Some(f(&mut ctx))
+    }
+
     pub fn show(&self) {
         if let Some(w) = self.state.upgrade() {
             let hwnd = w.hwnd.get();
@@ -1111,12 +1485,72 @@ impl WindowHandle {
         }
     }
 
+    /// Hide or show the window.
+    pub fn set_visible(&self, visible: bool) {
+        self.show_window(if visible { SW_SHOW } else { SW_HIDE });
+    }
+
     /// Bring this window to the front of the window stack and give it focus.
     pub fn bring_to_front_and_focus(&self) {
         //FIXME: implementation goes here
         log::warn!("bring_to_front_and_focus not yet implemented on windows");
     }
 
+    /// Set the size of the window's frame, in px.
+    pub fn set_size(&self, size: Size) {
+        if let Some(w) = self.state.upgrade() {
+            let hwnd = w.hwnd.get();
+            let (width, height) = self.px_to_pixels_xy(size.width as f32, size.height as f32);
+            unsafe {
+                let flags = SWP_NOMOVE | SWP_NOZORDER | SWP_NOACTIVATE;
+                if SetWindowPos(hwnd, null_mut(), 0, 0, width, height, flags) == FALSE {
+                    warn!("failed to set window size to {:?}", size);
+                }
+            }
+        }
+    }
+
+    /// Move the window's frame so that its top-left corner is at `position`,
+    /// in screen coordinates.
+    ///
+    /// These are converted at the system's scale, not the window's, as are
+    /// the rects of the monitors.
+    pub fn set_position(&self, position: Point) {
+        if let Some(w) = self.state.upgrade() {
+            let hwnd = w.hwnd.get();
+            let corner = Rect::from_origin_size(position, Size::ZERO);
+            let corner = screen::screen_coords().to_platform(corner);
+            let (x, y) = (corner.x0.round() as i32, corner.y0.round() as i32);
+            unsafe {
+                let flags = SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE;
+                if SetWindowPos(hwnd, null_mut(), x, y, 0, 0, flags) == FALSE {
+                    warn!("failed to set window position to {:?}", position);
+                }
+            }
+        }
+    }
+
+    pub fn minimize(&self) {
+        self.show_window(SW_MINIMIZE);
+    }
+
+    pub fn maximize(&self) {
+        self.show_window(SW_MAXIMIZE);
+    }
+
+    pub fn restore(&self) {
+        self.show_window(SW_RESTORE);
+    }
+
+    fn show_window(&self, cmd: c_int) {
+        if let Some(w) = self.state.upgrade() {
+            let hwnd = w.hwnd.get();
+            unsafe {
+                ShowWindow(hwnd, cmd);
+            }
+        }
+    }
+
     pub fn invalidate(&self) {
         if let Some(w) = self.state.upgrade() {
             let hwnd = w.hwnd.get();
@@ -1126,6 +1560,14 @@ impl WindowHandle {
         }
     }
 
+    /// Request invalidation of part of the window.
+    ///
+    /// The swap chain can only be presented in full, so this invalidates
+    /// the whole window.
+    pub fn invalidate_rect(&self, _rect: Rect) {
+        self.invalidate();
+    }
+
     /// Set the title for this menu.
     pub fn set_title(&self, title: &str) {
         if let Some(w) = self.state.upgrade() {
@@ -1175,6 +1617,21 @@ impl WindowHandle {
         self.state.upgrade().map(|w| w.hwnd.get())
     }
 
+    /// The HWND of the window, and the module that created it.
+    #[cfg(feature = "raw-win-handle")]
+    pub fn raw_window_handle(&self) -> RawWindowHandle {
+        let hwnd = match self.get_hwnd() {
+            Some(hwnd) if !hwnd.is_null() => hwnd,
+            _ => return RawWindowHandle::Windows(WindowsHandle::empty()),
+        };
+        let hinstance = unsafe { GetWindowLongPtrW(hwnd, GWLP_HINSTANCE) };
+        RawWindowHandle::Windows(WindowsHandle {
+            hwnd: hwnd as *mut std::ffi::c_void,
+            hinstance: hinstance as *mut std::ffi::c_void,
+            ..WindowsHandle::empty()
+        })
+    }
+
     /// Get a handle that can be used to schedule an idle task.
     pub fn get_idle_handle(&self) -> Option<IdleHandle> {
         self.state.upgrade().map(|w| IdleHandle {
@@ -1264,13 +1721,25 @@ impl IdleHandle {
     }
 
     pub fn add_idle_token(&self, token: IdleToken) {
+        self.push(IdleKind::Token(token));
+    }
+
+    /// Show a file dialog from the idle handler, once the current message
+    /// has been handled.
+    fn add_file_dialog(&self, ty: FileDialogType, options: FileDialogOptions) -> FileDialogToken {
+        let token = FileDialogToken::next();
+        self.push(IdleKind::FileDialog(ty, options, token));
+        token
+    }
+
+    fn push(&self, kind: IdleKind) {
         let mut queue = self.queue.lock().unwrap();
         if queue.is_empty() {
             unsafe {
                 PostMessageW(self.hwnd, XI_RUN_IDLE, 0, 0);
             }
         }
-        queue.push(IdleKind::Token(token));
+        queue.push(kind);
     }
 
     fn invalidate(&self) {
@@ -1300,6 +1769,30 @@ impl<'a> WinCtx<'a> for WinCtxImpl<'a> {
         }
     }
 
+    fn set_ime_cursor_area(&mut self, rect: Rect) {
+        if let Some(hwnd) = self.handle.get_hwnd() {
+            let (x0, y0) = self.handle.px_to_pixels_xy(rect.x0 as f32, rect.y0 as f32);
+            let (x1, y1) = self.handle.px_to_pixels_xy(rect.x1 as f32, rect.y1 as f32);
+            let form = COMPOSITIONFORM {
+                dwStyle: CFS_EXCLUDE,
+                ptCurrentPos: POINT { x: x0, y: y0 },
+                rcArea: RECT {
+                    left: x0,
+                    top: y0,
+                    right: x1,
+                    bottom: y1,
+                },
+            };
+            unsafe {
+                let himc = ImmGetContext(hwnd);
+                if !himc.is_null() {
+                    ImmSetCompositionWindow(himc, &form as *const _ as *mut _);
+                    ImmReleaseContext(hwnd, himc);
+                }
+            }
+        }
+    }
+
     /// Request a timer event.
     ///
     /// The return value is an identifier.
@@ -1334,6 +1827,16 @@ impl<'a> WinCtx<'a> for WinCtxImpl<'a> {
         }
     }
 
+    fn open_file(&mut self, options: FileDialogOptions) -> Option<FileDialogToken> {
+        let idle = self.handle.get_idle_handle()?;
+        Some(idle.add_file_dialog(FileDialogType::Open, options))
+    }
+
+    fn save_as(&mut self, options: FileDialogOptions) -> Option<FileDialogToken> {
+        let idle = self.handle.get_idle_handle()?;
+        Some(idle.add_file_dialog(FileDialogType::Save, options))
+    }
+
     /// Prompt the user to chose a file to open.
     ///
     /// Blocks while the user picks the file.
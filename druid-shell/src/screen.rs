@@ -0,0 +1,223 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Information about the monitors connected to the system.
+
+use crate::kurbo::{Point, Rect};
+use crate::platform::screen as platform;
+
+/// A monitor, as reported by the platform.
+///
+/// Rects are in px, in the screen coordinates of [`WindowHandle::set_position`].
+/// The primary monitor's top-left corner is usually the origin; monitors to
+/// the left of, or above, it have negative coordinates.
+///
+/// On Windows, screen coordinates are converted from physical pixels at the
+/// system's scale, so a monitor with a scale of its own is not as many px
+/// across in its `rect` as a window that fills it is.
+///
+/// These are also the coordinates of the position passed to
+/// [`WinHandler::position`], which is the top-left corner of the window's
+/// frame, as for `set_position`.
+///
+/// [`WinHandler::position`]: trait.WinHandler.html#method.position
+/// [`WindowHandle::set_position`]: struct.WindowHandle.html#method.set_position
+#[derive(Debug, Clone)]
+pub struct Monitor {
+    primary: bool,
+    rect: Rect,
+    work_rect: Rect,
+    scale: f64,
+}
+
+impl Monitor {
+    /// Create a `Monitor`; this is what the platform code does, and is
+    /// useful in tests.
+    pub fn new(primary: bool, rect: Rect, work_rect: Rect, scale: f64) -> Monitor {
+        Monitor {
+            primary,
+            rect,
+            work_rect,
+            scale,
+        }
+    }
+
+    /// Returns `true` if this is the primary monitor.
+    pub fn is_primary(&self) -> bool {
+        self.primary
+    }
+
+    /// The whole area of the monitor.
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    /// The part of the monitor that windows should be in, without the
+    /// taskbar, dock, or menu bar.
+    pub fn work_rect(&self) -> Rect {
+        self.work_rect
+    }
+
+    /// The scale factor of the monitor, where `1.0` is 96 dpi.
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Returns `true` if `point`, in screen coordinates, is on the monitor.
+    pub fn contains(&self, point: Point) -> bool {
+        self.rect.x0 <= point.x
+            && point.x < self.rect.x1
+            && self.rect.y0 <= point.y
+            && point.y < self.rect.y1
+    }
+}
+
+// kurbo's `Rect` is not `PartialEq`.
+impl PartialEq for Monitor {
+    fn eq(&self, other: &Monitor) -> bool {
+        let coords = |r: Rect| (r.x0, r.y0, r.x1, r.y1);
+        self.primary == other.primary
+            && coords(self.rect) == coords(other.rect)
+            && coords(self.work_rect) == coords(other.work_rect)
+            && self.scale == other.scale
+    }
+}
+
+/// How a platform's screen coordinates map to the px of [`Monitor`] rects
+/// and window positions.
+///
+/// The platform code converts every rect and position with the same
+/// `ScreenCoords`, so that a position read from a window can be passed back
+/// to `set_position`, and compared with the monitors.
+///
+/// [`Monitor`]: struct.Monitor.html
+#[derive(Debug, Clone, Copy)]
+// GTK's coordinates are already px.
+#[allow(dead_code)]
+pub(crate) struct ScreenCoords {
+    /// The number of platform units in a px.
+    pub scale: f64,
+    /// The height of the primary monitor, in platform units, if the
+    /// platform's origin is its bottom-left corner, with y pointing up.
+    pub flip_height: Option<f64>,
+}
+
+#[allow(dead_code)]
+impl ScreenCoords {
+    /// Convert a rect from platform units to px.
+    pub fn to_px(self, rect: Rect) -> Rect {
+        let rect = self.flip(rect);
+        Rect::new(
+            rect.x0 / self.scale,
+            rect.y0 / self.scale,
+            rect.x1 / self.scale,
+            rect.y1 / self.scale,
+        )
+    }
+
+    /// Convert a rect from px to platform units.
+    pub fn to_platform(self, rect: Rect) -> Rect {
+        let rect = Rect::new(
+            rect.x0 * self.scale,
+            rect.y0 * self.scale,
+            rect.x1 * self.scale,
+            rect.y1 * self.scale,
+        );
+        self.flip(rect)
+    }
+
+    /// Flip `rect` vertically around the middle of the primary monitor, if
+    /// the platform's y axis points up; this is its own inverse.
+    fn flip(self, rect: Rect) -> Rect {
+        match self.flip_height {
+            Some(height) => Rect::new(rect.x0, height - rect.y1, rect.x1, height - rect.y0),
+            None => rect,
+        }
+    }
+}
+
+/// The monitors of the system.
+pub struct Screen;
+
+impl Screen {
+    /// The connected monitors, the primary one first.
+    ///
+    /// This is empty if the platform cannot tell.
+    pub fn get_monitors() -> Vec<Monitor> {
+        let mut monitors = platform::get_monitors();
+        // the sort is stable, so the others keep the platform's order.
+        monitors.sort_by_key(|monitor| !monitor.is_primary());
+        monitors
+    }
+
+    /// The primary monitor, or any monitor if none is marked as primary.
+    pub fn get_primary() -> Option<Monitor> {
+        Screen::get_monitors().into_iter().next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kurbo::Size;
+
+    fn coords(r: Rect) -> (f64, f64, f64, f64) {
+        (r.x0, r.y0, r.x1, r.y1)
+    }
+
+    #[test]
+    fn flipped_coords_are_from_the_primary_top_left() {
+        // a 1440x900 primary monitor, with a 1920x1080 one above and to the
+        // left of it, in bottom-left coordinates.
+        let screen = ScreenCoords {
+            scale: 1.0,
+            flip_height: Some(900.0),
+        };
+        let primary = Rect::new(0.0, 0.0, 1440.0, 900.0);
+        let other = Rect::new(-1920.0, 900.0, 0.0, 1980.0);
+        assert_eq!(coords(screen.to_px(primary)), (0.0, 0.0, 1440.0, 900.0));
+        assert_eq!(coords(screen.to_px(other)), (-1920.0, -1080.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn window_position_round_trips() {
+        let platforms = [
+            ScreenCoords {
+                scale: 1.0,
+                flip_height: None,
+            },
+            // windows, at 150%.
+            ScreenCoords {
+                scale: 1.5,
+                flip_height: None,
+            },
+            // mac, with a primary monitor 900 points tall.
+            ScreenCoords {
+                scale: 1.0,
+                flip_height: Some(900.0),
+            },
+        ];
+        let size = Size::new(400.0, 300.0);
+        for screen in platforms.iter() {
+            for &position in [Point::new(100.0, 50.0), Point::new(-1800.0, -1000.0)].iter() {
+                // `set_position` converts the frame to platform units, and
+                // the move notification converts it back.
+                let frame = screen.to_platform(Rect::from_origin_size(position, size));
+                let reported = screen.to_px(frame);
+                assert_eq!(reported.origin(), position, "{:?}", screen);
+                assert_eq!(reported.size(), size, "{:?}", screen);
+            }
+        }
+    }
+}
@@ -16,13 +16,16 @@
 
 use std::any::Any;
 
+#[cfg(feature = "raw-win-handle")]
+use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
+
 use crate::common_util::Counter;
-use crate::dialog::{FileDialogOptions, FileInfo};
+use crate::dialog::{FileDialogOptions, FileDialogToken, FileInfo};
 use crate::error::Error;
 use crate::keyboard::{KeyEvent, KeyModifiers};
-use crate::kurbo::{Point, Size, Vec2};
+use crate::kurbo::{Point, Rect, Size, Vec2};
 use crate::menu::Menu;
-use crate::mouse::{Cursor, MouseEvent};
+use crate::mouse::{Cursor, MouseEvent, WheelPhase, WheelUnit};
 use crate::platform::window as platform;
 
 // It's possible we'll want to make this type alias at a lower level,
@@ -55,6 +58,20 @@ impl TimerToken {
     }
 }
 
+/// A change in the power state of the system.
+///
+/// These are system-wide notifications: the platform delivers them to the
+/// handler of every open window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerEvent {
+    /// The system is about to sleep.
+    WillSleep,
+    /// The system has woken up from sleep.
+    DidWake,
+    /// The system is running on a battery that is low or critical.
+    BatteryLow,
+}
+
 //NOTE: this has a From<platform::Handle> impl for construction
 /// A handle that can enqueue tasks on the window loop.
 #[derive(Clone)]
@@ -110,16 +127,60 @@ impl WindowHandle {
         self.0.close()
     }
 
+    /// Hide the window, or show it again after it was hidden.
+    ///
+    /// A hidden window is not destroyed; its handler stays alive, and it can
+    /// be shown again later.
+    pub fn set_visible(&self, visible: bool) {
+        self.0.set_visible(visible)
+    }
+
     /// Bring this window to the front of the window stack and give it focus.
     pub fn bring_to_front_and_focus(&self) {
         self.0.bring_to_front_and_focus()
     }
 
+    /// Set the size of the window's frame, in px.
+    pub fn set_size(&self, size: Size) {
+        self.0.set_size(size)
+    }
+
+    /// Move the window so that its top-left corner is at `position`, in
+    /// screen coordinates.
+    pub fn set_position(&self, position: Point) {
+        self.0.set_position(position)
+    }
+
+    /// Minimize the window.
+    pub fn minimize(&self) {
+        self.0.minimize()
+    }
+
+    /// Maximize the window.
+    pub fn maximize(&self) {
+        self.0.maximize()
+    }
+
+    /// Restore the window from being minimized or maximized.
+    pub fn restore(&self) {
+        self.0.restore()
+    }
+
     /// Request invalidation of the entire window contents.
     pub fn invalidate(&self) {
         self.0.invalidate()
     }
 
+    /// Request invalidation of part of the window, in display points.
+    ///
+    /// The rect is passed to the next [`paint`]. Platforms that can only
+    /// repaint the whole window invalidate all of it.
+    ///
+    /// [`paint`]: trait.WinHandler.html#tymethod.paint
+    pub fn invalidate_rect(&self, rect: Rect) {
+        self.0.invalidate_rect(rect)
+    }
+
     /// Set the title for this menu.
     pub fn set_title(&self, title: &str) {
         self.0.set_title(title)
@@ -142,6 +203,23 @@ impl WindowHandle {
         self.0.get_idle_handle().map(IdleHandle)
     }
 
+    /// Call `f` with a [`WinCtx`] for this window.
+    ///
+    /// Handler methods are given a context for their own window; this is
+    /// for when a handler needs to act on a different window, for instance
+    /// to deliver an event to it.
+    ///
+    /// Returns `None`, without calling `f`, if a context can't be made for
+    /// the window.
+    ///
+    /// [`WinCtx`]: trait.WinCtx.html
+    pub fn with_context<F, R>(&self, f: F) -> Option<R>
+    where
+        F: FnOnce(&mut dyn WinCtx) -> R,
+    {
+        self.0.with_context(f)
+    }
+
     /// Get the dpi of the window.
     ///
     /// TODO: we want to migrate this from dpi (with 96 as nominal) to a scale
@@ -151,6 +229,17 @@ impl WindowHandle {
     }
 }
 
+/// The handle of the platform window, for embedding native views or
+/// rendering to the window with another library.
+///
+/// The handle is empty if the window has been closed.
+#[cfg(feature = "raw-win-handle")]
+unsafe impl HasRawWindowHandle for WindowHandle {
+    fn raw_window_handle(&self) -> RawWindowHandle {
+        self.0.raw_window_handle()
+    }
+}
+
 /// A builder type for creating new windows.
 pub struct WindowBuilder(platform::WindowBuilder);
 
@@ -183,6 +272,25 @@ impl WindowBuilder {
         self.0.set_menu(menu.into_inner())
     }
 
+    /// Set whether the user can resize the window. The default is `true`.
+    pub fn set_resizable(&mut self, resizable: bool) {
+        self.0.set_resizable(resizable)
+    }
+
+    /// Set whether the window stays above other windows. The default is `false`.
+    pub fn set_always_on_top(&mut self, always_on_top: bool) {
+        self.0.set_always_on_top(always_on_top)
+    }
+
+    /// Set whether the window's background is transparent, so that only what
+    /// the handler paints is visible. The default is `false`.
+    ///
+    /// Platforms that cannot make a window transparent log a warning, and
+    /// build an opaque window.
+    pub fn set_transparent(&mut self, transparent: bool) {
+        self.0.set_transparent(transparent)
+    }
+
     /// Attempt to construct the platform window.
     ///
     /// If this fails, your application should exit.
@@ -204,6 +312,16 @@ pub trait WinCtx<'a> {
     /// Set the cursor icon.
     fn set_cursor(&mut self, cursor: &Cursor);
 
+    /// Tell the input method where the text being composed is.
+    ///
+    /// `rect` is the caret, or the text under composition, in window
+    /// coordinates; the input method uses it to place its candidate window.
+    /// The default implementation, for contexts without input methods, does
+    /// nothing.
+    fn set_ime_cursor_area(&mut self, rect: Rect) {
+        let _ = rect;
+    }
+
     /// Schedule a timer.
     ///
     /// This causes a [`WinHandler::timer()`] call at the deadline. The
@@ -227,6 +345,40 @@ pub trait WinCtx<'a> {
     ///
     /// Blocks while the user picks a file.
     fn save_as_sync(&mut self, options: FileDialogOptions) -> Option<FileInfo>;
+
+    /// Prompt the user to choose a file to open, once the caller has returned.
+    ///
+    /// This returns immediately; when the dialog is dismissed, the result is
+    /// delivered to [`WinHandler::open_file()`], along with the returned token.
+    ///
+    /// The event loop keeps running while the dialog is open, so the window
+    /// still paints, and timers and commands are still handled. On macOS the
+    /// dialog is a sheet on the window; on Windows it disables the window's
+    /// input until it closes.
+    ///
+    /// Returns `None` if this context can't defer the dialog, which is what
+    /// the default implementation does; the caller should then use
+    /// [`open_file_sync`] instead.
+    ///
+    /// [`WinHandler::open_file()`]: trait.WinHandler.html#method.open_file
+    /// [`open_file_sync`]: #tymethod.open_file_sync
+    fn open_file(&mut self, options: FileDialogOptions) -> Option<FileDialogToken> {
+        let _ = options;
+        None
+    }
+
+    /// Prompt the user to choose a path for saving, once the caller has
+    /// returned.
+    ///
+    /// The result is delivered to [`WinHandler::save_as()`]; see [`open_file`]
+    /// for the details, which are the same.
+    ///
+    /// [`WinHandler::save_as()`]: trait.WinHandler.html#method.save_as
+    /// [`open_file`]: #method.open_file
+    fn save_as(&mut self, options: FileDialogOptions) -> Option<FileDialogToken> {
+        let _ = options;
+        None
+    }
 }
 
 /// App behavior, supplied by the app.
@@ -254,10 +406,39 @@ pub trait WinHandler {
     #[allow(unused_variables)]
     fn size(&mut self, width: u32, height: u32, ctx: &mut dyn WinCtx) {}
 
+    /// Called when the window is moved. `pos` is the position of the top-left
+    /// corner of the window's frame, in px, in the screen coordinates of
+    /// [`WindowHandle::set_position`] and the [`Monitor`] rects.
+    ///
+    /// [`WindowHandle::set_position`]: struct.WindowHandle.html#method.set_position
+    /// [`Monitor`]: struct.Monitor.html
+    ///
+    /// Some platforms also call this when the window is resized, without the
+    /// position having changed.
+    #[allow(unused_variables)]
+    fn position(&mut self, pos: Point, ctx: &mut dyn WinCtx) {}
+
+    /// Called when the scale factor of the window changes, for instance
+    /// because it was moved to a monitor with a different DPI. A scale of
+    /// `1.0` corresponds to 96 dpi.
+    ///
+    /// On platforms that always work in logical units, such as macOS, this
+    /// is never called.
+    #[allow(unused_variables)]
+    fn scale(&mut self, scale: f64, ctx: &mut dyn WinCtx) {}
+
     /// Request the handler to paint the window contents. Return value
     /// indicates whether window is animating, i.e. whether another paint
     /// should be scheduled for the next animation frame.
-    fn paint(&mut self, piet: &mut piet_common::Piet, ctx: &mut dyn WinCtx) -> bool;
+    ///
+    /// `invalid_rect` is the part of the window that needs to be painted, in
+    /// display points; drawing outside of it may be clipped.
+    fn paint(
+        &mut self,
+        piet: &mut piet_common::Piet,
+        invalid_rect: Rect,
+        ctx: &mut dyn WinCtx,
+    ) -> bool;
 
     /// Called when the resources need to be rebuilt.
     ///
@@ -284,6 +465,21 @@ pub trait WinHandler {
     #[allow(unused_variables)]
     fn key_up(&mut self, event: KeyEvent, ctx: &mut dyn WinCtx) {}
 
+    /// Called when the text being composed by an input method changes.
+    ///
+    /// `text` is the whole composition so far, and `cursor` is the byte
+    /// offset of the caret within it. An empty `text` means the composition
+    /// was cancelled. The keys that built the composition are not delivered
+    /// to [`key_down`].
+    ///
+    /// [`key_down`]: #method.key_down
+    #[allow(unused_variables)]
+    fn ime_composition(&mut self, text: &str, cursor: usize, ctx: &mut dyn WinCtx) {}
+
+    /// Called when an input method commits text, ending the composition.
+    #[allow(unused_variables)]
+    fn ime_commit(&mut self, text: &str, ctx: &mut dyn WinCtx) {}
+
     /// Called on a mouse wheel event.
     ///
     /// The polarity is the amount to be added to the scroll position,
@@ -291,19 +487,74 @@ pub trait WinHandler {
     /// move on scrolling. This polarity is consistent with the
     /// deltaX and deltaY values in a web [WheelEvent].
     ///
+    /// The delta is in `unit`s; `phase` places the event in a trackpad
+    /// gesture, if the platform reports one.
+    ///
     /// [WheelEvent]: https://w3c.github.io/uievents/#event-type-wheel
     #[allow(unused_variables)]
-    fn wheel(&mut self, delta: Vec2, mods: KeyModifiers, ctx: &mut dyn WinCtx) {}
+    fn wheel(
+        &mut self,
+        delta: Vec2,
+        unit: WheelUnit,
+        phase: WheelPhase,
+        mods: KeyModifiers,
+        ctx: &mut dyn WinCtx,
+    ) {
+    }
 
     /// Called when a platform-defined zoom gesture occurs (such as pinching
     /// on the trackpad).
     #[allow(unused_variables)]
     fn zoom(&mut self, delta: f64, ctx: &mut dyn WinCtx) {}
 
+    /// Called when files dragged from another application enter the window.
+    ///
+    /// `pos` is in the coordinate space of the window. Return `true` if the
+    /// files would be accepted if they were dropped here; the platform uses
+    /// this to choose the cursor.
+    #[allow(unused_variables)]
+    fn drag_enter(&mut self, files: &[FileInfo], pos: Point, ctx: &mut dyn WinCtx) -> bool {
+        false
+    }
+
+    /// Called when the files being dragged move within the window.
+    ///
+    /// The return value has the same meaning as for [`drag_enter`].
+    ///
+    /// [`drag_enter`]: #method.drag_enter
+    #[allow(unused_variables)]
+    fn drag_over(&mut self, pos: Point, ctx: &mut dyn WinCtx) -> bool {
+        false
+    }
+
+    /// Called when the files being dragged leave the window without being
+    /// dropped.
+    #[allow(unused_variables)]
+    fn drag_leave(&mut self, ctx: &mut dyn WinCtx) {}
+
+    /// Called when files are dropped on the window. Return `true` if they
+    /// were accepted.
+    ///
+    /// Some platforms do not report the hover phase, and call this without
+    /// a preceding [`drag_enter`].
+    ///
+    /// [`drag_enter`]: #method.drag_enter
+    #[allow(unused_variables)]
+    fn drop_files(&mut self, files: &[FileInfo], pos: Point, ctx: &mut dyn WinCtx) -> bool {
+        false
+    }
+
     /// Called when the mouse moves.
     #[allow(unused_variables)]
     fn mouse_move(&mut self, event: &MouseEvent, ctx: &mut dyn WinCtx) {}
 
+    /// Called when the mouse leaves the window.
+    ///
+    /// If a button is held down when the mouse leaves, the window keeps
+    /// receiving `mouse_move` and `mouse_up` until it is released.
+    #[allow(unused_variables)]
+    fn mouse_leave(&mut self, ctx: &mut dyn WinCtx) {}
+
     /// Called on mouse button down.
     #[allow(unused_variables)]
     fn mouse_down(&mut self, event: &MouseEvent, ctx: &mut dyn WinCtx) {}
@@ -322,10 +573,56 @@ pub trait WinHandler {
     #[allow(unused_variables)]
     fn timer(&mut self, token: TimerToken, ctx: &mut dyn WinCtx) {}
 
+    /// Called when a dialog opened by [`WinCtx::open_file()`] is dismissed.
+    ///
+    /// `file` is `None` if the dialog was cancelled.
+    ///
+    /// [`WinCtx::open_file()`]: trait.WinCtx.html#tymethod.open_file
+    #[allow(unused_variables)]
+    fn open_file(&mut self, token: FileDialogToken, file: Option<FileInfo>, ctx: &mut dyn WinCtx) {}
+
+    /// Called when a dialog opened by [`WinCtx::save_as()`] is dismissed.
+    ///
+    /// `file` is `None` if the dialog was cancelled.
+    ///
+    /// [`WinCtx::save_as()`]: trait.WinCtx.html#tymethod.save_as
+    #[allow(unused_variables)]
+    fn save_as(&mut self, token: FileDialogToken, file: Option<FileInfo>, ctx: &mut dyn WinCtx) {}
+
     /// Called when this window becomes the focused window.
     #[allow(unused_variables)]
     fn got_focus(&mut self, ctx: &mut dyn WinCtx) {}
 
+    /// Called when this window stops being the focused window.
+    ///
+    /// When focus moves between two windows of the application, the platforms
+    /// usually call this on the window losing focus before calling
+    /// [`got_focus`] on the window gaining it, but this is not guaranteed.
+    ///
+    /// [`got_focus`]: #method.got_focus
+    #[allow(unused_variables)]
+    fn lost_focus(&mut self, ctx: &mut dyn WinCtx) {}
+
+    /// Called when the power state of the system changes.
+    ///
+    /// This corresponds to `WM_POWERBROADCAST` on Windows and to the
+    /// `NSWorkspace` sleep and wake notifications on macOS. It is not
+    /// currently delivered on GTK.
+    #[allow(unused_variables)]
+    fn power_event(&mut self, event: PowerEvent, ctx: &mut dyn WinCtx) {}
+
+    /// Called when the user asks to close the window, for instance with the
+    /// close button in the title bar.
+    ///
+    /// Return `false` to keep the window open. This is not called when the
+    /// window is closed programmatically with [`WindowHandle::close()`].
+    ///
+    /// [`WindowHandle::close()`]: struct.WindowHandle.html#method.close
+    #[allow(unused_variables)]
+    fn request_close(&mut self, ctx: &mut dyn WinCtx) -> bool {
+        true
+    }
+
     /// Called when the window is being destroyed. Note that this happens
     /// earlier in the sequence than drop (at WM_DESTROY, while the latter is
     /// WM_NCDESTROY).
@@ -141,10 +141,10 @@ fn flex_row<T: Data>(
     w4: impl Widget<T> + 'static,
 ) -> impl Widget<T> {
     Flex::row()
-        .with_child(w1, 1.0)
-        .with_child(w2, 1.0)
-        .with_child(w3, 1.0)
-        .with_child(w4, 1.0)
+        .with_flex_child(w1, 1.0)
+        .with_flex_child(w2, 1.0)
+        .with_flex_child(w3, 1.0)
+        .with_flex_child(w4, 1.0)
 }
 
 fn build_calc() -> impl Widget<CalcState> {
@@ -153,8 +153,8 @@ fn build_calc() -> impl Widget<CalcState> {
         CalcState::value,
     );
     Flex::column()
-        .with_child(pad(display), 0.0)
-        .with_child(
+        .with_child(pad(display))
+        .with_flex_child(
             flex_row(
                 op_button_label('c', "CE".to_string()),
                 op_button('C'),
@@ -163,7 +163,7 @@ fn build_calc() -> impl Widget<CalcState> {
             ),
             1.0,
         )
-        .with_child(
+        .with_flex_child(
             flex_row(
                 digit_button(7),
                 digit_button(8),
@@ -172,7 +172,7 @@ fn build_calc() -> impl Widget<CalcState> {
             ),
             1.0,
         )
-        .with_child(
+        .with_flex_child(
             flex_row(
                 digit_button(4),
                 digit_button(5),
@@ -181,7 +181,7 @@ fn build_calc() -> impl Widget<CalcState> {
             ),
             1.0,
         )
-        .with_child(
+        .with_flex_child(
             flex_row(
                 digit_button(1),
                 digit_button(2),
@@ -190,7 +190,7 @@ fn build_calc() -> impl Widget<CalcState> {
             ),
             1.0,
         )
-        .with_child(
+        .with_flex_child(
             flex_row(
                 op_button('±'),
                 digit_button(0),
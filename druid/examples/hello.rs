@@ -33,6 +33,6 @@ fn ui_builder() -> impl Widget<u32> {
     let button = Button::new("increment", |_ctx, data, _env| *data += 1);
 
     Flex::column()
-        .with_child(Align::centered(Padding::new(5.0, label)), 1.0)
-        .with_child(Padding::new(5.0, button), 1.0)
+        .with_flex_child(Align::centered(Padding::new(5.0, label)), 1.0)
+        .with_flex_child(Padding::new(5.0, button), 1.0)
 }
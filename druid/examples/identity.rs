@@ -39,7 +39,7 @@ use druid::{
 
 const CYCLE_DURATION: Duration = Duration::from_millis(100);
 
-const FREEZE_COLOR: Selector = Selector::new("identity-example.freeze-color");
+const FREEZE_COLOR: Selector<Color> = Selector::new("identity-example.freeze-color");
 const UNFREEZE_COLOR: Selector = Selector::new("identity-example.unfreeze-color");
 
 /// Honestly: it's just a color in fancy clothing.
@@ -105,15 +105,10 @@ impl Widget<OurData> for ColorWell {
                 self.token = ctx.request_timer(Instant::now() + CYCLE_DURATION);
             }
 
-            Event::Command(cmd) if cmd.selector == FREEZE_COLOR => {
-                self.frozen = cmd
-                    .get_object::<Color>()
-                    .ok()
-                    .cloned()
-                    .expect("payload is always a Color")
-                    .into();
+            Event::Command(cmd) if cmd.is(FREEZE_COLOR) => {
+                self.frozen = cmd.get(FREEZE_COLOR).cloned();
             }
-            Event::Command(cmd) if cmd.selector == UNFREEZE_COLOR => self.frozen = None,
+            Event::Command(cmd) if cmd.is(UNFREEZE_COLOR) => self.frozen = None,
             _ => (),
         }
     }
@@ -162,18 +157,18 @@ fn make_ui() -> impl Widget<OurData> {
     let id_three = WidgetId::next();
 
     Flex::column()
-        .with_child(ColorWell::new(true).padding(10.0), 1.0)
-        .with_child(
+        .with_flex_child(ColorWell::new(true).padding(10.0), 1.0)
+        .with_flex_child(
             Flex::row()
-                .with_child(ColorWell::new(false).padding(10.).with_id(ID_ONE), 1.0)
-                .with_child(
+                .with_flex_child(ColorWell::new(false).padding(10.).with_id(ID_ONE), 1.0)
+                .with_flex_child(
                     Button::<OurData>::new("freeze", move |ctx, data, _env| {
                         ctx.submit_command(Command::new(FREEZE_COLOR, data.color.clone()), ID_ONE)
                     })
                     .padding(10.0),
                     0.5,
                 )
-                .with_child(
+                .with_flex_child(
                     Button::<OurData>::new("unfreeze", move |ctx, _, _env| {
                         ctx.submit_command(UNFREEZE_COLOR, ID_ONE)
                     })
@@ -182,17 +177,17 @@ fn make_ui() -> impl Widget<OurData> {
                 ),
             0.5,
         )
-        .with_child(
+        .with_flex_child(
             Flex::row()
-                .with_child(ColorWell::new(false).padding(10.).with_id(id_two), 1.)
-                .with_child(
+                .with_flex_child(ColorWell::new(false).padding(10.).with_id(id_two), 1.)
+                .with_flex_child(
                     Button::<OurData>::new("freeze", move |ctx, data, _env| {
                         ctx.submit_command(Command::new(FREEZE_COLOR, data.color.clone()), id_two)
                     })
                     .padding(10.0),
                     0.5,
                 )
-                .with_child(
+                .with_flex_child(
                     Button::<OurData>::new("unfreeze", move |ctx, _, _env| {
                         ctx.submit_command(UNFREEZE_COLOR, id_two)
                     })
@@ -201,17 +196,17 @@ fn make_ui() -> impl Widget<OurData> {
                 ),
             0.5,
         )
-        .with_child(
+        .with_flex_child(
             Flex::row()
-                .with_child(ColorWell::new(false).padding(10.0).with_id(id_three), 1.)
-                .with_child(
+                .with_flex_child(ColorWell::new(false).padding(10.0).with_id(id_three), 1.)
+                .with_flex_child(
                     Button::<OurData>::new("freeze", move |ctx, data, _env| {
                         ctx.submit_command(Command::new(FREEZE_COLOR, data.color.clone()), id_three)
                     })
                     .padding(10.0),
                     0.5,
                 )
-                .with_child(
+                .with_flex_child(
                     Button::<OurData>::new("unfreeze", move |ctx, _, _env| {
                         ctx.submit_command(UNFREEZE_COLOR, id_three)
                     })
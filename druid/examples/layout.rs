@@ -14,7 +14,7 @@
 
 //! This example shows how to construct a basic layout.
 
-use druid::widget::{Button, Flex, Label, SizedBox, WidgetExt};
+use druid::widget::{Button, Flex, Label, WidgetExt};
 use druid::{AppLauncher, Color, LocalizedString, Widget, WindowDesc};
 
 fn build_app() -> impl Widget<u32> {
@@ -29,24 +29,22 @@ fn build_app() -> impl Widget<u32> {
             .fix_width(60.0)
             .background(Color::rgb8(0x77, 0x77, 0))
             .border(Color::WHITE, 3.0),
-        0.0,
     );
     // Spacing element that will fill all available space in between label
-    // and a button. Notice that weight is non-zero.
-    header.add_child(SizedBox::empty().expand(), 1.0);
-    header.add_child(Button::new("Two", Button::noop).padding(20.), 0.0);
+    // and a button.
+    header.add_flex_spacer(1.0);
+    header.add_child(Button::new("Two", Button::noop).padding(20.));
     col.add_child(
         header
             .fix_height(100.0)
             .background(Color::rgb8(0, 0x77, 0x88)),
-        0.0,
     );
 
     for i in 0..5 {
         // Give a larger weight to one of the buttons for it to
         // occupy more space.
         let weight = if i == 2 { 3.0 } else { 1.0 };
-        col.add_child(Button::new(format!("Button #{}", i), Button::noop), weight);
+        col.add_flex_child(Button::new(format!("Button #{}", i), Button::noop), weight);
     }
 
     col
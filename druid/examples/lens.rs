@@ -45,6 +45,6 @@ fn ui_builder() -> impl Widget<MyComplexState> {
     let slider = Slider::new().lens(MyComplexState::scale);
 
     Flex::column()
-        .with_child(searchbar.padding(32.0), 1.0)
-        .with_child(slider.padding(32.0), 1.0)
+        .with_flex_child(searchbar.padding(32.0), 1.0)
+        .with_flex_child(slider.padding(32.0), 1.0)
 }
@@ -56,13 +56,12 @@ fn ui_builder() -> impl Widget<AppData> {
             Arc::make_mut(&mut data.right).push(value as u32);
         })
         .fix_height(30.0),
-        0.0,
     );
 
     let mut lists = Flex::row();
 
     // Build a simple list
-    lists.add_child(
+    lists.add_flex_child(
         Scroll::new(List::new(|| {
             Label::new(|item: &u32, _env: &_| format!("List item #{}", item))
                 .padding(10.0)
@@ -76,10 +75,10 @@ fn ui_builder() -> impl Widget<AppData> {
     );
 
     // Build a list with shared data
-    lists.add_child(
+    lists.add_flex_child(
         Scroll::new(List::new(|| {
             Flex::row()
-                .with_child(
+                .with_flex_child(
                     Label::new(|(_, item): &(Arc<Vec<u32>>, u32), _env: &_| {
                         format!("List item #{}", item)
                     }),
@@ -96,7 +95,6 @@ fn ui_builder() -> impl Widget<AppData> {
                     )
                     .fix_size(80.0, 20.0)
                     .align_vertical(UnitPoint::CENTER),
-                    0.0,
                 )
                 .padding(10.0)
                 .background(Color::rgb(0.5, 0.0, 0.5))
@@ -114,7 +112,7 @@ fn ui_builder() -> impl Widget<AppData> {
         1.0,
     );
 
-    root.add_child(lists, 1.0);
+    root.add_flex_child(lists, 1.0);
 
     root
 }
@@ -16,13 +16,13 @@
 
 use druid::widget::{Align, Button, Flex, Label, Padding};
 use druid::{
-    AppDelegate, AppLauncher, Command, ContextMenu, Data, DelegateCtx, Env, Event, EventCtx,
-    LocalizedString, MenuDesc, MenuItem, Selector, Widget, WindowDesc, WindowId,
+    AppDelegate, AppLauncher, Command, Data, DelegateCtx, Env, Event, LocalizedString, MenuDesc,
+    MenuItem, Selector, Widget, WindowDesc, WindowId,
 };
 
 use log::info;
 
-const MENU_COUNT_ACTION: Selector = Selector::new("menu-count-action");
+const MENU_COUNT_ACTION: Selector<usize> = Selector::new("menu-count-action");
 const MENU_INCREMENT_ACTION: Selector = Selector::new("menu-increment-action");
 const MENU_DECREMENT_ACTION: Selector = Selector::new("menu-decrement-action");
 
@@ -45,44 +45,25 @@ fn main() {
         .expect("launch failed");
 }
 
-// this is just an experiment for how we might reduce boilerplate.
-trait EventCtxExt {
-    fn set_menu<T: 'static>(&mut self, menu: MenuDesc<T>);
-}
-
-impl EventCtxExt for EventCtx<'_, '_> {
-    fn set_menu<T: 'static>(&mut self, menu: MenuDesc<T>) {
-        let cmd = Command::new(druid::commands::SET_MENU, menu);
-        self.submit_command(cmd, None);
-    }
-}
-
-impl EventCtxExt for DelegateCtx<'_> {
-    fn set_menu<T: 'static>(&mut self, menu: MenuDesc<T>) {
-        let cmd = Command::new(druid::commands::SET_MENU, menu);
-        self.submit_command(cmd, None);
-    }
-}
-
 fn ui_builder() -> impl Widget<State> {
     let text = LocalizedString::new("hello-counter")
         .with_arg("count", |data: &State, _env| data.menu_count.into());
     let label = Label::new(text);
     let inc_button = Button::<State>::new("Add menu item", |ctx, data, _env| {
         data.menu_count += 1;
-        ctx.set_menu(make_menu::<State>(data));
+        ctx.set_menu(make_menu::<State>(data)).unwrap();
     });
     let dec_button = Button::<State>::new("Remove menu item", |ctx, data, _env| {
         data.menu_count = data.menu_count.saturating_sub(1);
-        ctx.set_menu(make_menu::<State>(data));
+        ctx.set_menu(make_menu::<State>(data)).unwrap();
     });
 
     let mut col = Flex::column();
-    col.add_child(Align::centered(Padding::new(5.0, label)), 1.0);
+    col.add_flex_child(Align::centered(Padding::new(5.0, label)), 1.0);
     let mut row = Flex::row();
-    row.add_child(Padding::new(5.0, inc_button), 1.0);
-    row.add_child(Padding::new(5.0, dec_button), 1.0);
-    col.add_child(row, 1.0);
+    row.add_flex_child(Padding::new(5.0, inc_button), 1.0);
+    row.add_flex_child(Padding::new(5.0, dec_button), 1.0);
+    col.add_flex_child(row, 1.0);
     col
 }
 
@@ -97,35 +78,33 @@ impl AppDelegate<State> for Delegate {
         ctx: &mut DelegateCtx,
     ) -> Option<Event> {
         match event {
-            Event::TargetedCommand(_, ref cmd) if cmd.selector == druid::commands::NEW_FILE => {
+            Event::TargetedCommand(_, ref cmd) if cmd.is(druid::commands::NEW_FILE) => {
                 let new_win = WindowDesc::new(ui_builder)
                     .menu(make_menu(data))
                     .window_size((data.selected as f64 * 100.0 + 300.0, 500.0));
-                let command = Command::new(druid::commands::NEW_WINDOW, new_win);
-                ctx.submit_command(command, None);
+                ctx.new_window(new_win).unwrap();
                 None
             }
-            Event::TargetedCommand(_, ref cmd) if cmd.selector == MENU_COUNT_ACTION => {
-                data.selected = *cmd.get_object().unwrap();
-                ctx.set_menu(make_menu::<State>(data));
+            Event::TargetedCommand(_, ref cmd) if cmd.is(MENU_COUNT_ACTION) => {
+                data.selected = *cmd.get(MENU_COUNT_ACTION).unwrap();
+                ctx.set_menu(make_menu::<State>(data)).unwrap();
                 None
             }
             // wouldn't it be nice if a menu (like a button) could just mutate state
             // directly if desired?
-            Event::TargetedCommand(_, ref cmd) if cmd.selector == MENU_INCREMENT_ACTION => {
+            Event::TargetedCommand(_, ref cmd) if cmd.is(MENU_INCREMENT_ACTION) => {
                 data.menu_count += 1;
-                ctx.set_menu(make_menu::<State>(data));
+                ctx.set_menu(make_menu::<State>(data)).unwrap();
                 None
             }
-            Event::TargetedCommand(_, ref cmd) if cmd.selector == MENU_DECREMENT_ACTION => {
+            Event::TargetedCommand(_, ref cmd) if cmd.is(MENU_DECREMENT_ACTION) => {
                 data.menu_count = data.menu_count.saturating_sub(1);
-                ctx.set_menu(make_menu::<State>(data));
+                ctx.set_menu(make_menu::<State>(data)).unwrap();
                 None
             }
             Event::MouseDown(ref mouse) if mouse.button.is_right() => {
-                let menu = ContextMenu::new(make_context_menu::<State>(), mouse.pos);
-                let cmd = Command::new(druid::commands::SHOW_CONTEXT_MENU, menu);
-                ctx.submit_command(cmd, None);
+                ctx.show_context_menu(make_context_menu::<State>(), mouse.pos)
+                    .unwrap();
                 None
             }
             other => Some(other),
@@ -29,15 +29,15 @@ fn build_app() -> impl Widget<()> {
     );
 
     Flex::column()
-        .with_child(
+        .with_flex_child(
             Flex::row()
-                .with_child(
+                .with_flex_child(
                     Label::new("top left")
                         .border(gradient.clone(), 4.0)
                         .padding(10.0),
                     1.0,
                 )
-                .with_child(
+                .with_flex_child(
                     Label::new("top right")
                         .background(solid.clone())
                         .padding(10.0),
@@ -45,16 +45,16 @@ fn build_app() -> impl Widget<()> {
                 ),
             1.0,
         )
-        .with_child(
+        .with_flex_child(
             Flex::row()
-                .with_child(
+                .with_flex_child(
                     Label::new("bottom left")
                         .background(gradient.clone())
                         .rounded(10.0)
                         .padding(10.0),
                     1.0,
                 )
-                .with_child(
+                .with_flex_child(
                     Label::new("bottom right")
                         .border(solid.clone(), 4.0)
                         .rounded(10.0)
@@ -33,7 +33,7 @@ fn ui_builder() -> impl Widget<Option<u32>> {
     let input = Parse::new(TextBox::new());
 
     let mut col = Flex::column();
-    col.add_child(Align::centered(Padding::new(5.0, label)), 1.0);
-    col.add_child(Padding::new(5.0, input), 1.0);
+    col.add_flex_child(Align::centered(Padding::new(5.0, label)), 1.0);
+    col.add_flex_child(Padding::new(5.0, input), 1.0);
     col
 }
@@ -34,8 +34,8 @@ fn build_widget() -> impl Widget<DemoState> {
     let checkbox = LensWrap::new(Checkbox::new(), DemoState::double);
     let checkbox_label = Label::new("double the value");
     let row = Flex::row()
-        .with_child(checkbox, 0.0)
-        .with_child(Padding::new(5.0, checkbox_label), 1.0);
+        .with_child(checkbox)
+        .with_flex_child(Padding::new(5.0, checkbox_label), 1.0);
 
     let bar = LensWrap::new(ProgressBar::new(), DemoState::value);
     let slider = LensWrap::new(Slider::new(), DemoState::value);
@@ -51,12 +51,12 @@ fn build_widget() -> impl Widget<DemoState> {
     });
 
     Flex::column()
-        .with_child(Padding::new(5.0, bar), 1.0)
-        .with_child(Padding::new(5.0, slider), 1.0)
-        .with_child(Padding::new(5.0, label), 1.0)
-        .with_child(Padding::new(5.0, row), 1.0)
-        .with_child(Padding::new(5.0, Align::right(button_1)), 0.0)
-        .with_child(Padding::new(5.0, button_2), 1.0)
+        .with_flex_child(Padding::new(5.0, bar), 1.0)
+        .with_flex_child(Padding::new(5.0, slider), 1.0)
+        .with_flex_child(Padding::new(5.0, label), 1.0)
+        .with_flex_child(Padding::new(5.0, row), 1.0)
+        .with_child(Padding::new(5.0, Align::right(button_1)))
+        .with_flex_child(Padding::new(5.0, button_2), 1.0)
 }
 
 fn main() {
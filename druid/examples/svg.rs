@@ -56,7 +56,7 @@ fn ui_builder() -> impl Widget<u32> {
 
     let mut col = Flex::column();
 
-    col.add_child(Svg::new(tiger_svg.clone()).fix_width(100.0).center(), 1.0);
-    col.add_child(Svg::new(tiger_svg), 1.0);
+    col.add_flex_child(Svg::new(tiger_svg.clone()).fix_width(100.0).center(), 1.0);
+    col.add_flex_child(Svg::new(tiger_svg), 1.0);
     col
 }
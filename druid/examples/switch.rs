@@ -27,8 +27,8 @@ fn build_widget() -> impl Widget<DemoState> {
     let switch = LensWrap::new(Switch::new(), DemoState::value);
     let switch_label = Label::new("Setting label");
 
-    row.add_child(Padding::new(5.0, switch_label), 0.0);
-    row.add_child(Padding::new(5.0, switch), 0.0);
+    row.add_child(Padding::new(5.0, switch_label));
+    row.add_child(Padding::new(5.0, switch));
 
     let stepper = LensWrap::new(
         Stepper::new().max(10.0).min(0.0).step(0.5).wrap(false),
@@ -40,8 +40,8 @@ fn build_widget() -> impl Widget<DemoState> {
         Parse::new(TextBox::new()),
         DemoState::stepper_value.map(|x| Some(*x), |x, y| *x = y.unwrap_or(0.0)),
     );
-    textbox_row.add_child(Padding::new(5.0, textbox), 0.0);
-    textbox_row.add_child(Padding::new(5.0, stepper.center()), 0.0);
+    textbox_row.add_child(Padding::new(5.0, textbox));
+    textbox_row.add_child(Padding::new(5.0, stepper.center()));
 
     let mut label_row = Flex::row();
 
@@ -49,11 +49,11 @@ fn build_widget() -> impl Widget<DemoState> {
         format!("Stepper value: {0:.2}", data.stepper_value)
     });
 
-    label_row.add_child(Padding::new(5.0, label), 0.0);
+    label_row.add_child(Padding::new(5.0, label));
 
-    col.add_child(Padding::new(5.0, row), 1.0);
-    col.add_child(Padding::new(5.0, textbox_row), 1.0);
-    col.add_child(Padding::new(5.0, label_row), 1.0);
+    col.add_flex_child(Padding::new(5.0, row), 1.0);
+    col.add_flex_child(Padding::new(5.0, textbox_row), 1.0);
+    col.add_flex_child(Padding::new(5.0, label_row), 1.0);
     col
 }
 
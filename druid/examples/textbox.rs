@@ -48,9 +48,9 @@ fn build_widget() -> impl Widget<String> {
     let label = Label::new(|data: &String, _env: &_| format!("value: {}", data));
 
     Flex::column()
-        .with_child(Padding::new(5.0, textbox), 1.0)
-        .with_child(Padding::new(5.0, textbox_2), 1.0)
-        .with_child(Padding::new(5.0, label), 1.0)
+        .with_flex_child(Padding::new(5.0, textbox), 1.0)
+        .with_flex_child(Padding::new(5.0, textbox_2), 1.0)
+        .with_flex_child(Padding::new(5.0, label), 1.0)
 }
 
 fn make_main_menu<T: Data>() -> MenuDesc<T> {
@@ -0,0 +1,183 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A description of the widget tree for assistive technology.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::kurbo::{Point, Rect};
+use crate::WidgetId;
+
+/// What a widget is, to assistive technology such as a screen reader.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// A widget that has not described itself, usually a container.
+    Generic,
+    /// Static text.
+    Label,
+    /// Something that performs an action when clicked.
+    Button,
+    /// A two-state toggle.
+    CheckBox,
+    /// One of a group of mutually exclusive options.
+    RadioButton,
+    /// An editable text field.
+    TextInput,
+    /// A control that selects one of a list of options, from a popup.
+    ComboBox,
+}
+
+/// One widget in the accessibility tree.
+///
+/// There is a node for each [`WidgetPod`]; a widget describes itself by
+/// handling [`LifeCycle::CollectAccessibility`].
+///
+/// [`WidgetPod`]: struct.WidgetPod.html
+/// [`LifeCycle::CollectAccessibility`]: enum.LifeCycle.html#variant.CollectAccessibility
+#[derive(Debug, Clone)]
+pub struct AccessNode {
+    /// The widget.
+    pub id: WidgetId,
+    /// What the widget is.
+    pub role: Role,
+    /// The text that identifies the widget, such as a button's label.
+    pub name: Option<String>,
+    /// The current value of the widget, such as the contents of a text field.
+    pub value: Option<String>,
+    /// Whether the widget has keyboard focus.
+    pub focused: bool,
+    /// Whether the widget is checked, for widgets that can be.
+    pub checked: Option<bool>,
+    /// Whether the widget is disabled.
+    pub disabled: bool,
+    /// The widget's layout rect, in window coordinates.
+    pub bounds: Rect,
+    /// The nodes of the widget's children, in tree order.
+    pub children: Vec<AccessNode>,
+}
+
+/// Builds the accessibility tree during a
+/// [`LifeCycle::CollectAccessibility`] pass.
+///
+/// A widget that handles that event describes itself through the setters,
+/// which apply to the node of the nearest enclosing [`WidgetPod`]. A
+/// widget that forwards the event to a child it owns directly, without a
+/// `WidgetPod`, shares its node with that child; whichever describes the node
+/// last wins.
+///
+/// ```
+/// # use druid::{LifeCycle, Role};
+/// fn describe(event: &LifeCycle, checked: bool) {
+///     if let LifeCycle::CollectAccessibility(access) = event {
+///         access.set_role(Role::CheckBox);
+///         access.set_checked(checked);
+///     }
+/// }
+/// ```
+///
+/// [`LifeCycle::CollectAccessibility`]: enum.LifeCycle.html#variant.CollectAccessibility
+/// [`WidgetPod`]: struct.WidgetPod.html
+#[derive(Debug, Clone)]
+pub struct AccessibilityCollector(Rc<RefCell<Collected>>);
+
+#[derive(Debug)]
+struct Collected {
+    focus: Option<WidgetId>,
+    /// The nodes we are currently inside of, innermost last.
+    stack: Vec<AccessNode>,
+    root: Option<AccessNode>,
+}
+
+impl AccessibilityCollector {
+    /// A collector for a window where `focus` has keyboard focus.
+    pub(crate) fn new(focus: Option<WidgetId>) -> Self {
+        AccessibilityCollector(Rc::new(RefCell::new(Collected {
+            focus,
+            stack: Vec::new(),
+            root: None,
+        })))
+    }
+
+    /// Set the role of the current node.
+    pub fn set_role(&self, role: Role) {
+        self.with_current(|node| node.role = role);
+    }
+
+    /// Set the name of the current node.
+    pub fn set_name(&self, name: impl Into<String>) {
+        let name = name.into();
+        self.with_current(|node| node.name = Some(name));
+    }
+
+    /// Set the value of the current node.
+    pub fn set_value(&self, value: impl Into<String>) {
+        let value = value.into();
+        self.with_current(|node| node.value = Some(value));
+    }
+
+    /// Set whether the current node is checked.
+    pub fn set_checked(&self, checked: bool) {
+        self.with_current(|node| node.checked = Some(checked));
+    }
+
+    /// Set whether the current node is disabled.
+    pub fn set_disabled(&self, disabled: bool) {
+        self.with_current(|node| node.disabled = disabled);
+    }
+
+    fn with_current(&self, f: impl FnOnce(&mut AccessNode)) {
+        if let Some(node) = self.0.borrow_mut().stack.last_mut() {
+            f(node);
+        }
+    }
+
+    /// Start the node of a widget; `rect` is in its parent's coordinates.
+    pub(crate) fn enter(&self, id: WidgetId, rect: Rect) {
+        let mut collected = self.0.borrow_mut();
+        let parent = collected
+            .stack
+            .last()
+            .map(|node| node.bounds.origin())
+            .unwrap_or(Point::ORIGIN);
+        let focused = collected.focus == Some(id);
+        collected.stack.push(AccessNode {
+            id,
+            role: Role::Generic,
+            name: None,
+            value: None,
+            focused,
+            checked: None,
+            disabled: false,
+            bounds: rect + parent.to_vec2(),
+            children: Vec::new(),
+        });
+    }
+
+    /// Finish the most recently entered node.
+    pub(crate) fn exit(&self) {
+        let mut collected = self.0.borrow_mut();
+        if let Some(node) = collected.stack.pop() {
+            match collected.stack.last_mut() {
+                Some(parent) => parent.children.push(node),
+                None => collected.root = Some(node),
+            }
+        }
+    }
+
+    /// The finished tree, if a root widget was visited.
+    pub(crate) fn tree(&self) -> Option<AccessNode> {
+        self.0.borrow().root.clone()
+    }
+}
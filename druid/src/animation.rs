@@ -0,0 +1,349 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Easing curves, and interpolation of values, for animations.
+//!
+//! An [`Animator`] turns the intervals of [`LifeCycle::AnimFrame`] into the
+//! progress of an animation, and that progress into a value between two
+//! others:
+//!
+//! ```
+//! use std::time::Duration;
+//! use druid::animation::{Animator, Easing};
+//! use druid::{
+//!     BoxConstraints, Color, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+//!     Point, Rect, RenderContext, Size, UpdateCtx, Widget,
+//! };
+//!
+//! /// Fades in when it is added.
+//! struct FadeIn {
+//!     fade: Animator,
+//! }
+//!
+//! impl Widget<()> for FadeIn {
+//!     fn event(&mut self, _: &mut EventCtx, _: &Event, _: &mut (), _: &Env) {}
+//!
+//!     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _: &(), _: &Env) {
+//!         match event {
+//!             LifeCycle::WidgetAdded => ctx.request_anim_frame(),
+//!             LifeCycle::AnimFrame(interval) => {
+//!                 if self.fade.advance(*interval) {
+//!                     ctx.request_anim_frame();
+//!                 }
+//!                 ctx.invalidate();
+//!             }
+//!             _ => (),
+//!         }
+//!     }
+//!
+//!     fn update(&mut self, _: &mut UpdateCtx, _: &(), _: &(), _: &Env) {}
+//!
+//!     fn layout(&mut self, _: &mut LayoutCtx, bc: &BoxConstraints, _: &(), _: &Env) -> Size {
+//!         bc.max()
+//!     }
+//!
+//!     fn paint(&mut self, ctx: &mut PaintCtx, _: &(), _: &Env) {
+//!         let color = self.fade.value(&Color::BLACK, &Color::WHITE);
+//!         let rect = Rect::from_origin_size(Point::ORIGIN, ctx.size());
+//!         ctx.fill(rect, &color);
+//!     }
+//! }
+//!
+//! let widget = FadeIn {
+//!     fade: Animator::new(Duration::from_millis(300)).with_easing(Easing::EaseOut),
+//! };
+//! ```
+//!
+//! [`Animator`]: struct.Animator.html
+//! [`LifeCycle::AnimFrame`]: ../enum.LifeCycle.html#variant.AnimFrame
+
+use std::time::Duration;
+
+use crate::kurbo::{Point, Rect, Size, Vec2};
+use crate::Color;
+
+/// How the progress of an animation speeds up and slows down.
+///
+/// The named curves are those of CSS.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    /// Constant speed.
+    Linear,
+    /// Starts slowly, and ends at full speed.
+    EaseIn,
+    /// Starts at full speed, and slows down to a stop.
+    EaseOut,
+    /// Starts slowly, and slows down again at the end.
+    EaseInOut,
+    /// A cubic Bézier curve from `(0, 0)` to `(1, 1)`, with the control points
+    /// `(x1, y1)` and `(x2, y2)`, like CSS's `cubic-bezier()`.
+    ///
+    /// `x1` and `x2` must be within `0..=1`; the `y`s can be outside that
+    /// range, for curves that overshoot.
+    CubicBezier(f64, f64, f64, f64),
+}
+
+impl Easing {
+    /// The eased progress at `t`, where `t` goes from 0 to 1.
+    ///
+    /// `t` is clamped to that range; the eased progress is 0 at the start,
+    /// and 1 at the end.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0., 1.);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => cubic_bezier(0.42, 0., 1., 1., t),
+            Easing::EaseOut => cubic_bezier(0., 0., 0.58, 1., t),
+            Easing::EaseInOut => cubic_bezier(0.42, 0., 0.58, 1., t),
+            Easing::CubicBezier(x1, y1, x2, y2) => cubic_bezier(x1, y1, x2, y2, t),
+        }
+    }
+}
+
+/// The `y` of a unit cubic Bézier curve at `x`.
+fn cubic_bezier(x1: f64, y1: f64, x2: f64, y2: f64, x: f64) -> f64 {
+    // the coordinates of the curve, as polynomials of its parameter.
+    let coords = |p1: f64, p2: f64, s: f64| {
+        let c = 3. * p1;
+        let b = 3. * (p2 - p1) - c;
+        let a = 1. - c - b;
+        ((a * s + b) * s + c) * s
+    };
+    let slope = |p1: f64, p2: f64, s: f64| {
+        let c = 3. * p1;
+        let b = 3. * (p2 - p1) - c;
+        let a = 1. - c - b;
+        (3. * a * s + 2. * b) * s + c
+    };
+
+    // x grows with the parameter, so Newton's method usually finds it in a
+    // few steps; where the curve is flat, fall back to bisection.
+    let mut s = x;
+    for _ in 0..8 {
+        let error = coords(x1, x2, s) - x;
+        if error.abs() < 1e-7 {
+            return coords(y1, y2, s);
+        }
+        let d = slope(x1, x2, s);
+        if d.abs() < 1e-6 {
+            break;
+        }
+        s -= error / d;
+    }
+    let (mut low, mut high) = (0., 1.);
+    s = x;
+    while high - low > 1e-7 {
+        if coords(x1, x2, s) < x {
+            low = s;
+        } else {
+            high = s;
+        }
+        s = (low + high) / 2.;
+    }
+    coords(y1, y2, s)
+}
+
+/// Values that can be interpolated.
+pub trait Lerp {
+    /// The value a fraction `t` of the way from `self` to `other`.
+    ///
+    /// `t` is usually within `0..=1`, but may be outside it, for easing
+    /// curves that overshoot.
+    fn lerp(&self, other: &Self, t: f64) -> Self;
+}
+
+impl Lerp for f64 {
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for Point {
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        Point::new(self.x.lerp(&other.x, t), self.y.lerp(&other.y, t))
+    }
+}
+
+impl Lerp for Vec2 {
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        Vec2::new(self.x.lerp(&other.x, t), self.y.lerp(&other.y, t))
+    }
+}
+
+impl Lerp for Size {
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        Size::new(
+            self.width.lerp(&other.width, t),
+            self.height.lerp(&other.height, t),
+        )
+    }
+}
+
+impl Lerp for Rect {
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        Rect::new(
+            self.x0.lerp(&other.x0, t),
+            self.y0.lerp(&other.y0, t),
+            self.x1.lerp(&other.x1, t),
+            self.y1.lerp(&other.y1, t),
+        )
+    }
+}
+
+/// Colors are interpolated per channel, alpha included.
+impl Lerp for Color {
+    fn lerp(&self, other: &Self, t: f64) -> Self {
+        let (from, to) = (self.as_rgba_u32(), other.as_rgba_u32());
+        let channel = |shift: u32| {
+            let from = ((from >> shift) & 0xff) as f64;
+            let to = ((to >> shift) & 0xff) as f64;
+            from.lerp(&to, t) / 255.
+        };
+        Color::rgba(channel(24), channel(16), channel(8), channel(0))
+    }
+}
+
+/// The progress of an animation with a fixed duration.
+///
+/// Feed it the interval of each [`LifeCycle::AnimFrame`] with [`advance`],
+/// and read the eased progress with [`progress`], or the value at that
+/// progress with [`value`].
+///
+/// [`LifeCycle::AnimFrame`]: ../enum.LifeCycle.html#variant.AnimFrame
+/// [`advance`]: #method.advance
+/// [`progress`]: #method.progress
+/// [`value`]: #method.value
+#[derive(Debug, Clone)]
+pub struct Animator {
+    /// In nanoseconds, like the intervals of `AnimFrame`.
+    duration: u64,
+    elapsed: u64,
+    easing: Easing,
+}
+
+impl Animator {
+    /// Create an animator for an animation that lasts `duration`, with
+    /// linear easing.
+    pub fn new(duration: Duration) -> Self {
+        Animator {
+            duration: duration.as_nanos() as u64,
+            elapsed: 0,
+            easing: Easing::Linear,
+        }
+    }
+
+    /// Builder-style method to set the easing curve.
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Move the animation forward by `interval` nanoseconds.
+    ///
+    /// Returns `true` if the animation is still running, and the widget
+    /// should request another frame.
+    pub fn advance(&mut self, interval: u64) -> bool {
+        self.elapsed = self.elapsed.saturating_add(interval).min(self.duration);
+        !self.is_done()
+    }
+
+    /// The eased progress, from 0 at the start to 1 at the end.
+    pub fn progress(&self) -> f64 {
+        if self.duration == 0 {
+            return 1.;
+        }
+        self.easing
+            .apply(self.elapsed as f64 / self.duration as f64)
+    }
+
+    /// The value between `from` and `to` at the current progress.
+    pub fn value<V: Lerp>(&self, from: &V, to: &V) -> V {
+        from.lerp(to, self.progress())
+    }
+
+    /// Returns `true` once the whole duration has passed.
+    pub fn is_done(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    /// Start the animation over.
+    pub fn restart(&mut self) {
+        self.elapsed = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn easing_curves() {
+        let curves = [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+            Easing::CubicBezier(0.1, 0.7, 1.0, 0.1),
+        ];
+        for &easing in &curves {
+            assert!(close(easing.apply(0.), 0.));
+            assert!(close(easing.apply(1.), 1.));
+            assert!(close(easing.apply(2.), 1.));
+        }
+        assert!(Easing::EaseIn.apply(0.25) < 0.25);
+        assert!(Easing::EaseOut.apply(0.25) > 0.25);
+        // ease-in-out is symmetric about its middle.
+        assert!(close(Easing::EaseInOut.apply(0.5), 0.5));
+        let (a, b) = (Easing::EaseInOut.apply(0.2), Easing::EaseInOut.apply(0.8));
+        assert!(close(a, 1. - b));
+        // a Bézier with its control points on the diagonal is linear.
+        let diagonal = Easing::CubicBezier(0.25, 0.25, 0.75, 0.75);
+        assert!(close(diagonal.apply(0.3), 0.3));
+    }
+
+    #[test]
+    fn lerp_values() {
+        assert!(close(2.0.lerp(&4.0, 0.25), 2.5));
+        let rect = Rect::new(0., 0., 10., 10.).lerp(&Rect::new(10., 0., 30., 20.), 0.5);
+        assert_eq!((rect.x0, rect.y0, rect.x1, rect.y1), (5., 0., 20., 15.));
+        let color = Color::rgba8(0, 0xff, 0, 0).lerp(&Color::rgba8(0xff, 0xff, 0, 0xff), 0.2);
+        assert_eq!(color.as_rgba_u32(), 0x33ff_0033);
+    }
+
+    #[test]
+    fn animator_progress() {
+        let ms = |n: u64| n * 1_000_000;
+        let mut anim = Animator::new(Duration::from_millis(100));
+        assert!(close(anim.progress(), 0.));
+        assert!(anim.advance(ms(40)));
+        assert!(close(anim.value(&10., &20.), 14.));
+        assert!(!anim.advance(ms(80)));
+        assert!(anim.is_done());
+        assert!(close(anim.progress(), 1.));
+
+        anim.restart();
+        assert!(!anim.is_done());
+        assert!(close(anim.progress(), 0.));
+
+        // an animation without a duration is over at once.
+        assert!(close(
+            Animator::new(Duration::from_millis(0)).progress(),
+            1.
+        ));
+    }
+}
@@ -14,19 +14,100 @@
 
 //! Window building and app lifecycle.
 
+use std::any::Any;
 use std::cell::RefCell;
+use std::path::PathBuf;
 use std::rc::Rc;
+use std::time::Duration;
 
+use crate::autosave::Autosave;
+use crate::debounce::Debouncer;
 use crate::ext_event::{ExtEventHost, ExtEventSink};
-use crate::kurbo::Size;
-use crate::shell::{Application, Error as PlatformError, RunLoop, WindowBuilder, WindowHandle};
+use crate::kurbo::{Point, Size};
+use crate::local_state::LocalState;
+use crate::shell::{
+    Application, Error as PlatformError, Monitor, RunLoop, Screen, WindowBuilder, WindowHandle,
+};
 use crate::widget::WidgetExt;
 use crate::win_handler::AppState;
-use crate::window::{PendingWindow, WindowId};
-use crate::{theme, AppDelegate, Data, DruidHandler, Env, LocalizedString, MenuDesc, Widget};
+use crate::window::{MouseRouter, PendingWindow, WindowId};
+use crate::{
+    theme, AppDelegate, Color, Command, Data, DataVersion, DruidHandler, Env, Event, GestureSet,
+    HotKey, LocalizedString, MenuDesc, MouseEvent, MouseRoute, Selector, Widget,
+};
 
 /// A function that modifies the initial environment.
 type EnvSetupFn<T> = dyn FnOnce(&mut Env, &T);
+pub(crate) type FirstPaintFn<T> = dyn FnOnce(&mut T, &Env);
+pub(crate) type EventFilterFn = dyn FnMut(&Event, WindowId) -> EventFilterResult;
+
+/// What an [`event_filter`] decided to do with an event.
+///
+/// [`event_filter`]: struct.AppLauncher.html#method.event_filter
+#[derive(Debug, Clone)]
+pub enum EventFilterResult {
+    /// Deliver the event unchanged.
+    Pass,
+    /// Discard the event; neither the delegate nor any widget will see it.
+    Drop,
+    /// Deliver this event instead.
+    Replace(Event),
+}
+
+/// Where a window opens, set with [`WindowDesc::position`].
+///
+/// [`WindowDesc::position`]: struct.WindowDesc.html#method.position
+#[derive(Debug, Clone, PartialEq)]
+pub enum WindowPosition {
+    /// With its top-left corner at a point, in screen coordinates, such as
+    /// where the window was when it was last closed.
+    ///
+    /// If the point is on no monitor, for instance because the monitor it
+    /// was on has been disconnected, the window is centered on the primary
+    /// monitor instead.
+    At(Point),
+    /// Centered on the monitor of the application's focused window, or on
+    /// the primary monitor if none of its windows has focus.
+    Centered,
+    /// Centered on one of the monitors from [`Screen::get_monitors`], or on
+    /// the primary monitor if that one is no longer connected.
+    ///
+    /// [`Screen::get_monitors`]: struct.Screen.html#method.get_monitors
+    CenteredOn(Monitor),
+}
+
+impl WindowPosition {
+    /// The top-left corner of a window of `size` at this position.
+    ///
+    /// `monitors` are those of the system, the primary first; `active` is
+    /// a point on the active monitor, if one is known. Returns `None` if
+    /// there are no monitors, and the platform should place the window.
+    fn resolve(&self, size: Size, monitors: &[Monitor], active: Option<Point>) -> Option<Point> {
+        let primary = monitors.first()?;
+        let monitor = match self {
+            WindowPosition::At(point) => {
+                if monitors.iter().any(|monitor| monitor.contains(*point)) {
+                    return Some(*point);
+                }
+                primary
+            }
+            WindowPosition::Centered => active
+                .and_then(|point| monitors.iter().find(|monitor| monitor.contains(point)))
+                .unwrap_or(primary),
+            WindowPosition::CenteredOn(monitor) => monitors
+                .iter()
+                .find(|connected| *connected == monitor)
+                .unwrap_or(primary),
+        };
+        // a window larger than the work area is pinned to its top left.
+        let work = monitor.work_rect();
+        let centered = work.center() - size.to_vec2() / 2.;
+        Some(Point::new(
+            centered.x.min(work.x1 - size.width).max(work.x0),
+            centered.y.min(work.y1 - size.height).max(work.y0),
+        ))
+    }
+}
 
 /// Handles initial setup of an application, and starts the runloop.
 pub struct AppLauncher<T> {
@@ -34,6 +115,13 @@ pub struct AppLauncher<T> {
     env_setup: Option<Box<EnvSetupFn<T>>>,
     delegate: Option<Box<dyn AppDelegate<T>>>,
     ext_event_host: ExtEventHost,
+    debouncer: Debouncer,
+    autosave: Option<Autosave>,
+    data_version: Option<fn(&T) -> u64>,
+    first_paint: Option<Box<FirstPaintFn<T>>>,
+    event_filter: Option<Box<EventFilterFn>>,
+    quit_on_last_window_close: bool,
+    hotkeys: Vec<(HotKey, Command)>,
 }
 
 /// A description of a window to be instantiated.
@@ -45,6 +133,14 @@ pub struct WindowDesc<T> {
     pub(crate) title: LocalizedString<T>,
     pub(crate) size: Option<Size>,
     pub(crate) menu: Option<MenuDesc<T>>,
+    pub(crate) mouse_router: Option<Box<MouseRouter>>,
+    pub(crate) gestures: GestureSet,
+    pub(crate) hide_on_close: bool,
+    pub(crate) resizable: bool,
+    pub(crate) always_on_top: bool,
+    pub(crate) transparent: bool,
+    pub(crate) background: Option<Color>,
+    pub(crate) position: Option<WindowPosition>,
     /// The `WindowId` that will be assigned to this window.
     ///
     /// This can be used to track a window from when it is launched and when
@@ -52,6 +148,31 @@ pub struct WindowDesc<T> {
     pub id: WindowId,
 }
 
+/// A [`WindowDesc`] for any type of data, the argument of [`NEW_WINDOW`]
+/// and [`SHOW_MODAL`].
+///
+/// A selector is a constant, so the type of its argument can't depend on the
+/// application's data. This is only created by the context methods that
+/// submit these commands, such as [`EventCtx::new_window`], which check that
+/// the window is for the application's data.
+///
+/// [`WindowDesc`]: struct.WindowDesc.html
+/// [`NEW_WINDOW`]: commands/constant.NEW_WINDOW.html
+/// [`SHOW_MODAL`]: commands/constant.SHOW_MODAL.html
+/// [`EventCtx::new_window`]: struct.EventCtx.html#method.new_window
+pub struct AnyWindowDesc(Box<dyn Any>);
+
+impl AnyWindowDesc {
+    pub(crate) fn new<T: Data>(desc: WindowDesc<T>) -> Self {
+        AnyWindowDesc(Box::new(desc))
+    }
+
+    /// The window description, if it is for data of type `T`.
+    pub(crate) fn into_desc<T: Data>(self) -> Option<WindowDesc<T>> {
+        self.0.downcast().ok().map(|desc| *desc)
+    }
+}
+
 impl<T: Data> AppLauncher<T> {
     /// Create a new `AppLauncher` with the provided window.
     pub fn with_window(window: WindowDesc<T>) -> Self {
@@ -60,6 +181,13 @@ impl<T: Data> AppLauncher<T> {
             env_setup: None,
             delegate: None,
             ext_event_host: ExtEventHost::new(),
+            debouncer: Debouncer::default(),
+            autosave: None,
+            data_version: None,
+            first_paint: None,
+            event_filter: None,
+            quit_on_last_window_close: !cfg!(all(target_os = "macos", not(feature = "use_gtk"))),
+            hotkeys: Vec::new(),
         }
     }
 
@@ -80,6 +208,133 @@ impl<T: Data> AppLauncher<T> {
         self
     }
 
+    /// Debounce commands with the given [`Selector`].
+    ///
+    /// When a command with this selector is submitted, it is held instead of
+    /// being dispatched immediately. Each subsequent submission to the same
+    /// target replaces the held command and restarts the quiet period; once
+    /// `interval` has passed without a new submission, only the most recent
+    /// command is delivered. Commands sent to different targets are held
+    /// separately.
+    ///
+    /// This is useful for chatty commands, such as a live search query that
+    /// is submitted on every keystroke.
+    ///
+    /// [`Selector`]: struct.Selector.html
+    pub fn debounce_command<A>(mut self, selector: Selector<A>, interval: Duration) -> Self {
+        self.debouncer.add(selector.symbol(), interval);
+        self
+    }
+
+    /// Periodically save the application state to a recovery file.
+    ///
+    /// Every `interval` while the application is running, the [`AppDelegate`]'s
+    /// [`autosave`] method is called, and the bytes it returns replace the
+    /// contents of the file at `path`. The file is removed when the application
+    /// exits normally; if it is still there at the next launch, its contents
+    /// are available from [`restore_from_crash`].
+    ///
+    /// [`AppDelegate`]: trait.AppDelegate.html
+    /// [`autosave`]: trait.AppDelegate.html#method.autosave
+    /// [`restore_from_crash`]: #method.restore_from_crash
+    pub fn autosave(mut self, path: impl Into<PathBuf>, interval: Duration) -> Self {
+        self.autosave = Some(Autosave::new(path.into(), interval));
+        self
+    }
+
+    /// The state saved by [`autosave`] during the previous run, if that run
+    /// did not exit normally.
+    ///
+    /// This should be checked before calling [`launch`], so that the recovered
+    /// bytes can be used to construct the initial data.
+    ///
+    /// [`autosave`]: #method.autosave
+    /// [`launch`]: #method.launch
+    pub fn restore_from_crash(&self) -> Option<Vec<u8>> {
+        self.autosave.as_ref().and_then(Autosave::read)
+    }
+
+    /// Skip updates while the [`DataVersion`] of the application data is
+    /// unchanged.
+    ///
+    /// [`DataVersion`]: trait.DataVersion.html
+    pub fn use_data_version(mut self) -> Self
+    where
+        T: DataVersion,
+    {
+        self.data_version = Some(T::version);
+        self
+    }
+
+    /// Provide a closure to run once the first frame has been painted.
+    ///
+    /// This is a place for initialization that is not needed to show the
+    /// application, such as warming caches, so that it does not delay the
+    /// first frame. The closure runs exactly once, right after the first
+    /// window to be painted has been painted; any changes it makes to the
+    /// data are picked up in an update pass shortly after.
+    pub fn on_first_paint(mut self, f: impl FnOnce(&mut T, &Env) + 'static) -> Self {
+        self.first_paint = Some(Box::new(f));
+        self
+    }
+
+    /// Provide a filter that sees every window event before anything else.
+    ///
+    /// The filter is called with each event the platform delivers to a
+    /// window, along with that window's id, before the [`AppDelegate`] or
+    /// any widget. It can let the event through, drop it, or replace it with
+    /// another event. Unlike the delegate it has no access to the
+    /// application data, which makes it a cheap place for things like input
+    /// recording or global key remapping.
+    ///
+    /// [`AppDelegate`]: trait.AppDelegate.html
+    pub fn event_filter(
+        mut self,
+        filter: impl FnMut(&Event, WindowId) -> EventFilterResult + 'static,
+    ) -> Self {
+        self.event_filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Set whether the application quits when its last window is closed.
+    ///
+    /// This defaults to `true`, except on macOS, where applications usually
+    /// keep running without any windows. The check happens after the
+    /// [`AppDelegate`] has been told about the closed window, so a delegate
+    /// can still keep the application alive by opening a new one.
+    ///
+    /// [`AppDelegate`]: trait.AppDelegate.html
+    pub fn quit_on_last_window_close(mut self, quit: bool) -> Self {
+        self.quit_on_last_window_close = quit;
+        self
+    }
+
+    /// Submit `command` whenever `hotkey` is pressed in any window.
+    ///
+    /// Unlike a menu item's accelerator, this binding does not need a
+    /// visible menu entry. It is checked before the key is delivered to the
+    /// [`AppDelegate`] or any widget, and a matching key press is consumed;
+    /// the command is submitted to the window where the key was pressed.
+    /// Plain typing, without Ctrl, Alt or Meta, is the exception: while a
+    /// widget has focus it is delivered as usual, so a binding such as
+    /// `HotKey::new(None, "p")` does not swallow text input.
+    ///
+    /// ```
+    /// # use druid::{AppLauncher, HotKey, Selector, SysMods, WindowDesc};
+    /// # use druid::widget::Label;
+    /// const SHOW_PALETTE: Selector = Selector::new("my-app.show-palette");
+    ///
+    /// let window = WindowDesc::new(|| Label::new("hello"));
+    /// let launcher = AppLauncher::<()>::with_window(window)
+    ///     .hotkey(HotKey::new(SysMods::CmdShift, "P"), SHOW_PALETTE);
+    /// ```
+    ///
+    /// [`AppDelegate`]: trait.AppDelegate.html
+    pub fn hotkey(mut self, hotkey: HotKey, command: impl Into<Command>) -> Self {
+        self.hotkeys.push((hotkey, command.into()));
+        self
+    }
+
     /// Initialize a minimal logger for printing logs out to stderr.
     ///
     /// Meant for use during development only.
@@ -105,6 +360,20 @@ impl<T: Data> AppLauncher<T> {
         })
     }
 
+    /// Show the widget inspector in every window.
+    ///
+    /// The inspector outlines the layout rect of each widget, and labels the
+    /// widget under the mouse with its type, id and size. It can also be
+    /// toggled per window with the [`TOGGLE_INSPECTOR`] command; nothing is
+    /// drawn unless one of the two asks for it.
+    ///
+    /// [`TOGGLE_INSPECTOR`]: commands/constant.TOGGLE_INSPECTOR.html
+    pub fn debug_inspector(self) -> Self {
+        self.configure_env(|env, _| {
+            env.set(Env::DEBUG_INSPECT, true);
+        })
+    }
+
     /// Build the windows and start the runloop.
     ///
     /// Returns an error if a window cannot be instantiated. This is usually
@@ -112,14 +381,10 @@ impl<T: Data> AppLauncher<T> {
     pub fn launch(mut self, data: T) -> Result<(), PlatformError> {
         Application::init();
         let mut main_loop = RunLoop::new();
-        let mut env = theme::init();
-        if let Some(f) = self.env_setup.take() {
-            f(&mut env, &data);
-        }
+        let windows = std::mem::take(&mut self.windows);
+        let state = self.build_state(data);
 
-        let state = AppState::new(data, env, self.delegate.take(), self.ext_event_host);
-
-        for desc in self.windows {
+        for desc in windows {
             let window = desc.build_native(&state)?;
             window.show();
         }
@@ -127,6 +392,27 @@ impl<T: Data> AppLauncher<T> {
         main_loop.run();
         Ok(())
     }
+
+    /// Create the application state, with the options set on this launcher.
+    pub(crate) fn build_state(mut self, data: T) -> Rc<RefCell<AppState<T>>> {
+        let mut env = theme::init();
+        if let Some(f) = self.env_setup.take() {
+            f(&mut env, &data);
+        }
+
+        let state = AppState::new(data, env, self.delegate.take(), self.ext_event_host);
+        {
+            let mut s = state.borrow_mut();
+            s.debouncer = self.debouncer;
+            s.autosave = self.autosave;
+            s.data_version = self.data_version;
+            s.first_paint = self.first_paint.take();
+            s.event_filter = self.event_filter.take();
+            s.quit_on_last_window_close = self.quit_on_last_window_close;
+            s.hotkeys = self.hotkeys;
+        }
+        state
+    }
 }
 
 impl<T: Data> WindowDesc<T> {
@@ -148,10 +434,50 @@ impl<T: Data> WindowDesc<T> {
             title: LocalizedString::new("app-name"),
             size: None,
             menu: MenuDesc::platform_default(),
+            mouse_router: None,
+            gestures: GestureSet::default(),
+            hide_on_close: false,
+            resizable: true,
+            always_on_top: false,
+            transparent: false,
+            background: None,
+            position: None,
             id: WindowId::next(),
         }
     }
 
+    /// Create a new `WindowDesc` for a window with state of its own.
+    ///
+    /// The root widget sees the application data and the window's state as
+    /// a pair, `(T, L)`; use [`lens!`] to reach either half. Each window made
+    /// from this `WindowDesc` starts with `local`, and keeps its own copy
+    /// from then on. This is where to keep what belongs to one view of the
+    /// data, such as a selection or a filter, when several windows show
+    /// the same document.
+    ///
+    /// ```
+    /// use druid::widget::{Flex, Label, TextBox};
+    /// use druid::{lens, LensWrap, WindowDesc};
+    ///
+    /// // a document, shared by all windows, and a note that each window keeps.
+    /// let window = WindowDesc::<String>::with_local_state(String::new(), || {
+    ///     Flex::column()
+    ///         .with_child(LensWrap::new(TextBox::new(), lens!((String, String), 0)))
+    ///         .with_child(LensWrap::new(TextBox::new(), lens!((String, String), 1)))
+    ///         .with_child(Label::new(|data: &(String, String), _: &_| data.1.clone()))
+    /// });
+    /// ```
+    ///
+    /// [`lens!`]: macro.lens.html
+    pub fn with_local_state<L, W, F>(local: L, root: F) -> WindowDesc<T>
+    where
+        L: Data,
+        W: Widget<(T, L)> + 'static,
+        F: Fn() -> W + 'static,
+    {
+        WindowDesc::new(move || LocalState::new(local.clone(), root()))
+    }
+
     /// Set the title for this window. This is a [`LocalizedString`] that will
     /// be kept up to date as the application's state changes.
     ///
@@ -167,6 +493,95 @@ impl<T: Data> WindowDesc<T> {
         self
     }
 
+    /// Set a strategy for routing mouse events in this window.
+    ///
+    /// The closure is called with every mouse event, in window coordinates,
+    /// along with the current window size, before the event is passed down the
+    /// widget tree. By returning a [`MouseRoute`] it can let the event be
+    /// hit-tested as usual, redirect it to specific widgets regardless of
+    /// their position or paint order, or drop it entirely.
+    ///
+    /// This is intended for custom containers with overlapping children;
+    /// most applications will not need it.
+    ///
+    /// [`MouseRoute`]: enum.MouseRoute.html
+    pub fn mouse_router(
+        mut self,
+        f: impl FnMut(&MouseEvent, Size) -> MouseRoute + 'static,
+    ) -> Self {
+        self.mouse_router = Some(Box::new(f));
+        self
+    }
+
+    /// Set the gestures this window recognizes.
+    ///
+    /// Recognized gestures are delivered as [`Event::Gesture`], alongside
+    /// the raw events they are made of. By default none are recognized.
+    ///
+    /// [`Event::Gesture`]: enum.Event.html#variant.Gesture
+    pub fn gestures(mut self, gestures: GestureSet) -> Self {
+        self.gestures = gestures;
+        self
+    }
+
+    /// Set whether closing this window hides it instead of destroying it.
+    ///
+    /// A hidden window keeps its widgets, and their state, and is still
+    /// updated when the data changes; it is not painted. Show it again with
+    /// [`SHOW_WINDOW`]. This applies both when the user closes the window and
+    /// to [`CLOSE_WINDOW`]; the [`AppDelegate`] is not told about a window
+    /// that was only hidden.
+    ///
+    /// This is what macOS applications commonly do with windows such as a
+    /// preferences panel.
+    ///
+    /// [`SHOW_WINDOW`]: commands/constant.SHOW_WINDOW.html
+    /// [`CLOSE_WINDOW`]: commands/constant.CLOSE_WINDOW.html
+    /// [`AppDelegate`]: trait.AppDelegate.html
+    pub fn hide_on_close(mut self, hide: bool) -> Self {
+        self.hide_on_close = hide;
+        self
+    }
+
+    /// Set whether the user can resize the window. The default is `true`.
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        self.resizable = resizable;
+        self
+    }
+
+    /// Set whether the window stays above other windows. The default is `false`.
+    pub fn always_on_top(mut self, always_on_top: bool) -> Self {
+        self.always_on_top = always_on_top;
+        self
+    }
+
+    /// Set whether the window has a transparent background.
+    ///
+    /// A transparent window does not paint [`WINDOW_BACKGROUND_COLOR`], so
+    /// that whatever is behind the window shows through wherever the widgets
+    /// do not paint. This can be used for windows with a custom shape. The
+    /// default is `false`.
+    ///
+    /// On platforms that do not support it, this is ignored with a warning.
+    ///
+    /// [`WINDOW_BACKGROUND_COLOR`]: theme/constant.WINDOW_BACKGROUND_COLOR.html
+    pub fn transparent(mut self, transparent: bool) -> Self {
+        self.transparent = transparent;
+        self
+    }
+
+    /// Set the color the window is cleared to before its widgets paint,
+    /// instead of the [`WINDOW_BACKGROUND_COLOR`] of the `Env`.
+    ///
+    /// This has no effect on a [`transparent`] window, which is not cleared.
+    ///
+    /// [`WINDOW_BACKGROUND_COLOR`]: theme/constant.WINDOW_BACKGROUND_COLOR.html
+    /// [`transparent`]: #method.transparent
+    pub fn background_color(mut self, color: Color) -> Self {
+        self.background = Some(color);
+        self
+    }
+
     /// Set the initial window size.
     ///
     /// You can pass in a tuple `(width, height)` or `kurbo::Size` e.g.
@@ -179,6 +594,19 @@ impl<T: Data> WindowDesc<T> {
         self
     }
 
+    /// Set where the window opens; by default, the platform decides.
+    ///
+    /// To open a window where it was when it was closed, save its position
+    /// from [`Event::WindowMoved`], and restore it with
+    /// [`WindowPosition::At`].
+    ///
+    /// [`Event::WindowMoved`]: enum.Event.html#variant.WindowMoved
+    /// [`WindowPosition::At`]: enum.WindowPosition.html#variant.At
+    pub fn position(mut self, position: WindowPosition) -> Self {
+        self.position = Some(position);
+        self
+    }
+
     /// Attempt to create a platform window from this `WindowDesc`.
     pub(crate) fn build_native(
         mut self,
@@ -202,13 +630,88 @@ impl<T: Data> WindowDesc<T> {
         }
 
         builder.set_title(self.title.localized_str());
+        builder.set_resizable(self.resizable);
+        builder.set_always_on_top(self.always_on_top);
+        builder.set_transparent(self.transparent);
         if let Some(menu) = platform_menu {
             builder.set_menu(menu);
         }
 
-        let window = PendingWindow::new(self.root, self.title, self.menu);
+        let origin = self.position.as_ref().and_then(|position| {
+            // the size the platforms give a window that has none.
+            let size = self.size.unwrap_or_else(|| Size::new(500., 400.));
+            let active = state.borrow().focused_window_position();
+            position.resolve(size, &Screen::get_monitors(), active)
+        });
+
+        let mut window = PendingWindow::new(self.root, self.title, self.menu);
+        window.mouse_router = self.mouse_router;
+        window.gestures = self.gestures;
+        window.hide_on_close = self.hide_on_close;
+        window.transparent = self.transparent;
+        window.background = self.background;
         state.borrow_mut().add_window(self.id, window);
 
-        builder.build()
+        let handle = builder.build()?;
+        if let Some(origin) = origin {
+            handle.set_position(origin);
+        }
+        Ok(handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kurbo::Rect;
+
+    #[test]
+    fn resolve_window_position() {
+        let primary = Monitor::new(
+            true,
+            Rect::new(0., 0., 1000., 800.),
+            Rect::new(0., 0., 1000., 760.),
+            1.,
+        );
+        // a second monitor to the left of, and above, the primary.
+        let left = Monitor::new(
+            false,
+            Rect::new(-800., -200., 0., 400.),
+            Rect::new(-800., -200., 0., 400.),
+            2.,
+        );
+        let monitors = [primary.clone(), left.clone()];
+        let size = Size::new(200., 100.);
+        let resolve = |position: WindowPosition, active| {
+            let origin = position.resolve(size, &monitors, active).unwrap();
+            (origin.x, origin.y)
+        };
+
+        assert_eq!(
+            resolve(WindowPosition::At(Point::new(-500., -100.)), None),
+            (-500., -100.)
+        );
+        // a position on a monitor that is gone.
+        assert_eq!(
+            resolve(WindowPosition::At(Point::new(2000., 10.)), None),
+            (400., 330.)
+        );
+        assert_eq!(resolve(WindowPosition::Centered, None), (400., 330.));
+        let active = Some(Point::new(-10., 0.));
+        assert_eq!(resolve(WindowPosition::Centered, active), (-500., 50.));
+        assert_eq!(
+            resolve(WindowPosition::CenteredOn(left), None),
+            (-500., 50.)
+        );
+        let gone = Monitor::new(false, Rect::new(1000., 0., 2000., 800.), Rect::ZERO, 1.);
+        assert_eq!(
+            resolve(WindowPosition::CenteredOn(gone), None),
+            (400., 330.)
+        );
+
+        // too big for the work area: pinned to its top left.
+        let big = WindowPosition::Centered.resolve(Size::new(1200., 100.), &monitors, None);
+        assert_eq!(big, Some(Point::new(0., 330.)));
+        assert_eq!(WindowPosition::Centered.resolve(size, &[], None), None);
     }
 }
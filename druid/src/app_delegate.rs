@@ -14,24 +14,41 @@
 
 //! Customizing application-level behaviour.
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
 
-use crate::{Command, Data, Env, Event, Target, WindowId};
+use crate::command::{self, DataType, WrongDataType};
+use crate::interval::IntervalTimers;
+use crate::{
+    commands, AnyContextMenu, AnyMenuDesc, AnyWindowDesc, Command, ContextMenu, Data, Env, Event,
+    MenuDesc, Point, Target, TimerToken, WindowDesc, WindowHandle, WindowId,
+};
 
 /// A context passed in to [`AppDelegate`] functions.
 pub struct DelegateCtx<'a> {
     pub(crate) source_id: WindowId,
+    /// The type of the application's data.
+    pub(crate) data_type: DataType,
+    pub(crate) focused_window: Option<WindowId>,
     pub(crate) command_queue: &'a mut VecDeque<(Target, Command)>,
+    /// The interval timers of the source window, if it is still open.
+    pub(crate) interval_timers: Option<&'a mut IntervalTimers>,
+    pub(crate) window_handles: &'a HashMap<WindowId, WindowHandle>,
+    /// The time the event is handled at, from the app's clock.
+    pub(crate) now: Instant,
 }
 
 impl<'a> DelegateCtx<'a> {
     /// Submit a [`Command`] to be run after this event is handled.
     ///
-    /// Commands are run in the order they are submitted; all commands
-    /// submitted during the handling of an event are executed before
-    /// the [`update()`] method is called.
+    /// Commands are run in the order they are submitted, unless they are
+    /// [`urgent`] or [`replace_pending`]; all commands submitted during the
+    /// handling of an event are executed before the [`update()`] method is
+    /// called.
     ///
     /// [`Command`]: struct.Command.html
+    /// [`urgent`]: struct.Command.html#method.urgent
+    /// [`replace_pending`]: struct.Command.html#method.replace_pending
     /// [`update()`]: trait.Widget.html#tymethod.update
     pub fn submit_command(
         &mut self,
@@ -40,7 +57,131 @@ impl<'a> DelegateCtx<'a> {
     ) {
         let command = command.into();
         let target = target.into().unwrap_or_else(|| self.source_id.into());
-        self.command_queue.push_back((target, command))
+        command::enqueue(self.command_queue, target, command)
+    }
+
+    /// Create a new window.
+    ///
+    /// This submits a [`NEW_WINDOW`] command; the window is created after
+    /// this event is handled. It is an error if the window is not for the
+    /// application's data.
+    ///
+    /// [`NEW_WINDOW`]: commands/constant.NEW_WINDOW.html
+    pub fn new_window<T: Data>(&mut self, desc: WindowDesc<T>) -> Result<(), WrongDataType> {
+        self.data_type.check::<T>()?;
+        let desc = AnyWindowDesc::new(desc);
+        self.submit_command(Command::one_shot(commands::NEW_WINDOW, desc), None);
+        Ok(())
+    }
+
+    /// Set the menu of the window this event came from.
+    ///
+    /// It is an error if the menu is not for the application's data.
+    pub fn set_menu<T: Data>(&mut self, menu: MenuDesc<T>) -> Result<(), WrongDataType> {
+        self.data_type.check::<T>()?;
+        let menu = AnyMenuDesc::new(menu);
+        self.submit_command(Command::new(commands::SET_MENU, menu), None);
+        Ok(())
+    }
+
+    /// Show a context menu in the window this event came from.
+    ///
+    /// `location` is in window coordinates. It is an error if the menu is
+    /// not for the application's data.
+    pub fn show_context_menu<T: Data>(
+        &mut self,
+        menu: MenuDesc<T>,
+        location: Point,
+    ) -> Result<(), WrongDataType> {
+        self.data_type.check::<T>()?;
+        let menu = AnyContextMenu::new(ContextMenu::new(menu, location));
+        self.submit_command(Command::new(commands::SHOW_CONTEXT_MENU, menu), None);
+        Ok(())
+    }
+
+    /// Request a recurring timer event.
+    ///
+    /// The delegate will receive an [`Event::Timer`] with the returned token
+    /// every `interval`, until the timer is cancelled with [`cancel_timer`].
+    /// The timer belongs to the window this event came from, and stops when
+    /// that window is closed.
+    ///
+    /// [`Event::Timer`]: enum.Event.html#variant.Timer
+    /// [`cancel_timer`]: #method.cancel_timer
+    pub fn request_interval_timer(&mut self, interval: Duration) -> TimerToken {
+        match self.interval_timers.as_mut() {
+            Some(timers) => timers.request(interval, self.now),
+            None => {
+                log::warn!(
+                    "request_interval_timer: window {:?} is gone",
+                    self.source_id
+                );
+                TimerToken::INVALID
+            }
+        }
+    }
+
+    /// The window that has keyboard focus, if any.
+    ///
+    /// This is the window a global command, such as "close the current
+    /// tab", is usually meant for. It is `None` while the application is in
+    /// the background. It is not always the window this event came from: a
+    /// timer, for instance, may fire in a window behind the focused one.
+    pub fn focused_window(&self) -> Option<WindowId> {
+        self.focused_window
+    }
+
+    /// The platform handle of a window, if it is open.
+    ///
+    /// With the `raw-win-handle` feature, the handle implements
+    /// [`HasRawWindowHandle`], for embedding native views, or for rendering
+    /// to the window with a library such as `wgpu`.
+    ///
+    /// [`HasRawWindowHandle`]: trait.HasRawWindowHandle.html
+    pub fn window_handle(&self, id: WindowId) -> Option<&WindowHandle> {
+        self.window_handles.get(&id)
+    }
+
+    /// Cancel a timer requested with [`request_interval_timer`].
+    ///
+    /// [`request_interval_timer`]: #method.request_interval_timer
+    pub fn cancel_timer(&mut self, token: TimerToken) {
+        let cancelled = self
+            .interval_timers
+            .as_mut()
+            .map(|timers| timers.cancel(token))
+            .unwrap_or(false);
+        if !cancelled {
+            log::warn!("cancel_timer called with unknown token {:?}", token);
+        }
+    }
+}
+
+/// Whether a command was handled, returned by [`AppDelegate::command`].
+///
+/// [`AppDelegate::command`]: trait.AppDelegate.html#method.command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Handled {
+    /// The command was handled, and should not be passed on.
+    Yes,
+    /// The command was not handled, and should be passed on to the widgets.
+    No,
+}
+
+impl Handled {
+    /// Returns `true` if this is `Handled::Yes`.
+    pub fn is_handled(self) -> bool {
+        self == Handled::Yes
+    }
+}
+
+impl From<bool> for Handled {
+    fn from(handled: bool) -> Handled {
+        if handled {
+            Handled::Yes
+        } else {
+            Handled::No
+        }
     }
 }
 
@@ -70,6 +211,26 @@ pub trait AppDelegate<T: Data> {
         Some(event)
     }
 
+    /// The delegate's command handler.
+    ///
+    /// This is called for every command, after [`event`] and before the
+    /// command is passed down the tree. If it returns [`Handled::Yes`], the
+    /// command goes no further. This is the natural place for commands that
+    /// concern the whole application, such as "save all".
+    ///
+    /// [`event`]: #method.event
+    /// [`Handled::Yes`]: enum.Handled.html#variant.Yes
+    fn command(
+        &mut self,
+        target: Target,
+        cmd: &Command,
+        data: &mut T,
+        env: &Env,
+        ctx: &mut DelegateCtx,
+    ) -> Handled {
+        Handled::No
+    }
+
     /// The handler for window creation events.
     /// This function is called after a window has been added,
     /// allowing you to customize the window creation behavior of your app.
@@ -78,4 +239,77 @@ pub trait AppDelegate<T: Data> {
     /// The handler for window deletion events.
     /// This function is called after a window has been removed.
     fn window_removed(&mut self, id: WindowId, data: &mut T, env: &Env, ctx: &mut DelegateCtx) {}
+
+    /// Called after the last window has been removed.
+    ///
+    /// A delegate can open a new window from here, such as a welcome
+    /// window, or submit [`QUIT_APP`]. A window opened from here, or from
+    /// [`window_removed`], keeps the application running even if it quits
+    /// when its last window closes.
+    ///
+    /// [`QUIT_APP`]: commands/constant.QUIT_APP.html
+    /// [`window_removed`]: #method.window_removed
+    fn all_windows_closed(&mut self, data: &mut T, env: &Env, ctx: &mut DelegateCtx) {}
+
+    /// Called when the user asks to close a window, for instance with the
+    /// close button in the title bar, before the window is told about it.
+    ///
+    /// Return `false` to keep the window open; its widgets then never see
+    /// the [`Event::WindowCloseRequested`]. To ask for confirmation first,
+    /// keep the window open, show a dialog, and once the user agrees close
+    /// the window with the [`CLOSE_WINDOW`] command, which does not go
+    /// through this method.
+    ///
+    /// [`Event::WindowCloseRequested`]: enum.Event.html#variant.WindowCloseRequested
+    /// [`CLOSE_WINDOW`]: commands/constant.CLOSE_WINDOW.html
+    fn window_should_close(
+        &mut self,
+        id: WindowId,
+        data: &mut T,
+        env: &Env,
+        ctx: &mut DelegateCtx,
+    ) -> bool {
+        true
+    }
+
+    /// Called when the user asks to quit, for instance with the 'Quit' menu
+    /// item, before the application quits.
+    ///
+    /// Return `false` to keep running. This is the place to check for
+    /// unsaved changes across all windows: to ask for confirmation, keep
+    /// running, show a dialog, and once the user agrees submit
+    /// [`QUIT_APP`] again, answering `true` this time.
+    ///
+    /// This is not called when the application quits because its last
+    /// window was closed.
+    ///
+    /// [`QUIT_APP`]: commands/constant.QUIT_APP.html
+    fn application_should_quit(&mut self, data: &mut T, env: &Env, ctx: &mut DelegateCtx) -> bool {
+        true
+    }
+
+    /// Called when the system is about to go to sleep.
+    ///
+    /// This is a good time to pause expensive work such as animations, and
+    /// to save any unsaved state. Like the other power hooks, this is called
+    /// once for the whole application, regardless of how many windows are
+    /// open; it is never called on platforms without power notifications.
+    fn system_will_sleep(&mut self, data: &mut T, env: &Env, ctx: &mut DelegateCtx) {}
+
+    /// Called when the system has woken up from sleep.
+    fn system_did_wake(&mut self, data: &mut T, env: &Env, ctx: &mut DelegateCtx) {}
+
+    /// Called when the system is running on a battery that is low or critical.
+    fn battery_low(&mut self, data: &mut T, env: &Env, ctx: &mut DelegateCtx) {}
+
+    /// Serialize the state that should survive a crash.
+    ///
+    /// This is called periodically if autosave was enabled with
+    /// [`AppLauncher::autosave`]; the returned bytes replace the contents of
+    /// the recovery file. Returning `None` leaves the file untouched.
+    ///
+    /// [`AppLauncher::autosave`]: struct.AppLauncher.html#method.autosave
+    fn autosave(&mut self, data: &T, env: &Env) -> Option<Vec<u8>> {
+        None
+    }
 }
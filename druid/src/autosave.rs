@@ -0,0 +1,135 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Periodic autosave of application state, for crash recovery.
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::{TimerToken, WindowId};
+
+/// The autosave configuration and the recovery file it manages.
+///
+/// While the application is running, an interval timer on one of its windows
+/// asks the [`AppDelegate`] for a snapshot of the state, which is written to
+/// the recovery file. The file is removed when the application quits
+/// normally, so if it is present at launch the previous session did not shut
+/// down cleanly, and its contents can be offered to the application.
+///
+/// [`AppDelegate`]: trait.AppDelegate.html
+#[derive(Debug, Clone)]
+pub(crate) struct Autosave {
+    pub(crate) path: PathBuf,
+    pub(crate) interval: Duration,
+    /// The interval timer driving the autosave, and the window it lives on.
+    pub(crate) timer: Option<(WindowId, TimerToken)>,
+}
+
+impl Autosave {
+    pub(crate) fn new(path: PathBuf, interval: Duration) -> Self {
+        Autosave {
+            path,
+            interval,
+            timer: None,
+        }
+    }
+
+    /// Returns `true` if this is the token of our timer.
+    pub(crate) fn is_timer(&self, token: TimerToken) -> bool {
+        self.timer.map(|(_, t)| t == token).unwrap_or(false)
+    }
+
+    /// Replace the contents of the recovery file.
+    ///
+    /// The bytes are written to a sibling file which is then moved into
+    /// place, so a crash during the write can never leave behind a truncated
+    /// recovery file.
+    pub(crate) fn write(&self, bytes: &[u8]) -> io::Result<()> {
+        let mut tmp = self.path.clone().into_os_string();
+        tmp.push(".tmp");
+        fs::write(&tmp, bytes)?;
+        fs::rename(&tmp, &self.path)
+    }
+
+    /// The contents of the recovery file, if one was left behind.
+    pub(crate) fn read(&self) -> Option<Vec<u8>> {
+        match fs::read(&self.path) {
+            Ok(bytes) => Some(bytes),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => {
+                log::warn!("failed to read recovery file {:?}: {}", self.path, e);
+                None
+            }
+        }
+    }
+
+    /// Remove the recovery file, after a clean shutdown.
+    pub(crate) fn clear(&self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            if e.kind() != io::ErrorKind::NotFound {
+                log::warn!("failed to remove recovery file {:?}: {}", self.path, e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ext_event::ExtEventHost;
+    use crate::widget::Label;
+    use crate::win_handler::AppState;
+    use crate::{theme, AppDelegate, AppLauncher, Env, WindowDesc};
+
+    struct Snapshot;
+
+    impl AppDelegate<String> for Snapshot {
+        fn autosave(&mut self, data: &String, _env: &Env) -> Option<Vec<u8>> {
+            Some(data.as_bytes().to_vec())
+        }
+    }
+
+    #[test]
+    fn autosave_survives_restart() {
+        let path = std::env::temp_dir().join(format!("druid-autosave-{}", std::process::id()));
+        let interval = Duration::from_secs(30);
+        let autosave = Autosave::new(path.clone(), interval);
+        autosave.clear();
+
+        let state = AppState::new(
+            String::from("unsaved work"),
+            theme::init(),
+            Some(Box::new(Snapshot)),
+            ExtEventHost::new(),
+        );
+        state.borrow_mut().autosave = Some(autosave.clone());
+        state.borrow_mut().run_autosave(WindowId::next());
+
+        // the process "crashes" here, without clearing the file; on the next
+        // launch the launcher offers the saved bytes.
+        drop(state);
+        let launcher = AppLauncher::<String>::with_window(WindowDesc::new(|| Label::new("")))
+            .autosave(&path, interval);
+        assert_eq!(
+            launcher.restore_from_crash(),
+            Some(b"unsaved work".to_vec())
+        );
+
+        // after a clean exit there is nothing to recover.
+        autosave.clear();
+        assert_eq!(launcher.restore_from_crash(), None);
+    }
+}
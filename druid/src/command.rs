@@ -14,20 +14,36 @@
 
 //! Custom commands.
 
-use std::any::Any;
+use std::any::{Any, TypeId};
+use std::collections::VecDeque;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use crate::{WidgetId, WindowId};
 
+/// The unique string that identifies a [`Selector`].
+///
+/// [`Selector`]: struct.Selector.html
+pub type SelectorSymbol = &'static str;
+
 /// An identifier for a particular command.
 ///
 /// This should be a unique string identifier. Certain `Selector`s are defined
 /// by druid, and have special meaning to the framework; these are listed in the
 /// [`druid::commands`] module.
 ///
+/// The type parameter is the type of the argument carried by commands with
+/// this selector, so that a [`Command`] can only be created with an argument
+/// of the right type, and the argument can be retrieved with [`Command::get`]
+/// without the risk of a type mismatch. Selectors for commands without an
+/// argument use the default, `Selector<()>`.
+///
 /// [`druid::commands`]: commands/index.html
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct Selector(&'static str);
+/// [`Command`]: struct.Command.html
+/// [`Command::get`]: struct.Command.html#method.get
+pub struct Selector<T = ()>(SelectorSymbol, PhantomData<fn() -> T>);
 
 /// An arbitrary command.
 ///
@@ -40,7 +56,7 @@ pub struct Selector(&'static str);
 /// Commands come in two varieties, 'reusable' and 'one-shot'.
 ///
 /// Regular commands are created with [`Command::new`], and their argument
-/// objects may be accessed repeatedly, via [`Command::get_object`].
+/// objects may be accessed repeatedly, via [`Command::get`].
 ///
 /// One-shot commands are intended for cases where an object should only be
 /// used once; an example would be if you have some resource that cannot be
@@ -50,17 +66,27 @@ pub struct Selector(&'static str);
 /// ```
 /// use druid::{Command, Selector};
 ///
-/// let selector = Selector::new("process_rows");
+/// let selector: Selector<Vec<i32>> = Selector::new("process_rows");
 /// let rows = vec![1, 3, 10, 12];
 /// let command = Command::new(selector, rows);
 ///
-/// assert_eq!(command.get_object(), Ok(&vec![1, 3, 10, 12]));
+/// assert_eq!(command.get(selector), Some(&vec![1, 3, 10, 12]));
 /// ```
 #[derive(Debug, Clone)]
 pub struct Command {
-    /// The command's `Selector`.
-    pub selector: Selector,
+    symbol: SelectorSymbol,
     object: Option<Arg>,
+    pub(crate) retry: Option<Retry>,
+    pub(crate) urgent: bool,
+    pub(crate) replace_pending: bool,
+}
+
+/// How often, and how quickly, an unhandled command is delivered again.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Retry {
+    /// The number of further attempts that will be made.
+    pub(crate) remaining: u32,
+    pub(crate) delay: Duration,
 }
 
 #[derive(Debug, Clone)]
@@ -82,23 +108,65 @@ pub enum ArgumentError {
     Consumed,
 }
 
+/// The error returned when a window or a menu is submitted for a different
+/// type of data than the application's.
+///
+/// The arguments of [`NEW_WINDOW`], [`SHOW_MODAL`], [`SET_MENU`] and
+/// [`SHOW_CONTEXT_MENU`] can hold a window or a menu for any type of data,
+/// so this is checked when the command is submitted.
+///
+/// [`NEW_WINDOW`]: commands/constant.NEW_WINDOW.html
+/// [`SHOW_MODAL`]: commands/constant.SHOW_MODAL.html
+/// [`SET_MENU`]: commands/constant.SET_MENU.html
+/// [`SHOW_CONTEXT_MENU`]: commands/constant.SHOW_CONTEXT_MENU.html
+#[derive(Debug, Clone, PartialEq)]
+pub struct WrongDataType {
+    expected: &'static str,
+    found: &'static str,
+}
+
+/// The type of the application's data.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct DataType {
+    id: TypeId,
+    name: &'static str,
+}
+
 /// The target of a command.
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Target {
     /// The target is a window; the event will be delivered to all
     /// widgets in that window.
     Window(WindowId),
     /// The target is a specific widget.
     Widget(WidgetId),
+    /// The target is a widget and all of its descendants; the event will
+    /// not be delivered anywhere else in the window.
+    Subtree(WidgetId),
+    /// The target is every window; the event will be delivered to all
+    /// widgets in all windows, even if an earlier window handled it.
+    Global,
 }
 
 /// [`Command`]s with special meaning, defined by druid.
 ///
 /// [`Command`]: struct.Command.html
 pub mod sys {
-    use super::Selector;
+    use super::{Selector, Target};
+    use crate::kurbo::{Point, Rect, Size};
+    use crate::overlay::Overlay;
+    use crate::{
+        AccessNode, AnyContextMenu, AnyMenuDesc, AnyWindowDesc, Env, FileDialogOptions, FileInfo,
+        ModalResult, ModalValue, WidgetBounds, WidgetId, WindowId,
+    };
 
     /// Quit the running application. This command is handled by the druid library.
+    ///
+    /// The [`AppDelegate`] can keep the application running, with
+    /// [`application_should_quit`].
+    ///
+    /// [`AppDelegate`]: ../trait.AppDelegate.html
+    /// [`application_should_quit`]: ../trait.AppDelegate.html#method.application_should_quit
     pub const QUIT_APP: Selector = Selector::new("druid-builtin.quit-app");
 
     /// Hide the application. (mac only?)
@@ -108,28 +176,166 @@ pub mod sys {
     pub const HIDE_OTHERS: Selector = Selector::new("druid-builtin.menu-hide-others");
 
     /// The selector for a command to create a new window.
-    pub const NEW_WINDOW: Selector = Selector::new("druid-builtin.new-window");
-
-    /// The selector for a command to close a window. The command's argument
-    /// should be the id of the window to close.
+    ///
+    /// The argument is the [`WindowDesc`] for the new window, in a one-shot
+    /// command; it is submitted with [`EventCtx::new_window`] or
+    /// [`DelegateCtx::new_window`].
+    ///
+    /// [`WindowDesc`]: ../struct.WindowDesc.html
+    /// [`EventCtx::new_window`]: ../struct.EventCtx.html#method.new_window
+    /// [`DelegateCtx::new_window`]: ../struct.DelegateCtx.html#method.new_window
+    pub const NEW_WINDOW: Selector<AnyWindowDesc> = Selector::new("druid-builtin.new-window");
+
+    /// The selector for a command to close a window. It closes the window the
+    /// command is sent to, so to close another window, target it with
+    /// [`Target::Window`].
+    ///
+    /// A window built with [`hide_on_close`] is hidden instead.
+    ///
+    /// [`Target::Window`]: ../enum.Target.html#variant.Window
+    /// [`hide_on_close`]: ../struct.WindowDesc.html#method.hide_on_close
     pub const CLOSE_WINDOW: Selector = Selector::new("druid-builtin.close-window");
 
     /// The selector for a command to bring a window to the front, and give it focus.
     ///
-    /// The command's argument should be the id of the target window.
-    pub const SHOW_WINDOW: Selector = Selector::new("druid-builtin.show-window");
+    /// The command's argument should be the id of the target window. If the
+    /// window was hidden by closing it (see [`hide_on_close`]), it is shown
+    /// again.
+    ///
+    /// [`hide_on_close`]: ../struct.WindowDesc.html#method.hide_on_close
+    pub const SHOW_WINDOW: Selector<WindowId> = Selector::new("druid-builtin.show-window");
+
+    /// The selector for a command to change the title of a window.
+    ///
+    /// The argument is the new title. It replaces the title the window was
+    /// created with, which is no longer updated when the data changes.
+    pub const SET_WINDOW_TITLE: Selector<String> = Selector::new("druid-builtin.set-window-title");
+
+    /// The selector for a command to replace the application's [`Env`], for
+    /// instance to switch between a light and a dark theme.
+    ///
+    /// The argument is the new environment; it is usually the result of
+    /// [`theme::init`], with some values changed. Every widget in every
+    /// window receives [`LifeCycle::ThemeChanged`], and is then updated,
+    /// laid out and painted with the new values.
+    ///
+    /// [`Env`]: ../struct.Env.html
+    /// [`theme::init`]: ../theme/fn.init.html
+    /// [`LifeCycle::ThemeChanged`]: ../enum.LifeCycle.html#variant.ThemeChanged
+    pub const SET_THEME: Selector<Env> = Selector::new("druid-builtin.set-theme");
+
+    /// The selector for a command to change the application's locale.
+    ///
+    /// The argument is a BCP47 language tag, such as `"fr-CA"`; it is
+    /// resolved against the available localizations, falling back to
+    /// `en-US`. Like [`SET_THEME`], every widget in every window receives
+    /// [`LifeCycle::ThemeChanged`] and is updated, so that each
+    /// [`LocalizedString`] is resolved again.
+    ///
+    /// [`SET_THEME`]: constant.SET_THEME.html
+    /// [`LifeCycle::ThemeChanged`]: ../enum.LifeCycle.html#variant.ThemeChanged
+    /// [`LocalizedString`]: ../struct.LocalizedString.html
+    pub const SET_LOCALE: Selector<String> = Selector::new("druid-builtin.set-locale");
+
+    /// The selector for a command to resize a window.
+    ///
+    /// The argument is the new size of the window's frame, in px.
+    pub const SET_WINDOW_SIZE: Selector<Size> = Selector::new("druid-builtin.set-window-size");
+
+    /// The selector for a command to move a window.
+    ///
+    /// The argument is the new position of the window's top-left corner, in
+    /// screen coordinates.
+    pub const SET_WINDOW_POSITION: Selector<Point> =
+        Selector::new("druid-builtin.set-window-position");
 
-    /// Display a context (right-click) menu. The argument must be the [`ContextMenu`].
-    /// object to be displayed.
+    /// The selector for a command to minimize a window.
+    pub const MINIMIZE_WINDOW: Selector = Selector::new("druid-builtin.minimize-window");
+
+    /// The selector for a command to maximize a window.
+    pub const MAXIMIZE_WINDOW: Selector = Selector::new("druid-builtin.maximize-window");
+
+    /// The selector for a command to restore a minimized or maximized window
+    /// to its normal size.
+    pub const RESTORE_WINDOW: Selector = Selector::new("druid-builtin.restore-window");
+
+    /// The selector for a command that asks a [`Scroll`] to scroll so that
+    /// a region of its content is visible.
+    ///
+    /// The argument is the region, in the coordinate space of the scroll's
+    /// child. The command should be targeted at the `Scroll`'s [`WidgetId`];
+    /// if the region is larger than the viewport, its top-left corner is
+    /// shown.
+    ///
+    /// [`Scroll`]: ../widget/struct.Scroll.html
+    /// [`WidgetId`]: ../struct.WidgetId.html
+    pub const SCROLL_TO_VIEW: Selector<Rect> = Selector::new("druid-builtin.scroll-to-view");
+
+    /// Display a context (right-click) menu. The argument is the
+    /// [`ContextMenu`] to be displayed; it is submitted with
+    /// [`EventCtx::show_context_menu`].
     ///
     /// [`ContextMenu`]: ../struct.ContextMenu.html
-    pub const SHOW_CONTEXT_MENU: Selector = Selector::new("druid-builtin.show-context-menu");
+    /// [`EventCtx::show_context_menu`]: ../struct.EventCtx.html#method.show_context_menu
+    pub const SHOW_CONTEXT_MENU: Selector<AnyContextMenu> =
+        Selector::new("druid-builtin.show-context-menu");
+
+    /// Show an overlay in the target window. The argument is taken with
+    /// [`Command::take`]; widgets use [`EventCtx::show_overlay`].
+    ///
+    /// [`Command::take`]: ../struct.Command.html#method.take
+    /// [`EventCtx::show_overlay`]: ../struct.EventCtx.html#method.show_overlay
+    pub(crate) const SHOW_OVERLAY: Selector<Overlay> = Selector::new("druid-builtin.show-overlay");
+
+    /// Dismiss the overlay of the widget with this id, and those above it.
+    pub(crate) const DISMISS_OVERLAY: Selector<WidgetId> =
+        Selector::new("druid-builtin.dismiss-overlay");
+
+    /// Sent to a widget when its overlay has been dismissed, whether by a
+    /// click outside of it, by `Escape`, or by [`EventCtx::dismiss_overlay`].
+    ///
+    /// [`EventCtx::dismiss_overlay`]: ../struct.EventCtx.html#method.dismiss_overlay
+    pub const OVERLAY_DISMISSED: Selector = Selector::new("druid-builtin.overlay-dismissed");
 
-    /// The selector for a command to set the window's menu. The argument should
-    /// be a [`MenuDesc`] object.
+    /// The selector for a command to set the window's menu. The argument is a
+    /// [`MenuDesc`]; it is submitted with [`EventCtx::set_menu`] or
+    /// [`DelegateCtx::set_menu`].
     ///
     /// [`MenuDesc`]: ../struct.MenuDesc.html
-    pub const SET_MENU: Selector = Selector::new("druid-builtin.set-menu");
+    /// [`EventCtx::set_menu`]: ../struct.EventCtx.html#method.set_menu
+    /// [`DelegateCtx::set_menu`]: ../struct.DelegateCtx.html#method.set_menu
+    pub const SET_MENU: Selector<AnyMenuDesc> = Selector::new("druid-builtin.set-menu");
+
+    /// The selector for a command to open a modal window.
+    ///
+    /// The argument is the [`WindowDesc`] for the modal window, in a one-shot
+    /// command; it is submitted with [`EventCtx::show_modal`]. The window is
+    /// shown, and a nested event loop runs until it is dismissed; while it
+    /// runs, input to all other windows is ignored. The window that submitted
+    /// this command is then sent a [`MODAL_RESULT`] command.
+    ///
+    /// [`WindowDesc`]: ../struct.WindowDesc.html
+    /// [`EventCtx::show_modal`]: ../struct.EventCtx.html#method.show_modal
+    /// [`MODAL_RESULT`]: constant.MODAL_RESULT.html
+    pub const SHOW_MODAL: Selector<AnyWindowDesc> = Selector::new("druid-builtin.show-modal");
+
+    /// Dismiss the modal window this command is submitted to, and close it.
+    ///
+    /// This command is delivered to the window that opened the modal, as the
+    /// [`ModalResult::Ended`] argument of a [`MODAL_RESULT`] command; its
+    /// [`ModalValue`] can hold any value, to pass back to that window.
+    ///
+    /// [`ModalResult::Ended`]: ../enum.ModalResult.html#variant.Ended
+    /// [`MODAL_RESULT`]: constant.MODAL_RESULT.html
+    /// [`ModalValue`]: ../struct.ModalValue.html
+    pub const END_MODAL: Selector<ModalValue> = Selector::new("druid-builtin.end-modal");
+
+    /// Sent to the window that submitted [`SHOW_MODAL`], once the modal
+    /// window has been dismissed. The argument is a [`ModalResult`].
+    ///
+    /// [`SHOW_MODAL`]: constant.SHOW_MODAL.html
+    /// [`ModalResult`]: ../enum.ModalResult.html
+    pub const MODAL_RESULT: Selector<ModalResult> = Selector::new("druid-builtin.modal-result");
 
     /// Show the application preferences.
     pub const SHOW_PREFERENCES: Selector = Selector::new("druid-builtin.menu-show-preferences");
@@ -144,33 +350,51 @@ pub mod sys {
     pub const NEW_FILE: Selector = Selector::new("druid-builtin.menu-file-new");
 
     /// System command. A file picker dialog will be shown to the user, and an
-    /// `OPEN_FILE` command will be sent if a file is chosen.
+    /// `OPEN_FILE` command will be sent if a file is chosen, or an
+    /// `OPEN_PANEL_CANCELLED` command if the dialog is dismissed.
+    ///
+    /// The dialog does not block: the command returns immediately, and the
+    /// result arrives later, targeted at the window that issued this command.
     ///
     /// The argument should be a [`FileDialogOptions`] struct.
     ///
     /// [`FileDialogOptions`]: struct.FileDialogOptions.html
-    pub const SHOW_OPEN_PANEL: Selector = Selector::new("druid-builtin.menu-file-open");
+    pub const SHOW_OPEN_PANEL: Selector<FileDialogOptions> =
+        Selector::new("druid-builtin.menu-file-open");
 
     /// Open a file.
     ///
     /// The argument must be a [`FileInfo`] object for the file to be opened.
     ///
     /// [`FileInfo`]: struct.FileInfo.html
-    pub const OPEN_FILE: Selector = Selector::new("druid-builtin.open-file-path");
+    pub const OPEN_FILE: Selector<FileInfo> = Selector::new("druid-builtin.open-file-path");
+
+    /// Sent when the panel shown by `SHOW_OPEN_PANEL` is dismissed without
+    /// choosing a file. It has no argument.
+    pub const OPEN_PANEL_CANCELLED: Selector = Selector::new("druid-builtin.open-panel-cancelled");
 
     /// Special command. When issued, the system will show the 'save as' panel,
     /// and if a path is selected the system will issue a `SAVE_FILE` command
-    /// with the selected path as the argument.
+    /// with the selected path as the argument; otherwise it will issue a
+    /// `SAVE_PANEL_CANCELLED` command.
+    ///
+    /// Like `SHOW_OPEN_PANEL`, this does not block.
     ///
     /// The argument should be a [`FileDialogOptions`] object.
     ///
     /// [`FileDialogOptions`]: struct.FileDialogOptions.html
-    pub const SHOW_SAVE_PANEL: Selector = Selector::new("druid-builtin.menu-file-save-as");
+    pub const SHOW_SAVE_PANEL: Selector<FileDialogOptions> =
+        Selector::new("druid-builtin.menu-file-save-as");
 
     /// Save the current file.
     ///
-    /// The argument, if present, should be the path where the file should be saved.
-    pub const SAVE_FILE: Selector = Selector::new("druid-builtin.menu-file-save");
+    /// The argument is the path where the file should be saved, or `None` to
+    /// save it where it was last saved.
+    pub const SAVE_FILE: Selector<Option<FileInfo>> = Selector::new("druid-builtin.menu-file-save");
+
+    /// Sent when the panel shown by `SHOW_SAVE_PANEL` is dismissed without
+    /// choosing a path. It has no argument.
+    pub const SAVE_PANEL_CANCELLED: Selector = Selector::new("druid-builtin.save-panel-cancelled");
 
     /// Show the print-setup window.
     pub const PRINT_SETUP: Selector = Selector::new("druid-builtin.menu-file-print-setup");
@@ -182,9 +406,17 @@ pub mod sys {
     pub const PRINT_PREVIEW: Selector = Selector::new("druid-builtin.menu-file-print");
 
     /// Cut the current selection.
+    ///
+    /// The focused widget receives an [`Event::Cut`].
+    ///
+    /// [`Event::Cut`]: ../enum.Event.html#variant.Cut
     pub const CUT: Selector = Selector::new("druid-builtin.menu-cut");
 
     /// Copy the current selection.
+    ///
+    /// The focused widget receives an [`Event::Copy`].
+    ///
+    /// [`Event::Copy`]: ../enum.Event.html#variant.Copy
     pub const COPY: Selector = Selector::new("druid-builtin.menu-copy");
 
     /// Paste.
@@ -195,25 +427,122 @@ pub mod sys {
 
     /// Redo.
     pub const REDO: Selector = Selector::new("druid-builtin.menu-redo");
+
+    /// Collect the bounds of every widget in the target window.
+    ///
+    /// The window replies with a [`WIDGET_BOUNDS`] command, sent to the
+    /// [`Target`] given as the argument, or to the window itself if the
+    /// argument is `None`.
+    ///
+    /// [`WIDGET_BOUNDS`]: constant.WIDGET_BOUNDS.html
+    /// [`Target`]: ../enum.Target.html
+    pub const COLLECT_WIDGET_BOUNDS: Selector<Option<Target>> =
+        Selector::new("druid-builtin.collect-widget-bounds");
+
+    /// The reply to [`COLLECT_WIDGET_BOUNDS`].
+    ///
+    /// The argument is a `Vec<`[`WidgetBounds`]`>`, with an entry for every
+    /// widget in the window, in tree order, reflecting the last layout.
+    ///
+    /// [`COLLECT_WIDGET_BOUNDS`]: constant.COLLECT_WIDGET_BOUNDS.html
+    /// [`WidgetBounds`]: ../struct.WidgetBounds.html
+    pub const WIDGET_BOUNDS: Selector<Vec<WidgetBounds>> =
+        Selector::new("druid-builtin.widget-bounds");
+
+    /// Show or hide the widget inspector overlay of the target window.
+    ///
+    /// The inspector outlines the layout rect of each widget, and labels the
+    /// widget under the mouse with its type, id and size. It is shown from
+    /// the start in every window if the app was launched with
+    /// [`AppLauncher::debug_inspector`].
+    ///
+    /// [`AppLauncher::debug_inspector`]: ../struct.AppLauncher.html#method.debug_inspector
+    pub const TOGGLE_INSPECTOR: Selector = Selector::new("druid-builtin.toggle-inspector");
+
+    /// Collect a description of the target window for assistive technology.
+    ///
+    /// The window replies with an [`ACCESSIBILITY_TREE`] command, sent to the
+    /// [`Target`] given as the argument, or to the window itself if the
+    /// argument is `None`.
+    ///
+    /// [`ACCESSIBILITY_TREE`]: constant.ACCESSIBILITY_TREE.html
+    /// [`Target`]: ../enum.Target.html
+    pub const COLLECT_ACCESSIBILITY_TREE: Selector<Option<Target>> =
+        Selector::new("druid-builtin.collect-accessibility-tree");
+
+    /// The reply to [`COLLECT_ACCESSIBILITY_TREE`].
+    ///
+    /// The argument is the [`AccessNode`] of the root widget.
+    ///
+    /// [`COLLECT_ACCESSIBILITY_TREE`]: constant.COLLECT_ACCESSIBILITY_TREE.html
+    /// [`AccessNode`]: ../struct.AccessNode.html
+    pub const ACCESSIBILITY_TREE: Selector<AccessNode> =
+        Selector::new("druid-builtin.accessibility-tree");
+
+    /// Give keyboard focus to a widget in the target window.
+    ///
+    /// The argument is the [`WidgetId`] of the widget. It gets
+    /// [`FocusChanged(true)`], and the widget that had focus gets
+    /// `FocusChanged(false)`. A widget that is not in the window is ignored,
+    /// with a warning. A widget can focus itself with
+    /// [`EventCtx::request_focus`].
+    ///
+    /// [`WidgetId`]: ../struct.WidgetId.html
+    /// [`FocusChanged(true)`]: ../enum.LifeCycle.html#variant.FocusChanged
+    /// [`EventCtx::request_focus`]: ../struct.EventCtx.html#method.request_focus
+    pub const FOCUS_WIDGET: Selector<WidgetId> = Selector::new("druid-builtin.focus-widget");
+
+    /// Cancel the animation frame requests of a widget in the target window.
+    ///
+    /// The argument is the [`WidgetId`] of the widget; if it is `None`,
+    /// every animation in the window is cancelled. The widget will
+    /// not receive another [`AnimFrame`] unless it requests one again.
+    ///
+    /// [`WidgetId`]: ../struct.WidgetId.html
+    /// [`AnimFrame`]: ../enum.LifeCycle.html#variant.AnimFrame
+    pub const CANCEL_ANIMATION: Selector<Option<WidgetId>> =
+        Selector::new("druid-builtin.cancel-animation");
 }
 
 impl Selector {
     /// A selector that does nothing.
     pub const NOOP: Selector = Selector::new("");
+}
 
+impl<T> Selector<T> {
     /// Create a new `Selector` with the given string.
-    pub const fn new(s: &'static str) -> Selector {
-        Selector(s)
+    pub const fn new(s: &'static str) -> Selector<T> {
+        Selector(s, PhantomData)
+    }
+
+    /// The string that identifies this selector.
+    pub const fn symbol(self) -> SelectorSymbol {
+        self.0
+    }
+}
+
+impl<T: Any> Selector<T> {
+    /// Create a [`Command`] with this selector and the given argument.
+    ///
+    /// This is a shorthand for [`Command::new`].
+    ///
+    /// [`Command`]: struct.Command.html
+    /// [`Command::new`]: struct.Command.html#method.new
+    pub fn with(self, arg: T) -> Command {
+        Command::new(self, arg)
     }
 }
 
 impl Command {
     /// Create a new `Command` with an argument. If you do not need
-    /// an argument, `Selector` implements `Into<Command>`.
-    pub fn new(selector: Selector, arg: impl Any) -> Self {
+    /// an argument, `Selector<()>` implements `Into<Command>`.
+    pub fn new<T: Any>(selector: Selector<T>, arg: T) -> Self {
         Command {
-            selector,
+            symbol: selector.symbol(),
             object: Some(Arg::Reusable(Arc::new(arg))),
+            retry: None,
+            urgent: false,
+            replace_pending: false,
         }
     }
 
@@ -221,21 +550,149 @@ impl Command {
     ///
     /// Unlike those created with `Command::new`, one-shot commands cannot
     /// be reused; their argument is consumed when it is accessed, via
-    /// [`Command::take_object`].
+    /// [`Command::take`].
     ///
-    /// [`Command::take_object`]: #method.take_object
-    pub fn one_shot(selector: Selector, arg: impl Any) -> Self {
+    /// [`Command::take`]: #method.take
+    pub fn one_shot<T: Any>(selector: Selector<T>, arg: T) -> Self {
         Command {
-            selector,
+            symbol: selector.symbol(),
             object: Some(Arg::OneShot(Arc::new(Mutex::new(Some(Box::new(arg)))))),
+            retry: None,
+            urgent: false,
+            replace_pending: false,
         }
     }
 
     /// Used to create a command from the types sent via an `ExtEventSink`.
-    pub(crate) fn from_ext(selector: Selector, object: Option<Box<dyn Any + Send>>) -> Self {
+    pub(crate) fn from_ext(symbol: SelectorSymbol, object: Option<Box<dyn Any + Send>>) -> Self {
         let object: Option<Box<dyn Any>> = object.map(|obj| obj as Box<dyn Any>);
         let object = object.map(|o| Arg::Reusable(o.into()));
-        Command { selector, object }
+        Command {
+            symbol,
+            object,
+            retry: None,
+            urgent: false,
+            replace_pending: false,
+        }
+    }
+
+    /// Returns `true` if this command has the given selector.
+    pub fn is<T>(&self, selector: Selector<T>) -> bool {
+        self.symbol == selector.symbol()
+    }
+
+    /// The string that identifies this command's selector.
+    pub fn symbol(&self) -> SelectorSymbol {
+        self.symbol
+    }
+
+    /// Return a reference to this command's argument, if it has the given
+    /// selector.
+    ///
+    /// The argument of a command created with this selector always has the
+    /// selector's type. This returns `None` if the command has a different
+    /// selector, or if it is a [`one-shot`] command.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the argument has the wrong type, which can only happen if
+    /// two selectors of different types were created with the same string.
+    ///
+    /// [`one-shot`]: #method.one_shot
+    pub fn get<T: Any>(&self, selector: Selector<T>) -> Option<&T> {
+        if !self.is(selector) {
+            return None;
+        }
+        match self.object.as_ref()? {
+            Arg::Reusable(o) => Some(
+                o.downcast_ref()
+                    .unwrap_or_else(|| panic!("{} has an argument of the wrong type", selector)),
+            ),
+            Arg::OneShot(_) => None,
+        }
+    }
+
+    /// Take the argument of a [`one-shot`] command, if it has the given
+    /// selector.
+    ///
+    /// This returns `None` if the command has a different selector, if it is
+    /// not a one-shot command, or if the argument has already been taken.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the argument has the wrong type, which can only happen if
+    /// two selectors of different types were created with the same string.
+    ///
+    /// [`one-shot`]: #method.one_shot
+    pub fn take<T: Any>(&self, selector: Selector<T>) -> Option<T> {
+        if !self.is(selector) {
+            return None;
+        }
+        match self.object.as_ref()? {
+            Arg::OneShot(inner) => {
+                let obj = inner.lock().unwrap().take()?;
+                let obj = obj
+                    .downcast::<T>()
+                    .unwrap_or_else(|_| panic!("{} has an argument of the wrong type", selector));
+                Some(*obj)
+            }
+            Arg::Reusable(_) => None,
+        }
+    }
+
+    /// Deliver this command again if it is not handled.
+    ///
+    /// Some commands can fail transiently, for instance if they are sent to
+    /// a subsystem that is not ready yet. A handler reports failure by *not*
+    /// marking the command as handled (see [`EventCtx::set_handled`]); if no
+    /// one handles it, the command is submitted again after `delay`, up to
+    /// `attempts` more times.
+    ///
+    /// Retries reuse the same argument, so they are not useful for a
+    /// [`one-shot`] command whose argument has already been taken.
+    ///
+    /// [`EventCtx::set_handled`]: struct.EventCtx.html#method.set_handled
+    /// [`one-shot`]: #method.one_shot
+    pub fn retry(mut self, attempts: u32, delay: Duration) -> Self {
+        self.retry = Some(Retry {
+            remaining: attempts,
+            delay,
+        });
+        self
+    }
+
+    /// Deliver this command before any ordinary commands already waiting.
+    ///
+    /// Submitted commands are normally delivered in the order they were
+    /// submitted. An urgent command goes ahead of every command that is not
+    /// urgent; urgent commands are still delivered in order among themselves.
+    pub fn urgent(mut self) -> Self {
+        self.urgent = true;
+        self
+    }
+
+    /// Replace a pending command with the same selector and target, instead
+    /// of queueing this one behind it.
+    ///
+    /// This is useful for commands where only the latest one matters, such as
+    /// a request to refresh some view: submitting it repeatedly before it is
+    /// delivered results in a single delivery, with the latest argument, in
+    /// the place of the earlier command.
+    pub fn replace_pending(mut self) -> Self {
+        self.replace_pending = true;
+        self
+    }
+
+    /// If this command has attempts left, use one up and return the delay
+    /// until it should be delivered again.
+    pub(crate) fn next_retry(&mut self) -> Option<Duration> {
+        match self.retry.as_mut() {
+            Some(retry) if retry.remaining > 0 => {
+                retry.remaining -= 1;
+                Some(retry.delay)
+            }
+            _ => None,
+        }
     }
 
     /// Return a reference to this `Command`'s object, if it has one.
@@ -243,7 +700,11 @@ impl Command {
     /// This only works for 'reusable' commands; it does not work for commands
     /// created with [`Command::one_shot`]
     ///
+    /// Unlike [`Command::get`], this does not check the selector, and fails
+    /// if the type is wrong; prefer `get` where the selector is known.
+    ///
     /// [`Command::one_shot`]: #method.one_shot
+    /// [`Command::get`]: #method.get
     pub fn get_object<T: Any>(&self) -> Result<&T, ArgumentError> {
         match self.object.as_ref() {
             Some(Arg::Reusable(o)) => o.downcast_ref().ok_or(ArgumentError::IncorrectType),
@@ -254,7 +715,12 @@ impl Command {
 
     /// Attempt to take the object of a [`one-shot`] command.
     ///
+    /// Like [`get_object`], this does not check the selector; prefer
+    /// [`Command::take`] where the selector is known.
+    ///
     /// [`one-shot`]: #method.one_shot
+    /// [`get_object`]: #method.get_object
+    /// [`Command::take`]: #method.take
     pub fn take_object<T: Any>(&self) -> Result<Box<T>, ArgumentError> {
         match self.object.as_ref() {
             Some(Arg::Reusable(_)) => Err(ArgumentError::WrongVariant),
@@ -277,21 +743,81 @@ impl Command {
     }
 }
 
+/// Add a command to a queue of pending commands, honoring its
+/// [`urgent`] and [`replace_pending`] flags.
+///
+/// [`urgent`]: struct.Command.html#method.urgent
+/// [`replace_pending`]: struct.Command.html#method.replace_pending
+pub(crate) fn enqueue(queue: &mut VecDeque<(Target, Command)>, target: Target, command: Command) {
+    if command.replace_pending {
+        let pending = queue
+            .iter_mut()
+            .find(|(t, cmd)| *t == target && cmd.symbol == command.symbol);
+        if let Some(pending) = pending {
+            pending.1 = command;
+            return;
+        }
+    }
+    if command.urgent {
+        let idx = queue
+            .iter()
+            .position(|(_, cmd)| !cmd.urgent)
+            .unwrap_or(queue.len());
+        queue.insert(idx, (target, command));
+    } else {
+        queue.push_back((target, command));
+    }
+}
+
 impl From<Selector> for Command {
     fn from(selector: Selector) -> Command {
         Command {
-            selector,
+            symbol: selector.symbol(),
             object: None,
+            retry: None,
+            urgent: false,
+            replace_pending: false,
         }
     }
 }
 
-impl std::fmt::Display for Selector {
+impl<T> std::fmt::Display for Selector<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "Selector('{}')", self.0)
     }
 }
 
+// These are implemented by hand, because the derived impls would require
+// the argument type to implement them as well.
+
+impl<T> std::fmt::Debug for Selector<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_tuple("Selector").field(&self.0).finish()
+    }
+}
+
+impl<T> Clone for Selector<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Selector<T> {}
+
+impl<T> PartialEq for Selector<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T> Eq for Selector<T> {}
+
+impl<T> Hash for Selector<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
 impl std::fmt::Display for ArgumentError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
@@ -309,6 +835,39 @@ impl std::fmt::Display for ArgumentError {
 
 impl std::error::Error for ArgumentError {}
 
+impl DataType {
+    pub(crate) fn of<T: Any>() -> Self {
+        DataType {
+            id: TypeId::of::<T>(),
+            name: std::any::type_name::<T>(),
+        }
+    }
+
+    /// Check that `U` is the application's data.
+    pub(crate) fn check<U: Any>(self) -> Result<(), WrongDataType> {
+        if self.id == TypeId::of::<U>() {
+            Ok(())
+        } else {
+            Err(WrongDataType {
+                expected: self.name,
+                found: std::any::type_name::<U>(),
+            })
+        }
+    }
+}
+
+impl std::fmt::Display for WrongDataType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "expected a window or menu for {}, found one for {}",
+            self.expected, self.found
+        )
+    }
+}
+
+impl std::error::Error for WrongDataType {}
+
 impl From<WindowId> for Target {
     fn from(id: WindowId) -> Target {
         Target::Window(id)
@@ -342,5 +901,64 @@ mod tests {
         let objs = vec![0, 1, 2];
         let command = Command::new(sel, objs);
         assert_eq!(command.get_object(), Ok(&vec![0, 1, 2]));
+        assert_eq!(command.get(sel), Some(&vec![0, 1, 2]));
+    }
+
+    #[test]
+    fn get_checks_selector() {
+        const ROWS: Selector<Vec<i32>> = Selector::new("druid-test.rows");
+        const COLUMNS: Selector<Vec<i32>> = Selector::new("druid-test.columns");
+        let command = ROWS.with(vec![1, 2]);
+        assert!(command.is(ROWS));
+        assert_eq!(command.get(COLUMNS), None);
+        assert_eq!(command.take(ROWS), None);
+
+        let command = Command::one_shot(COLUMNS, vec![3]);
+        assert_eq!(command.get(COLUMNS), None);
+        assert_eq!(command.take(COLUMNS), Some(vec![3]));
+        assert_eq!(command.take(COLUMNS), None);
+    }
+
+    #[test]
+    fn urgent_commands_go_first() {
+        const A: Selector<u32> = Selector::new("druid-test.a");
+        const B: Selector<u32> = Selector::new("druid-test.b");
+        let target = Target::Global;
+        let mut queue = VecDeque::new();
+        enqueue(&mut queue, target, A.with(1));
+        enqueue(&mut queue, target, B.with(2).urgent());
+        enqueue(&mut queue, target, A.with(3));
+        enqueue(&mut queue, target, A.with(4).urgent());
+
+        let order: Vec<u32> = queue
+            .iter()
+            .map(|(_, cmd)| *cmd.get(A).or_else(|| cmd.get(B)).unwrap())
+            .collect();
+        assert_eq!(order, vec![2, 4, 1, 3]);
+    }
+
+    #[test]
+    fn replace_pending_command() {
+        const REFRESH: Selector<u32> = Selector::new("druid-test.refresh");
+        const OTHER: Selector<u32> = Selector::new("druid-test.other");
+        let window = Target::Window(WindowId::next());
+        let mut queue = VecDeque::new();
+        enqueue(&mut queue, window, REFRESH.with(1));
+        enqueue(&mut queue, window, OTHER.with(2));
+        enqueue(
+            &mut queue,
+            Target::Global,
+            REFRESH.with(3).replace_pending(),
+        );
+        enqueue(&mut queue, window, REFRESH.with(4).replace_pending());
+
+        let queued: Vec<(Target, Option<u32>)> = queue
+            .iter()
+            .map(|(target, cmd)| (*target, cmd.get(REFRESH).copied()))
+            .collect();
+        assert_eq!(
+            queued,
+            vec![(window, Some(4)), (window, None), (Target::Global, Some(3))]
+        );
     }
 }
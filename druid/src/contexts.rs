@@ -15,16 +15,20 @@
 //! The context types that are passed into various widget methods.
 
 use std::ops::{Deref, DerefMut};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use log;
 
+use crate::command::{self, DataType, WrongDataType};
 use crate::core::{BaseState, CommandQueue, FocusChange};
+use crate::interval::IntervalTimers;
+use crate::overlay::Overlay;
 use crate::piet::Piet;
 use crate::piet::RenderContext;
 use crate::{
-    Affine, Command, Cursor, Insets, Rect, Size, Target, Text, TimerToken, WidgetId, WinCtx,
-    WindowHandle, WindowId,
+    commands, Affine, AnyContextMenu, AnyMenuDesc, AnyWindowDesc, Command, ContextMenu, Cursor,
+    Data, Insets, MenuDesc, Point, Rect, Size, Target, Text, TimerToken, Widget, WidgetId, WinCtx,
+    WindowDesc, WindowHandle, WindowId,
 };
 
 /// A mutable context provided to event handling methods of widgets.
@@ -37,9 +41,13 @@ pub struct EventCtx<'a, 'b> {
     // Note: there's a bunch of state that's just passed down, might
     // want to group that into a single struct.
     pub(crate) win_ctx: &'a mut dyn WinCtx<'b>,
+    /// The type of the application's data.
+    pub(crate) data_type: DataType,
     pub(crate) cursor: &'a mut Option<Cursor>,
     /// Commands submitted to be run after this event.
     pub(crate) command_queue: &'a mut CommandQueue,
+    /// Recurring timers for this window.
+    pub(crate) interval_timers: &'a mut IntervalTimers,
     pub(crate) window_id: WindowId,
     // TODO: migrate most usage of `WindowHandle` to `WinCtx` instead.
     pub(crate) window: &'a WindowHandle,
@@ -48,6 +56,10 @@ pub struct EventCtx<'a, 'b> {
     pub(crate) had_active: bool,
     pub(crate) is_handled: bool,
     pub(crate) is_root: bool,
+    /// The origin of this widget, in window coordinates.
+    pub(crate) window_origin: Point,
+    /// The time the event is handled at, from the app's clock.
+    pub(crate) now: Instant,
 }
 
 /// A mutable context provided to the [`lifecycle`] method on widgets.
@@ -63,6 +75,8 @@ pub struct LifeCycleCtx<'a> {
     pub(crate) command_queue: &'a mut CommandQueue,
     pub(crate) base_state: &'a mut BaseState,
     pub(crate) window_id: WindowId,
+    /// The origin of this widget, in window coordinates.
+    pub(crate) window_origin: Point,
 }
 
 /// A mutable context provided to data update methods of widgets.
@@ -80,6 +94,8 @@ pub struct UpdateCtx<'a, 'b: 'a> {
     // now keep it super-simple.
     pub(crate) window_id: WindowId,
     pub(crate) base_state: &'a mut BaseState,
+    /// The origin of this widget, in window coordinates.
+    pub(crate) window_origin: Point,
 }
 
 /// A context provided to layout handling methods of widgets.
@@ -91,6 +107,9 @@ pub struct LayoutCtx<'a, 'b: 'a> {
     pub(crate) text_factory: &'a mut Text<'b>,
     pub(crate) paint_insets: Insets,
     pub(crate) window_id: WindowId,
+    pub(crate) scale: f64,
+    /// Set when a widget added children during layout.
+    pub(crate) children_changed: bool,
 }
 
 /// Z-order paint operations with transformations.
@@ -117,6 +136,8 @@ pub struct PaintCtx<'a, 'b: 'a> {
     pub(crate) region: Region,
     pub(crate) base_state: &'a BaseState,
     pub(crate) focus_widget: Option<WidgetId>,
+    /// The size of the window, in display points.
+    pub(crate) window_size: Size,
 }
 
 /// A region of a widget, generally used to describe what needs to be drawn.
@@ -126,17 +147,26 @@ pub struct Region(Rect);
 impl<'a, 'b> EventCtx<'a, 'b> {
     /// Invalidate.
     ///
-    /// Right now, it just invalidates the entire window, but we'll want
-    /// finer grained invalidation before long.
+    /// This invalidates the entire window; when only part of the widget
+    /// changed, [`request_paint_rect`] is cheaper.
+    ///
+    /// [`request_paint_rect`]: #method.request_paint_rect
     pub fn invalidate(&mut self) {
-        // Note: for the current functionality, we could shortcut and just
-        // request an invalidate on the window. But when we do fine-grained
-        // invalidation, we'll want to compute the invalidation region, and
-        // that needs to be propagated (with, likely, special handling for
-        // scrolling).
         self.base_state.needs_inval = true;
     }
 
+    /// Request that part of the widget be repainted.
+    ///
+    /// `rect` is in the widget's coordinate space. Unlike [`invalidate`],
+    /// this lets platforms that support it repaint only the part of the
+    /// window that changed, as when a caret blinks.
+    ///
+    /// [`invalidate`]: #method.invalidate
+    pub fn request_paint_rect(&mut self, rect: Rect) {
+        let rect = rect + self.window_origin.to_vec2();
+        self.base_state.invalid.add_rect(rect);
+    }
+
     /// Indicate that your children have changed.
     ///
     /// Widgets must call this method after adding a new child.
@@ -161,17 +191,42 @@ impl<'a, 'b> EventCtx<'a, 'b> {
     /// event handler, but can also be called in response to other events,
     /// for example pressing a key to change the behavior of a widget.
     ///
-    /// [`MouseMoved`]: enum.Event.html#variant.MouseDown
+    /// Every mouse move starts from the arrow cursor, so a widget does not
+    /// need to restore it: once the pointer moves off the widget, and the
+    /// widget no longer sets the cursor, the arrow comes back by itself.
+    ///
+    /// [`MouseMoved`]: enum.Event.html#variant.MouseMoved
     pub fn set_cursor(&mut self, cursor: &Cursor) {
         *self.cursor = Some(cursor.clone());
     }
 
+    /// Tell the input method where the caret is.
+    ///
+    /// `rect` is in the widget's coordinate space. The input method places
+    /// its candidate window next to it, so a widget that accepts
+    /// [`ImeComposition`] events should call this whenever it gains focus or
+    /// its caret moves.
+    ///
+    /// [`ImeComposition`]: enum.Event.html#variant.ImeComposition
+    pub fn set_ime_cursor_area(&mut self, rect: Rect) {
+        let rect = rect + self.window_origin.to_vec2();
+        self.win_ctx.set_ime_cursor_area(rect);
+    }
+
     /// Set the "active" state of the widget.
     ///
+    /// An active widget captures the mouse: until it calls
+    /// `set_active(false)` it receives every mouse event, including the
+    /// `MouseUp` that ends a drag, wherever the pointer goes, ahead of
+    /// hit-testing and of the window's [`mouse_router`]. While a button is
+    /// held, the platform keeps reporting the pointer after it leaves the
+    /// window.
+    ///
     /// See [`EventCtx::is_active`](struct.EventCtx.html#method.is_active).
+    ///
+    /// [`mouse_router`]: struct.WindowDesc.html#method.mouse_router
     pub fn set_active(&mut self, active: bool) {
         self.base_state.is_active = active;
-        // TODO: plumb mouse grab through to platform (through druid-shell)
     }
 
     /// The "hot" (aka hover) status of a widget.
@@ -253,9 +308,11 @@ impl<'a, 'b> EventCtx<'a, 'b> {
 
     /// Request keyboard focus.
     ///
-    /// See [`has_focus`] for more information.
+    /// See [`has_focus`] for more information. To focus another widget,
+    /// submit a [`FOCUS_WIDGET`] command with its id.
     ///
     /// [`has_focus`]: struct.EventCtx.html#method.has_focus
+    /// [`FOCUS_WIDGET`]: commands/constant.FOCUS_WIDGET.html
     pub fn request_focus(&mut self) {
         self.base_state.request_focus = Some(FocusChange::Focus(self.widget_id()));
     }
@@ -295,17 +352,50 @@ impl<'a, 'b> EventCtx<'a, 'b> {
 
     /// Request an animation frame.
     pub fn request_anim_frame(&mut self) {
-        self.base_state.request_anim = true;
+        let id = self.base_state.id;
+        self.base_state.add_anim_request(id);
         self.base_state.needs_inval = true;
     }
 
     /// Request a timer event.
     ///
     /// The return value is a token, which can be used to associate the
-    /// request with the event.
+    /// request with the event, or to cancel it with [`cancel_timer`].
+    ///
+    /// [`cancel_timer`]: #method.cancel_timer
     pub fn request_timer(&mut self, deadline: Instant) -> TimerToken {
         self.base_state.request_timer = true;
-        self.win_ctx.request_timer(deadline)
+        let token = self.win_ctx.request_timer(deadline);
+        self.interval_timers.track_once(token);
+        token
+    }
+
+    /// Request a recurring timer event.
+    ///
+    /// This widget will receive an [`Event::Timer`] with the returned token
+    /// every `interval`, without needing to request a new timer each time,
+    /// until the timer is cancelled with [`cancel_timer`].
+    ///
+    /// [`Event::Timer`]: enum.Event.html#variant.Timer
+    /// [`cancel_timer`]: #method.cancel_timer
+    pub fn request_interval_timer(&mut self, interval: Duration) -> TimerToken {
+        self.base_state.request_timer = true;
+        self.interval_timers.request(interval, self.now)
+    }
+
+    /// Cancel a timer requested with [`request_timer`] or
+    /// [`request_interval_timer`].
+    ///
+    /// No [`Event::Timer`] is delivered for the token after this call.
+    /// Timers are also cancelled when their window is closed.
+    ///
+    /// [`request_timer`]: #method.request_timer
+    /// [`request_interval_timer`]: #method.request_interval_timer
+    /// [`Event::Timer`]: enum.Event.html#variant.Timer
+    pub fn cancel_timer(&mut self, token: TimerToken) {
+        if !self.interval_timers.cancel(token) {
+            log::warn!("cancel_timer called with unknown token {:?}", token);
+        }
     }
 
     /// The layout size.
@@ -323,11 +413,14 @@ impl<'a, 'b> EventCtx<'a, 'b> {
 
     /// Submit a [`Command`] to be run after this event is handled.
     ///
-    /// Commands are run in the order they are submitted; all commands
-    /// submitted during the handling of an event are executed before
-    /// the [`update()`] method is called.
+    /// Commands are run in the order they are submitted, unless they are
+    /// [`urgent`] or [`replace_pending`]; all commands submitted during the
+    /// handling of an event are executed before the [`update()`] method is
+    /// called.
     ///
     /// [`Command`]: struct.Command.html
+    /// [`urgent`]: struct.Command.html#method.urgent
+    /// [`replace_pending`]: struct.Command.html#method.replace_pending
     /// [`update()`]: trait.Widget.html#tymethod.update
     pub fn submit_command(
         &mut self,
@@ -335,7 +428,95 @@ impl<'a, 'b> EventCtx<'a, 'b> {
         target: impl Into<Option<Target>>,
     ) {
         let target = target.into().unwrap_or_else(|| self.window_id.into());
-        self.command_queue.push_back((target, command.into()))
+        command::enqueue(self.command_queue, target, command.into())
+    }
+
+    /// Create a new window.
+    ///
+    /// This submits a [`NEW_WINDOW`] command; the window is created after
+    /// this event is handled. It is an error if the window is not for the
+    /// application's data.
+    ///
+    /// [`NEW_WINDOW`]: commands/constant.NEW_WINDOW.html
+    pub fn new_window<T: Data>(&mut self, desc: WindowDesc<T>) -> Result<(), WrongDataType> {
+        self.data_type.check::<T>()?;
+        let desc = AnyWindowDesc::new(desc);
+        self.submit_command(Command::one_shot(commands::NEW_WINDOW, desc), None);
+        Ok(())
+    }
+
+    /// Open a modal window.
+    ///
+    /// This submits a [`SHOW_MODAL`] command; this widget's window is sent
+    /// the [`MODAL_RESULT`] once the modal window is dismissed. It is an
+    /// error if the window is not for the application's data.
+    ///
+    /// [`SHOW_MODAL`]: commands/constant.SHOW_MODAL.html
+    /// [`MODAL_RESULT`]: commands/constant.MODAL_RESULT.html
+    pub fn show_modal<T: Data>(&mut self, desc: WindowDesc<T>) -> Result<(), WrongDataType> {
+        self.data_type.check::<T>()?;
+        let desc = AnyWindowDesc::new(desc);
+        self.submit_command(Command::one_shot(commands::SHOW_MODAL, desc), None);
+        Ok(())
+    }
+
+    /// Set the menu of this widget's window.
+    ///
+    /// It is an error if the menu is not for the application's data.
+    pub fn set_menu<T: Data>(&mut self, menu: MenuDesc<T>) -> Result<(), WrongDataType> {
+        self.data_type.check::<T>()?;
+        let menu = AnyMenuDesc::new(menu);
+        self.submit_command(Command::new(commands::SET_MENU, menu), None);
+        Ok(())
+    }
+
+    /// Show a context menu in this widget's window.
+    ///
+    /// `location` is in window coordinates; to show the menu under the
+    /// cursor, pass the [`window_pos`] of the mouse event that asked for it.
+    /// The menu is shown once the current event has been handled. It is an
+    /// error if the menu is not for the application's data.
+    ///
+    /// [`window_pos`]: struct.MouseEvent.html#structfield.window_pos
+    pub fn show_context_menu<T: Data>(
+        &mut self,
+        menu: MenuDesc<T>,
+        location: Point,
+    ) -> Result<(), WrongDataType> {
+        self.data_type.check::<T>()?;
+        let menu = AnyContextMenu::new(ContextMenu::new(menu, location));
+        self.submit_command(Command::new(commands::SHOW_CONTEXT_MENU, menu), None);
+        Ok(())
+    }
+
+    /// Show `widget` in an overlay, above the content of this widget's window.
+    ///
+    /// The overlay gets the mouse events over it, and key events, before
+    /// the rest of the window. It is dismissed by a click outside of it, by
+    /// an `Escape` it doesn't handle, or by [`dismiss_overlay`]; this widget
+    /// then gets an [`OVERLAY_DISMISSED`] command.
+    ///
+    /// `origin` is in this widget's coordinates; the overlay is moved, if
+    /// need be, to fit in the window. The overlay has no data, so its
+    /// widgets report back to this one with commands. A widget has at most
+    /// one overlay; showing another replaces it.
+    ///
+    /// [`dismiss_overlay`]: #method.dismiss_overlay
+    /// [`OVERLAY_DISMISSED`]: commands/constant.OVERLAY_DISMISSED.html
+    pub fn show_overlay(&mut self, widget: impl Widget<()> + 'static, origin: Point) {
+        let overlay = Overlay {
+            owner: self.widget_id(),
+            origin: origin + self.window_origin.to_vec2(),
+            widget: Box::new(widget),
+        };
+        let cmd = Command::one_shot(commands::SHOW_OVERLAY, overlay);
+        self.submit_command(cmd, None);
+    }
+
+    /// Dismiss the overlay shown by this widget, if any.
+    pub fn dismiss_overlay(&mut self) {
+        let cmd = Command::new(commands::DISMISS_OVERLAY, self.widget_id());
+        self.submit_command(cmd, None);
     }
 
     /// Get the window id.
@@ -353,6 +534,7 @@ impl<'a, 'b> EventCtx<'a, 'b> {
             command_queue: self.command_queue,
             base_state: self.base_state,
             window_id: self.window_id,
+            window_origin: self.window_origin,
         }
     }
 }
@@ -366,6 +548,16 @@ impl<'a> LifeCycleCtx<'a> {
         self.base_state.needs_inval = true;
     }
 
+    /// Request that part of the widget be repainted.
+    ///
+    /// See [`EventCtx::request_paint_rect`] for more discussion.
+    ///
+    /// [`EventCtx::request_paint_rect`]: struct.EventCtx.html#method.request_paint_rect
+    pub fn request_paint_rect(&mut self, rect: Rect) {
+        let rect = rect + self.window_origin.to_vec2();
+        self.base_state.invalid.add_rect(rect);
+    }
+
     /// Returns the current widget's `WidgetId`.
     pub fn widget_id(&self) -> WidgetId {
         self.base_state.id
@@ -395,16 +587,20 @@ impl<'a> LifeCycleCtx<'a> {
 
     /// Request an animation frame.
     pub fn request_anim_frame(&mut self) {
-        self.base_state.request_anim = true;
+        let id = self.base_state.id;
+        self.base_state.add_anim_request(id);
     }
 
     /// Submit a [`Command`] to be run after this event is handled.
     ///
-    /// Commands are run in the order they are submitted; all commands
-    /// submitted during the handling of an event are executed before
-    /// the [`update()`] method is called.
+    /// Commands are run in the order they are submitted, unless they are
+    /// [`urgent`] or [`replace_pending`]; all commands submitted during the
+    /// handling of an event are executed before the [`update()`] method is
+    /// called.
     ///
     /// [`Command`]: struct.Command.html
+    /// [`urgent`]: struct.Command.html#method.urgent
+    /// [`replace_pending`]: struct.Command.html#method.replace_pending
     /// [`update()`]: trait.Widget.html#tymethod.update
     pub fn submit_command(
         &mut self,
@@ -412,7 +608,7 @@ impl<'a> LifeCycleCtx<'a> {
         target: impl Into<Option<Target>>,
     ) {
         let target = target.into().unwrap_or_else(|| self.window_id.into());
-        self.command_queue.push_back((target, command.into()))
+        command::enqueue(self.command_queue, target, command.into())
     }
 }
 
@@ -425,6 +621,16 @@ impl<'a, 'b> UpdateCtx<'a, 'b> {
         self.base_state.needs_inval = true;
     }
 
+    /// Request that part of the widget be repainted.
+    ///
+    /// See [`EventCtx::request_paint_rect`] for more discussion.
+    ///
+    /// [`EventCtx::request_paint_rect`]: struct.EventCtx.html#method.request_paint_rect
+    pub fn request_paint_rect(&mut self, rect: Rect) {
+        let rect = rect + self.window_origin.to_vec2();
+        self.base_state.invalid.add_rect(rect);
+    }
+
     /// Indicate that your children have changed.
     ///
     /// Widgets must call this method after adding a new child.
@@ -469,6 +675,30 @@ impl<'a, 'b> LayoutCtx<'a, 'b> {
         self.window_id
     }
 
+    /// The scale factor of the window, where `1.0` is 96 dpi.
+    ///
+    /// Widgets that cache measurements, such as text layouts, should
+    /// discard them when this changes; see [`Event::ScaleChanged`].
+    ///
+    /// [`Event::ScaleChanged`]: enum.Event.html#variant.ScaleChanged
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Indicate that your children have changed.
+    ///
+    /// Most widgets add children in `update`; this is for widgets that can
+    /// only decide which children they need once they know their size, such
+    /// as a [`VirtualList`]. The new children receive `WidgetAdded` after
+    /// this layout pass, and are then laid out again, before anything is
+    /// painted. Until then, their [`WidgetPod`] does not lay them out.
+    ///
+    /// [`VirtualList`]: widget/struct.VirtualList.html
+    /// [`WidgetPod`]: struct.WidgetPod.html
+    pub fn children_changed(&mut self) {
+        self.children_changed = true;
+    }
+
     /// Set explicit paint [`Insets`] for this widget.
     ///
     /// You are not required to set explicit paint bounds unless you need
@@ -527,6 +757,16 @@ impl<'a, 'b: 'a> PaintCtx<'a, 'b> {
         &self.region
     }
 
+    /// The size of the window being painted, in display points.
+    ///
+    /// This is useful for widgets that paint outside of their bounds, with
+    /// [`paint_with_z_index`], and need to stay inside the window.
+    ///
+    /// [`paint_with_z_index`]: #method.paint_with_z_index
+    pub fn window_size(&self) -> Size {
+        self.window_size
+    }
+
     /// Creates a temporary `PaintCtx` with a new visible region, and calls
     /// the provided function with that `PaintCtx`.
     ///
@@ -540,6 +780,7 @@ impl<'a, 'b: 'a> PaintCtx<'a, 'b> {
             window_id: self.window_id,
             focus_widget: self.focus_widget,
             region: region.into(),
+            window_size: self.window_size,
         };
         f(&mut child_ctx);
         self.z_ops.append(&mut child_ctx.z_ops);
@@ -563,11 +804,38 @@ impl<'a, 'b: 'a> PaintCtx<'a, 'b> {
 }
 
 impl Region {
+    /// The empty region.
+    pub const EMPTY: Region = Region(Rect::ZERO);
+
     /// Returns the smallest `Rect` that encloses the entire region.
     pub fn to_rect(&self) -> Rect {
         self.0
     }
 
+    /// Returns `true` if the region covers no area.
+    pub fn is_empty(&self) -> bool {
+        self.0.area() == 0.
+    }
+
+    /// Add `rect` to the region.
+    pub fn add_rect(&mut self, rect: Rect) {
+        if self.is_empty() {
+            self.0 = rect;
+        } else if rect.area() != 0. {
+            self.0 = self.0.union(rect);
+        }
+    }
+
+    /// Add `other` to the region.
+    pub fn union_with(&mut self, other: &Region) {
+        self.add_rect(other.0);
+    }
+
+    /// Make the region empty.
+    pub fn clear(&mut self) {
+        self.0 = Rect::ZERO;
+    }
+
     /// Returns `true` if `self` intersects with `other`.
     #[inline]
     pub fn intersects(&self, other: Rect) -> bool {
@@ -19,6 +19,8 @@ use std::collections::VecDeque;
 use log;
 
 use crate::bloom::Bloom;
+use crate::contexts::Region;
+use crate::event::InternalLifeCycle;
 use crate::kurbo::{Affine, Insets, Point, Rect, Shape, Size};
 use crate::piet::RenderContext;
 use crate::{
@@ -76,9 +78,11 @@ pub(crate) struct BaseState {
     paint_insets: Insets,
 
     // TODO: consider using bitflags for the booleans.
-
-    // This should become an invalidation rect.
+    /// The whole window needs to be repainted.
     pub(crate) needs_inval: bool,
+    /// The parts of the window that need to be repainted, in window
+    /// coordinates.
+    pub(crate) invalid: Region,
 
     pub(crate) is_hot: bool,
 
@@ -90,6 +94,12 @@ pub(crate) struct BaseState {
     /// Any descendant has requested an animation frame.
     pub(crate) request_anim: bool,
 
+    /// The widgets in this subtree that have requested an animation frame.
+    ///
+    /// This is non-empty exactly when `request_anim` is set; it is what
+    /// lets a single widget's request be cancelled.
+    pub(crate) anim_requests: Vec<WidgetId>,
+
     /// Any descendant has requested a timer.
     ///
     /// Note: we don't have any way of clearing this request, as it's
@@ -100,6 +110,9 @@ pub(crate) struct BaseState {
     pub(crate) request_focus: Option<FocusChange>,
     pub(crate) children: Bloom<WidgetId>,
     pub(crate) children_changed: bool,
+    /// This widget, or a descendant, needs an update even if the data has
+    /// not changed.
+    pub(crate) needs_update: bool,
 }
 
 /// Methods by which a widget can attempt to change focus state.
@@ -138,6 +151,16 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
         &self.state
     }
 
+    /// Returns `true` if the widget has received [`LifeCycle::WidgetAdded`].
+    ///
+    /// Until it has, the pod does not pass on any other lifecycle event, nor
+    /// updates, layout, or paint.
+    ///
+    /// [`LifeCycle::WidgetAdded`]: enum.LifeCycle.html#variant.WidgetAdded
+    pub fn is_initialized(&self) -> bool {
+        self.old_data.is_some()
+    }
+
     /// Query the "active" state of the widget.
     pub fn is_active(&self) -> bool {
         self.state.is_active
@@ -163,6 +186,11 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
         &mut self.inner
     }
 
+    /// Take the inner widget out, discarding the pod's state.
+    pub(crate) fn into_inner(self) -> W {
+        self.inner
+    }
+
     /// Get the identity of the widget.
     pub fn id(&self) -> WidgetId {
         self.state.id
@@ -247,6 +275,9 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
     /// [`paint`]: widget/trait.Widget.html#tymethod.paint
     /// [`paint_with_offset`]: #method.paint_with_offset
     pub fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &T, env: &Env) {
+        if !self.is_initialized() {
+            return;
+        }
         let mut ctx = PaintCtx {
             render_ctx: paint_ctx.render_ctx,
             window_id: paint_ctx.window_id,
@@ -254,6 +285,7 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
             region: paint_ctx.region.clone(),
             base_state: &self.state,
             focus_widget: paint_ctx.focus_widget,
+            window_size: paint_ctx.window_size,
         };
         self.inner.paint(&mut ctx, data, &env);
         paint_ctx.z_ops.append(&mut ctx.z_ops);
@@ -266,6 +298,7 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
         }
 
         self.state.needs_inval = false;
+        self.state.invalid.clear();
     }
 
     /// Paint the widget, translating it by the origin of its layout rectangle.
@@ -325,9 +358,19 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
         data: &T,
         env: &Env,
     ) -> Size {
+        // a widget added during this layout pass is laid out once it has
+        // received `WidgetAdded`.
+        if !self.is_initialized() {
+            return Size::ZERO;
+        }
         layout_ctx.paint_insets = Insets::ZERO;
+        let parent_changed = std::mem::replace(&mut layout_ctx.children_changed, false);
         let size = self.inner.layout(layout_ctx, bc, data, &env);
         self.state.paint_insets = layout_ctx.paint_insets;
+        // mark the path to a widget that added children, so that
+        // `WidgetAdded` reaches it.
+        self.state.children_changed |= layout_ctx.children_changed;
+        layout_ctx.children_changed |= parent_changed;
         size
     }
 
@@ -356,10 +399,13 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
             return;
         }
         let had_active = self.state.has_active;
+        let window_origin = ctx.window_origin + self.state.layout_rect.origin().to_vec2();
         let mut child_ctx = EventCtx {
             win_ctx: ctx.win_ctx,
+            data_type: ctx.data_type,
             cursor: ctx.cursor,
             command_queue: ctx.command_queue,
+            interval_timers: ctx.interval_timers,
             window: &ctx.window,
             window_id: ctx.window_id,
             base_state: &mut self.state,
@@ -367,6 +413,8 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
             is_handled: false,
             is_root: false,
             focus_widget: ctx.focus_widget,
+            window_origin,
+            now: ctx.now,
         };
         let rect = child_ctx.base_state.layout_rect;
         // Note: could also represent this as `Option<Event>`.
@@ -374,6 +422,11 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
         let mut hot_changed = None;
         let child_event = match event {
             Event::WindowConnected => Event::WindowConnected,
+            Event::WindowCloseRequested => Event::WindowCloseRequested,
+            Event::WindowGotFocus => Event::WindowGotFocus,
+            Event::WindowLostFocus => Event::WindowLostFocus,
+            Event::WindowMoved(pos) => Event::WindowMoved(*pos),
+            Event::ScaleChanged(scale) => Event::ScaleChanged(*scale),
             Event::Size(size) => {
                 recurse = ctx.is_root;
                 Event::Size(*size)
@@ -407,6 +460,15 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
                 mouse_event.pos -= rect.origin().to_vec2();
                 Event::MouseMoved(mouse_event)
             }
+            Event::MouseLeave => {
+                let had_hot = child_ctx.base_state.is_hot;
+                child_ctx.base_state.is_hot = false;
+                if had_hot {
+                    hot_changed = Some(false);
+                }
+                recurse = had_active || had_hot;
+                Event::MouseLeave
+            }
             Event::KeyDown(e) => {
                 recurse = child_ctx.has_focus();
                 Event::KeyDown(*e)
@@ -419,6 +481,32 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
                 recurse = child_ctx.has_focus();
                 Event::Paste(e.clone())
             }
+            Event::Copy(e) => {
+                recurse = child_ctx.has_focus();
+                Event::Copy(e.clone())
+            }
+            Event::Cut(e) => {
+                recurse = child_ctx.has_focus();
+                Event::Cut(e.clone())
+            }
+            Event::ImeComposition { text, cursor } => {
+                recurse = child_ctx.has_focus();
+                Event::ImeComposition {
+                    text: text.clone(),
+                    cursor: *cursor,
+                }
+            }
+            Event::ImeCommit(text) => {
+                recurse = child_ctx.has_focus();
+                Event::ImeCommit(text.clone())
+            }
+            Event::FileDragOver(drop) | Event::FileDrop(drop) => {
+                recurse = rect.winding(drop.pos) != 0;
+                let mut drop = drop.clone();
+                drop.pos -= rect.origin().to_vec2();
+                event.with_file_drop(drop)
+            }
+            Event::FileDragLeave => Event::FileDragLeave,
             Event::Wheel(wheel_event) => {
                 recurse = had_active || child_ctx.base_state.is_hot;
                 Event::Wheel(wheel_event.clone())
@@ -427,21 +515,42 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
                 recurse = had_active || child_ctx.base_state.is_hot;
                 Event::Zoom(*zoom)
             }
+            Event::Gesture(gesture) => {
+                recurse = had_active || child_ctx.base_state.is_hot;
+                Event::Gesture(gesture.offset(-rect.origin().to_vec2()))
+            }
             Event::Timer(id) => {
                 recurse = child_ctx.base_state.request_timer;
                 Event::Timer(*id)
             }
             Event::Command(cmd) => Event::Command(cmd.clone()),
             Event::TargetedCommand(target, cmd) => match target {
-                Target::Window(_) => Event::Command(cmd.clone()),
+                Target::Window(_) | Target::Global => Event::Command(cmd.clone()),
                 Target::Widget(id) if *id == child_ctx.widget_id() => Event::Command(cmd.clone()),
                 Target::Widget(id) => {
                     recurse = child_ctx.base_state.children.contains(id);
                     Event::TargetedCommand(*target, cmd.clone())
                 }
+                // once we reach the root of the subtree, the command becomes
+                // a plain command, which is delivered to every descendant.
+                Target::Subtree(id) if *id == child_ctx.widget_id() => Event::Command(cmd.clone()),
+                Target::Subtree(id) => {
+                    recurse = child_ctx.base_state.children.contains(id);
+                    Event::TargetedCommand(*target, cmd.clone())
+                }
             },
+            Event::Routed(id, inner) => {
+                let inner = inner.translate_mouse(-rect.origin().to_vec2());
+                if *id == child_ctx.widget_id() {
+                    inner
+                } else {
+                    recurse = child_ctx.base_state.children.contains(id);
+                    Event::Routed(*id, Box::new(inner))
+                }
+            }
         };
         child_ctx.base_state.needs_inval = false;
+        child_ctx.base_state.invalid.clear();
         if let Some(is_hot) = hot_changed {
             let hot_changed_event = LifeCycle::HotChanged(is_hot);
             let mut lc_ctx = child_ctx.make_lifecycle_ctx();
@@ -459,30 +568,62 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
     }
 
     pub fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        let is_added = matches!(
+            event,
+            LifeCycle::WidgetAdded | LifeCycle::Internal(InternalLifeCycle::RouteWidgetAdded)
+        );
+        if !is_added && !self.is_initialized() {
+            return;
+        }
+        // the event for this widget, if it is not `event`.
+        let mut own_event = None;
         let recurse = match event {
             LifeCycle::AnimFrame(_) => {
                 let r = self.state.request_anim;
                 self.state.request_anim = false;
+                self.state.anim_requests.clear();
                 r
             }
-            LifeCycle::WidgetAdded => {
-                // if this is called, it means widgets were added. That might be
-                // be us; if we don't have old_data we set it now.
-                if self.old_data.is_none() {
+            LifeCycle::CancelAnimation(target) => {
+                let requests = &mut self.state.anim_requests;
+                let recurse = match target {
+                    Some(id) => requests.contains(id),
+                    None => !requests.is_empty(),
+                };
+                // the descendants that keep animating are merged back in
+                // as we return.
+                requests.retain(|id| target.map(|t| t != *id).unwrap_or(false));
+                self.state.request_anim = !requests.is_empty();
+                recurse
+            }
+            LifeCycle::WidgetAdded | LifeCycle::Internal(InternalLifeCycle::RouteWidgetAdded) => {
+                // if this is called, it means widgets were added. That might
+                // be us, if we don't have old_data yet; we are told once, and
+                // the widgets that were already there are only routed through.
+                if !self.is_initialized() {
                     self.old_data = Some(data.clone());
                     self.env = Some(env.clone());
+                    own_event = Some(LifeCycle::WidgetAdded);
+                } else if self.state.children_changed {
+                    own_event = Some(LifeCycle::Internal(InternalLifeCycle::RouteWidgetAdded));
                 }
                 if self.state.children_changed {
+                    // the children register again on the way back up; we
+                    // keep our own place in the focus chain, as we won't.
+                    let id = self.state.id;
                     self.state.children.clear();
-                    self.state.focus_chain.clear();
+                    self.state.focus_chain.retain(|focus| *focus == id);
                 }
-                self.state.children_changed
+                false
             }
             LifeCycle::HotChanged(_) => false,
             LifeCycle::RouteFocusChanged { old, new } => {
                 self.state.request_focus = None;
 
-                let this_changed = if *old == Some(self.state.id) {
+                // when focus stays put, this only clears the requests.
+                let this_changed = if old == new {
+                    None
+                } else if *old == Some(self.state.id) {
                     Some(false)
                 } else if *new == Some(self.state.id) {
                     Some(true)
@@ -491,8 +632,7 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
                 };
 
                 if let Some(change) = this_changed {
-                    let event = LifeCycle::FocusChanged(change);
-                    self.inner.lifecycle(ctx, &event, data, env);
+                    own_event = Some(LifeCycle::FocusChanged(change));
                     false
                 } else {
                     old.map(|id| self.state.children.contains(&id))
@@ -504,6 +644,16 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
                 self.state.request_focus = None;
                 true
             }
+            LifeCycle::ThemeChanged => true,
+            LifeCycle::CollectBounds(collector) => {
+                let type_name = self.inner.type_name();
+                collector.enter(self.state.id, type_name, self.state.layout_rect);
+                true
+            }
+            LifeCycle::CollectAccessibility(collector) => {
+                collector.enter(self.state.id, self.state.layout_rect);
+                true
+            }
             #[cfg(test)]
             LifeCycle::DebugRequestState { widget, state_cell } => {
                 if *widget == self.id() {
@@ -520,20 +670,30 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
             }
         };
 
+        let window_origin = ctx.window_origin + self.state.layout_rect.origin().to_vec2();
         let mut child_ctx = LifeCycleCtx {
             command_queue: ctx.command_queue,
             base_state: &mut self.state,
             window_id: ctx.window_id,
+            window_origin,
         };
 
-        if recurse {
+        if let Some(event) = &own_event {
+            self.inner.lifecycle(&mut child_ctx, event, data, env);
+        } else if recurse {
             self.inner.lifecycle(&mut child_ctx, event, data, env);
         }
 
+        match event {
+            LifeCycle::CollectBounds(collector) => collector.exit(),
+            LifeCycle::CollectAccessibility(collector) => collector.exit(),
+            _ => (),
+        }
+
         ctx.base_state.merge_up(&self.state);
 
         // we only want to update child state after this specific event.
-        if let LifeCycle::WidgetAdded = event {
+        if is_added {
             self.state.children_changed = false;
             ctx.base_state.children = ctx.base_state.children.union(self.state.children);
             ctx.base_state.focus_chain.extend(&self.state.focus_chain);
@@ -549,21 +709,20 @@ impl<T: Data, W: Widget<T>> WidgetPod<T, W> {
     /// [`update`]: trait.Widget.html#method.update
     pub fn update(&mut self, ctx: &mut UpdateCtx, data: &T, env: &Env) {
         match (self.old_data.as_ref(), self.env.as_ref()) {
-            (Some(d), Some(e)) if d.same(data) && e.same(env) => return,
-            (None, _) => {
-                log::warn!("old_data missing in {:?}, skipping update", self.id());
-                self.old_data = Some(data.clone());
-                self.env = Some(env.clone());
-                return;
-            }
+            (Some(d), Some(e)) if d.same(data) && e.same(env) && !self.state.needs_update => return,
+            // the widget gets its data with `WidgetAdded`.
+            (None, _) => return,
             _ => (),
         }
 
+        self.state.needs_update = false;
+        let window_origin = ctx.window_origin + self.state.layout_rect.origin().to_vec2();
         let mut child_ctx = UpdateCtx {
             window: ctx.window,
             text_factory: ctx.text_factory,
             base_state: &mut self.state,
             window_id: ctx.window_id,
+            window_origin,
         };
 
         self.inner
@@ -592,28 +751,44 @@ impl BaseState {
             layout_rect: Rect::ZERO,
             paint_insets: Insets::ZERO,
             needs_inval: false,
+            invalid: Region::EMPTY,
             is_hot: false,
             is_active: false,
             has_active: false,
             request_anim: false,
+            anim_requests: Vec::new(),
             request_timer: false,
             request_focus: None,
             focus_chain: Vec::new(),
             children: Bloom::new(),
             children_changed: false,
+            needs_update: false,
         }
     }
 
     /// Update to incorporate state changes from a child.
     fn merge_up(&mut self, child_state: &BaseState) {
         self.needs_inval |= child_state.needs_inval;
+        self.invalid.union_with(&child_state.invalid);
         self.request_anim |= child_state.request_anim;
+        for id in &child_state.anim_requests {
+            self.add_anim_request(*id);
+        }
         self.request_timer |= child_state.request_timer;
         self.has_active |= child_state.has_active;
         self.children_changed |= child_state.children_changed;
+        self.needs_update |= child_state.needs_update;
         self.request_focus = self.request_focus.or(child_state.request_focus);
     }
 
+    /// Record that this widget wants an animation frame.
+    pub(crate) fn add_anim_request(&mut self, id: WidgetId) {
+        self.request_anim = true;
+        if !self.anim_requests.contains(&id) {
+            self.anim_requests.push(id);
+        }
+    }
+
     #[inline]
     pub(crate) fn size(&self) -> Size {
         self.layout_rect.size()
@@ -644,9 +819,9 @@ mod tests {
         fn make_widgets() -> impl Widget<Option<u32>> {
             Split::vertical(
                 Flex::<Option<u32>>::row()
-                    .with_child(TextBox::raw().with_id(ID_1).parse(), 1.0)
-                    .with_child(TextBox::raw().with_id(ID_2).parse(), 1.0)
-                    .with_child(TextBox::raw().with_id(ID_3).parse(), 1.0),
+                    .with_flex_child(TextBox::raw().with_id(ID_1).parse(), 1.0)
+                    .with_flex_child(TextBox::raw().with_id(ID_2).parse(), 1.0)
+                    .with_flex_child(TextBox::raw().with_id(ID_3).parse(), 1.0),
                 Scroll::new(TextBox::raw().parse()),
             )
         }
@@ -660,6 +835,7 @@ mod tests {
             command_queue: &mut command_queue,
             base_state: &mut state,
             window_id: WindowId::next(),
+            window_origin: Point::ORIGIN,
         };
 
         let env = Env::default();
@@ -14,8 +14,15 @@
 
 //! Traits for handling value types.
 
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6};
+use std::num::{
+    NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU128,
+    NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize,
+};
+use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
 use crate::kurbo::{self, ParamCurve};
 use crate::piet;
@@ -83,11 +90,15 @@ pub use druid_derive::Data;
 /// }
 /// ```
 ///
-/// ## C-style enums
+/// ## Enums
+///
+/// Two values of an enum are the same if they are the same variant, and
+/// each of their fields is the same. Generic enums get a `Data` bound on
+/// each of their type parameters, as structs do.
 ///
 /// In the case of a "c-style" enum (one that only contains unit variants,
-/// that is where no variant has fields), the implementation that is generated
-/// checks for equality. Therefore, such types must also implement `PartialEq`.
+/// that is where no variant has fields), only the variants are compared, so
+/// such types do not need to implement `PartialEq`.
 ///
 /// [`Data::same`]: trait.Data.html#tymethod.same
 pub trait Data: Clone + 'static {
@@ -104,6 +115,25 @@ pub trait Data: Clone + 'static {
     fn same(&self, other: &Self) -> bool;
 }
 
+/// A cheap way to tell whether application data has changed.
+///
+/// Before every `update`, each widget compares its data with [`Data::same`];
+/// for a large data model these checks can add up. If the model keeps a
+/// counter that is bumped on every mutation, it can implement this trait,
+/// and the application can opt in with [`AppLauncher::use_data_version`].
+/// The version is then checked after each event, and when it has not changed
+/// the update pass is skipped entirely.
+///
+/// The version must change whenever the data does; otherwise widgets will
+/// not be updated to reflect the change.
+///
+/// [`Data::same`]: trait.Data.html#tymethod.same
+/// [`AppLauncher::use_data_version`]: struct.AppLauncher.html#method.use_data_version
+pub trait DataVersion {
+    /// A value that changes whenever the data changes.
+    fn version(&self) -> u64;
+}
+
 /// An impl of `Data` suitable for simple types.
 ///
 /// The `same` method is implemented with equality, so the type should
@@ -122,15 +152,40 @@ impl_data_simple!(i8);
 impl_data_simple!(i16);
 impl_data_simple!(i32);
 impl_data_simple!(i64);
+impl_data_simple!(i128);
 impl_data_simple!(isize);
 impl_data_simple!(u8);
 impl_data_simple!(u16);
 impl_data_simple!(u32);
 impl_data_simple!(u64);
+impl_data_simple!(u128);
 impl_data_simple!(usize);
 impl_data_simple!(char);
 impl_data_simple!(bool);
 impl_data_simple!(String);
+impl_data_simple!(&'static str);
+impl_data_simple!(NonZeroI8);
+impl_data_simple!(NonZeroI16);
+impl_data_simple!(NonZeroI32);
+impl_data_simple!(NonZeroI64);
+impl_data_simple!(NonZeroI128);
+impl_data_simple!(NonZeroIsize);
+impl_data_simple!(NonZeroU8);
+impl_data_simple!(NonZeroU16);
+impl_data_simple!(NonZeroU32);
+impl_data_simple!(NonZeroU64);
+impl_data_simple!(NonZeroU128);
+impl_data_simple!(NonZeroUsize);
+impl_data_simple!(Duration);
+impl_data_simple!(Instant);
+impl_data_simple!(SystemTime);
+impl_data_simple!(PathBuf);
+impl_data_simple!(IpAddr);
+impl_data_simple!(Ipv4Addr);
+impl_data_simple!(Ipv6Addr);
+impl_data_simple!(SocketAddr);
+impl_data_simple!(SocketAddrV4);
+impl_data_simple!(SocketAddrV6);
 
 impl Data for f32 {
     fn same(&self, other: &Self) -> bool {
@@ -156,6 +211,12 @@ impl<T: ?Sized + 'static> Data for Rc<T> {
     }
 }
 
+impl<T: Data, const N: usize> Data for [T; N] {
+    fn same(&self, other: &Self) -> bool {
+        self.iter().zip(other.iter()).all(|(a, b)| a.same(b))
+    }
+}
+
 impl<T: Data> Data for Option<T> {
     fn same(&self, other: &Self) -> bool {
         match (self, other) {
@@ -366,3 +427,29 @@ impl Data for piet::Color {
         self.as_rgba_u32().same(&other.as_rgba_u32())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn std_types() {
+        let path = PathBuf::from("/tmp/a");
+        assert!(path.same(&PathBuf::from("/tmp/a")));
+        assert!(!path.same(&PathBuf::from("/tmp/b")));
+        let second = Duration::from_secs(1);
+        assert!(second.same(&Duration::from_millis(1000)));
+        let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        assert!(!localhost.same(&IpAddr::V6(Ipv6Addr::LOCALHOST)));
+    }
+
+    #[test]
+    fn arrays() {
+        let uuid = [7u8; 16];
+        let mut other = uuid;
+        assert!(uuid.same(&other));
+        other[15] = 0;
+        assert!(!uuid.same(&other));
+        assert!([0.0f64, f64::NAN].same(&[0.0, f64::NAN]));
+    }
+}
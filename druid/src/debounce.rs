@@ -0,0 +1,270 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Debouncing of chatty command selectors.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::{Command, SelectorSymbol, Target, TimerToken, WinCtx, WindowId};
+
+/// Per-selector debounce state, owned by the `AppState`.
+///
+/// When a command with a debounced selector is popped off the command queue,
+/// it is not dispatched; instead it replaces any command with the same
+/// selector and target that is already being held, and a new timer is
+/// requested for the end of the quiet period. Only the timer belonging to the
+/// most recent submission releases the held command; timers from earlier
+/// submissions are stale and are dropped when they fire.
+///
+/// Commands with the same selector but different targets are held, and
+/// released, independently of each other.
+///
+/// Like the timers of [`Retries`], ours belong to a window, so when that
+/// window closes, its held commands are moved to the timers of another.
+///
+/// [`Retries`]: ../retry/struct.Retries.html
+#[derive(Default)]
+pub(crate) struct Debouncer {
+    intervals: HashMap<SelectorSymbol, Duration>,
+    pending: HashMap<Key, Pending>,
+    /// All outstanding timers we have requested, including stale ones, and
+    /// the windows they belong to.
+    timers: HashMap<TimerToken, (Key, WindowId)>,
+}
+
+/// The selector and target a held command is debounced by.
+type Key = (SelectorSymbol, Target);
+
+/// The latest command held for a debounced selector.
+struct Pending {
+    /// The window whose timer releases the command.
+    window: WindowId,
+    deadline: Instant,
+    target: Target,
+    command: Command,
+    /// The timer, or `None` if its window has closed and no other window has
+    /// taken the command over yet.
+    token: Option<TimerToken>,
+}
+
+impl Debouncer {
+    /// Debounce commands with this selector by `interval`.
+    pub(crate) fn add(&mut self, selector: SelectorSymbol, interval: Duration) {
+        self.intervals.insert(selector, interval);
+    }
+
+    /// Returns `true` if commands with this selector should be held.
+    pub(crate) fn is_debounced(&self, selector: SelectorSymbol) -> bool {
+        self.intervals.contains_key(selector)
+    }
+
+    /// Hold a command, replacing any command with the same selector and
+    /// target that is already pending.
+    ///
+    /// Returns the deadline at which the command should be released; the
+    /// caller is expected to request a timer for this deadline from `window`,
+    /// and to register it with [`arm`].
+    ///
+    /// [`arm`]: #method.arm
+    pub(crate) fn hold(
+        &mut self,
+        window: WindowId,
+        target: Target,
+        command: Command,
+        now: Instant,
+    ) -> Instant {
+        let interval = self
+            .intervals
+            .get(command.symbol())
+            .copied()
+            .unwrap_or_default();
+        let key = (command.symbol(), target);
+        let deadline = now + interval;
+        let pending = Pending {
+            window,
+            deadline,
+            target,
+            command,
+            token: None,
+        };
+        self.pending.insert(key, pending);
+        deadline
+    }
+
+    /// Associate the timer that was requested for the most recent submission
+    /// of `selector` to `target`.
+    pub(crate) fn arm(&mut self, selector: SelectorSymbol, target: Target, token: TimerToken) {
+        let key = (selector, target);
+        if let Some(pending) = self.pending.get_mut(&key) {
+            pending.token = Some(token);
+            self.timers.insert(token, (key, pending.window));
+        }
+    }
+
+    /// Returns `true` if this timer was requested by the debouncer.
+    pub(crate) fn owns(&self, token: TimerToken) -> bool {
+        self.timers.contains_key(&token)
+    }
+
+    /// Called when one of our timers fires.
+    ///
+    /// Returns the held command if this timer belongs to the most recent
+    /// submission for its selector and target.
+    pub(crate) fn fire(&mut self, token: TimerToken) -> Option<(Target, Command)> {
+        let (key, _) = self.timers.remove(&token)?;
+        let is_latest = self
+            .pending
+            .get(&key)
+            .map(|p| p.token == Some(token))
+            .unwrap_or(false);
+        if is_latest {
+            self.pending.remove(&key).map(|p| (p.target, p.command))
+        } else {
+            None
+        }
+    }
+
+    /// Called when `closed` is gone, and its timers with it.
+    ///
+    /// Its held commands wait until [`rearm`] is called with another window,
+    /// except those sent to the closed window, which are dropped. Returns
+    /// `true` if any are waiting.
+    ///
+    /// [`rearm`]: #method.rearm
+    pub(crate) fn window_closed(&mut self, closed: WindowId) -> bool {
+        self.timers.retain(|_, (_, window)| *window != closed);
+        self.pending.retain(|_, pending| {
+            if pending.window != closed {
+                return true;
+            }
+            if pending.target == Target::Window(closed) {
+                log::warn!(
+                    "debounced command {} dropped, its window is gone",
+                    pending.command.symbol()
+                );
+                return false;
+            }
+            pending.token = None;
+            true
+        });
+        self.pending.values().any(|pending| pending.token.is_none())
+    }
+
+    /// Request timers from `window` for the held commands of closed windows,
+    /// keeping their deadlines.
+    pub(crate) fn rearm(&mut self, window: WindowId, win_ctx: &mut dyn WinCtx) {
+        for (key, pending) in self.pending.iter_mut() {
+            if pending.token.is_none() {
+                let token = win_ctx.request_timer(pending.deadline);
+                pending.window = window;
+                pending.token = Some(token);
+                self.timers.insert(token, (*key, window));
+            }
+        }
+    }
+
+    /// Drop the held commands of closed windows, when there is no window left.
+    pub(crate) fn clear_unarmed(&mut self) {
+        self.pending.retain(|_, pending| {
+            if pending.token.is_none() {
+                log::warn!(
+                    "debounced command {} dropped, there is no window left",
+                    pending.command.symbol()
+                );
+            }
+            pending.token.is_some()
+        });
+    }
+
+    /// The timers held commands are waiting on, and their windows.
+    #[cfg(test)]
+    pub(crate) fn timers(&self) -> Vec<(TimerToken, WindowId)> {
+        self.pending
+            .values()
+            .filter_map(|pending| pending.token.map(|token| (token, pending.window)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Selector;
+
+    const QUERY: Selector<String> = Selector::new("druid-test.query");
+
+    #[test]
+    fn only_last_fires() {
+        let mut debouncer = Debouncer::default();
+        let interval = Duration::from_millis(100);
+        debouncer.add(QUERY.symbol(), interval);
+        assert!(debouncer.is_debounced(QUERY.symbol()));
+        assert!(!debouncer.is_debounced("druid-test.other"));
+
+        let window = WindowId::next();
+        let target = Target::Window(window);
+        let start = Instant::now();
+        let mut tokens = Vec::new();
+        for (i, query) in ["d", "dr", "dru"].iter().enumerate() {
+            let now = start + Duration::from_millis(10 * i as u64);
+            let query = Command::new(QUERY, query.to_string());
+            let deadline = debouncer.hold(window, target, query, now);
+            // every keystroke pushes the deadline out by the full interval.
+            assert_eq!(deadline, now + interval);
+            let token = TimerToken::next();
+            debouncer.arm(QUERY.symbol(), target, token);
+            tokens.push(token);
+        }
+
+        // the earlier timers fire, but they are stale.
+        assert!(debouncer.owns(tokens[0]));
+        assert!(debouncer.fire(tokens[0]).is_none());
+        assert!(debouncer.fire(tokens[1]).is_none());
+
+        let (fired_target, cmd) = debouncer.fire(tokens[2]).expect("last query fires");
+        assert_eq!(fired_target, target);
+        assert_eq!(cmd.get(QUERY), Some(&"dru".to_string()));
+
+        // nothing is left pending.
+        assert!(!debouncer.owns(tokens[2]));
+        assert!(debouncer.fire(tokens[2]).is_none());
+    }
+
+    #[test]
+    fn targets_are_held_apart() {
+        let mut debouncer = Debouncer::default();
+        debouncer.add(QUERY.symbol(), Duration::from_millis(100));
+
+        let (first, second) = (WindowId::next(), WindowId::next());
+        let now = Instant::now();
+        let mut tokens = Vec::new();
+        for &(id, query) in &[(first, "a"), (second, "b"), (first, "ab")] {
+            let target = Target::Window(id);
+            debouncer.hold(id, target, Command::new(QUERY, query.to_string()), now);
+            let token = TimerToken::next();
+            debouncer.arm(QUERY.symbol(), target, token);
+            tokens.push(token);
+        }
+
+        // the query to the second window is not replaced by the first's.
+        assert!(debouncer.fire(tokens[0]).is_none());
+        let (target, cmd) = debouncer.fire(tokens[1]).expect("second window's query");
+        assert_eq!(target, Target::Window(second));
+        assert_eq!(cmd.get(QUERY), Some(&"b".to_string()));
+        let (target, cmd) = debouncer.fire(tokens[2]).expect("first window's query");
+        assert_eq!(target, Target::Window(first));
+        assert_eq!(cmd.get(QUERY), Some(&"ab".to_string()));
+    }
+}
@@ -23,9 +23,10 @@ use std::sync::Arc;
 
 use crate::kurbo::{Point, Rect, Size};
 use crate::piet::{Color, LinearGradient};
+use unic_langid::LanguageIdentifier;
 
 use crate::localization::L10nManager;
-use crate::Data;
+use crate::{theme, Data};
 
 /// An environment passed down through all widget traversals.
 ///
@@ -168,6 +169,21 @@ impl Env {
         &self.0.l10n
     }
 
+    /// Switch the localization resources to `locale`.
+    pub(crate) fn set_locale(&mut self, locale: LanguageIdentifier) {
+        let l10n = self.0.l10n.with_locale(locale);
+        Arc::make_mut(&mut self.0).l10n = Arc::new(l10n);
+    }
+
+    /// Whether widgets should be laid out from right to left.
+    ///
+    /// This is [`theme::RIGHT_TO_LEFT`], or `false` if it is not set.
+    ///
+    /// [`theme::RIGHT_TO_LEFT`]: theme/constant.RIGHT_TO_LEFT.html
+    pub(crate) fn is_right_to_left(&self) -> bool {
+        self.try_get(theme::RIGHT_TO_LEFT).unwrap_or(false)
+    }
+
     /// Given an id, returns one of 18 distinct colors
     #[doc(hidden)]
     pub fn get_debug_color(&self, id: u64) -> Color {
@@ -182,6 +198,16 @@ impl Env {
     ///
     /// [`AppLauncher`]: struct.AppLauncher.html
     pub(crate) const DEBUG_PAINT: Key<bool> = Key::new("debug_paint");
+
+    /// State for whether or not windows start out with the widget inspector
+    /// overlay shown.
+    ///
+    /// Set by the `debug_inspector()` method on [`AppLauncher`]; the
+    /// [`TOGGLE_INSPECTOR`] command flips it for a single window.
+    ///
+    /// [`AppLauncher`]: struct.AppLauncher.html
+    /// [`TOGGLE_INSPECTOR`]: commands/constant.TOGGLE_INSPECTOR.html
+    pub(crate) const DEBUG_INSPECT: Key<bool> = Key::new("debug_inspect");
 }
 
 impl<T> Key<T> {
@@ -279,7 +305,8 @@ impl Data for Env {
 
 impl Data for EnvImpl {
     fn same(&self, other: &EnvImpl) -> bool {
-        self.map.len() == other.map.len()
+        Arc::ptr_eq(&self.l10n, &other.l10n)
+            && self.map.len() == other.map.len()
             && self
                 .map
                 .iter()
@@ -320,7 +347,9 @@ impl Default for Env {
             debug_colors,
         };
 
-        Env(Arc::new(inner)).adding(Env::DEBUG_PAINT, false)
+        Env(Arc::new(inner))
+            .adding(Env::DEBUG_PAINT, false)
+            .adding(Env::DEBUG_INSPECT, false)
     }
 }
 
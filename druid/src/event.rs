@@ -14,12 +14,15 @@
 
 //! Events.
 
-use crate::kurbo::{Rect, Shape, Size, Vec2};
+use std::cell::RefCell;
+use std::rc::Rc;
 
-use druid_shell::{Clipboard, KeyEvent, KeyModifiers, TimerToken};
+use crate::kurbo::{Point, Rect, Shape, Size, Vec2};
+
+use druid_shell::{Clipboard, FileInfo, KeyEvent, KeyModifiers, TimerToken, WheelPhase, WheelUnit};
 
 use crate::mouse::MouseEvent;
-use crate::{Command, Target, WidgetId};
+use crate::{theme, AccessibilityCollector, Command, Env, Gesture, Target, WidgetId};
 
 /// An event, propagated downwards during event flow.
 ///
@@ -56,6 +59,47 @@ pub enum Event {
     ///
     /// [`LifeCycle::WidgetAdded`]: enum.LifeCycle.html#variant.WidgetAdded
     WindowConnected,
+    /// Sent to all widgets in a window when the user asks to close it, for
+    /// instance with the close button in the title bar.
+    ///
+    /// The window stays open if this event is handled: a widget can keep it
+    /// open by calling [`set_handled`], and an [`AppDelegate`] by returning
+    /// `false` from [`window_should_close`], or by not passing the event on.
+    /// Otherwise the window is closed once the event has been dispatched.
+    ///
+    /// This is not sent when a window is closed with the
+    /// [`CLOSE_WINDOW`] command.
+    ///
+    /// [`set_handled`]: struct.EventCtx.html#method.set_handled
+    /// [`AppDelegate`]: trait.AppDelegate.html
+    /// [`window_should_close`]: trait.AppDelegate.html#method.window_should_close
+    /// [`CLOSE_WINDOW`]: command/sys/constant.CLOSE_WINDOW.html
+    WindowCloseRequested,
+    /// Sent to all widgets in a window when the window gains focus.
+    WindowGotFocus,
+    /// Sent to all widgets in a window when the window loses focus.
+    ///
+    /// When focus moves from one window of the application to another, the
+    /// window losing focus always receives this before the other window
+    /// receives [`WindowGotFocus`].
+    ///
+    /// [`WindowGotFocus`]: #variant.WindowGotFocus
+    WindowLostFocus,
+    /// Sent to all widgets in a window when the window is moved.
+    ///
+    /// The point is the new position of the window's top-left corner, in
+    /// screen coordinates. Together with [`Size`], this is enough to save
+    /// and restore the geometry of a window.
+    ///
+    /// [`Size`]: #variant.Size
+    WindowMoved(Point),
+    /// Sent to all widgets in a window when its scale factor changes, for
+    /// instance when it is moved to a monitor with a different DPI.
+    ///
+    /// The argument is the new scale, where `1.0` is 96 dpi. The window is
+    /// laid out and painted again at the new scale; widgets that cache
+    /// measurements, text layouts in particular, should discard them here.
+    ScaleChanged(f64),
     /// Called on the root widget when the window size changes.
     ///
     /// Discussion: it's not obvious this should be propagated to user
@@ -84,6 +128,14 @@ pub enum Event {
     ///
     /// [`set_cursor`]: struct.EventCtx.html#method.set_cursor
     MouseMoved(MouseEvent),
+    /// Called when the mouse leaves the window.
+    ///
+    /// Every widget that is hot stops being hot, and receives a
+    /// [`HotChanged(false)`]. If a button is held down, the active widget
+    /// still receives the `MouseUp` when it is released.
+    ///
+    /// [`HotChanged(false)`]: enum.LifeCycle.html#variant.HotChanged
+    MouseLeave,
     /// Called when a key is pressed.
     ///
     /// Note: the intent is for each physical key press to correspond to
@@ -97,14 +149,125 @@ pub enum Event {
     /// Because of repeat, there may be a number `KeyDown` events before
     /// a corresponding `KeyUp` is sent.
     KeyUp(KeyEvent),
+    /// Called when the text being composed by an input method changes.
+    ///
+    /// Like `KeyDown`, this is sent to the focused widget. `text` is the
+    /// whole composition so far, and `cursor` is the byte offset of the caret
+    /// within it; an empty `text` means the composition was cancelled. The
+    /// composition should be shown in place, but it is not yet part of the
+    /// widget's data.
+    ///
+    /// A widget that accepts composed text should report where its caret is
+    /// with [`EventCtx::set_ime_cursor_area`].
+    ///
+    /// [`EventCtx::set_ime_cursor_area`]: struct.EventCtx.html#method.set_ime_cursor_area
+    ImeComposition {
+        /// The text under composition.
+        text: String,
+        /// The byte offset of the caret in `text`.
+        cursor: usize,
+    },
+    /// Called when an input method commits text, ending the composition.
+    ///
+    /// Like `KeyDown`, this is sent to the focused widget, which should
+    /// insert the text as if it had been typed.
+    ImeCommit(String),
     /// Called when a paste command is received.
+    ///
+    /// The clipboard may hold the same content in several formats; use
+    /// [`Clipboard::preferred_format`] to pick the best one you support, or
+    /// [`Clipboard::available_formats`] to see everything that is on offer.
+    /// Widgets that only deal in text can keep using
+    /// [`Clipboard::get_string`].
+    ///
+    /// # Examples
+    ///
+    /// Preferring HTML, and falling back to plain text:
+    ///
+    /// ```
+    /// # use druid::{Clipboard, ClipboardFormat};
+    /// fn paste_markup(clipboard: &Clipboard) -> Option<String> {
+    ///     match clipboard.preferred_format(&[ClipboardFormat::HTML]) {
+    ///         Some(format) => clipboard
+    ///             .get_format(format)
+    ///             .and_then(|data| String::from_utf8(data).ok()),
+    ///         None => clipboard.get_string(),
+    ///     }
+    /// }
+    ///
+    /// let mut clipboard = Clipboard::in_memory();
+    /// clipboard.put_formats(&[
+    ///     ClipboardFormat::new(ClipboardFormat::HTML, "<b>bold</b>"),
+    ///     ClipboardFormat::from("bold"),
+    /// ]);
+    /// assert_eq!(paste_markup(&clipboard).as_deref(), Some("<b>bold</b>"));
+    ///
+    /// clipboard.put_string("plain");
+    /// assert_eq!(paste_markup(&clipboard).as_deref(), Some("plain"));
+    /// ```
+    ///
+    /// [`Clipboard::get_string`]: struct.Clipboard.html#method.get_string
+    /// [`Clipboard::preferred_format`]: struct.Clipboard.html#method.preferred_format
+    /// [`Clipboard::available_formats`]: struct.Clipboard.html#method.available_formats
     Paste(Clipboard),
+    /// Sent to the focused widget when the user copies, for instance with
+    /// the 'Copy' menu item or its shortcut.
+    ///
+    /// A widget with a selection should put it on the clipboard, and call
+    /// [`set_handled`]. The clipboard is a handle: write to a clone of it.
+    ///
+    /// ```
+    /// # use druid::Event;
+    /// fn copy(event: &Event, selection: &str) {
+    ///     if let Event::Copy(clipboard) = event {
+    ///         clipboard.clone().put_string(selection);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`set_handled`]: struct.EventCtx.html#method.set_handled
+    Copy(Clipboard),
+    /// Sent to the focused widget when the user cuts, for instance with the
+    /// 'Cut' menu item or its shortcut.
+    ///
+    /// Like `Copy`, except the widget should also remove the selection from
+    /// its data.
+    Cut(Clipboard),
+    /// Sent to the widgets under the pointer while files dragged from another
+    /// application move over the window.
+    ///
+    /// A widget that would accept the files calls [`set_handled`]; the
+    /// platform then shows a cursor indicating that they can be dropped.
+    ///
+    /// [`set_handled`]: struct.EventCtx.html#method.set_handled
+    FileDragOver(FileDropEvent),
+    /// Sent to all widgets in a window when the files being dragged leave
+    /// it without being dropped.
+    FileDragLeave,
+    /// Sent to the widgets under the pointer when files are dropped on the
+    /// window, whether or not the window has focus.
+    ///
+    /// A widget that accepts the files should call [`set_handled`].
+    ///
+    /// [`set_handled`]: struct.EventCtx.html#method.set_handled
+    FileDrop(FileDropEvent),
     /// Called when the mouse wheel or trackpad is scrolled.
+    ///
+    /// Like mouse events, this is sent to the widgets under the pointer, and
+    /// to the active widget; a container should pass it to its children
+    /// first, and only scroll itself if none of them handled it.
     Wheel(WheelEvent),
     /// Called when the trackpad is pinched.
     ///
-    /// The value is a delta.
+    /// The value is a delta. Like wheel events, this is sent to the widgets
+    /// under the pointer, and to the active widget.
     Zoom(f64),
+    /// Called when a gesture is recognized, right after the raw event that
+    /// completed it.
+    ///
+    /// Like wheel events, gestures are propagated to the active widget, if
+    /// there is one, otherwise to hot widgets.
+    Gesture(Gesture),
     /// Called on a timer event.
     ///
     /// Request a timer event through [`EventCtx::request_timer()`]. That will
@@ -131,6 +294,34 @@ pub enum Event {
     /// event and should generally not be handled directly by widgets, but is
     /// important for containers to dispatch to their children.
     TargetedCommand(Target, Command),
+    /// An event that a [`MouseRoute`] has redirected to a specific widget.
+    ///
+    /// Like [`TargetedCommand`], this is an internal event; the inner event is
+    /// delivered to the target widget, bypassing the usual hit-testing.
+    ///
+    /// [`MouseRoute`]: enum.MouseRoute.html
+    /// [`TargetedCommand`]: #variant.TargetedCommand
+    Routed(WidgetId, Box<Event>),
+}
+
+/// The result of a window's mouse routing strategy.
+///
+/// A window can be given a routing strategy with [`WindowDesc::mouse_router`];
+/// this is consulted for every mouse event before it is passed down the widget
+/// tree, and decides how the event is delivered. It is not consulted while
+/// a widget is [active], as that widget has captured the mouse.
+///
+/// [active]: struct.EventCtx.html#method.set_active
+/// [`WindowDesc::mouse_router`]: struct.WindowDesc.html#method.mouse_router
+#[derive(Debug, Clone)]
+pub enum MouseRoute {
+    /// Deliver the event normally, by hit-testing the widget tree.
+    Tree,
+    /// Deliver the event to each of these widgets in turn, regardless of
+    /// their position or paint order, stopping at the first that handles it.
+    Widgets(Vec<WidgetId>),
+    /// Do not deliver the event.
+    Drop,
 }
 
 /// Application life cycle events.
@@ -140,6 +331,11 @@ pub enum LifeCycle {
     /// the first message that each widget receives.
     ///
     /// Widgets should handle this event in order to do any initial setup.
+    /// It is sent exactly once, before the widget's first update, layout, or
+    /// paint; this includes widgets built later, such as the children of a
+    /// [`List`] or [`ViewSwitcher`]. The exception is a [`VirtualList`],
+    /// which reuses the widgets of rows that scroll out of view: a reused
+    /// widget is sent `WidgetAdded` again, with the data of its new row.
     ///
     /// In addition to setup, this event is also used by the framework to
     /// track certain types of important widget state.
@@ -159,12 +355,31 @@ pub enum LifeCycle {
     /// [`LifeCycleCtx::register_child`]: struct.LifeCycleCtx.html#method.register_child
     /// [`WidgetPod`]: struct.WidgetPod.html
     /// [`LifeCycleCtx::register_for_focus`]: struct.LifeCycleCtx.html#method.register_for_focus
+    /// [`List`]: widget/struct.List.html
+    /// [`ViewSwitcher`]: widget/struct.ViewSwitcher.html
+    /// [`VirtualList`]: widget/struct.VirtualList.html
     WidgetAdded,
+    /// Internal lifecycle events, used by the framework.
+    ///
+    /// A [`WidgetPod`] turns these into the events above, so widgets never
+    /// see them; containers forward them to their children like any other
+    /// event.
+    ///
+    /// [`WidgetPod`]: struct.WidgetPod.html
+    #[doc(hidden)]
+    Internal(InternalLifeCycle),
     /// Called at the beginning of a new animation frame.
     ///
-    /// On the first frame when transitioning from idle to animating, `interval`
-    /// will be 0. (This logic is presently per-window but might change to
-    /// per-widget to make it more consistent). Otherwise it is in nanoseconds.
+    /// This is only sent to widgets that requested it, with
+    /// [`request_anim_frame`], since the previous frame. The value is the
+    /// time since the previous frame, in nanoseconds, so that animations can
+    /// run at the same speed whatever the frame rate.
+    ///
+    /// On the first frame when transitioning from idle to animating, the
+    /// interval will be 0. (This logic is presently per-window but might
+    /// change to per-widget to make it more consistent).
+    ///
+    /// [`request_anim_frame`]: struct.LifeCycleCtx.html#method.request_anim_frame
     AnimFrame(u64),
     /// Called when the "hot" status changes.
     ///
@@ -190,6 +405,37 @@ pub enum LifeCycle {
     /// See [`has_focus`](struct.BaseState.html#method.has_focus) for
     /// discussion about the focus status.
     FocusChanged(bool),
+    /// Called on every widget when the application's [`Env`] is replaced,
+    /// with the [`SET_THEME`] command.
+    ///
+    /// It is sent before the `update` that carries the new `Env`, so a widget
+    /// that caches values derived from the environment, such as fonts or
+    /// text layouts, should drop them here.
+    ///
+    /// [`Env`]: struct.Env.html
+    /// [`SET_THEME`]: commands/constant.SET_THEME.html
+    ThemeChanged,
+    /// Internal: used by the framework to gather the bounds of every widget,
+    /// in response to the [`COLLECT_WIDGET_BOUNDS`] command.
+    ///
+    /// [`COLLECT_WIDGET_BOUNDS`]: commands/constant.COLLECT_WIDGET_BOUNDS.html
+    CollectBounds(BoundsCollector),
+    /// Called to build the accessibility tree, in response to the
+    /// [`COLLECT_ACCESSIBILITY_TREE`] command.
+    ///
+    /// A widget that has a role, such as a button or a text field, describes
+    /// itself through the [`AccessibilityCollector`]; containers should just
+    /// pass this on to their children.
+    ///
+    /// [`COLLECT_ACCESSIBILITY_TREE`]: commands/constant.COLLECT_ACCESSIBILITY_TREE.html
+    /// [`AccessibilityCollector`]: struct.AccessibilityCollector.html
+    CollectAccessibility(AccessibilityCollector),
+    /// Internal: used by the framework to cancel the animation frame
+    /// requests of a widget, or of every widget if there is no id, in
+    /// response to the [`CANCEL_ANIMATION`] command.
+    ///
+    /// [`CANCEL_ANIMATION`]: commands/constant.CANCEL_ANIMATION.html
+    CancelAnimation(Option<WidgetId>),
     /// Testing only: request the `BaseState` of a specific widget.
     ///
     /// During testing, you may wish to verify that the state of a widget
@@ -205,6 +451,16 @@ pub enum LifeCycle {
     DebugInspectState(StateCheckFn),
 }
 
+/// Lifecycle events that only the framework sends and handles.
+#[derive(Debug, Clone)]
+pub enum InternalLifeCycle {
+    /// Route [`LifeCycle::WidgetAdded`] to the widgets that were added since
+    /// it was last sent.
+    ///
+    /// [`LifeCycle::WidgetAdded`]: enum.LifeCycle.html#variant.WidgetAdded
+    RouteWidgetAdded,
+}
+
 /// A mouse wheel event.
 ///
 /// An event generated by a mouse wheel or trackpad device. Perhaps a
@@ -225,12 +481,50 @@ pub struct WheelEvent {
     /// move on scrolling. This polarity is consistent with the
     /// deltaX and deltaY values in a web [WheelEvent].
     ///
+    /// The delta is in `unit`s; use [`pixel_delta`] to get the distance
+    /// to scroll.
+    ///
     /// [WheelEvent]: https://w3c.github.io/uievents/#event-type-wheel
+    /// [`pixel_delta`]: #method.pixel_delta
     pub delta: Vec2,
+    /// The unit of `delta`.
+    pub unit: WheelUnit,
+    /// Where this event falls in a trackpad gesture, if the platform
+    /// reports one.
+    pub phase: WheelPhase,
     /// The keyboard modifiers at the time of the event.
     pub mods: KeyModifiers,
 }
 
+/// Files dragged from another application, and where they are.
+#[derive(Debug, Clone)]
+pub struct FileDropEvent {
+    /// The position of the pointer, in the widget's coordinate space.
+    pub pos: Point,
+    /// The files; there may be more than one.
+    pub files: Vec<FileInfo>,
+}
+
+impl WheelEvent {
+    /// The distance to scroll, in display points, for content whose
+    /// visible area is `page`.
+    ///
+    /// Lines are [`theme::SCROLL_LINE_HEIGHT`] high, and pages are a little
+    /// smaller than `page`, so that some content stays in view.
+    ///
+    /// [`theme::SCROLL_LINE_HEIGHT`]: theme/constant.SCROLL_LINE_HEIGHT.html
+    pub fn pixel_delta(&self, page: Size, env: &Env) -> Vec2 {
+        match self.unit {
+            WheelUnit::Pixels => self.delta,
+            WheelUnit::Lines => self.delta * env.get(theme::SCROLL_LINE_HEIGHT),
+            WheelUnit::Pages => Vec2::new(
+                self.delta.x * page.width * 0.9,
+                self.delta.y * page.height * 0.9,
+            ),
+        }
+    }
+}
+
 impl Event {
     /// Transform the event for the contents of a scrolling container.
     pub fn transform_scroll(&self, offset: Vec2, viewport: Rect) -> Option<Event> {
@@ -264,9 +558,103 @@ impl Event {
                     None
                 }
             }
+            Event::FileDragOver(drop) | Event::FileDrop(drop) => {
+                if viewport.winding(drop.pos) != 0 {
+                    let mut drop = drop.clone();
+                    drop.pos += offset;
+                    Some(self.with_file_drop(drop))
+                } else {
+                    None
+                }
+            }
+            Event::Routed(id, inner) => inner
+                .transform_scroll(offset, viewport)
+                .map(|inner| Event::Routed(*id, Box::new(inner))),
             _ => Some(self.clone()),
         }
     }
+
+    /// This event, with the position of a mouse button or move event moved
+    /// by `offset`; other events are unchanged.
+    pub(crate) fn translate_mouse(&self, offset: Vec2) -> Event {
+        let mut event = self.clone();
+        match &mut event {
+            Event::MouseDown(mouse) | Event::MouseUp(mouse) | Event::MouseMoved(mouse) => {
+                mouse.pos += offset
+            }
+            _ => (),
+        }
+        event
+    }
+
+    /// This `FileDragOver` or `FileDrop` event, with a different payload.
+    pub(crate) fn with_file_drop(&self, drop: FileDropEvent) -> Event {
+        match self {
+            Event::FileDragOver(_) => Event::FileDragOver(drop),
+            _ => Event::FileDrop(drop),
+        }
+    }
+}
+
+/// The bounds of a widget, as of the last layout pass.
+///
+/// This is the payload of the [`WIDGET_BOUNDS`] command.
+///
+/// [`WIDGET_BOUNDS`]: commands/constant.WIDGET_BOUNDS.html
+#[derive(Debug, Clone, Copy)]
+pub struct WidgetBounds {
+    /// The widget.
+    pub id: WidgetId,
+    /// The name of the widget's type.
+    pub type_name: &'static str,
+    /// The widget's layout rect, in window coordinates.
+    ///
+    /// This does not account for any transforms applied during painting,
+    /// such as the offset of a scroll container.
+    pub rect: Rect,
+    /// The number of ancestors of this widget; the root widget has depth 0.
+    pub depth: usize,
+}
+
+/// Gathers [`WidgetBounds`] during a [`LifeCycle::CollectBounds`] pass.
+///
+/// [`WidgetBounds`]: struct.WidgetBounds.html
+/// [`LifeCycle::CollectBounds`]: enum.LifeCycle.html#variant.CollectBounds
+#[derive(Debug, Clone, Default)]
+pub struct BoundsCollector(Rc<RefCell<Bounds>>);
+
+#[derive(Debug, Default)]
+struct Bounds {
+    /// The window-space origins of the widgets we are currently inside of.
+    origins: Vec<Point>,
+    widgets: Vec<WidgetBounds>,
+}
+
+impl BoundsCollector {
+    /// Record a widget, and descend into it; `rect` is in its parent's coordinates.
+    pub(crate) fn enter(&self, id: WidgetId, type_name: &'static str, rect: Rect) {
+        let mut bounds = self.0.borrow_mut();
+        let parent = bounds.origins.last().copied().unwrap_or(Point::ORIGIN);
+        let rect = rect + parent.to_vec2();
+        let depth = bounds.origins.len();
+        bounds.origins.push(rect.origin());
+        bounds.widgets.push(WidgetBounds {
+            id,
+            type_name,
+            rect,
+            depth,
+        });
+    }
+
+    /// Return to the parent of the most recently entered widget.
+    pub(crate) fn exit(&self) {
+        self.0.borrow_mut().origins.pop();
+    }
+
+    /// The bounds collected so far, in tree order.
+    pub(crate) fn bounds(&self) -> Vec<WidgetBounds> {
+        self.0.borrow().widgets.clone()
+    }
 }
 
 #[cfg(test)]
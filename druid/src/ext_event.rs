@@ -16,13 +16,20 @@
 
 use std::any::Any;
 use std::collections::VecDeque;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
 
 use crate::shell::IdleHandle;
 use crate::win_handler::EXT_EVENT_IDLE_TOKEN;
-use crate::{Command, Selector, Target, WindowId};
+use crate::{Command, Selector, SelectorSymbol, Target, WindowId};
 
-pub(crate) type ExtCommand = (Selector, Option<Box<dyn Any + Send>>, Option<Target>);
+pub(crate) type ExtCommand = (SelectorSymbol, Option<Box<dyn Any + Send>>, Option<Target>);
+
+/// The most commands that can be waiting to be handled.
+///
+/// Past this, [`ExtEventSink::submit_command`] fails with
+/// [`ExtEventError::QueueFull`] until the application catches up.
+pub(crate) const EXT_EVENT_QUEUE_CAPACITY: usize = 65_536;
 
 /// A thing that can move into other threads and be used to submit commands back
 /// to the running application.
@@ -30,8 +37,10 @@ pub(crate) type ExtCommand = (Selector, Option<Box<dyn Any + Send>>, Option<Targ
 /// This API is preliminary, and may be changed or removed without warning.
 #[derive(Clone)]
 pub struct ExtEventSink {
-    queue: Arc<Mutex<VecDeque<ExtCommand>>>,
+    /// This is weak so that we can tell when the application has gone away.
+    queue: Weak<Mutex<VecDeque<ExtCommand>>>,
     handle: Arc<Mutex<Option<IdleHandle>>>,
+    closed: Arc<AtomicBool>,
 }
 
 /// The stuff that we hold onto inside the app that is related to the
@@ -43,15 +52,26 @@ pub(crate) struct ExtEventHost {
     /// This doesn't exist when the app starts and it can go away if a window
     /// closes, so we keep a reference here and can update it when needed.
     handle: Arc<Mutex<Option<IdleHandle>>>,
+    /// Whether the last window has closed, and none has opened since.
+    closed: Arc<AtomicBool>,
     /// The window that the handle belongs to, so we can keep track of when
     /// we need to get a new handle.
     pub(crate) handle_window_id: Option<WindowId>,
 }
 
 /// An error that occurs if an external event cannot be submitted.
-/// This probably means that the application has gone away.
-#[derive(Debug, Clone)]
-pub struct ExtEventError;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtEventError {
+    /// There is no window to receive the command: the last one has closed,
+    /// or the application has shut down.
+    ///
+    /// Where the application can outlive its windows, commands are accepted
+    /// again once a new window opens.
+    WindowClosed,
+    /// Too many commands are waiting to be handled. The command was not
+    /// submitted; it can be tried again once the application catches up.
+    QueueFull,
+}
 
 impl ExtEventHost {
     pub(crate) fn new() -> Self {
@@ -60,8 +80,9 @@ impl ExtEventHost {
 
     pub(crate) fn make_sink(&self) -> ExtEventSink {
         ExtEventSink {
-            queue: self.queue.clone(),
+            queue: Arc::downgrade(&self.queue),
             handle: self.handle.clone(),
+            closed: self.closed.clone(),
         }
     }
 
@@ -70,6 +91,28 @@ impl ExtEventHost {
         self.handle_window_id = Some(window_id);
     }
 
+    /// Forget the idle handle, after the window it belongs to has closed.
+    ///
+    /// Commands are still queued, and are handled once a new handle is set.
+    pub(crate) fn clear_idle(&mut self) {
+        self.handle.lock().unwrap().take();
+        self.handle_window_id = None;
+    }
+
+    /// Refuse new commands, after the last window has closed.
+    ///
+    /// Commands that are already queued are kept, and are handled if a new
+    /// window opens.
+    pub(crate) fn close(&mut self) {
+        self.clear_idle();
+        self.closed.store(true, Ordering::SeqCst);
+    }
+
+    /// Accept commands again, once a window has opened.
+    pub(crate) fn open(&mut self) {
+        self.closed.store(false, Ordering::SeqCst);
+    }
+
     pub(crate) fn has_pending_items(&self) -> bool {
         !self.queue.lock().unwrap().is_empty()
     }
@@ -90,8 +133,8 @@ impl ExtEventSink {
     /// instead you have to pass the [`Selector`] and the (optional) argument
     /// separately, and it will be turned into a `Command` when it is received.
     ///
-    /// The `obj` argument can be any type which implements `Any + Send`, or `None`
-    /// if this command has no argument.
+    /// The `obj` argument must have the selector's argument type, which must
+    /// implement `Any + Send`; it can be `None` if this command has no argument.
     ///
     /// If no explicit `Target` is submitted, the `Command` will be sent to
     /// the application's first window; if that window is subsequently closed,
@@ -99,31 +142,176 @@ impl ExtEventSink {
     ///
     /// This limitation may be removed in the future.
     ///
+    /// # Errors
+    ///
+    /// Returns [`ExtEventError::WindowClosed`] if the last window has closed,
+    /// or the application has shut down, in which case the caller can stop
+    /// producing commands, and [`ExtEventError::QueueFull`] if too many
+    /// commands are already waiting to be handled.
+    ///
     /// [`Command`]: struct.Command.html
     /// [`Selector`]: struct.Selector.html
+    /// [`ExtEventError::WindowClosed`]: enum.ExtEventError.html#variant.WindowClosed
+    /// [`ExtEventError::QueueFull`]: enum.ExtEventError.html#variant.QueueFull
     pub fn submit_command<T: Any + Send>(
         &self,
-        sel: Selector,
+        sel: Selector<T>,
         obj: impl Into<Option<T>>,
         target: impl Into<Option<Target>>,
     ) -> Result<(), ExtEventError> {
         let target = target.into();
         let obj = obj.into().map(|o| Box::new(o) as Box<dyn Any + Send>);
+        let queue = self.queue.upgrade().ok_or(ExtEventError::WindowClosed)?;
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(ExtEventError::WindowClosed);
+        }
+        {
+            let mut queue = queue.lock().map_err(|_| ExtEventError::WindowClosed)?;
+            if queue.len() >= EXT_EVENT_QUEUE_CAPACITY {
+                return Err(ExtEventError::QueueFull);
+            }
+            queue.push_back((sel.symbol(), obj, target));
+        }
+        // wake the application only once the command can be received.
+        self.wake();
+        Ok(())
+    }
+
+    /// Submit a batch of commands with the same [`Selector`], in order.
+    ///
+    /// This is like calling [`submit_command`] for each `(obj, target)`
+    /// pair, but the commands are queued together, and the application is
+    /// woken up once, which is cheaper when a worker streams many results.
+    ///
+    /// # Errors
+    ///
+    /// Like [`submit_command`]; a batch that doesn't fit in the queue is
+    /// refused whole, with [`ExtEventError::QueueFull`], so that none of
+    /// its commands are received out of order.
+    ///
+    /// [`Selector`]: struct.Selector.html
+    /// [`submit_command`]: #method.submit_command
+    /// [`ExtEventError::QueueFull`]: enum.ExtEventError.html#variant.QueueFull
+    pub fn submit_all<T: Any + Send>(
+        &self,
+        sel: Selector<T>,
+        batch: impl IntoIterator<Item = (T, Option<Target>)>,
+    ) -> Result<(), ExtEventError> {
+        let batch: Vec<ExtCommand> = batch
+            .into_iter()
+            .map(|(obj, target)| {
+                let obj = Box::new(obj) as Box<dyn Any + Send>;
+                (sel.symbol(), Some(obj), target)
+            })
+            .collect();
+        let queue = self.queue.upgrade().ok_or(ExtEventError::WindowClosed)?;
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(ExtEventError::WindowClosed);
+        }
+        if batch.is_empty() {
+            return Ok(());
+        }
+        {
+            let mut queue = queue.lock().map_err(|_| ExtEventError::WindowClosed)?;
+            if queue.len() + batch.len() > EXT_EVENT_QUEUE_CAPACITY {
+                return Err(ExtEventError::QueueFull);
+            }
+            queue.extend(batch);
+        }
+        self.wake();
+        Ok(())
+    }
+
+    /// Wake the application, to receive the commands in the queue.
+    fn wake(&self) {
         if let Some(handle) = self.handle.lock().unwrap().as_mut() {
             handle.schedule_idle(EXT_EVENT_IDLE_TOKEN);
         }
-        self.queue
-            .lock()
-            .map_err(|_| ExtEventError)?
-            .push_back((sel, obj, target));
-        Ok(())
     }
 }
 
 impl std::fmt::Display for ExtEventError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "Window missing for external event")
+        match self {
+            ExtEventError::WindowClosed => write!(f, "Window missing for external event"),
+            ExtEventError::QueueFull => write!(f, "Too many external events are pending"),
+        }
     }
 }
 
 impl std::error::Error for ExtEventError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PING: Selector<u32> = Selector::new("druid-test.ping");
+
+    #[test]
+    fn submit_reports_failures() {
+        let mut host = ExtEventHost::new();
+        let sink = host.make_sink();
+        for n in 0..EXT_EVENT_QUEUE_CAPACITY {
+            sink.submit_command(PING, n as u32, None).unwrap();
+        }
+        assert_eq!(
+            sink.submit_command(PING, 0, None),
+            Err(ExtEventError::QueueFull)
+        );
+
+        // once the application catches up there is room again.
+        assert!(host.recv().is_some());
+        assert_eq!(sink.submit_command(PING, 0, None), Ok(()));
+
+        // with no window left, commands are refused until one opens.
+        host.close();
+        assert_eq!(
+            sink.submit_command(PING, 0, None),
+            Err(ExtEventError::WindowClosed)
+        );
+
+        drop(host);
+        assert_eq!(
+            sink.submit_command(PING, 0, None),
+            Err(ExtEventError::WindowClosed)
+        );
+    }
+
+    #[test]
+    fn batches_keep_their_order() {
+        let mut host = ExtEventHost::new();
+        let sink = host.make_sink();
+        let target = Some(Target::Global);
+        sink.submit_all(PING, (0..3).map(|n| (n, target))).unwrap();
+        sink.submit_command(PING, 3, None).unwrap();
+        let pings: Vec<_> = std::iter::from_fn(|| host.recv())
+            .map(|(_, cmd)| *cmd.get(PING).unwrap())
+            .collect();
+        assert_eq!(pings, vec![0, 1, 2, 3]);
+
+        // a batch that doesn't fit is refused whole.
+        let batch = (0..EXT_EVENT_QUEUE_CAPACITY as u32).map(|n| (n, None));
+        sink.submit_all(PING, batch).unwrap();
+        assert_eq!(
+            sink.submit_all(PING, vec![(0, None), (1, None)]),
+            Err(ExtEventError::QueueFull)
+        );
+        host.recv();
+        assert_eq!(sink.submit_all(PING, vec![(0, None)]), Ok(()));
+
+        host.close();
+        assert_eq!(
+            sink.submit_all(PING, vec![(0, None)]),
+            Err(ExtEventError::WindowClosed)
+        );
+        host.open();
+        host.recv();
+        assert_eq!(sink.submit_all(PING, vec![(0, None)]), Ok(()));
+
+        drop(host);
+        assert_eq!(
+            sink.submit_all(PING, vec![(0, None)]),
+            Err(ExtEventError::WindowClosed)
+        );
+    }
+}
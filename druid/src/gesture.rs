@@ -0,0 +1,265 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Recognition of high-level gestures from raw input events.
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use crate::kurbo::{Point, Vec2};
+use crate::{Event, MouseButton, TimerToken, WheelEvent, WheelUnit, WinCtx};
+
+/// How far the pointer can move before a press becomes a drag.
+const SLOP: f64 = 4.0;
+/// How long a press must be held, without moving, to be a long press.
+const LONG_PRESS_DELAY: Duration = Duration::from_millis(500);
+/// The longest a press can last and still be a swipe.
+const SWIPE_MAX_DURATION: Duration = Duration::from_millis(300);
+/// The shortest distance a swipe can cover.
+const SWIPE_MIN_DISTANCE: f64 = 50.0;
+/// The scale applied by one notch of the wheel when it is used to pinch.
+const WHEEL_PINCH_STEP: f64 = 1.1;
+
+/// The vertical movement of a wheel event, in notches of a mouse wheel.
+///
+/// A notch scrolls three lines, or 120 pixels.
+fn wheel_notches(wheel: &WheelEvent) -> f64 {
+    match wheel.unit {
+        WheelUnit::Pixels => wheel.delta.y / 120.0,
+        WheelUnit::Lines => wheel.delta.y / 3.0,
+        WheelUnit::Pages => wheel.delta.y,
+    }
+}
+
+/// A gesture recognized from the raw input events of a window.
+///
+/// Gestures are delivered as [`Event::Gesture`], immediately after the raw
+/// event that completed them; the raw events are still delivered as usual.
+/// A window only recognizes the gestures that were enabled with
+/// [`WindowDesc::gestures`].
+///
+/// [`Event::Gesture`]: enum.Event.html#variant.Gesture
+/// [`WindowDesc::gestures`]: struct.WindowDesc.html#method.gestures
+#[derive(Debug, Clone, PartialEq)]
+pub enum Gesture {
+    /// The pointer was dragged with the left button held down.
+    ///
+    /// `delta` is the movement since the previous `Pan` of the same drag.
+    Pan {
+        /// The position of the pointer, in the widget's coordinate space.
+        pos: Point,
+        /// The movement since the last event.
+        delta: Vec2,
+    },
+    /// The trackpad was pinched, or the wheel was turned with ctrl held.
+    ///
+    /// `scale` is the change since the previous `Pinch`; values above `1.0`
+    /// mean zooming in.
+    Pinch {
+        /// The relative change in scale.
+        scale: f64,
+    },
+    /// The left button was held down, without moving, for half a second.
+    LongPress {
+        /// The position of the pointer, in the widget's coordinate space.
+        pos: Point,
+    },
+    /// The pointer was flicked with the left button held down.
+    Swipe(SwipeDirection),
+}
+
+/// The direction of a [`Gesture::Swipe`].
+///
+/// [`Gesture::Swipe`]: enum.Gesture.html#variant.Swipe
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwipeDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// The gestures a window recognizes.
+///
+/// Recognition is opt-in; by default, no gestures are enabled.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GestureSet {
+    /// Recognize [`Gesture::Pan`](enum.Gesture.html#variant.Pan).
+    pub pan: bool,
+    /// Recognize [`Gesture::Pinch`](enum.Gesture.html#variant.Pinch).
+    pub pinch: bool,
+    /// Recognize [`Gesture::LongPress`](enum.Gesture.html#variant.LongPress).
+    pub long_press: bool,
+    /// Recognize [`Gesture::Swipe`](enum.Gesture.html#variant.Swipe).
+    pub swipe: bool,
+}
+
+impl GestureSet {
+    /// Recognize every gesture.
+    pub const ALL: GestureSet = GestureSet {
+        pan: true,
+        pinch: true,
+        long_press: true,
+        swipe: true,
+    };
+}
+
+impl Gesture {
+    /// The gesture, with any position moved by `offset`.
+    pub(crate) fn offset(&self, offset: Vec2) -> Gesture {
+        match self {
+            Gesture::Pan { pos, delta } => Gesture::Pan {
+                pos: *pos + offset,
+                delta: *delta,
+            },
+            Gesture::LongPress { pos } => Gesture::LongPress { pos: *pos + offset },
+            other => other.clone(),
+        }
+    }
+}
+
+/// The per-window gesture state machine.
+///
+/// It is fed every raw event the window receives. A left button press
+/// starts tracking; moving further than a few pixels turns the press into a
+/// drag, which produces `Pan`s, and releasing the button ends it, producing
+/// a `Swipe` if the drag was short and fast. A timer is requested when the
+/// press starts, and if it fires before the press has moved or ended, it
+/// produces a `LongPress`. Pinches need no state: each `Zoom`, or wheel
+/// event with ctrl held, is translated directly.
+#[derive(Default)]
+pub(crate) struct GestureRecognizer {
+    enabled: GestureSet,
+    press: Option<Press>,
+    /// The long press timer for the current press, if it is still live.
+    long_press: Option<TimerToken>,
+    /// Every long press timer that has not fired yet, including stale ones.
+    timers: HashSet<TimerToken>,
+}
+
+struct Press {
+    start: Point,
+    last: Point,
+    time: Instant,
+    dragging: bool,
+}
+
+impl GestureRecognizer {
+    pub(crate) fn new(enabled: GestureSet) -> Self {
+        GestureRecognizer {
+            enabled,
+            ..Default::default()
+        }
+    }
+
+    /// Feed a raw event to the recognizer; returns the gesture it completes.
+    pub(crate) fn event(
+        &mut self,
+        event: &Event,
+        now: Instant,
+        win_ctx: &mut dyn WinCtx,
+    ) -> Option<Gesture> {
+        if self.enabled == GestureSet::default() {
+            return None;
+        }
+        match event {
+            Event::MouseDown(mouse) if mouse.button == MouseButton::Left => {
+                self.press = Some(Press {
+                    start: mouse.pos,
+                    last: mouse.pos,
+                    time: now,
+                    dragging: false,
+                });
+                if self.enabled.long_press {
+                    let token = win_ctx.request_timer(now + LONG_PRESS_DELAY);
+                    self.timers.insert(token);
+                    self.long_press = Some(token);
+                }
+                None
+            }
+            Event::MouseMoved(mouse) => {
+                let press = self.press.as_mut()?;
+                if !press.dragging && (mouse.pos - press.start).hypot() > SLOP {
+                    press.dragging = true;
+                    self.long_press = None;
+                }
+                let delta = mouse.pos - press.last;
+                press.last = mouse.pos;
+                if press.dragging && self.enabled.pan {
+                    Some(Gesture::Pan {
+                        pos: mouse.pos,
+                        delta,
+                    })
+                } else {
+                    None
+                }
+            }
+            Event::MouseUp(mouse) if mouse.button == MouseButton::Left => {
+                self.long_press = None;
+                let press = self.press.take()?;
+                let travel = mouse.pos - press.start;
+                let quick = now.duration_since(press.time) <= SWIPE_MAX_DURATION;
+                if self.enabled.swipe && quick && travel.hypot() >= SWIPE_MIN_DISTANCE {
+                    Some(Gesture::Swipe(swipe_direction(travel)))
+                } else {
+                    None
+                }
+            }
+            Event::Zoom(delta) if self.enabled.pinch => Some(Gesture::Pinch { scale: 1.0 + delta }),
+            Event::Wheel(wheel) if self.enabled.pinch && wheel.mods.ctrl => {
+                // scrolling down, away from the screen, zooms out.
+                let notches = -wheel_notches(wheel);
+                Some(Gesture::Pinch {
+                    scale: WHEEL_PINCH_STEP.powf(notches),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if this timer was requested by the recognizer.
+    pub(crate) fn owns(&self, token: TimerToken) -> bool {
+        self.timers.contains(&token)
+    }
+
+    /// Called when one of our timers fires.
+    ///
+    /// Returns a `LongPress` if the press that requested the timer is still
+    /// held, and has not moved.
+    pub(crate) fn timer(&mut self, token: TimerToken) -> Option<Gesture> {
+        self.timers.remove(&token);
+        if self.long_press != Some(token) {
+            return None;
+        }
+        self.long_press = None;
+        self.press
+            .as_ref()
+            .map(|press| Gesture::LongPress { pos: press.last })
+    }
+}
+
+/// The direction of a swipe that covered `travel`, by its dominant axis.
+fn swipe_direction(travel: Vec2) -> SwipeDirection {
+    if travel.x.abs() >= travel.y.abs() {
+        if travel.x < 0.0 {
+            SwipeDirection::Left
+        } else {
+            SwipeDirection::Right
+        }
+    } else if travel.y < 0.0 {
+        SwipeDirection::Up
+    } else {
+        SwipeDirection::Down
+    }
+}
@@ -0,0 +1,136 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Recurring timers that are re-armed by the framework, and timer cancellation.
+
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use crate::shell::Counter;
+use crate::{TimerToken, WinCtx};
+
+/// The registry of interval timers for a window.
+///
+/// The platform only knows about one-shot timers, so each tick of an
+/// interval is scheduled as a separate platform timer. The token handed out
+/// to the caller stays the same for the lifetime of the interval; when one of
+/// our platform timers fires it is translated back to that token, and the
+/// next tick is queued to be armed.
+///
+/// Interval tokens are allocated counting down from the top of the `u64`
+/// range, so that they cannot collide with tokens issued by the platform.
+///
+/// One-shot timers requested by widgets are tracked here as well, so that
+/// they can be cancelled: the platform cannot take back a timer, so a
+/// cancelled token is remembered until it fires, and then dropped.
+///
+/// Everything here belongs to a single window, and is dropped with it; any
+/// platform timers still outstanding die with the window's handler.
+#[derive(Default)]
+pub(crate) struct IntervalTimers {
+    intervals: HashMap<TimerToken, Interval>,
+    /// Intervals whose next tick has not been scheduled with the platform yet.
+    unarmed: Vec<TimerToken>,
+    /// Maps each outstanding platform timer to the interval it belongs to.
+    ///
+    /// Entries for cancelled intervals are kept until their timer fires, so
+    /// that the stale tick can be dropped.
+    scheduled: HashMap<TimerToken, TimerToken>,
+    /// One-shot timers that have not fired yet.
+    once: HashSet<TimerToken>,
+    /// One-shot timers that were cancelled before they fired.
+    cancelled: HashSet<TimerToken>,
+}
+
+struct Interval {
+    interval: Duration,
+    deadline: Instant,
+}
+
+impl IntervalTimers {
+    /// Register a new interval timer, with its first tick at `now + interval`.
+    ///
+    /// The timer is not scheduled until [`arm`] is called.
+    ///
+    /// [`arm`]: #method.arm
+    pub(crate) fn request(&mut self, interval: Duration, now: Instant) -> TimerToken {
+        static INTERVAL_COUNTER: Counter = Counter::new();
+        let token = TimerToken::from_raw(u64::MAX - INTERVAL_COUNTER.next());
+        let deadline = now + interval;
+        self.intervals
+            .insert(token, Interval { interval, deadline });
+        self.unarmed.push(token);
+        token
+    }
+
+    /// Track a one-shot timer that was requested from the platform, so that
+    /// it can be cancelled.
+    pub(crate) fn track_once(&mut self, token: TimerToken) {
+        self.once.insert(token);
+    }
+
+    /// Stop an interval timer, or a one-shot timer that has not fired.
+    ///
+    /// Returns `false` if this token does not belong to a pending timer.
+    pub(crate) fn cancel(&mut self, token: TimerToken) -> bool {
+        self.unarmed.retain(|t| *t != token);
+        if self.intervals.remove(&token).is_some() {
+            true
+        } else if self.once.remove(&token) {
+            self.cancelled.insert(token);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Schedule the next tick of every interval that is waiting for one.
+    pub(crate) fn arm(&mut self, win_ctx: &mut dyn WinCtx) {
+        for token in self.unarmed.drain(..) {
+            if let Some(interval) = self.intervals.get(&token) {
+                let platform_token = win_ctx.request_timer(interval.deadline);
+                self.scheduled.insert(platform_token, token);
+            }
+        }
+    }
+
+    /// Returns `true` if this platform timer is tracked here.
+    pub(crate) fn owns(&self, platform_token: TimerToken) -> bool {
+        self.scheduled.contains_key(&platform_token)
+            || self.once.contains(&platform_token)
+            || self.cancelled.contains(&platform_token)
+    }
+
+    /// Called when one of our platform timers fires.
+    ///
+    /// Returns the token of the interval that ticked, unless it has been
+    /// cancelled, and queues its next tick. A one-shot timer is returned
+    /// as-is, unless it has been cancelled. Ticks are scheduled relative to
+    /// the previous deadline so that they do not drift; if we have fallen
+    /// behind `now`, the missed ticks are skipped rather than delivered in
+    /// a burst.
+    pub(crate) fn fire(&mut self, platform_token: TimerToken, now: Instant) -> Option<TimerToken> {
+        if self.cancelled.remove(&platform_token) {
+            return None;
+        }
+        if self.once.remove(&platform_token) {
+            return Some(platform_token);
+        }
+        let token = self.scheduled.remove(&platform_token)?;
+        let interval = self.intervals.get_mut(&token)?;
+        interval.deadline = (interval.deadline + interval.interval).max(now);
+        self.unarmed.push(token);
+        Some(token)
+    }
+}
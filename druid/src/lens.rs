@@ -42,10 +42,12 @@
 //!     // ...
 //!
 //!     // We can now use `searchbar` just like any other `Widget<MyState>`
-//!     Flex::column().with_child(searchbar, 1.0)
+//!     Flex::column().with_flex_child(searchbar, 1.0)
 //! }
 //! ```
 
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
 use std::marker::PhantomData;
 use std::ops;
 use std::sync::Arc;
@@ -149,6 +151,31 @@ pub trait LensExt<A: ?Sized, B: ?Sized>: Lens<A, B> {
         self.then(Map::new(get, put))
     }
 
+    /// Combine a `Lens<A, B>` with a function that computes a `C` from a `B`,
+    /// for widgets that only display the value.
+    ///
+    /// This is [`map`] without the inverse. Writes through the resulting lens
+    /// are discarded, and logged as a warning if they changed the value.
+    ///
+    /// ```
+    /// # use druid::*;
+    /// let lens = lens!((bool, u32), 1).read_only(|x| format!("{} items", x));
+    /// let mut data = (true, 3);
+    /// assert_eq!(lens.get(&data), "3 items");
+    /// lens.put(&mut data, "5 items".into());
+    /// assert_eq!(data.1, 3);
+    /// ```
+    ///
+    /// [`map`]: #method.map
+    fn read_only<Get, C>(self, get: Get) -> Then<Self, ReadOnly<Get>, B>
+    where
+        Get: Fn(&B) -> C,
+        C: Data,
+        Self: Sized,
+    {
+        self.then(ReadOnly::new(get))
+    }
+
     /// Invoke a type's `Deref` impl
     ///
     /// ```
@@ -442,6 +469,44 @@ where
     }
 }
 
+/// `Lens` built from a getter alone, which ignores writes
+///
+/// See also `LensExt::read_only`.
+#[derive(Debug, Copy, Clone)]
+pub struct ReadOnly<Get> {
+    get: Get,
+}
+
+impl<Get> ReadOnly<Get> {
+    /// Construct a read-only mapping
+    pub fn new<A: ?Sized, B>(get: Get) -> Self
+    where
+        Get: Fn(&A) -> B,
+    {
+        Self { get }
+    }
+}
+
+impl<A: ?Sized, B, Get> Lens<A, B> for ReadOnly<Get>
+where
+    Get: Fn(&A) -> B,
+    B: Data,
+{
+    fn with<V, F: FnOnce(&B) -> V>(&self, data: &A, f: F) -> V {
+        f(&(self.get)(data))
+    }
+
+    fn with_mut<V, F: FnOnce(&mut B) -> V>(&self, data: &mut A, f: F) -> V {
+        let original = (self.get)(data);
+        let mut temp = original.clone();
+        let x = f(&mut temp);
+        if !temp.same(&original) {
+            log::warn!("a write through a read-only lens was discarded");
+        }
+        x
+    }
+}
+
 /// `Lens` for invoking `Deref` and `DerefMut` on a type
 ///
 /// See also `LensExt::deref`.
@@ -488,6 +553,60 @@ where
     }
 }
 
+/// `Lens` for the value at a particular key of a `HashMap`
+///
+/// The value is seen as an `Option`: `None` if the key is absent. Writing
+/// `Some` inserts the value, replacing any old one, and writing `None`
+/// removes the key.
+///
+/// ```
+/// # use druid::*; use std::collections::HashMap;
+/// let lens = lens::MapKey::new("b");
+/// let mut map = HashMap::new();
+/// map.insert("a", 1);
+/// assert_eq!(lens.get(&map), None);
+/// lens.put(&mut map, Some(2));
+/// assert_eq!(map["b"], 2);
+/// lens.with_mut(&mut map, |value| *value = None);
+/// assert!(!map.contains_key("b"));
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct MapKey<K> {
+    key: K,
+}
+
+impl<K> MapKey<K> {
+    /// Construct a lens that accesses the value at `key`
+    pub fn new(key: K) -> Self {
+        Self { key }
+    }
+}
+
+impl<K, V, S> Lens<HashMap<K, V, S>, Option<V>> for MapKey<K>
+where
+    K: Hash + Eq + Clone,
+    V: Clone,
+    S: BuildHasher,
+{
+    fn with<R, F: FnOnce(&Option<V>) -> R>(&self, data: &HashMap<K, V, S>, f: F) -> R {
+        f(&data.get(&self.key).cloned())
+    }
+
+    fn with_mut<R, F: FnOnce(&mut Option<V>) -> R>(&self, data: &mut HashMap<K, V, S>, f: F) -> R {
+        let mut temp = data.get(&self.key).cloned();
+        let r = f(&mut temp);
+        match temp {
+            Some(value) => {
+                data.insert(self.key.clone(), value);
+            }
+            None => {
+                data.remove(&self.key);
+            }
+        }
+        r
+    }
+}
+
 /// The identity lens: the lens which does nothing, i.e. exposes exactly the original value.
 ///
 /// Useful for starting a lens combinator chain, or passing to lens-based interfaces.
@@ -21,24 +21,35 @@
 use druid_shell as shell;
 pub use druid_shell::{kurbo, piet};
 
+mod accessibility;
+pub mod animation;
 mod app;
 mod app_delegate;
+mod autosave;
 mod bloom;
 mod box_constraints;
 mod command;
 mod contexts;
 mod core;
 mod data;
+mod debounce;
 mod env;
 mod event;
 mod ext_event;
+mod gesture;
+mod interval;
 pub mod lens;
+mod local_state;
 mod localization;
 mod menu;
+mod modal;
 mod mouse;
+mod offscreen;
+mod overlay;
+mod retry;
 #[cfg(test)]
 mod tests;
-mod text;
+pub mod text;
 pub mod theme;
 pub mod widget;
 mod win_handler;
@@ -50,24 +61,35 @@ pub use piet::{Color, LinearGradient, PaintBrush, RadialGradient, RenderContext,
 // these are the types from shell that we expose; others we only use internally.
 pub use shell::{
     Application, Clipboard, ClipboardFormat, Cursor, Error as PlatformError, FileDialogOptions,
-    FileInfo, FileSpec, FormatId, HotKey, KeyCode, KeyEvent, KeyModifiers, MouseButton, RawMods,
-    SysMods, Text, TimerToken, WinCtx, WindowHandle,
+    FileDialogToken, FileInfo, FileSpec, FormatId, HotKey, KeyCode, KeyEvent, KeyModifiers,
+    Monitor, MouseButton, RawMods, Screen, SysMods, Text, TimerToken, WheelPhase, WheelUnit,
+    WinCtx, WindowHandle,
 };
+#[cfg(feature = "raw-win-handle")]
+pub use shell::{HasRawWindowHandle, RawWindowHandle};
 
 pub use crate::core::{BoxedWidget, WidgetPod};
-pub use app::{AppLauncher, WindowDesc};
-pub use app_delegate::{AppDelegate, DelegateCtx};
+pub use accessibility::{AccessNode, AccessibilityCollector, Role};
+pub use app::{AnyWindowDesc, AppLauncher, EventFilterResult, WindowDesc, WindowPosition};
+pub use app_delegate::{AppDelegate, DelegateCtx, Handled};
 pub use box_constraints::BoxConstraints;
-pub use command::{sys as commands, Command, Selector, Target};
+pub use command::{sys as commands, Command, Selector, SelectorSymbol, Target, WrongDataType};
 pub use contexts::{EventCtx, LayoutCtx, LifeCycleCtx, PaintCtx, UpdateCtx};
-pub use data::Data;
+pub use data::{Data, DataVersion};
 pub use env::{Env, Key, Value};
-pub use event::{Event, LifeCycle, WheelEvent};
+pub use event::{
+    BoundsCollector, Event, FileDropEvent, LifeCycle, MouseRoute, WheelEvent, WidgetBounds,
+};
 pub use ext_event::{ExtEventError, ExtEventSink};
+pub use gesture::{Gesture, GestureSet, SwipeDirection};
 pub use lens::{Lens, LensExt, LensWrap};
 pub use localization::LocalizedString;
-pub use menu::{sys as platform_menus, ContextMenu, MenuDesc, MenuItem};
+pub use menu::{
+    sys as platform_menus, AnyContextMenu, AnyMenuDesc, ContextMenu, MenuDesc, MenuItem,
+};
+pub use modal::{ModalResult, ModalValue};
 pub use mouse::MouseEvent;
+pub use offscreen::Snapshot;
 pub use widget::{Widget, WidgetId};
 pub use win_handler::DruidHandler;
 pub use window::{Window, WindowId};
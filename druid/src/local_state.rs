@@ -0,0 +1,92 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! State that belongs to a single window.
+
+use std::marker::PhantomData;
+
+use crate::kurbo::Size;
+use crate::{
+    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    UpdateCtx, Widget, WidgetId,
+};
+
+/// The root of a window with local state, made by
+/// [`WindowDesc::with_local_state`].
+///
+/// The child sees the application data and the window's own state as a
+/// pair; changes to either are written back to where they belong.
+///
+/// [`WindowDesc::with_local_state`]: struct.WindowDesc.html#method.with_local_state
+pub(crate) struct LocalState<T, L, W> {
+    local: L,
+    /// The local state at the last update.
+    old_local: L,
+    child: W,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Data, L: Data, W: Widget<(T, L)>> LocalState<T, L, W> {
+    pub(crate) fn new(local: L, child: W) -> Self {
+        LocalState {
+            old_local: local.clone(),
+            local,
+            child,
+            phantom: Default::default(),
+        }
+    }
+
+    fn with_local(&self, data: &T) -> (T, L) {
+        (data.clone(), self.local.clone())
+    }
+}
+
+impl<T: Data, L: Data, W: Widget<(T, L)>> Widget<T> for LocalState<T, L, W> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        let mut pair = self.with_local(data);
+        self.child.event(ctx, event, &mut pair, env);
+        let (new_data, new_local) = pair;
+        if !new_data.same(data) {
+            *data = new_data;
+        }
+        if !new_local.same(&self.local) {
+            self.local = new_local;
+            // the application data may be unchanged; update us anyway.
+            ctx.base_state.needs_update = true;
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.child
+            .lifecycle(ctx, event, &self.with_local(data), env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        let old = (old_data.clone(), self.old_local.clone());
+        self.child.update(ctx, &old, &self.with_local(data), env);
+        self.old_local = self.local.clone();
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        self.child.layout(ctx, bc, &self.with_local(data), env)
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.child.paint(ctx, &self.with_local(data), env)
+    }
+
+    fn id(&self) -> Option<WidgetId> {
+        self.child.id()
+    }
+}
@@ -58,10 +58,7 @@ use unic_langid::LanguageIdentifier;
 static FALLBACK_STRINGS: &str = include_str!("../resources/i18n/en-US/builtin.ftl");
 
 /// Provides access to the localization strings for the current locale.
-#[allow(dead_code)]
 pub(crate) struct L10nManager {
-    // these two are not currently used; will be used when we let the user
-    // add additional localization files.
     res_mgr: ResourceManager,
     resources: Vec<String>,
     current_bundle: BundleStack,
@@ -69,6 +66,7 @@ pub(crate) struct L10nManager {
 }
 
 /// Manages a collection of localization files.
+#[derive(Clone)]
 struct ResourceManager {
     resources: HashMap<String, Arc<FluentResource>>,
     locales: Vec<LanguageIdentifier>,
@@ -288,7 +286,20 @@ impl L10nManager {
             Some(result)
         }
     }
-    //TODO: handle locale change
+
+    /// A manager for the same resources, in another locale.
+    ///
+    /// The locale is resolved against the available ones, as at startup.
+    pub(crate) fn with_locale(&self, locale: LanguageIdentifier) -> L10nManager {
+        let mut res_mgr = self.res_mgr.clone();
+        let current_bundle = res_mgr.get_bundle(&locale, &self.resources);
+        L10nManager {
+            res_mgr,
+            resources: self.resources.clone(),
+            current_bundle,
+            current_locale: locale,
+        }
+    }
 }
 
 impl<T> LocalizedString<T> {
@@ -32,8 +32,9 @@
 //!
 //! ## Changing the menu
 //!
-//! To change the menu for a window, you issue a [`SET_MENU`] command, the payload
-//! of which should be a new [`MenuDesc`]. The new menu will replace the old menu.
+//! To change the menu for a window, call [`EventCtx::set_menu`] or
+//! [`DelegateCtx::set_menu`] with a new [`MenuDesc`]; this issues a
+//! [`SET_MENU`] command, and the new menu will replace the old menu.
 //!
 //! ## The macOS app menu
 //!
@@ -103,13 +104,20 @@
 //! [`Command`]: ../struct.Command.html
 //! [`Command` event]: ../enum.Event.html#variant.Command
 //! [`Selector`]: ../struct.Selector.html
-//! [`SET_MENU`]: ../struct.Selector.html#associatedconstant.SET_MENU
+//! [`SET_MENU`]: ../commands/constant.SET_MENU.html
+//! [`EventCtx::set_menu`]: ../struct.EventCtx.html#method.set_menu
+//! [`DelegateCtx::set_menu`]: ../struct.DelegateCtx.html#method.set_menu
 
+use std::any::Any;
 use std::num::NonZeroU32;
+use std::sync::Arc;
 
 use crate::kurbo::Point;
 use crate::shell::{HotKey, KeyCompare, Menu as PlatformMenu, RawMods, SysMods};
-use crate::{commands, Command, Data, Env, KeyCode, LocalizedString, Selector};
+use crate::{
+    commands, Command, Data, Env, FileDialogOptions, KeyCode, Lens, LensExt, LocalizedString,
+    Selector,
+};
 
 /// A platform-agnostic description of an application, window, or context
 /// menu.
@@ -135,8 +143,12 @@ pub enum MenuEntry<T> {
 /// that is sent to the application when the item is selected.
 ///
 /// In addition, other properties can be set during construction, such as whether
-/// the item is selected (checked), or enabled, or if it has a hotkey.
+/// the item is selected (checked), or enabled, or if it has a hotkey. Whether
+/// the item is enabled or selected can also depend on the application data;
+/// see [`enabled_when`] and [`selected_when`].
 ///
+/// [`enabled_when`]: #method.enabled_when
+/// [`selected_when`]: #method.selected_when
 /// [`LocalizedString`]: ../struct.LocalizedString.html
 /// [`Command`]: ../struct.Command.html
 #[derive(Debug, Clone)]
@@ -148,10 +160,28 @@ pub struct MenuItem<T> {
     //highlighted: bool,
     selected: bool,
     enabled: bool, // (or state is stored elsewhere)
+    selected_when: Option<MenuPredicate<T>>,
+    enabled_when: Option<MenuPredicate<T>>,
+    /// Run on the data when the item is chosen, before its command is sent.
+    action: Option<MenuAction<T>>,
+    /// The `(enabled, selected)` state the platform item was built with.
+    built_state: Option<(bool, bool)>,
     /// Identifies the platform object corresponding to this item.
     platform_id: MenuItemId,
 }
 
+type PredicateClosure<T> = Arc<dyn Fn(&T, &Env) -> bool + 'static>;
+
+/// Wraps a closure that decides the state of a menu item from the data.
+#[derive(Clone)]
+struct MenuPredicate<T>(PredicateClosure<T>);
+
+type ActionClosure<T> = Arc<dyn Fn(&mut T, &Env) + 'static>;
+
+/// Wraps a closure that changes the data when a menu item is chosen.
+#[derive(Clone)]
+struct MenuAction<T>(ActionClosure<T>);
+
 /// A menu displayed as a pop-over.
 #[derive(Debug, Clone)]
 pub struct ContextMenu<T> {
@@ -159,6 +189,27 @@ pub struct ContextMenu<T> {
     pub(crate) location: Point,
 }
 
+/// A [`MenuDesc`] for any type of data, the argument of [`SET_MENU`].
+///
+/// A selector is a constant, so the type of its argument can't depend on the
+/// application's data. This is only created by [`EventCtx::set_menu`] and
+/// [`DelegateCtx::set_menu`], which check that the menu is for the
+/// application's data.
+///
+/// [`MenuDesc`]: struct.MenuDesc.html
+/// [`SET_MENU`]: commands/constant.SET_MENU.html
+/// [`EventCtx::set_menu`]: struct.EventCtx.html#method.set_menu
+/// [`DelegateCtx::set_menu`]: struct.DelegateCtx.html#method.set_menu
+pub struct AnyMenuDesc(Box<dyn Any>);
+
+/// A [`ContextMenu`] for any type of data, the argument of
+/// [`SHOW_CONTEXT_MENU`]; see [`AnyMenuDesc`].
+///
+/// [`ContextMenu`]: struct.ContextMenu.html
+/// [`SHOW_CONTEXT_MENU`]: commands/constant.SHOW_CONTEXT_MENU.html
+/// [`AnyMenuDesc`]: struct.AnyMenuDesc.html
+pub struct AnyContextMenu(Box<dyn Any>);
+
 /// Uniquely identifies a menu item.
 ///
 /// On the druid-shell side, the id is represented as a u32.
@@ -178,6 +229,10 @@ impl<T> MenuItem<T> {
             tool_tip: None,
             selected: false,
             enabled: true,
+            selected_when: None,
+            enabled_when: None,
+            action: None,
+            built_state: None,
             platform_id: MenuItemId::PLACEHOLDER,
         }
     }
@@ -228,6 +283,81 @@ impl<T> MenuItem<T> {
         }
         self
     }
+
+    /// Enable this item only while the provided predicate is true.
+    ///
+    /// The predicate is evaluated with the current data whenever the menu
+    /// is built, and again after each update; if its result has changed,
+    /// the window's menu is rebuilt.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use druid::{platform_menus, MenuDesc};
+    /// // a "Paste" item that is grayed out when there is nothing to paste.
+    /// let paste = platform_menus::common::paste()
+    ///     .enabled_when(|clipboard: &Option<String>, _| clipboard.is_some());
+    /// # MenuDesc::empty().append(paste);
+    /// ```
+    pub fn enabled_when(mut self, p: impl Fn(&T, &Env) -> bool + 'static) -> Self {
+        self.enabled_when = Some(MenuPredicate(Arc::new(p)));
+        self
+    }
+
+    /// Mark this item as selected only while the provided predicate is true.
+    ///
+    /// Like [`enabled_when`], the predicate is evaluated with the current
+    /// data whenever the menu is built or updated.
+    ///
+    /// [`enabled_when`]: #method.enabled_when
+    pub fn selected_when(mut self, p: impl Fn(&T, &Env) -> bool + 'static) -> Self {
+        self.selected_when = Some(MenuPredicate(Arc::new(p)));
+        self
+    }
+
+    /// The command sent when this item is chosen.
+    pub(crate) fn command(&self) -> &Command {
+        &self.command
+    }
+
+    /// Change the data as this item was chosen, if it does that.
+    pub(crate) fn activate(&self, data: &mut T, env: &Env) {
+        if let Some(action) = &self.action {
+            (action.0)(data, env);
+        }
+    }
+
+    /// Whether this item is enabled, given the data.
+    fn is_enabled(&self, data: &T, env: &Env) -> bool {
+        match self.enabled_when {
+            Some(ref p) => self.enabled && (p.0)(data, env),
+            None => self.enabled,
+        }
+    }
+
+    /// Whether this item is selected, given the data.
+    fn is_selected(&self, data: &T, env: &Env) -> bool {
+        match self.selected_when {
+            Some(ref p) => self.selected || (p.0)(data, env),
+            None => self.selected,
+        }
+    }
+
+    /// The state to build the platform item with, remembering it so that
+    /// changes can be detected.
+    fn resolve_state(&mut self, data: &T, env: &Env) -> (bool, bool) {
+        let state = (self.is_enabled(data, env), self.is_selected(data, env));
+        self.built_state = Some(state);
+        state
+    }
+
+    /// Returns `true` if the state of this item has changed since it was built.
+    fn state_changed(&self, data: &T, env: &Env) -> bool {
+        if self.enabled_when.is_none() && self.selected_when.is_none() {
+            return false;
+        }
+        self.built_state != Some((self.is_enabled(data, env), self.is_selected(data, env)))
+    }
 }
 
 impl<T: Data> MenuDesc<T> {
@@ -268,7 +398,7 @@ impl<T: Data> MenuDesc<T> {
     /// use druid::{Command, LocalizedString, MenuDesc, MenuItem, Selector};
     ///
     /// let num_items: usize = 4;
-    /// const MENU_COUNT_ACTION: Selector = Selector::new("menu-count-action");
+    /// const MENU_COUNT_ACTION: Selector<usize> = Selector::new("menu-count-action");
     ///
     /// let my_menu: MenuDesc<u32> = MenuDesc::empty()
     ///     .append_iter(|| (0..num_items).map(|i| {
@@ -302,6 +432,53 @@ impl<T: Data> MenuDesc<T> {
         self
     }
 
+    /// Append a group of mutually exclusive items, each of which stands for
+    /// a value of the field that `lens` points to.
+    ///
+    /// The item for the current value is shown as selected. Choosing an
+    /// item sets the field to its value, and then sends the item's command
+    /// as usual; the menu is rebuilt to show the new selection.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use druid::{lens, LocalizedString, MenuDesc, MenuItem, Selector};
+    ///
+    /// const ZOOM_CHANGED: Selector = Selector::new("zoom-changed");
+    ///
+    /// let zoom_menu: MenuDesc<f64> = MenuDesc::new(LocalizedString::new("Zoom"))
+    ///     .append_radio_group(
+    ///         lens::Id,
+    ///         vec![
+    ///             (MenuItem::new(LocalizedString::new("50%"), ZOOM_CHANGED), 0.5),
+    ///             (MenuItem::new(LocalizedString::new("100%"), ZOOM_CHANGED), 1.0),
+    ///             (MenuItem::new(LocalizedString::new("200%"), ZOOM_CHANGED), 2.0),
+    ///         ],
+    ///     );
+    ///
+    /// assert_eq!(zoom_menu.len(), 3);
+    /// ```
+    pub fn append_radio_group<V, L>(
+        mut self,
+        lens: L,
+        items: impl IntoIterator<Item = (MenuItem<T>, V)>,
+    ) -> Self
+    where
+        V: Data,
+        L: Lens<T, V> + Clone + 'static,
+    {
+        for (item, value) in items {
+            let (get_lens, get_value) = (lens.clone(), value.clone());
+            let (put_lens, put_value) = (lens.clone(), value);
+            let mut item = item.selected_when(move |data, _| get_lens.get(data).same(&get_value));
+            item.action = Some(MenuAction(Arc::new(move |data, _| {
+                put_lens.put(data, put_value.clone())
+            })));
+            self.items.push(item.into());
+        }
+        self
+    }
+
     /// Append a separator.
     pub fn append_separator(mut self) -> Self {
         self.items.push(MenuEntry::Separator);
@@ -332,6 +509,18 @@ impl<T: Data> MenuDesc<T> {
         self.build_native_menu(data, env, true)
     }
 
+    /// Returns `true` if any item's enabled or selected state depends on the
+    /// data, and has changed since the menu was last built.
+    pub(crate) fn needs_rebuild(&self, data: &T, env: &Env) -> bool {
+        self.items.iter().any(|item| match item {
+            MenuEntry::Item(item) => item.state_changed(data, env),
+            MenuEntry::SubMenu(submenu) => {
+                submenu.item.state_changed(data, env) || submenu.needs_rebuild(data, env)
+            }
+            MenuEntry::Separator => false,
+        })
+    }
+
     /// impl shared for window & context menus
     fn build_native_menu(&mut self, data: &T, env: &Env, for_popup: bool) -> PlatformMenu {
         let mut menu = if for_popup {
@@ -344,40 +533,36 @@ impl<T: Data> MenuDesc<T> {
                 MenuEntry::Item(ref mut item) => {
                     item.title.resolve(data, env);
                     item.platform_id = MenuItemId::next();
+                    let (enabled, selected) = item.resolve_state(data, env);
                     menu.add_item(
                         item.platform_id.as_u32(),
                         item.title.localized_str(),
                         item.hotkey.as_ref(),
-                        item.enabled,
-                        item.selected,
+                        enabled,
+                        selected,
                     );
                 }
                 MenuEntry::Separator => menu.add_separator(),
                 MenuEntry::SubMenu(ref mut submenu) => {
                     let sub = submenu.build_native_menu(data, env, false);
                     submenu.item.title.resolve(data, env);
-                    menu.add_dropdown(
-                        sub,
-                        &submenu.item.title.localized_str(),
-                        submenu.item.enabled,
-                    );
+                    let (enabled, _) = submenu.item.resolve_state(data, env);
+                    menu.add_dropdown(sub, &submenu.item.title.localized_str(), enabled);
                 }
             }
         }
         menu
     }
 
-    /// Given a command identifier from druid-shell, returns the command
+    /// Given a command identifier from druid-shell, returns the item
     /// corresponding to that id in this menu, if one exists.
-    pub(crate) fn command_for_id(&self, id: u32) -> Option<Command> {
+    pub(crate) fn item_for_id(&self, id: u32) -> Option<&MenuItem<T>> {
         for item in &self.items {
             match item {
-                MenuEntry::Item(item) if item.platform_id.as_u32() == id => {
-                    return Some(item.command.clone())
-                }
+                MenuEntry::Item(item) if item.platform_id.as_u32() == id => return Some(item),
                 MenuEntry::SubMenu(menu) => {
-                    if let Some(cmd) = menu.command_for_id(id) {
-                        return Some(cmd);
+                    if let Some(item) = menu.item_for_id(id) {
+                        return Some(item);
                     }
                 }
                 _ => (),
@@ -393,6 +578,28 @@ impl<T> ContextMenu<T> {
     }
 }
 
+impl AnyMenuDesc {
+    pub(crate) fn new<T: 'static>(menu: MenuDesc<T>) -> Self {
+        AnyMenuDesc(Box::new(menu))
+    }
+
+    /// The menu, if it is for data of type `T`.
+    pub(crate) fn get<T: 'static>(&self) -> Option<&MenuDesc<T>> {
+        self.0.downcast_ref()
+    }
+}
+
+impl AnyContextMenu {
+    pub(crate) fn new<T: 'static>(menu: ContextMenu<T>) -> Self {
+        AnyContextMenu(Box::new(menu))
+    }
+
+    /// The menu, if it is for data of type `T`.
+    pub(crate) fn get<T: 'static>(&self) -> Option<&ContextMenu<T>> {
+        self.0.downcast_ref()
+    }
+}
+
 impl MenuItemId {
     /// The value for a menu item that has not been instantiated by
     /// the platform.
@@ -439,6 +646,18 @@ impl<T> std::fmt::Debug for MenuDesc<T> {
     }
 }
 
+impl<T> std::fmt::Debug for MenuPredicate<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Menu Predicate {:p}", self.0)
+    }
+}
+
+impl<T> std::fmt::Debug for MenuAction<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Menu Action {:p}", self.0)
+    }
+}
+
 impl<T> From<MenuItem<T>> for MenuEntry<T> {
     fn from(src: MenuItem<T>) -> MenuEntry<T> {
         MenuEntry::Item(src)
@@ -541,7 +760,7 @@ pub mod sys {
             pub fn open<T: Data>() -> MenuItem<T> {
                 MenuItem::new(
                     LocalizedString::new("common-menu-file-open"),
-                    commands::OPEN_FILE,
+                    Command::new(commands::SHOW_OPEN_PANEL, FileDialogOptions::default()),
                 )
                 .hotkey(RawMods::Ctrl, "o")
             }
@@ -558,7 +777,7 @@ pub mod sys {
             pub fn save<T: Data>() -> MenuItem<T> {
                 MenuItem::new(
                     LocalizedString::new("common-menu-file-save"),
-                    commands::SAVE_FILE,
+                    Command::new(commands::SAVE_FILE, None),
                 )
                 .hotkey(RawMods::Ctrl, "s")
             }
@@ -567,7 +786,7 @@ pub mod sys {
             pub fn save_ellipsis<T: Data>() -> MenuItem<T> {
                 MenuItem::new(
                     LocalizedString::new("common-menu-file-save"),
-                    commands::SAVE_FILE,
+                    Command::new(commands::SAVE_FILE, None),
                 )
                 .hotkey(RawMods::Ctrl, "s")
             }
@@ -576,7 +795,7 @@ pub mod sys {
             pub fn save_as<T: Data>() -> MenuItem<T> {
                 MenuItem::new(
                     LocalizedString::new("common-menu-file-save-as"),
-                    commands::SHOW_SAVE_PANEL,
+                    Command::new(commands::SHOW_SAVE_PANEL, FileDialogOptions::default()),
                 )
                 .hotkey(RawMods::CtrlShift, "s")
             }
@@ -742,7 +961,7 @@ pub mod sys {
             pub fn open_file<T: Data>() -> MenuItem<T> {
                 MenuItem::new(
                     LocalizedString::new("common-menu-file-open"),
-                    commands::OPEN_FILE,
+                    Command::new(commands::SHOW_OPEN_PANEL, FileDialogOptions::default()),
                 )
                 .hotkey(RawMods::Meta, "o")
             }
@@ -760,7 +979,7 @@ pub mod sys {
             pub fn save<T: Data>() -> MenuItem<T> {
                 MenuItem::new(
                     LocalizedString::new("common-menu-file-save"),
-                    commands::SAVE_FILE,
+                    Command::new(commands::SAVE_FILE, None),
                 )
                 .hotkey(RawMods::Meta, "s")
             }
@@ -771,7 +990,7 @@ pub mod sys {
             pub fn save_ellipsis<T: Data>() -> MenuItem<T> {
                 MenuItem::new(
                     LocalizedString::new("common-menu-file-save-ellipsis"),
-                    commands::SAVE_FILE,
+                    Command::new(commands::SAVE_FILE, None),
                 )
                 .hotkey(RawMods::Meta, "s")
             }
@@ -780,7 +999,7 @@ pub mod sys {
             pub fn save_as<T: Data>() -> MenuItem<T> {
                 MenuItem::new(
                     LocalizedString::new("common-menu-file-save-as"),
-                    commands::SHOW_SAVE_PANEL,
+                    Command::new(commands::SHOW_SAVE_PANEL, FileDialogOptions::default()),
                 )
                 .hotkey(RawMods::MetaShift, "s")
             }
@@ -805,3 +1024,62 @@ pub mod sys {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn item_state_follows_data() {
+        let env = Env::default();
+        let paste = sys::common::paste().enabled_when(|data: &u32, _| *data > 0);
+        let check = MenuItem::new(LocalizedString::new("check"), Selector::NOOP)
+            .selected_when(|data: &u32, _| *data % 2 == 1);
+        let mut menu = MenuDesc::empty().append(paste).append(check);
+
+        assert!(menu.needs_rebuild(&0, &env));
+        menu.build_window_menu(&0, &env);
+        assert!(!menu.needs_rebuild(&0, &env));
+        // enabled
+        assert!(menu.needs_rebuild(&2, &env));
+        menu.build_window_menu(&2, &env);
+        // selected
+        assert!(menu.needs_rebuild(&3, &env));
+        assert!(!menu.needs_rebuild(&4, &env));
+    }
+
+    #[test]
+    fn radio_group_selects_one_item() {
+        const CHOSEN: Selector = Selector::new("druid-tests.chosen");
+        let env = Env::default();
+        let item = |name| MenuItem::new(LocalizedString::new(name), CHOSEN);
+        let mut menu = MenuDesc::empty().append_radio_group(
+            crate::lens::Id,
+            vec![(item("one"), 1u32), (item("two"), 2), (item("three"), 3)],
+        );
+        let mut data = 1u32;
+        menu.build_window_menu(&data, &env);
+
+        let ids: Vec<u32> = menu
+            .items
+            .iter()
+            .map(|entry| match entry {
+                MenuEntry::Item(item) => item.platform_id.as_u32(),
+                _ => panic!("expected an item"),
+            })
+            .collect();
+        let selected = |menu: &MenuDesc<u32>, data: &u32| -> Vec<bool> {
+            ids.iter()
+                .map(|id| menu.item_for_id(*id).unwrap().is_selected(data, &env))
+                .collect()
+        };
+        assert_eq!(selected(&menu, &data), vec![true, false, false]);
+
+        let chosen = menu.item_for_id(ids[2]).unwrap();
+        chosen.activate(&mut data, &env);
+        assert!(chosen.command().is(CHOSEN));
+        assert_eq!(data, 3);
+        assert!(menu.needs_rebuild(&data, &env));
+        assert_eq!(selected(&menu, &data), vec![false, false, true]);
+    }
+}
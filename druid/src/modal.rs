@@ -0,0 +1,119 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Modal windows, and the nested event loops that run them.
+
+use std::any::Any;
+
+use crate::{Command, WindowId};
+
+/// The value a modal window passes back with [`END_MODAL`].
+///
+/// This can be of any type; the window that opened the modal gets it back
+/// with [`get`], asking for the type it expects.
+///
+/// [`END_MODAL`]: commands/constant.END_MODAL.html
+/// [`get`]: #method.get
+pub struct ModalValue(Box<dyn Any>);
+
+impl ModalValue {
+    /// Wrap a value to pass back from a modal window.
+    pub fn new<V: Any>(value: V) -> Self {
+        ModalValue(Box::new(value))
+    }
+
+    /// The value, if it is of type `V`.
+    pub fn get<V: Any>(&self) -> Option<&V> {
+        self.0.downcast_ref()
+    }
+}
+
+/// How a modal window was dismissed.
+///
+/// This is the argument of the [`MODAL_RESULT`] command.
+///
+/// [`MODAL_RESULT`]: commands/constant.MODAL_RESULT.html
+#[derive(Debug, Clone)]
+pub enum ModalResult {
+    /// The modal window submitted [`END_MODAL`]. This is that command, so
+    /// the [`ModalValue`] it carried can be retrieved with [`Command::get`].
+    ///
+    /// [`END_MODAL`]: commands/constant.END_MODAL.html
+    /// [`ModalValue`]: struct.ModalValue.html
+    /// [`Command::get`]: struct.Command.html#method.get
+    Ended(Command),
+    /// The modal window was closed without a result, for instance by the
+    /// user, or because the application is quitting.
+    Closed,
+}
+
+/// The modal sessions that are currently running, owned by the `AppState`.
+///
+/// A modal window can open another modal window, so sessions form a stack;
+/// only the window of the innermost session receives input.
+#[derive(Default)]
+pub(crate) struct Modals {
+    sessions: Vec<Session>,
+}
+
+struct Session {
+    window: WindowId,
+    result: Option<ModalResult>,
+}
+
+impl Modals {
+    /// Start a session for this window.
+    pub(crate) fn begin(&mut self, window: WindowId) {
+        self.sessions.push(Session {
+            window,
+            result: None,
+        });
+    }
+
+    /// Record how the session for this window ended.
+    ///
+    /// Only the first result counts: a window that submits `END_MODAL` is
+    /// then closed, and that should not overwrite its result with `Closed`.
+    pub(crate) fn end(&mut self, window: WindowId, result: ModalResult) {
+        if let Some(session) = self.sessions.iter_mut().find(|s| s.window == window) {
+            if session.result.is_none() {
+                session.result = Some(result);
+            }
+        }
+    }
+
+    /// Returns `true` if this window is running a modal session.
+    pub(crate) fn is_modal(&self, window: WindowId) -> bool {
+        self.sessions.iter().any(|s| s.window == window)
+    }
+
+    /// If the session for this window has ended, remove it and return its
+    /// result.
+    pub(crate) fn finish(&mut self, window: WindowId) -> Option<ModalResult> {
+        let idx = self
+            .sessions
+            .iter()
+            .position(|s| s.window == window && s.result.is_some())?;
+        self.sessions.remove(idx).result
+    }
+
+    /// Returns `true` if input to this window should be ignored, because a
+    /// modal session for some other window is in progress.
+    pub(crate) fn blocks(&self, window: WindowId) -> bool {
+        match self.sessions.last() {
+            Some(session) => session.window != window,
+            None => false,
+        }
+    }
+}
@@ -14,9 +14,16 @@
 
 //! The mousey bits
 
+use std::time::{Duration, Instant};
+
 use crate::kurbo::Point;
 use crate::{KeyModifiers, MouseButton};
 
+/// The longest time between two clicks that still counts as a multi-click.
+const MULTI_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+/// How far the mouse can move between two clicks of a multi-click.
+const MULTI_CLICK_SLOP: f64 = 4.0;
+
 /// The state of the mouse for a click, mouse-up, or move event.
 ///
 /// In `druid`, unlike in `druid_shell`, we treat the widget's coordinate
@@ -29,9 +36,14 @@ pub struct MouseEvent {
     pub window_pos: Point,
     /// Keyboard modifiers at the time of the mouse event.
     pub mods: KeyModifiers,
-    /// The number of mouse clicks associated with this event. This will always
-    /// be `0` for a mouse-up event.
-    pub count: u32,
+    /// The number of mouse clicks associated with this event: `1` for a
+    /// single click, `2` for a double click, and so on. This will always be
+    /// `0` for a mouse-up event.
+    ///
+    /// Clicks are counted by druid, rather than the platform: a press
+    /// continues a multi-click if it is made with the same button as the
+    /// previous press, within half a second of it, and within a few pixels.
+    pub count: u8,
     /// The currently pressed button in the case of a move or click event,
     /// or the released button in the case of a mouse-up event.
     pub button: MouseButton,
@@ -49,8 +61,67 @@ impl From<druid_shell::MouseEvent> for MouseEvent {
             pos,
             window_pos: pos,
             mods,
-            count,
+            count: count.min(u8::MAX.into()) as u8,
             button,
         }
     }
 }
+
+/// Counts the presses of a multi-click, for one window.
+#[derive(Default)]
+pub(crate) struct ClickCounter {
+    last: Option<(Instant, Point, MouseButton)>,
+    count: u8,
+}
+
+impl ClickCounter {
+    /// Register a mouse press, and return its click count.
+    ///
+    /// The count starts over if the button changes, or if the press is too
+    /// long after, or too far from, the previous one.
+    pub(crate) fn press(&mut self, pos: Point, button: MouseButton, now: Instant) -> u8 {
+        let continues = match self.last {
+            Some((time, last_pos, last_button)) => {
+                button == last_button
+                    && now.duration_since(time) <= MULTI_CLICK_INTERVAL
+                    && (pos - last_pos).hypot() <= MULTI_CLICK_SLOP
+            }
+            None => false,
+        };
+        self.count = if continues {
+            self.count.saturating_add(1)
+        } else {
+            1
+        };
+        self.last = Some((now, pos, button));
+        self.count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn multi_click() {
+        let mut clicks = ClickCounter::default();
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let pos = Point::new(20., 20.);
+
+        assert_eq!(clicks.press(pos, MouseButton::Left, at(0)), 1);
+        assert_eq!(clicks.press(pos, MouseButton::Left, at(200)), 2);
+        // a little jitter is fine, and the interval is from the last click.
+        let nudged = Point::new(22., 19.);
+        assert_eq!(clicks.press(nudged, MouseButton::Left, at(650)), 3);
+
+        // too slow.
+        assert_eq!(clicks.press(nudged, MouseButton::Left, at(1200)), 1);
+        // another button.
+        assert_eq!(clicks.press(nudged, MouseButton::Right, at(1300)), 1);
+        assert_eq!(clicks.press(nudged, MouseButton::Right, at(1400)), 2);
+        // too far.
+        let moved = Point::new(40., 20.);
+        assert_eq!(clicks.press(moved, MouseButton::Right, at(1500)), 1);
+    }
+}
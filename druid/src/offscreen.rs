@@ -0,0 +1,174 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Rendering a widget tree to an image, without a window.
+
+use std::time::Instant;
+
+use crate::core::CommandQueue;
+use crate::kurbo::{Point, Rect, Size};
+use crate::piet::{self, Device, ImageFormat, RenderContext};
+use crate::shell::{FileDialogOptions, FileInfo, WindowHandle};
+use crate::window::PendingWindow;
+use crate::{
+    Cursor, Data, Env, Event, LocalizedString, Text, TimerToken, Widget, WinCtx, WindowId,
+};
+
+/// A widget tree, rendered to an image.
+///
+/// This is meant for snapshot testing: render a widget in a known state,
+/// and compare the result with an image that is known to be good. The
+/// pixels are in RGBA order, with premultiplied alpha, a row at a time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+/// A `WinCtx` for a window that is never shown.
+///
+/// Timers are never fired, and dialogs are cancelled.
+struct OffscreenCtx<'a, 't: 'a> {
+    text: &'a mut Text<'t>,
+}
+
+impl Snapshot {
+    /// Render `root`, showing `data`, to an image.
+    ///
+    /// `size` is in display points, and `scale` is the number of pixels per
+    /// point. The widgets are sent the same events as in a new window, and
+    /// any commands they submit are run; they are then laid out and painted
+    /// once. Timers never fire.
+    pub fn render<T: Data>(
+        root: impl Widget<T> + 'static,
+        mut data: T,
+        env: &Env,
+        size: Size,
+        scale: f64,
+    ) -> Result<Snapshot, piet::Error> {
+        let width = (size.width * scale).ceil() as usize;
+        let height = (size.height * scale).ceil() as usize;
+        let mut device = Device::new()?;
+        let mut target = device.bitmap_target(width, height, scale)?;
+
+        let mut window = PendingWindow::new(root, LocalizedString::new(""), None)
+            .into_window(WindowId::next(), WindowHandle::default());
+        let mut queue = CommandQueue::new();
+        {
+            let mut piet = target.render_context();
+            {
+                let mut ctx = OffscreenCtx { text: piet.text() };
+                let physical = Size::new(width as f64, height as f64);
+                let now = Instant::now();
+                let events = [
+                    Event::WindowConnected,
+                    Event::ScaleChanged(scale),
+                    Event::Size(physical),
+                ];
+                for event in events.iter().cloned() {
+                    window.event(&mut ctx, &mut queue, event, &mut data, env, now);
+                    while let Some((target, cmd)) = queue.pop_front() {
+                        let event = Event::TargetedCommand(target, cmd);
+                        window.event(&mut ctx, &mut queue, event, &mut data, env, now);
+                    }
+                    window.update(&mut ctx, &data, env);
+                    window.invalidate_and_finalize(&mut queue, &data, env);
+                }
+            }
+            let everything = Rect::from_origin_size(Point::ORIGIN, size);
+            window.do_paint(&mut piet, everything, &mut queue, &data, env);
+            piet.finish()?;
+        }
+
+        let pixels = target.into_raw_pixels(ImageFormat::RgbaPremul)?;
+        Ok(Snapshot {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// The width of the image, in pixels.
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// The height of the image, in pixels.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The pixels of the image, in RGBA order, a row at a time.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// The pixel at `x`, `y`, in RGBA order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the pixel is outside of the image.
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        assert!(x < self.width && y < self.height, "pixel out of bounds");
+        let idx = (y * self.width + x) * 4;
+        let mut pixel = [0; 4];
+        pixel.copy_from_slice(&self.pixels[idx..idx + 4]);
+        pixel
+    }
+}
+
+impl<'a, 't> WinCtx<'t> for OffscreenCtx<'a, 't> {
+    fn invalidate(&mut self) {}
+
+    fn text_factory(&mut self) -> &mut Text<'t> {
+        self.text
+    }
+
+    fn set_cursor(&mut self, _cursor: &Cursor) {}
+
+    fn request_timer(&mut self, _deadline: Instant) -> TimerToken {
+        TimerToken::next()
+    }
+
+    fn open_file_sync(&mut self, _: FileDialogOptions) -> Option<FileInfo> {
+        None
+    }
+
+    fn save_as_sync(&mut self, _: FileDialogOptions) -> Option<FileInfo> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::widget::{Align, SizedBox, WidgetExt};
+    use crate::{theme, Color, UnitPoint};
+
+    #[test]
+    fn renders_widgets_to_pixels() {
+        let red = Color::rgb8(0xff, 0, 0);
+        let square = SizedBox::empty().width(10.).height(10.).background(red);
+        let widget = Align::new(UnitPoint::TOP_LEFT, square);
+        let blue = Color::rgb8(0, 0, 0xff);
+        let env = theme::init().adding(theme::WINDOW_BACKGROUND_COLOR, blue);
+
+        let snapshot = Snapshot::render(widget, (), &env, Size::new(20., 20.), 2.).unwrap();
+        assert_eq!((snapshot.width(), snapshot.height()), (40, 40));
+        assert_eq!(snapshot.pixel(10, 10), [0xff, 0, 0, 0xff]);
+        // the square is 20 pixels across at this scale.
+        assert_eq!(snapshot.pixel(30, 30), [0, 0, 0xff, 0xff]);
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Transient layers, such as popups, above the content of a window.
+
+use crate::core::{BaseState, CommandQueue};
+use crate::kurbo::{Point, Rect, Shape, Size};
+use crate::{
+    BoxConstraints, Env, LayoutCtx, LifeCycle, LifeCycleCtx, Widget, WidgetId, WidgetPod, WindowId,
+};
+
+/// A request to show an overlay, the argument of `SHOW_OVERLAY`.
+pub(crate) struct Overlay {
+    /// The widget that asked for the overlay.
+    pub(crate) owner: WidgetId,
+    /// Where the overlay should be, in window coordinates.
+    pub(crate) origin: Point,
+    pub(crate) widget: Box<dyn Widget<()>>,
+}
+
+/// An overlay that is shown in a window.
+///
+/// Each layer is a separate widget tree, laid out and painted after the
+/// window's root, in window coordinates.
+pub(crate) struct OverlayLayer {
+    pub(crate) owner: WidgetId,
+    origin: Point,
+    pub(crate) pod: WidgetPod<(), Box<dyn Widget<()>>>,
+}
+
+impl OverlayLayer {
+    pub(crate) fn new(overlay: Overlay) -> Self {
+        OverlayLayer {
+            owner: overlay.owner,
+            origin: overlay.origin,
+            pod: WidgetPod::new(overlay.widget),
+        }
+    }
+
+    /// Send a lifecycle event to the layer alone.
+    pub(crate) fn lifecycle(
+        &mut self,
+        queue: &mut CommandQueue,
+        window_id: WindowId,
+        event: &LifeCycle,
+        env: &Env,
+    ) {
+        let mut base_state = BaseState::new(self.pod.id());
+        let mut ctx = LifeCycleCtx {
+            command_queue: queue,
+            window_id,
+            base_state: &mut base_state,
+            window_origin: Point::ORIGIN,
+        };
+        self.pod.lifecycle(&mut ctx, event, &(), env);
+    }
+
+    /// Lay out the layer at its size, moved if need be to fit in the window.
+    pub(crate) fn layout(&mut self, ctx: &mut LayoutCtx, window: Size, env: &Env) {
+        let bc = BoxConstraints::new(Size::ZERO, window);
+        let size = self.pod.layout(ctx, &bc, &(), env);
+        let origin = fit_in_window(self.origin, size, window);
+        self.pod
+            .set_layout_rect(Rect::from_origin_size(origin, size));
+    }
+
+    /// Returns `true` if `pos`, in window coordinates, is over the layer.
+    pub(crate) fn contains(&self, pos: Point) -> bool {
+        self.pod.layout_rect().winding(pos) != 0
+    }
+}
+
+/// Move a rect of `size` at `origin` left and up, as little as possible, so
+/// that it is inside the window.
+fn fit_in_window(origin: Point, size: Size, window: Size) -> Point {
+    Point::new(
+        origin.x.min(window.width - size.width).max(0.),
+        origin.y.min(window.height - size.height).max(0.),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layers_fit_in_window() {
+        let window = Size::new(200., 100.);
+        let size = Size::new(50., 40.);
+        let fit = |x, y| {
+            let origin = fit_in_window(Point::new(x, y), size, window);
+            (origin.x, origin.y)
+        };
+        assert_eq!(fit(10., 10.), (10., 10.));
+        assert_eq!(fit(180., 80.), (150., 60.));
+        // too big: pinned to the top left.
+        let big = fit_in_window(Point::new(10., 10.), Size::new(300., 20.), window);
+        assert_eq!((big.x, big.y), (0., 10.));
+    }
+}
@@ -0,0 +1,169 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Re-delivery of commands that were not handled.
+
+use std::collections::HashMap;
+use std::time::Instant;
+
+use crate::{Command, Target, TimerToken, WinCtx, WindowId};
+
+/// Commands waiting for another attempt, owned by the `AppState`.
+///
+/// When a command that was submitted with [`Command::retry`] is not handled,
+/// one of its attempts is used up and a timer is requested for the end of its
+/// delay; the command is held here until that timer fires, at which point it
+/// goes back through the normal dispatch, and may fail again.
+///
+/// Timers belong to a window, so when that window closes, its commands are
+/// moved to the timers of another.
+///
+/// [`Command::retry`]: struct.Command.html#method.retry
+#[derive(Default)]
+pub(crate) struct Retries {
+    pending: HashMap<TimerToken, Pending>,
+    /// The commands of closed windows, waiting for a window to take them.
+    unarmed: Vec<Pending>,
+}
+
+/// A command waiting for the timer of its next attempt.
+struct Pending {
+    /// The window whose timer this is.
+    window: WindowId,
+    deadline: Instant,
+    target: Target,
+    command: Command,
+}
+
+/// Deliver `command` with `handle`, which returns `true` if it was handled.
+///
+/// If the command was not handled, and it can be retried, it is returned, to
+/// be passed on to [`Retries::failed`] once `handle` is done.
+///
+/// [`Retries::failed`]: struct.Retries.html#method.failed
+pub(crate) fn deliver(
+    target: Target,
+    command: Command,
+    handle: impl FnOnce(Target, Command) -> bool,
+) -> Option<Command> {
+    let retry = if command.retry.is_some() {
+        Some(command.clone())
+    } else {
+        None
+    };
+    if handle(target, command) {
+        None
+    } else {
+        retry
+    }
+}
+
+impl Retries {
+    /// Called when a command was not handled at `now`; `win_ctx` belongs to
+    /// `window`.
+    ///
+    /// If the command has no attempts left, it is dropped.
+    pub(crate) fn failed(
+        &mut self,
+        window: WindowId,
+        target: Target,
+        mut command: Command,
+        now: Instant,
+        win_ctx: &mut dyn WinCtx,
+    ) {
+        match command.next_retry() {
+            Some(delay) => {
+                let deadline = now + delay;
+                let token = win_ctx.request_timer(deadline);
+                let pending = Pending {
+                    window,
+                    deadline,
+                    target,
+                    command,
+                };
+                self.pending.insert(token, pending);
+            }
+            None => {
+                log::warn!(
+                    "command {} failed, and has no retries left",
+                    command.symbol()
+                );
+            }
+        }
+    }
+
+    /// Called when one of our timers fires; returns the command to retry.
+    pub(crate) fn fire(&mut self, token: TimerToken) -> Option<(Target, Command)> {
+        self.pending
+            .remove(&token)
+            .map(|pending| (pending.target, pending.command))
+    }
+
+    /// Called when `closed` is gone, and its timers with it.
+    ///
+    /// Its commands are held until [`rearm`] is called with another window,
+    /// except those sent to the closed window, which are dropped. Returns
+    /// `true` if any are held.
+    ///
+    /// [`rearm`]: #method.rearm
+    pub(crate) fn window_closed(&mut self, closed: WindowId) -> bool {
+        let tokens: Vec<_> = self
+            .pending
+            .iter()
+            .filter(|(_, pending)| pending.window == closed)
+            .map(|(token, _)| *token)
+            .collect();
+        for token in tokens {
+            let pending = self.pending.remove(&token).unwrap();
+            if pending.target == Target::Window(closed) {
+                log::warn!(
+                    "command {} dropped, its window is gone",
+                    pending.command.symbol()
+                );
+            } else {
+                self.unarmed.push(pending);
+            }
+        }
+        !self.unarmed.is_empty()
+    }
+
+    /// Request timers from `window` for the commands of closed windows,
+    /// keeping their deadlines.
+    pub(crate) fn rearm(&mut self, window: WindowId, win_ctx: &mut dyn WinCtx) {
+        for mut pending in self.unarmed.drain(..) {
+            let token = win_ctx.request_timer(pending.deadline);
+            pending.window = window;
+            self.pending.insert(token, pending);
+        }
+    }
+
+    /// Drop the commands of closed windows, when there is no window left.
+    pub(crate) fn clear_unarmed(&mut self) {
+        for pending in self.unarmed.drain(..) {
+            log::warn!(
+                "command {} dropped, there is no window left",
+                pending.command.symbol()
+            );
+        }
+    }
+
+    /// The timers commands are waiting on, and their windows.
+    #[cfg(test)]
+    pub(crate) fn timers(&self) -> Vec<(TimerToken, WindowId)> {
+        self.pending
+            .iter()
+            .map(|(token, pending)| (*token, pending.window))
+            .collect()
+    }
+}
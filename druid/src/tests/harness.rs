@@ -13,12 +13,17 @@
 // limitations under the License.
 
 //! Tools and infrastructure for testing widgets.
+use std::time::{Duration, Instant};
+
 use crate::core::{BaseState, CommandQueue};
 use crate::piet::{BitmapTarget, Device, Piet};
+use crate::retry::{self, Retries};
 use crate::window::PendingWindow;
 use crate::*;
 
 pub(crate) const DEFAULT_SIZE: Size = Size::new(400., 400.);
+/// An invalid rect that covers any window.
+pub(crate) const WHOLE_WINDOW: Rect = Rect::new(0., 0., f64::INFINITY, f64::INFINITY);
 
 /// A type that tries very hard to provide a comforting and safe environment
 /// for widgets who are trying to find their way.
@@ -31,15 +36,24 @@ pub(crate) const DEFAULT_SIZE: Size = Size::new(400., 400.);
 /// it will call `update` automatically after an event.
 ///
 /// That said, it _is_ missing a bunch of logic that would normally be handled
-/// in `AppState`: for instance it does not clear the `needs_inval` and
-/// `children_changed` flags on the window after an update.
+/// in `AppState`: for instance it does not clear the `needs_inval` flag on the
+/// window after an update. Children added during an update are sent
+/// `WidgetAdded`, as they would be in a window.
 ///
 /// In addition, layout and paint **are not called automatically**. This is
 /// because paint is triggered by druid-shell, and there is no druid-shell here;
 ///
 /// if you want those functions run you will need to call them yourself.
 ///
-/// Also, timers don't work.  ¯\_(ツ)_/¯
+/// Time does not pass on its own either: timers only fire when the mock clock
+/// is moved forward with [`advance_time`].
+///
+/// The calls the window makes to its `WinCtx`, and the commands that were
+/// dispatched, are recorded; see [`calls`] and [`take_commands`].
+///
+/// [`advance_time`]: #method.advance_time
+/// [`calls`]: #method.calls
+/// [`take_commands`]: #method.take_commands
 pub struct Harness<'a, T: Data> {
     piet: Piet<'a>,
     inner: Inner<T>,
@@ -52,16 +66,79 @@ struct Inner<T: Data> {
     env: Env,
     window: Window<T>,
     cmds: CommandQueue,
+    retries: Retries,
+    /// The mock clock.
+    now: Instant,
+    /// Timers that have been requested and have not fired yet.
+    timers: Vec<(Instant, TimerToken)>,
+    calls: WinCtxCalls,
+    /// Commands that have been dispatched, in order.
+    dispatched: Vec<(Target, Command)>,
+}
+
+/// The calls made to a [`MockWinCtx`], for tests to assert on.
+///
+/// [`MockWinCtx`]: struct.MockWinCtx.html
+#[derive(Debug, Default, Clone)]
+pub struct WinCtxCalls {
+    /// The number of calls to `invalidate`.
+    pub invalidate: usize,
+    /// The cursors that were set, in order.
+    pub cursors: Vec<Cursor>,
+    /// The deadlines of the timers that were requested, in order.
+    pub timers: Vec<Instant>,
+    /// The input method cursor areas that were set, in order.
+    pub ime_areas: Vec<Rect>,
 }
 
 /// A `WinCtx` impl that we can conjure from the ether.
-pub struct MockWinCtx<'a, 't: 'a>(&'a mut Text<'t>);
+pub struct MockWinCtx<'a, 't: 'a> {
+    text: &'a mut Text<'t>,
+    timers: &'a mut Vec<(Instant, TimerToken)>,
+    calls: &'a mut WinCtxCalls,
+}
 
 /// A way to clean up resources when our target goes out of scope.
 // the inner type is an option so that we can take ownership in `drop` even
 // though self is `& mut`.
 struct TargetGuard<'a>(Option<BitmapTarget<'a>>);
 
+/// Call `f` with a `WinCtx` that does not belong to any window.
+///
+/// This is for testing code that sits above individual windows, such as the
+/// `AppState`, which needs a `WinCtx` but not a `Harness`.
+pub(crate) fn with_win_ctx(f: impl FnOnce(&mut dyn WinCtx)) {
+    with_mock_win_ctx(|win_ctx| f(win_ctx))
+}
+
+/// Like [`with_win_ctx`], for tests that look at the calls made to the
+/// `WinCtx`, or fire its timers by hand.
+///
+/// [`with_win_ctx`]: fn.with_win_ctx.html
+pub(crate) fn with_mock_win_ctx(f: impl FnOnce(&mut MockWinCtx)) {
+    let mut device = Device::new().expect("failed to get device");
+    let target = device.bitmap_target(1, 1, 1.).expect("bitmap_target");
+    let mut target = TargetGuard(Some(target));
+    let mut piet = target.0.as_mut().unwrap().render_context();
+    let mut timers = Vec::new();
+    let mut calls = WinCtxCalls::default();
+    let mut win_ctx = MockWinCtx {
+        text: piet.text(),
+        timers: &mut timers,
+        calls: &mut calls,
+    };
+    f(&mut win_ctx);
+}
+
+/// Call `f` with a render context for a small bitmap.
+pub(crate) fn with_piet(f: impl FnOnce(&mut Piet)) {
+    let mut device = Device::new().expect("failed to get device");
+    let target = device.bitmap_target(40, 40, 1.).expect("bitmap_target");
+    let mut target = TargetGuard(Some(target));
+    let mut piet = target.0.as_mut().unwrap().render_context();
+    f(&mut piet);
+}
+
 impl<T: Data> Harness<'_, T> {
     /// Create a new `Harness` with the given data and a root widget,
     /// and provide that harness to the passed in function.
@@ -80,6 +157,11 @@ impl<T: Data> Harness<'_, T> {
             window: PendingWindow::new(root, LocalizedString::new(""), None)
                 .into_window(WindowId::next(), Default::default()),
             cmds: Default::default(),
+            retries: Default::default(),
+            now: Instant::now(),
+            timers: Vec::new(),
+            calls: WinCtxCalls::default(),
+            dispatched: Vec::new(),
         };
 
         let mut harness = Harness { piet, inner };
@@ -100,6 +182,29 @@ impl<T: Data> Harness<'_, T> {
         &self.inner.data
     }
 
+    /// Change the data outside of an event, as an app delegate might, and
+    /// send the resulting `update`.
+    pub fn edit_data(&mut self, f: impl FnOnce(&mut T)) {
+        f(&mut self.inner.data);
+        self.update();
+    }
+
+    /// The calls made to the `WinCtx` so far.
+    pub fn calls(&self) -> &WinCtxCalls {
+        &self.inner.calls
+    }
+
+    /// Forget the calls made to the `WinCtx` so far.
+    pub fn clear_calls(&mut self) {
+        self.inner.calls = WinCtxCalls::default();
+    }
+
+    /// Remove and return the commands that have been dispatched since the
+    /// last call to this method, in the order they were dispatched.
+    pub fn take_commands(&mut self) -> Vec<(Target, Command)> {
+        std::mem::take(&mut self.inner.dispatched)
+    }
+
     /// Retrieve a copy of this widget's `BaseState`, if possible.
     //FIXME: make this unwrap, and add a `try_get_state` variant?
     pub(crate) fn get_state(&mut self, widget: WidgetId) -> Option<BaseState> {
@@ -137,22 +242,63 @@ impl<T: Data> Harness<'_, T> {
     /// as will any resulting commands. This will also trigger `update`.
     ///
     /// Commands dispatched during `update` will not be sent?
-    pub fn event(&mut self, event: Event) {
-        self.inner.event(event, &mut self.piet);
+    ///
+    /// Returns `true` if the event was handled.
+    pub fn event(&mut self, event: Event) -> bool {
+        let is_handled = self.inner.event(event, &mut self.piet);
         self.process_commands();
         self.update();
+        is_handled
+    }
+
+    /// Move the mock clock forward by `duration`.
+    ///
+    /// Any timers that come due are fired in order of their deadlines, with
+    /// the clock set to each deadline as it fires.
+    pub fn advance_time(&mut self, duration: Duration) {
+        let end = self.inner.now + duration;
+        while let Some((deadline, token)) = self.inner.pop_timer(end) {
+            self.inner.now = deadline;
+            if let Some((target, cmd)) = self.inner.retries.fire(token) {
+                self.dispatch_cmd(target, cmd);
+                self.process_commands();
+            } else if let Some(event) = self.inner.window.timer_event(token, deadline) {
+                self.event(event);
+            }
+        }
+        self.inner.now = end;
     }
 
     fn process_commands(&mut self) {
         loop {
             let cmd = self.inner.cmds.pop_front();
             match cmd {
-                Some((target, cmd)) => self.event(Event::TargetedCommand(target, cmd)),
+                Some((target, cmd)) => self.dispatch_cmd(target, cmd),
                 None => break,
             }
         }
     }
 
+    /// Send a command that has come off the queue, scheduling a retry if it
+    /// asked for one and was not handled.
+    fn dispatch_cmd(&mut self, target: Target, cmd: Command) {
+        self.inner.dispatched.push((target, cmd.clone()));
+        let failed = retry::deliver(target, cmd, |target, cmd| {
+            self.event(Event::TargetedCommand(target, cmd))
+        });
+        if let Some(cmd) = failed {
+            let mut win_ctx = MockWinCtx {
+                text: self.piet.text(),
+                timers: &mut self.inner.timers,
+                calls: &mut self.inner.calls,
+            };
+            let (id, now) = (self.inner.window.id, self.inner.now);
+            self.inner
+                .retries
+                .failed(id, target, cmd, now, &mut win_ctx);
+        }
+    }
+
     fn lifecycle(&mut self, event: LifeCycle) {
         self.inner.lifecycle(event)
     }
@@ -169,20 +315,39 @@ impl<T: Data> Harness<'_, T> {
 
     #[allow(dead_code)]
     pub fn paint(&mut self) {
-        self.inner.paint(&mut self.piet)
+        self.inner.paint(&mut self.piet, WHOLE_WINDOW)
+    }
+
+    /// Paint only the widgets that intersect `invalid_rect`, as the platform
+    /// does after a call to `invalidate_rect`.
+    pub fn paint_rect(&mut self, invalid_rect: Rect) {
+        self.inner.paint(&mut self.piet, invalid_rect)
     }
 }
 
 impl<T: Data> Inner<T> {
-    fn event(&mut self, event: Event, piet: &mut Piet) {
-        let mut win_ctx = MockWinCtx(piet.text());
+    /// Remove and return the earliest timer that is due at `deadline`.
+    fn pop_timer(&mut self, deadline: Instant) -> Option<(Instant, TimerToken)> {
+        let idx = (0..self.timers.len())
+            .filter(|i| self.timers[*i].0 <= deadline)
+            .min_by_key(|i| self.timers[*i].0)?;
+        Some(self.timers.remove(idx))
+    }
+
+    fn event(&mut self, event: Event, piet: &mut Piet) -> bool {
+        let mut win_ctx = MockWinCtx {
+            text: piet.text(),
+            timers: &mut self.timers,
+            calls: &mut self.calls,
+        };
         self.window.event(
             &mut win_ctx,
             &mut self.cmds,
             event,
             &mut self.data,
             &self.env,
-        );
+            self.now,
+        )
     }
 
     fn lifecycle(&mut self, event: LifeCycle) {
@@ -191,8 +356,14 @@ impl<T: Data> Inner<T> {
     }
 
     fn update(&mut self, piet: &mut Piet) {
-        let mut win_ctx = MockWinCtx(piet.text());
+        let mut win_ctx = MockWinCtx {
+            text: piet.text(),
+            timers: &mut self.timers,
+            calls: &mut self.calls,
+        };
         self.window.update(&mut win_ctx, &self.data, &self.env);
+        self.window
+            .invalidate_and_finalize(&mut self.cmds, &self.data, &self.env);
     }
 
     fn layout(&mut self, piet: &mut Piet) {
@@ -200,22 +371,43 @@ impl<T: Data> Inner<T> {
     }
 
     #[allow(dead_code)]
-    fn paint(&mut self, piet: &mut Piet) {
+    fn paint(&mut self, piet: &mut Piet, invalid_rect: Rect) {
         self.window
-            .do_paint(piet, &mut self.cmds, &self.data, &self.env);
+            .do_paint(piet, invalid_rect, &mut self.cmds, &self.data, &self.env);
+    }
+}
+
+impl MockWinCtx<'_, '_> {
+    /// The calls made to this `WinCtx` so far.
+    pub fn calls(&self) -> &WinCtxCalls {
+        self.calls
+    }
+
+    /// The timers that have been requested and have not fired yet.
+    pub fn timers(&mut self) -> &mut Vec<(Instant, TimerToken)> {
+        self.timers
     }
 }
 
 impl<'a, 't> WinCtx<'t> for MockWinCtx<'a, 't> {
-    fn invalidate(&mut self) {}
+    fn invalidate(&mut self) {
+        self.calls.invalidate += 1;
+    }
     fn text_factory(&mut self) -> &mut Text<'t> {
-        self.0
+        self.text
     }
 
-    fn set_cursor(&mut self, _cursor: &Cursor) {}
-    //TODO: we could actually implement timers if we were ambitious
-    fn request_timer(&mut self, _deadline: std::time::Instant) -> TimerToken {
-        TimerToken::next()
+    fn set_cursor(&mut self, cursor: &Cursor) {
+        self.calls.cursors.push(cursor.clone());
+    }
+    fn set_ime_cursor_area(&mut self, rect: Rect) {
+        self.calls.ime_areas.push(rect);
+    }
+    fn request_timer(&mut self, deadline: Instant) -> TimerToken {
+        self.calls.timers.push(deadline);
+        let token = TimerToken::next();
+        self.timers.push((deadline, token));
+        token
     }
     fn open_file_sync(&mut self, _: FileDialogOptions) -> Option<FileInfo> {
         None
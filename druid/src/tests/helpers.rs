@@ -20,7 +20,7 @@ use std::cell::RefCell;
 use std::collections::VecDeque;
 use std::rc::Rc;
 
-use crate::widget::WidgetExt;
+use crate::widget::{Flex, Scroll, SizedBox, WidgetExt};
 use crate::*;
 
 // taken from the matches crate; useful for the Recorder widget.
@@ -34,12 +34,22 @@ macro_rules! assert_matches {
      }
  }
 
-pub type EventFn<S, T> = dyn FnMut(&mut S, &mut EventCtx, &Event, &T, &Env);
+pub type EventFn<S, T> = dyn FnMut(&mut S, &mut EventCtx, &Event, &mut T, &Env);
 pub type LifeCycleFn<S, T> = dyn FnMut(&mut S, &mut LifeCycleCtx, &LifeCycle, &T, &Env);
 pub type UpdateFn<S, T> = dyn FnMut(&mut S, &mut UpdateCtx, &T, &T, &Env);
 pub type LayoutFn<S, T> = dyn FnMut(&mut S, &mut LayoutCtx, &BoxConstraints, &T, &Env) -> Size;
 pub type PaintFn<S, T> = dyn FnMut(&mut S, &mut PaintCtx, &T, &Env);
 
+pub type DelegateEventFn<S, T> =
+    dyn FnMut(&mut S, Event, &mut T, &Env, &mut DelegateCtx) -> Option<Event>;
+pub type DelegateCommandFn<S, T> =
+    dyn FnMut(&mut S, Target, &Command, &mut T, &Env, &mut DelegateCtx) -> Handled;
+pub type DelegateWindowFn<S, T> = dyn FnMut(&mut S, WindowId, &mut T, &Env, &mut DelegateCtx);
+pub type DelegateShouldCloseFn<S, T> =
+    dyn FnMut(&mut S, WindowId, &mut T, &Env, &mut DelegateCtx) -> bool;
+pub type DelegateAppFn<S, T> = dyn FnMut(&mut S, &mut T, &Env, &mut DelegateCtx);
+pub type DelegateShouldQuitFn<S, T> = dyn FnMut(&mut S, &mut T, &Env, &mut DelegateCtx) -> bool;
+
 pub const REPLACE_CHILD: Selector = Selector::new("druid-test.replace-child");
 
 /// A widget that can be constructed from individual functions, builder-style.
@@ -54,6 +64,26 @@ pub struct ModularWidget<S, T> {
     paint: Option<Box<PaintFn<S, T>>>,
 }
 
+/// An `AppDelegate` that can be constructed from individual functions,
+/// builder-style, like [`ModularWidget`].
+///
+/// Any hook without a function behaves as the trait's default.
+///
+/// [`ModularWidget`]: struct.ModularWidget.html
+pub struct ModularDelegate<S, T> {
+    state: S,
+    event: Option<Box<DelegateEventFn<S, T>>>,
+    command: Option<Box<DelegateCommandFn<S, T>>>,
+    window_added: Option<Box<DelegateWindowFn<S, T>>>,
+    window_removed: Option<Box<DelegateWindowFn<S, T>>>,
+    all_windows_closed: Option<Box<DelegateAppFn<S, T>>>,
+    window_should_close: Option<Box<DelegateShouldCloseFn<S, T>>>,
+    application_should_quit: Option<Box<DelegateShouldQuitFn<S, T>>>,
+    system_will_sleep: Option<Box<DelegateAppFn<S, T>>>,
+    system_did_wake: Option<Box<DelegateAppFn<S, T>>>,
+    battery_low: Option<Box<DelegateAppFn<S, T>>>,
+}
+
 /// A widget that can replace its child on command
 pub struct ReplaceChild<T: Data> {
     inner: WidgetPod<T, Box<dyn Widget<T>>>,
@@ -127,7 +157,7 @@ impl<S, T> ModularWidget<S, T> {
 
     pub fn event_fn(
         mut self,
-        f: impl FnMut(&mut S, &mut EventCtx, &Event, &T, &Env) + 'static,
+        f: impl FnMut(&mut S, &mut EventCtx, &Event, &mut T, &Env) + 'static,
     ) -> Self {
         self.event = Some(Box::new(f));
         self
@@ -201,6 +231,190 @@ impl<S, T: Data> Widget<T> for ModularWidget<S, T> {
     }
 }
 
+#[allow(dead_code)]
+impl<S, T> ModularDelegate<S, T> {
+    pub fn new(state: S) -> Self {
+        ModularDelegate {
+            state,
+            event: None,
+            command: None,
+            window_added: None,
+            window_removed: None,
+            all_windows_closed: None,
+            window_should_close: None,
+            application_should_quit: None,
+            system_will_sleep: None,
+            system_did_wake: None,
+            battery_low: None,
+        }
+    }
+
+    pub fn event_fn(
+        mut self,
+        f: impl FnMut(&mut S, Event, &mut T, &Env, &mut DelegateCtx) -> Option<Event> + 'static,
+    ) -> Self {
+        self.event = Some(Box::new(f));
+        self
+    }
+
+    pub fn command_fn(
+        mut self,
+        f: impl FnMut(&mut S, Target, &Command, &mut T, &Env, &mut DelegateCtx) -> Handled + 'static,
+    ) -> Self {
+        self.command = Some(Box::new(f));
+        self
+    }
+
+    pub fn window_added_fn(
+        mut self,
+        f: impl FnMut(&mut S, WindowId, &mut T, &Env, &mut DelegateCtx) + 'static,
+    ) -> Self {
+        self.window_added = Some(Box::new(f));
+        self
+    }
+
+    pub fn window_removed_fn(
+        mut self,
+        f: impl FnMut(&mut S, WindowId, &mut T, &Env, &mut DelegateCtx) + 'static,
+    ) -> Self {
+        self.window_removed = Some(Box::new(f));
+        self
+    }
+
+    pub fn all_windows_closed_fn(
+        mut self,
+        f: impl FnMut(&mut S, &mut T, &Env, &mut DelegateCtx) + 'static,
+    ) -> Self {
+        self.all_windows_closed = Some(Box::new(f));
+        self
+    }
+
+    pub fn window_should_close_fn(
+        mut self,
+        f: impl FnMut(&mut S, WindowId, &mut T, &Env, &mut DelegateCtx) -> bool + 'static,
+    ) -> Self {
+        self.window_should_close = Some(Box::new(f));
+        self
+    }
+
+    pub fn application_should_quit_fn(
+        mut self,
+        f: impl FnMut(&mut S, &mut T, &Env, &mut DelegateCtx) -> bool + 'static,
+    ) -> Self {
+        self.application_should_quit = Some(Box::new(f));
+        self
+    }
+
+    pub fn system_will_sleep_fn(
+        mut self,
+        f: impl FnMut(&mut S, &mut T, &Env, &mut DelegateCtx) + 'static,
+    ) -> Self {
+        self.system_will_sleep = Some(Box::new(f));
+        self
+    }
+
+    pub fn system_did_wake_fn(
+        mut self,
+        f: impl FnMut(&mut S, &mut T, &Env, &mut DelegateCtx) + 'static,
+    ) -> Self {
+        self.system_did_wake = Some(Box::new(f));
+        self
+    }
+
+    pub fn battery_low_fn(
+        mut self,
+        f: impl FnMut(&mut S, &mut T, &Env, &mut DelegateCtx) + 'static,
+    ) -> Self {
+        self.battery_low = Some(Box::new(f));
+        self
+    }
+}
+
+impl<S, T: Data> AppDelegate<T> for ModularDelegate<S, T> {
+    fn event(
+        &mut self,
+        event: Event,
+        data: &mut T,
+        env: &Env,
+        ctx: &mut DelegateCtx,
+    ) -> Option<Event> {
+        match self.event.as_mut() {
+            Some(f) => f(&mut self.state, event, data, env, ctx),
+            None => Some(event),
+        }
+    }
+
+    fn command(
+        &mut self,
+        target: Target,
+        cmd: &Command,
+        data: &mut T,
+        env: &Env,
+        ctx: &mut DelegateCtx,
+    ) -> Handled {
+        match self.command.as_mut() {
+            Some(f) => f(&mut self.state, target, cmd, data, env, ctx),
+            None => Handled::No,
+        }
+    }
+
+    fn window_added(&mut self, id: WindowId, data: &mut T, env: &Env, ctx: &mut DelegateCtx) {
+        if let Some(f) = self.window_added.as_mut() {
+            f(&mut self.state, id, data, env, ctx)
+        }
+    }
+
+    fn window_removed(&mut self, id: WindowId, data: &mut T, env: &Env, ctx: &mut DelegateCtx) {
+        if let Some(f) = self.window_removed.as_mut() {
+            f(&mut self.state, id, data, env, ctx)
+        }
+    }
+
+    fn all_windows_closed(&mut self, data: &mut T, env: &Env, ctx: &mut DelegateCtx) {
+        if let Some(f) = self.all_windows_closed.as_mut() {
+            f(&mut self.state, data, env, ctx)
+        }
+    }
+
+    fn window_should_close(
+        &mut self,
+        id: WindowId,
+        data: &mut T,
+        env: &Env,
+        ctx: &mut DelegateCtx,
+    ) -> bool {
+        match self.window_should_close.as_mut() {
+            Some(f) => f(&mut self.state, id, data, env, ctx),
+            None => true,
+        }
+    }
+
+    fn application_should_quit(&mut self, data: &mut T, env: &Env, ctx: &mut DelegateCtx) -> bool {
+        match self.application_should_quit.as_mut() {
+            Some(f) => f(&mut self.state, data, env, ctx),
+            None => true,
+        }
+    }
+
+    fn system_will_sleep(&mut self, data: &mut T, env: &Env, ctx: &mut DelegateCtx) {
+        if let Some(f) = self.system_will_sleep.as_mut() {
+            f(&mut self.state, data, env, ctx)
+        }
+    }
+
+    fn system_did_wake(&mut self, data: &mut T, env: &Env, ctx: &mut DelegateCtx) {
+        if let Some(f) = self.system_did_wake.as_mut() {
+            f(&mut self.state, data, env, ctx)
+        }
+    }
+
+    fn battery_low(&mut self, data: &mut T, env: &Env, ctx: &mut DelegateCtx) {
+        if let Some(f) = self.battery_low.as_mut() {
+            f(&mut self.state, data, env, ctx)
+        }
+    }
+}
+
 impl<T: Data> ReplaceChild<T> {
     pub fn new<W: Widget<T> + 'static>(
         inner: impl Widget<T> + 'static,
@@ -215,7 +429,7 @@ impl<T: Data> ReplaceChild<T> {
 impl<T: Data> Widget<T> for ReplaceChild<T> {
     fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
         if let Event::Command(cmd) = event {
-            if cmd.selector == REPLACE_CHILD {
+            if cmd.is(REPLACE_CHILD) {
                 self.inner = WidgetPod::new((self.replacer)());
                 ctx.children_changed();
                 return;
@@ -345,3 +559,49 @@ pub fn widget_id6() -> (WidgetId, WidgetId, WidgetId, WidgetId, WidgetId, Widget
         WidgetId::next(),
     )
 }
+
+/// A left-button `MouseEvent` at `(x, y)`, which is also its position in the
+/// window, with a click count of `count`.
+pub fn left_mouse(x: f64, y: f64, count: u8) -> MouseEvent {
+    let pos = Point::new(x, y);
+    MouseEvent {
+        pos,
+        window_pos: pos,
+        mods: KeyModifiers::default(),
+        count,
+        button: MouseButton::Left,
+    }
+}
+
+/// The platform's left-button mouse event at `(x, y)` in the window, with a
+/// click count of `count`, for driving a window handler directly.
+pub fn left_shell_mouse(x: f64, y: f64, count: u32) -> crate::shell::MouseEvent {
+    crate::shell::MouseEvent {
+        pos: Point::new(x, y),
+        mods: KeyModifiers::default(),
+        count,
+        button: MouseButton::Left,
+    }
+}
+
+/// A vertical `Scroll` of two 100x300 boxes, one above the other, whose
+/// events are recorded in `first` and `second`.
+pub fn two_box_scroll(first: &Recording, second: &Recording) -> impl Widget<()> {
+    let child = |rec| SizedBox::empty().width(100.).height(300.).record(rec);
+    Scroll::new(
+        Flex::column()
+            .with_child(child(first))
+            .with_child(child(second)),
+    )
+    .vertical()
+}
+
+/// A vertical `Wheel` event of `lines` lines, in the given phase.
+pub fn wheel(lines: f64, phase: WheelPhase) -> Event {
+    Event::Wheel(WheelEvent {
+        delta: Vec2::new(0., lines),
+        unit: WheelUnit::Lines,
+        phase,
+        mods: KeyModifiers::default(),
+    })
+}
@@ -44,18 +44,18 @@ fn simple_layout() {
 fn row_column() {
     let (id1, id2, id3, id4, id5, id6) = widget_id6();
     let widget = Flex::row()
-        .with_child(
+        .with_flex_child(
             Flex::column()
-                .with_child(SizedBox::empty().with_id(id1), 1.0)
-                .with_child(SizedBox::empty().with_id(id2), 1.0),
+                .with_flex_child(SizedBox::empty().with_id(id1), 1.0)
+                .with_flex_child(SizedBox::empty().with_id(id2), 1.0),
             1.0,
         )
-        .with_child(
+        .with_flex_child(
             Flex::column()
-                .with_child(SizedBox::empty().with_id(id3), 1.0)
-                .with_child(SizedBox::empty().with_id(id4), 1.0)
-                .with_child(SizedBox::empty().with_id(id5), 1.0)
-                .with_child(SizedBox::empty().with_id(id6), 1.0),
+                .with_flex_child(SizedBox::empty().with_id(id3), 1.0)
+                .with_flex_child(SizedBox::empty().with_id(id4), 1.0)
+                .with_flex_child(SizedBox::empty().with_id(id5), 1.0)
+                .with_flex_child(SizedBox::empty().with_id(id6), 1.0),
             1.0,
         );
 
@@ -124,28 +124,28 @@ fn flex_paint_rect_overflow() {
     let id = WidgetId::next();
 
     let widget = Flex::row()
-        .with_child(
+        .with_flex_child(
             ModularWidget::new(()).layout_fn(|_, ctx, bc, _, _| {
                 ctx.set_paint_insets(Insets::new(20., 0., 0., 0.));
                 bc.constrain(Size::new(10., 10.))
             }),
             1.0,
         )
-        .with_child(
+        .with_flex_child(
             ModularWidget::new(()).layout_fn(|_, ctx, bc, _, _| {
                 ctx.set_paint_insets(Insets::new(0., 20., 0., 0.));
                 bc.constrain(Size::new(10., 10.))
             }),
             1.0,
         )
-        .with_child(
+        .with_flex_child(
             ModularWidget::new(()).layout_fn(|_, ctx, bc, _, _| {
                 ctx.set_paint_insets(Insets::new(0., 0., 0., 20.));
                 bc.constrain(Size::new(10., 10.))
             }),
             1.0,
         )
-        .with_child(
+        .with_flex_child(
             ModularWidget::new(()).layout_fn(|_, ctx, bc, _, _| {
                 ctx.set_paint_insets(Insets::new(0., 0., 20., 0.));
                 bc.constrain(Size::new(10., 10.))
@@ -170,3 +170,69 @@ fn flex_paint_rect_overflow() {
         );
     })
 }
+
+#[test]
+fn right_to_left_layout() {
+    let (id1, id2, id3, id4) = widget_id4();
+    let row_id = WidgetId::next();
+    let sized = |width| SizedBox::<()>::empty().width(width).height(10.);
+
+    let ltr_row = Flex::row()
+        .with_child(sized(10.).with_id(id3))
+        .with_child(sized(20.).with_id(id4))
+        .env_scope(|env, _| env.set(theme::RIGHT_TO_LEFT, false));
+    let rtl_row = Flex::row()
+        .with_child(sized(10.).with_id(id1))
+        .with_child(sized(20.).with_id(id2))
+        .with_id(row_id)
+        .padding((5., 0., 15., 0.));
+    let widget = Flex::column()
+        .with_child(rtl_row)
+        .with_child(ltr_row)
+        .env_scope(|env, _| env.set(theme::RIGHT_TO_LEFT, true));
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        let mut origin = |id| harness.get_state(id).expect("state").layout_rect.origin();
+
+        // the first child is on the right, and the insets are swapped.
+        assert_eq!(origin(id1), Point::new(20., 0.));
+        assert_eq!(origin(id2), Point::new(0., 0.));
+        assert_eq!(origin(row_id), Point::new(15., 0.));
+        // a nested scope can switch back.
+        assert_eq!(origin(id3), Point::new(0., 0.));
+        assert_eq!(origin(id4), Point::new(10., 0.));
+    })
+}
+
+#[test]
+fn flex_factors_and_spacers() {
+    let (id1, id2, id3, id4) = widget_id4();
+    let sized = |width| SizedBox::<()>::empty().width(width).height(10.);
+
+    // 400px wide: 100 fixed, 10 of fixed spacing, and 290 to share 1:1:3.
+    let shared = Flex::row()
+        .with_child(sized(100.).with_id(id1))
+        .with_spacer(10.)
+        .with_flex_child(SizedBox::empty().with_id(id2), 1.0)
+        .with_flex_spacer(1.0)
+        .with_flex_child(SizedBox::empty().with_id(id3), 3.0);
+    // more fixed content than fits: the flex child gets nothing.
+    let overflowing = Flex::row()
+        .with_child(sized(300.))
+        .with_flex_child(SizedBox::empty().with_id(id4), 1.0)
+        .with_child(sized(300.));
+    let widget = Flex::column().with_child(shared).with_child(overflowing);
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        let mut rect = |id| harness.get_state(id).expect("state").layout_rect;
+
+        assert_eq!(rect(id1).width(), 100.);
+        assert_eq!((rect(id2).x0, rect(id2).width()), (110., 58.));
+        assert_eq!((rect(id3).x0, rect(id3).width()), (226., 174.));
+        assert_eq!((rect(id4).x0, rect(id4).width()), (300., 0.));
+    })
+}
@@ -14,13 +14,18 @@
 
 //! Additional unit tests that cross file or module boundaries.
 
-mod harness;
-mod helpers;
+pub(crate) mod harness;
+pub(crate) mod helpers;
 mod layout_tests;
 
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
+use std::path::Path;
 use std::rc::Rc;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use crate::commands::{CANCEL_ANIMATION, COLLECT_WIDGET_BOUNDS, WIDGET_BOUNDS};
+use crate::gesture::GestureRecognizer;
 use crate::widget::*;
 use crate::*;
 use harness::*;
@@ -129,7 +134,7 @@ fn take_focus() {
         ModularWidget::new(inner)
             .event_fn(|_, ctx, event, _data, _env| {
                 if let Event::Command(cmd) = event {
-                    if cmd.selector == TAKE_FOCUS {
+                    if cmd.is(TAKE_FOCUS) {
                         ctx.request_focus();
                     }
                 }
@@ -173,6 +178,47 @@ fn take_focus() {
     })
 }
 
+#[test]
+fn focus_widget_command() {
+    fn focus_recorder(changes: Rc<RefCell<Vec<bool>>>) -> impl Widget<()> {
+        ModularWidget::new(changes).lifecycle_fn(|changes, _, event, _, _| {
+            if let LifeCycle::FocusChanged(focus) = event {
+                changes.borrow_mut().push(*focus);
+            }
+        })
+    }
+
+    let (id_1, id_2) = widget_id2();
+    let left: Rc<RefCell<Vec<bool>>> = Default::default();
+    let right: Rc<RefCell<Vec<bool>>> = Default::default();
+    let widget = Split::vertical(
+        focus_recorder(left.clone()).with_id(id_1),
+        focus_recorder(right.clone()).with_id(id_2),
+    );
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.submit_command(Command::new(commands::FOCUS_WIDGET, id_2), None);
+        assert_eq!(harness.window().focus, Some(id_2));
+        assert_eq!(*right.borrow(), vec![true]);
+
+        harness.submit_command(Command::new(commands::FOCUS_WIDGET, id_1), None);
+        assert_eq!(harness.window().focus, Some(id_1));
+        assert_eq!(*left.borrow(), vec![true]);
+        assert_eq!(*right.borrow(), vec![true, false]);
+
+        // focusing the focused widget again changes nothing.
+        harness.submit_command(Command::new(commands::FOCUS_WIDGET, id_1), None);
+        assert_eq!(*left.borrow(), vec![true]);
+
+        // a widget that isn't in the window is ignored.
+        let stranger = WidgetId::next();
+        harness.submit_command(Command::new(commands::FOCUS_WIDGET, stranger), None);
+        assert_eq!(harness.window().focus, Some(id_1));
+        assert_eq!(*left.borrow(), vec![true]);
+    })
+}
+
 #[test]
 fn simple_lifecyle() {
     let record = Recording::default();
@@ -230,9 +276,9 @@ fn participate_in_autofocus() {
 
     let widget = Split::vertical(
         Flex::row()
-            .with_child(TextBox::raw().with_id(id_1), 1.0)
-            .with_child(TextBox::raw().with_id(id_2), 1.0)
-            .with_child(TextBox::raw().with_id(id_3), 1.0),
+            .with_flex_child(TextBox::raw().with_id(id_1), 1.0)
+            .with_flex_child(TextBox::raw().with_id(id_2), 1.0)
+            .with_flex_child(TextBox::raw().with_id(id_3), 1.0),
         replacer,
     );
 
@@ -285,3 +331,1662 @@ fn child_tracking() {
         assert_eq!(split.children.entry_count(), 2);
     });
 }
+
+#[test]
+fn mouse_router_redirects_quadrant() {
+    let (left, right) = widget_id2();
+    let left_rec = Recording::default();
+    let right_rec = Recording::default();
+
+    let widget = Split::horizontal(
+        SizedBox::empty().expand().record(&left_rec).with_id(left),
+        SizedBox::empty().expand().record(&right_rec).with_id(right),
+    );
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        // clicks in the top-right quadrant always go to the left widget
+        harness.window_mut().mouse_router = Some(Box::new(move |mouse, size| {
+            if mouse.pos.x > size.width / 2. && mouse.pos.y < size.height / 2. {
+                MouseRoute::Widgets(vec![left])
+            } else {
+                MouseRoute::Tree
+            }
+        }));
+        left_rec.clear();
+        right_rec.clear();
+
+        harness.event(Event::MouseDown(left_mouse(350., 50., 1)));
+        assert_matches!(left_rec.next(), Record::E(Event::MouseDown(_)));
+        assert!(right_rec.is_empty());
+
+        left_rec.clear();
+        harness.event(Event::MouseDown(left_mouse(350., 350., 1)));
+        assert_matches!(right_rec.next(), Record::L(LifeCycle::HotChanged(true)));
+        assert_matches!(right_rec.next(), Record::E(Event::MouseDown(_)));
+        assert!(left_rec.is_empty());
+    });
+}
+
+#[test]
+fn active_widget_captures_mouse_from_router() {
+    let (left, right) = widget_id2();
+    let widget = Split::horizontal(
+        SizedBox::empty().expand().with_id(left),
+        SizedBox::empty().expand().with_id(right),
+    )
+    .draggable(true);
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        // nothing in the right half of the window gets the mouse.
+        harness.window_mut().mouse_router = Some(Box::new(move |mouse, size| {
+            if mouse.pos.x > size.width / 2. {
+                MouseRoute::Drop
+            } else {
+                MouseRoute::Tree
+            }
+        }));
+        let left_width = |harness: &mut Harness<()>| {
+            harness.just_layout();
+            harness.get_state(left).unwrap().layout_rect.width()
+        };
+        assert_eq!(left_width(harness), 195.);
+
+        // the dragged splitter follows the mouse into the right half...
+        harness.event(Event::MouseDown(left_mouse(199., 10., 1)));
+        harness.event(Event::MouseMoved(left_mouse(300., 10., 0)));
+        assert_eq!(left_width(harness), 295.);
+        harness.event(Event::MouseUp(left_mouse(300., 10., 0)));
+
+        // ...until the drag ends.
+        harness.event(Event::MouseDown(left_mouse(299., 10., 1)));
+        harness.event(Event::MouseMoved(left_mouse(350., 10., 0)));
+        assert_eq!(left_width(harness), 295.);
+    });
+}
+
+#[test]
+fn zoom_follows_scrolled_content() {
+    let first_rec = Recording::default();
+    let second_rec = Recording::default();
+    let widget = two_box_scroll(&first_rec, &second_rec);
+
+    fn received_zoom(rec: &Recording) -> bool {
+        let mut zoomed = false;
+        while !rec.is_empty() {
+            if let Record::E(Event::Zoom(_)) = rec.next() {
+                zoomed = true;
+            }
+        }
+        zoomed
+    }
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        harness.event(Event::MouseMoved(left_mouse(50., 250., 0)));
+        harness.event(Event::Zoom(0.5));
+        assert!(received_zoom(&first_rec));
+        assert!(!received_zoom(&second_rec));
+
+        // scrolling moves the second box under the pointer.
+        harness.event(Event::Wheel(WheelEvent {
+            delta: Vec2::new(0., 100.),
+            unit: WheelUnit::Pixels,
+            phase: WheelPhase::None,
+            mods: KeyModifiers::default(),
+        }));
+        harness.event(Event::Zoom(0.5));
+        assert!(!received_zoom(&first_rec));
+        assert!(received_zoom(&second_rec));
+    });
+}
+
+#[test]
+fn wheel_gesture_stays_on_one_widget() {
+    let first_rec = Recording::default();
+    let second_rec = Recording::default();
+    let widget = two_box_scroll(&first_rec, &second_rec);
+
+    fn received_wheel(rec: &Recording) -> bool {
+        let mut wheeled = false;
+        while !rec.is_empty() {
+            if let Record::E(Event::Wheel(_)) = rec.next() {
+                wheeled = true;
+            }
+        }
+        wheeled
+    }
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        harness.event(Event::MouseMoved(left_mouse(50., 250., 0)));
+        first_rec.clear();
+
+        // a line is 40 points: the first event scrolls the second box under
+        // the pointer, but the rest of the gesture goes where it began.
+        harness.event(wheel(2.5, WheelPhase::Began));
+        assert!(received_wheel(&first_rec));
+        harness.event(wheel(1., WheelPhase::Changed));
+        harness.event(wheel(1., WheelPhase::Ended));
+        harness.event(wheel(0., WheelPhase::MomentumEnded));
+        assert!(received_wheel(&first_rec));
+        assert!(!received_wheel(&second_rec));
+
+        // once the gesture is over, the pointer is hit-tested again.
+        harness.event(wheel(1., WheelPhase::None));
+        assert!(!received_wheel(&first_rec));
+        assert!(received_wheel(&second_rec));
+    });
+}
+
+#[test]
+fn scroll_gesture_without_momentum_rehits() {
+    let first_rec = Recording::default();
+    let second_rec = Recording::default();
+    let widget = two_box_scroll(&first_rec, &second_rec);
+
+    fn hot(rec: &Recording) -> Option<bool> {
+        let mut hot = None;
+        while !rec.is_empty() {
+            if let Record::L(LifeCycle::HotChanged(is_hot)) = rec.next() {
+                hot = Some(is_hot);
+            }
+        }
+        hot
+    }
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        harness.event(Event::MouseMoved(left_mouse(50., 250., 0)));
+        assert_eq!(hot(&first_rec), Some(true));
+
+        // the fingers lift with the second box under the pointer, and no
+        // momentum follows: it becomes hot without the mouse moving.
+        harness.event(wheel(2.5, WheelPhase::Began));
+        harness.event(wheel(1., WheelPhase::Ended));
+        assert_eq!(hot(&second_rec), None);
+        harness.advance_time(Duration::from_millis(100));
+        assert_eq!(hot(&first_rec), Some(false));
+        assert_eq!(hot(&second_rec), Some(true));
+    });
+}
+
+#[test]
+fn split_respects_min_size_and_snaps() {
+    let (left, right) = widget_id2();
+    let widget = Split::horizontal(
+        SizedBox::empty().expand().with_id(left),
+        SizedBox::empty().expand().with_id(right),
+    )
+    .draggable(true)
+    .min_size(100., 50.)
+    .snap_to_collapse(30.);
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        let left_width = |harness: &mut Harness<()>| {
+            harness.just_layout();
+            harness.get_state(left).unwrap().layout_rect.width()
+        };
+        // the 10px splitter starts in the middle of the 400px window.
+        assert_eq!(left_width(harness), 195.);
+
+        // grabbing just outside the visible splitter still works.
+        harness.event(Event::MouseDown(left_mouse(193., 10., 1)));
+        harness.event(Event::MouseMoved(left_mouse(60., 10., 0)));
+        assert_eq!(left_width(harness), 100.);
+        harness.event(Event::MouseMoved(left_mouse(20., 10., 0)));
+        assert_eq!(left_width(harness), 0.);
+        harness.event(Event::MouseMoved(left_mouse(350., 10., 0)));
+        assert_eq!(left_width(harness), 340.);
+        harness.event(Event::MouseMoved(left_mouse(390., 10., 0)));
+        assert_eq!(left_width(harness), 390.);
+        harness.event(Event::MouseUp(left_mouse(390., 10., 0)));
+        assert_eq!(harness.get_state(right).unwrap().layout_rect.width(), 0.);
+
+        // double-clicking resets the split point.
+        harness.event(Event::MouseDown(left_mouse(395., 10., 1)));
+        harness.event(Event::MouseUp(left_mouse(395., 10., 0)));
+        harness.event(Event::MouseDown(left_mouse(395., 10., 2)));
+        harness.event(Event::MouseUp(left_mouse(395., 10., 0)));
+        assert_eq!(left_width(harness), 195.);
+    });
+}
+
+#[test]
+fn interval_timer_repeats_until_cancelled() {
+    const START: Selector = Selector::new("druid-tests.start-interval");
+    const STOP: Selector = Selector::new("druid-tests.stop-interval");
+    let interval = Duration::from_secs(1);
+
+    // the widget only requests the timer once, and never re-arms it.
+    let ticks: Rc<Cell<usize>> = Default::default();
+    let widget = ModularWidget::new((ticks.clone(), None)).event_fn(
+        move |(ticks, token), ctx, event, _data, _env| match event {
+            Event::Command(cmd) if cmd.is(START) => {
+                *token = Some(ctx.request_interval_timer(interval));
+            }
+            Event::Command(cmd) if cmd.is(STOP) => {
+                ctx.cancel_timer(token.take().unwrap());
+            }
+            Event::Timer(t) if Some(*t) == *token => ticks.set(ticks.get() + 1),
+            _ => (),
+        },
+    );
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.submit_command(START, None);
+        assert_eq!(ticks.get(), 0);
+
+        harness.advance_time(interval / 2);
+        assert_eq!(ticks.get(), 0);
+
+        harness.advance_time(interval * 3);
+        assert_eq!(ticks.get(), 3);
+
+        harness.submit_command(STOP, None);
+        harness.advance_time(interval * 3);
+        assert_eq!(ticks.get(), 3);
+    });
+}
+
+#[test]
+fn cancelled_timer_never_fires() {
+    const CANCEL: Selector = Selector::new("druid-tests.cancel-timer");
+    let delay = Duration::from_secs(1);
+
+    let fired: Rc<Cell<usize>> = Default::default();
+    let widget = ModularWidget::new((fired.clone(), Vec::new())).event_fn(
+        move |(fired, tokens), ctx, event, _data, _env| match event {
+            Event::WindowConnected => {
+                tokens.push(ctx.request_timer(Instant::now() + delay));
+                tokens.push(ctx.request_timer(Instant::now() + delay));
+            }
+            Event::Command(cmd) if cmd.is(CANCEL) => ctx.cancel_timer(tokens[0]),
+            Event::Timer(t) if tokens.contains(t) => fired.set(fired.get() + 1),
+            _ => (),
+        },
+    );
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.submit_command(CANCEL, None);
+        harness.advance_time(delay * 2);
+        assert_eq!(fired.get(), 1);
+    });
+}
+
+#[test]
+fn unhandled_command_is_retried() {
+    const START: Selector = Selector::new("druid-tests.start");
+    const FLAKY: Selector = Selector::new("druid-tests.flaky");
+
+    // the handler fails (does not handle the command) twice, then succeeds.
+    let attempts: Rc<Cell<usize>> = Default::default();
+    let widget = ModularWidget::new(attempts.clone()).event_fn(|attempts, ctx, event, _, _| {
+        if let Event::Command(cmd) = event {
+            if cmd.is(START) {
+                let flaky = Command::from(FLAKY).retry(5, Duration::from_millis(10));
+                ctx.submit_command(flaky, None);
+            } else if cmd.is(FLAKY) {
+                attempts.set(attempts.get() + 1);
+                if attempts.get() == 3 {
+                    ctx.set_handled();
+                }
+            }
+        }
+    });
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.submit_command(START, None);
+        assert_eq!(attempts.get(), 1);
+
+        harness.advance_time(Duration::from_secs(1));
+        assert_eq!(attempts.get(), 3);
+
+        // once handled, it is not delivered again.
+        harness.advance_time(Duration::from_secs(1));
+        assert_eq!(attempts.get(), 3);
+    });
+}
+
+#[test]
+fn collect_widget_bounds() {
+    let (root, outer, middle, inner) = widget_id4();
+    let rec = Recording::default();
+
+    let widget = SizedBox::empty()
+        .width(100.)
+        .height(50.)
+        .record(&rec)
+        .with_id(inner)
+        .padding(10.)
+        .with_id(middle)
+        .padding(5.)
+        .with_id(outer)
+        .center()
+        .with_id(root);
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        rec.clear();
+
+        harness.submit_command(
+            Command::new(COLLECT_WIDGET_BOUNDS, Some(Target::Widget(inner))),
+            None,
+        );
+        let bounds = loop {
+            match rec.next() {
+                Record::E(Event::Command(cmd)) if cmd.is(WIDGET_BOUNDS) => {
+                    break cmd.get(WIDGET_BOUNDS).unwrap().clone();
+                }
+                Record::None => panic!("no WIDGET_BOUNDS reply"),
+                _ => (),
+            }
+        };
+
+        // the 130x80 padded box is centered in the 400x400 window.
+        let expected = [
+            (root, Rect::new(0., 0., 400., 400.), 0),
+            (outer, Rect::new(135., 160., 265., 240.), 1),
+            (middle, Rect::new(140., 165., 260., 235.), 2),
+            (inner, Rect::new(150., 175., 250., 225.), 3),
+        ];
+        assert_eq!(bounds.len(), expected.len());
+        for (found, (id, rect, depth)) in bounds.iter().zip(expected.iter()) {
+            assert_eq!(found.id, *id);
+            assert_eq!(found.rect.origin(), rect.origin());
+            assert_eq!(found.rect.size(), rect.size());
+            assert_eq!(found.depth, *depth);
+        }
+        assert!(bounds[0]
+            .type_name
+            .starts_with("druid::widget::align::Align<"));
+    });
+}
+
+#[test]
+fn subtree_command_stays_in_subtree() {
+    const PING: Selector = Selector::new("druid-tests.ping");
+    let (subtree, inner, sibling) = widget_id3();
+    let subtree_rec = Recording::default();
+    let inner_rec = Recording::default();
+    let sibling_rec = Recording::default();
+
+    let left = SizedBox::empty()
+        .record(&inner_rec)
+        .with_id(inner)
+        .padding(5.)
+        .record(&subtree_rec)
+        .with_id(subtree);
+    let right = SizedBox::empty().record(&sibling_rec).with_id(sibling);
+    let widget = Split::vertical(left, right);
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        subtree_rec.clear();
+        inner_rec.clear();
+        sibling_rec.clear();
+
+        harness.submit_command(PING, Target::Subtree(subtree));
+        assert_matches!(subtree_rec.next(), Record::E(Event::Command(cmd)) if cmd.is(PING));
+        assert_matches!(inner_rec.next(), Record::E(Event::Command(cmd)) if cmd.is(PING));
+        assert!(sibling_rec.is_empty());
+    });
+}
+
+#[test]
+fn cancel_one_animation() {
+    /// A widget that counts its animation frames, and always wants another.
+    fn make_animated(frames: Rc<Cell<usize>>) -> impl Widget<()> {
+        ModularWidget::new(frames).lifecycle_fn(|frames, ctx, event, _, _| match event {
+            LifeCycle::WidgetAdded => ctx.request_anim_frame(),
+            LifeCycle::AnimFrame(_) => {
+                frames.set(frames.get() + 1);
+                ctx.request_anim_frame();
+            }
+            _ => (),
+        })
+    }
+
+    let (left, right) = widget_id2();
+    let left_frames = Rc::new(Cell::new(0));
+    let right_frames = Rc::new(Cell::new(0));
+    let widget = Split::vertical(
+        make_animated(left_frames.clone()).with_id(left),
+        make_animated(right_frames.clone()).with_id(right),
+    );
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.paint();
+        assert_eq!((left_frames.get(), right_frames.get()), (1, 1));
+        assert_eq!(harness.window().animating_widgets(), &[left, right]);
+
+        harness.submit_command(Command::new(CANCEL_ANIMATION, Some(left)), None);
+        assert_eq!(harness.window().animating_widgets(), &[right]);
+        harness.paint();
+        harness.paint();
+        assert_eq!((left_frames.get(), right_frames.get()), (1, 3));
+        assert!(harness.window().wants_animation_frame());
+
+        // with no argument, everything stops.
+        harness.submit_command(Command::new(CANCEL_ANIMATION, None), None);
+        assert!(!harness.window().wants_animation_frame());
+        harness.paint();
+        assert_eq!((left_frames.get(), right_frames.get()), (1, 3));
+    });
+}
+
+#[test]
+fn pinch_is_recognized() {
+    let record = Recording::default();
+    let widget = SizedBox::empty().record(&record);
+    Harness::create((), widget, |harness| {
+        harness.window_mut().gestures = GestureRecognizer::new(GestureSet {
+            pinch: true,
+            ..Default::default()
+        });
+        harness.send_initial_events();
+        harness.just_layout();
+
+        let pos = Point::new(10., 10.);
+        harness.event(Event::MouseMoved(MouseEvent {
+            pos,
+            window_pos: pos,
+            mods: KeyModifiers::default(),
+            count: 0,
+            button: MouseButton::Left,
+        }));
+        record.clear();
+
+        // each step of the pinch arrives as a raw zoom, followed by the gesture.
+        for &(delta, scale) in &[(0.25, 1.25), (-0.5, 0.5)] {
+            harness.event(Event::Zoom(delta));
+            assert_matches!(record.next(), Record::E(Event::Zoom(_)));
+            match record.next() {
+                Record::E(Event::Gesture(Gesture::Pinch { scale: s })) => assert_eq!(s, scale),
+                other => panic!("expected a pinch, found {:?}", other),
+            }
+        }
+
+        // turning the wheel with ctrl held pinches too; one notch up zooms in.
+        let mods = KeyModifiers {
+            ctrl: true,
+            ..Default::default()
+        };
+        harness.event(Event::Wheel(WheelEvent {
+            delta: Vec2::new(0., -3.),
+            unit: WheelUnit::Lines,
+            phase: WheelPhase::None,
+            mods,
+        }));
+        assert_matches!(record.next(), Record::E(Event::Wheel(_)));
+        assert_matches!(
+            record.next(),
+            Record::E(Event::Gesture(Gesture::Pinch { scale })) if (scale - 1.1).abs() < 1e-9
+        );
+
+        // other gestures were not enabled.
+        harness.event(Event::MouseDown(MouseEvent {
+            pos,
+            window_pos: pos,
+            mods: KeyModifiers::default(),
+            count: 1,
+            button: MouseButton::Left,
+        }));
+        assert_matches!(record.next(), Record::E(Event::MouseDown(_)));
+        assert!(record.is_empty());
+    })
+}
+
+#[test]
+fn files_are_dropped_on_widget_under_pointer() {
+    let (left, right) = widget_id2();
+    let left_rec = Recording::default();
+    let dropped = Rc::new(RefCell::new(Vec::new()));
+
+    let target = ModularWidget::new(dropped.clone())
+        .event_fn(|dropped, ctx, event, _data, _env| match event {
+            Event::FileDragOver(_) => ctx.set_handled(),
+            Event::FileDrop(drop) => {
+                dropped.borrow_mut().push(drop.clone());
+                ctx.set_handled();
+            }
+            _ => (),
+        })
+        .layout_fn(|_, _, bc, _, _| bc.max());
+    let widget = Split::horizontal(
+        SizedBox::empty().expand().record(&left_rec).with_id(left),
+        target.with_id(right),
+    );
+    let files = vec![FileInfo::new("/tmp/a.txt"), FileInfo::new("/tmp/b.txt")];
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        left_rec.clear();
+
+        let rect = harness.get_state(right).unwrap().layout_rect;
+        let pos = rect.origin() + Vec2::new(10., 10.);
+        let over = FileDropEvent {
+            pos,
+            files: files.clone(),
+        };
+        assert!(harness.event(Event::FileDragOver(over.clone())));
+        assert!(harness.event(Event::FileDrop(over)));
+        assert!(left_rec.is_empty());
+
+        // nothing accepts files dropped on the left.
+        let elsewhere = FileDropEvent {
+            pos: Point::new(10., 10.),
+            files: files.clone(),
+        };
+        assert!(!harness.event(Event::FileDrop(elsewhere)));
+        assert_matches!(left_rec.next(), Record::E(Event::FileDrop(_)));
+    });
+
+    let dropped = dropped.borrow();
+    assert_eq!(dropped.len(), 1);
+    assert_eq!(dropped[0].pos, Point::new(10., 10.));
+    let paths: Vec<_> = dropped[0].files.iter().map(|f| f.path()).collect();
+    assert_eq!(
+        paths,
+        vec![Path::new("/tmp/a.txt"), Path::new("/tmp/b.txt")]
+    );
+}
+
+#[test]
+fn mouse_leave_clears_hot() {
+    let (button, empty, root) = widget_id3();
+    let button_rec = Recording::default();
+
+    let widget = Split::horizontal(
+        SizedBox::empty().with_id(empty),
+        Button::new("hot", |_, _, _| {})
+            .record(&button_rec)
+            .with_id(button),
+    )
+    .with_id(root);
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+
+        // press the button, and drag out of the window.
+        harness.event(Event::MouseMoved(left_mouse(210., 10., 0)));
+        harness.event(Event::MouseDown(left_mouse(210., 10., 1)));
+        button_rec.clear();
+        harness.event(Event::MouseLeave);
+
+        assert!(!harness.get_state(root).unwrap().is_hot);
+        assert!(!harness.get_state(button).unwrap().is_hot);
+        assert_matches!(button_rec.next(), Record::L(LifeCycle::HotChanged(false)));
+        assert_matches!(button_rec.next(), Record::E(Event::MouseLeave));
+
+        // the release outside the window still reaches the active widget.
+        harness.event(Event::MouseUp(left_mouse(500., 10., 1)));
+        assert_matches!(button_rec.next(), Record::E(Event::MouseUp(_)));
+        assert!(!harness.get_state(button).unwrap().is_active);
+    });
+}
+
+#[test]
+fn tab_traverses_focus_chain() {
+    let (id_1, id_2, id_3) = widget_id3();
+
+    // a widget that uses tab itself, like an editor; shift-tab still leaves.
+    let consumer = ModularWidget::new(())
+        .event_fn(|_, ctx, event, _data, _env| {
+            if let Event::KeyDown(key) = event {
+                if key.key_code == KeyCode::Tab && !key.mods.shift {
+                    ctx.set_handled();
+                }
+            }
+        })
+        .lifecycle_fn(|_, ctx, event, _data, _env| {
+            if let LifeCycle::WidgetAdded = event {
+                ctx.register_for_focus();
+            }
+        });
+
+    let widget = Flex::row()
+        .with_flex_child(TextBox::raw().with_id(id_1), 1.0)
+        .with_flex_child(consumer.with_id(id_2), 1.0)
+        .with_flex_child(TextBox::raw().with_id(id_3), 1.0);
+
+    let tab = || {
+        Event::KeyDown(KeyEvent::for_test(
+            KeyModifiers::default(),
+            "\t",
+            KeyCode::Tab,
+        ))
+    };
+    let shift_tab = || {
+        let shift = KeyModifiers {
+            shift: true,
+            ..Default::default()
+        };
+        Event::KeyDown(KeyEvent::for_test(shift, "\t", KeyCode::Tab))
+    };
+
+    Harness::create(String::new(), widget, |harness| {
+        harness.send_initial_events();
+        assert_eq!(harness.window().focus, None);
+
+        assert!(harness.event(tab()));
+        assert_eq!(harness.window().focus, Some(id_1));
+        harness.event(tab());
+        assert_eq!(harness.window().focus, Some(id_2));
+        // the consumer keeps focus when it handles tab.
+        harness.event(tab());
+        assert_eq!(harness.window().focus, Some(id_2));
+        assert_eq!(harness.data(), "");
+
+        harness.event(shift_tab());
+        assert_eq!(harness.window().focus, Some(id_1));
+        // traversal wraps around in both directions.
+        harness.event(shift_tab());
+        assert_eq!(harness.window().focus, Some(id_3));
+        harness.event(tab());
+        assert_eq!(harness.window().focus, Some(id_1));
+    });
+}
+
+#[test]
+fn tab_without_focus_chain_is_not_handled() {
+    let tab = Event::KeyDown(KeyEvent::for_test(
+        KeyModifiers::default(),
+        "\t",
+        KeyCode::Tab,
+    ));
+    let widget = Flex::row().with_child(Label::new("no focus"));
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        // the key is left for the platform, or the delegate.
+        assert!(!harness.event(tab.clone()));
+        assert_eq!(harness.window().focus, None);
+    });
+}
+
+#[test]
+fn env_scope_does_not_leak_to_siblings() {
+    // (widget, method, text size)
+    let seen = Rc::new(RefCell::new(Vec::new()));
+
+    let probe = |name: &'static str| {
+        let (lc_seen, layout_seen, paint_seen) = (seen.clone(), seen.clone(), seen.clone());
+        ModularWidget::new(())
+            .lifecycle_fn(move |_, _, event, _, env| {
+                if let LifeCycle::WidgetAdded = event {
+                    let size = env.get(theme::TEXT_SIZE_NORMAL);
+                    lc_seen.borrow_mut().push((name, "lifecycle", size));
+                }
+            })
+            .layout_fn(move |_, _, bc, _, env| {
+                let size = env.get(theme::TEXT_SIZE_NORMAL);
+                layout_seen.borrow_mut().push((name, "layout", size));
+                bc.max()
+            })
+            .paint_fn(move |_, _, _, env| {
+                let size = env.get(theme::TEXT_SIZE_NORMAL);
+                paint_seen.borrow_mut().push((name, "paint", size));
+            })
+    };
+
+    let widget = Split::horizontal(
+        probe("scoped").env_scope(|env, _| env.set(theme::TEXT_SIZE_NORMAL, 31.0)),
+        probe("sibling"),
+    );
+    let default_size = theme::init().get(theme::TEXT_SIZE_NORMAL);
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.paint();
+
+        let seen = seen.borrow();
+        for method in &["lifecycle", "layout", "paint"] {
+            assert!(seen.contains(&("scoped", method, 31.0)), "{}", method);
+            assert!(
+                seen.contains(&("sibling", method, default_size)),
+                "{}",
+                method
+            );
+        }
+    });
+}
+
+#[test]
+fn virtual_list_builds_and_reuses_visible_rows() {
+    let built = Rc::new(Cell::new(0));
+    let added = Rc::new(Cell::new(0));
+    // (the item a widget was added for, the item it painted)
+    let painted = Rc::new(RefCell::new(Vec::new()));
+
+    let (built2, added2, painted2) = (built.clone(), added.clone(), painted.clone());
+    let list = VirtualList::new(20., move || {
+        built2.set(built2.get() + 1);
+        let added = added2.clone();
+        let painted = painted2.clone();
+        ModularWidget::new(None)
+            .lifecycle_fn(move |added_for, _, event, data: &u32, _| {
+                if let LifeCycle::WidgetAdded = event {
+                    added.set(added.get() + 1);
+                    *added_for = Some(*data);
+                }
+            })
+            .layout_fn(|_, _, bc, _, _| bc.max())
+            .paint_fn(move |added_for, _, data, _| {
+                painted.borrow_mut().push((added_for.unwrap(), *data));
+            })
+    })
+    .overscan(2)
+    .with_key(|item: &u32| *item);
+
+    let data = Arc::new((0..10_000).collect::<Vec<u32>>());
+    Harness::create(data, list, |harness| {
+        harness.send_initial_events();
+        harness.paint();
+        // 20 rows fill the 400px viewport, plus 2 rows of overscan below.
+        // only the visible rows are painted.
+        assert_eq!(built.get(), 22);
+        assert_eq!(added.get(), 22);
+        assert_eq!(painted.borrow().len(), 20);
+
+        // the wheel goes to the widget under the pointer.
+        let pos = Point::new(100., 100.);
+        harness.event(Event::MouseMoved(MouseEvent {
+            pos,
+            window_pos: pos,
+            mods: KeyModifiers::default(),
+            count: 0,
+            button: MouseButton::Left,
+        }));
+        harness.event(Event::Wheel(WheelEvent {
+            delta: Vec2::new(0., 400.),
+            unit: WheelUnit::Pixels,
+            phase: WheelPhase::None,
+            mods: KeyModifiers::default(),
+        }));
+        painted.borrow_mut().clear();
+        harness.paint();
+        // rows 18..42 are wanted; four are kept, and only two of the
+        // other twenty need a new widget. The eighteen reused widgets are
+        // added again, for their new rows.
+        assert_eq!(built.get(), 24);
+        assert_eq!(added.get(), 42);
+        assert_eq!(painted.borrow().len(), 20);
+        for (added_for, data) in painted.borrow().iter() {
+            assert_eq!(added_for, data);
+        }
+
+        // inserting at the front moves every row down; rows keep their
+        // widgets by key.
+        harness.edit_data(|data| Arc::make_mut(data).insert(0, 10_000));
+        painted.borrow_mut().clear();
+        harness.paint();
+        assert!(!painted.borrow().is_empty());
+        for (added_for, data) in painted.borrow().iter() {
+            assert_eq!(added_for, data);
+        }
+    });
+}
+
+#[test]
+fn caret_blink_repaints_only_the_caret() {
+    let root = WidgetId::next();
+    let textbox = TextBox::raw().padding(10.).with_id(root);
+
+    Harness::create(String::from("hello"), textbox, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        let pos = Point::new(20., 20.);
+        harness.event(Event::MouseDown(MouseEvent {
+            pos,
+            window_pos: pos,
+            mods: KeyModifiers::default(),
+            count: 1,
+            button: MouseButton::Left,
+        }));
+        harness.paint();
+
+        harness.advance_time(Duration::from_millis(600));
+        let state = harness.get_state(root).unwrap();
+        assert!(!state.needs_inval);
+        let rect = state.invalid.to_rect();
+        // a thin strip, in window coordinates.
+        assert!(rect.width() < 10.);
+        assert!(rect.x0 > 10.);
+        assert_eq!(rect.y0, 10.);
+    });
+}
+
+#[test]
+fn paint_skips_widgets_outside_invalid_rect() {
+    let painted = Rc::new(RefCell::new(Vec::new()));
+    let probe = |name: &'static str| {
+        let painted = painted.clone();
+        ModularWidget::new(())
+            .layout_fn(|_, _, bc, _, _| bc.max())
+            .paint_fn(move |_, _, _, _| painted.borrow_mut().push(name))
+    };
+    let widget = Split::horizontal(probe("left"), probe("right"));
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.paint();
+        assert_eq!(*painted.borrow(), vec!["left", "right"]);
+
+        painted.borrow_mut().clear();
+        harness.paint_rect(Rect::new(300., 100., 310., 110.));
+        assert_eq!(*painted.borrow(), vec!["right"]);
+    });
+}
+
+#[test]
+fn anim_frame_interval_starts_at_zero() {
+    const START: Selector = Selector::new("druid-tests.start-animation");
+
+    let intervals = Rc::new(RefCell::new(Vec::new()));
+    let intervals2 = intervals.clone();
+    // animates for two frames at a time.
+    let widget = ModularWidget::new(0)
+        .event_fn(|frames_left, ctx, event, _, _| {
+            if let Event::Command(cmd) = event {
+                if cmd.is(START) {
+                    *frames_left = 2;
+                    ctx.request_anim_frame();
+                }
+            }
+        })
+        .lifecycle_fn(move |frames_left, ctx, event, _, _| {
+            if let LifeCycle::AnimFrame(interval) = event {
+                intervals2.borrow_mut().push(*interval);
+                *frames_left -= 1;
+                if *frames_left > 0 {
+                    ctx.request_anim_frame();
+                }
+            }
+        });
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        for _ in 0..2 {
+            harness.submit_command(START, None);
+            for _ in 0..3 {
+                harness.paint();
+            }
+        }
+
+        // after an idle frame, the interval starts over.
+        let intervals = intervals.borrow();
+        assert_eq!(intervals.len(), 4);
+        assert_eq!(intervals[0], 0);
+        assert!(intervals[1] > 0);
+        assert_eq!(intervals[2], 0);
+        assert!(intervals[3] > 0);
+    });
+}
+
+#[test]
+fn harness_records_win_ctx_calls_and_commands() {
+    const CLICKED: Selector = Selector::new("druid-tests.clicked");
+    let button = Button::new("click", |ctx, _, _| ctx.submit_command(CLICKED, None));
+    let widget = Split::horizontal(button, TextBox::raw());
+
+    Harness::create(String::new(), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        harness.clear_calls();
+
+        harness.event(Event::MouseMoved(left_mouse(100., 100., 0)));
+        harness.event(Event::MouseDown(left_mouse(100., 100., 1)));
+        harness.event(Event::MouseUp(left_mouse(100., 100., 1)));
+        let commands = harness.take_commands();
+        assert_eq!(commands.len(), 1);
+        assert!(commands[0].1.is(CLICKED));
+        assert!(harness.take_commands().is_empty());
+
+        harness.event(Event::MouseMoved(left_mouse(300., 10., 0)));
+        assert_eq!(harness.calls().cursors.last(), Some(&Cursor::IBeam));
+        // the caret starts blinking.
+        harness.event(Event::MouseDown(left_mouse(300., 10., 1)));
+        assert!(!harness.calls().timers.is_empty());
+    });
+}
+
+#[test]
+fn controller_intercepts_commands() {
+    const RESET: Selector = Selector::new("druid-tests.reset");
+    struct Reset;
+
+    impl<W: Widget<String>> Controller<String, W> for Reset {
+        fn event(
+            &mut self,
+            child: &mut W,
+            ctx: &mut EventCtx,
+            event: &Event,
+            data: &mut String,
+            env: &Env,
+        ) {
+            match event {
+                Event::Command(cmd) if cmd.is(RESET) => {
+                    data.clear();
+                    ctx.set_handled();
+                }
+                _ => child.event(ctx, event, data, env),
+            }
+        }
+    }
+
+    let widget = TextBox::raw().controller(Reset);
+    Harness::create(String::from("hello"), widget, |harness| {
+        harness.send_initial_events();
+        assert_eq!(harness.data(), "hello");
+        harness.submit_command(RESET, None);
+        assert!(harness.data().is_empty());
+    });
+}
+
+#[test]
+fn view_switcher_rebuilds_and_adds_new_child() {
+    let added = Rc::new(Cell::new(0));
+    let built = Rc::new(Cell::new(0));
+    let (added2, built2) = (added.clone(), built.clone());
+    let widget = ViewSwitcher::new(
+        |data: &u32, _| *data < 3,
+        move |_, _| {
+            built2.set(built2.get() + 1);
+            let added = added2.clone();
+            ModularWidget::new(())
+                .lifecycle_fn(move |_, _, event, _, _| {
+                    if let LifeCycle::WidgetAdded = event {
+                        added.set(added.get() + 1);
+                    }
+                })
+                .boxed()
+        },
+    );
+
+    Harness::create(0u32, widget, |harness| {
+        harness.send_initial_events();
+        assert_eq!((built.get(), added.get()), (1, 1));
+        // same view, no rebuild
+        harness.edit_data(|data| *data = 2);
+        assert_eq!((built.get(), added.get()), (1, 1));
+        harness.edit_data(|data| *data = 3);
+        assert_eq!((built.get(), added.get()), (2, 2));
+        harness.just_layout();
+    });
+}
+
+#[test]
+fn either_updates_reactivated_branch() {
+    let seen = Rc::new(Cell::new(0));
+    let seen2 = seen.clone();
+    let false_branch = ModularWidget::new(()).update_fn(move |_, _, _, data: &u32, _| {
+        seen2.set(*data);
+    });
+    let widget = Either::new(|data: &u32, _| *data >= 10, Label::new("big"), false_branch);
+
+    Harness::create(1u32, widget, |harness| {
+        harness.send_initial_events();
+        harness.edit_data(|data| *data = 2);
+        assert_eq!(seen.get(), 2);
+        // the false branch is inactive, and does not see these.
+        harness.edit_data(|data| *data = 10);
+        harness.edit_data(|data| *data = 11);
+        assert_eq!(seen.get(), 2);
+        // when it becomes active again, it is brought up to date.
+        harness.edit_data(|data| *data = 3);
+        assert_eq!(seen.get(), 3);
+    });
+}
+
+#[test]
+fn maybe_rebuilds_on_some_none_transitions() {
+    let added = Rc::new(RefCell::new(Vec::new()));
+    let probe = |name: &'static str| {
+        let added = added.clone();
+        move |_: &mut (), _: &mut LifeCycleCtx, event: &LifeCycle| {
+            if let LifeCycle::WidgetAdded = event {
+                added.borrow_mut().push(name);
+            }
+        }
+    };
+    let (some_probe, none_probe) = (probe("some"), probe("none"));
+    let widget = Maybe::new(
+        move || {
+            let probe = some_probe.clone();
+            ModularWidget::new(())
+                .lifecycle_fn(move |s, ctx, event, _: &u32, _| probe(s, ctx, event))
+        },
+        move || {
+            let probe = none_probe.clone();
+            ModularWidget::new(())
+                .lifecycle_fn(move |s, ctx, event, _: &(), _| probe(s, ctx, event))
+        },
+    );
+
+    Harness::create(None, widget, |harness| {
+        harness.send_initial_events();
+        harness.edit_data(|data| *data = Some(1));
+        harness.edit_data(|data| *data = Some(2));
+        harness.edit_data(|data| *data = None);
+        harness.just_layout();
+        assert_eq!(*added.borrow(), vec!["none", "some", "none"]);
+    });
+}
+
+#[test]
+fn show_context_menu_from_widget() {
+    let widget = ModularWidget::new(()).event_fn(|_, ctx, event, _: &mut (), _| {
+        if let Event::MouseDown(mouse) = event {
+            ctx.show_context_menu(MenuDesc::<()>::empty(), mouse.window_pos)
+                .unwrap();
+        }
+    });
+    let widget = widget.padding(20.);
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        let pos = Point::new(30., 40.);
+        harness.event(Event::MouseDown(MouseEvent {
+            pos,
+            window_pos: pos,
+            mods: KeyModifiers::default(),
+            count: 1,
+            button: MouseButton::Right,
+        }));
+        let commands = harness.take_commands();
+        assert_eq!(commands.len(), 1);
+        let cmd = &commands[0].1;
+        assert!(cmd.is(commands::SHOW_CONTEXT_MENU));
+        let menu = cmd.get(commands::SHOW_CONTEXT_MENU).unwrap();
+        let menu = menu.get::<()>().unwrap();
+        assert_eq!(menu.location, pos);
+    });
+}
+
+#[test]
+fn windows_and_menus_for_other_data_are_rejected() {
+    let errors = Rc::new(RefCell::new(Vec::new()));
+    let errors2 = errors.clone();
+    let widget = ModularWidget::new(()).event_fn(move |_, ctx, event, _: &mut (), _| {
+        if let Event::MouseDown(mouse) = event {
+            let mut errors = errors2.borrow_mut();
+            errors.push(ctx.new_window(WindowDesc::new(|| Label::<u32>::new("u32"))));
+            errors.push(ctx.show_modal(WindowDesc::new(|| Label::<u32>::new("u32"))));
+            errors.push(ctx.set_menu(MenuDesc::<u32>::empty()));
+            errors.push(ctx.show_context_menu(MenuDesc::<u32>::empty(), mouse.window_pos));
+        }
+    });
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        harness.event(Event::MouseDown(MouseEvent {
+            pos: Point::ORIGIN,
+            window_pos: Point::ORIGIN,
+            mods: KeyModifiers::default(),
+            count: 1,
+            button: MouseButton::Right,
+        }));
+        assert!(harness.take_commands().is_empty());
+    });
+    let errors = errors.borrow();
+    assert_eq!(errors.len(), 4);
+    assert!(errors.iter().all(Result::is_err));
+}
+
+#[test]
+fn scroll_to_view_command() {
+    let scroll_id = WidgetId::next();
+    let clicked = Rc::new(Cell::new(Point::ORIGIN));
+    let clicked2 = clicked.clone();
+    let content = ModularWidget::new(())
+        .event_fn(move |_, _, event, _: &mut (), _| {
+            if let Event::MouseDown(mouse) = event {
+                clicked2.set(mouse.pos);
+            }
+        })
+        .layout_fn(|_, _, _, _, _| Size::new(100., 1000.));
+    let scroll = Scroll::new(content)
+        .vertical()
+        .with_id(scroll_id)
+        .fix_size(100., 100.);
+    let widget = Align::new(UnitPoint::TOP_LEFT, scroll);
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        let offset_after = |harness: &mut Harness<()>, rect: Rect| {
+            let cmd = Command::new(commands::SCROLL_TO_VIEW, rect);
+            harness.submit_command(cmd, Target::Widget(scroll_id));
+            harness.just_layout();
+            let pos = Point::new(10., 10.);
+            harness.event(Event::MouseDown(MouseEvent {
+                pos,
+                window_pos: pos,
+                mods: KeyModifiers::default(),
+                count: 1,
+                button: MouseButton::Left,
+            }));
+            clicked.get().y - 10.
+        };
+
+        // below the viewport: scroll just far enough
+        assert_eq!(offset_after(harness, Rect::new(0., 500., 100., 520.)), 420.);
+        // already visible: stay put
+        assert_eq!(offset_after(harness, Rect::new(0., 430., 100., 450.)), 420.);
+        // above the viewport
+        assert_eq!(offset_after(harness, Rect::new(0., 100., 100., 120.)), 100.);
+        // larger than the viewport: show its top
+        assert_eq!(offset_after(harness, Rect::new(0., 200., 100., 600.)), 200.);
+        // clamped to the content
+        assert_eq!(
+            offset_after(harness, Rect::new(0., 990., 100., 1010.)),
+            900.
+        );
+    });
+}
+
+#[test]
+fn scrollbar_track_pages() {
+    fn make_widget(
+        visibility: ScrollbarVisibility,
+        clicked: Rc<Cell<Option<Point>>>,
+    ) -> impl Widget<()> {
+        let content = ModularWidget::new(())
+            .event_fn(move |_, _, event, _: &mut (), _| {
+                if let Event::MouseDown(mouse) = event {
+                    clicked.set(Some(mouse.pos));
+                }
+            })
+            .layout_fn(|_, _, _, _, _| Size::new(100., 300.));
+        let scroll = Scroll::new(content)
+            .vertical()
+            .scrollbar_visibility(visibility)
+            .fix_size(100., 100.);
+        Align::new(UnitPoint::TOP_LEFT, scroll)
+    }
+
+    // where the content was clicked, if it was.
+    fn click(
+        harness: &mut Harness<()>,
+        clicked: &Cell<Option<Point>>,
+        x: f64,
+        y: f64,
+    ) -> Option<Point> {
+        clicked.set(None);
+        let pos = Point::new(x, y);
+        harness.event(Event::MouseDown(MouseEvent {
+            pos,
+            window_pos: pos,
+            mods: KeyModifiers::default(),
+            count: 1,
+            button: MouseButton::Left,
+        }));
+        harness.just_layout();
+        clicked.get()
+    }
+
+    let clicked = Rc::new(Cell::new(None));
+    let widget = make_widget(ScrollbarVisibility::Always, clicked.clone());
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        // below the thumb: down a page
+        assert_eq!(click(harness, &clicked, 95., 80.), None);
+        assert_eq!(
+            click(harness, &clicked, 10., 10.),
+            Some(Point::new(10., 110.))
+        );
+        // above the thumb: back up
+        assert_eq!(click(harness, &clicked, 95., 5.), None);
+        assert_eq!(
+            click(harness, &clicked, 10., 10.),
+            Some(Point::new(10., 10.))
+        );
+    });
+
+    let widget = make_widget(ScrollbarVisibility::Never, clicked.clone());
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        assert_eq!(
+            click(harness, &clicked, 95., 80.),
+            Some(Point::new(95., 80.))
+        );
+    });
+}
+#[test]
+fn textarea_moves_between_lines() {
+    let key = |text: &'static str, code: KeyCode| {
+        Event::KeyDown(KeyEvent::for_test(KeyModifiers::default(), text, code))
+    };
+
+    Harness::create(String::new(), TextArea::new(), |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        // give it focus
+        harness.event(key("\t", KeyCode::Tab));
+
+        harness.event(key("a", KeyCode::KeyA));
+        harness.event(key("b", KeyCode::KeyB));
+        harness.event(key("\r", KeyCode::Return));
+        harness.event(key("c", KeyCode::KeyC));
+        harness.event(key("d", KeyCode::KeyD));
+        assert_eq!(harness.data(), "ab\ncd");
+
+        harness.event(key("", KeyCode::ArrowUp));
+        harness.event(key("X", KeyCode::KeyX));
+        assert_eq!(harness.data(), "abX\ncd");
+
+        harness.event(key("", KeyCode::ArrowDown));
+        harness.event(key("Y", KeyCode::KeyY));
+        assert_eq!(harness.data(), "abX\ncdY");
+
+        harness.event(key("", KeyCode::Home));
+        harness.event(key("Z", KeyCode::KeyZ));
+        assert_eq!(harness.data(), "abX\nZcdY");
+    });
+}
+
+#[test]
+fn tooltip_shows_after_delay() {
+    let widget = SizedBox::empty().expand().tooltip("hello");
+    let moved = |x: f64| {
+        let pos = Point::new(x, 10.);
+        Event::MouseMoved(MouseEvent {
+            pos,
+            window_pos: pos,
+            mods: KeyModifiers::default(),
+            count: 0,
+            button: MouseButton::Left,
+        })
+    };
+
+    Harness::create((), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        harness.event(moved(10.));
+        assert_eq!(harness.calls().timers.len(), 1);
+        // moving again restarts the delay
+        harness.event(moved(20.));
+        assert_eq!(harness.calls().timers.len(), 2);
+
+        // painting the shown tooltip goes through the z-order ops
+        harness.advance_time(Duration::from_secs(1));
+        harness.paint();
+
+        // moving away from the child doesn't start a new delay
+        harness.event(moved(500.));
+        assert_eq!(harness.calls().timers.len(), 2);
+    });
+}
+
+#[test]
+fn tabs_switch_and_keep_hidden_children() {
+    let clicks = Rc::new(RefCell::new(Vec::new()));
+    let make_child = |name: &'static str| {
+        let clicks = clicks.clone();
+        ModularWidget::new(0)
+            .event_fn(move |count, _, event, _: &mut usize, _| {
+                if let Event::MouseDown(_) = event {
+                    *count += 1;
+                    clicks.borrow_mut().push((name, *count));
+                }
+            })
+            .layout_fn(|_, _, bc, _, _| bc.max())
+    };
+    let widget = Tabs::new(lens::Id)
+        .with_tab("one", make_child("one"))
+        .with_tab("two", make_child("two"));
+
+    let click = |harness: &mut Harness<usize>, x: f64, y: f64| {
+        let pos = Point::new(x, y);
+        let mouse = MouseEvent {
+            pos,
+            window_pos: pos,
+            mods: KeyModifiers::default(),
+            count: 1,
+            button: MouseButton::Left,
+        };
+        harness.event(Event::MouseDown(mouse.clone()));
+        harness.event(Event::MouseUp(mouse));
+    };
+    let key = |code: KeyCode| Event::KeyDown(KeyEvent::for_test(KeyModifiers::default(), "", code));
+
+    Harness::create(0usize, widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+
+        // clicking the first tab gives the tab bar focus
+        click(harness, 2., 2.);
+        click(harness, 10., 100.);
+        assert_eq!(*harness.data(), 0);
+
+        harness.event(key(KeyCode::ArrowRight));
+        assert_eq!(*harness.data(), 1);
+        harness.just_layout();
+        click(harness, 10., 100.);
+
+        harness.event(key(KeyCode::ArrowLeft));
+        assert_eq!(*harness.data(), 0);
+        harness.just_layout();
+        click(harness, 10., 100.);
+
+        assert_eq!(*clicks.borrow(), vec![("one", 1), ("two", 1), ("one", 2)]);
+    });
+}
+
+#[test]
+fn spinner_stops_animating_when_removed() {
+    let widget = ViewSwitcher::new(
+        |data: &bool, _env| *data,
+        |busy, _data| {
+            if *busy {
+                Box::new(Spinner::new())
+            } else {
+                Box::new(Label::new("done"))
+            }
+        },
+    );
+
+    Harness::create(true, widget, |harness| {
+        harness.send_initial_events();
+        harness.paint();
+        harness.paint();
+        assert!(harness.window().wants_animation_frame());
+
+        harness.edit_data(|busy| *busy = false);
+        harness.paint();
+        assert!(!harness.window().wants_animation_frame());
+    });
+}
+
+#[test]
+fn radio_group_arrow_keys() {
+    let group = RadioGroup::new(vec![("one", 1u32), ("two", 2), ("three", 3)]);
+    let key = |code| Event::KeyDown(KeyEvent::for_test(KeyModifiers::default(), "", code));
+
+    Harness::create(1u32, group, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+
+        // without focus, the arrow keys do nothing.
+        harness.event(key(KeyCode::ArrowDown));
+        assert_eq!(*harness.data(), 1);
+
+        harness.event(key(KeyCode::Tab));
+        harness.event(key(KeyCode::ArrowDown));
+        assert_eq!(*harness.data(), 2);
+        harness.event(key(KeyCode::ArrowRight));
+        harness.event(key(KeyCode::ArrowDown));
+        assert_eq!(*harness.data(), 3);
+        harness.event(key(KeyCode::ArrowUp));
+        assert_eq!(*harness.data(), 2);
+    });
+}
+
+#[test]
+fn collect_accessibility_tree() {
+    use crate::commands::{ACCESSIBILITY_TREE, COLLECT_ACCESSIBILITY_TREE};
+
+    fn flatten(node: &AccessNode, out: &mut Vec<AccessNode>) {
+        out.push(node.clone());
+        node.children.iter().for_each(|child| flatten(child, out));
+    }
+
+    let widget = Flex::column()
+        .with_child(Label::new("Name"))
+        .with_child(TextBox::with_placeholder("first and last"))
+        .with_child(Button::new("OK", |_, _, _| ()));
+
+    Harness::create("Ada".to_string(), widget, |harness| {
+        harness.send_initial_events();
+        harness.just_layout();
+        let key = KeyEvent::for_test(KeyModifiers::default(), "", KeyCode::Tab);
+        harness.event(Event::KeyDown(key));
+        harness.take_commands();
+
+        harness.submit_command(Command::new(COLLECT_ACCESSIBILITY_TREE, None), None);
+        let tree = harness
+            .take_commands()
+            .into_iter()
+            .find_map(|(_, cmd)| cmd.get(ACCESSIBILITY_TREE).cloned())
+            .expect("no ACCESSIBILITY_TREE reply");
+
+        let mut nodes = Vec::new();
+        flatten(&tree, &mut nodes);
+        let described: Vec<_> = nodes
+            .iter()
+            .filter(|node| node.role != Role::Generic)
+            .map(|node| (node.role, node.name.as_deref(), node.value.as_deref()))
+            .collect();
+        assert_eq!(
+            described,
+            vec![
+                (Role::Label, Some("Name"), None),
+                (Role::TextInput, Some("first and last"), Some("Ada")),
+                (Role::Button, Some("OK"), None),
+            ]
+        );
+        let focused: Vec<_> = nodes.iter().filter(|node| node.focused).collect();
+        assert_eq!(focused.len(), 1);
+        assert_eq!(focused[0].role, Role::TextInput);
+    });
+}
+
+#[test]
+fn combo_box_popup_overlays_content() {
+    const SELECT: Selector<u32> = Selector::new("druid-test.select");
+
+    // a combo box, 30 high, that opens a list of three items below itself;
+    // the list reports the item clicked to the combo box.
+    let selected = Rc::new(Cell::new(None));
+    let dismissed = Rc::new(Cell::new(0));
+    let (sel, dis) = (selected.clone(), dismissed.clone());
+    let combo = ModularWidget::new(())
+        .event_fn(move |_, ctx, event, _: &mut (), _| match event {
+            Event::MouseDown(_) => {
+                let owner = ctx.widget_id();
+                let mut list = Flex::column();
+                for i in 0..3 {
+                    let item = ModularWidget::new(i)
+                        .event_fn(move |i, ctx, event, _: &mut (), _| {
+                            if let Event::MouseDown(_) = event {
+                                ctx.submit_command(Command::new(SELECT, *i), owner);
+                                ctx.set_handled();
+                            }
+                        })
+                        .layout_fn(|_, _, bc, _, _| bc.constrain((200., 30.)));
+                    list.add_child(item);
+                }
+                ctx.show_overlay(list, Point::new(0., 30.));
+            }
+            Event::Command(cmd) if cmd.is(SELECT) => {
+                sel.set(cmd.get(SELECT).copied());
+                ctx.dismiss_overlay();
+            }
+            Event::Command(cmd) if cmd.is(commands::OVERLAY_DISMISSED) => dis.set(dis.get() + 1),
+            _ => (),
+        })
+        .layout_fn(|_, _, bc, _, _| bc.constrain((200., 30.)));
+    // a sibling that the list covers.
+    let sibling_rec = Recording::default();
+    let sibling = SizedBox::empty()
+        .width(200.)
+        .height(100.)
+        .record(&sibling_rec);
+    let widget = Flex::column().with_child(combo).with_child(sibling);
+
+    fn clicked(rec: &Recording) -> bool {
+        let mut clicked = false;
+        while !rec.is_empty() {
+            if let Record::E(Event::MouseDown(_)) = rec.next() {
+                clicked = true;
+            }
+        }
+        clicked
+    }
+
+    Harness::create((), widget, |harness| {
+        let click = |harness: &mut Harness<()>, x, y| {
+            harness.event(Event::MouseMoved(left_mouse(x, y, 0)));
+            harness.event(Event::MouseDown(left_mouse(x, y, 1)));
+            harness.event(Event::MouseUp(left_mouse(x, y, 1)));
+            harness.just_layout();
+        };
+        harness.send_initial_events();
+        harness.just_layout();
+
+        // the second item, over the sibling, is chosen.
+        click(harness, 50., 15.);
+        click(harness, 50., 75.);
+        assert_eq!(selected.get(), Some(1));
+        assert_eq!(dismissed.get(), 1);
+        assert!(!clicked(&sibling_rec));
+
+        // a click outside the list closes it, and goes no further.
+        click(harness, 50., 15.);
+        click(harness, 50., 125.);
+        assert_eq!(dismissed.get(), 2);
+        assert!(!clicked(&sibling_rec));
+
+        // so does escape; then the sibling can be clicked again.
+        click(harness, 50., 15.);
+        let escape = KeyEvent::for_test(KeyModifiers::default(), "", KeyCode::Escape);
+        harness.event(Event::KeyDown(escape));
+        assert_eq!(dismissed.get(), 3);
+        click(harness, 50., 75.);
+        assert!(clicked(&sibling_rec));
+        assert_eq!(selected.get(), Some(1));
+    });
+}
+
+#[test]
+fn window_local_state() {
+    const BUMP_LOCAL: Selector = Selector::new("druid-test.bump-local");
+    const BUMP_SHARED: Selector = Selector::new("druid-test.bump-shared");
+
+    /// Records the `(shared, local)` pairs it is updated with.
+    struct Counter(Rc<RefCell<Vec<(u32, u32)>>>);
+
+    impl Widget<(u32, u32)> for Counter {
+        fn event(&mut self, _: &mut EventCtx, event: &Event, data: &mut (u32, u32), _: &Env) {
+            match event {
+                Event::Command(cmd) if cmd.is(BUMP_LOCAL) => data.1 += 1,
+                Event::Command(cmd) if cmd.is(BUMP_SHARED) => data.0 += 1,
+                _ => (),
+            }
+        }
+
+        fn lifecycle(&mut self, _: &mut LifeCycleCtx, _: &LifeCycle, _: &(u32, u32), _: &Env) {}
+
+        fn update(&mut self, _: &mut UpdateCtx, _: &(u32, u32), data: &(u32, u32), _: &Env) {
+            self.0.borrow_mut().push(*data);
+        }
+
+        fn layout(
+            &mut self,
+            _: &mut LayoutCtx,
+            bc: &BoxConstraints,
+            _: &(u32, u32),
+            _: &Env,
+        ) -> Size {
+            bc.max()
+        }
+
+        fn paint(&mut self, _: &mut PaintCtx, _: &(u32, u32), _: &Env) {}
+    }
+
+    let updates = Rc::new(RefCell::new(Vec::new()));
+    let widget = crate::local_state::LocalState::new(10, Counter(updates.clone()));
+
+    Harness::create(0u32, widget, |harness| {
+        harness.send_initial_events();
+
+        // the local state changes, and the shared data does not; the child
+        // is updated anyway.
+        harness.submit_command(BUMP_LOCAL, None);
+        assert_eq!(*harness.data(), 0);
+        assert_eq!(updates.borrow().as_slice(), &[(0, 11)]);
+
+        harness.submit_command(BUMP_SHARED, None);
+        assert_eq!(*harness.data(), 1);
+        assert_eq!(updates.borrow().as_slice(), &[(0, 11), (1, 11)]);
+
+        // nothing changed, so nothing is updated.
+        harness.submit_command(Selector::NOOP, None);
+        assert_eq!(updates.borrow().len(), 2);
+    });
+}
+
+#[test]
+fn widget_added_exactly_once() {
+    let root_rec = Recording::default();
+    let row_rec = Recording::default();
+    let view_rec = Recording::default();
+    let (row_rec2, view_rec2) = (row_rec.clone(), view_rec.clone());
+
+    let list = List::new(move || SizedBox::empty().height(10.).record(&row_rec2));
+    let switcher = ViewSwitcher::new(
+        |data: &Arc<Vec<u32>>, _| data.len() > 1,
+        move |_, _| SizedBox::empty().height(10.).record(&view_rec2).boxed(),
+    );
+    let widget = Flex::column()
+        .with_child(list)
+        .with_child(switcher)
+        .record(&root_rec);
+
+    /// The number of `WidgetAdded`s in the recording; the first must be
+    /// one, and come before any layout or paint.
+    fn added(rec: &Recording) -> usize {
+        let mut added = 0;
+        let mut first = true;
+        while !rec.is_empty() {
+            match rec.next() {
+                Record::L(LifeCycle::WidgetAdded) => added += 1,
+                Record::Layout(_) | Record::Paint if first => panic!("laid out before added"),
+                _ => (),
+            }
+            first = false;
+        }
+        added
+    }
+
+    Harness::create(Arc::new(vec![1]), widget, |harness| {
+        harness.send_initial_events();
+        harness.paint();
+        assert_eq!(added(&root_rec), 1);
+        assert_eq!(added(&row_rec), 1);
+        assert_eq!(added(&view_rec), 1);
+
+        // new rows, and a new view, are added; the rest are not again.
+        harness.edit_data(|data| Arc::make_mut(data).extend(&[2, 3]));
+        harness.paint();
+        assert_eq!(added(&root_rec), 0);
+        assert_eq!(added(&row_rec), 2);
+        assert_eq!(added(&view_rec), 1);
+
+        harness.edit_data(|data| Arc::make_mut(data).push(4));
+        harness.paint();
+        assert_eq!(added(&root_rec), 0);
+        assert_eq!(added(&row_rec), 1);
+        assert_eq!(added(&view_rec), 0);
+    });
+}
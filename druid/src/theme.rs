@@ -35,6 +35,8 @@ pub const BORDER: Key<Color> = Key::new("border");
 pub const BORDER_LIGHT: Key<Color> = Key::new("border_light");
 pub const SELECTION_COLOR: Key<Color> = Key::new("selection_color");
 pub const CURSOR_COLOR: Key<Color> = Key::new("cursor_color");
+/// The color of validation errors, and the outline of the widgets showing them.
+pub const ERROR_COLOR: Key<Color> = Key::new("error_color");
 
 pub const FONT_NAME: Key<&str> = Key::new("font_name");
 pub const TEXT_SIZE_NORMAL: Key<f64> = Key::new("text_size_normal");
@@ -49,6 +51,17 @@ pub const SCROLL_BAR_WIDTH: Key<f64> = Key::new("scroll_bar_width");
 pub const SCROLL_BAR_PAD: Key<f64> = Key::new("scroll_bar_pad");
 pub const SCROLL_BAR_RADIUS: Key<f64> = Key::new("scroll_bar_radius");
 pub const SCROLL_BAR_EDGE_WIDTH: Key<f64> = Key::new("scroll_bar_edge_width");
+/// The distance a wheel event scrolls per line.
+pub const SCROLL_LINE_HEIGHT: Key<f64> = Key::new("scroll_line_height");
+
+pub const TOOLTIP_DELAY: Key<u64> = Key::new("tooltip_delay");
+
+/// Whether the layout direction is right-to-left.
+///
+/// When this is `true`, rows in a `Flex` run from right to left, a `Padding`
+/// swaps its left and right insets, and a `Label` mirrors its alignment.
+/// Set it on a subtree with `WidgetExt::env_scope`.
+pub const RIGHT_TO_LEFT: Key<bool> = Key::new("right_to_left");
 
 /// An initial theme.
 pub fn init() -> Env {
@@ -68,6 +81,7 @@ pub fn init() -> Env {
         .adding(BORDER_LIGHT, Color::rgb8(0xa1, 0xa1, 0xa1))
         .adding(SELECTION_COLOR, Color::rgb8(0xf3, 0x00, 0x21))
         .adding(CURSOR_COLOR, Color::WHITE)
+        .adding(ERROR_COLOR, Color::rgb8(0xe5, 0x39, 0x35))
         .adding(TEXT_SIZE_NORMAL, 15.0)
         .adding(BASIC_WIDGET_HEIGHT, 18.0)
         .adding(BORDERED_WIDGET_HEIGHT, 24.0)
@@ -78,7 +92,10 @@ pub fn init() -> Env {
         .adding(SCROLL_BAR_WIDTH, 8.)
         .adding(SCROLL_BAR_PAD, 2.)
         .adding(SCROLL_BAR_RADIUS, 5.)
-        .adding(SCROLL_BAR_EDGE_WIDTH, 1.);
+        .adding(SCROLL_BAR_EDGE_WIDTH, 1.)
+        .adding(SCROLL_LINE_HEIGHT, 40.)
+        .adding(TOOLTIP_DELAY, 600u64)
+        .adding(RIGHT_TO_LEFT, false);
 
     #[cfg(target_os = "windows")]
     {
@@ -19,7 +19,7 @@ use crate::theme;
 use crate::widget::{Label, LabelText};
 use crate::{
     BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, LinearGradient,
-    PaintCtx, RenderContext, UnitPoint, UpdateCtx, Widget,
+    PaintCtx, RenderContext, Role, UnitPoint, UpdateCtx, Widget,
 };
 
 /// A button with a text label.
@@ -78,7 +78,11 @@ impl<T: Data> Widget<T> for Button<T> {
         if let LifeCycle::HotChanged(_) = event {
             ctx.invalidate();
         }
-        self.label.lifecycle(ctx, event, data, env)
+        // the label names the button.
+        self.label.lifecycle(ctx, event, data, env);
+        if let LifeCycle::CollectAccessibility(access) = event {
+            access.set_role(Role::Button);
+        }
     }
 
     fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
@@ -19,8 +19,8 @@ use crate::piet::{LineCap, LineJoin, LinearGradient, RenderContext, StrokeStyle,
 use crate::theme;
 use crate::widget::Align;
 use crate::{
-    BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, UpdateCtx,
-    Widget,
+    BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Role,
+    UpdateCtx, Widget,
 };
 
 /// A checkbox that toggles a boolean
@@ -57,9 +57,14 @@ impl Widget<bool> for Checkbox {
         }
     }
 
-    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &bool, _env: &Env) {
-        if let LifeCycle::HotChanged(_) = event {
-            ctx.invalidate();
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &bool, _env: &Env) {
+        match event {
+            LifeCycle::HotChanged(_) => ctx.invalidate(),
+            LifeCycle::CollectAccessibility(access) => {
+                access.set_role(Role::CheckBox);
+                access.set_checked(*data);
+            }
+            _ => (),
         }
     }
 
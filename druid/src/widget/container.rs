@@ -15,6 +15,7 @@
 //! A widget that provides simple visual styling options to a child.
 
 use crate::shell::kurbo::{Point, Rect, RoundedRect, Size};
+use crate::widget::BackgroundBrush;
 use crate::{
     BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintBrush,
     PaintCtx, RenderContext, UpdateCtx, Widget, WidgetPod,
@@ -27,7 +28,7 @@ struct BorderStyle {
 
 /// A widget that provides simple visual styling options to a child.
 pub struct Container<T: Data> {
-    background: Option<PaintBrush>,
+    background: Option<BackgroundBrush<T>>,
     border: Option<BorderStyle>,
     corner_radius: f64,
 
@@ -45,8 +46,10 @@ impl<T: Data> Container<T> {
         }
     }
 
-    /// Paint background with a color or a gradient.
-    pub fn background(mut self, brush: impl Into<PaintBrush>) -> Self {
+    /// Paint background with a color, a gradient or a [`Painter`].
+    ///
+    /// [`Painter`]: struct.Painter.html
+    pub fn background(mut self, brush: impl Into<BackgroundBrush<T>>) -> Self {
         self.background = Some(brush.into());
         self
     }
@@ -86,7 +89,12 @@ impl<T: Data> Widget<T> for Container<T> {
         self.inner.lifecycle(ctx, event, data, env)
     }
 
-    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        if let Some(BackgroundBrush::Painter(_)) = self.background {
+            if !old_data.same(data) {
+                ctx.invalidate();
+            }
+        }
         self.inner.update(ctx, data, env);
     }
 
@@ -125,9 +133,20 @@ impl<T: Data> Widget<T> for Container<T> {
             paint_ctx.stroke(panel, &border.brush, border.width);
         };
 
-        if let Some(background) = &self.background {
-            paint_ctx.fill(panel, background);
-        };
+        match &mut self.background {
+            Some(BackgroundBrush::Brush(brush)) => paint_ctx.fill(panel, brush),
+            Some(BackgroundBrush::Painter(painter)) => match paint_ctx.save() {
+                Ok(()) => {
+                    paint_ctx.clip(panel);
+                    painter.paint(paint_ctx, data, env);
+                    if let Err(e) = paint_ctx.restore() {
+                        log::error!("restoring render context failed: {:?}", e);
+                    }
+                }
+                Err(e) => log::error!("saving render context failed: {:?}", e),
+            },
+            None => (),
+        }
 
         self.inner.paint(paint_ctx, data, env);
     }
@@ -0,0 +1,139 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A widget-controlling widget.
+
+use crate::kurbo::Size;
+use crate::{
+    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    UpdateCtx, Widget, WidgetId,
+};
+
+/// A trait for types that modify the behaviour of a child widget.
+///
+/// A `Controller` is a type that manages a child widget, overriding or
+/// customizing its event, lifecycle and update behaviour, without having to
+/// implement the whole `Widget` trait. It is installed with a
+/// [`ControllerHost`], usually through [`WidgetExt::controller`].
+///
+/// Each method is passed the child, and is responsible for calling the
+/// child's method if it should be called; the default implementations only
+/// do that.
+///
+/// # Examples
+///
+/// A controller that adds its child to the focus chain:
+///
+/// ```
+/// # use druid::widget::{Controller, TextBox, WidgetExt};
+/// # use druid::{Env, LifeCycle, LifeCycleCtx, Widget};
+/// struct Focusable;
+///
+/// impl<T, W: Widget<T>> Controller<T, W> for Focusable {
+///     fn lifecycle(
+///         &mut self,
+///         child: &mut W,
+///         ctx: &mut LifeCycleCtx,
+///         event: &LifeCycle,
+///         data: &T,
+///         env: &Env,
+///     ) {
+///         if let LifeCycle::WidgetAdded = event {
+///             ctx.register_for_focus();
+///         }
+///         child.lifecycle(ctx, event, data, env)
+///     }
+/// }
+///
+/// let textbox = TextBox::new().controller(Focusable);
+/// ```
+///
+/// [`ControllerHost`]: struct.ControllerHost.html
+/// [`WidgetExt::controller`]: trait.WidgetExt.html#method.controller
+pub trait Controller<T, W: Widget<T>> {
+    /// Analogous to [`Widget::event`].
+    ///
+    /// [`Widget::event`]: ../trait.Widget.html#tymethod.event
+    fn event(&mut self, child: &mut W, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        child.event(ctx, event, data, env)
+    }
+
+    /// Analogous to [`Widget::lifecycle`].
+    ///
+    /// [`Widget::lifecycle`]: ../trait.Widget.html#tymethod.lifecycle
+    fn lifecycle(
+        &mut self,
+        child: &mut W,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &T,
+        env: &Env,
+    ) {
+        child.lifecycle(ctx, event, data, env)
+    }
+
+    /// Analogous to [`Widget::update`].
+    ///
+    /// [`Widget::update`]: ../trait.Widget.html#tymethod.update
+    fn update(&mut self, child: &mut W, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        child.update(ctx, old_data, data, env)
+    }
+}
+
+/// A [`Widget`] that manages a child and a [`Controller`].
+///
+/// Layout and paint are passed straight through to the child.
+///
+/// [`Widget`]: ../trait.Widget.html
+/// [`Controller`]: trait.Controller.html
+pub struct ControllerHost<W, C> {
+    widget: W,
+    controller: C,
+}
+
+impl<W, C> ControllerHost<W, C> {
+    /// Create a new `ControllerHost`, managing `widget` with `controller`.
+    pub fn new(widget: W, controller: C) -> ControllerHost<W, C> {
+        ControllerHost { widget, controller }
+    }
+}
+
+impl<T: Data, W: Widget<T>, C: Controller<T, W>> Widget<T> for ControllerHost<W, C> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        self.controller
+            .event(&mut self.widget, ctx, event, data, env)
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        self.controller
+            .lifecycle(&mut self.widget, ctx, event, data, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        self.controller
+            .update(&mut self.widget, ctx, old_data, data, env)
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        self.widget.layout(ctx, bc, data, env)
+    }
+
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.widget.paint(paint_ctx, data, env)
+    }
+
+    fn id(&self) -> Option<WidgetId> {
+        self.widget.id()
+    }
+}
@@ -0,0 +1,512 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A dropdown, for picking one of a list of values.
+
+use std::time::{Duration, Instant};
+
+use crate::kurbo::{BezPath, Point, Rect, RoundedRect, Size, Vec2};
+use crate::piet::{
+    FontBuilder, PietText, PietTextLayout, RenderContext, Text, TextLayout, TextLayoutBuilder,
+};
+use crate::theme;
+use crate::{
+    commands, BoxConstraints, Command, Data, Env, Event, EventCtx, KeyCode, KeyEvent, KeyModifiers,
+    LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Role, Selector, UpdateCtx, Widget, WidgetId,
+};
+
+/// Sent by the list to its dropdown, with the index of the chosen option.
+const CHOOSE_OPTION: Selector<usize> = Selector::new("druid-builtin.dropdown-choose-option");
+
+/// Keys typed within this long of each other make up one search.
+const TYPE_SELECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The horizontal space around the text of an option.
+const TEXT_PADDING: f64 = 6.;
+
+/// The width of the area with the arrow.
+const ARROW_WIDTH: f64 = 18.;
+
+/// A dropdown, for picking one of a list of values.
+///
+/// The dropdown shows the label of the selected value; a click opens a list
+/// of all the options, in an overlay below it, and clicking an option sets
+/// the data to its value.
+///
+/// The dropdown can take focus. While it has focus, the up and down arrow
+/// keys select the previous and next value, typing selects the first option
+/// whose label starts with the typed text, and space, enter or alt+down
+/// open the list. In the open list the arrow keys move the highlight,
+/// typing moves it to a matching option, enter chooses the highlighted
+/// option, and escape closes the list without changing the data. The list
+/// also closes when the dropdown loses focus.
+pub struct Dropdown<T> {
+    labels: Vec<String>,
+    values: Vec<T>,
+    is_open: bool,
+    type_select: TypeSelect,
+}
+
+/// A list of options, in the overlay of a `Dropdown`.
+struct DropdownList {
+    owner: WidgetId,
+    labels: Vec<String>,
+    highlighted: Option<usize>,
+    min_width: f64,
+    row_height: f64,
+    type_select: TypeSelect,
+}
+
+/// Finds the option that matches the text typed so far.
+#[derive(Default)]
+struct TypeSelect {
+    typed: String,
+    last_key: Option<Instant>,
+}
+
+impl<T: Data + PartialEq> Dropdown<T> {
+    /// Create a dropdown from `(label, value)` pairs, in the order they are
+    /// listed.
+    pub fn new(options: impl IntoIterator<Item = (impl Into<String>, T)>) -> Self {
+        let (labels, values) = options
+            .into_iter()
+            .map(|(label, value)| (label.into(), value))
+            .unzip();
+        Dropdown {
+            labels,
+            values,
+            is_open: false,
+            type_select: TypeSelect::default(),
+        }
+    }
+
+    fn selected(&self, data: &T) -> Option<usize> {
+        self.values.iter().position(|value| value == data)
+    }
+
+    fn select(&self, index: usize, data: &mut T) {
+        if let Some(value) = self.values.get(index) {
+            *data = value.clone();
+        }
+    }
+
+    fn open(&mut self, ctx: &mut EventCtx, data: &T, env: &Env) {
+        let list = DropdownList {
+            owner: ctx.widget_id(),
+            labels: self.labels.clone(),
+            highlighted: self.selected(data),
+            min_width: ctx.size().width,
+            row_height: env.get(theme::BASIC_WIDGET_HEIGHT),
+            type_select: TypeSelect::default(),
+        };
+        ctx.show_overlay(list, Point::new(0., ctx.size().height));
+        self.is_open = true;
+        ctx.invalidate();
+    }
+
+    /// Handle a key while the list is closed.
+    fn key_down(&mut self, ctx: &mut EventCtx, key: &KeyEvent, data: &mut T, env: &Env) {
+        let plain = key.mods == KeyModifiers::default();
+        let alt = KeyModifiers {
+            alt: true,
+            ..Default::default()
+        };
+        match key.key_code {
+            KeyCode::Space | KeyCode::Return | KeyCode::NumpadEnter if plain => {
+                self.open(ctx, data, env)
+            }
+            KeyCode::ArrowDown if key.mods == alt => self.open(ctx, data, env),
+            KeyCode::ArrowUp | KeyCode::ArrowDown if plain => {
+                let next = match (self.selected(data), key.key_code) {
+                    (Some(index), KeyCode::ArrowUp) => index.saturating_sub(1),
+                    (Some(index), _) => index + 1,
+                    (None, _) => 0,
+                };
+                self.select(next, data);
+            }
+            _ => {
+                let text = match typed_text(key) {
+                    Some(text) => text,
+                    None => return,
+                };
+                if let Some(index) = self
+                    .type_select
+                    .find(text, &self.labels, self.selected(data))
+                {
+                    self.select(index, data);
+                }
+            }
+        }
+        ctx.set_handled();
+    }
+}
+
+impl<T: Data + PartialEq> Widget<T> for Dropdown<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::MouseDown(_) => {
+                ctx.request_focus();
+                if self.is_open {
+                    ctx.dismiss_overlay();
+                } else {
+                    self.open(ctx, data, env);
+                }
+            }
+            // while the list is open, it gets keys first.
+            Event::KeyDown(key) if ctx.has_focus() && !self.is_open => {
+                self.key_down(ctx, key, data, env)
+            }
+            Event::Command(cmd) if cmd.is(CHOOSE_OPTION) => {
+                if let Some(&index) = cmd.get(CHOOSE_OPTION) {
+                    self.select(index, data);
+                }
+                ctx.dismiss_overlay();
+            }
+            Event::Command(cmd) if cmd.is(commands::OVERLAY_DISMISSED) => {
+                self.is_open = false;
+                ctx.invalidate();
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, _env: &Env) {
+        match event {
+            LifeCycle::WidgetAdded => ctx.register_for_focus(),
+            LifeCycle::FocusChanged(has_focus) => {
+                if !has_focus && self.is_open {
+                    let dismiss = Command::new(commands::DISMISS_OVERLAY, ctx.widget_id());
+                    ctx.submit_command(dismiss, None);
+                }
+                ctx.invalidate();
+            }
+            LifeCycle::CollectAccessibility(access) => {
+                access.set_role(Role::ComboBox);
+                if let Some(index) = self.selected(data) {
+                    access.set_value(self.labels[index].as_str());
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, _env: &Env) {
+        if !old_data.same(data) {
+            ctx.invalidate();
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &T, env: &Env) -> Size {
+        bc.debug_check("Dropdown");
+
+        let widest = widest_label(ctx.text(), &self.labels, env);
+        bc.constrain(Size::new(
+            widest + 2. * TEXT_PADDING + ARROW_WIDTH,
+            env.get(theme::BORDERED_WIDGET_HEIGHT),
+        ))
+    }
+
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &T, env: &Env) {
+        let size = paint_ctx.size();
+        let border_color = if paint_ctx.has_focus() || self.is_open {
+            env.get(theme::PRIMARY_LIGHT)
+        } else {
+            env.get(theme::BORDER)
+        };
+        let rect = RoundedRect::from_origin_size(
+            Point::new(0.5, 0.5),
+            Size::new(size.width - 1., size.height - 1.).to_vec2(),
+            2.,
+        );
+        paint_ctx.fill(rect, &env.get(theme::BACKGROUND_LIGHT));
+        paint_ctx.stroke(rect, &border_color, 1.);
+
+        if let Some(index) = self.selected(data) {
+            let layout = text_layout(paint_ctx.text(), &self.labels[index], env);
+            let origin = text_origin(size.height, env);
+            paint_ctx.draw_text(&layout, origin, &env.get(theme::LABEL_COLOR));
+        }
+
+        // a small triangle, pointing down.
+        let center = Point::new(size.width - ARROW_WIDTH / 2., size.height / 2.);
+        let mut arrow = BezPath::new();
+        arrow.move_to((center.x - 4., center.y - 2.));
+        arrow.line_to((center.x + 4., center.y - 2.));
+        arrow.line_to((center.x, center.y + 3.));
+        arrow.close_path();
+        paint_ctx.fill(arrow, &env.get(theme::LABEL_COLOR));
+    }
+}
+
+impl DropdownList {
+    fn row_at(&self, pos: Point) -> Option<usize> {
+        if pos.y < 0. {
+            return None;
+        }
+        let row = (pos.y / self.row_height) as usize;
+        if row < self.labels.len() {
+            Some(row)
+        } else {
+            None
+        }
+    }
+
+    fn highlight(&mut self, ctx: &mut EventCtx, row: Option<usize>) {
+        if row != self.highlighted {
+            self.highlighted = row;
+            ctx.invalidate();
+        }
+    }
+
+    fn choose(&self, ctx: &mut EventCtx, row: usize) {
+        ctx.submit_command(Command::new(CHOOSE_OPTION, row), self.owner);
+    }
+
+    /// Handle a key; those it doesn't use go on to the window.
+    fn key_down(&mut self, ctx: &mut EventCtx, key: &KeyEvent) {
+        let last = self.labels.len().saturating_sub(1);
+        let row = match key.key_code {
+            KeyCode::ArrowUp => Some(self.highlighted.map_or(last, |row| row.saturating_sub(1))),
+            KeyCode::ArrowDown => Some(self.highlighted.map_or(0, |row| (row + 1).min(last))),
+            KeyCode::Home => Some(0),
+            KeyCode::End => Some(last),
+            KeyCode::Return | KeyCode::NumpadEnter => {
+                if let Some(row) = self.highlighted {
+                    self.choose(ctx, row);
+                }
+                None
+            }
+            _ => match typed_text(key) {
+                Some(text) => self.type_select.find(text, &self.labels, self.highlighted),
+                None => return,
+            },
+        };
+        if row.is_some() && !self.labels.is_empty() {
+            self.highlight(ctx, row);
+        }
+        ctx.set_handled();
+    }
+}
+
+impl Widget<()> for DropdownList {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, _data: &mut (), _env: &Env) {
+        match event {
+            Event::MouseMoved(mouse) if ctx.is_hot() => {
+                let row = self.row_at(mouse.pos);
+                self.highlight(ctx, row);
+            }
+            Event::MouseDown(_) => ctx.set_active(true),
+            // only a press that began in the list chooses an option.
+            Event::MouseUp(mouse) if ctx.is_active() => {
+                ctx.set_active(false);
+                if let Some(row) = self.row_at(mouse.pos).filter(|_| ctx.is_hot()) {
+                    self.choose(ctx, row);
+                }
+            }
+            Event::KeyDown(key) => self.key_down(ctx, key),
+            _ => (),
+        }
+    }
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _data: &(), _env: &Env) {}
+
+    fn update(&mut self, _ctx: &mut UpdateCtx, _old_data: &(), _data: &(), _env: &Env) {}
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &(), env: &Env) -> Size {
+        let widest = widest_label(ctx.text(), &self.labels, env) + 2. * TEXT_PADDING;
+        bc.constrain(Size::new(
+            widest.max(self.min_width),
+            self.row_height * self.labels.len() as f64,
+        ))
+    }
+
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, _data: &(), env: &Env) {
+        let size = paint_ctx.size();
+        let rect = Rect::from_origin_size(Point::ORIGIN, size);
+        paint_ctx.fill(rect, &env.get(theme::BACKGROUND_LIGHT));
+
+        let text_color = env.get(theme::LABEL_COLOR);
+        for (row, label) in self.labels.iter().enumerate() {
+            let y = row as f64 * self.row_height;
+            if self.highlighted == Some(row) {
+                let row_rect = Rect::new(0., y, size.width, y + self.row_height);
+                paint_ctx.fill(row_rect, &env.get(theme::PRIMARY_DARK));
+            }
+            let layout = text_layout(paint_ctx.text(), label, env);
+            let origin = text_origin(self.row_height, env) + Vec2::new(0., y);
+            paint_ctx.draw_text(&layout, origin, &text_color);
+        }
+
+        paint_ctx.stroke(rect.inset(-0.5), &env.get(theme::BORDER), 1.);
+    }
+}
+
+impl TypeSelect {
+    /// Add `text` to what has been typed, and find the option to select.
+    ///
+    /// The search starts at the `current` option, so that typing more of
+    /// its label keeps it; typing the same letter again moves on to the
+    /// next option that starts with that letter.
+    fn find(&mut self, text: &str, labels: &[String], current: Option<usize>) -> Option<usize> {
+        let now = Instant::now();
+        let is_new_search = match self.last_key {
+            Some(last) => now.duration_since(last) > TYPE_SELECT_TIMEOUT,
+            None => true,
+        };
+        if is_new_search {
+            self.typed.clear();
+        }
+        self.last_key = Some(now);
+        self.typed.push_str(&text.to_lowercase());
+
+        let first = self.typed.chars().next()?;
+        let (prefix, start) = if self.typed.chars().all(|c| c == first) {
+            (first.to_string(), current.map_or(0, |row| row + 1))
+        } else {
+            (self.typed.clone(), current.unwrap_or(0))
+        };
+        (0..labels.len())
+            .map(|i| (start + i) % labels.len())
+            .find(|&i| labels[i].to_lowercase().starts_with(&prefix))
+    }
+}
+
+/// The text a key types, if it types any, for type-to-select.
+fn typed_text(key: &KeyEvent) -> Option<&str> {
+    if key.mods.ctrl || key.mods.meta || key.mods.alt {
+        return None;
+    }
+    key.text()
+        .filter(|text| text.chars().all(|c| !c.is_control()))
+}
+
+fn text_layout(text: &mut PietText, label: &str, env: &Env) -> PietTextLayout {
+    let font = text
+        .new_font_by_name(env.get(theme::FONT_NAME), env.get(theme::TEXT_SIZE_NORMAL))
+        .build()
+        .unwrap();
+    text.new_text_layout(&font, label).build().unwrap()
+}
+
+fn widest_label(text: &mut PietText, labels: &[String], env: &Env) -> f64 {
+    labels
+        .iter()
+        .map(|label| text_layout(text, label, env).width())
+        .fold(0., f64::max)
+}
+
+/// The origin of a line of text, centered in a row of `height`.
+fn text_origin(height: f64, env: &Env) -> Point {
+    let font_size = env.get(theme::TEXT_SIZE_NORMAL);
+    Point::new(TEXT_PADDING, (height + font_size) / 2. - font_size * 0.2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::Harness;
+    use crate::tests::helpers::left_mouse;
+    use crate::widget::{Flex, WidgetExt};
+    use crate::{RawMods, Target};
+
+    fn key(text: &'static str, code: KeyCode) -> Event {
+        Event::KeyDown(KeyEvent::for_test(RawMods::None, text, code))
+    }
+
+    fn click(harness: &mut Harness<u32>, x: f64, y: f64) {
+        harness.event(Event::MouseMoved(left_mouse(x, y, 0)));
+        harness.event(Event::MouseDown(left_mouse(x, y, 1)));
+        harness.event(Event::MouseUp(left_mouse(x, y, 1)));
+        harness.just_layout();
+    }
+
+    fn dropdown() -> Dropdown<u32> {
+        Dropdown::new(vec![("Alpha", 0), ("Beta", 1), ("Gamma", 2), ("Delta", 3)])
+    }
+
+    #[test]
+    fn choose_with_mouse_and_keys() {
+        let (first, second) = (WidgetId::next(), WidgetId::next());
+        let widget = Flex::column()
+            .with_child(dropdown().with_id(first))
+            .with_child(dropdown().with_id(second));
+        Harness::create(0u32, widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            let height = harness.get_state(first).unwrap().layout_rect.height();
+            let row = |n: f64| height + 18. * n + 9.;
+
+            // the list opens below, over the second dropdown.
+            click(harness, 10., 10.);
+            click(harness, 10., row(2.));
+            assert_eq!(*harness.data(), 2);
+
+            // arrows move the highlight, from the selected option.
+            click(harness, 10., 10.);
+            harness.event(key("", KeyCode::ArrowDown));
+            harness.event(key("", KeyCode::Return));
+            assert_eq!(*harness.data(), 3);
+
+            // typing moves it to a match.
+            harness.event(key(" ", KeyCode::Space));
+            harness.event(key("b", KeyCode::KeyB));
+            harness.event(key("", KeyCode::Return));
+            assert_eq!(*harness.data(), 1);
+
+            // escape closes the list, and changes nothing; then the arrows
+            // select directly.
+            harness.event(key(" ", KeyCode::Space));
+            harness.event(key("", KeyCode::ArrowDown));
+            harness.event(key("", KeyCode::Escape));
+            assert_eq!(*harness.data(), 1);
+            harness.event(key("", KeyCode::ArrowUp));
+            assert_eq!(*harness.data(), 0);
+            harness.event(key("g", KeyCode::KeyG));
+            assert_eq!(*harness.data(), 2);
+
+            // the list closes when focus moves on.
+            harness.event(key(" ", KeyCode::Space));
+            harness.take_commands();
+            harness.event(key("", KeyCode::Tab));
+            assert_eq!(harness.window().focus, Some(second));
+            let dismissed = harness.take_commands().into_iter().any(|(target, cmd)| {
+                target == Target::Widget(first) && cmd.is(commands::OVERLAY_DISMISSED)
+            });
+            assert!(dismissed);
+            // ...so a click below the first dropdown is for the second.
+            click(harness, 10., row(0.));
+            harness.event(key("d", KeyCode::KeyD));
+            harness.event(key("", KeyCode::Return));
+            assert_eq!(*harness.data(), 3);
+        });
+    }
+
+    #[test]
+    fn type_select_cycles_and_extends() {
+        let labels: Vec<String> = vec!["Beta", "Alpha", "Bravo", "Bread"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let mut search = TypeSelect::default();
+        assert_eq!(search.find("b", &labels, None), Some(0));
+        assert_eq!(search.find("b", &labels, Some(0)), Some(2));
+        assert_eq!(search.find("b", &labels, Some(2)), Some(3));
+
+        let mut search = TypeSelect::default();
+        assert_eq!(search.find("b", &labels, Some(1)), Some(2));
+        assert_eq!(search.find("r", &labels, Some(2)), Some(2));
+        assert_eq!(search.find("e", &labels, Some(2)), Some(3));
+        assert_eq!(search.find("x", &labels, Some(3)), None);
+    }
+}
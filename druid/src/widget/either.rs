@@ -21,6 +21,11 @@ use crate::{
 };
 
 /// A widget that switches between two possible child views.
+///
+/// Only the active branch receives events, updates, layout and paint. Both
+/// branches are kept, and both receive lifecycle events, so switching back
+/// and forth is cheap; a branch that becomes active is updated with the
+/// current data before it is laid out or painted again.
 pub struct Either<T: Data> {
     closure: Box<dyn Fn(&T, &Env) -> bool>,
     true_branch: WidgetPod<T, Box<dyn Widget<T>>>,
@@ -47,14 +52,20 @@ impl<T: Data> Either<T> {
     }
 }
 
-impl<T: Data> Widget<T> for Either<T> {
-    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+impl<T: Data> Either<T> {
+    fn current_widget(&mut self) -> &mut WidgetPod<T, Box<dyn Widget<T>>> {
         if self.current {
-            self.true_branch.event(ctx, event, data, env)
+            &mut self.true_branch
         } else {
-            self.false_branch.event(ctx, event, data, env)
+            &mut self.false_branch
         }
     }
+}
+
+impl<T: Data> Widget<T> for Either<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        self.current_widget().event(ctx, event, data, env)
+    }
 
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
         if let LifeCycle::WidgetAdded = event {
@@ -69,13 +80,10 @@ impl<T: Data> Widget<T> for Either<T> {
         if current != self.current {
             self.current = current;
             ctx.invalidate();
-            // TODO: more event flow to request here.
-        }
-        if self.current {
-            self.true_branch.update(ctx, data, env);
-        } else {
-            self.false_branch.update(ctx, data, env);
         }
+        // the branch keeps the data it last saw, so a branch that was
+        // inactive catches up with every change it missed here.
+        self.current_widget().update(ctx, data, env);
     }
 
     fn layout(
@@ -85,26 +93,14 @@ impl<T: Data> Widget<T> for Either<T> {
         data: &T,
         env: &Env,
     ) -> Size {
-        if self.current {
-            let size = self.true_branch.layout(layout_ctx, bc, data, env);
-            self.true_branch
-                .set_layout_rect(Rect::from_origin_size(Point::ORIGIN, size));
-            layout_ctx.set_paint_insets(self.true_branch.paint_insets());
-            size
-        } else {
-            let size = self.false_branch.layout(layout_ctx, bc, data, env);
-            self.false_branch
-                .set_layout_rect(Rect::from_origin_size(Point::ORIGIN, size));
-            layout_ctx.set_paint_insets(self.true_branch.paint_insets());
-            size
-        }
+        let current_widget = self.current_widget();
+        let size = current_widget.layout(layout_ctx, bc, data, env);
+        current_widget.set_layout_rect(Rect::from_origin_size(Point::ORIGIN, size));
+        layout_ctx.set_paint_insets(current_widget.paint_insets());
+        size
     }
 
     fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &T, env: &Env) {
-        if self.current {
-            self.true_branch.paint(paint_ctx, data, env);
-        } else {
-            self.false_branch.paint(paint_ctx, data, env);
-        }
+        self.current_widget().paint(paint_ctx, data, env);
     }
 }
@@ -23,8 +23,12 @@ use crate::{
 };
 
 /// A widget that accepts a closure to update the environment for its child.
+///
+/// The closure receives a copy of the inherited `Env`, so anything it does
+/// not set keeps its inherited value, and its changes are only seen by the
+/// child and its descendants.
 pub struct EnvScope<T: Data, W: Widget<T>> {
-    f: Box<dyn Fn(&mut Env, &T)>,
+    f: Box<dyn FnMut(&mut Env, &T)>,
     child: W,
     phantom: PhantomData<T>,
 }
@@ -32,7 +36,8 @@ pub struct EnvScope<T: Data, W: Widget<T>> {
 impl<T: Data, W: Widget<T>> EnvScope<T, W> {
     /// Create a widget that updates the environment for its child.
     ///
-    /// Accepts a closure that sets Env values.
+    /// Accepts a closure that sets Env values. It is called each time the
+    /// child is passed an `Env`, with the current data.
     ///
     /// # Examples
     /// ```
@@ -51,7 +56,7 @@ impl<T: Data, W: Widget<T>> EnvScope<T, W> {
     ///
     /// # }
     /// ```
-    pub fn new(f: impl Fn(&mut Env, &T) + 'static, child: W) -> EnvScope<T, W> {
+    pub fn new(f: impl FnMut(&mut Env, &T) + 'static, child: W) -> EnvScope<T, W> {
         EnvScope {
             f: Box::new(f),
             child,
@@ -71,7 +76,8 @@ impl<T: Data, W: Widget<T>> Widget<T> for EnvScope<T, W> {
     fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
         let mut new_env = env.clone();
         (self.f)(&mut new_env, &data);
-        self.child.lifecycle(ctx, event, data, env)
+
+        self.child.lifecycle(ctx, event, data, &new_env)
     }
 
     fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
@@ -16,6 +16,7 @@
 
 use crate::kurbo::{Point, Rect, Size};
 
+use crate::widget::SizedBox;
 use crate::{
     BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
     UpdateCtx, Widget, WidgetPod,
@@ -43,7 +44,7 @@ struct Params {
 }
 
 impl Axis {
-    fn major(&self, coords: Size) -> f64 {
+    pub(crate) fn major(&self, coords: Size) -> f64 {
         match *self {
             Axis::Horizontal => coords.width,
             Axis::Vertical => coords.height,
@@ -89,23 +90,53 @@ impl<T: Data> Flex<T> {
     /// Builder-style variant of `add_child`
     ///
     /// Convenient for assembling a group of widgets in a single expression.
-    pub fn with_child(mut self, child: impl Widget<T> + 'static, flex: f64) -> Self {
-        self.add_child(child, flex);
+    pub fn with_child(mut self, child: impl Widget<T> + 'static) -> Self {
+        self.add_child(child);
         self
     }
 
-    /// Add a child widget.
-    ///
-    /// If `flex` is zero, then the child is non-flex. It is given the same
-    /// constraints on the "minor axis" as its parent, but unconstrained on the
-    /// "major axis".
+    /// Builder-style variant of `add_flex_child`
+    pub fn with_flex_child(mut self, child: impl Widget<T> + 'static, flex: f64) -> Self {
+        self.add_flex_child(child, flex);
+        self
+    }
+
+    /// Builder-style variant of `add_spacer`
+    pub fn with_spacer(mut self, len: f64) -> Self {
+        self.add_spacer(len);
+        self
+    }
+
+    /// Builder-style variant of `add_flex_spacer`
+    pub fn with_flex_spacer(mut self, flex: f64) -> Self {
+        self.add_flex_spacer(flex);
+        self
+    }
+
+    /// Add a non-flex child widget.
     ///
-    /// If `flex` is non-zero, then all the space left over after layout of
-    /// the non-flex children is divided up, in proportion to the `flex` value,
-    /// among the flex children.
+    /// The child is given the same constraints on the "minor axis" as its
+    /// parent, but is unconstrained on the "major axis", and takes up as much
+    /// space as it asks for.
     ///
     /// See also `with_child`.
-    pub fn add_child(&mut self, child: impl Widget<T> + 'static, flex: f64) {
+    pub fn add_child(&mut self, child: impl Widget<T> + 'static) {
+        self.add_flex_child(child, 0.0);
+    }
+
+    /// Add a flex child widget.
+    ///
+    /// All the space left over after layout of the non-flex children is
+    /// divided up among the flex children, in proportion to their `flex`
+    /// factors. A `flex` of zero makes the child non-flex.
+    ///
+    /// If the non-flex children already need more than the available space,
+    /// the flex children get none; the non-flex children keep their size and
+    /// overflow the end of the container.
+    ///
+    /// See also `with_flex_child`.
+    pub fn add_flex_child(&mut self, child: impl Widget<T> + 'static, flex: f64) {
+        assert!(flex >= 0.0, "flex must be 0.0 or greater!");
         let params = Params { flex };
         let child = ChildWidget {
             widget: WidgetPod::new(child).boxed(),
@@ -113,6 +144,24 @@ impl<T: Data> Flex<T> {
         };
         self.children.push(child);
     }
+
+    /// Add an empty space of a fixed length along the major axis.
+    pub fn add_spacer(&mut self, len: f64) {
+        let spacer = match self.direction {
+            Axis::Horizontal => SizedBox::empty().width(len),
+            Axis::Vertical => SizedBox::empty().height(len),
+        };
+        self.add_child(spacer);
+    }
+
+    /// Add an empty space that takes up a share of the leftover space along
+    /// the major axis, like a flex child.
+    ///
+    /// A spacer between two children pushes them apart; spacers on both
+    /// sides of a child center it.
+    pub fn add_flex_spacer(&mut self, flex: f64) {
+        self.add_flex_child(SizedBox::empty(), flex);
+    }
 }
 
 impl<T: Data> Widget<T> for Flex<T> {
@@ -196,24 +245,40 @@ impl<T: Data> Widget<T> for Flex<T> {
             }
         }
 
-        // Finalize layout, assigning positions to each child.
-        let mut major = 0.0;
+        if flex_sum > 0.0 && total_major.is_infinite() {
+            log::warn!("A child of Flex is flex, but Flex is unbounded.")
+        }
+
+        let content_major: f64 = self
+            .children
+            .iter()
+            .map(|child| self.direction.major(child.widget.layout_rect().size()))
+            .sum();
+        let major = if flex_sum > 0.0 {
+            total_major
+        } else {
+            content_major
+        };
+
+        // Finalize layout, assigning positions to each child. In a
+        // right-to-left layout, a row starts at the right, and a column
+        // aligns its children to the right.
+        let rtl = env.is_right_to_left();
+        let mut offset = 0.0;
         let mut child_paint_rect = Rect::ZERO;
         for child in &mut self.children {
             // top-align, could do center etc. based on child height
             let rect = child.widget.layout_rect();
-            let pos: Point = self.direction.pack(major, 0.0).into();
+            let child_major = self.direction.major(rect.size());
+            let (child_major_pos, child_minor_pos) = match self.direction {
+                Axis::Horizontal if rtl => (major - offset - child_major, 0.0),
+                Axis::Vertical if rtl => (offset, minor - self.direction.minor(rect.size())),
+                _ => (offset, 0.0),
+            };
+            let pos: Point = self.direction.pack(child_major_pos, child_minor_pos).into();
             child.widget.set_layout_rect(rect.with_origin(pos));
             child_paint_rect = child_paint_rect.union(child.widget.paint_rect());
-            major += self.direction.major(rect.size());
-        }
-
-        if flex_sum > 0.0 && total_major.is_infinite() {
-            log::warn!("A child of Flex is flex, but Flex is unbounded.")
-        }
-
-        if flex_sum > 0.0 {
-            major = total_major;
+            offset += child_major;
         }
 
         let (width, height) = self.direction.pack(major, minor);
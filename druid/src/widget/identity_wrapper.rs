@@ -57,4 +57,8 @@ impl<T: Data, W: Widget<T>> Widget<T> for IdentityWrapper<W> {
     fn id(&self) -> Option<WidgetId> {
         Some(self.id)
     }
+
+    fn type_name(&self) -> &'static str {
+        self.inner.type_name()
+    }
 }
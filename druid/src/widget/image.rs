@@ -0,0 +1,292 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A widget that displays a bitmap image.
+
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use log::error;
+
+use crate::piet::{ImageFormat, InterpolationMode};
+use crate::{
+    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    Point, Rect, RenderContext, Size, UpdateCtx, Widget,
+};
+
+/// How an [`Image`] fits the space it is given.
+///
+/// The image is always centered in that space. Parts of it that fall
+/// outside of the space are clipped.
+///
+/// [`Image`]: struct.Image.html
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillStrat {
+    /// As large as possible, while keeping the aspect ratio and showing all
+    /// of the image.
+    Contain,
+    /// As small as possible, while keeping the aspect ratio and filling all
+    /// of the space; some of the image may be cut off.
+    Cover,
+    /// Stretched to fill the space, ignoring the aspect ratio.
+    Fill,
+    /// Like `Contain`, but never larger than the image's own size.
+    ScaleDown,
+    /// At the image's own size, whatever the size of the space.
+    None,
+}
+
+impl FillStrat {
+    /// The rectangle that an image of `image` size is drawn into, in a
+    /// space of `space` size.
+    pub fn fit(self, image: Size, space: Size) -> Rect {
+        let size = if image.width <= 0. || image.height <= 0. {
+            Size::ZERO
+        } else {
+            let scale_x = space.width / image.width;
+            let scale_y = space.height / image.height;
+            match self {
+                FillStrat::Contain => image * scale_x.min(scale_y),
+                FillStrat::Cover => image * scale_x.max(scale_y),
+                FillStrat::Fill => space,
+                FillStrat::ScaleDown => image * scale_x.min(scale_y).min(1.),
+                FillStrat::None => image,
+            }
+        };
+        let origin = Point::new(
+            (space.width - size.width) / 2.,
+            (space.height - size.height) / 2.,
+        );
+        Rect::from_origin_size(origin, size)
+    }
+}
+
+/// Decoded pixels for an [`Image`].
+///
+/// The pixels are shared, so cloning an `ImageData` is cheap.
+///
+/// [`Image`]: struct.Image.html
+#[derive(Clone)]
+pub struct ImageData {
+    pixels: Arc<[u8]>,
+    width: usize,
+    height: usize,
+    format: ImageFormat,
+}
+
+impl ImageData {
+    /// Create an `ImageData` from raw pixels, with rows running from top to
+    /// bottom.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `format` is not one of `Rgb`, `RgbaSeparate` or
+    /// `RgbaPremul`, or if the length of `pixels` does not match the size.
+    pub fn from_raw(
+        pixels: impl Into<Arc<[u8]>>,
+        format: ImageFormat,
+        width: usize,
+        height: usize,
+    ) -> ImageData {
+        let bytes_per_pixel = match format {
+            ImageFormat::Rgb => 3,
+            ImageFormat::RgbaSeparate | ImageFormat::RgbaPremul => 4,
+            _ => panic!("unsupported image format {:?}", format),
+        };
+        let pixels = pixels.into();
+        assert_eq!(
+            pixels.len(),
+            width * height * bytes_per_pixel,
+            "image data does not match a {}x{} image",
+            width,
+            height
+        );
+        ImageData {
+            pixels,
+            width,
+            height,
+            format,
+        }
+    }
+
+    /// An image with no pixels.
+    pub fn empty() -> ImageData {
+        ImageData::from_raw(Vec::new(), ImageFormat::RgbaSeparate, 0, 0)
+    }
+
+    /// The size of the image, in pixels.
+    pub fn size(&self) -> Size {
+        Size::new(self.width as f64, self.height as f64)
+    }
+}
+
+/// A widget that displays a bitmap image.
+///
+/// Given room, the image is shown at its own size; when it must be smaller,
+/// it keeps its aspect ratio as far as the constraints allow. How the image
+/// fits into the size it ends up with is set by its [`FillStrat`].
+///
+/// [`FillStrat`]: enum.FillStrat.html
+pub struct Image<T> {
+    image_data: ImageData,
+    fill: FillStrat,
+    interpolation: InterpolationMode,
+    phantom: PhantomData<T>,
+}
+
+impl<T: Data> Image<T> {
+    /// Create an image-drawing widget from `ImageData`.
+    ///
+    /// The image is fit with [`FillStrat::Contain`], and interpolated
+    /// bilinearly.
+    ///
+    /// [`FillStrat::Contain`]: enum.FillStrat.html#variant.Contain
+    pub fn new(image_data: ImageData) -> Self {
+        Image {
+            image_data,
+            fill: FillStrat::Contain,
+            interpolation: InterpolationMode::Bilinear,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Builder-style method to set how the image fits its space.
+    pub fn fill_mode(mut self, fill: FillStrat) -> Self {
+        self.fill = fill;
+        self
+    }
+
+    /// Builder-style method to set how the image is interpolated when it is
+    /// scaled.
+    pub fn interpolation_mode(mut self, interpolation: InterpolationMode) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
+    /// Set how the image fits its space.
+    pub fn set_fill_mode(&mut self, fill: FillStrat) {
+        self.fill = fill;
+    }
+
+    /// Set the image to display.
+    ///
+    /// The caller is responsible for requesting a layout and a paint.
+    pub fn set_image_data(&mut self, image_data: ImageData) {
+        self.image_data = image_data;
+    }
+}
+
+impl<T: Data> Widget<T> for Image<T> {
+    fn event(&mut self, _ctx: &mut EventCtx, _event: &Event, _data: &mut T, _env: &Env) {}
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _data: &T, _env: &Env) {}
+
+    fn update(&mut self, _ctx: &mut UpdateCtx, _old_data: &T, _data: &T, _env: &Env) {}
+
+    fn layout(
+        &mut self,
+        _layout_ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        _data: &T,
+        _env: &Env,
+    ) -> Size {
+        bc.debug_check("Image");
+        let size = self.image_data.size();
+        // shrink to fit the maximum size, keeping the aspect ratio.
+        let fitted = FillStrat::ScaleDown.fit(size, bc.max()).size();
+        bc.constrain(fitted)
+    }
+
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, _data: &T, _env: &Env) {
+        let data = &self.image_data;
+        if data.width == 0 || data.height == 0 {
+            return;
+        }
+        let image = match paint_ctx.make_image(data.width, data.height, &data.pixels, data.format) {
+            Ok(image) => image,
+            Err(e) => {
+                error!("failed to create image: {}", e);
+                return;
+            }
+        };
+        let rect = self.fill.fit(data.size(), paint_ctx.size());
+        let clip_rect = Rect::from_origin_size(Point::ORIGIN, paint_ctx.size());
+
+        if let Err(e) = paint_ctx.save() {
+            error!("saving render context failed: {:?}", e);
+            return;
+        }
+        paint_ctx.clip(clip_rect);
+        paint_ctx.draw_image(&image, rect, self.interpolation);
+        if let Err(e) = paint_ctx.restore() {
+            error!("restoring render context failed: {:?}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{theme, Color, Snapshot};
+
+    fn coords(rect: Rect) -> (f64, f64, f64, f64) {
+        (rect.x0, rect.y0, rect.x1, rect.y1)
+    }
+
+    #[test]
+    fn fill_strats() {
+        let image = Size::new(20., 10.);
+        let space = Size::new(40., 40.);
+
+        assert_eq!(
+            coords(FillStrat::Contain.fit(image, space)),
+            (0., 10., 40., 30.)
+        );
+        assert_eq!(
+            coords(FillStrat::Cover.fit(image, space)),
+            (-20., 0., 60., 40.)
+        );
+        assert_eq!(
+            coords(FillStrat::Fill.fit(image, space)),
+            (0., 0., 40., 40.)
+        );
+        assert_eq!(
+            coords(FillStrat::ScaleDown.fit(image, space)),
+            (10., 15., 30., 25.)
+        );
+        assert_eq!(
+            coords(FillStrat::ScaleDown.fit(image, Size::new(10., 10.))),
+            (0., 2.5, 10., 7.5)
+        );
+        assert_eq!(
+            coords(FillStrat::None.fit(image, Size::new(10., 10.))),
+            (-5., 0., 15., 10.)
+        );
+    }
+
+    #[test]
+    fn image_is_stretched_to_fill() {
+        // one red pixel, and one blue.
+        let pixels = vec![0xff, 0, 0, 0, 0, 0xff];
+        let image_data = ImageData::from_raw(pixels, ImageFormat::Rgb, 2, 1);
+        let image = Image::new(image_data)
+            .fill_mode(FillStrat::Fill)
+            .interpolation_mode(InterpolationMode::NearestNeighbor);
+        let env = theme::init().adding(theme::WINDOW_BACKGROUND_COLOR, Color::BLACK);
+
+        let snapshot = Snapshot::render(image, (), &env, Size::new(10., 10.), 1.).unwrap();
+        assert_eq!(snapshot.pixel(0, 9), [0xff, 0, 0, 0xff]);
+        assert_eq!(snapshot.pixel(9, 0), [0, 0, 0xff, 0xff]);
+    }
+}
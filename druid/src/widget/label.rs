@@ -22,7 +22,7 @@ use crate::piet::{
 use crate::theme;
 use crate::{
     BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
-    LocalizedString, PaintCtx, UpdateCtx, Widget,
+    LocalizedString, PaintCtx, Role, UpdateCtx, Widget,
 };
 
 /// The text for the label
@@ -111,7 +111,13 @@ impl<T: Data> LabelText<T> {
 impl<T: Data> Widget<T> for Label<T> {
     fn event(&mut self, _ctx: &mut EventCtx, _event: &Event, _data: &mut T, _env: &Env) {}
 
-    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _data: &T, _env: &Env) {}
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let LifeCycle::CollectAccessibility(access) = event {
+            access.set_role(Role::Label);
+            self.text
+                .with_display_text(data, env, |text| access.set_name(text));
+        }
+    }
 
     fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
         if self.text.resolve(data, env) {
@@ -147,6 +153,11 @@ impl<T: Data> Widget<T> for Label<T> {
             ),
         ));
 
+        // the alignment is mirrored in a right-to-left layout.
+        if env.is_right_to_left() {
+            origin.x = (paint_ctx.size().width - text_layout.width()).max(0.0) - origin.x;
+        }
+
         //Make sure we don't draw the text too low
         origin.y = origin.y.min(paint_ctx.size().height);
 
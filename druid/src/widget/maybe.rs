@@ -0,0 +1,163 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A widget for optional data.
+
+use crate::kurbo::{Point, Rect, Size};
+use crate::widget::SizedBox;
+use crate::{
+    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    UpdateCtx, Widget, WidgetPod,
+};
+
+/// A widget that switches between two children, depending on whether its
+/// data is `Some` or `None`.
+///
+/// The `some` child is a `Widget<T>`, and is passed the value inside the
+/// `Option`; the `none` child is a `Widget<()>`. Each time the data changes
+/// from `None` to `Some`, or back, the child for the new state is built
+/// again from its closure, and receives [`LifeCycle::WidgetAdded`] before
+/// it sees any other event.
+///
+/// [`LifeCycle::WidgetAdded`]: ../enum.LifeCycle.html#variant.WidgetAdded
+pub struct Maybe<T: Data> {
+    some_maker: Box<dyn Fn() -> Box<dyn Widget<T>>>,
+    none_maker: Box<dyn Fn() -> Box<dyn Widget<()>>>,
+    widget: Option<MaybeWidget<T>>,
+}
+
+/// The child of a `Maybe`.
+enum MaybeWidget<T: Data> {
+    Some(WidgetPod<T, Box<dyn Widget<T>>>),
+    None(WidgetPod<(), Box<dyn Widget<()>>>),
+}
+
+impl<T: Data> Maybe<T> {
+    /// Create a new `Maybe` widget, with a closure that builds the child
+    /// shown when the data is `Some`, and one that builds the child shown
+    /// when it is `None`.
+    pub fn new<W1, W2>(
+        some_maker: impl Fn() -> W1 + 'static,
+        none_maker: impl Fn() -> W2 + 'static,
+    ) -> Maybe<T>
+    where
+        W1: Widget<T> + 'static,
+        W2: Widget<()> + 'static,
+    {
+        Maybe {
+            some_maker: Box::new(move || Box::new(some_maker())),
+            none_maker: Box::new(move || Box::new(none_maker())),
+            widget: None,
+        }
+    }
+
+    /// Create a new `Maybe` widget that shows nothing when the data is `None`.
+    pub fn or_empty<W: Widget<T> + 'static>(some_maker: impl Fn() -> W + 'static) -> Maybe<T> {
+        Maybe::new(some_maker, SizedBox::empty)
+    }
+
+    /// Build the child for `data`, if the current one is for the other state.
+    ///
+    /// Returns `true` if a new child was built.
+    fn rebuild_if_needed(&mut self, data: &Option<T>) -> bool {
+        let is_some = match self.widget {
+            Some(MaybeWidget::Some(_)) => Some(true),
+            Some(MaybeWidget::None(_)) => Some(false),
+            None => None,
+        };
+        if is_some == Some(data.is_some()) {
+            return false;
+        }
+        self.widget = Some(if data.is_some() {
+            MaybeWidget::Some(WidgetPod::new((self.some_maker)()))
+        } else {
+            MaybeWidget::None(WidgetPod::new((self.none_maker)()))
+        });
+        true
+    }
+}
+
+impl<T: Data> Widget<Option<T>> for Maybe<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut Option<T>, env: &Env) {
+        match (self.widget.as_mut(), data.as_mut()) {
+            (Some(MaybeWidget::Some(child)), Some(data)) => child.event(ctx, event, data, env),
+            (Some(MaybeWidget::None(child)), None) => child.event(ctx, event, &mut (), env),
+            _ => (),
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &Option<T>,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            if self.widget.is_none() {
+                self.rebuild_if_needed(data);
+            }
+        }
+        match (self.widget.as_mut(), data.as_ref()) {
+            (Some(MaybeWidget::Some(child)), Some(data)) => child.lifecycle(ctx, event, data, env),
+            (Some(MaybeWidget::None(child)), None) => child.lifecycle(ctx, event, &(), env),
+            _ => (),
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &Option<T>, data: &Option<T>, env: &Env) {
+        if self.rebuild_if_needed(data) {
+            ctx.children_changed();
+            ctx.invalidate();
+            return;
+        }
+        match (self.widget.as_mut(), data.as_ref()) {
+            (Some(MaybeWidget::Some(child)), Some(data)) => child.update(ctx, data, env),
+            (Some(MaybeWidget::None(child)), None) => child.update(ctx, &(), env),
+            _ => (),
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &Option<T>,
+        env: &Env,
+    ) -> Size {
+        match (self.widget.as_mut(), data.as_ref()) {
+            (Some(MaybeWidget::Some(child)), Some(data)) => {
+                let size = child.layout(ctx, bc, data, env);
+                child.set_layout_rect(Rect::from_origin_size(Point::ORIGIN, size));
+                ctx.set_paint_insets(child.paint_insets());
+                size
+            }
+            (Some(MaybeWidget::None(child)), None) => {
+                let size = child.layout(ctx, bc, &(), env);
+                child.set_layout_rect(Rect::from_origin_size(Point::ORIGIN, size));
+                ctx.set_paint_insets(child.paint_insets());
+                size
+            }
+            _ => bc.min(),
+        }
+    }
+
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &Option<T>, env: &Env) {
+        match (self.widget.as_mut(), data.as_ref()) {
+            (Some(MaybeWidget::Some(child)), Some(data)) => child.paint(paint_ctx, data, env),
+            (Some(MaybeWidget::None(child)), None) => child.paint(paint_ctx, &(), env),
+            _ => (),
+        }
+    }
+}
@@ -18,52 +18,80 @@ mod align;
 mod button;
 mod checkbox;
 mod container;
+mod controller;
+mod dropdown;
 mod either;
 mod env_scope;
 mod flex;
 mod identity_wrapper;
+mod image;
 mod label;
 mod list;
+mod maybe;
 mod padding;
+mod painter;
 mod parse;
 mod progress_bar;
 mod radio;
+mod reorder_list;
 mod scroll;
 mod sized_box;
 mod slider;
+mod spinner;
 mod split;
 mod stepper;
 #[cfg(feature = "svg")]
 #[cfg_attr(docsrs, doc(cfg(feature = "svg")))]
 mod svg;
 mod switch;
+mod tabs;
+mod textarea;
 mod textbox;
+mod tooltip;
+mod tree;
+mod validated;
+mod view_switcher;
+mod virtual_list;
 mod widget_ext;
 
 pub use align::Align;
 pub use button::Button;
 pub use checkbox::Checkbox;
 pub use container::Container;
+pub use controller::{Controller, ControllerHost};
+pub use dropdown::Dropdown;
 pub use either::Either;
 pub use env_scope::EnvScope;
 pub use flex::Flex;
 pub use identity_wrapper::IdentityWrapper;
+pub use image::{FillStrat, Image, ImageData};
 pub use label::{Label, LabelText};
 pub use list::{List, ListIter};
+pub use maybe::Maybe;
 pub use padding::Padding;
+pub use painter::{BackgroundBrush, Painter};
 pub use parse::Parse;
 pub use progress_bar::ProgressBar;
 pub use radio::{Radio, RadioGroup};
-pub use scroll::Scroll;
+pub use reorder_list::ReorderList;
+pub use scroll::{Scroll, ScrollbarVisibility};
 pub use sized_box::SizedBox;
 pub use slider::Slider;
+pub use spinner::Spinner;
 pub use split::Split;
 pub use stepper::Stepper;
 #[cfg(feature = "svg")]
 #[cfg_attr(docsrs, doc(cfg(feature = "svg")))]
 pub use svg::{Svg, SvgData};
 pub use switch::Switch;
+pub use tabs::Tabs;
+pub use textarea::TextArea;
 pub use textbox::TextBox;
+pub use tooltip::Tooltip;
+pub use tree::{Tree, TreeNode};
+pub use validated::ValidatedTextBox;
+pub use view_switcher::ViewSwitcher;
+pub use virtual_list::VirtualList;
 pub use widget_ext::WidgetExt;
 
 use std::num::NonZeroU64;
@@ -104,7 +132,7 @@ use crate::{
 /// [`WidgetExt::with_id`]: ../trait.WidgetExt.html#tymethod.with_id
 /// [`IdentityWrapper`]: struct.IdentityWrapper.html
 // this is NonZeroU64 because we regularly store Option<WidgetId>
-#[derive(Clone, Copy, Debug, Hash, PartialEq)]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
 pub struct WidgetId(NonZeroU64);
 
 /// The trait implemented by all widgets.
@@ -228,6 +256,13 @@ pub trait Widget<T> {
     fn id(&self) -> Option<WidgetId> {
         None
     }
+
+    #[doc(hidden)]
+    /// The name of the widget's type, for debugging tools such as the
+    /// widget inspector. Widgets should not implement this on their own.
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
 }
 
 impl WidgetId {
@@ -289,4 +324,8 @@ impl<T> Widget<T> for Box<dyn Widget<T>> {
     fn id(&self) -> Option<WidgetId> {
         self.deref().id()
     }
+
+    fn type_name(&self) -> &'static str {
+        self.deref().type_name()
+    }
 }
@@ -99,7 +99,13 @@ impl<T: Data> Widget<T> for Padding<T> {
 
         let child_bc = bc.shrink((hpad, vpad));
         let size = self.child.layout(layout_ctx, &child_bc, data, env);
-        let origin = Point::new(self.left, self.top);
+        // the leading inset is on the right, in a right-to-left layout.
+        let leading = if env.is_right_to_left() {
+            self.right
+        } else {
+            self.left
+        };
+        let origin = Point::new(leading, self.top);
         self.child
             .set_layout_rect(Rect::from_origin_size(origin, size));
 
@@ -0,0 +1,167 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A widget that paints with a closure, and the backgrounds that can use it.
+
+use crate::kurbo::Size;
+use crate::piet::{LinearGradient, RadialGradient};
+use crate::{
+    BoxConstraints, Color, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintBrush, PaintCtx, UpdateCtx, Widget,
+};
+
+type PaintFn<T> = Box<dyn FnMut(&mut PaintCtx, &T, &Env)>;
+
+/// A widget that only paints, with a closure.
+///
+/// The closure is called with the [`PaintCtx`], the data and the `Env` each
+/// time the widget is painted. A `Painter` has no child, and ignores events;
+/// it takes up all the space it is given, like a background layer. It is
+/// painted again whenever its data changes.
+///
+/// A `Painter` can be used on its own, or as the background of a
+/// [`Container`], through [`WidgetExt::background`].
+///
+/// # Examples
+///
+/// ```
+/// # use druid::widget::{Label, Painter, WidgetExt};
+/// # use druid::{Color, Point, Rect, RenderContext};
+/// let stripe = Painter::new(|paint_ctx, _data: &u32, _env| {
+///     let rect = Rect::from_origin_size(Point::ORIGIN, (4., paint_ctx.size().height));
+///     paint_ctx.fill(rect, &Color::WHITE);
+/// });
+/// let label = Label::new("striped").background(stripe);
+/// ```
+///
+/// [`PaintCtx`]: ../struct.PaintCtx.html
+/// [`Container`]: struct.Container.html
+/// [`WidgetExt::background`]: trait.WidgetExt.html#method.background
+pub struct Painter<T>(PaintFn<T>);
+
+/// Something that can fill the background of a [`Container`]: a color, a
+/// gradient, or a [`Painter`].
+///
+/// [`Container`]: struct.Container.html
+/// [`Painter`]: struct.Painter.html
+pub enum BackgroundBrush<T> {
+    /// A color or a gradient.
+    Brush(PaintBrush),
+    /// A closure that paints the background.
+    Painter(Painter<T>),
+}
+
+impl<T> Painter<T> {
+    /// Create a new `Painter` with the provided paint closure.
+    pub fn new(f: impl FnMut(&mut PaintCtx, &T, &Env) + 'static) -> Self {
+        Painter(Box::new(f))
+    }
+}
+
+impl<T: Data> Widget<T> for Painter<T> {
+    fn event(&mut self, _ctx: &mut EventCtx, _event: &Event, _data: &mut T, _env: &Env) {}
+
+    fn lifecycle(&mut self, _ctx: &mut LifeCycleCtx, _event: &LifeCycle, _data: &T, _env: &Env) {}
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, _env: &Env) {
+        if !old_data.same(data) {
+            ctx.invalidate();
+        }
+    }
+
+    fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &T, _env: &Env) -> Size {
+        bc.debug_check("Painter");
+        let width = if bc.is_width_bounded() {
+            bc.max().width
+        } else {
+            bc.min().width
+        };
+        let height = if bc.is_height_bounded() {
+            bc.max().height
+        } else {
+            bc.min().height
+        };
+        Size::new(width, height)
+    }
+
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &T, env: &Env) {
+        (self.0)(paint_ctx, data, env)
+    }
+}
+
+impl<T> From<Painter<T>> for BackgroundBrush<T> {
+    fn from(src: Painter<T>) -> BackgroundBrush<T> {
+        BackgroundBrush::Painter(src)
+    }
+}
+
+impl<T> From<PaintBrush> for BackgroundBrush<T> {
+    fn from(src: PaintBrush) -> BackgroundBrush<T> {
+        BackgroundBrush::Brush(src)
+    }
+}
+
+impl<T> From<Color> for BackgroundBrush<T> {
+    fn from(src: Color) -> BackgroundBrush<T> {
+        BackgroundBrush::Brush(src.into())
+    }
+}
+
+impl<T> From<LinearGradient> for BackgroundBrush<T> {
+    fn from(src: LinearGradient) -> BackgroundBrush<T> {
+        BackgroundBrush::Brush(src.into())
+    }
+}
+
+impl<T> From<RadialGradient> for BackgroundBrush<T> {
+    fn from(src: RadialGradient) -> BackgroundBrush<T> {
+        BackgroundBrush::Brush(src.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kurbo::{Point, Rect};
+    use crate::widget::{SizedBox, WidgetExt};
+    use crate::{theme, RenderContext, Snapshot};
+
+    #[test]
+    fn painter_fills_its_space() {
+        let red = Color::rgb8(0xff, 0, 0);
+        let painter = Painter::new(move |paint_ctx, _: &(), _| {
+            let rect = Rect::from_origin_size(Point::ORIGIN, paint_ctx.size());
+            paint_ctx.fill(rect, &red);
+        });
+        let env = theme::init().adding(theme::WINDOW_BACKGROUND_COLOR, Color::BLACK);
+
+        let snapshot = Snapshot::render(painter, (), &env, Size::new(10., 10.), 1.).unwrap();
+        assert_eq!(snapshot.pixel(0, 0), [0xff, 0, 0, 0xff]);
+        assert_eq!(snapshot.pixel(9, 9), [0xff, 0, 0, 0xff]);
+    }
+
+    #[test]
+    fn painter_as_background() {
+        let painter = Painter::new(|paint_ctx, data: &Color, _| {
+            let rect = Rect::from_origin_size(Point::ORIGIN, paint_ctx.size());
+            paint_ctx.fill(rect, data);
+        });
+        let widget = SizedBox::empty().expand().background(painter);
+        let env = theme::init().adding(theme::WINDOW_BACKGROUND_COLOR, Color::BLACK);
+        let blue = Color::rgb8(0, 0, 0xff);
+
+        let snapshot = Snapshot::render(widget, blue, &env, Size::new(10., 10.), 1.).unwrap();
+        assert_eq!(snapshot.pixel(5, 5), [0, 0, 0xff, 0xff]);
+    }
+}
@@ -23,6 +23,11 @@ use crate::{
 };
 
 /// A progress bar, displaying a numeric progress value.
+///
+/// The value is between 0.0 and 1.0; values outside of that range are clamped.
+/// For work of unknown length, use a [`Spinner`] instead.
+///
+/// [`Spinner`]: struct.Spinner.html
 #[derive(Debug, Clone, Default)]
 pub struct ProgressBar {}
 
@@ -14,20 +14,23 @@
 
 //! A radio button widget.
 
-use std::marker::PhantomData;
-
 use crate::kurbo::{Circle, Point, Rect, Size};
 use crate::theme;
 use crate::widget::{Align, Flex, Label, LabelText, Padding};
 use crate::{
-    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, LinearGradient,
-    PaintCtx, RenderContext, UnitPoint, UpdateCtx, Widget, WidgetPod,
+    BoxConstraints, Data, Env, Event, EventCtx, KeyCode, KeyModifiers, LayoutCtx, LifeCycle,
+    LifeCycleCtx, LinearGradient, PaintCtx, RenderContext, Role, UnitPoint, UpdateCtx, Widget,
+    WidgetPod,
 };
 
-/// A group of radio buttons
-#[derive(Debug, Clone)]
+/// A group of radio buttons, for picking one of a set of values.
+///
+/// Clicking a button sets the data to its value. The group can take focus;
+/// while it has focus, the up and down (or left and right) arrow keys select
+/// the previous and next value.
 pub struct RadioGroup<T: Data + PartialEq> {
-    phantom: PhantomData<T>,
+    variants: Vec<T>,
+    column: Flex<T>,
 }
 
 impl<T: Data + PartialEq> RadioGroup<T> {
@@ -35,12 +38,83 @@ impl<T: Data + PartialEq> RadioGroup<T> {
     pub fn new(
         variants: impl IntoIterator<Item = (impl Into<LabelText<T>> + 'static, T)>,
     ) -> impl Widget<T> {
-        let mut col = Flex::column();
+        let mut column = Flex::column();
+        let mut values = Vec::new();
         for (label, variant) in variants.into_iter() {
-            let radio = Radio::new(label, variant);
-            col.add_child(Padding::new(5.0, radio), 0.0);
+            let radio = Radio::new(label, variant.clone());
+            column.add_child(Padding::new(5.0, radio));
+            values.push(variant);
+        }
+        RadioGroup {
+            variants: values,
+            column,
+        }
+    }
+
+    /// Select the value `delta` places away from the selected one, if there
+    /// is such a value.
+    ///
+    /// If no value is selected, moving forward selects the first one.
+    fn select_relative(&self, data: &mut T, delta: isize) -> bool {
+        let target = match self.variants.iter().position(|v| v == data) {
+            Some(idx) => idx as isize + delta,
+            None if delta > 0 => 0,
+            None => return false,
+        };
+        if target < 0 || target as usize >= self.variants.len() {
+            return false;
+        }
+        *data = self.variants[target as usize].clone();
+        true
+    }
+}
+
+impl<T: Data + PartialEq> Widget<T> for RadioGroup<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::MouseDown(_) => ctx.request_focus(),
+            Event::KeyDown(key) if ctx.has_focus() => {
+                let delta = match key.key_code {
+                    KeyCode::ArrowUp | KeyCode::ArrowLeft => -1,
+                    KeyCode::ArrowDown | KeyCode::ArrowRight => 1,
+                    _ => 0,
+                };
+                if delta != 0 && key.mods == KeyModifiers::default() {
+                    if self.select_relative(data, delta) {
+                        ctx.invalidate();
+                    }
+                    ctx.set_handled();
+                    return;
+                }
+            }
+            _ => (),
+        }
+        self.column.event(ctx, event, data, env)
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        match event {
+            LifeCycle::WidgetAdded => ctx.register_for_focus(),
+            LifeCycle::FocusChanged(_) => ctx.invalidate(),
+            _ => (),
+        }
+        self.column.lifecycle(ctx, event, data, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        self.column.update(ctx, old_data, data, env)
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        self.column.layout(ctx, bc, data, env)
+    }
+
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.column.paint(paint_ctx, data, env);
+        if paint_ctx.has_focus() {
+            let rect = Rect::from_origin_size(Point::ORIGIN, paint_ctx.size()).inset(-0.5);
+            paint_ctx.stroke(rect, &env.get(theme::PRIMARY_LIGHT), 1.);
         }
-        col
     }
 }
 
@@ -81,9 +155,14 @@ impl<T: Data + PartialEq> Widget<T> for Radio<T> {
         }
     }
 
-    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &T, _env: &Env) {
-        if let LifeCycle::HotChanged(_) = event {
-            ctx.invalidate();
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, _env: &Env) {
+        match event {
+            LifeCycle::HotChanged(_) => ctx.invalidate(),
+            LifeCycle::CollectAccessibility(access) => {
+                access.set_role(Role::RadioButton);
+                access.set_checked(*data == self.variant);
+            }
+            _ => (),
         }
     }
 
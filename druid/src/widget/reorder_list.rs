@@ -0,0 +1,394 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A list whose rows can be reordered by dragging.
+
+use std::sync::Arc;
+
+use crate::kurbo::{Point, Rect, Size};
+use crate::piet::RenderContext;
+use crate::theme;
+use crate::widget::ListIter;
+use crate::{
+    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, MouseButton,
+    PaintCtx, UpdateCtx, Widget, WidgetPod,
+};
+
+/// The time, in nanoseconds, the rows take to slide out of the way of the
+/// dragged row.
+const GAP_DURATION: f64 = 150_000_000.;
+
+/// A row being dragged.
+struct Drag {
+    /// The index of the row.
+    from: usize,
+    /// The vertical distance from the top of the row to the pointer.
+    grab_offset: f64,
+    /// The vertical position of the pointer.
+    pointer_y: f64,
+}
+
+/// A list like [`List`], whose rows the user can reorder by dragging.
+///
+/// A row is picked up by a mouse down that the row itself does not handle;
+/// it follows the pointer, while the other rows slide apart to show where it
+/// will land. On release, the item is moved within the `Vec`, and its widget
+/// moves with it.
+///
+/// [`List`]: struct.List.html
+pub struct ReorderList<T: Data> {
+    closure: Box<dyn Fn() -> Box<dyn Widget<T>>>,
+    children: Vec<WidgetPod<T, Box<dyn Widget<T>>>>,
+    /// The row heights, as of the last layout.
+    heights: Vec<f64>,
+    drag: Option<Drag>,
+    /// How far each row is currently pushed down to make room for the
+    /// dragged row, and how far it should be.
+    shifts: Vec<f64>,
+    target_shifts: Vec<f64>,
+}
+
+impl<T: Data> ReorderList<T> {
+    /// Create a new reorderable list. The closure is called every time a new
+    /// child needs to be constructed.
+    pub fn new<W: Widget<T> + 'static>(closure: impl Fn() -> W + 'static) -> Self {
+        ReorderList {
+            closure: Box::new(move || Box::new(closure())),
+            children: Vec::new(),
+            heights: Vec::new(),
+            drag: None,
+            shifts: Vec::new(),
+            target_shifts: Vec::new(),
+        }
+    }
+
+    /// Create or remove children to match the data.
+    ///
+    /// Returns `true` if children were added or removed.
+    fn update_child_count(&mut self, data: &Arc<Vec<T>>) -> bool {
+        let len = self.children.len();
+        if len == data.len() {
+            return false;
+        }
+        self.children.truncate(data.len());
+        while self.children.len() < data.len() {
+            self.children.push(WidgetPod::new((self.closure)()));
+        }
+        // a drag can't survive the rows changing under it.
+        self.drag = None;
+        self.shifts = vec![0.; data.len()];
+        self.target_shifts = vec![0.; data.len()];
+        true
+    }
+
+    fn row_at(&self, y: f64) -> Option<usize> {
+        self.children.iter().position(|child| {
+            let rect = child.layout_rect();
+            y >= rect.y0 && y < rect.y1
+        })
+    }
+
+    /// The index the dragged row would have if it were dropped now.
+    ///
+    /// This is an index into the list without the dragged row, which is
+    /// where it is inserted after being removed.
+    fn drop_index(&self, drag: &Drag) -> usize {
+        let height = self.heights[drag.from];
+        let center = drag.pointer_y - drag.grab_offset + height / 2.;
+        let mut y = 0.;
+        let mut index = 0;
+        for (i, row_height) in self.heights.iter().enumerate() {
+            if i == drag.from {
+                continue;
+            }
+            if y + row_height / 2. >= center {
+                break;
+            }
+            y += row_height;
+            index += 1;
+        }
+        index
+    }
+
+    /// Push the rows at or after the drop index down by the dragged row's
+    /// height, and start sliding them there.
+    fn update_gap(&mut self, ctx: &mut EventCtx) {
+        let drag = match &self.drag {
+            Some(drag) => drag,
+            None => return,
+        };
+        let to = self.drop_index(drag);
+        let gap = self.heights[drag.from];
+        let others = (0..self.children.len()).filter(|i| *i != drag.from);
+        for (index, i) in others.enumerate() {
+            self.target_shifts[i] = if index >= to { gap } else { 0. };
+        }
+        if self.shifts != self.target_shifts {
+            ctx.request_anim_frame();
+        }
+        ctx.invalidate();
+    }
+
+    fn drop_row(&mut self, data: &mut Arc<Vec<T>>) {
+        let drag = match self.drag.take() {
+            Some(drag) => drag,
+            None => return,
+        };
+        let to = self.drop_index(&drag);
+        if to != drag.from {
+            let items = Arc::make_mut(data);
+            let item = items.remove(drag.from);
+            items.insert(to, item);
+            let child = self.children.remove(drag.from);
+            self.children.insert(to, child);
+        }
+        self.shifts = vec![0.; self.children.len()];
+        self.target_shifts = vec![0.; self.children.len()];
+    }
+}
+
+impl<T: Data> Widget<Arc<Vec<T>>> for ReorderList<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut Arc<Vec<T>>, env: &Env) {
+        if self.drag.is_none() {
+            let mut children = self.children.iter_mut();
+            data.for_each_mut(|child_data, _| {
+                if let Some(child) = children.next() {
+                    child.event(ctx, event, child_data, env);
+                }
+            });
+            if ctx.is_handled() {
+                return;
+            }
+        }
+
+        match event {
+            Event::MouseDown(mouse) if mouse.button == MouseButton::Left => {
+                if let Some(from) = self.row_at(mouse.pos.y) {
+                    let rect = self.children[from].layout_rect();
+                    self.drag = Some(Drag {
+                        from,
+                        grab_offset: mouse.pos.y - rect.y0,
+                        pointer_y: mouse.pos.y,
+                    });
+                    // rows below the dragged row start out pushed down by it.
+                    let gap = self.heights[from];
+                    for i in 0..self.children.len() {
+                        let shift = if i > from { gap } else { 0. };
+                        self.shifts[i] = shift;
+                        self.target_shifts[i] = shift;
+                    }
+                    ctx.set_active(true);
+                    ctx.set_handled();
+                    ctx.invalidate();
+                }
+            }
+            Event::MouseMoved(mouse) if ctx.is_active() => {
+                if let Some(drag) = self.drag.as_mut() {
+                    drag.pointer_y = mouse.pos.y;
+                    self.update_gap(ctx);
+                }
+            }
+            Event::MouseUp(_) if ctx.is_active() => {
+                self.drop_row(data);
+                ctx.set_active(false);
+                ctx.invalidate();
+            }
+            // events other than the mouse still reach the rows during a drag.
+            _ if self.drag.is_some() => {
+                let mut children = self.children.iter_mut();
+                data.for_each_mut(|child_data, _| {
+                    if let Some(child) = children.next() {
+                        child.event(ctx, event, child_data, env);
+                    }
+                });
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &Arc<Vec<T>>,
+        env: &Env,
+    ) {
+        if let LifeCycle::WidgetAdded = event {
+            if self.update_child_count(data) {
+                ctx.children_changed();
+            }
+        }
+        if let LifeCycle::AnimFrame(interval) = event {
+            let gap = match &self.drag {
+                Some(drag) => self.heights[drag.from],
+                None => 0.,
+            };
+            let step = gap * *interval as f64 / GAP_DURATION;
+            for (shift, target) in self.shifts.iter_mut().zip(&self.target_shifts) {
+                *shift = if *shift < *target {
+                    (*shift + step).min(*target)
+                } else {
+                    (*shift - step).max(*target)
+                };
+            }
+            if self.shifts != self.target_shifts {
+                ctx.request_anim_frame();
+            }
+            ctx.invalidate();
+        }
+
+        let mut children = self.children.iter_mut();
+        data.for_each(|child_data, _| {
+            if let Some(child) = children.next() {
+                child.lifecycle(ctx, event, child_data, env);
+            }
+        });
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        _old_data: &Arc<Vec<T>>,
+        data: &Arc<Vec<T>>,
+        env: &Env,
+    ) {
+        let mut children = self.children.iter_mut();
+        data.for_each(|child_data, _| {
+            if let Some(child) = children.next() {
+                child.update(ctx, child_data, env);
+            }
+        });
+
+        if self.update_child_count(data) {
+            ctx.children_changed();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        layout_ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &Arc<Vec<T>>,
+        env: &Env,
+    ) -> Size {
+        let child_bc = BoxConstraints::new(
+            Size::new(bc.min().width, 0.0),
+            Size::new(bc.max().width, f64::INFINITY),
+        );
+        let mut width = bc.min().width;
+        let mut sizes = Vec::with_capacity(self.children.len());
+        let mut children = self.children.iter_mut();
+        data.for_each(|child_data, _| {
+            if let Some(child) = children.next() {
+                let size = child.layout(layout_ctx, &child_bc, child_data, env);
+                width = width.max(size.width);
+                sizes.push(size);
+            }
+        });
+        self.heights = sizes.iter().map(|size| size.height).collect();
+        let height: f64 = self.heights.iter().sum();
+
+        // while dragging, the other rows are laid out as if the dragged row
+        // was gone, and then pushed down to open a gap where it would land.
+        let dragged = self.drag.as_ref().map(|drag| drag.from);
+        let mut y = 0.;
+        for (i, (child, size)) in self.children.iter_mut().zip(&sizes).enumerate() {
+            if Some(i) == dragged {
+                continue;
+            }
+            let shift = if dragged.is_some() {
+                self.shifts[i]
+            } else {
+                0.
+            };
+            let origin = Point::new(0., y + shift);
+            child.set_layout_rect(Rect::from_origin_size(origin, *size));
+            y += size.height;
+        }
+        if let Some(drag) = &self.drag {
+            let size = sizes[drag.from];
+            let top = (drag.pointer_y - drag.grab_offset)
+                .min(height - size.height)
+                .max(0.);
+            let rect = Rect::from_origin_size(Point::new(0., top), size);
+            self.children[drag.from].set_layout_rect(rect);
+        }
+
+        bc.constrain(Size::new(width, height))
+    }
+
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &Arc<Vec<T>>, env: &Env) {
+        let dragged = self.drag.as_ref().map(|drag| drag.from);
+        let mut children = self.children.iter_mut();
+        data.for_each(|child_data, i| {
+            if let Some(child) = children.next() {
+                if Some(i) != dragged {
+                    child.paint_with_offset(paint_ctx, child_data, env);
+                }
+            }
+        });
+
+        // the dragged row is painted last, over a background that hides
+        // the rows it passes over.
+        if let Some(from) = dragged {
+            let child = &mut self.children[from];
+            paint_ctx.fill(child.layout_rect(), &env.get(theme::BACKGROUND_LIGHT));
+            child.paint_with_offset_always(paint_ctx, &data[from], env);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::Harness;
+    use crate::tests::helpers::left_mouse;
+    use crate::widget::SizedBox;
+    use crate::MouseEvent;
+
+    fn mouse(y: f64) -> MouseEvent {
+        left_mouse(10., y, 1)
+    }
+
+    fn drag(harness: &mut Harness<Arc<Vec<u32>>>, from: f64, to: f64) {
+        harness.event(Event::MouseDown(mouse(from)));
+        harness.just_layout();
+        harness.event(Event::MouseMoved(mouse(to)));
+        harness.just_layout();
+        harness.event(Event::MouseUp(mouse(to)));
+        harness.just_layout();
+    }
+
+    #[test]
+    fn dragging_moves_items() {
+        let widget = ReorderList::new(|| SizedBox::empty().height(20.));
+        Harness::create(Arc::new(vec![0, 1, 2]), widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+
+            // past the second row's middle, but not the third's.
+            drag(harness, 5., 22.);
+            assert_eq!(**harness.data(), vec![1, 0, 2]);
+            // to the end, past the original position.
+            drag(harness, 5., 50.);
+            assert_eq!(**harness.data(), vec![0, 2, 1]);
+            // back to the front.
+            drag(harness, 45., 4.);
+            assert_eq!(**harness.data(), vec![1, 0, 2]);
+            // a small move puts it back where it was.
+            drag(harness, 25., 22.);
+            assert_eq!(**harness.data(), vec![1, 0, 2]);
+        });
+    }
+}
@@ -21,8 +21,8 @@ use std::time::{Duration, Instant};
 use crate::kurbo::{Affine, Point, Rect, RoundedRect, Size, Vec2};
 use crate::theme;
 use crate::{
-    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
-    RenderContext, TimerToken, UpdateCtx, Widget, WidgetPod,
+    commands, BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, RenderContext, TimerToken, UpdateCtx, Widget, WidgetPod,
 };
 
 #[derive(Debug, Clone)]
@@ -46,6 +46,25 @@ impl ScrollDirection {
     }
 }
 
+/// When the scrollbars of a [`Scroll`] are shown.
+///
+/// [`Scroll`]: struct.Scroll.html
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScrollbarVisibility {
+    /// Show the scrollbars while scrolling, or while the mouse is over them,
+    /// and fade them out after [`SCROLL_BAR_FADE_DELAY`].
+    ///
+    /// [`SCROLL_BAR_FADE_DELAY`]: ../theme/constant.SCROLL_BAR_FADE_DELAY.html
+    Auto,
+    /// Always show the scrollbars when the content is larger than the viewport.
+    Always,
+    /// Never show the scrollbars. The content can still be scrolled with the
+    /// wheel, or with [`SCROLL_TO_VIEW`].
+    ///
+    /// [`SCROLL_TO_VIEW`]: ../commands/constant.SCROLL_TO_VIEW.html
+    Never,
+}
+
 enum BarHoveredState {
     None,
     Vertical,
@@ -105,11 +124,21 @@ impl ScrollBarsState {
 /// when the child's bounds are larger than the viewport.
 ///
 /// The child is laid out with completely unconstrained layout bounds.
+///
+/// A region of the child can be scrolled into view with a
+/// [`SCROLL_TO_VIEW`] command targeted at the scroll container, such as
+/// when a list item is selected with the keyboard.
+///
+/// The scrollbar thumbs can be dragged, and clicking in a scrollbar's track
+/// on either side of the thumb scrolls by a page.
+///
+/// [`SCROLL_TO_VIEW`]: ../commands/constant.SCROLL_TO_VIEW.html
 pub struct Scroll<T: Data, W: Widget<T>> {
     child: WidgetPod<T, W>,
     child_size: Size,
     scroll_offset: Vec2,
     direction: ScrollDirection,
+    visibility: ScrollbarVisibility,
     scroll_bars: ScrollBarsState,
 }
 
@@ -125,6 +154,7 @@ impl<T: Data, W: Widget<T>> Scroll<T, W> {
             child_size: Default::default(),
             scroll_offset: Vec2::new(0.0, 0.0),
             direction: ScrollDirection::All,
+            visibility: ScrollbarVisibility::Auto,
             scroll_bars: ScrollBarsState::default(),
         }
     }
@@ -143,6 +173,16 @@ impl<T: Data, W: Widget<T>> Scroll<T, W> {
         self
     }
 
+    /// Builder-style method to set when the scrollbars are shown.
+    ///
+    /// The default is [`ScrollbarVisibility::Auto`].
+    ///
+    /// [`ScrollbarVisibility::Auto`]: enum.ScrollbarVisibility.html#variant.Auto
+    pub fn scrollbar_visibility(mut self, visibility: ScrollbarVisibility) -> Self {
+        self.visibility = visibility;
+        self
+    }
+
     /// Returns a reference to the child widget.
     pub fn child(&self) -> &W {
         self.child.widget()
@@ -168,8 +208,39 @@ impl<T: Data, W: Widget<T>> Scroll<T, W> {
         }
     }
 
+    /// Scroll so that `rect`, in the child's coordinate space, is visible
+    /// in a viewport of `size`.
+    ///
+    /// On each axis, the offset changes as little as it can; if `rect` is
+    /// larger than the viewport, its top-left corner is shown.
+    ///
+    /// Returns `true` if the scroll has been updated.
+    pub fn scroll_to_view(&mut self, rect: Rect, size: Size) -> bool {
+        fn axis_offset(offset: f64, viewport: f64, start: f64, end: f64) -> f64 {
+            if end - start > viewport || start < offset {
+                start
+            } else if end > offset + viewport {
+                end - viewport
+            } else {
+                offset
+            }
+        }
+        let target = Vec2::new(
+            axis_offset(self.scroll_offset.x, size.width, rect.x0, rect.x1),
+            axis_offset(self.scroll_offset.y, size.height, rect.y0, rect.y1),
+        );
+        self.scroll(target - self.scroll_offset, size)
+    }
+
     /// Makes the scrollbars visible, and resets the fade timer.
+    ///
+    /// This does nothing unless the scrollbars are [`ScrollbarVisibility::Auto`].
+    ///
+    /// [`ScrollbarVisibility::Auto`]: enum.ScrollbarVisibility.html#variant.Auto
     pub fn reset_scrollbar_fade(&mut self, ctx: &mut EventCtx, env: &Env) {
+        if self.visibility != ScrollbarVisibility::Auto {
+            return;
+        }
         // Display scroll bars and schedule their disappearance
         self.scroll_bars.opacity = env.get(theme::SCROLL_BAR_MAX_OPACITY);
         let fade_delay = env.get(theme::SCROLL_BAR_FADE_DELAY);
@@ -218,20 +289,27 @@ impl<T: Data, W: Widget<T>> Scroll<T, W> {
         Rect::new(x0, y0, x1, y1)
     }
 
+    fn bar_opacity(&self, env: &Env) -> f64 {
+        match self.visibility {
+            ScrollbarVisibility::Auto => self.scroll_bars.opacity,
+            ScrollbarVisibility::Always => env.get(theme::SCROLL_BAR_MAX_OPACITY),
+            ScrollbarVisibility::Never => 0.0,
+        }
+    }
+
     /// Draw scroll bars.
     fn draw_bars(&self, paint_ctx: &mut PaintCtx, viewport: Rect, env: &Env) {
-        if self.scroll_bars.opacity <= 0.0 {
+        let opacity = self.bar_opacity(env);
+        if opacity <= 0.0 {
             return;
         }
 
-        let brush = paint_ctx.render_ctx.solid_brush(
-            env.get(theme::SCROLL_BAR_COLOR)
-                .with_alpha(self.scroll_bars.opacity),
-        );
-        let border_brush = paint_ctx.render_ctx.solid_brush(
-            env.get(theme::SCROLL_BAR_BORDER_COLOR)
-                .with_alpha(self.scroll_bars.opacity),
-        );
+        let brush = paint_ctx
+            .render_ctx
+            .solid_brush(env.get(theme::SCROLL_BAR_COLOR).with_alpha(opacity));
+        let border_brush = paint_ctx
+            .render_ctx
+            .solid_brush(env.get(theme::SCROLL_BAR_BORDER_COLOR).with_alpha(opacity));
 
         let radius = env.get(theme::SCROLL_BAR_RADIUS);
         let edge_width = env.get(theme::SCROLL_BAR_EDGE_WIDTH);
@@ -254,7 +332,9 @@ impl<T: Data, W: Widget<T>> Scroll<T, W> {
     }
 
     fn point_hits_vertical_bar(&self, viewport: Rect, pos: Point, env: &Env) -> bool {
-        if viewport.height() < self.child_size.height {
+        if self.visibility != ScrollbarVisibility::Never
+            && viewport.height() < self.child_size.height
+        {
             let bounds = self.calc_vertical_bar_bounds(viewport, &env);
             return pos.y > bounds.y0 && pos.y < bounds.y1 && pos.x > bounds.x0;
         }
@@ -263,13 +343,36 @@ impl<T: Data, W: Widget<T>> Scroll<T, W> {
     }
 
     fn point_hits_horizontal_bar(&self, viewport: Rect, pos: Point, env: &Env) -> bool {
-        if viewport.width() < self.child_size.width {
+        if self.visibility != ScrollbarVisibility::Never && viewport.width() < self.child_size.width
+        {
             let bounds = self.calc_horizontal_bar_bounds(viewport, &env);
             return pos.x > bounds.x0 && pos.x < bounds.x1 && pos.y > bounds.y0;
         }
 
         false
     }
+
+    /// Whether `pos` is in the track of the vertical scrollbar, on either
+    /// side of the thumb. The track only responds while the bar is visible.
+    fn point_hits_vertical_track(&self, viewport: Rect, pos: Point, env: &Env) -> bool {
+        if self.bar_opacity(env) > 0.0 && viewport.height() < self.child_size.height {
+            let bounds = self.calc_vertical_bar_bounds(viewport, env);
+            return pos.x > bounds.x0 && !self.point_hits_vertical_bar(viewport, pos, env);
+        }
+
+        false
+    }
+
+    /// Whether `pos` is in the track of the horizontal scrollbar, on either
+    /// side of the thumb. The track only responds while the bar is visible.
+    fn point_hits_horizontal_track(&self, viewport: Rect, pos: Point, env: &Env) -> bool {
+        if self.bar_opacity(env) > 0.0 && viewport.width() < self.child_size.width {
+            let bounds = self.calc_horizontal_bar_bounds(viewport, env);
+            return pos.y > bounds.y0 && !self.point_hits_horizontal_bar(viewport, pos, env);
+        }
+
+        false
+    }
 }
 
 impl<T: Data, W: Widget<T>> Widget<T> for Scroll<T, W> {
@@ -277,11 +380,24 @@ impl<T: Data, W: Widget<T>> Widget<T> for Scroll<T, W> {
         let size = ctx.size();
         let viewport = Rect::from_origin_size(Point::ORIGIN, size);
 
+        if let Event::Command(cmd) = event {
+            if let Some(rect) = cmd.get(commands::SCROLL_TO_VIEW) {
+                if self.scroll_to_view(rect.abs(), size) {
+                    ctx.invalidate();
+                    self.reset_scrollbar_fade(ctx, env);
+                }
+                ctx.set_handled();
+                return;
+            }
+        }
+
         let scroll_bar_is_hovered = match event {
             Event::MouseMoved(e) | Event::MouseUp(e) | Event::MouseDown(e) => {
                 let offset_pos = e.pos + self.scroll_offset;
                 self.point_hits_vertical_bar(viewport, offset_pos, &env)
                     || self.point_hits_horizontal_bar(viewport, offset_pos, &env)
+                    || self.point_hits_vertical_track(viewport, offset_pos, env)
+                    || self.point_hits_horizontal_track(viewport, offset_pos, env)
             }
             _ => false,
         };
@@ -317,7 +433,9 @@ impl<T: Data, W: Widget<T>> Widget<T> for Scroll<T, W> {
             match event {
                 Event::MouseMoved(event) => {
                     let offset_pos = event.pos + self.scroll_offset;
-                    if self.point_hits_vertical_bar(viewport, offset_pos, &env) {
+                    if self.point_hits_vertical_bar(viewport, offset_pos, &env)
+                        || self.point_hits_vertical_track(viewport, offset_pos, env)
+                    {
                         self.scroll_bars.hovered = BarHoveredState::Vertical;
                     } else {
                         self.scroll_bars.hovered = BarHoveredState::Horizontal;
@@ -338,7 +456,21 @@ impl<T: Data, W: Widget<T>> Widget<T> for Scroll<T, W> {
                         self.scroll_bars.held = BarHeldState::Horizontal(
                             pos.x - self.calc_horizontal_bar_bounds(viewport, &env).x0,
                         );
+                    } else if self.point_hits_vertical_track(viewport, pos, env) {
+                        // page towards the click
+                        let thumb = self.calc_vertical_bar_bounds(viewport, env);
+                        let page = viewport.height();
+                        let delta = if pos.y < thumb.y0 { -page } else { page };
+                        self.scroll(Vec2::new(0.0, delta), size);
+                        ctx.invalidate();
+                    } else if self.point_hits_horizontal_track(viewport, pos, env) {
+                        let thumb = self.calc_horizontal_bar_bounds(viewport, env);
+                        let page = viewport.width();
+                        let delta = if pos.x < thumb.x0 { -page } else { page };
+                        self.scroll(Vec2::new(delta, 0.0), size);
+                        ctx.invalidate();
                     }
+                    ctx.set_handled();
                 }
                 // if the mouse was downed elsewhere, moved over a scroll bar and released: noop.
                 Event::MouseUp(_) => (),
@@ -347,7 +479,10 @@ impl<T: Data, W: Widget<T>> Widget<T> for Scroll<T, W> {
         } else {
             let child_event = event.transform_scroll(self.scroll_offset, viewport);
             if let Some(child_event) = child_event {
-                self.child.event(ctx, &child_event, data, env)
+                let window_origin = ctx.window_origin;
+                ctx.window_origin -= self.scroll_offset;
+                self.child.event(ctx, &child_event, data, env);
+                ctx.window_origin = window_origin;
             };
 
             match event {
@@ -371,7 +506,7 @@ impl<T: Data, W: Widget<T>> Widget<T> for Scroll<T, W> {
 
         if !ctx.is_handled() {
             if let Event::Wheel(wheel) = event {
-                if self.scroll(wheel.delta, size) {
+                if self.scroll(wheel.pixel_delta(size, env), size) {
                     ctx.invalidate();
                     ctx.set_handled();
                     self.reset_scrollbar_fade(ctx, &env);
@@ -384,7 +519,9 @@ impl<T: Data, W: Widget<T>> Widget<T> for Scroll<T, W> {
         // Guard by the timer id being invalid, otherwise the scroll bars would fade
         // immediately if some other widgeet started animating.
         if let LifeCycle::AnimFrame(interval) = event {
-            if self.scroll_bars.timer_id == TimerToken::INVALID {
+            if self.visibility == ScrollbarVisibility::Auto
+                && self.scroll_bars.timer_id == TimerToken::INVALID
+            {
                 // Animate scroll bars opacity
                 let diff = 2.0 * (*interval as f64) * 1e-9;
                 self.scroll_bars.opacity -= diff;
@@ -393,11 +530,17 @@ impl<T: Data, W: Widget<T>> Widget<T> for Scroll<T, W> {
                 }
             }
         }
-        self.child.lifecycle(ctx, event, data, env)
+        let window_origin = ctx.window_origin;
+        ctx.window_origin -= self.scroll_offset;
+        self.child.lifecycle(ctx, event, data, env);
+        ctx.window_origin = window_origin;
     }
 
     fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        let window_origin = ctx.window_origin;
+        ctx.window_origin -= self.scroll_offset;
         self.child.update(ctx, data, env);
+        ctx.window_origin = window_origin;
     }
 
     fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
@@ -0,0 +1,98 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! An animated spinner widget.
+
+use std::f64::consts::PI;
+
+use crate::kurbo::{Line, Point, Size, Vec2};
+use crate::theme;
+use crate::{
+    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    RenderContext, UpdateCtx, Widget,
+};
+
+/// The number of spokes in the spinner.
+const SPOKES: usize = 12;
+/// The time for one turn of the spinner, in nanoseconds.
+const PERIOD: f64 = 1_000_000_000.;
+
+/// An animated spinner, for showing that work of unknown length is being
+/// done.
+///
+/// The spinner animates for as long as it is in the widget tree; it stops
+/// requesting animation frames when it is removed.
+#[derive(Debug, Clone, Default)]
+pub struct Spinner {
+    /// How far through a turn the spinner is, from 0 to 1.
+    t: f64,
+}
+
+impl Spinner {
+    /// Create a new `Spinner`.
+    pub fn new() -> Spinner {
+        Spinner::default()
+    }
+}
+
+impl<T: Data> Widget<T> for Spinner {
+    fn event(&mut self, _ctx: &mut EventCtx, _event: &Event, _data: &mut T, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &T, _env: &Env) {
+        match event {
+            LifeCycle::WidgetAdded => ctx.request_anim_frame(),
+            LifeCycle::AnimFrame(interval) => {
+                self.t = (self.t + *interval as f64 / PERIOD).fract();
+                ctx.invalidate();
+                ctx.request_anim_frame();
+            }
+            _ => (),
+        }
+    }
+
+    fn update(&mut self, _ctx: &mut UpdateCtx, _old_data: &T, _data: &T, _env: &Env) {}
+
+    fn layout(
+        &mut self,
+        _layout_ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        _data: &T,
+        env: &Env,
+    ) -> Size {
+        bc.debug_check("Spinner");
+        let side = env.get(theme::BASIC_WIDGET_HEIGHT);
+        bc.constrain(Size::new(side, side))
+    }
+
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, _data: &T, env: &Env) {
+        let size = paint_ctx.size();
+        let center = Point::new(size.width / 2., size.height / 2.);
+        let radius = size.width.min(size.height) / 2.;
+        let color = env.get(theme::LABEL_COLOR);
+        // the spoke at the head of the turn is opaque, the others fade out.
+        let head = (self.t * SPOKES as f64).floor() as usize;
+
+        for spoke in 0..SPOKES {
+            let angle = 2. * PI * spoke as f64 / SPOKES as f64;
+            let direction = Vec2::new(angle.sin(), -angle.cos());
+            let line = Line::new(
+                center + direction * radius * 0.5,
+                center + direction * (radius - 1.),
+            );
+            let age = (head + SPOKES - spoke) % SPOKES;
+            let alpha = 1. - age as f64 / SPOKES as f64;
+            paint_ctx.stroke(line, &color.clone().with_alpha(alpha), 2.);
+        }
+    }
+}
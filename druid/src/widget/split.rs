@@ -21,12 +21,18 @@ use crate::{
     PaintCtx, RenderContext, UpdateCtx, Widget, WidgetPod,
 };
 
+/// How far beyond its visible edges the splitter can be grabbed, in pixels.
+const SPLITTER_HIT_PADDING: f64 = 3.0;
+
 ///A container containing two other widgets, splitting the area either horizontally or vertically.
 pub struct Split<T: Data> {
     split_direction: Axis,
     draggable: bool,
     split_point: f64,
+    default_split_point: f64,
     splitter_size: f64,
+    min_size: (f64, f64),
+    snap_threshold: f64,
     child1: WidgetPod<T, Box<dyn Widget<T>>>,
     child2: WidgetPod<T, Box<dyn Widget<T>>>,
 }
@@ -41,7 +47,10 @@ impl<T: Data> Split<T> {
         Split {
             split_direction,
             split_point: 0.5,
+            default_split_point: 0.5,
             splitter_size: 10.0,
+            min_size: (0.0, 0.0),
+            snap_threshold: 0.0,
             draggable: false,
             child1: WidgetPod::new(child1).boxed(),
             child2: WidgetPod::new(child2).boxed(),
@@ -57,12 +66,41 @@ impl<T: Data> Split<T> {
     }
     /// Set container's split point as a fraction of the split dimension
     /// The value must be between 0.0 and 1.0, exclusive
+    ///
+    /// Double-clicking a draggable splitter resets it to this point.
     pub fn split_point(mut self, split_point: f64) -> Self {
         assert!(
             split_point > 0.0 && split_point < 1.0,
             "split_point must be between 0.0 and 1.0!"
         );
         self.split_point = split_point;
+        self.default_split_point = split_point;
+        self
+    }
+    /// Set the minimum size of each child along the split dimension, in pixels.
+    ///
+    /// Dragging the splitter stops at these bounds. If there is not room for
+    /// both, the first child gets its minimum size first.
+    pub fn min_size(mut self, first: f64, second: f64) -> Self {
+        assert!(
+            first >= 0.0 && second >= 0.0,
+            "min_size must be 0.0 or greater!"
+        );
+        self.min_size = (first, second);
+        self
+    }
+    /// Collapse a child entirely when the splitter is dragged to within
+    /// `threshold` pixels of its far edge.
+    ///
+    /// This is most useful together with [`min_size`]: the splitter stops at
+    /// a child's minimum size, until it is dragged far enough past it to
+    /// collapse the child. A collapsed child can be restored by dragging the
+    /// splitter back out, or by double-clicking it.
+    ///
+    /// [`min_size`]: #method.min_size
+    pub fn snap_to_collapse(mut self, threshold: f64) -> Self {
+        assert!(threshold >= 0.0, "snap threshold must be 0.0 or greater!");
+        self.snap_threshold = threshold;
         self
     }
     /// Set the width of the splitter bar, in pixels
@@ -80,47 +118,50 @@ impl<T: Data> Split<T> {
         self.draggable = draggable;
         self
     }
-    fn splitter_hit_test(&self, size: Size, mouse_pos: Point) -> bool {
+    /// The length of the mouse position along the split dimension.
+    fn major_pos(&self, pos: Point) -> f64 {
         match self.split_direction {
-            Axis::Horizontal => {
-                let center = size.width * self.split_point;
-                (center - mouse_pos.x).abs() < self.splitter_size.min(5.0) / 2.0
-            }
-            Axis::Vertical => {
-                let center = size.height * self.split_point;
-                (center - mouse_pos.y).abs() < self.splitter_size.min(5.0) / 2.0
-            }
+            Axis::Horizontal => pos.x,
+            Axis::Vertical => pos.y,
         }
     }
+    /// The length of the first child along the split dimension, given the
+    /// length that the two children share.
+    fn first_length(&self, reduced: f64) -> f64 {
+        let length = reduced * self.split_point;
+        // a collapsed child stays collapsed, whatever its minimum size.
+        if self.split_point <= 0.0 || self.split_point >= 1.0 {
+            return length.max(0.0);
+        }
+        let max = reduced - self.min_size.1;
+        length.min(max).max(self.min_size.0).max(0.0)
+    }
+    fn splitter_hit_test(&self, size: Size, mouse_pos: Point) -> bool {
+        let reduced = self.split_direction.major(size) - self.splitter_size;
+        let edge1 = self.first_length(reduced);
+        let edge2 = edge1 + self.splitter_size;
+        let pos = self.major_pos(mouse_pos);
+        pos >= edge1 - SPLITTER_HIT_PADDING && pos <= edge2 + SPLITTER_HIT_PADDING
+    }
     fn update_splitter(&mut self, size: Size, mouse_pos: Point) {
-        self.split_point = match self.split_direction {
-            Axis::Horizontal => {
-                let max_limit = size.width - (self.splitter_size * 0.5).min(5.0);
-                let min_limit = (self.splitter_size * 0.5).min(5.0);
-                let max_split = max_limit / size.width;
-                let min_split = min_limit / size.width;
-                if mouse_pos.x > max_limit {
-                    max_split
-                } else if mouse_pos.x < min_limit {
-                    min_split
-                } else {
-                    mouse_pos.x / size.width
-                }
-            }
-            Axis::Vertical => {
-                let max_limit = size.height - (self.splitter_size * 0.5).min(5.0);
-                let min_limit = (self.splitter_size * 0.5).min(5.0);
-                let max_split = max_limit / size.height;
-                let min_split = min_limit / size.height;
-                if mouse_pos.y > max_limit {
-                    max_split
-                } else if mouse_pos.y < min_limit {
-                    min_split
-                } else {
-                    mouse_pos.y / size.height
-                }
-            }
+        let reduced = self.split_direction.major(size) - self.splitter_size;
+        if reduced <= 0.0 {
+            return;
         }
+        // keep the middle of the splitter under the mouse.
+        let length = self.major_pos(mouse_pos) - self.splitter_size / 2.0;
+        let length = if length < self.snap_threshold {
+            0.0
+        } else if reduced - length < self.snap_threshold {
+            reduced
+        } else {
+            length
+                .min(reduced - self.min_size.1)
+                .max(self.min_size.0)
+                .max(0.0)
+                .min(reduced)
+        };
+        self.split_point = length / reduced;
     }
 }
 impl<T: Data> Widget<T> for Split<T> {
@@ -141,7 +182,12 @@ impl<T: Data> Widget<T> for Split<T> {
             match event {
                 Event::MouseDown(mouse) => {
                     if mouse.button.is_left() && self.splitter_hit_test(ctx.size(), mouse.pos) {
-                        ctx.set_active(true);
+                        if mouse.count == 2 {
+                            self.split_point = self.default_split_point;
+                            ctx.invalidate();
+                        } else {
+                            ctx.set_active(true);
+                        }
                         ctx.set_handled();
                     }
                 }
@@ -199,7 +245,7 @@ impl<T: Data> Widget<T> for Split<T> {
                 if !bc.is_width_bounded() {
                     log::warn!("A Split widget was given an unbounded width to split.")
                 }
-                let child1_width = (reduced_width * self.split_point).max(0.0);
+                let child1_width = self.first_length(reduced_width);
                 let child2_width = (reduced_width - child1_width).max(0.0);
                 (
                     BoxConstraints::new(
@@ -216,7 +262,7 @@ impl<T: Data> Widget<T> for Split<T> {
                 if !bc.is_width_bounded() {
                     log::warn!("A Split widget was given an unbounded height to split.")
                 }
-                let child1_height = (reduced_height * self.split_point).max(0.0);
+                let child1_height = self.first_length(reduced_height);
                 let child2_height = (reduced_height - child1_height).max(0.0);
                 (
                     BoxConstraints::new(
@@ -274,7 +320,7 @@ impl<T: Data> Widget<T> for Split<T> {
         let (line1, line2) = match self.split_direction {
             Axis::Horizontal => {
                 let reduced_width = size.width - self.splitter_size;
-                let edge1 = reduced_width * self.split_point;
+                let edge1 = self.first_length(reduced_width);
                 let edge2 = edge1 + self.splitter_size;
                 (
                     Line::new(
@@ -289,7 +335,7 @@ impl<T: Data> Widget<T> for Split<T> {
             }
             Axis::Vertical => {
                 let reduced_height = size.height - self.splitter_size;
-                let edge1 = reduced_height * self.split_point;
+                let edge1 = self.first_length(reduced_height);
                 let edge2 = edge1 + self.splitter_size;
                 (
                     Line::new(
@@ -0,0 +1,276 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A widget that shows one of several children, chosen from a row of tabs.
+
+use crate::kurbo::{Line, Point, Rect, Size};
+use crate::piet::{FontBuilder, RenderContext, Text, TextLayout, TextLayoutBuilder};
+use crate::theme;
+use crate::{
+    BoxConstraints, Data, Env, Event, EventCtx, HotKey, KeyCode, LayoutCtx, Lens, LensExt,
+    LifeCycle, LifeCycleCtx, PaintCtx, UpdateCtx, Widget, WidgetPod,
+};
+
+/// The space between a tab's label and its edges.
+const TAB_PADDING: f64 = 8.;
+
+/// A widget with a row of tabs, that shows the child of the selected tab.
+///
+/// The index of the selected tab is part of the data, through a [`Lens`].
+/// Clicking a tab selects it; when the tab bar has focus, the left and right
+/// arrow keys select the previous and next tab.
+///
+/// All of the children are kept while they are hidden, so each one keeps its
+/// state, such as a scroll position, when another tab is selected. Hidden
+/// children are updated when the data changes, and receive commands and
+/// timers, but no mouse or keyboard events; only the selected child is laid
+/// out and painted.
+///
+/// # Examples
+///
+/// ```
+/// # use druid::widget::{Label, Tabs};
+/// # use druid::{Data, Lens};
+/// #[derive(Clone, Data, Lens)]
+/// struct AppState {
+///     tab: usize,
+/// }
+///
+/// let tabs = Tabs::new(AppState::tab)
+///     .with_tab("First", Label::new("the first tab"))
+///     .with_tab("Second", Label::new("the second tab"));
+/// ```
+///
+/// [`Lens`]: ../trait.Lens.html
+pub struct Tabs<T: Data, L> {
+    selected: L,
+    tabs: Vec<Tab<T>>,
+}
+
+struct Tab<T: Data> {
+    label: String,
+    /// The tab's rectangle in the tab bar, set in layout.
+    rect: Rect,
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+}
+
+impl<T: Data, L: Lens<T, usize>> Tabs<T, L> {
+    /// Create a new `Tabs` widget, with no tabs, that keeps the index of the
+    /// selected tab in the field of the data that `selected` points to.
+    pub fn new(selected: L) -> Self {
+        Tabs {
+            selected,
+            tabs: Vec::new(),
+        }
+    }
+
+    /// Builder-style method to add a tab, with its label and its child.
+    pub fn with_tab(mut self, label: impl Into<String>, child: impl Widget<T> + 'static) -> Self {
+        self.tabs.push(Tab {
+            label: label.into(),
+            rect: Rect::ZERO,
+            child: WidgetPod::new(child).boxed(),
+        });
+        self
+    }
+
+    /// The index of the selected tab, which is always a valid index, unless
+    /// there are no tabs.
+    fn selected_index(&self, data: &T) -> usize {
+        self.selected
+            .get(data)
+            .min(self.tabs.len().saturating_sub(1))
+    }
+
+    fn select(&self, ctx: &mut EventCtx, data: &mut T, idx: usize) {
+        if idx != self.selected.get(data) {
+            self.selected.put(data, idx);
+            ctx.invalidate();
+        }
+    }
+
+    fn bar_height(env: &Env) -> f64 {
+        env.get(theme::BASIC_WIDGET_HEIGHT) + TAB_PADDING
+    }
+}
+
+impl<T: Data, L: Lens<T, usize>> Widget<T> for Tabs<T, L> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        if self.tabs.is_empty() {
+            return;
+        }
+        let selected = self.selected_index(data);
+        match event {
+            Event::MouseDown(mouse) => {
+                let clicked = self
+                    .tabs
+                    .iter()
+                    .position(|tab| tab.rect.contains(mouse.pos));
+                if let Some(idx) = clicked {
+                    ctx.request_focus();
+                    self.select(ctx, data, idx);
+                    ctx.set_handled();
+                    return;
+                }
+            }
+            Event::KeyDown(key) if ctx.has_focus() => {
+                if HotKey::new(None, KeyCode::ArrowLeft).matches(key) && selected > 0 {
+                    self.select(ctx, data, selected - 1);
+                    ctx.set_handled();
+                    return;
+                }
+                if HotKey::new(None, KeyCode::ArrowRight).matches(key)
+                    && selected + 1 < self.tabs.len()
+                {
+                    self.select(ctx, data, selected + 1);
+                    ctx.set_handled();
+                    return;
+                }
+            }
+            _ => (),
+        }
+
+        match event {
+            Event::MouseDown(_)
+            | Event::MouseUp(_)
+            | Event::Wheel(_)
+            | Event::KeyDown(_)
+            | Event::KeyUp(_)
+            | Event::ImeComposition { .. }
+            | Event::ImeCommit(_)
+            | Event::Paste(_)
+            | Event::Copy(_)
+            | Event::Cut(_)
+            | Event::FileDragOver(_)
+            | Event::FileDragLeave
+            | Event::FileDrop(_)
+            | Event::Zoom(_)
+            | Event::Gesture(_) => self.tabs[selected].child.event(ctx, event, data, env),
+            // hidden children have an empty layout rect, so moving the
+            // mouse makes them lose their hot state.
+            _ => {
+                for tab in &mut self.tabs {
+                    tab.child.event(ctx, event, data, env);
+                }
+            }
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            ctx.register_for_focus();
+        }
+        for tab in &mut self.tabs {
+            tab.child.lifecycle(ctx, event, data, env);
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        if self.selected.get(old_data) != self.selected.get(data) {
+            ctx.invalidate();
+        }
+        for tab in &mut self.tabs {
+            tab.child.update(ctx, data, env);
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        bc.debug_check("Tabs");
+
+        let bar_height = Self::bar_height(env);
+        let font_name = env.get(theme::FONT_NAME);
+        let font_size = env.get(theme::TEXT_SIZE_NORMAL);
+        let font = ctx
+            .text()
+            .new_font_by_name(font_name, font_size)
+            .build()
+            .unwrap();
+        let mut x = 0.;
+        for tab in &mut self.tabs {
+            let layout = ctx
+                .text()
+                .new_text_layout(&font, &tab.label)
+                .build()
+                .unwrap();
+            let width = layout.width() + TAB_PADDING * 2.;
+            tab.rect = Rect::new(x, 0., x + width, bar_height);
+            x += width;
+        }
+
+        let mut size = Size::new(x, bar_height);
+        if !self.tabs.is_empty() {
+            let selected = self.selected_index(data);
+            let child_bc = bc.shrink((0., bar_height));
+            for (idx, tab) in self.tabs.iter_mut().enumerate() {
+                if idx == selected {
+                    let child_size = tab.child.layout(ctx, &child_bc, data, env);
+                    let origin = Point::new(0., bar_height);
+                    tab.child
+                        .set_layout_rect(Rect::from_origin_size(origin, child_size));
+                    size.width = size.width.max(child_size.width);
+                    size.height += child_size.height;
+                } else {
+                    tab.child.set_layout_rect(Rect::ZERO);
+                }
+            }
+        }
+        bc.constrain(size)
+    }
+
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &T, env: &Env) {
+        let bar_height = Self::bar_height(env);
+        let font_name = env.get(theme::FONT_NAME);
+        let font_size = env.get(theme::TEXT_SIZE_NORMAL);
+        let font = paint_ctx
+            .text()
+            .new_font_by_name(font_name, font_size)
+            .build()
+            .unwrap();
+        let selected = self.selected_index(data);
+        let width = paint_ctx.size().width;
+
+        let bar = Rect::new(0., 0., width, bar_height);
+        paint_ctx.fill(bar, &env.get(theme::BACKGROUND_DARK));
+        let baseline = Line::new((0., bar_height - 0.5), (width, bar_height - 0.5));
+        paint_ctx.stroke(baseline, &env.get(theme::BORDER_LIGHT), 1.);
+
+        for (idx, tab) in self.tabs.iter().enumerate() {
+            if idx == selected {
+                paint_ctx.fill(tab.rect, &env.get(theme::BACKGROUND_LIGHT));
+                let y = bar_height - 1.;
+                let underline = Line::new((tab.rect.x0, y), (tab.rect.x1, y));
+                let color = if paint_ctx.has_focus() {
+                    env.get(theme::PRIMARY_LIGHT)
+                } else {
+                    env.get(theme::PRIMARY_DARK)
+                };
+                paint_ctx.stroke(underline, &color, 2.);
+            }
+            let layout = paint_ctx
+                .text()
+                .new_text_layout(&font, &tab.label)
+                .build()
+                .unwrap();
+            let text_pos = Point::new(
+                tab.rect.x0 + TAB_PADDING,
+                (bar_height - font_size) / 2. + font_size * 0.8,
+            );
+            paint_ctx.draw_text(&layout, text_pos, &env.get(theme::LABEL_COLOR));
+        }
+
+        if let Some(tab) = self.tabs.get_mut(selected) {
+            tab.child.paint_with_offset(paint_ctx, data, env);
+        }
+    }
+}
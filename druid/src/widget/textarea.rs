@@ -0,0 +1,736 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A multi-line text editing widget.
+
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use xi_unicode::LineBreakIterator;
+
+use crate::kurbo::{Line, Point, Rect, RoundedRect, Size, Vec2};
+use crate::piet::{
+    FontBuilder, PietFont, PietText, PietTextLayout, RenderContext, Text, TextLayout,
+    TextLayoutBuilder,
+};
+use crate::theme;
+use crate::{
+    BoxConstraints, Cursor, Env, Event, EventCtx, HotKey, KeyCode, KeyEvent, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, RawMods, Selector, SysMods, TimerToken, UpdateCtx, Widget,
+};
+
+use crate::text::{movement, offset_for_delete_backwards, EditableText, Movement, Selection};
+
+const BORDER_WIDTH: f64 = 1.;
+const PADDING_TOP: f64 = 5.;
+const PADDING_LEFT: f64 = 4.;
+/// The space between the bottom of one line and the top of the next.
+const LINE_SPACING: f64 = 4.;
+/// The width of the selection shown for a selected line break.
+const NEWLINE_WIDTH: f64 = 4.;
+
+// we send ourselves this when we want to reset blink, which must be done in event.
+const RESET_BLINK: Selector = Selector::new("druid-builtin.reset-textarea-blink");
+
+/// A widget that allows user text input, over several lines.
+///
+/// Unlike a [`TextBox`], a `TextArea` wraps its text to its width, and grows
+/// vertically to fit all of it; to limit its height, put it in a vertical
+/// [`Scroll`]. Enter inserts a line break, and Up, Down, Home and End move
+/// the caret by the lines as they are shown, not by the line breaks in the
+/// text.
+///
+/// [`TextBox`]: struct.TextBox.html
+/// [`Scroll`]: struct.Scroll.html
+#[derive(Debug, Clone)]
+pub struct TextArea {
+    placeholder: String,
+    width: f64,
+    /// The text that is shown, as it was last wrapped.
+    wrapped: Wrapped,
+    /// The placeholder, as it was last wrapped.
+    wrapped_placeholder: Wrapped,
+    selection: Selection,
+    /// The text being composed by an input method, shown in place of the
+    /// selection.
+    composition: String,
+    /// The byte offset of the caret in the composition.
+    composition_cursor: usize,
+    /// The x position the caret stays close to as it moves up and down.
+    preferred_x: Option<f64>,
+    cursor_timer: TimerToken,
+    cursor_on: bool,
+}
+
+/// The lines that a text was broken into, and the width and text they were
+/// broken for.
+#[derive(Debug, Clone)]
+struct Wrapped {
+    width: f64,
+    text: String,
+    /// The byte range of each line in the text, not including a line break.
+    ranges: Vec<Range<usize>>,
+}
+
+/// A line of text, as it is shown after wrapping.
+struct VisualLine {
+    /// The byte range of the line in the text, not including a line break.
+    range: Range<usize>,
+    layout: PietTextLayout,
+}
+
+impl TextArea {
+    /// Create a new TextArea widget.
+    pub fn new() -> TextArea {
+        TextArea {
+            placeholder: String::new(),
+            width: 0.,
+            wrapped: Wrapped::new(),
+            wrapped_placeholder: Wrapped::new(),
+            selection: Selection::caret(0),
+            composition: String::new(),
+            composition_cursor: 0,
+            preferred_x: None,
+            cursor_timer: TimerToken::INVALID,
+            cursor_on: false,
+        }
+    }
+
+    /// Create a new TextArea widget with placeholder.
+    pub fn with_placeholder<T: Into<String>>(placeholder: T) -> TextArea {
+        let mut textarea = Self::new();
+        textarea.placeholder = placeholder.into();
+        textarea
+    }
+
+    /// The current selection, as byte offsets into the data.
+    pub fn selection(&self) -> Selection {
+        self.selection
+    }
+
+    /// Set the selection, or the position of the caret.
+    ///
+    /// The offsets are in bytes, and should be on grapheme boundaries of the
+    /// data; they are clamped to its length.
+    pub fn set_selection(&mut self, selection: Selection) {
+        self.selection = selection;
+        self.preferred_x = None;
+    }
+
+    /// Wrap the text that is shown to the current width.
+    fn rewrap(&mut self, piet_text: &mut PietText, data: &str, env: &Env) {
+        let text = self.display_text(data);
+        self.wrapped.rewrap(piet_text, &text, self.width, env);
+    }
+
+    /// The text to display: `data`, with any composition in place of the
+    /// selection.
+    fn display_text(&self, data: &str) -> String {
+        if self.composition.is_empty() {
+            return data.to_string();
+        }
+        let (min, max) = (self.selection.min(), self.selection.max());
+        format!("{}{}{}", &data[..min], self.composition, &data[max..])
+    }
+
+    /// The offset of the caret in the display text.
+    fn display_cursor(&self) -> usize {
+        if self.composition.is_empty() {
+            self.selection.end
+        } else {
+            self.selection.min() + self.composition_cursor
+        }
+    }
+
+    /// Tell the input method where the caret is.
+    fn report_ime_area(&self, ctx: &mut EventCtx, lines: &[VisualLine], env: &Env) {
+        let font_size = env.get(theme::TEXT_SIZE_NORMAL);
+        let cursor = self.display_cursor();
+        let idx = line_for_offset(lines, cursor);
+        let x = x_for_offset(&lines[idx], cursor);
+        let origin = Point::new(
+            x + PADDING_LEFT,
+            PADDING_TOP + idx as f64 * line_height(env),
+        );
+        ctx.set_ime_cursor_area(Rect::from_origin_size(origin, (1., font_size)));
+    }
+
+    /// Insert text at the cursor position.
+    /// Replaces selected text if there's a selection.
+    fn insert(&mut self, src: &mut String, new: &str) {
+        let selection = self.selection.constrain_to(src);
+
+        src.edit(selection.range(), new);
+        self.selection = Selection::caret(selection.min() + new.len());
+    }
+
+    /// Edit a selection using a `Movement`.
+    fn move_selection(&mut self, mvmnt: Movement, text: &mut String, modify: bool) {
+        self.selection = movement(mvmnt, self.selection, text, modify);
+    }
+
+    /// Move the active edge of the selection to `offset`; if `modify` is
+    /// `false`, the selection becomes a caret.
+    fn select_to(&mut self, offset: usize, modify: bool) {
+        self.selection = if modify {
+            Selection::new(self.selection.start, offset)
+        } else {
+            Selection::caret(offset)
+        };
+    }
+
+    /// Delete to previous grapheme if in caret mode.
+    /// Otherwise just delete everything inside the selection.
+    fn delete_backward(&mut self, text: &mut String) {
+        if self.selection.is_caret() {
+            let cursor = self.selection.end;
+            let new_cursor = offset_for_delete_backwards(&self.selection, text);
+            text.edit(new_cursor..cursor, "");
+            self.selection = Selection::caret(new_cursor);
+        } else {
+            text.edit(self.selection.range(), "");
+            self.selection = Selection::caret(self.selection.min());
+        }
+    }
+
+    /// Move the caret to the line above or below, keeping as close as
+    /// possible to its original x position.
+    fn move_vertically(&mut self, lines: &[VisualLine], text: &str, down: bool, modify: bool) {
+        let idx = line_for_offset(lines, self.selection.end);
+        let x = match self.preferred_x {
+            Some(x) => x,
+            None => x_for_offset(&lines[idx], self.selection.end),
+        };
+        let offset = if down && idx + 1 < lines.len() {
+            offset_in_line(lines, text, idx + 1, x)
+        } else if down {
+            text.len()
+        } else if idx > 0 {
+            offset_in_line(lines, text, idx - 1, x)
+        } else {
+            0
+        };
+        self.select_to(offset, modify);
+        self.preferred_x = Some(x);
+    }
+
+    /// For a given point, returns the corresponding offset (in bytes) of
+    /// the grapheme cluster closest to that point.
+    fn offset_for_point(&self, point: Point, lines: &[VisualLine], text: &str, env: &Env) -> usize {
+        let row = ((point.y - PADDING_TOP) / line_height(env)).floor().max(0.) as usize;
+        let idx = row.min(lines.len() - 1);
+        offset_in_line(lines, text, idx, point.x - PADDING_LEFT)
+    }
+
+    fn handle_key(&mut self, key_event: &KeyEvent, lines: &[VisualLine], data: &mut String) {
+        match key_event {
+            k_e if HotKey::new(None, KeyCode::ArrowUp).matches(k_e) => {
+                return self.move_vertically(lines, data, false, false);
+            }
+            k_e if HotKey::new(RawMods::Shift, KeyCode::ArrowUp).matches(k_e) => {
+                return self.move_vertically(lines, data, false, true);
+            }
+            k_e if HotKey::new(None, KeyCode::ArrowDown).matches(k_e) => {
+                return self.move_vertically(lines, data, true, false);
+            }
+            k_e if HotKey::new(RawMods::Shift, KeyCode::ArrowDown).matches(k_e) => {
+                return self.move_vertically(lines, data, true, true);
+            }
+            _ => self.preferred_x = None,
+        }
+
+        let idx = line_for_offset(lines, self.selection.end);
+        match key_event {
+            // Select all (Ctrl+A || Cmd+A)
+            k_e if (HotKey::new(SysMods::Cmd, "a")).matches(k_e) => {
+                self.selection.all(data);
+            }
+            // Jump to the start of the line (Ctrl+ArrowLeft || Cmd+ArrowLeft)
+            k_e if (HotKey::new(SysMods::Cmd, KeyCode::ArrowLeft)).matches(k_e)
+                || HotKey::new(None, KeyCode::Home).matches(k_e) =>
+            {
+                self.select_to(lines[idx].range.start, false);
+            }
+            // Jump to the end of the line (Ctrl+ArrowRight || Cmd+ArrowRight)
+            k_e if (HotKey::new(SysMods::Cmd, KeyCode::ArrowRight)).matches(k_e)
+                || HotKey::new(None, KeyCode::End).matches(k_e) =>
+            {
+                self.select_to(line_end(lines, idx, data), false);
+            }
+            // Select left (Shift+ArrowLeft)
+            k_e if (HotKey::new(RawMods::Shift, KeyCode::ArrowLeft)).matches(k_e) => {
+                self.move_selection(Movement::Left, data, true);
+            }
+            // Select right (Shift+ArrowRight)
+            k_e if (HotKey::new(RawMods::Shift, KeyCode::ArrowRight)).matches(k_e) => {
+                self.move_selection(Movement::Right, data, true);
+            }
+            // Move left (ArrowLeft)
+            k_e if (HotKey::new(None, KeyCode::ArrowLeft)).matches(k_e) => {
+                self.move_selection(Movement::Left, data, false);
+            }
+            // Move right (ArrowRight)
+            k_e if (HotKey::new(None, KeyCode::ArrowRight)).matches(k_e) => {
+                self.move_selection(Movement::Right, data, false);
+            }
+            // Backspace
+            k_e if (HotKey::new(None, KeyCode::Backspace)).matches(k_e) => {
+                self.delete_backward(data);
+            }
+            // Delete
+            k_e if (HotKey::new(None, KeyCode::Delete)).matches(k_e) => {
+                if self.selection.is_caret() {
+                    // Never touch the characters before the cursor.
+                    if data.next_grapheme_offset(self.selection.end).is_some() {
+                        self.move_selection(Movement::Right, data, false);
+                        self.delete_backward(data);
+                    }
+                } else {
+                    self.delete_backward(data);
+                }
+            }
+            // Tab and shift+tab move focus; the window handles them.
+            k_e if k_e.key_code == KeyCode::Tab => {}
+            // Enter starts a new line
+            k_e if k_e.key_code == KeyCode::Return || k_e.key_code == KeyCode::NumpadEnter => {
+                self.insert(data, "\n");
+            }
+            // Actual typing
+            k_e if k_e.key_code.is_printable() => {
+                let incoming_text = k_e.text().unwrap_or("");
+                self.insert(data, incoming_text);
+            }
+            _ => {}
+        }
+    }
+
+    fn reset_cursor_blink(&mut self, ctx: &mut EventCtx) {
+        self.cursor_on = true;
+        let deadline = Instant::now() + Duration::from_millis(500);
+        self.cursor_timer = ctx.request_timer(deadline);
+    }
+}
+
+impl Wrapped {
+    /// The lines of an empty text.
+    fn new() -> Wrapped {
+        Wrapped {
+            width: 0.,
+            text: String::new(),
+            ranges: vec![Range { start: 0, end: 0 }],
+        }
+    }
+
+    /// Break `text` into the lines that fit in `width`, unless it was broken
+    /// for the same width last time.
+    fn rewrap(&mut self, piet_text: &mut PietText, text: &str, width: f64, env: &Env) {
+        if self.width == width && self.text == text {
+            return;
+        }
+        let font = font(piet_text, env);
+        let max_width = width - PADDING_LEFT * 2.;
+
+        self.ranges.clear();
+        let mut start = 0;
+        for paragraph in text.split('\n') {
+            let lines = wrap_paragraph(paragraph, |line| {
+                let layout = piet_text.new_text_layout(&font, line).build().unwrap();
+                layout.width() <= max_width
+            });
+            self.ranges
+                .extend(lines.into_iter().map(|r| r.start + start..r.end + start));
+            start += paragraph.len() + 1;
+        }
+        self.width = width;
+        self.text = text.to_string();
+    }
+
+    /// Lay out each of the lines.
+    fn lines(&self, piet_text: &mut PietText, env: &Env) -> Vec<VisualLine> {
+        let font = font(piet_text, env);
+        self.ranges
+            .iter()
+            .map(|range| VisualLine {
+                layout: piet_text
+                    .new_text_layout(&font, &self.text[range.clone()])
+                    .build()
+                    .unwrap(),
+                range: range.clone(),
+            })
+            .collect()
+    }
+}
+
+impl Default for TextArea {
+    fn default() -> Self {
+        TextArea::new()
+    }
+}
+
+impl Widget<String> for TextArea {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut String, env: &Env) {
+        // Guard against external changes in data?
+        self.selection = self.selection.constrain_to(data);
+
+        let lines = self.wrapped.lines(ctx.text(), env);
+        let composing = !self.composition.is_empty();
+        match event {
+            // while composing, the lines show the composition, not the data.
+            Event::MouseDown(mouse) if !composing => {
+                ctx.request_focus();
+                ctx.set_active(true);
+                let offset = self.offset_for_point(mouse.pos, &lines, data, env);
+                self.select_to(offset, mouse.mods.shift);
+                self.preferred_x = None;
+                ctx.invalidate();
+                self.reset_cursor_blink(ctx);
+            }
+            Event::MouseMoved(mouse) => {
+                ctx.set_cursor(&Cursor::IBeam);
+                if ctx.is_active() && !composing {
+                    self.selection.end = self.offset_for_point(mouse.pos, &lines, data, env);
+                    ctx.invalidate();
+                }
+            }
+            Event::MouseUp(_) if ctx.is_active() => {
+                ctx.set_active(false);
+                ctx.invalidate();
+            }
+            Event::Timer(id) if *id == self.cursor_timer => {
+                self.cursor_on = !self.cursor_on;
+                ctx.invalidate();
+                let deadline = Instant::now() + Duration::from_millis(500);
+                self.cursor_timer = ctx.request_timer(deadline);
+            }
+            Event::Copy(clipboard) | Event::Cut(clipboard) => {
+                if let Some(text) = data.slice(self.selection.range()) {
+                    clipboard.clone().put_string(text);
+                }
+                if !self.selection.is_caret() && matches!(event, Event::Cut(_)) {
+                    self.delete_backward(data);
+                    self.preferred_x = None;
+                    self.reset_cursor_blink(ctx);
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(RESET_BLINK) => {
+                self.reset_cursor_blink(ctx);
+                self.report_ime_area(ctx, &lines, env);
+            }
+            // the composition isn't data, so `update` won't wrap it.
+            Event::ImeComposition { text, cursor } => {
+                self.composition = text.clone();
+                self.composition_cursor = *cursor;
+                self.rewrap(ctx.text(), data, env);
+                let lines = self.wrapped.lines(ctx.text(), env);
+                self.report_ime_area(ctx, &lines, env);
+                ctx.invalidate();
+            }
+            Event::ImeCommit(text) => {
+                self.composition.clear();
+                self.insert(data, text);
+                self.preferred_x = None;
+                self.rewrap(ctx.text(), data, env);
+                let lines = self.wrapped.lines(ctx.text(), env);
+                self.report_ime_area(ctx, &lines, env);
+                self.reset_cursor_blink(ctx);
+                ctx.invalidate();
+            }
+            Event::Paste(ref item) => {
+                if let Some(string) = item.get_string() {
+                    self.insert(data, &string);
+                    self.preferred_x = None;
+                    self.reset_cursor_blink(ctx);
+                    ctx.invalidate();
+                }
+            }
+            Event::KeyDown(key_event) if !composing => {
+                self.handle_key(key_event, &lines, data);
+                self.reset_cursor_blink(ctx);
+                ctx.invalidate();
+            }
+            _ => (),
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &String, _env: &Env) {
+        match event {
+            LifeCycle::WidgetAdded => ctx.register_for_focus(),
+            LifeCycle::FocusChanged(true) => ctx.submit_command(RESET_BLINK, ctx.widget_id()),
+            _ => (),
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &String, data: &String, env: &Env) {
+        self.selection = self.selection.constrain_to(data);
+        self.rewrap(ctx.text(), data, env);
+        ctx.invalidate();
+    }
+
+    fn layout(
+        &mut self,
+        layout_ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &String,
+        env: &Env,
+    ) -> Size {
+        let default_width: f64 = 200.0;
+
+        self.width = if bc.is_width_bounded() {
+            bc.max().width
+        } else {
+            default_width.max(bc.min().width)
+        };
+
+        self.rewrap(layout_ctx.text(), data, env);
+        let line_count = if data.is_empty() && self.composition.is_empty() {
+            let placeholder = &self.placeholder;
+            self.wrapped_placeholder
+                .rewrap(layout_ctx.text(), placeholder, self.width, env);
+            self.wrapped_placeholder.ranges.len()
+        } else {
+            self.wrapped.ranges.len()
+        };
+        let height = line_count as f64 * line_height(env) + PADDING_TOP * 2.;
+        let min_height = env.get(theme::BORDERED_WIDGET_HEIGHT);
+        bc.constrain((self.width, height.max(min_height)))
+    }
+
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &String, env: &Env) {
+        // Guard against changes in data following `event`
+        self.selection = self.selection.constrain_to(data);
+        let showing_placeholder = data.is_empty() && self.composition.is_empty();
+        let wrapped = if showing_placeholder {
+            &self.wrapped_placeholder
+        } else {
+            &self.wrapped
+        };
+        let content = &wrapped.text;
+        let composing = !self.composition.is_empty();
+
+        let font_size = env.get(theme::TEXT_SIZE_NORMAL);
+        let line_height = line_height(env);
+        let background_color = env.get(theme::BACKGROUND_LIGHT);
+        let selection_color = env.get(theme::SELECTION_COLOR);
+        let text_color = if showing_placeholder {
+            env.get(theme::PLACEHOLDER_COLOR)
+        } else {
+            env.get(theme::LABEL_COLOR)
+        };
+        let cursor_color = env.get(theme::CURSOR_COLOR);
+
+        let has_focus = paint_ctx.has_focus();
+
+        let border_color = if has_focus {
+            env.get(theme::PRIMARY_LIGHT)
+        } else {
+            env.get(theme::BORDER)
+        };
+
+        // Paint the background
+        let size = paint_ctx.size();
+        let clip_rect = RoundedRect::from_origin_size(
+            Point::ORIGIN,
+            Size::new(self.width - BORDER_WIDTH, size.height).to_vec2(),
+            2.,
+        );
+
+        paint_ctx.fill(clip_rect, &background_color);
+
+        // Render text, selection, and cursor inside a clip
+        paint_ctx
+            .with_save(|rc| {
+                rc.clip(clip_rect);
+
+                let lines = wrapped.lines(rc.text(), env);
+                let (min, max) = (self.selection.min(), self.selection.max());
+
+                for (idx, line) in lines.iter().enumerate() {
+                    let top = PADDING_TOP + idx as f64 * line_height;
+                    let Range { start, end } = line.range;
+
+                    // Draw the part of the selection on this line
+                    let ends_with_break = content[end..].starts_with('\n');
+                    let selects_break = ends_with_break && min <= end && max > end;
+                    let selected = min.max(start) < max.min(end) || selects_break;
+                    if !showing_placeholder && !composing && selected {
+                        let x0 = x_for_offset(line, min.max(start));
+                        let mut x1 = x_for_offset(line, max.min(end));
+                        if selects_break {
+                            x1 += NEWLINE_WIDTH;
+                        }
+                        let selection_rect = RoundedRect::from_origin_size(
+                            Point::new(x0 + PADDING_LEFT - 1., top - 2.),
+                            Size::new(x1 - x0 + 2., font_size + 4.).to_vec2(),
+                            1.,
+                        );
+                        rc.fill(selection_rect, &selection_color);
+                    }
+
+                    let text_pos = Point::new(PADDING_LEFT, top + font_size * 0.8);
+                    rc.draw_text(&line.layout, text_pos, &text_color);
+
+                    // Underline the part of the composition on this line
+                    let composed = (min.max(start), (min + self.composition.len()).min(end));
+                    if composing && composed.0 < composed.1 {
+                        let y = text_pos.y + 2.;
+                        let x0 = x_for_offset(line, composed.0) + PADDING_LEFT;
+                        let x1 = x_for_offset(line, composed.1) + PADDING_LEFT;
+                        rc.stroke(Line::new((x0, y), (x1, y)), &text_color, 1.);
+                    }
+                }
+
+                // Paint the cursor if focused and there's no selection
+                if has_focus && self.cursor_on && (composing || self.selection.is_caret()) {
+                    let cursor = self.display_cursor();
+                    let idx = line_for_offset(&lines, cursor);
+                    let cursor_x = x_for_offset(&lines[idx], cursor);
+                    let top = PADDING_TOP + idx as f64 * line_height;
+                    let xy = Point::new(cursor_x + PADDING_LEFT, top);
+                    let x2y2 = xy + Vec2::new(0., font_size + 2.);
+                    rc.stroke(Line::new(xy, x2y2), &cursor_color, 1.);
+                }
+                Ok(())
+            })
+            .unwrap();
+
+        // Paint the border
+        paint_ctx.stroke(clip_rect, &border_color, BORDER_WIDTH);
+    }
+}
+
+/// The font that text is shown in.
+fn font(piet_text: &mut PietText, env: &Env) -> PietFont {
+    let font_name = env.get(theme::FONT_NAME);
+    let font_size = env.get(theme::TEXT_SIZE_NORMAL);
+    piet_text
+        .new_font_by_name(font_name, font_size)
+        .build()
+        .unwrap()
+}
+
+/// The distance between the tops of two consecutive lines.
+fn line_height(env: &Env) -> f64 {
+    env.get(theme::TEXT_SIZE_NORMAL) + LINE_SPACING
+}
+
+/// Break a paragraph, which has no line breaks of its own, into lines.
+///
+/// Lines are broken at the last opportunity that `fits` accepts; a word that
+/// does not fit on a line of its own is not broken.
+fn wrap_paragraph(text: &str, mut fits: impl FnMut(&str) -> bool) -> Vec<Range<usize>> {
+    let mut lines = Vec::new();
+    let mut line_start = 0;
+    // the last break opportunity that fits on the current line
+    let mut line_end = 0;
+    for (pos, _) in LineBreakIterator::new(text) {
+        // trailing whitespace may hang past the edge.
+        if line_end > line_start && !fits(text[line_start..pos].trim_end()) {
+            lines.push(line_start..line_end);
+            line_start = line_end;
+        }
+        line_end = pos;
+    }
+    lines.push(line_start..text.len());
+    lines
+}
+
+/// The index of the line that the caret is on when it is at `offset`.
+///
+/// An offset where a line was wrapped is at the start of the next line.
+fn line_for_offset(lines: &[VisualLine], offset: usize) -> usize {
+    lines
+        .iter()
+        .rposition(|line| line.range.start <= offset)
+        .unwrap_or(0)
+}
+
+/// The offset of the caret at the end of the line at `idx`.
+///
+/// If the line was wrapped, this is before the whitespace it was wrapped
+/// at, so that the caret stays on the same line.
+fn line_end(lines: &[VisualLine], idx: usize, text: &str) -> usize {
+    let range = lines[idx].range.clone();
+    let wrapped = matches!(lines.get(idx + 1), Some(next) if next.range.start == range.end);
+    match text[range.clone()].char_indices().next_back() {
+        Some((pos, c)) if wrapped && c.is_whitespace() => range.start + pos,
+        _ => range.end,
+    }
+}
+
+/// The x position of the caret at `offset`, relative to the start of `line`.
+fn x_for_offset(line: &VisualLine, offset: usize) -> f64 {
+    match line
+        .layout
+        .hit_test_text_position(offset - line.range.start)
+    {
+        Some(position) => position.point.x,
+        None => 0.,
+    }
+}
+
+/// The offset in the line at `idx` closest to `x`.
+fn offset_in_line(lines: &[VisualLine], text: &str, idx: usize, x: f64) -> usize {
+    let line = &lines[idx];
+    let hit_test = line.layout.hit_test_point(Point::new(x, 0.));
+    let offset = line.range.start + hit_test.metrics.text_position;
+    offset.min(line_end(lines, idx, text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wrap by counting characters, instead of measuring them.
+    fn wrap_chars(text: &str, width: usize) -> Vec<&str> {
+        wrap_paragraph(text, |line| line.chars().count() <= width)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect()
+    }
+
+    #[test]
+    fn wrap_at_word_boundaries() {
+        assert_eq!(
+            wrap_chars("the quick brown fox", 10),
+            vec!["the quick ", "brown fox"]
+        );
+        assert_eq!(
+            wrap_chars("the quick brown fox", 19),
+            vec!["the quick brown fox"]
+        );
+        assert_eq!(wrap_chars("", 10), vec![""]);
+    }
+
+    /// The composition is shown in place of the selection, with the caret
+    /// inside it.
+    #[test]
+    fn composition_replaces_selection() {
+        let mut widget = TextArea::new();
+        widget.selection = Selection::new(1, 3);
+        widget.composition = "かな".to_string();
+        widget.composition_cursor = 3;
+
+        assert_eq!(widget.display_text("ab\nd"), "aかなd");
+        assert_eq!(widget.display_cursor(), 4);
+    }
+
+    #[test]
+    fn long_words_are_not_broken() {
+        assert_eq!(
+            wrap_chars("a supercalifragilistic word", 8),
+            vec!["a ", "supercalifragilistic ", "word"]
+        );
+    }
+}
@@ -16,12 +16,14 @@
 
 use std::time::{Duration, Instant};
 
+use unicode_segmentation::UnicodeSegmentation;
+
 use crate::{
-    Application, BoxConstraints, Cursor, Env, Event, EventCtx, HotKey, KeyCode, LayoutCtx,
-    LifeCycle, LifeCycleCtx, PaintCtx, RawMods, Selector, SysMods, TimerToken, UpdateCtx, Widget,
+    BoxConstraints, Cursor, Env, Event, EventCtx, HotKey, KeyCode, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, RawMods, Role, Selector, SysMods, TimerToken, UpdateCtx, Widget,
 };
 
-use crate::kurbo::{Affine, Line, Point, RoundedRect, Size, Vec2};
+use crate::kurbo::{Affine, Line, Point, Rect, RoundedRect, Size, Vec2};
 use crate::piet::{
     FontBuilder, PietText, PietTextLayout, RenderContext, Text, TextLayout, TextLayoutBuilder,
     UnitPoint,
@@ -35,18 +37,33 @@ const BORDER_WIDTH: f64 = 1.;
 const PADDING_TOP: f64 = 5.;
 const PADDING_LEFT: f64 = 4.;
 
+/// The character shown in place of each grapheme in password mode.
+const BULLET: char = '\u{2022}';
+
 // we send ourselves this when we want to reset blink, which must be done in event.
 const RESET_BLINK: Selector = Selector::new("druid-builtin.reset-textbox-blink");
 
 /// A widget that allows user text input.
+///
+/// When the text is empty, a placeholder is shown in
+/// [`theme::PLACEHOLDER_COLOR`]. In password mode, each character is shown
+/// as a bullet; the data still holds the real text, but it is never drawn,
+/// and it can't be copied or cut to the clipboard.
+///
+/// [`theme::PLACEHOLDER_COLOR`]: ../theme/constant.PLACEHOLDER_COLOR.html
 #[derive(Debug, Clone)]
 pub struct TextBox {
     placeholder: String,
+    password: bool,
     width: f64,
     hscroll_offset: f64,
     selection: Selection,
     cursor_timer: TimerToken,
     cursor_on: bool,
+    /// Text being composed by an input method, shown at the selection.
+    composition: String,
+    /// The byte offset of the caret in the composition.
+    composition_cursor: usize,
 }
 
 impl TextBox {
@@ -62,6 +79,15 @@ impl TextBox {
         Align::vertical(UnitPoint::CENTER, textbox)
     }
 
+    /// Create a new TextBox widget for entering a password.
+    ///
+    /// The text is shown as a row of bullets.
+    pub fn password() -> impl Widget<String> {
+        let mut textbox = Self::raw();
+        textbox.password = true;
+        Align::vertical(UnitPoint::CENTER, textbox)
+    }
+
     /// Create a new TextBox widget with no Align wrapper
     pub fn raw() -> TextBox {
         Self {
@@ -71,9 +97,47 @@ impl TextBox {
             cursor_timer: TimerToken::INVALID,
             cursor_on: false,
             placeholder: String::new(),
+            password: false,
+            composition: String::new(),
+            composition_cursor: 0,
         }
     }
 
+    /// Set the text shown when the text box is empty.
+    pub fn set_placeholder(&mut self, placeholder: impl Into<String>) {
+        self.placeholder = placeholder.into();
+    }
+
+    /// Set whether the text is hidden, as in a password field.
+    pub fn set_password(&mut self, password: bool) {
+        self.password = password;
+    }
+
+    /// Whether the text is hidden, as in a password field.
+    pub fn is_password(&self) -> bool {
+        self.password
+    }
+
+    /// The current selection, as byte offsets into the data.
+    ///
+    /// If nothing is selected, this is a caret.
+    pub fn selection(&self) -> Selection {
+        self.selection
+    }
+
+    /// Set the selection, or the position of the caret.
+    ///
+    /// The [`Selection`]'s offsets are in bytes, and should be on grapheme
+    /// boundaries of the data; they are clamped to its length. This is most
+    /// useful from a [`Controller`], which has access to the text box it
+    /// manages.
+    ///
+    /// [`Selection`]: ../text/struct.Selection.html
+    /// [`Controller`]: trait.Controller.html
+    pub fn set_selection(&mut self, selection: Selection) {
+        self.selection = selection;
+    }
+
     /// Calculate the PietTextLayout from the given text, font, and font size
     fn get_layout(&self, piet_text: &mut PietText, text: &str, env: &Env) -> PietTextLayout {
         let font_name = env.get(theme::FONT_NAME);
@@ -90,6 +154,15 @@ impl TextBox {
             .unwrap()
     }
 
+    /// The layout of `text` as it is shown, which is masked in password mode.
+    fn text_layout(&self, piet_text: &mut PietText, text: &str, env: &Env) -> PietTextLayout {
+        if self.password {
+            self.get_layout(piet_text, &mask(text), env)
+        } else {
+            self.get_layout(piet_text, text, env)
+        }
+    }
+
     /// Insert text at the cursor position.
     /// Replaces selected text if there's a selection.
     fn insert(&mut self, src: &mut String, new: &str) {
@@ -119,6 +192,42 @@ impl TextBox {
         self.selection.end
     }
 
+    /// The text to display: `data`, with any composition in place of the
+    /// selection.
+    fn display_text(&self, data: &str) -> String {
+        if self.composition.is_empty() {
+            return data.to_string();
+        }
+        let (min, max) = (self.selection.min(), self.selection.max());
+        format!("{}{}{}", &data[..min], self.composition, &data[max..])
+    }
+
+    /// The offset of the caret in the display text.
+    fn display_cursor(&self) -> usize {
+        if self.composition.is_empty() {
+            self.cursor()
+        } else {
+            self.selection.min() + self.composition_cursor
+        }
+    }
+
+    /// Tell the input method where the caret is.
+    fn report_ime_area(&self, ctx: &mut EventCtx, layout: &PietTextLayout, text: &str, env: &Env) {
+        let font_size = env.get(theme::TEXT_SIZE_NORMAL);
+        let x = self.x_for_offset(layout, text, self.display_cursor()) - self.hscroll_offset;
+        let origin = Point::new(x + PADDING_LEFT, PADDING_TOP);
+        ctx.set_ime_cursor_area(Rect::from_origin_size(origin, (1., font_size)));
+    }
+
+    /// The strip of the text box that the caret is painted in.
+    fn caret_rect(&self, layout: &PietTextLayout, text: &str, env: &Env) -> Rect {
+        let height = env.get(theme::BORDERED_WIDGET_HEIGHT);
+        let x = self.x_for_offset(layout, text, self.display_cursor()) - self.hscroll_offset;
+        let x = x + PADDING_LEFT;
+        // leave room for antialiasing.
+        Rect::new(x - 2., 0., x + 2., height)
+    }
+
     /// Edit a selection using a `Movement`.    
     fn move_selection(&mut self, mvmnt: Movement, text: &mut String, modify: bool) {
         // This movement function should ensure all movements are legit.
@@ -140,19 +249,29 @@ impl TextBox {
         }
     }
 
-    /// For a given point, returns the corresponding offset (in bytes) of
-    /// the grapheme cluster closest to that point.
-    fn offset_for_point(&self, point: Point, layout: &PietTextLayout) -> usize {
+    /// For a given point, returns the corresponding offset (in bytes) in
+    /// `text` of the grapheme cluster closest to that point.
+    fn offset_for_point(&self, point: Point, layout: &PietTextLayout, text: &str) -> usize {
         // Translating from screenspace to Piet's text layout representation.
         // We need to account for hscroll_offset state and TextBox's padding.
         let translated_point = Point::new(point.x + self.hscroll_offset - PADDING_LEFT, point.y);
         let hit_test = layout.hit_test_point(translated_point);
-        hit_test.metrics.text_position
+        let offset = hit_test.metrics.text_position;
+        if self.password {
+            unmasked_offset(text, offset)
+        } else {
+            offset
+        }
     }
 
-    /// Given an offset (in bytes) of a valid grapheme cluster, return
-    /// the corresponding x coordinate of that grapheme on the screen.
-    fn x_for_offset(&self, layout: &PietTextLayout, offset: usize) -> f64 {
+    /// Given an offset (in bytes) in `text` of a valid grapheme cluster,
+    /// return the corresponding x coordinate of that grapheme on the screen.
+    fn x_for_offset(&self, layout: &PietTextLayout, text: &str, offset: usize) -> f64 {
+        let offset = if self.password {
+            masked_offset(text, offset)
+        } else {
+            offset
+        };
         if let Some(position) = layout.hit_test_text_position(offset) {
             position.point.x
         } else {
@@ -162,8 +281,8 @@ impl TextBox {
     }
 
     /// Calculate a stateful scroll offset
-    fn update_hscroll(&mut self, layout: &PietTextLayout) {
-        let cursor_x = self.x_for_offset(layout, self.cursor());
+    fn update_hscroll(&mut self, layout: &PietTextLayout, text: &str) {
+        let cursor_x = self.x_for_offset(layout, text, self.cursor());
         let overall_text_width = layout.width();
 
         let padding = PADDING_LEFT * 2.;
@@ -201,12 +320,12 @@ impl Widget<String> for TextBox {
         // Guard against external changes in data?
         self.selection = self.selection.constrain_to(data);
 
-        let mut text_layout = self.get_layout(ctx.text(), &data, env);
+        let mut text_layout = self.text_layout(ctx.text(), &data, env);
         match event {
             Event::MouseDown(mouse) => {
                 ctx.request_focus();
                 ctx.set_active(true);
-                let cursor_off = self.offset_for_point(mouse.pos, &text_layout);
+                let cursor_off = self.offset_for_point(mouse.pos, &text_layout, data);
                 if mouse.mods.shift {
                     self.selection.end = cursor_off;
                 } else {
@@ -218,7 +337,7 @@ impl Widget<String> for TextBox {
             Event::MouseMoved(mouse) => {
                 ctx.set_cursor(&Cursor::IBeam);
                 if ctx.is_active() {
-                    self.selection.end = self.offset_for_point(mouse.pos, &text_layout);
+                    self.selection.end = self.offset_for_point(mouse.pos, &text_layout, data);
                     ctx.invalidate();
                 }
             }
@@ -231,25 +350,43 @@ impl Widget<String> for TextBox {
             Event::Timer(id) => {
                 if *id == self.cursor_timer {
                     self.cursor_on = !self.cursor_on;
-                    ctx.invalidate();
+                    // only the caret changes; don't repaint the window.
+                    ctx.request_paint_rect(self.caret_rect(&text_layout, data, env));
                     let deadline = Instant::now() + Duration::from_millis(500);
                     self.cursor_timer = ctx.request_timer(deadline);
                 }
             }
-            Event::Command(ref cmd)
-                if ctx.has_focus()
-                    && (cmd.selector == crate::commands::COPY
-                        || cmd.selector == crate::commands::CUT) =>
-            {
+            Event::Copy(clipboard) | Event::Cut(clipboard) if !self.password => {
                 if let Some(text) = data.slice(self.selection.range()) {
-                    Application::clipboard().put_string(text);
+                    clipboard.clone().put_string(text);
                 }
-                if !self.selection.is_caret() && cmd.selector == crate::commands::CUT {
+                if !self.selection.is_caret() && matches!(event, Event::Cut(_)) {
                     self.delete_backward(data);
+                    self.reset_cursor_blink(ctx);
                 }
                 ctx.set_handled();
             }
-            Event::Command(cmd) if cmd.selector == RESET_BLINK => self.reset_cursor_blink(ctx),
+            Event::Command(cmd) if cmd.is(RESET_BLINK) => {
+                self.reset_cursor_blink(ctx);
+                self.report_ime_area(ctx, &text_layout, data, env);
+            }
+            Event::ImeComposition { text, cursor } => {
+                self.composition = text.clone();
+                self.composition_cursor = *cursor;
+                let display_text = self.display_text(data);
+                let display_layout = self.text_layout(ctx.text(), &display_text, env);
+                self.report_ime_area(ctx, &display_layout, &display_text, env);
+                ctx.invalidate();
+            }
+            Event::ImeCommit(text) => {
+                self.composition.clear();
+                self.insert(data, text);
+                self.reset_cursor_blink(ctx);
+                text_layout = self.text_layout(ctx.text(), &data, env);
+                self.update_hscroll(&text_layout, data);
+                self.report_ime_area(ctx, &text_layout, data, env);
+                ctx.invalidate();
+            }
             Event::Paste(ref item) => {
                 if let Some(string) = item.get_string() {
                     self.insert(data, &string);
@@ -313,11 +450,8 @@ impl Widget<String> for TextBox {
                         }
                         self.reset_cursor_blink(ctx);
                     }
-                    // Tab and shift+tab
-                    k_e if HotKey::new(None, KeyCode::Tab).matches(k_e) => ctx.focus_next(),
-                    k_e if HotKey::new(RawMods::Shift, KeyCode::Tab).matches(k_e) => {
-                        ctx.focus_prev()
-                    }
+                    // Tab and shift+tab move focus; the window handles them.
+                    k_e if k_e.key_code == KeyCode::Tab => {}
                     // Actual typing
                     k_e if k_e.key_code.is_printable() => {
                         let incoming_text = k_e.text().unwrap_or("");
@@ -326,19 +460,30 @@ impl Widget<String> for TextBox {
                     }
                     _ => {}
                 }
-                text_layout = self.get_layout(ctx.text(), &data, env);
-                self.update_hscroll(&text_layout);
+                text_layout = self.text_layout(ctx.text(), &data, env);
+                self.update_hscroll(&text_layout, data);
+                self.report_ime_area(ctx, &text_layout, data, env);
                 ctx.invalidate();
             }
             _ => (),
         }
     }
 
-    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _data: &String, _env: &Env) {
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &String, _env: &Env) {
         match event {
             LifeCycle::WidgetAdded => ctx.register_for_focus(),
             // an open question: should we be able to schedule timers here?
             LifeCycle::FocusChanged(true) => ctx.submit_command(RESET_BLINK, ctx.widget_id()),
+            LifeCycle::CollectAccessibility(access) => {
+                access.set_role(Role::TextInput);
+                if !self.placeholder.is_empty() {
+                    access.set_name(self.placeholder.as_str());
+                }
+                // the contents of a password field are never exposed.
+                if !self.password {
+                    access.set_value(data.as_str());
+                }
+            }
             _ => (),
         }
     }
@@ -367,13 +512,16 @@ impl Widget<String> for TextBox {
 
     fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &String, env: &Env) {
         // Guard against changes in data following `event`
-        let content = if data.is_empty() {
-            &self.placeholder
+        let showing_placeholder = data.is_empty() && self.composition.is_empty();
+        let content = if showing_placeholder {
+            self.placeholder.clone()
         } else {
-            data
+            self.selection = self.selection.constrain_to(data);
+            self.display_text(data)
         };
+        let composing = !self.composition.is_empty();
 
-        self.selection = self.selection.constrain_to(content);
+        self.selection = self.selection.constrain_to(&content);
 
         let font_size = env.get(theme::TEXT_SIZE_NORMAL);
         let height = env.get(theme::BORDERED_WIDGET_HEIGHT);
@@ -406,16 +554,20 @@ impl Widget<String> for TextBox {
                 rc.clip(clip_rect);
 
                 // Calculate layout
-                let text_layout = self.get_layout(rc.text(), &content, env);
+                let text_layout = if showing_placeholder {
+                    self.get_layout(rc.text(), &content, env)
+                } else {
+                    self.text_layout(rc.text(), &content, env)
+                };
 
                 // Shift everything inside the clip by the hscroll_offset
                 rc.transform(Affine::translate((-self.hscroll_offset, 0.)));
 
                 // Draw selection rect
-                if !self.selection.is_caret() {
+                if !composing && !self.selection.is_caret() {
                     let (left, right) = (self.selection.min(), self.selection.max());
-                    let left_offset = self.x_for_offset(&text_layout, left);
-                    let right_offset = self.x_for_offset(&text_layout, right);
+                    let left_offset = self.x_for_offset(&text_layout, &content, left);
+                    let right_offset = self.x_for_offset(&text_layout, &content, right);
 
                     let selection_width = right_offset - left_offset;
 
@@ -433,7 +585,7 @@ impl Widget<String> for TextBox {
                 // Layout, measure, and draw text
                 let text_height = font_size * 0.8;
                 let text_pos = Point::new(0.0 + PADDING_LEFT, text_height + PADDING_TOP);
-                let color = if data.is_empty() {
+                let color = if data.is_empty() && !composing {
                     &placeholder_color
                 } else {
                     &text_color
@@ -441,9 +593,19 @@ impl Widget<String> for TextBox {
 
                 rc.draw_text(&text_layout, text_pos, color);
 
+                // Underline the composition
+                if composing {
+                    let start = self.selection.min();
+                    let end = start + self.composition.len();
+                    let y = text_pos.y + 2.;
+                    let x0 = self.x_for_offset(&text_layout, &content, start) + PADDING_LEFT;
+                    let x1 = self.x_for_offset(&text_layout, &content, end) + PADDING_LEFT;
+                    rc.stroke(Line::new((x0, y), (x1, y)), color, 1.);
+                }
+
                 // Paint the cursor if focused and there's no selection
-                if has_focus && self.cursor_on && self.selection.is_caret() {
-                    let cursor_x = self.x_for_offset(&text_layout, self.cursor());
+                if has_focus && self.cursor_on && (composing || self.selection.is_caret()) {
+                    let cursor_x = self.x_for_offset(&text_layout, &content, self.display_cursor());
                     let xy = text_pos + Vec2::new(cursor_x, 2. - font_size);
                     let x2y2 = xy + Vec2::new(0., font_size + 2.);
                     let line = Line::new(xy, x2y2);
@@ -459,6 +621,24 @@ impl Widget<String> for TextBox {
     }
 }
 
+/// `text`, with each grapheme replaced by a bullet.
+fn mask(text: &str) -> String {
+    text.graphemes(true).map(|_| BULLET).collect()
+}
+
+/// The offset in the masked `text` that corresponds to `offset` in `text`.
+fn masked_offset(text: &str, offset: usize) -> usize {
+    text[..offset].graphemes(true).count() * BULLET.len_utf8()
+}
+
+/// The offset in `text` that corresponds to `offset` in the masked `text`.
+fn unmasked_offset(text: &str, offset: usize) -> usize {
+    text.grapheme_indices(true)
+        .nth(offset / BULLET.len_utf8())
+        .map(|(idx, _)| idx)
+        .unwrap_or_else(|| text.len())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -520,4 +700,86 @@ mod tests {
         widget.delete_backward(&mut data);
         assert_eq!(data, String::from(""));
     }
+
+    /// The composition is shown in place of the selection, with the caret
+    /// inside it.
+    #[test]
+    fn composition_replaces_selection() {
+        let mut widget = TextBox::raw();
+        let mut data = "".to_string();
+
+        widget.insert(&mut data, "abcd");
+        widget.selection = Selection::new(1, 3);
+        widget.composition = "かな".to_string();
+        widget.composition_cursor = 3;
+
+        assert_eq!(widget.display_text(&data), "aかなd");
+        assert_eq!(widget.display_cursor(), 4);
+    }
+
+    /// Password mode shows a bullet for each grapheme, and maps offsets
+    /// between the real and the masked text.
+    #[test]
+    fn password_masks_graphemes() {
+        let text = "a\u{006F}\u{0337}हि";
+        assert_eq!(mask(text), "\u{2022}\u{2022}\u{2022}");
+        assert!(!mask(text).contains('a'));
+
+        assert_eq!(masked_offset(text, 0), 0);
+        assert_eq!(masked_offset(text, 1), 3);
+        assert_eq!(masked_offset(text, 4), 6);
+        assert_eq!(masked_offset(text, text.len()), 9);
+        assert_eq!(unmasked_offset(text, 3), 1);
+        assert_eq!(unmasked_offset(text, 6), 4);
+        assert_eq!(unmasked_offset(text, 9), text.len());
+    }
+
+    /// Editing in password mode still changes the real text.
+    #[test]
+    fn password_edits_data() {
+        let mut widget = TextBox::raw();
+        widget.set_password(true);
+        let mut data = "".to_string();
+
+        widget.insert(&mut data, "secret");
+        widget.set_selection(Selection::new(1, 3));
+        assert_eq!(widget.selection().range(), 1..3);
+        widget.delete_backward(&mut data);
+        assert_eq!(data, "sret");
+        widget.delete_backward(&mut data);
+        assert_eq!(data, "ret");
+    }
+
+    /// Copy and cut go to the focused text box, which puts its selection on
+    /// the clipboard.
+    #[test]
+    fn copy_and_cut_selection() {
+        use crate::tests::harness::Harness;
+        use crate::widget::WidgetExt;
+        use crate::{Clipboard, KeyEvent, RawMods, WidgetId};
+
+        let id = WidgetId::next();
+        let widget = TextBox::raw().with_id(id);
+        Harness::create("hello".to_string(), widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            let clipboard = Clipboard::in_memory();
+
+            // with nothing focused, nothing is copied.
+            harness.event(Event::Copy(clipboard.clone()));
+            assert_eq!(clipboard.get_string(), None);
+
+            harness.window_mut().focus = Some(id);
+            for _ in 0..2 {
+                let key = KeyEvent::for_test(RawMods::Shift, "", KeyCode::ArrowRight);
+                harness.event(Event::KeyDown(key));
+            }
+            harness.event(Event::Copy(clipboard.clone()));
+            assert_eq!(clipboard.get_string().as_deref(), Some("he"));
+            assert_eq!(harness.data(), "hello");
+
+            harness.event(Event::Cut(Clipboard::in_memory()));
+            assert_eq!(harness.data(), "llo");
+        });
+    }
 }
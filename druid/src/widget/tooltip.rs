@@ -0,0 +1,207 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A widget that shows a tooltip when the pointer rests on its child.
+
+use std::time::{Duration, Instant};
+
+use crate::kurbo::{Point, Rect, Size, Vec2};
+use crate::piet::{FontBuilder, RenderContext, Text, TextLayout, TextLayoutBuilder};
+use crate::theme;
+use crate::{
+    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    TimerToken, UpdateCtx, Widget, WidgetId,
+};
+
+/// Tooltips are painted after everything else.
+const TOOLTIP_Z_INDEX: u32 = 1_000;
+/// The space between the text and the edge of the tooltip.
+const PADDING: Vec2 = Vec2::new(4., 2.);
+/// How far below the pointer the tooltip is shown.
+const POINTER_GAP: f64 = 16.;
+
+/// A widget that shows a short text near the pointer, once the pointer has
+/// rested on its child for a moment.
+///
+/// The delay is [`theme::TOOLTIP_DELAY`]. The tooltip is hidden when the
+/// pointer moves or leaves the child, or a button is pressed, and it is
+/// moved if it would otherwise extend past the edge of the window. This is
+/// usually created with [`WidgetExt::tooltip`].
+///
+/// [`theme::TOOLTIP_DELAY`]: ../theme/constant.TOOLTIP_DELAY.html
+/// [`WidgetExt::tooltip`]: trait.WidgetExt.html#method.tooltip
+pub struct Tooltip<W> {
+    child: W,
+    text: String,
+    timer: TimerToken,
+    /// The last pointer position, in our coordinate space.
+    pointer: Point,
+    /// The position of our origin in the window.
+    window_origin: Vec2,
+    shown: bool,
+}
+
+impl<W> Tooltip<W> {
+    /// Create a new `Tooltip`, showing `text` over `child`.
+    pub fn new(child: W, text: impl Into<String>) -> Tooltip<W> {
+        Tooltip {
+            child,
+            text: text.into(),
+            timer: TimerToken::INVALID,
+            pointer: Point::ORIGIN,
+            window_origin: Vec2::ZERO,
+            shown: false,
+        }
+    }
+
+    /// Stop waiting to show the tooltip, and hide it if it is shown.
+    ///
+    /// Returns `true` if the tooltip was shown.
+    fn dismiss(&mut self) -> bool {
+        self.timer = TimerToken::INVALID;
+        std::mem::replace(&mut self.shown, false)
+    }
+}
+
+impl<T: Data, W: Widget<T>> Widget<T> for Tooltip<W> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        match event {
+            Event::MouseMoved(_) | Event::MouseDown(_) | Event::Wheel(_) => {
+                if self.dismiss() {
+                    ctx.invalidate();
+                }
+                // wait for the pointer to rest again.
+                match event {
+                    Event::MouseMoved(mouse) if ctx.is_hot() => {
+                        self.pointer = mouse.pos;
+                        self.window_origin = mouse.window_pos - mouse.pos;
+                        let delay = Duration::from_millis(env.get(theme::TOOLTIP_DELAY));
+                        self.timer = ctx.request_timer(Instant::now() + delay);
+                    }
+                    _ => (),
+                }
+            }
+            Event::Timer(token) if *token == self.timer => {
+                self.timer = TimerToken::INVALID;
+                self.shown = true;
+                ctx.invalidate();
+                return;
+            }
+            _ => (),
+        }
+        self.child.event(ctx, event, data, env)
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let LifeCycle::HotChanged(false) = event {
+            if self.dismiss() {
+                ctx.invalidate();
+            }
+        }
+        self.child.lifecycle(ctx, event, data, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        self.child.update(ctx, old_data, data, env)
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        self.child.layout(ctx, bc, data, env)
+    }
+
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &T, env: &Env) {
+        self.child.paint(paint_ctx, data, env);
+        if !self.shown {
+            return;
+        }
+
+        let font_name = env.get(theme::FONT_NAME);
+        let font_size = env.get(theme::TEXT_SIZE_NORMAL);
+        let font = paint_ctx
+            .text()
+            .new_font_by_name(font_name, font_size)
+            .build()
+            .unwrap();
+        let layout = paint_ctx
+            .text()
+            .new_text_layout(&font, &self.text)
+            .build()
+            .unwrap();
+        let size = Size::new(layout.width() + PADDING.x * 2., font_size + PADDING.y * 2.);
+
+        let origin = tooltip_origin(
+            self.pointer + self.window_origin,
+            size,
+            paint_ctx.window_size(),
+        ) - self.window_origin;
+        let rect = Rect::from_origin_size(origin, size);
+        let text_pos = origin + PADDING + Vec2::new(0., font_size * 0.8);
+        let background = env.get(theme::BACKGROUND_LIGHT);
+        let border = env.get(theme::BORDER_LIGHT);
+        let text_color = env.get(theme::LABEL_COLOR);
+
+        paint_ctx.paint_with_z_index(TOOLTIP_Z_INDEX, move |paint_ctx| {
+            paint_ctx.fill(rect, &background);
+            paint_ctx.stroke(rect, &border, 1.);
+            paint_ctx.draw_text(&layout, text_pos, &text_color);
+        });
+    }
+
+    fn id(&self) -> Option<WidgetId> {
+        self.child.id()
+    }
+}
+
+/// Where to show a tooltip of `size`, in window coordinates, for a pointer
+/// at `pointer`.
+///
+/// The tooltip is below and to the right of the pointer, unless that would
+/// put it past the edge of the window; then it is moved left, or shown above
+/// the pointer.
+fn tooltip_origin(pointer: Point, size: Size, window: Size) -> Point {
+    let x = pointer.x.min(window.width - size.width).max(0.);
+    let below = pointer.y + POINTER_GAP;
+    let y = if below + size.height > window.height {
+        (pointer.y - size.height).max(0.)
+    } else {
+        below
+    };
+    Point::new(x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tooltip_stays_in_window() {
+        let window = Size::new(200., 100.);
+        let size = Size::new(50., 20.);
+
+        assert_eq!(
+            tooltip_origin(Point::new(10., 10.), size, window),
+            Point::new(10., 26.)
+        );
+        // near the right edge
+        assert_eq!(
+            tooltip_origin(Point::new(190., 10.), size, window),
+            Point::new(150., 26.)
+        );
+        // near the bottom edge
+        assert_eq!(
+            tooltip_origin(Point::new(10., 90.), size, window),
+            Point::new(10., 70.)
+        );
+    }
+}
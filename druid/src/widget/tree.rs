@@ -0,0 +1,489 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A tree widget, for hierarchical data.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::kurbo::{BezPath, Point, Rect, Size};
+use crate::piet::RenderContext;
+use crate::theme;
+use crate::{
+    BoxConstraints, Data, Env, Event, EventCtx, KeyCode, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, UpdateCtx, Widget, WidgetPod,
+};
+
+/// The default indentation of each level of the tree.
+const DEFAULT_INDENT: f64 = 16.0;
+
+/// Data that forms a tree, for use with [`Tree`].
+///
+/// Each node is the data of one row of the tree; its children are nodes of
+/// the same type.
+///
+/// [`Tree`]: struct.Tree.html
+pub trait TreeNode: Data {
+    /// The number of children of this node.
+    fn children_count(&self) -> usize;
+
+    /// The child at `index`, which is less than `children_count()`.
+    fn get_child(&self, index: usize) -> &Self;
+
+    /// Call `cb` with the child at `index`, which may be changed.
+    fn for_child_mut(&mut self, index: usize, cb: impl FnOnce(&mut Self));
+}
+
+/// The position of a node in a tree: the index of each child on the way
+/// to it from the root.
+type Path = Vec<usize>;
+
+/// A widget for hierarchical data, showing each node as an indented row.
+///
+/// Each row has a toggle that expands or collapses the node, and shows its
+/// children below it. Widgets are only built for the rows that are shown;
+/// the children of a collapsed node have none. Which nodes are expanded is
+/// remembered by their position in the tree, so it survives changes to the
+/// data that don't move nodes around.
+///
+/// Clicking a row selects it. While the tree has focus, the up and down
+/// arrow keys move the selection, and the right and left arrow keys expand
+/// and collapse the selected node, or move to its first child or its parent.
+///
+/// The tree is as tall as its rows; put it in a [`Scroll`] for trees that
+/// may not fit.
+///
+/// [`Scroll`]: struct.Scroll.html
+pub struct Tree<T: TreeNode> {
+    make_row: Box<dyn Fn() -> Box<dyn Widget<T>>>,
+    indent: f64,
+    expanded: HashSet<Path>,
+    selected: Option<Path>,
+    rows: Vec<Row<T>>,
+}
+
+struct Row<T: TreeNode> {
+    path: Path,
+    /// Whether the node has children, and so a toggle.
+    has_children: bool,
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+    /// The whole row, including the indentation and the toggle.
+    rect: Rect,
+}
+
+impl<T: TreeNode> Tree<T> {
+    /// Create a new tree. `make_row` is called whenever a node is shown, to
+    /// build the widget for its row.
+    pub fn new<W: Widget<T> + 'static>(make_row: impl Fn() -> W + 'static) -> Self {
+        Tree {
+            make_row: Box::new(move || Box::new(make_row())),
+            indent: DEFAULT_INDENT,
+            expanded: HashSet::new(),
+            selected: None,
+            rows: Vec::new(),
+        }
+    }
+
+    /// Builder-style method to set the indentation of each level.
+    pub fn indent(mut self, indent: f64) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Builder-style method to expand the root node.
+    pub fn expand_root(mut self) -> Self {
+        self.expanded.insert(Vec::new());
+        self
+    }
+
+    /// The path of the selected node: the index of each child on the way to
+    /// it from the root, which has the empty path.
+    pub fn selected(&self) -> Option<&[usize]> {
+        self.selected.as_deref()
+    }
+
+    /// Rebuild the list of rows from the data and the expanded nodes,
+    /// keeping the widgets of rows that are still shown.
+    ///
+    /// Returns `true` if rows were added.
+    fn build_rows(&mut self, data: &T) -> bool {
+        let mut old: HashMap<Path, Row<T>> = self
+            .rows
+            .drain(..)
+            .map(|row| (row.path.clone(), row))
+            .collect();
+        let mut shown = Vec::new();
+        visible_paths(data, &mut Vec::new(), &self.expanded, &mut shown);
+
+        let mut added = false;
+        for (path, has_children) in shown {
+            let row = match old.remove(&path) {
+                Some(mut row) => {
+                    row.has_children = has_children;
+                    row
+                }
+                None => {
+                    added = true;
+                    Row {
+                        path,
+                        has_children,
+                        child: WidgetPod::new((self.make_row)()),
+                        rect: Rect::ZERO,
+                    }
+                }
+            };
+            self.rows.push(row);
+        }
+
+        if let Some(path) = &self.selected {
+            if !self.rows.iter().any(|row| &row.path == path) {
+                self.selected = None;
+            }
+        }
+        added
+    }
+
+    fn set_expanded(&mut self, ctx: &mut EventCtx, path: &[usize], expanded: bool, data: &T) {
+        if expanded {
+            self.expanded.insert(path.to_vec());
+        } else {
+            self.expanded.remove(path);
+        }
+        if self.build_rows(data) {
+            ctx.children_changed();
+        }
+        ctx.invalidate();
+    }
+
+    fn select(&mut self, ctx: &mut EventCtx, path: Path) {
+        self.selected = Some(path);
+        ctx.invalidate();
+    }
+
+    /// Handle a navigation key, marking it handled if it did anything.
+    fn key_down(&mut self, ctx: &mut EventCtx, key: KeyCode, data: &T) {
+        let index = match &self.selected {
+            Some(path) => self.rows.iter().position(|row| &row.path == path),
+            None => None,
+        };
+        let index = match (index, key) {
+            (Some(index), _) => index,
+            // without a selection, the first key selects the first row.
+            (None, KeyCode::ArrowUp) | (None, KeyCode::ArrowDown) if !self.rows.is_empty() => {
+                self.select(ctx, self.rows[0].path.clone());
+                ctx.set_handled();
+                return;
+            }
+            (None, _) => return,
+        };
+        let row = &self.rows[index];
+        let expanded = self.expanded.contains(&row.path);
+        match key {
+            KeyCode::ArrowUp if index > 0 => {
+                self.select(ctx, self.rows[index - 1].path.clone());
+            }
+            KeyCode::ArrowDown if index + 1 < self.rows.len() => {
+                self.select(ctx, self.rows[index + 1].path.clone());
+            }
+            KeyCode::ArrowRight if row.has_children && !expanded => {
+                let path = row.path.clone();
+                self.set_expanded(ctx, &path, true, data);
+            }
+            KeyCode::ArrowRight if row.has_children => {
+                let mut path = row.path.clone();
+                path.push(0);
+                self.select(ctx, path);
+            }
+            KeyCode::ArrowLeft if expanded => {
+                let path = row.path.clone();
+                self.set_expanded(ctx, &path, false, data);
+            }
+            KeyCode::ArrowLeft if !row.path.is_empty() => {
+                let mut path = row.path.clone();
+                path.pop();
+                self.select(ctx, path);
+            }
+            _ => return,
+        }
+        ctx.set_handled();
+    }
+
+    /// The area of a row's toggle.
+    fn toggle_rect(&self, row: &Row<T>) -> Rect {
+        let x = row.path.len() as f64 * self.indent;
+        Rect::new(x, row.rect.y0, x + self.indent, row.rect.y1)
+    }
+}
+
+/// Collect the paths of the nodes that are shown, in order, along with
+/// whether they have children.
+fn visible_paths<T: TreeNode>(
+    node: &T,
+    path: &mut Path,
+    expanded: &HashSet<Path>,
+    out: &mut Vec<(Path, bool)>,
+) {
+    let count = node.children_count();
+    out.push((path.clone(), count > 0));
+    if count > 0 && expanded.contains(path) {
+        for i in 0..count {
+            path.push(i);
+            visible_paths(node.get_child(i), path, expanded, out);
+            path.pop();
+        }
+    }
+}
+
+/// The node at `path`, if there is one.
+fn node_at<'a, T: TreeNode>(node: &'a T, path: &[usize]) -> Option<&'a T> {
+    match path.split_first() {
+        None => Some(node),
+        Some((&i, rest)) if i < node.children_count() => node_at(node.get_child(i), rest),
+        Some(_) => None,
+    }
+}
+
+/// Call `f` with the node at `path`, which must exist.
+fn with_node_mut<T: TreeNode>(node: &mut T, path: &[usize], f: impl FnOnce(&mut T)) {
+    match path.split_first() {
+        None => f(node),
+        Some((&i, rest)) => node.for_child_mut(i, |child| with_node_mut(child, rest, f)),
+    }
+}
+
+impl<T: TreeNode> Widget<T> for Tree<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        for row in self.rows.iter_mut() {
+            let node = match node_at(data, &row.path) {
+                Some(node) => node,
+                None => continue,
+            };
+            let mut new_node = node.clone();
+            row.child.event(ctx, event, &mut new_node, env);
+            if !new_node.same(node) {
+                with_node_mut(data, &row.path, |node| *node = new_node);
+            }
+        }
+        if ctx.is_handled() {
+            return;
+        }
+
+        match event {
+            Event::MouseDown(mouse) => {
+                let row = match self.rows.iter().find(|row| row.rect.contains(mouse.pos)) {
+                    Some(row) => row,
+                    None => return,
+                };
+                let path = row.path.clone();
+                if row.has_children && self.toggle_rect(row).contains(mouse.pos) {
+                    let expanded = self.expanded.contains(&path);
+                    self.set_expanded(ctx, &path, !expanded, data);
+                } else {
+                    self.select(ctx, path);
+                }
+                ctx.request_focus();
+                ctx.set_handled();
+            }
+            Event::KeyDown(key) if ctx.has_focus() => self.key_down(ctx, key.key_code, data),
+            _ => (),
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        match event {
+            LifeCycle::WidgetAdded => {
+                ctx.register_for_focus();
+                if self.rows.is_empty() {
+                    self.build_rows(data);
+                }
+            }
+            LifeCycle::FocusChanged(_) => ctx.invalidate(),
+            _ => (),
+        }
+
+        for row in self.rows.iter_mut() {
+            if let Some(node) = node_at(data, &row.path) {
+                row.child.lifecycle(ctx, event, node, env);
+            }
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        if !old_data.same(data) && self.build_rows(data) {
+            ctx.children_changed();
+        }
+        for row in self.rows.iter_mut() {
+            if let Some(node) = node_at(data, &row.path) {
+                row.child.update(ctx, node, env);
+            }
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        bc.debug_check("Tree");
+
+        let min_height = env.get(theme::BASIC_WIDGET_HEIGHT);
+        let mut width = if bc.is_width_bounded() {
+            bc.max().width
+        } else {
+            bc.min().width
+        };
+        let mut y = 0.0;
+        for row in self.rows.iter_mut() {
+            let node = match node_at(data, &row.path) {
+                Some(node) => node,
+                None => continue,
+            };
+            let x = (row.path.len() + 1) as f64 * self.indent;
+            let row_bc = BoxConstraints::new(
+                Size::ZERO,
+                Size::new((bc.max().width - x).max(0.0), f64::INFINITY),
+            );
+            let size = row.child.layout(ctx, &row_bc, node, env);
+            let height = size.height.max(min_height);
+            let origin = Point::new(x, y + (height - size.height) / 2.0);
+            row.child
+                .set_layout_rect(Rect::from_origin_size(origin, size));
+            row.rect = Rect::new(0.0, y, 0.0, y + height);
+            width = width.max(x + size.width);
+            y += height;
+        }
+        let size = bc.constrain(Size::new(width, y));
+        for row in self.rows.iter_mut() {
+            row.rect.x1 = size.width;
+        }
+        size
+    }
+
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &T, env: &Env) {
+        let selection_color = if paint_ctx.has_focus() {
+            env.get(theme::PRIMARY_DARK)
+        } else {
+            env.get(theme::BACKGROUND_LIGHT)
+        };
+        let toggle_color = env.get(theme::FOREGROUND_DARK);
+        for row in self.rows.iter() {
+            if Some(&row.path) == self.selected.as_ref() {
+                paint_ctx.fill(row.rect, &selection_color);
+            }
+            if row.has_children {
+                let toggle = self.toggle_rect(row);
+                let center = toggle.center();
+                let r = (self.indent / 4.0).min(toggle.height() / 2.0);
+                let mut arrow = BezPath::new();
+                if self.expanded.contains(&row.path) {
+                    arrow.move_to((center.x - r, center.y - r / 2.0));
+                    arrow.line_to((center.x + r, center.y - r / 2.0));
+                    arrow.line_to((center.x, center.y + r / 2.0));
+                } else {
+                    arrow.move_to((center.x - r / 2.0, center.y - r));
+                    arrow.line_to((center.x + r / 2.0, center.y));
+                    arrow.line_to((center.x - r / 2.0, center.y + r));
+                }
+                arrow.close_path();
+                paint_ctx.fill(arrow, &toggle_color);
+            }
+        }
+
+        for row in self.rows.iter_mut() {
+            if let Some(node) = node_at(data, &row.path) {
+                row.child.paint_with_offset(paint_ctx, node, env);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::tests::harness::Harness;
+    use crate::tests::helpers::left_mouse;
+    use crate::widget::{Align, SizedBox, WidgetExt};
+    use crate::{KeyEvent, RawMods, WidgetId};
+
+    #[derive(Clone)]
+    struct Node(Arc<Vec<Node>>);
+
+    impl Data for Node {
+        fn same(&self, other: &Self) -> bool {
+            self.0.same(&other.0)
+        }
+    }
+
+    impl Node {
+        fn new(children: Vec<Node>) -> Self {
+            Node(Arc::new(children))
+        }
+    }
+
+    impl TreeNode for Node {
+        fn children_count(&self) -> usize {
+            self.0.len()
+        }
+
+        fn get_child(&self, index: usize) -> &Node {
+            &self.0[index]
+        }
+
+        fn for_child_mut(&mut self, index: usize, cb: impl FnOnce(&mut Node)) {
+            cb(&mut Arc::make_mut(&mut self.0)[index])
+        }
+    }
+
+    fn click(harness: &mut Harness<Node>, x: f64, y: f64) {
+        harness.event(Event::MouseDown(left_mouse(x, y, 1)));
+    }
+
+    fn key(harness: &mut Harness<Node>, code: KeyCode) {
+        let event = KeyEvent::for_test(RawMods::None, "", code);
+        harness.event(Event::KeyDown(event));
+    }
+
+    #[test]
+    fn expand_collapse_and_navigate() {
+        // the root has two children, and the second of them has one.
+        let data = Node::new(vec![Node::new(vec![]), Node::new(vec![Node::new(vec![])])]);
+        let id = WidgetId::next();
+        let tree = Tree::new(|| SizedBox::empty().height(20.)).with_id(id);
+        let widget = Align::new(crate::piet::UnitPoint::TOP_LEFT, tree);
+
+        Harness::create(data, widget, |harness| {
+            harness.send_initial_events();
+            harness.window_mut().focus = Some(id);
+            let height = |harness: &mut Harness<Node>| {
+                harness.just_layout();
+                harness.get_state(id).unwrap().layout_rect.height()
+            };
+            assert_eq!(height(harness), 20.);
+
+            // the root's toggle shows its children.
+            click(harness, 8., 10.);
+            assert_eq!(height(harness), 60.);
+
+            // select the second child, and expand it, and collapse it again.
+            click(harness, 100., 50.);
+            key(harness, KeyCode::ArrowRight);
+            assert_eq!(height(harness), 80.);
+            key(harness, KeyCode::ArrowLeft);
+            assert_eq!(height(harness), 60.);
+
+            // up to the root, which collapses.
+            key(harness, KeyCode::ArrowUp);
+            key(harness, KeyCode::ArrowUp);
+            key(harness, KeyCode::ArrowLeft);
+            assert_eq!(height(harness), 20.);
+        });
+    }
+}
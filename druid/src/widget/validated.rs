@@ -0,0 +1,226 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A text box that validates its contents.
+
+use std::fmt::Display;
+
+use crate::kurbo::{Point, RoundedRect, Size};
+use crate::piet::{FontBuilder, RenderContext, Text, TextLayoutBuilder};
+use crate::theme;
+use crate::widget::TextBox;
+use crate::{
+    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    UpdateCtx, Widget,
+};
+
+type ValidatorFn<T> = dyn Fn(&str) -> Result<T, String>;
+
+/// A [`TextBox`] for a value that is parsed from, and checked against, the
+/// text.
+///
+/// Each edit runs the validator on the text. If it returns `Ok`, the value
+/// is stored in the data; if it returns `Err`, the data keeps the last
+/// valid value, and the message is shown below the text box, which is
+/// outlined in [`theme::ERROR_COLOR`]. A change to the data from elsewhere
+/// replaces the text, and clears the error.
+///
+/// [`TextBox`]: struct.TextBox.html
+/// [`theme::ERROR_COLOR`]: ../theme/constant.ERROR_COLOR.html
+pub struct ValidatedTextBox<T> {
+    textbox: TextBox,
+    validator: Box<ValidatorFn<T>>,
+    text: String,
+    error: Option<String>,
+    /// The size of the text box, without the error message.
+    textbox_size: Size,
+}
+
+impl<T: Data + Display> ValidatedTextBox<T> {
+    /// Create a text box that stores the values `validator` accepts.
+    ///
+    /// The text is initially the data's `Display` representation.
+    pub fn new(validator: impl Fn(&str) -> Result<T, String> + 'static) -> Self {
+        ValidatedTextBox {
+            textbox: TextBox::raw(),
+            validator: Box::new(validator),
+            text: String::new(),
+            error: None,
+            textbox_size: Size::ZERO,
+        }
+    }
+
+    /// Builder-style method to set the text shown when the text box is empty.
+    pub fn with_placeholder(mut self, placeholder: impl Into<String>) -> Self {
+        self.textbox.set_placeholder(placeholder);
+        self
+    }
+
+    /// The message of the validator for the current text, if it was rejected.
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// The height of the line that shows the error message.
+    fn error_height(&self, env: &Env) -> f64 {
+        match self.error {
+            Some(_) => env.get(theme::TEXT_SIZE_NORMAL) * 1.4,
+            None => 0.,
+        }
+    }
+}
+
+impl<T: Data + Display> Widget<T> for ValidatedTextBox<T> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        let old_text = self.text.clone();
+        self.textbox.event(ctx, event, &mut self.text, env);
+        if self.text == old_text {
+            return;
+        }
+
+        let error = match (self.validator)(&self.text) {
+            Ok(value) => {
+                if !value.same(data) {
+                    *data = value;
+                }
+                None
+            }
+            Err(message) => Some(message),
+        };
+        if error != self.error {
+            self.error = error;
+            ctx.invalidate();
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            self.text = data.to_string();
+        }
+        self.textbox.lifecycle(ctx, event, &self.text, env)
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, old_data: &T, data: &T, env: &Env) {
+        if old_data.same(data) {
+            return;
+        }
+        // our own edits already match the text; don't reformat it under
+        // the caret.
+        let matches_text = match (self.validator)(&self.text) {
+            Ok(value) => value.same(data),
+            Err(_) => false,
+        };
+        if !matches_text {
+            let old_text = std::mem::replace(&mut self.text, data.to_string());
+            if self.error.take().is_some() {
+                ctx.invalidate();
+            }
+            self.textbox.update(ctx, &old_text, &self.text, env);
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, _data: &T, env: &Env) -> Size {
+        let error_height = self.error_height(env);
+        let textbox_bc = bc.shrink((0., error_height));
+        self.textbox_size = self.textbox.layout(ctx, &textbox_bc, &self.text, env);
+        bc.constrain((
+            self.textbox_size.width,
+            self.textbox_size.height + error_height,
+        ))
+    }
+
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, _data: &T, env: &Env) {
+        self.textbox.paint(paint_ctx, &self.text, env);
+
+        let message = match &self.error {
+            Some(message) => message,
+            None => return,
+        };
+        let color = env.get(theme::ERROR_COLOR);
+        let border = RoundedRect::from_origin_size(
+            Point::ORIGIN,
+            Size::new(self.textbox_size.width - 1., self.textbox_size.height).to_vec2(),
+            2.,
+        );
+        paint_ctx.stroke(border, &color, 1.);
+
+        let font_size = env.get(theme::TEXT_SIZE_NORMAL);
+        let font = paint_ctx
+            .text()
+            .new_font_by_name(env.get(theme::FONT_NAME), font_size)
+            .build()
+            .unwrap();
+        let layout = paint_ctx
+            .text()
+            .new_text_layout(&font, message)
+            .build()
+            .unwrap();
+        let origin = Point::new(0., self.textbox_size.height + font_size * 1.1);
+        paint_ctx.draw_text(&layout, origin, &color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::harness::Harness;
+    use crate::widget::WidgetExt;
+    use crate::{KeyCode, KeyEvent, RawMods, WidgetId};
+
+    fn key(text: &'static str, code: KeyCode) -> Event {
+        Event::KeyDown(KeyEvent::for_test(RawMods::None, text, code))
+    }
+
+    fn parse_even(text: &str) -> Result<u32, String> {
+        match text.parse::<u32>() {
+            Ok(n) if n % 2 == 0 => Ok(n),
+            Ok(_) => Err("must be even".into()),
+            Err(_) => Err("not a number".into()),
+        }
+    }
+
+    #[test]
+    fn keeps_last_valid_value() {
+        let id = WidgetId::next();
+        let widget = ValidatedTextBox::new(parse_even).with_id(id).center();
+        Harness::create(2u32, widget, |harness| {
+            harness.send_initial_events();
+            harness.just_layout();
+            harness.window_mut().focus = Some(id);
+            let shows_error = |harness: &mut Harness<u32>| {
+                harness.just_layout();
+                harness.get_state(id).unwrap().layout_rect.height() > 24.
+            };
+
+            // the caret starts at the front: "12" is valid, "1x2" is not.
+            harness.event(key("1", KeyCode::Key1));
+            assert_eq!(*harness.data(), 12);
+            assert!(!shows_error(harness));
+            harness.event(key("x", KeyCode::KeyX));
+            assert_eq!(*harness.data(), 12);
+            assert!(shows_error(harness));
+
+            // a change from elsewhere replaces the text.
+            harness.edit_data(|data| *data = 8);
+            assert!(!shows_error(harness));
+            harness.event(key("3", KeyCode::Key3));
+            assert_eq!(*harness.data(), 8);
+            assert!(shows_error(harness));
+            harness.event(key("", KeyCode::Backspace));
+            assert!(!shows_error(harness));
+            harness.event(key("6", KeyCode::Key6));
+            assert_eq!(*harness.data(), 86);
+        });
+    }
+}
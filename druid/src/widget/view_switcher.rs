@@ -0,0 +1,120 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A widget that rebuilds its child when the data selects a different view.
+
+use crate::kurbo::{Point, Rect, Size};
+use crate::{
+    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    UpdateCtx, Widget, WidgetPod,
+};
+
+type ChildPicker<T, U> = Box<dyn Fn(&T, &Env) -> U>;
+type ChildBuilder<T, U> = Box<dyn Fn(&U, &T) -> Box<dyn Widget<T>>>;
+
+/// A widget that switches between child views, building them on demand.
+///
+/// Unlike [`Either`], which keeps both of its children alive, a
+/// `ViewSwitcher` only has one child at a time. Whenever the `child_picker`
+/// closure returns a value that is not equal to the previous one, the old
+/// child is dropped and `child_builder` is called to build a new one, which
+/// receives [`LifeCycle::WidgetAdded`] before it sees any other event.
+///
+/// [`Either`]: struct.Either.html
+/// [`LifeCycle::WidgetAdded`]: ../enum.LifeCycle.html#variant.WidgetAdded
+pub struct ViewSwitcher<T: Data, U: PartialEq> {
+    child_picker: ChildPicker<T, U>,
+    child_builder: ChildBuilder<T, U>,
+    active_child: Option<WidgetPod<T, Box<dyn Widget<T>>>>,
+    active_child_id: Option<U>,
+}
+
+impl<T: Data, U: PartialEq> ViewSwitcher<T, U> {
+    /// Create a new `ViewSwitcher`.
+    ///
+    /// `child_picker` is called with the data whenever it changes, and
+    /// `child_builder` is called to build a child whenever the picked value
+    /// changes.
+    pub fn new(
+        child_picker: impl Fn(&T, &Env) -> U + 'static,
+        child_builder: impl Fn(&U, &T) -> Box<dyn Widget<T>> + 'static,
+    ) -> Self {
+        ViewSwitcher {
+            child_picker: Box::new(child_picker),
+            child_builder: Box::new(child_builder),
+            active_child: None,
+            active_child_id: None,
+        }
+    }
+
+    /// Build a new child if the picked value has changed.
+    ///
+    /// Returns `true` if a new child was built.
+    fn pick_child(&mut self, data: &T, env: &Env) -> bool {
+        let child_id = (self.child_picker)(data, env);
+        if self.active_child_id.as_ref() == Some(&child_id) {
+            return false;
+        }
+        let child = (self.child_builder)(&child_id, data);
+        self.active_child = Some(WidgetPod::new(child));
+        self.active_child_id = Some(child_id);
+        true
+    }
+}
+
+impl<T: Data, U: PartialEq> Widget<T> for ViewSwitcher<T, U> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut T, env: &Env) {
+        if let Some(child) = self.active_child.as_mut() {
+            child.event(ctx, event, data, env);
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, data: &T, env: &Env) {
+        if let LifeCycle::WidgetAdded = event {
+            if self.active_child.is_none() {
+                self.pick_child(data, env);
+            }
+        }
+        if let Some(child) = self.active_child.as_mut() {
+            child.lifecycle(ctx, event, data, env);
+        }
+    }
+
+    fn update(&mut self, ctx: &mut UpdateCtx, _old_data: &T, data: &T, env: &Env) {
+        if self.pick_child(data, env) {
+            ctx.children_changed();
+            ctx.invalidate();
+        } else if let Some(child) = self.active_child.as_mut() {
+            child.update(ctx, data, env);
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, data: &T, env: &Env) -> Size {
+        match self.active_child.as_mut() {
+            Some(child) => {
+                let size = child.layout(ctx, bc, data, env);
+                child.set_layout_rect(Rect::from_origin_size(Point::ORIGIN, size));
+                ctx.set_paint_insets(child.paint_insets());
+                size
+            }
+            None => bc.max(),
+        }
+    }
+
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &T, env: &Env) {
+        if let Some(child) = self.active_child.as_mut() {
+            child.paint(paint_ctx, data, env);
+        }
+    }
+}
@@ -0,0 +1,330 @@
+// Copyright 2020 The xi-editor Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A list that only builds the rows that are visible.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use log::error;
+
+use crate::kurbo::{Affine, Point, Rect, Size, Vec2};
+use crate::piet::RenderContext;
+use crate::{
+    BoxConstraints, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    UpdateCtx, Widget, WidgetPod,
+};
+
+/// The number of rows built on either side of the viewport, by default.
+const DEFAULT_OVERSCAN: usize = 4;
+
+/// Returns the key of an item, given the item and its index.
+type KeyFn<T, K> = Box<dyn Fn(&T, usize) -> K>;
+
+/// A scrolling list for large collections of items.
+///
+/// Unlike [`List`], which builds a child widget for every item, a
+/// `VirtualList` only builds children for the rows that are visible in its
+/// viewport, plus a few rows of `overscan` on either side. As the list is
+/// scrolled, the widgets of rows that move out of view are reused for the
+/// rows that move into it. Every row has the same height.
+///
+/// Rows are matched to their widgets by key. By default an item's key is
+/// its index; if items can be inserted, removed or reordered, use
+/// [`with_key`] so that a row keeps its widget, and any state in it, for
+/// as long as it stays near the viewport. A reused widget receives
+/// [`LifeCycle::WidgetAdded`] again, and should reset any state it keeps
+/// for its item when it does.
+///
+/// The list scrolls itself, so it should not be put inside a [`Scroll`].
+///
+/// [`List`]: struct.List.html
+/// [`Scroll`]: struct.Scroll.html
+/// [`with_key`]: #method.with_key
+/// [`LifeCycle::WidgetAdded`]: ../enum.LifeCycle.html#variant.WidgetAdded
+pub struct VirtualList<T: Data, K = usize> {
+    make_child: Box<dyn Fn() -> Box<dyn Widget<T>>>,
+    key: KeyFn<T, K>,
+    row_height: f64,
+    overscan: usize,
+    scroll_offset: f64,
+    viewport: Size,
+    rows: Vec<Row<T, K>>,
+    /// Widgets of rows that went out of view, ready to be reused.
+    spare: Vec<Box<dyn Widget<T>>>,
+}
+
+struct Row<T: Data, K> {
+    index: usize,
+    key: K,
+    child: WidgetPod<T, Box<dyn Widget<T>>>,
+}
+
+impl<T: Data> VirtualList<T> {
+    /// Create a new list with rows of `row_height`.
+    ///
+    /// `make_child` is called whenever a row needs a widget and none can be
+    /// reused.
+    pub fn new<W: Widget<T> + 'static>(
+        row_height: f64,
+        make_child: impl Fn() -> W + 'static,
+    ) -> Self {
+        VirtualList {
+            make_child: Box::new(move || Box::new(make_child())),
+            key: Box::new(|_, idx| idx),
+            row_height,
+            overscan: DEFAULT_OVERSCAN,
+            scroll_offset: 0.0,
+            viewport: Size::ZERO,
+            rows: Vec::new(),
+            spare: Vec::new(),
+        }
+    }
+}
+
+impl<T: Data, K: Hash + Eq + Clone> VirtualList<T, K> {
+    /// Builder-style method to match rows to widgets by the key `key`
+    /// returns for their item, instead of by index.
+    pub fn with_key<K2: Hash + Eq + Clone>(
+        self,
+        key: impl Fn(&T) -> K2 + 'static,
+    ) -> VirtualList<T, K2> {
+        VirtualList {
+            make_child: self.make_child,
+            key: Box::new(move |item, _| key(item)),
+            row_height: self.row_height,
+            overscan: self.overscan,
+            scroll_offset: self.scroll_offset,
+            viewport: self.viewport,
+            rows: Vec::new(),
+            spare: Vec::new(),
+        }
+    }
+
+    /// Builder-style method to set the number of rows built beyond either
+    /// edge of the viewport.
+    pub fn overscan(mut self, rows: usize) -> Self {
+        self.overscan = rows;
+        self
+    }
+
+    /// The current scroll offset, from the top of the first row.
+    pub fn offset(&self) -> f64 {
+        self.scroll_offset
+    }
+
+    /// The indices of the rows that should currently have widgets.
+    fn wanted_rows(&self, len: usize) -> (usize, usize) {
+        if self.row_height <= 0.0 {
+            return (0, 0);
+        }
+        let first = (self.scroll_offset / self.row_height).floor() as usize;
+        let last = ((self.scroll_offset + self.viewport.height) / self.row_height).ceil() as usize;
+        let start = first.saturating_sub(self.overscan).min(len);
+        let end = (last + self.overscan).min(len);
+        (start, end)
+    }
+
+    /// Make sure that exactly the wanted rows have widgets, reusing the
+    /// widgets of rows that are no longer wanted.
+    ///
+    /// Returns `true` if any widgets were added.
+    fn build_rows(&mut self, data: &Arc<Vec<T>>) -> bool {
+        let (start, end) = self.wanted_rows(data.len());
+        let mut old: HashMap<K, Row<T, K>> = self
+            .rows
+            .drain(..)
+            .map(|row| (row.key.clone(), row))
+            .collect();
+
+        let wanted: Vec<(usize, K)> = (start..end)
+            .map(|index| (index, (self.key)(&data[index], index)))
+            .collect();
+        let mut kept: HashMap<K, Row<T, K>> = HashMap::new();
+        for (_, key) in &wanted {
+            if let Some(row) = old.remove(key) {
+                kept.insert(key.clone(), row);
+            }
+        }
+        // free the widgets of the rows that are going away first, so that
+        // the new rows can reuse them.
+        self.spare
+            .extend(old.into_values().map(|row| row.child.into_inner()));
+
+        let mut added = false;
+        for (index, key) in wanted {
+            let row = match kept.remove(&key) {
+                Some(mut row) => {
+                    row.index = index;
+                    row
+                }
+                None => {
+                    added = true;
+                    let widget = self.spare.pop().unwrap_or_else(|| (self.make_child)());
+                    Row {
+                        index,
+                        key,
+                        child: WidgetPod::new(widget),
+                    }
+                }
+            };
+            self.rows.push(row);
+        }
+        added
+    }
+
+    /// Keep the scroll offset within the content.
+    fn clamp_offset(&mut self, len: usize) {
+        let content_height = len as f64 * self.row_height;
+        let max = (content_height - self.viewport.height).max(0.0);
+        self.scroll_offset = self.scroll_offset.min(max).max(0.0);
+    }
+}
+
+impl<T: Data, K: Hash + Eq + Clone> Widget<Arc<Vec<T>>> for VirtualList<T, K> {
+    fn event(&mut self, ctx: &mut EventCtx, event: &Event, data: &mut Arc<Vec<T>>, env: &Env) {
+        let offset = Vec2::new(0.0, self.scroll_offset);
+        let viewport = Rect::from_origin_size(Point::ORIGIN, self.viewport);
+        if let Some(child_event) = event.transform_scroll(offset, viewport) {
+            let window_origin = ctx.window_origin;
+            ctx.window_origin -= offset;
+            for row in self.rows.iter_mut() {
+                let item = match data.get(row.index) {
+                    Some(item) => item,
+                    None => continue,
+                };
+                let mut new_item = item.clone();
+                row.child.event(ctx, &child_event, &mut new_item, env);
+                if !new_item.same(item) {
+                    Arc::make_mut(data)[row.index] = new_item;
+                }
+            }
+            ctx.window_origin = window_origin;
+        }
+
+        if !ctx.is_handled() {
+            if let Event::Wheel(wheel) = event {
+                self.scroll_offset += wheel.pixel_delta(self.viewport, env).y;
+                self.clamp_offset(data.len());
+                if (self.scroll_offset - offset.y).abs() > 1e-12 {
+                    ctx.invalidate();
+                    ctx.set_handled();
+                }
+            }
+        }
+    }
+
+    fn lifecycle(
+        &mut self,
+        ctx: &mut LifeCycleCtx,
+        event: &LifeCycle,
+        data: &Arc<Vec<T>>,
+        env: &Env,
+    ) {
+        let window_origin = ctx.window_origin;
+        ctx.window_origin.y -= self.scroll_offset;
+        for row in self.rows.iter_mut() {
+            if let Some(item) = data.get(row.index) {
+                row.child.lifecycle(ctx, event, item, env);
+            }
+        }
+        ctx.window_origin = window_origin;
+    }
+
+    fn update(
+        &mut self,
+        ctx: &mut UpdateCtx,
+        old_data: &Arc<Vec<T>>,
+        data: &Arc<Vec<T>>,
+        env: &Env,
+    ) {
+        if self.build_rows(data) {
+            ctx.children_changed();
+        }
+        let window_origin = ctx.window_origin;
+        ctx.window_origin.y -= self.scroll_offset;
+        for row in self.rows.iter_mut() {
+            row.child.update(ctx, &data[row.index], env);
+        }
+        ctx.window_origin = window_origin;
+        if old_data.len() != data.len() {
+            ctx.invalidate();
+        }
+    }
+
+    fn layout(
+        &mut self,
+        ctx: &mut LayoutCtx,
+        bc: &BoxConstraints,
+        data: &Arc<Vec<T>>,
+        env: &Env,
+    ) -> Size {
+        bc.debug_check("VirtualList");
+
+        let content_height = data.len() as f64 * self.row_height;
+        let size = Size::new(
+            if bc.is_width_bounded() {
+                bc.max().width
+            } else {
+                bc.min().width
+            },
+            if bc.is_height_bounded() {
+                bc.max().height
+            } else {
+                content_height
+            },
+        );
+        let size = bc.constrain(size);
+        self.viewport = size;
+        self.clamp_offset(data.len());
+
+        if self.build_rows(data) {
+            ctx.children_changed();
+        }
+
+        let row_bc = BoxConstraints::tight(Size::new(size.width, self.row_height));
+        for row in self.rows.iter_mut() {
+            let item = &data[row.index];
+            let row_size = row.child.layout(ctx, &row_bc, item, env);
+            let origin = Point::new(0.0, row.index as f64 * self.row_height);
+            row.child
+                .set_layout_rect(Rect::from_origin_size(origin, row_size));
+        }
+        size
+    }
+
+    fn paint(&mut self, paint_ctx: &mut PaintCtx, data: &Arc<Vec<T>>, env: &Env) {
+        if let Err(e) = paint_ctx.save() {
+            error!("saving render context failed: {:?}", e);
+            return;
+        }
+        let viewport = Rect::from_origin_size(Point::ORIGIN, paint_ctx.size());
+        paint_ctx.clip(viewport);
+        paint_ctx.transform(Affine::translate((0.0, -self.scroll_offset)));
+
+        let visible = viewport.with_origin((0.0, self.scroll_offset));
+        paint_ctx.with_child_ctx(visible, |ctx| {
+            for row in self.rows.iter_mut() {
+                if let Some(item) = data.get(row.index) {
+                    row.child.paint_with_offset(ctx, item, env);
+                }
+            }
+        });
+
+        if let Err(e) = paint_ctx.restore() {
+            error!("restoring render context failed: {:?}", e);
+        }
+    }
+}
@@ -17,10 +17,41 @@
 use crate::kurbo::Insets;
 use crate::piet::{PaintBrush, UnitPoint};
 
-use super::{Align, Container, EnvScope, IdentityWrapper, Padding, Parse, SizedBox, WidgetId};
+use super::{
+    Align, BackgroundBrush, Container, Controller, ControllerHost, EnvScope, IdentityWrapper,
+    Padding, Parse, SizedBox, Tooltip, WidgetId,
+};
 use crate::{Data, Env, Lens, LensWrap, Widget};
 
 /// A trait that provides extra methods for combining `Widget`s.
+///
+/// Each method wraps the widget in another one, such as [`Padding`],
+/// [`Container`], [`SizedBox`], [`Align`] or [`LensWrap`], so that a tree can
+/// be built up in a single chain of calls.
+///
+/// # Examples
+///
+/// ```
+/// # use druid::widget::{Label, WidgetExt};
+/// # use druid::{Color, Data, Lens, Widget};
+/// #[derive(Clone, Data, Lens)]
+/// struct AppState {
+///     name: String,
+/// }
+///
+/// let name = Label::new(|data: &String, _env: &_| data.clone())
+///     .padding(8.0)
+///     .border(Color::WHITE, 1.0)
+///     .fix_width(200.0)
+///     .center()
+///     .lens(AppState::name);
+/// ```
+///
+/// [`Padding`]: struct.Padding.html
+/// [`Container`]: struct.Container.html
+/// [`SizedBox`]: struct.SizedBox.html
+/// [`Align`]: struct.Align.html
+/// [`LensWrap`]: ../struct.LensWrap.html
 pub trait WidgetExt<T: Data>: Widget<T> + Sized + 'static {
     /// Wrap this widget in a [`Padding`] widget with the given [`Insets`].
     ///
@@ -93,14 +124,16 @@ pub trait WidgetExt<T: Data>: Widget<T> + Sized + 'static {
         SizedBox::new(self).expand()
     }
 
-    /// Wrap this widget in a [`Container`] using the provided [`PaintBrush`]
-    /// as the background.
+    /// Wrap this widget in a [`Container`] using the provided
+    /// [`BackgroundBrush`] as the background.
     ///
-    /// The `PaintBrush` argument can be any color or gradient.
+    /// The `BackgroundBrush` argument can be any color or gradient, or a
+    /// [`Painter`].
     ///
     /// [`Container`]: struct.Container.html
-    /// [`PaintBrush`]: https://docs.rs/piet/0.0.7/piet/enum.PaintBrush.html
-    fn background(self, brush: impl Into<PaintBrush>) -> Container<T> {
+    /// [`BackgroundBrush`]: enum.BackgroundBrush.html
+    /// [`Painter`]: struct.Painter.html
+    fn background(self, brush: impl Into<BackgroundBrush<T>>) -> Container<T> {
         Container::new(self).background(brush)
     }
 
@@ -117,12 +150,20 @@ pub trait WidgetExt<T: Data>: Widget<T> + Sized + 'static {
     /// Wrap this widget in a [`EnvScope`] widget, modifying the parent
     /// [`Env`] with the provided closure.
     ///
-    /// [`EnvScope`]: struct.Container.html
+    /// [`EnvScope`]: struct.EnvScope.html
     /// [`Env`]: struct.Env.html
-    fn env_scope(self, f: impl Fn(&mut Env, &T) + 'static) -> EnvScope<T, Self> {
+    fn env_scope(self, f: impl FnMut(&mut Env, &T) + 'static) -> EnvScope<T, Self> {
         EnvScope::new(f, self)
     }
 
+    /// Wrap this widget in a [`ControllerHost`] with the provided [`Controller`].
+    ///
+    /// [`ControllerHost`]: struct.ControllerHost.html
+    /// [`Controller`]: trait.Controller.html
+    fn controller<C: Controller<T, Self>>(self, controller: C) -> ControllerHost<Self, C> {
+        ControllerHost::new(self, controller)
+    }
+
     /// Wrap this widget in a [`LensWrap`] widget for the provided [`Lens`].
     ///
     ///
@@ -153,6 +194,14 @@ pub trait WidgetExt<T: Data>: Widget<T> + Sized + 'static {
         IdentityWrapper::wrap(self, id)
     }
 
+    /// Wrap this widget in a [`Tooltip`], which shows `text` when the
+    /// pointer rests on it.
+    ///
+    /// [`Tooltip`]: struct.Tooltip.html
+    fn tooltip(self, text: impl Into<String>) -> Tooltip<Self> {
+        Tooltip::new(self, text)
+    }
+
     /// Wrap this widget in a `Box`.
     fn boxed(self) -> Box<dyn Widget<T>> {
         Box::new(self)
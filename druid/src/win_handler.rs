@@ -15,35 +15,50 @@
 //! The implementation of the WinHandler trait (druid-shell integration).
 
 use std::any::Any;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, VecDeque};
 use std::rc::Rc;
+use std::time::Instant;
 
 use log::{info, warn};
 
-use crate::kurbo::{Size, Vec2};
+use crate::kurbo::{Point, Rect, Size, Vec2};
 use crate::piet::Piet;
 use crate::shell::{
-    Application, FileDialogOptions, IdleToken, MouseEvent, WinCtx, WinHandler, WindowHandle,
+    Application, FileDialogToken, FileInfo, IdleToken, MouseEvent, PowerEvent, WheelPhase,
+    WheelUnit, WinCtx, WinHandler, WindowHandle,
 };
 
+use crate::app::{EventFilterFn, EventFilterResult, FirstPaintFn};
 use crate::app_delegate::{AppDelegate, DelegateCtx};
+use crate::autosave::Autosave;
 use crate::core::CommandQueue;
+use crate::debounce::Debouncer;
 use crate::ext_event::ExtEventHost;
 use crate::menu::ContextMenu;
+use crate::modal::{ModalResult, Modals};
+use crate::mouse::ClickCounter;
+use crate::retry::{self, Retries};
 use crate::window::{PendingWindow, Window};
 use crate::{
-    Command, Data, Env, Event, KeyEvent, KeyModifiers, MenuDesc, Target, TimerToken, WheelEvent,
-    WindowDesc, WindowId,
+    Command, Data, Env, Event, FileDropEvent, Handled, HotKey, KeyEvent, KeyModifiers, LifeCycle,
+    Target, TimerToken, WheelEvent, WindowId,
 };
 
-use crate::command::sys as sys_cmd;
+use crate::command::{self, sys as sys_cmd, DataType};
 
 pub(crate) const RUN_COMMANDS_TOKEN: IdleToken = IdleToken::new(1);
 
 /// A token we are called back with if an external event was submitted.
 pub(crate) const EXT_EVENT_IDLE_TOKEN: IdleToken = IdleToken::new(2);
 
+/// A token we are called back with to handle the latest mouse move.
+const MOUSE_MOVE_TOKEN: IdleToken = IdleToken::new(3);
+
+/// A token we are called back with to take over the retried and debounced
+/// commands of a closed window.
+const REARM_IDLE_TOKEN: IdleToken = IdleToken::new(4);
+
 /// The struct implements the druid-shell `WinHandler` trait.
 ///
 /// One `DruidHandler` exists per window.
@@ -55,6 +70,10 @@ pub struct DruidHandler<T: Data> {
     app_state: Rc<RefCell<AppState<T>>>,
     /// The id for the current window.
     window_id: WindowId,
+    /// Counts the presses of multi-clicks in this window.
+    clicks: ClickCounter,
+    /// The files being dragged over this window.
+    drag_files: Vec<FileInfo>,
 }
 
 /// State shared by all windows in the UI.
@@ -62,6 +81,32 @@ pub(crate) struct AppState<T: Data> {
     delegate: Option<Box<dyn AppDelegate<T>>>,
     command_queue: CommandQueue,
     ext_event_host: ExtEventHost,
+    /// Commands with debounced selectors, held until their quiet period ends.
+    pub(crate) debouncer: Debouncer,
+    /// Unhandled commands, waiting to be retried.
+    retries: Retries,
+    /// Where the deadlines of timers are measured from.
+    pub(crate) clock: Clock,
+    /// Where, and how often, the delegate's crash recovery state is saved.
+    pub(crate) autosave: Option<Autosave>,
+    /// The modal windows whose nested event loops are running.
+    modals: Modals,
+    /// If the application opted in to `DataVersion`, the function returning
+    /// the version, and the version at the last update.
+    pub(crate) data_version: Option<fn(&T) -> u64>,
+    last_version: Option<u64>,
+    /// Otherwise, the data as of the last update.
+    last_data: Option<T>,
+    /// Run after the first paint, and then cleared.
+    pub(crate) first_paint: Option<Box<FirstPaintFn<T>>>,
+    /// Consulted before any other handling of a window event.
+    pub(crate) event_filter: Option<Box<EventFilterFn>>,
+    /// Whether closing the last window quits the application.
+    pub(crate) quit_on_last_window_close: bool,
+    /// App-wide key bindings, consulted before a key reaches any widget.
+    pub(crate) hotkeys: Vec<(HotKey, Command)>,
+    /// The window that was last told it has focus.
+    focused_window: Option<WindowId>,
     windows: Windows<T>,
     pub(crate) env: Env,
     pub(crate) data: T,
@@ -71,11 +116,14 @@ pub(crate) struct AppState<T: Data> {
 struct Windows<T: Data> {
     pending: HashMap<WindowId, PendingWindow<T>>,
     windows: HashMap<WindowId, Window<T>>,
+    /// The handles of the live windows, for the delegate.
+    handles: HashMap<WindowId, WindowHandle>,
 }
 
 impl<T: Data> Windows<T> {
     fn connect(&mut self, id: WindowId, handle: WindowHandle) {
         if let Some(pending) = self.pending.remove(&id) {
+            self.handles.insert(id, handle.clone());
             let win = pending.into_window(id, handle);
             assert!(self.windows.insert(id, win).is_none(), "duplicate window");
         } else {
@@ -88,18 +136,62 @@ impl<T: Data> Windows<T> {
     }
 
     fn remove(&mut self, id: WindowId) -> Option<WindowHandle> {
+        self.handles.remove(&id);
         self.windows.remove(&id).map(|entry| entry.handle)
     }
 
+    /// Returns `true` if there is a window the user can see, or one that is
+    /// still being created.
+    fn any_visible(&self) -> bool {
+        !self.pending.is_empty() || self.windows.values().any(|win| !win.hidden)
+    }
+
+    fn contains(&self, id: WindowId) -> bool {
+        self.windows.contains_key(&id) || self.pending.contains_key(&id)
+    }
+
     fn iter_mut(&mut self) -> impl Iterator<Item = &'_ mut Window<T>> {
         self.windows.values_mut()
     }
 
+    fn get(&self, id: WindowId) -> Option<&Window<T>> {
+        self.windows.get(&id)
+    }
+
     fn get_mut(&mut self, id: WindowId) -> Option<&mut Window<T>> {
         self.windows.get_mut(&id)
     }
 }
 
+/// Where the current time comes from.
+///
+/// This is the system clock, except in tests, which move a mock clock forward
+/// by hand.
+#[derive(Clone, Default)]
+pub(crate) struct Clock(Option<Rc<Cell<Instant>>>);
+
+impl Clock {
+    /// A clock that stands still until it is advanced, starting now.
+    #[cfg(test)]
+    pub(crate) fn mock() -> Self {
+        Clock(Some(Rc::new(Cell::new(Instant::now()))))
+    }
+
+    pub(crate) fn now(&self) -> Instant {
+        match &self.0 {
+            Some(now) => now.get(),
+            None => Instant::now(),
+        }
+    }
+
+    /// Move a mock clock forward by `duration`.
+    #[cfg(test)]
+    pub(crate) fn advance(&self, duration: std::time::Duration) {
+        let now = self.0.as_ref().expect("only a mock clock can be advanced");
+        now.set(now.get() + duration);
+    }
+}
+
 impl<T: Data> AppState<T> {
     pub(crate) fn new(
         data: T,
@@ -111,17 +203,42 @@ impl<T: Data> AppState<T> {
             delegate,
             command_queue: VecDeque::new(),
             ext_event_host,
+            debouncer: Debouncer::default(),
+            retries: Retries::default(),
+            clock: Clock::default(),
+            autosave: None,
+            modals: Modals::default(),
+            data_version: None,
+            last_version: None,
+            last_data: None,
+            first_paint: None,
+            event_filter: None,
+            quit_on_last_window_close: false,
+            hotkeys: Vec::new(),
+            focused_window: None,
             data,
             env,
             windows: Windows::default(),
         }))
     }
 
-    fn get_menu_cmd(&self, window_id: WindowId, cmd_id: u32) -> Option<Command> {
+    fn window_handle(&self, window_id: WindowId) -> Option<WindowHandle> {
         self.windows
             .windows
             .get(&window_id)
-            .and_then(|w| w.get_menu_cmd(cmd_id))
+            .map(|w| w.handle.clone())
+    }
+
+    /// Choose the menu item with this id: apply any change it makes to the
+    /// data, and return its command.
+    fn activate_menu_item(&mut self, window_id: WindowId, cmd_id: u32) -> Option<Command> {
+        let item = self
+            .windows
+            .windows
+            .get(&window_id)?
+            .get_menu_item(cmd_id)?;
+        item.activate(&mut self.data, &self.env);
+        Some(item.command().clone())
     }
 
     /// A helper fn for setting up the `DelegateCtx`. Takes a closure with
@@ -134,13 +251,26 @@ impl<T: Data> AppState<T> {
         let AppState {
             ref mut delegate,
             ref mut command_queue,
+            windows:
+                Windows {
+                    ref mut windows,
+                    ref handles,
+                    ..
+                },
             ref mut data,
             ref env,
+            ref clock,
+            focused_window,
             ..
-        } = self;
+        } = *self;
         let mut ctx = DelegateCtx {
             source_id: id,
+            data_type: DataType::of::<T>(),
+            focused_window,
             command_queue,
+            interval_timers: windows.get_mut(&id).map(|win| &mut win.interval_timers),
+            window_handles: handles,
+            now: clock.now(),
         };
         if let Some(delegate) = delegate {
             Some(f(delegate, data, env, &mut ctx))
@@ -149,6 +279,44 @@ impl<T: Data> AppState<T> {
         }
     }
 
+    /// Run the launcher's event filter, if any; returns `None` if the event
+    /// was dropped.
+    fn filter_event(&mut self, id: WindowId, event: Event) -> Option<Event> {
+        let filter = match self.event_filter.as_mut() {
+            Some(filter) => filter,
+            None => return Some(event),
+        };
+        match filter(&event, id) {
+            EventFilterResult::Pass => Some(event),
+            EventFilterResult::Drop => None,
+            EventFilterResult::Replace(event) => Some(event),
+        }
+    }
+
+    /// The command bound to a key press in a window, if any.
+    ///
+    /// A bound key wins over the widget tree, except that plain typing is
+    /// left alone while a widget has focus, so that a text box still
+    /// receives it.
+    fn hotkey_command(&self, id: WindowId, key: &KeyEvent) -> Option<Command> {
+        let (_, cmd) = self
+            .hotkeys
+            .iter()
+            .find(|(hotkey, _)| hotkey.matches(key))?;
+        let mods = key.mods;
+        let printable = match key.text() {
+            Some(text) => !text.chars().any(char::is_control),
+            None => false,
+        };
+        let typing = printable && !(mods.ctrl || mods.alt || mods.meta);
+        let focused = self.windows.get(id).and_then(|win| win.focus).is_some();
+        if typing && focused {
+            None
+        } else {
+            Some(cmd.clone())
+        }
+    }
+
     fn delegate_event(&mut self, id: WindowId, event: Event) -> Option<Event> {
         if self.delegate.is_some() {
             self.with_delegate(id, |del, data, env, ctx| del.event(event, data, env, ctx))
@@ -158,8 +326,34 @@ impl<T: Data> AppState<T> {
         }
     }
 
+    /// Ask the delegate whether a window may be closed by the user.
+    fn window_should_close(&mut self, id: WindowId) -> bool {
+        self.with_delegate(id, |del, data, env, ctx| {
+            del.window_should_close(id, data, env, ctx)
+        })
+        .unwrap_or(true)
+    }
+
+    /// Ask the delegate whether the application may quit.
+    fn application_should_quit(&mut self, id: WindowId) -> bool {
+        self.with_delegate(id, |del, data, env, ctx| {
+            del.application_should_quit(data, env, ctx)
+        })
+        .unwrap_or(true)
+    }
+
+    /// Offer a command to the delegate; returns `true` if it was handled.
+    fn delegate_command(&mut self, id: WindowId, target: Target, cmd: &Command) -> bool {
+        self.with_delegate(id, |del, data, env, ctx| {
+            del.command(target, cmd, data, env, ctx)
+        })
+        .map(Handled::is_handled)
+        .unwrap_or(false)
+    }
+
     fn connect(&mut self, id: WindowId, handle: WindowHandle) {
         self.windows.connect(id, handle);
+        self.ext_event_host.open();
 
         // If the external event host has no handle, it cannot wake us
         // when an event arrives.
@@ -167,23 +361,62 @@ impl<T: Data> AppState<T> {
             self.set_ext_event_idle_handler(id);
         }
 
+        if self.autosave.as_ref().map(|a| a.timer.is_none()) == Some(true) {
+            self.schedule_autosave(id);
+        }
+
         self.with_delegate(id, |del, data, env, ctx| {
             del.window_added(id, data, env, ctx)
         });
     }
 
+    /// The position of the window that has focus, if it is known.
+    pub(crate) fn focused_window_position(&self) -> Option<Point> {
+        self.focused_window
+            .and_then(|id| self.windows.get(id))
+            .and_then(|win| win.position())
+    }
+
     pub(crate) fn add_window(&mut self, id: WindowId, window: PendingWindow<T>) {
         self.windows.add(id, window);
     }
 
     /// Called after this window has been closed by the platform.
     ///
-    /// We clean up resources and notifiy the delegate, if necessary.
-    fn remove_window(&mut self, window_id: WindowId, _ctx: &mut dyn WinCtx) {
+    /// We clean up resources and notifiy the delegate, if necessary. A hidden
+    /// window can't be shown again once the last visible one is gone, so the
+    /// hidden windows are removed as well; their handles are returned, to be
+    /// closed once the app state is no longer borrowed.
+    fn remove_window(&mut self, window_id: WindowId, _ctx: &mut dyn WinCtx) -> Vec<WindowHandle> {
+        self.forget_window(window_id);
+        let mut hidden = Vec::new();
+        if !self.windows.any_visible() {
+            let ids: Vec<WindowId> = self.windows.windows.keys().cloned().collect();
+            hidden.extend(ids.into_iter().filter_map(|id| self.forget_window(id)));
+        }
+
+        // with no window left, external events have nowhere to go.
+        if self.windows.windows.is_empty() {
+            self.ext_event_host.close();
+        }
+        if !self.windows.any_visible() {
+            self.with_delegate(window_id, |del, data, env, ctx| {
+                del.all_windows_closed(data, env, ctx)
+            });
+        }
+        hidden
+    }
+
+    /// Remove a single window and hand its timers and duties to another one.
+    fn forget_window(&mut self, window_id: WindowId) -> Option<WindowHandle> {
         self.with_delegate(window_id, |del, data, env, ctx| {
             del.window_removed(window_id, data, env, ctx)
         });
-        self.windows.remove(window_id);
+        let handle = self.windows.remove(window_id);
+        self.modals.end(window_id, ModalResult::Closed);
+        if self.focused_window == Some(window_id) {
+            self.focused_window = None;
+        }
 
         // if we are closing the window that is currently responsible for
         // waking us when external events arrive, we want to pass that responsibility
@@ -192,8 +425,71 @@ impl<T: Data> AppState<T> {
             self.ext_event_host.handle_window_id = None;
             // find any other live window
             let win_id = self.windows.windows.keys().find(|k| *k != &window_id);
-            if let Some(any_other_window) = win_id.cloned() {
-                self.set_ext_event_idle_handler(any_other_window);
+            match win_id.cloned() {
+                Some(any_other_window) => self.set_ext_event_idle_handler(any_other_window),
+                // the next window to connect will pick up the queue.
+                None => self.ext_event_host.clear_idle(),
+            }
+        }
+
+        // retried and debounced commands wait on timers, which die with their
+        // window too; another window takes them over when it is next idle.
+        let retries = self.retries.window_closed(window_id);
+        let debounced = self.debouncer.window_closed(window_id);
+        if retries || debounced {
+            let windows = &self.windows.windows;
+            if windows.is_empty() {
+                self.retries.clear_unarmed();
+                self.debouncer.clear_unarmed();
+            } else if let Some(mut idle) = windows.values().find_map(|w| w.handle.get_idle_handle())
+            {
+                idle.schedule_idle(REARM_IDLE_TOKEN);
+            }
+        }
+
+        // likewise, the autosave timer dies with its window.
+        let autosave_window = self.autosave.as_ref().and_then(|a| a.timer).map(|t| t.0);
+        if autosave_window == Some(window_id) {
+            let win_id = self.windows.windows.keys().next().cloned();
+            match win_id {
+                Some(any_other_window) => self.schedule_autosave(any_other_window),
+                None => self.autosave.as_mut().unwrap().timer = None,
+            }
+        }
+        handle
+    }
+
+    /// Returns `true` if the application should quit because its last window
+    /// is gone.
+    ///
+    /// Hidden windows don't count. A window that is still being created does,
+    /// so that a delegate can replace the last window from `window_removed`.
+    fn should_quit_after_close(&self) -> bool {
+        self.quit_on_last_window_close && !self.windows.any_visible()
+    }
+
+    /// Start the autosave interval timer on this window.
+    ///
+    /// The timer is armed with the window's other timers, the next time it
+    /// handles an event.
+    fn schedule_autosave(&mut self, id: WindowId) {
+        if let (Some(autosave), Some(win)) = (self.autosave.as_mut(), self.windows.get_mut(id)) {
+            let token = win
+                .interval_timers
+                .request(autosave.interval, self.clock.now());
+            autosave.timer = Some((id, token));
+        }
+    }
+
+    /// Ask the delegate for a snapshot of the state, and write it to the
+    /// recovery file.
+    pub(crate) fn run_autosave(&mut self, id: WindowId) {
+        let bytes = self
+            .with_delegate(id, |del, data, env, _ctx| del.autosave(data, env))
+            .flatten();
+        if let (Some(autosave), Some(bytes)) = (self.autosave.as_ref(), bytes) {
+            if let Err(e) = autosave.write(&bytes) {
+                log::error!("failed to write recovery file {:?}: {}", autosave.path, e);
             }
         }
     }
@@ -218,21 +514,113 @@ impl<T: Data> AppState<T> {
     /// window handle; the platform should close the window, and then call
     /// our handlers `destroy()` method, at which point we can do our cleanup.
     fn request_close_window(&mut self, window_id: WindowId) {
+        if self.hide_window(window_id) {
+            return;
+        }
         if let Some(win) = self.windows.get_mut(window_id) {
             win.handle.close();
         }
     }
 
+    /// Hide a window that hides instead of closing.
+    ///
+    /// Returns `false` if the window should be closed as usual.
+    fn hide_window(&mut self, id: WindowId) -> bool {
+        match self.windows.get_mut(id) {
+            Some(win) if win.hide_on_close => {
+                win.hidden = true;
+                win.handle.set_visible(false);
+                true
+            }
+            _ => false,
+        }
+    }
+
     fn show_window(&mut self, id: WindowId) {
         if let Some(win) = self.windows.get_mut(id) {
+            if win.hidden {
+                // it was not painted while hidden.
+                win.hidden = false;
+                win.handle.set_visible(true);
+                win.handle.invalidate();
+            }
             win.handle.bring_to_front_and_focus();
         }
     }
 
+    /// Replace the environment, and tell every widget in every window.
+    ///
+    /// The windows are updated with the new `Env` at the end of this cycle,
+    /// and repainted.
+    fn set_env(&mut self, env: Env) {
+        self.env = env;
+        // the data may be unchanged, but the widgets must see the new env.
+        self.last_version = None;
+        self.last_data = None;
+        let AppState {
+            ref mut command_queue,
+            ref mut windows,
+            ref data,
+            ref env,
+            ..
+        } = self;
+        for win in windows.iter_mut() {
+            win.lifecycle(command_queue, &LifeCycle::ThemeChanged, data, env);
+            win.handle.invalidate();
+        }
+    }
+
+    /// Switch the `Env` to another locale; see `set_env`.
+    fn set_locale(&mut self, locale: &str) {
+        match locale.parse() {
+            Ok(locale) => {
+                let mut env = self.env.clone();
+                env.set_locale(locale);
+                self.set_env(env);
+            }
+            Err(e) => log::warn!("SET_LOCALE with invalid locale '{}': {:?}", locale, e),
+        }
+    }
+
+    fn set_window_title(&mut self, id: WindowId, title: &str) {
+        match self.windows.get_mut(id) {
+            Some(win) => win.set_title(title),
+            None => log::warn!("SET_WINDOW_TITLE sent to unknown window {:?}", id),
+        }
+    }
+
     /// Returns `true` if an animation frame was requested.
-    fn paint(&mut self, window_id: WindowId, piet: &mut Piet, _ctx: &mut dyn WinCtx) -> bool {
+    ///
+    /// After the first paint of any window, the `on_first_paint` callback is
+    /// run. It may change the data, but we can't update from inside a paint:
+    /// some platforms discard invalidations made while painting. Instead we
+    /// ask to be called back on idle, where the update pass will happen.
+    fn paint(
+        &mut self,
+        window_id: WindowId,
+        piet: &mut Piet,
+        invalid_rect: Rect,
+        _ctx: &mut dyn WinCtx,
+    ) -> bool {
         if let Some(win) = self.windows.get_mut(window_id) {
-            win.do_paint(piet, &mut self.command_queue, &self.data, &self.env);
+            // a hidden window keeps up with the data, but is not painted.
+            if win.hidden {
+                return false;
+            }
+            win.do_paint(
+                piet,
+                invalid_rect,
+                &mut self.command_queue,
+                &self.data,
+                &self.env,
+            );
+            if let Some(first_paint) = self.first_paint.take() {
+                first_paint(&mut self.data, &self.env);
+                match win.handle.get_idle_handle() {
+                    Some(mut idle) => idle.schedule_idle(RUN_COMMANDS_TOKEN),
+                    None => log::error!("failed to get idle handle"),
+                }
+            }
             win.wants_animation_frame()
         } else {
             false
@@ -240,26 +628,72 @@ impl<T: Data> AppState<T> {
     }
 
     fn do_event(&mut self, source_id: WindowId, event: Event, win_ctx: &mut dyn WinCtx) -> bool {
+        if self.modals.blocks(source_id) {
+            match event {
+                // while a modal window is open, the others can't be closed.
+                Event::WindowCloseRequested => return true,
+                Event::MouseDown(_)
+                | Event::MouseUp(_)
+                | Event::MouseMoved(_)
+                | Event::KeyDown(_)
+                | Event::KeyUp(_)
+                | Event::Paste(_)
+                | Event::Copy(_)
+                | Event::Cut(_)
+                | Event::ImeComposition { .. }
+                | Event::ImeCommit(_)
+                | Event::Wheel(_)
+                | Event::Zoom(_)
+                | Event::FileDragOver(_)
+                | Event::FileDrop(_) => return false,
+                _ => (),
+            }
+        }
+
+        if let Event::KeyDown(key) = &event {
+            if let Some(cmd) = self.hotkey_command(source_id, key) {
+                command::enqueue(&mut self.command_queue, source_id.into(), cmd);
+                return true;
+            }
+        }
+
+        if let Event::WindowCloseRequested = event {
+            if !self.window_should_close(source_id) {
+                return true;
+            }
+        }
+
         // if the event was swallowed by the delegate we consider it handled?
-        let event = match self.delegate_event(source_id, event) {
+        let event = self.delegate_event(source_id, event);
+
+        // the delegate may have requested interval timers, and the event may
+        // never reach the window that would otherwise schedule them.
+        if let Some(win) = self.windows.get_mut(source_id) {
+            win.interval_timers.arm(win_ctx);
+        }
+
+        let event = match event {
             Some(event) => event,
             None => return true,
         };
 
+        if let Event::TargetedCommand(target, ref cmd) = event {
+            if self.delegate_command(source_id, target, cmd) {
+                return true;
+            }
+        }
+
         if let Event::TargetedCommand(_target, ref cmd) = event {
-            match cmd.selector {
-                sys_cmd::SET_MENU => {
-                    self.set_menu(source_id, cmd);
-                    return true;
-                }
-                sys_cmd::SHOW_CONTEXT_MENU => {
-                    self.show_context_menu(source_id, cmd);
-                    return true;
-                }
-                _ => (),
+            if cmd.is(sys_cmd::SET_MENU) {
+                self.set_menu(source_id, cmd);
+                return true;
+            } else if cmd.is(sys_cmd::SHOW_CONTEXT_MENU) {
+                self.show_context_menu(source_id, cmd);
+                return true;
             }
         }
 
+        let now = self.clock.now();
         let AppState {
             ref mut command_queue,
             ref mut windows,
@@ -269,23 +703,34 @@ impl<T: Data> AppState<T> {
         } = self;
 
         match event {
-            Event::TargetedCommand(Target::Widget(_), _) => {
+            Event::TargetedCommand(target @ Target::Widget(_), _)
+            | Event::TargetedCommand(target @ Target::Subtree(_), _)
+            | Event::TargetedCommand(target @ Target::Global, _) => {
                 let mut any_handled = false;
 
-                // TODO: this is using the WinCtx of the window originating the event,
-                // rather than a WinCtx appropriate to the target window. This probably
-                // needs to get rethought.
+                // each window gets its own WinCtx; only the source window's
+                // is the one we were called with.
                 for window in windows.iter_mut() {
-                    let handled = window.event(win_ctx, command_queue, event.clone(), data, env);
+                    let handled = if window.id == source_id {
+                        window.event(win_ctx, command_queue, event.clone(), data, env, now)
+                    } else {
+                        let handle = window.handle.clone();
+                        handle
+                            .with_context(|ctx| {
+                                window.event(ctx, command_queue, event.clone(), data, env, now)
+                            })
+                            .unwrap_or(false)
+                    };
                     any_handled |= handled;
-                    if handled {
+                    // a global command reaches every window, handled or not.
+                    if handled && target != Target::Global {
                         break;
                     }
                 }
                 any_handled
             }
             _ => match windows.get_mut(source_id) {
-                Some(win) => win.event(win_ctx, command_queue, event, data, env),
+                Some(win) => win.event(win_ctx, command_queue, event, data, env, now),
                 None => false,
             },
         }
@@ -293,25 +738,34 @@ impl<T: Data> AppState<T> {
 
     fn set_menu(&mut self, window_id: WindowId, cmd: &Command) {
         if let Some(win) = self.windows.get_mut(window_id) {
-            match cmd.get_object::<MenuDesc<T>>() {
-                Ok(menu) => win.set_menu(menu.to_owned(), &self.data, &self.env),
-                Err(e) => log::warn!("set-menu object error: '{}'", e),
+            let menu = cmd.get(sys_cmd::SET_MENU);
+            match menu.and_then(|menu| menu.get::<T>()) {
+                Some(menu) => win.set_menu(menu.to_owned(), &self.data, &self.env),
+                None => log::warn!("set-menu argument is not a MenuDesc"),
             }
         }
     }
 
     fn show_context_menu(&mut self, window_id: WindowId, cmd: &Command) {
         if let Some(win) = self.windows.get_mut(window_id) {
-            match cmd.get_object::<ContextMenu<T>>() {
-                Ok(ContextMenu { menu, location }) => {
+            let menu = cmd.get(sys_cmd::SHOW_CONTEXT_MENU);
+            match menu.and_then(|menu| menu.get::<T>()) {
+                Some(ContextMenu { menu, location }) => {
                     win.show_context_menu(menu.to_owned(), *location, &self.data, &self.env)
                 }
-                Err(e) => log::warn!("show-context-menu object error: '{}'", e),
+                None => log::warn!("show-context-menu argument is not a ContextMenu"),
             }
         }
     }
 
     fn do_update(&mut self, win_ctx: &mut dyn WinCtx) {
+        // invalidation and new widgets are requested by events and
+        // animations as well as by updates, so they are always handled.
+        let unchanged = self.data_unchanged();
+        if unchanged && !self.windows.iter_mut().any(|win| win.needs_update()) {
+            self.invalidate_and_finalize();
+            return;
+        }
         // we send `update` to all windows, not just the active one:
         for window in self.windows.iter_mut() {
             window.update(win_ctx, &self.data, &self.env);
@@ -319,6 +773,28 @@ impl<T: Data> AppState<T> {
         self.invalidate_and_finalize();
     }
 
+    /// Returns `true` if the data has not changed since the last time this
+    /// was called, in which case no widget would be updated.
+    ///
+    /// If the application uses `DataVersion`, this compares versions, which
+    /// also skips every `Data::same` check on the way down the widget tree.
+    /// Otherwise, it compares the data with `Data::same`, to save visiting
+    /// every window. After the `Env` changes, this returns `false` once.
+    fn data_unchanged(&mut self) -> bool {
+        if let Some(version) = self.data_version {
+            let version = version(&self.data);
+            let unchanged = self.last_version == Some(version);
+            self.last_version = Some(version);
+            return unchanged;
+        }
+        let data = &self.data;
+        if self.last_data.as_ref().map(|old| old.same(data)) == Some(true) {
+            return true;
+        }
+        self.last_data = Some(data.clone());
+        false
+    }
+
     /// invalidate any window handles that need it.
     ///
     /// This should always be called at the end of an event update cycle,
@@ -329,6 +805,129 @@ impl<T: Data> AppState<T> {
         }
     }
 
+    /// Run a nested event loop until the modal window `id` is dismissed, and
+    /// return how it was dismissed.
+    ///
+    /// The platform's run loop is not re-entered: `pump` is expected to
+    /// block until the next platform event arrives and to dispatch it (see
+    /// `Application::pump_events`), returning `false` if the application is
+    /// quitting. This is called from within a handler callback, so the rules
+    /// for re-entrancy are:
+    ///
+    /// - the `AppState` is only borrowed between calls to `pump`, never
+    ///   across one, so handlers for other windows can run as normal;
+    /// - the handler that opened the modal is still on the stack, so the
+    ///   platform drops events for its window until this returns; the modal
+    ///   window is a separate window, with its own handler;
+    /// - input to every window other than the innermost modal is ignored by
+    ///   `do_event`, so the rest of the UI cannot change underneath it.
+    ///
+    /// The loop ends when the modal window submits `END_MODAL`, when it is
+    /// closed, or when the application quits.
+    pub(crate) fn run_modal(
+        state: &Rc<RefCell<Self>>,
+        id: WindowId,
+        mut pump: impl FnMut() -> bool,
+    ) -> ModalResult {
+        {
+            let mut state = state.borrow_mut();
+            if !state.windows.contains(id) {
+                log::warn!("no window {:?} to run modally", id);
+                return ModalResult::Closed;
+            }
+            state.modals.begin(id);
+        }
+        loop {
+            if let Some(result) = state.borrow_mut().modals.finish(id) {
+                return result;
+            }
+            if !pump() {
+                state.borrow_mut().modals.end(id, ModalResult::Closed);
+            }
+        }
+    }
+
+    /// Record the result of a modal window, and close it.
+    fn end_modal(&mut self, window_id: WindowId, cmd: Command) {
+        if !self.modals.is_modal(window_id) {
+            log::warn!("END_MODAL sent to {:?}, which is not modal", window_id);
+            return;
+        }
+        self.modals.end(window_id, ModalResult::Ended(cmd));
+        self.request_close_window(window_id);
+    }
+
+    /// The event to dispatch for a timer firing in this window.
+    ///
+    /// Ticks of the autosave timer are handled here, and are not dispatched.
+    fn timer_event(
+        &mut self,
+        window_id: WindowId,
+        token: TimerToken,
+        now: Instant,
+        win_ctx: &mut dyn WinCtx,
+    ) -> Option<Event> {
+        let event = match self.windows.get_mut(window_id) {
+            Some(win) => win.timer_event(token, now),
+            None => Some(Event::Timer(token)),
+        };
+        match event {
+            Some(Event::Timer(token)) if self.autosave.iter().any(|a| a.is_timer(token)) => {
+                self.run_autosave(window_id);
+                // the event is not dispatched, so arm the next tick ourselves.
+                if let Some(win) = self.windows.get_mut(window_id) {
+                    win.interval_timers.arm(win_ctx);
+                }
+                None
+            }
+            other => other,
+        }
+    }
+
+    /// Tell the delegate about a change in the power state of the system.
+    ///
+    /// The platform sends power notifications to every window, so only the
+    /// one delivered to the open window with the lowest id is passed on; this
+    /// way the delegate hears about each change exactly once.
+    ///
+    /// Returns `true` if the delegate was called.
+    fn power_event(&mut self, source_id: WindowId, event: PowerEvent) -> bool {
+        if self.windows.windows.keys().min() != Some(&source_id) {
+            return false;
+        }
+        self.with_delegate(source_id, |del, data, env, ctx| match event {
+            PowerEvent::WillSleep => del.system_will_sleep(data, env, ctx),
+            PowerEvent::DidWake => del.system_did_wake(data, env, ctx),
+            PowerEvent::BatteryLow => del.battery_low(data, env, ctx),
+        })
+        .is_some()
+    }
+
+    /// Record that this window has focus.
+    ///
+    /// If another window still has focus, because the platform has not told
+    /// it yet that it lost it, that window is returned; it should receive
+    /// `WindowLostFocus` before this window receives `WindowGotFocus`.
+    fn set_focused_window(&mut self, window_id: WindowId) -> Option<WindowId> {
+        let previous = self.focused_window.replace(window_id)?;
+        if previous != window_id && self.windows.contains(previous) {
+            Some(previous)
+        } else {
+            None
+        }
+    }
+
+    /// Record that this window lost focus; returns `false` if it did not
+    /// have focus, in which case it was already told that it lost it.
+    fn clear_focused_window(&mut self, window_id: WindowId) -> bool {
+        if self.focused_window == Some(window_id) {
+            self.focused_window = None;
+            true
+        } else {
+            false
+        }
+    }
+
     #[cfg(target_os = "macos")]
     fn window_got_focus(&mut self, window_id: WindowId) {
         if let Some(win) = self.windows.get_mut(window_id) {
@@ -349,6 +948,8 @@ impl<T: Data> DruidHandler<T> {
         DruidHandler {
             app_state,
             window_id,
+            clicks: ClickCounter::default(),
+            drag_files: Vec::new(),
         }
     }
 
@@ -358,7 +959,36 @@ impl<T: Data> DruidHandler<T> {
     ///
     /// This is principally because in certain cases (such as keydown on Windows)
     /// the OS needs to know if an event was handled.
+    ///
+    /// A pending mouse move is handled first.
     fn do_event(&mut self, event: Event, win_ctx: &mut dyn WinCtx) -> bool {
+        self.flush_mouse_move(win_ctx);
+        self.handle_event(event, win_ctx)
+    }
+
+    /// Handle the pending mouse move, if there is one.
+    fn flush_mouse_move(&mut self, win_ctx: &mut dyn WinCtx) {
+        let pending = self
+            .app_state
+            .borrow_mut()
+            .windows
+            .get_mut(self.window_id)
+            .and_then(|win| win.pending_move.take());
+        if let Some(event) = pending {
+            self.handle_event(Event::MouseMoved(event), win_ctx);
+        }
+    }
+
+    fn handle_event(&mut self, event: Event, win_ctx: &mut dyn WinCtx) -> bool {
+        let event = match self
+            .app_state
+            .borrow_mut()
+            .filter_event(self.window_id, event)
+        {
+            Some(event) => event,
+            // a dropped event was consumed by the filter.
+            None => return true,
+        };
         let result = self
             .app_state
             .borrow_mut()
@@ -372,28 +1002,75 @@ impl<T: Data> DruidHandler<T> {
         loop {
             let next_cmd = self.app_state.borrow_mut().command_queue.pop_front();
             match next_cmd {
-                Some((target, cmd)) => self.handle_cmd(target, cmd, win_ctx),
+                Some((target, cmd)) => self.dispatch_cmd(target, cmd, win_ctx),
                 None => break,
             }
         }
     }
 
+    /// Dispatch a command that has come off a queue.
+    ///
+    /// Commands with a debounced selector are handed to the `Debouncer` and
+    /// a timer is requested for the end of their quiet period; all other
+    /// commands are delivered immediately.
+    fn dispatch_cmd(&mut self, target: Target, cmd: Command, win_ctx: &mut dyn WinCtx) {
+        let mut state = self.app_state.borrow_mut();
+        if state.debouncer.is_debounced(cmd.symbol()) {
+            let symbol = cmd.symbol();
+            let now = state.clock.now();
+            let deadline = state.debouncer.hold(self.window_id, target, cmd, now);
+            let token = win_ctx.request_timer(deadline);
+            state.debouncer.arm(symbol, target, token);
+        } else {
+            drop(state);
+            self.deliver_cmd(target, cmd, win_ctx);
+        }
+    }
+
+    /// Handle a command now.
+    ///
+    /// If a command that can be retried is not handled, it is handed to
+    /// `Retries`, which holds it until its next attempt.
+    fn deliver_cmd(&mut self, target: Target, cmd: Command, win_ctx: &mut dyn WinCtx) {
+        let failed = retry::deliver(target, cmd, |target, cmd| {
+            self.handle_cmd(target, cmd, win_ctx)
+        });
+        if let Some(cmd) = failed {
+            let mut state = self.app_state.borrow_mut();
+            let now = state.clock.now();
+            state
+                .retries
+                .failed(self.window_id, target, cmd, now, win_ctx);
+        }
+    }
+
+    /// Handle every pending external event, and then run a single update.
+    ///
+    /// A background thread can submit many events between two idle
+    /// callbacks, and there is no point in updating the widgets for each of
+    /// them. The commands an event generates are handled before the next
+    /// event, so the order is the same as if each event had been handled on
+    /// its own.
     fn process_ext_events(&mut self, win_ctx: &mut dyn WinCtx) {
         loop {
             let ext_cmd = self.app_state.borrow_mut().ext_event_host.recv();
             match ext_cmd {
                 Some((targ, cmd)) => {
                     let targ = targ.unwrap_or_else(|| self.window_id.into());
-                    self.handle_cmd(targ, cmd, win_ctx);
+                    self.dispatch_cmd(targ, cmd, win_ctx);
+                    self.process_commands(win_ctx);
                 }
                 None => break,
             }
         }
-        self.app_state.borrow_mut().invalidate_and_finalize();
+        self.app_state.borrow_mut().do_update(win_ctx);
     }
 
     fn handle_system_cmd(&mut self, cmd_id: u32, win_ctx: &mut dyn WinCtx) {
-        let cmd = self.app_state.borrow().get_menu_cmd(self.window_id, cmd_id);
+        let cmd = self
+            .app_state
+            .borrow_mut()
+            .activate_menu_item(self.window_id, cmd_id);
         match cmd {
             Some(cmd) => self
                 .app_state
@@ -407,96 +1084,233 @@ impl<T: Data> DruidHandler<T> {
 
     /// Handle a command. Top level commands (e.g. for creating and destroying windows)
     /// have their logic here; other commands are passed to the window.
-    fn handle_cmd(&mut self, target: Target, cmd: Command, win_ctx: &mut dyn WinCtx) {
-        //FIXME: we need some way of getting the correct `WinCtx` for this window.
-        if let Target::Window(window_id) = target {
-            match &cmd.selector {
-                &sys_cmd::SHOW_OPEN_PANEL => self.show_open_panel(cmd, window_id, win_ctx),
-                &sys_cmd::SHOW_SAVE_PANEL => self.show_save_panel(cmd, window_id, win_ctx),
-                &sys_cmd::NEW_WINDOW => {
-                    if let Err(e) = self.new_window(cmd) {
-                        log::error!("failed to create window: '{}'", e);
-                    }
-                }
-                &sys_cmd::CLOSE_WINDOW => self.request_close_window(cmd, window_id),
-                &sys_cmd::SHOW_WINDOW => self.show_window(cmd),
-                &sys_cmd::QUIT_APP => self.quit(),
-                &sys_cmd::HIDE_APPLICATION => self.hide_app(),
-                &sys_cmd::HIDE_OTHERS => self.hide_others(),
-                &sys_cmd::PASTE => self.do_paste(window_id, win_ctx),
-                sel => {
-                    info!("handle_cmd {}", sel);
-                    let event = Event::TargetedCommand(target, cmd);
-                    self.app_state
-                        .borrow_mut()
-                        .do_event(window_id, event, win_ctx);
+    ///
+    /// Returns `true` if the command was handled; top level commands always are.
+    fn handle_cmd(&mut self, target: Target, cmd: Command, win_ctx: &mut dyn WinCtx) -> bool {
+        match target {
+            Target::Window(window_id) if window_id != self.window_id => {
+                // the command is for another window, so use that window's WinCtx.
+                let handle = self.app_state.borrow().window_handle(window_id);
+                let mut cmd = Some(cmd);
+                let handled = handle.and_then(|handle| {
+                    handle.with_context(|ctx| {
+                        let cmd = cmd.take().unwrap();
+                        self.handle_window_cmd(window_id, target, cmd, ctx)
+                    })
+                });
+                match handled {
+                    Some(handled) => handled,
+                    // without a context of its own, make do with ours.
+                    None => self.handle_window_cmd(window_id, target, cmd.unwrap(), win_ctx),
                 }
             }
+            Target::Window(window_id) => self.handle_window_cmd(window_id, target, cmd, win_ctx),
+            Target::Widget(_) | Target::Subtree(_) | Target::Global => {
+                info!("handle_cmd {} -> {:?}", cmd.symbol(), target);
+                let event = Event::TargetedCommand(target, cmd);
+                // TODO: self.window_id the correct source identifier here?
+                self.app_state
+                    .borrow_mut()
+                    .do_event(self.window_id, event, win_ctx)
+            }
+        }
+    }
+
+    /// Handle a command for a window, with that window's `WinCtx`.
+    fn handle_window_cmd(
+        &mut self,
+        window_id: WindowId,
+        target: Target,
+        cmd: Command,
+        win_ctx: &mut dyn WinCtx,
+    ) -> bool {
+        if cmd.is(sys_cmd::SHOW_OPEN_PANEL) {
+            self.show_open_panel(cmd, win_ctx);
+        } else if cmd.is(sys_cmd::SHOW_SAVE_PANEL) {
+            self.show_save_panel(cmd, win_ctx);
+        } else if cmd.is(sys_cmd::NEW_WINDOW) {
+            if let Err(e) = self.new_window(cmd) {
+                log::error!("failed to create window: '{}'", e);
+            }
+        } else if cmd.is(sys_cmd::SHOW_MODAL) {
+            self.show_modal(cmd, window_id);
+        } else if cmd.is(sys_cmd::END_MODAL) {
+            self.app_state.borrow_mut().end_modal(window_id, cmd);
+        } else if cmd.is(sys_cmd::CLOSE_WINDOW) {
+            self.request_close_window(window_id);
+        } else if cmd.is(sys_cmd::SHOW_WINDOW) {
+            self.show_window(cmd);
+        } else if let Some(env) = cmd.get(sys_cmd::SET_THEME) {
+            self.app_state.borrow_mut().set_env(env.clone());
+        } else if let Some(locale) = cmd.get(sys_cmd::SET_LOCALE) {
+            self.app_state.borrow_mut().set_locale(locale);
+        } else if let Some(title) = cmd.get(sys_cmd::SET_WINDOW_TITLE) {
+            self.app_state
+                .borrow_mut()
+                .set_window_title(window_id, title);
+        } else if cmd.is(sys_cmd::SET_WINDOW_SIZE)
+            || cmd.is(sys_cmd::SET_WINDOW_POSITION)
+            || cmd.is(sys_cmd::MINIMIZE_WINDOW)
+            || cmd.is(sys_cmd::MAXIMIZE_WINDOW)
+            || cmd.is(sys_cmd::RESTORE_WINDOW)
+        {
+            self.change_window_state(window_id, cmd);
+        } else if cmd.is(sys_cmd::QUIT_APP) {
+            self.request_quit(window_id);
+        } else if cmd.is(sys_cmd::HIDE_APPLICATION) {
+            self.hide_app();
+        } else if cmd.is(sys_cmd::HIDE_OTHERS) {
+            self.hide_others();
+        } else if cmd.is(sys_cmd::PASTE) {
+            self.do_paste(window_id, win_ctx);
+        } else if cmd.is(sys_cmd::COPY) || cmd.is(sys_cmd::CUT) {
+            self.do_copy(window_id, cmd.is(sys_cmd::CUT), win_ctx);
         } else {
-            info!("handle_cmd {} -> widget", cmd.selector);
+            info!("handle_cmd {}", cmd.symbol());
             let event = Event::TargetedCommand(target, cmd);
-            // TODO: self.window_id the correct source identifier here?
-            self.app_state
+            return self
+                .app_state
                 .borrow_mut()
-                .do_event(self.window_id, event, win_ctx);
+                .do_event(window_id, event, win_ctx);
         }
+        true
     }
 
-    fn show_open_panel(&mut self, cmd: Command, window_id: WindowId, win_ctx: &mut dyn WinCtx) {
+    fn show_open_panel(&mut self, cmd: Command, win_ctx: &mut dyn WinCtx) {
         let options = cmd
-            .get_object::<FileDialogOptions>()
-            .map(|opts| opts.to_owned())
+            .get(sys_cmd::SHOW_OPEN_PANEL)
+            .cloned()
             .unwrap_or_default();
-        let result = win_ctx.open_file_sync(options);
-        if let Some(info) = result {
-            let cmd = Command::new(sys_cmd::OPEN_FILE, info);
-            let event = Event::TargetedCommand(window_id.into(), cmd);
-            self.app_state
-                .borrow_mut()
-                .do_event(window_id, event, win_ctx);
+        // the result is delivered to `WinHandler::open_file`, unless the
+        // dialog can only be shown while we wait.
+        if win_ctx.open_file(options.clone()).is_none() {
+            let file = win_ctx.open_file_sync(options);
+            self.open_file(FileDialogToken::next(), file, win_ctx);
         }
     }
 
-    fn show_save_panel(&mut self, cmd: Command, window_id: WindowId, win_ctx: &mut dyn WinCtx) {
+    fn show_save_panel(&mut self, cmd: Command, win_ctx: &mut dyn WinCtx) {
         let options = cmd
-            .get_object::<FileDialogOptions>()
-            .map(|opts| opts.to_owned())
+            .get(sys_cmd::SHOW_SAVE_PANEL)
+            .cloned()
             .unwrap_or_default();
-        let result = win_ctx.save_as_sync(options);
-        if let Some(info) = result {
-            let cmd = Command::new(sys_cmd::SAVE_FILE, info);
-            let event = Event::TargetedCommand(window_id.into(), cmd);
-            self.app_state
-                .borrow_mut()
-                .do_event(window_id, event, win_ctx);
+        // the result is delivered to `WinHandler::save_as`, unless the
+        // dialog can only be shown while we wait.
+        if win_ctx.save_as(options.clone()).is_none() {
+            let file = win_ctx.save_as_sync(options);
+            self.save_as(FileDialogToken::next(), file, win_ctx);
         }
     }
 
     fn new_window(&mut self, cmd: Command) -> Result<(), Box<dyn std::error::Error>> {
-        let desc = cmd.take_object::<WindowDesc<T>>()?;
+        let desc = cmd
+            .take(sys_cmd::NEW_WINDOW)
+            .ok_or("new-window command has no argument")?
+            .into_desc::<T>()
+            .ok_or("new-window argument is not a WindowDesc for the application data")?;
         let window = desc.build_native(&self.app_state)?;
         window.show();
         Ok(())
     }
 
-    fn request_close_window(&mut self, cmd: Command, window_id: WindowId) {
-        let id = cmd.get_object().unwrap_or(&window_id);
-        self.app_state.borrow_mut().request_close_window(*id);
+    /// Open a modal window, and block until it is dismissed.
+    ///
+    /// The result is sent to the window that asked for the modal.
+    fn show_modal(&mut self, cmd: Command, window_id: WindowId) {
+        let desc = cmd.take(sys_cmd::SHOW_MODAL);
+        let desc = match desc.and_then(|desc| desc.into_desc::<T>()) {
+            Some(desc) => desc,
+            None => {
+                log::error!("show-modal argument is not a WindowDesc");
+                return;
+            }
+        };
+        let modal_id = desc.id;
+        match desc.build_native(&self.app_state) {
+            Ok(window) => window.show(),
+            Err(e) => {
+                log::error!("failed to create modal window: '{}'", e);
+                return;
+            }
+        }
+        let result = AppState::run_modal(&self.app_state, modal_id, Application::pump_events);
+        let cmd = Command::new(sys_cmd::MODAL_RESULT, result);
+        self.app_state
+            .borrow_mut()
+            .command_queue
+            .push_back((window_id.into(), cmd));
+    }
+
+    fn request_close_window(&mut self, window_id: WindowId) {
+        self.app_state.borrow_mut().request_close_window(window_id);
     }
 
     fn show_window(&mut self, cmd: Command) {
-        let id: WindowId = *cmd
-            .get_object()
+        let id = *cmd
+            .get(sys_cmd::SHOW_WINDOW)
             .expect("show window selector missing window id");
         self.app_state.borrow_mut().show_window(id);
     }
 
+    /// Resize, move, minimize, maximize or restore a window.
+    ///
+    /// The platform may call back into the handler synchronously, so the
+    /// app state must not be borrowed while the handle is used.
+    fn change_window_state(&mut self, window_id: WindowId, cmd: Command) {
+        let handle = match self.app_state.borrow().window_handle(window_id) {
+            Some(handle) => handle,
+            None => {
+                log::warn!("{} sent to unknown window {:?}", cmd.symbol(), window_id);
+                return;
+            }
+        };
+        if let Some(size) = cmd.get(sys_cmd::SET_WINDOW_SIZE) {
+            handle.set_size(*size);
+        } else if let Some(pos) = cmd.get(sys_cmd::SET_WINDOW_POSITION) {
+            handle.set_position(*pos);
+        } else if cmd.is(sys_cmd::MINIMIZE_WINDOW) {
+            handle.minimize();
+        } else if cmd.is(sys_cmd::MAXIMIZE_WINDOW) {
+            handle.maximize();
+        } else if cmd.is(sys_cmd::RESTORE_WINDOW) {
+            handle.restore();
+        }
+    }
+
     fn do_paste(&mut self, window_id: WindowId, ctx: &mut dyn WinCtx) {
         let event = Event::Paste(Application::clipboard());
         self.app_state.borrow_mut().do_event(window_id, event, ctx);
     }
 
+    /// Ask the focused widget to copy, or cut, its selection; with nothing
+    /// focused, this does nothing.
+    fn do_copy(&mut self, window_id: WindowId, cut: bool, ctx: &mut dyn WinCtx) {
+        let clipboard = Application::clipboard();
+        let event = if cut {
+            Event::Cut(clipboard)
+        } else {
+            Event::Copy(clipboard)
+        };
+        self.app_state.borrow_mut().do_event(window_id, event, ctx);
+    }
+
+    /// Quit, if the delegate agrees.
+    fn request_quit(&mut self, window_id: WindowId) {
+        if self
+            .app_state
+            .borrow_mut()
+            .application_should_quit(window_id)
+        {
+            self.quit();
+        }
+    }
+
     fn quit(&self) {
+        // we are exiting normally, so there is nothing to recover. This can't
+        // wait for the run loop to return: on macOS it never does.
+        if let Some(autosave) = self.app_state.borrow().autosave.as_ref() {
+            autosave.clear();
+        }
         Application::quit()
     }
 
@@ -525,8 +1339,10 @@ impl<T: Data> WinHandler for DruidHandler<T> {
         self.do_event(event, ctx);
     }
 
-    fn paint(&mut self, piet: &mut Piet, ctx: &mut dyn WinCtx) -> bool {
-        self.app_state.borrow_mut().paint(self.window_id, piet, ctx)
+    fn paint(&mut self, piet: &mut Piet, invalid_rect: Rect, ctx: &mut dyn WinCtx) -> bool {
+        self.app_state
+            .borrow_mut()
+            .paint(self.window_id, piet, invalid_rect, ctx)
     }
 
     fn size(&mut self, width: u32, height: u32, ctx: &mut dyn WinCtx) {
@@ -534,13 +1350,24 @@ impl<T: Data> WinHandler for DruidHandler<T> {
         self.do_event(event, ctx);
     }
 
+    fn position(&mut self, pos: Point, ctx: &mut dyn WinCtx) {
+        self.do_event(Event::WindowMoved(pos), ctx);
+    }
+
+    fn scale(&mut self, scale: f64, ctx: &mut dyn WinCtx) {
+        self.do_event(Event::ScaleChanged(scale), ctx);
+    }
+
     fn command(&mut self, id: u32, ctx: &mut dyn WinCtx) {
         self.handle_system_cmd(id, ctx);
     }
 
     fn mouse_down(&mut self, event: &MouseEvent, ctx: &mut dyn WinCtx) {
-        // TODO: double-click detection (or is this done in druid-shell?)
-        let event = Event::MouseDown(event.clone().into());
+        // platforms disagree on how to count clicks, so we do it ourselves.
+        let mut event: crate::MouseEvent = event.clone().into();
+        let now = self.app_state.borrow().clock.now();
+        event.count = self.clicks.press(event.pos, event.button, now);
+        let event = Event::MouseDown(event);
         self.do_event(event, ctx);
     }
 
@@ -549,44 +1376,198 @@ impl<T: Data> WinHandler for DruidHandler<T> {
         self.do_event(event, ctx);
     }
 
-    fn mouse_move(&mut self, event: &MouseEvent, ctx: &mut dyn WinCtx) {
-        let event = Event::MouseMoved(event.clone().into());
-        self.do_event(event, ctx);
+    fn mouse_leave(&mut self, ctx: &mut dyn WinCtx) {
+        self.do_event(Event::MouseLeave, ctx);
     }
 
-    fn key_down(&mut self, event: KeyEvent, ctx: &mut dyn WinCtx) -> bool {
-        self.do_event(Event::KeyDown(event), ctx)
+    fn drag_enter(&mut self, files: &[FileInfo], pos: Point, ctx: &mut dyn WinCtx) -> bool {
+        self.drag_files = files.to_vec();
+        self.drag_over(pos, ctx)
     }
 
-    fn key_up(&mut self, event: KeyEvent, ctx: &mut dyn WinCtx) {
-        self.do_event(Event::KeyUp(event), ctx);
+    fn drag_over(&mut self, pos: Point, ctx: &mut dyn WinCtx) -> bool {
+        let files = self.drag_files.clone();
+        self.do_event(Event::FileDragOver(FileDropEvent { pos, files }), ctx)
     }
 
-    fn wheel(&mut self, delta: Vec2, mods: KeyModifiers, ctx: &mut dyn WinCtx) {
-        let event = Event::Wheel(WheelEvent { delta, mods });
-        self.do_event(event, ctx);
+    fn drag_leave(&mut self, ctx: &mut dyn WinCtx) {
+        self.drag_files.clear();
+        self.do_event(Event::FileDragLeave, ctx);
     }
 
-    fn zoom(&mut self, delta: f64, ctx: &mut dyn WinCtx) {
-        let event = Event::Zoom(delta);
+    fn drop_files(&mut self, files: &[FileInfo], pos: Point, ctx: &mut dyn WinCtx) -> bool {
+        self.drag_files.clear();
+        let files = files.to_vec();
+        self.do_event(Event::FileDrop(FileDropEvent { pos, files }), ctx)
+    }
+
+    fn mouse_move(&mut self, event: &MouseEvent, _ctx: &mut dyn WinCtx) {
+        let mut state = self.app_state.borrow_mut();
+        // without the window, the move is gone with it.
+        let win = match state.windows.get_mut(self.window_id) {
+            Some(win) => win,
+            None => return,
+        };
+        if win.pending_move.is_none() {
+            if let Some(mut idle) = win.handle.get_idle_handle() {
+                idle.schedule_idle(MOUSE_MOVE_TOKEN);
+            }
+        }
+        win.pending_move = Some(event.clone().into());
+    }
+
+    fn key_down(&mut self, event: KeyEvent, ctx: &mut dyn WinCtx) -> bool {
+        self.do_event(Event::KeyDown(event), ctx)
+    }
+
+    fn key_up(&mut self, event: KeyEvent, ctx: &mut dyn WinCtx) {
+        self.do_event(Event::KeyUp(event), ctx);
+    }
+
+    fn ime_composition(&mut self, text: &str, cursor: usize, ctx: &mut dyn WinCtx) {
+        let text = text.to_owned();
+        self.do_event(Event::ImeComposition { text, cursor }, ctx);
+    }
+
+    fn ime_commit(&mut self, text: &str, ctx: &mut dyn WinCtx) {
+        self.do_event(Event::ImeCommit(text.to_owned()), ctx);
+    }
+
+    fn wheel(
+        &mut self,
+        delta: Vec2,
+        unit: WheelUnit,
+        phase: WheelPhase,
+        mods: KeyModifiers,
+        ctx: &mut dyn WinCtx,
+    ) {
+        let event = Event::Wheel(WheelEvent {
+            delta,
+            unit,
+            phase,
+            mods,
+        });
         self.do_event(event, ctx);
     }
 
-    fn got_focus(&mut self, _ctx: &mut dyn WinCtx) {
+    fn zoom(&mut self, delta: f64, ctx: &mut dyn WinCtx) {
+        let event = Event::Zoom(delta);
+        self.do_event(event, ctx);
+    }
+
+    fn got_focus(&mut self, ctx: &mut dyn WinCtx) {
+        let previous = self
+            .app_state
+            .borrow_mut()
+            .set_focused_window(self.window_id);
+        // focus moved here from another of our windows, which has not heard
+        // about it yet; it is told first, with its own WinCtx, and through
+        // a handler of its own, so that its pending move is handled first and
+        // the commands and updates the blur causes are run for it.
+        if let Some(previous) = previous {
+            let handle = self.app_state.borrow().window_handle(previous);
+            if let Some(handle) = handle {
+                let mut prev = DruidHandler::new_shared(self.app_state.clone(), previous);
+                handle.with_context(|prev_ctx| {
+                    prev.do_event(Event::WindowLostFocus, prev_ctx);
+                });
+            }
+        }
         self.app_state.borrow_mut().window_got_focus(self.window_id);
+        self.do_event(Event::WindowGotFocus, ctx);
+    }
+
+    fn lost_focus(&mut self, ctx: &mut dyn WinCtx) {
+        let had_focus = self
+            .app_state
+            .borrow_mut()
+            .clear_focused_window(self.window_id);
+        if had_focus {
+            self.do_event(Event::WindowLostFocus, ctx);
+        }
+    }
+
+    fn power_event(&mut self, event: PowerEvent, ctx: &mut dyn WinCtx) {
+        self.flush_mouse_move(ctx);
+        let handled = self
+            .app_state
+            .borrow_mut()
+            .power_event(self.window_id, event);
+        if handled {
+            self.process_commands(ctx);
+            self.app_state.borrow_mut().do_update(ctx);
+        }
     }
 
     fn timer(&mut self, token: TimerToken, ctx: &mut dyn WinCtx) {
-        self.do_event(Event::Timer(token), ctx);
+        // commands may read the mouse position, so it has to be current.
+        self.flush_mouse_move(ctx);
+        // retried and released commands have already been debounced.
+        let retry = self.app_state.borrow_mut().retries.fire(token);
+        if let Some((target, cmd)) = retry {
+            self.deliver_cmd(target, cmd, ctx);
+            self.process_commands(ctx);
+            self.app_state.borrow_mut().do_update(ctx);
+            return;
+        }
+
+        if self.app_state.borrow().debouncer.owns(token) {
+            let released = self.app_state.borrow_mut().debouncer.fire(token);
+            if let Some((target, cmd)) = released {
+                self.deliver_cmd(target, cmd, ctx);
+                self.process_commands(ctx);
+                self.app_state.borrow_mut().do_update(ctx);
+            }
+            return;
+        }
+
+        // Interval timers are re-armed here: the tick is translated back to
+        // the token of its interval, and the window schedules the next tick
+        // once the event has been handled.
+        let event = {
+            let mut state = self.app_state.borrow_mut();
+            let now = state.clock.now();
+            state.timer_event(self.window_id, token, now, ctx)
+        };
+        if let Some(event) = event {
+            self.do_event(event, ctx);
+        }
+    }
+
+    fn open_file(&mut self, _token: FileDialogToken, file: Option<FileInfo>, ctx: &mut dyn WinCtx) {
+        let cmd = match file {
+            Some(info) => Command::new(sys_cmd::OPEN_FILE, info),
+            None => sys_cmd::OPEN_PANEL_CANCELLED.into(),
+        };
+        let event = Event::TargetedCommand(self.window_id.into(), cmd);
+        self.do_event(event, ctx);
+    }
+
+    fn save_as(&mut self, _token: FileDialogToken, file: Option<FileInfo>, ctx: &mut dyn WinCtx) {
+        let cmd = match file {
+            Some(info) => Command::new(sys_cmd::SAVE_FILE, Some(info)),
+            None => sys_cmd::SAVE_PANEL_CANCELLED.into(),
+        };
+        let event = Event::TargetedCommand(self.window_id.into(), cmd);
+        self.do_event(event, ctx);
     }
 
     fn idle(&mut self, token: IdleToken, ctx: &mut dyn WinCtx) {
+        self.flush_mouse_move(ctx);
         match token {
             RUN_COMMANDS_TOKEN => {
                 self.process_commands(ctx);
-                self.app_state.borrow_mut().invalidate_and_finalize();
+                self.app_state.borrow_mut().do_update(ctx);
             }
             EXT_EVENT_IDLE_TOKEN => self.process_ext_events(ctx),
+            // the move was handled above.
+            MOUSE_MOVE_TOKEN => (),
+            REARM_IDLE_TOKEN => {
+                let id = self.window_id;
+                let mut state = self.app_state.borrow_mut();
+                state.retries.rearm(id, ctx);
+                state.debouncer.rearm(id, ctx);
+            }
             other => log::warn!("unexpected idle token {:?}", other),
         }
     }
@@ -595,10 +1576,39 @@ impl<T: Data> WinHandler for DruidHandler<T> {
         self
     }
 
+    fn request_close(&mut self, ctx: &mut dyn WinCtx) -> bool {
+        // the window stays open if the request was handled.
+        if self.do_event(Event::WindowCloseRequested, ctx) {
+            return false;
+        }
+        !self.app_state.borrow_mut().hide_window(self.window_id)
+    }
+
     fn destroy(&mut self, ctx: &mut dyn WinCtx) {
-        self.app_state
+        // hidden windows are removed along with the last visible one, before
+        // the platform gets around to destroying them.
+        if self
+            .app_state
+            .borrow()
+            .windows
+            .get(self.window_id)
+            .is_none()
+        {
+            return;
+        }
+        let hidden = self
+            .app_state
             .borrow_mut()
             .remove_window(self.window_id, ctx);
+        // some platforms destroy a window as soon as it is closed.
+        for handle in hidden {
+            handle.close();
+        }
+        // the delegate may have asked for a replacement window.
+        self.process_commands(ctx);
+        if self.app_state.borrow().should_quit_after_close() {
+            self.quit();
+        }
     }
 }
 
@@ -607,6 +1617,1451 @@ impl<T: Data> Default for Windows<T> {
         Windows {
             windows: HashMap::new(),
             pending: HashMap::new(),
+            handles: HashMap::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::shell::FileDialogOptions;
+    use crate::tests::harness::{
+        with_mock_win_ctx, with_piet, with_win_ctx, MockWinCtx, WHOLE_WINDOW,
+    };
+    use crate::tests::helpers::{left_mouse, left_shell_mouse, ModularDelegate, ModularWidget};
+    use crate::widget::WidgetExt;
+    use crate::{
+        theme, AppLauncher, Color, Cursor, DataVersion, ExtEventError, KeyCode, LocalizedString,
+        ModalValue, RawMods, Rect, Selector, SelectorSymbol, Widget, WidgetId, WindowDesc,
+    };
+
+    /// A new app state with the default theme.
+    fn test_state<T: Data>(
+        data: T,
+        delegate: Option<Box<dyn AppDelegate<T>>>,
+    ) -> Rc<RefCell<AppState<T>>> {
+        AppState::new(data, theme::init(), delegate, ExtEventHost::new())
+    }
+
+    /// Add a connected window with this root widget, and return its id.
+    fn open_window<T: Data>(
+        state: &Rc<RefCell<AppState<T>>>,
+        root: impl Widget<T> + 'static,
+    ) -> WindowId {
+        let id = WindowId::next();
+        let mut state = state.borrow_mut();
+        state.add_window(id, PendingWindow::new(root, LocalizedString::new(""), None));
+        state.connect(id, WindowHandle::default());
+        id
+    }
+
+    /// Add a connected window with this root widget, and return its handler.
+    fn open_handler<T: Data>(
+        state: &Rc<RefCell<AppState<T>>>,
+        root: impl Widget<T> + 'static,
+    ) -> DruidHandler<T> {
+        let id = open_window(state, root);
+        DruidHandler::new_shared(state.clone(), id)
+    }
+
+    /// A widget that fills its window, and does nothing else until given
+    /// more functions.
+    fn filler<S, T>(state: S) -> ModularWidget<S, T> {
+        ModularWidget::new(state).layout_fn(|_, _, bc, _, _| bc.max())
+    }
+
+    /// Keeps requesting animation frames for as long as the data is `true`.
+    fn spinner() -> ModularWidget<(), bool> {
+        filler(()).lifecycle_fn(|_, ctx, event, data, _| match event {
+            LifeCycle::WidgetAdded | LifeCycle::AnimFrame(_) if *data => ctx.request_anim_frame(),
+            _ => (),
+        })
+    }
+
+    /// Send a lifecycle event to the window.
+    fn lifecycle<T: Data>(state: &mut AppState<T>, id: WindowId, event: &LifeCycle) {
+        let AppState {
+            ref mut windows,
+            ref mut command_queue,
+            ref data,
+            ref env,
+            ..
+        } = state;
+        let win = windows.get_mut(id).unwrap();
+        win.lifecycle(command_queue, event, data, env);
+    }
+
+    /// Send an animation frame to the window, and report whether it asked for another.
+    fn animation_frame(state: &mut AppState<bool>, id: WindowId) -> bool {
+        lifecycle(state, id, &LifeCycle::AnimFrame(0));
+        state.windows.get(id).unwrap().wants_animation_frame()
+    }
+
+    #[test]
+    fn power_events_reach_delegate_once() {
+        let low_battery = Rc::new(Cell::new(0));
+        // pauses animations while the system sleeps or the battery is low.
+        let delegate = ModularDelegate::new(low_battery.clone())
+            .system_will_sleep_fn(|_, data, _, _| *data = false)
+            .system_did_wake_fn(|_, data, _, _| *data = true)
+            .battery_low_fn(|low_battery, data, _, _| {
+                low_battery.set(low_battery.get() + 1);
+                *data = false;
+            });
+        let state = test_state(true, Some(Box::new(delegate)));
+        let first = open_window(&state, spinner());
+        let second = open_window(&state, spinner());
+        let mut state = state.borrow_mut();
+        for &id in &[first, second] {
+            lifecycle(&mut state, id, &LifeCycle::WidgetAdded);
+        }
+        assert!(animation_frame(&mut state, first));
+
+        // every window is notified, but the delegate only hears about it once.
+        assert!(!state.power_event(second, PowerEvent::WillSleep));
+        assert!(state.data);
+        assert!(state.power_event(first, PowerEvent::WillSleep));
+        assert!(!state.data);
+        assert!(!animation_frame(&mut state, first));
+
+        assert!(state.power_event(first, PowerEvent::DidWake));
+        assert!(state.data);
+
+        assert!(state.power_event(first, PowerEvent::BatteryLow));
+        assert!(!state.power_event(second, PowerEvent::BatteryLow));
+        assert_eq!(low_battery.get(), 1);
+        assert!(!state.data);
+
+        // once the first window closes, the next one takes over.
+        state.windows.remove(first);
+        assert!(state.power_event(second, PowerEvent::BatteryLow));
+        assert_eq!(low_battery.get(), 2);
+    }
+
+    /// A large model that keeps a version, and counts its deep comparisons.
+    #[derive(Clone)]
+    struct Document {
+        version: u64,
+        words: Vec<String>,
+        comparisons: Rc<Cell<usize>>,
+    }
+
+    impl Data for Document {
+        fn same(&self, other: &Self) -> bool {
+            self.comparisons.set(self.comparisons.get() + 1);
+            self.words == other.words
+        }
+    }
+
+    impl DataVersion for Document {
+        fn version(&self) -> u64 {
+            self.version
+        }
+    }
+
+    /// Counts calls to `update`.
+    fn update_counter<T: Data>(updates: Rc<Cell<usize>>) -> ModularWidget<Rc<Cell<usize>>, T> {
+        filler(updates).update_fn(|updates, _, _, _, _| updates.set(updates.get() + 1))
+    }
+
+    #[test]
+    fn unchanged_version_skips_update() {
+        let comparisons = Rc::new(Cell::new(0));
+        let updates = Rc::new(Cell::new(0));
+        let document = Document {
+            version: 0,
+            words: vec!["hello".into()],
+            comparisons: comparisons.clone(),
+        };
+        let state = test_state(document, None);
+        state.borrow_mut().data_version = Some(Document::version);
+        let id = open_window(&state, update_counter(updates.clone()));
+        let mut state = state.borrow_mut();
+        lifecycle(&mut state, id, &LifeCycle::WidgetAdded);
+
+        with_win_ctx(|win_ctx| {
+            // the first update always runs.
+            state.do_update(win_ctx);
+            assert_eq!(updates.get(), 0);
+            assert!(comparisons.get() > 0);
+
+            // the words change, but the version doesn't: a deep comparison
+            // would find the change, but none is made.
+            comparisons.set(0);
+            state.data.words.push("world".into());
+            state.do_update(win_ctx);
+            assert_eq!(comparisons.get(), 0);
+            assert_eq!(updates.get(), 0);
+
+            // once the version moves, the widget is updated.
+            state.data.version += 1;
+            state.do_update(win_ctx);
+            assert!(comparisons.get() > 0);
+            assert_eq!(updates.get(), 1);
+        });
+    }
+
+    #[test]
+    fn unchanged_data_skips_update() {
+        let comparisons = Rc::new(Cell::new(0));
+        let updates = Rc::new(Cell::new(0));
+        let document = Document {
+            version: 0,
+            words: vec!["hello".into()],
+            comparisons: comparisons.clone(),
+        };
+        let state = test_state(document, None);
+        let ids = [
+            open_window(&state, update_counter(updates.clone())),
+            open_window(&state, update_counter(updates.clone())),
+        ];
+        let mut state = state.borrow_mut();
+
+        with_win_ctx(|win_ctx| {
+            for id in &ids {
+                state.do_event(*id, Event::WindowConnected, win_ctx);
+            }
+            state.do_update(win_ctx);
+
+            // a single comparison at the top is enough to skip both windows.
+            comparisons.set(0);
+            state.do_update(win_ctx);
+            assert_eq!(comparisons.get(), 1);
+            assert_eq!(updates.get(), 0);
+
+            state.data.words.push("world".into());
+            state.do_update(win_ctx);
+            assert_eq!(updates.get(), 2);
+
+            // a new env is seen even though the data is unchanged.
+            let mut env = state.env.clone();
+            env.set(theme::TEXT_SIZE_NORMAL, 31.0);
+            state.set_env(env);
+            state.do_update(win_ctx);
+            assert_eq!(updates.get(), 4);
+        });
+    }
+
+    #[test]
+    fn first_paint_callback_runs_once() {
+        let state = test_state(false, None);
+        let id = open_window(&state, spinner());
+        let mut state = state.borrow_mut();
+        let runs = Rc::new(Cell::new(0));
+        let runs2 = runs.clone();
+        state.first_paint = Some(Box::new(move |data: &mut bool, _: &Env| {
+            runs2.set(runs2.get() + 1);
+            *data = true;
+        }));
+
+        with_win_ctx(|win_ctx| {
+            state.do_event(id, Event::WindowConnected, win_ctx);
+            state.do_event(id, Event::Size(Size::new(40., 40.)), win_ctx);
+            state.do_update(win_ctx);
+        });
+        // nothing has been painted yet.
+        assert_eq!(runs.get(), 0);
+        assert!(!state.data);
+
+        with_piet(|piet| {
+            with_win_ctx(|win_ctx| {
+                state.paint(id, piet, WHOLE_WINDOW, win_ctx);
+                assert_eq!(runs.get(), 1);
+                assert!(state.data);
+
+                state.paint(id, piet, WHOLE_WINDOW, win_ctx);
+                assert_eq!(runs.get(), 1);
+            })
+        });
+    }
+
+    #[test]
+    fn modal_loop_returns_result() {
+        let state = test_state(true, None);
+        let parent = open_window(&state, spinner());
+        let modal = open_window(&state, spinner());
+
+        let mut pumped = 0;
+        let result = AppState::run_modal(&state, modal, || {
+            pumped += 1;
+            let mut state = state.borrow_mut();
+            // only the modal window takes input while the loop runs.
+            assert!(state.modals.blocks(parent));
+            assert!(!state.modals.blocks(modal));
+            if pumped == 2 {
+                // the modal submits its result; it is then closed, which must
+                // not replace the result.
+                let cmd = Command::new(sys_cmd::END_MODAL, ModalValue::new(String::from("teal")));
+                state.end_modal(modal, cmd);
+                state.windows.remove(modal);
+                state.modals.end(modal, ModalResult::Closed);
+            }
+            true
+        });
+
+        assert_eq!(pumped, 2);
+        match result {
+            ModalResult::Ended(cmd) => {
+                let teal = cmd.get(sys_cmd::END_MODAL).unwrap();
+                assert_eq!(teal.get::<String>(), Some(&String::from("teal")))
+            }
+            ModalResult::Closed => panic!("modal was dismissed with a result"),
+        }
+        assert!(!state.borrow().modals.blocks(parent));
+
+        // if the application quits, the loop unwinds without a result.
+        let result = AppState::run_modal(&state, parent, || false);
+        if let ModalResult::Ended(_) = result {
+            panic!("modal ended without a result");
+        }
+        assert!(!state.borrow().modals.is_modal(parent));
+    }
+
+    const POINT: Selector = Selector::new("druid-test.point");
+
+    /// Shows a pointer cursor when it receives `POINT`.
+    fn pointer(received: Rc<Cell<bool>>) -> ModularWidget<Rc<Cell<bool>>, bool> {
+        filler(received).event_fn(|received, ctx, event, _, _| {
+            if let Event::Command(cmd) = event {
+                if cmd.is(POINT) {
+                    ctx.set_cursor(&Cursor::OpenHand);
+                    received.set(true);
+                    ctx.set_handled();
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn targeted_command_uses_target_window_ctx() {
+        let state = test_state(false, None);
+        let received = Rc::new(Cell::new(false));
+        let widget = WidgetId::next();
+        let source = open_window(&state, spinner());
+        let target = open_window(&state, pointer(received.clone()).with_id(widget));
+        let mut state = state.borrow_mut();
+
+        // the command comes from the source window, but the cursor belongs to
+        // the target window, so the source's WinCtx must not be touched.
+        with_mock_win_ctx(|win_ctx| {
+            let event = Event::TargetedCommand(widget.into(), Command::from(POINT));
+            assert!(state.do_event(source, event, win_ctx));
+            assert!(win_ctx.calls().cursors.is_empty());
+        });
+        assert!(received.get());
+        let target_cursor = state.windows.get(target).unwrap().cursor();
+        assert_eq!(target_cursor, Some(&Cursor::OpenHand));
+    }
+
+    #[test]
+    fn global_command_reaches_every_window() {
+        let state = test_state(false, None);
+        let received = [Rc::new(Cell::new(false)), Rc::new(Cell::new(false))];
+        let first = open_window(&state, pointer(received[0].clone()));
+        open_window(&state, pointer(received[1].clone()));
+
+        // both windows handle the command, but neither stops the other.
+        with_win_ctx(|win_ctx| {
+            let event = Event::TargetedCommand(Target::Global, Command::from(POINT));
+            assert!(state.borrow_mut().do_event(first, event, win_ctx));
+        });
+        assert!(received.iter().all(|r| r.get()));
+    }
+
+    /// Records the selectors of the commands it receives.
+    fn command_log(
+        selectors: Rc<RefCell<Vec<SelectorSymbol>>>,
+    ) -> ModularWidget<Rc<RefCell<Vec<SelectorSymbol>>>, bool> {
+        filler(selectors).event_fn(|selectors, _, event, _, _| {
+            if let Event::Command(cmd) = event {
+                selectors.borrow_mut().push(cmd.symbol());
+            }
+        })
+    }
+
+    #[test]
+    fn retries_move_when_their_window_closes() {
+        const FLAKY: Selector = Selector::new("druid-tests.flaky");
+        let state = test_state(false, None);
+        let selectors = Rc::new(RefCell::new(Vec::new()));
+        let mut first = open_handler(&state, spinner());
+        let mut second = open_handler(&state, command_log(selectors.clone()));
+
+        // nobody handles the command, so it waits on a timer of the window
+        // that dispatched it.
+        with_win_ctx(|win_ctx| {
+            let flaky = Command::from(FLAKY).retry(3, Duration::from_millis(10));
+            first.dispatch_cmd(Target::Global, flaky, win_ctx);
+        });
+        assert_eq!(*selectors.borrow(), vec![FLAKY.symbol()]);
+        let timers = state.borrow().retries.timers();
+        assert_eq!(timers.len(), 1);
+        assert_eq!(timers[0].1, first.window_id);
+
+        with_win_ctx(|win_ctx| {
+            first.destroy(win_ctx);
+            assert!(state.borrow().retries.timers().is_empty());
+            second.idle(REARM_IDLE_TOKEN, win_ctx);
+        });
+        let timers = state.borrow().retries.timers();
+        assert_eq!(timers.len(), 1);
+        assert_eq!(timers[0].1, second.window_id);
+
+        // the next attempt fails again, and waits on the same window.
+        with_win_ctx(|win_ctx| second.timer(timers[0].0, win_ctx));
+        assert_eq!(*selectors.borrow(), vec![FLAKY.symbol(); 2]);
+        let timers = state.borrow().retries.timers();
+        assert_eq!(timers.len(), 1);
+        assert_eq!(timers[0].1, second.window_id);
+    }
+
+    /// Move the mock clock forward by `duration`, firing the timers of
+    /// `win_ctx` that come due, in order of their deadlines.
+    fn advance_time<T: Data>(
+        handler: &mut DruidHandler<T>,
+        clock: &Clock,
+        win_ctx: &mut MockWinCtx,
+        duration: Duration,
+    ) {
+        let end = clock.now() + duration;
+        loop {
+            win_ctx.timers().sort_by_key(|(deadline, _)| *deadline);
+            match win_ctx.timers().first().copied() {
+                Some((deadline, token)) if deadline <= end => {
+                    win_ctx.timers().remove(0);
+                    clock.advance(deadline.saturating_duration_since(clock.now()));
+                    handler.timer(token, win_ctx);
+                }
+                _ => break,
+            }
+        }
+        clock.advance(end.saturating_duration_since(clock.now()));
+    }
+
+    #[test]
+    fn debounced_queries_only_deliver_the_last() {
+        const QUERY: Selector<String> = Selector::new("druid-tests.query");
+        let interval = Duration::from_millis(100);
+        let state = AppLauncher::with_window(WindowDesc::new(spinner))
+            .debounce_command(QUERY, interval)
+            .build_state(false);
+        let clock = Clock::mock();
+        state.borrow_mut().clock = clock.clone();
+        let queries = Rc::new(RefCell::new(Vec::new()));
+        let root = filler(queries.clone()).event_fn(|queries, _, event, _, _| {
+            if let Event::Command(cmd) = event {
+                if let Some(query) = cmd.get(QUERY) {
+                    queries.borrow_mut().push(query.clone());
+                }
+            }
+        });
+        let mut handler = open_handler(&state, root);
+        let target = Target::Window(handler.window_id);
+
+        with_mock_win_ctx(|win_ctx| {
+            // a query for every keystroke, typed faster than the interval.
+            for query in &["d", "dr", "dru"] {
+                let cmd = Command::new(QUERY, query.to_string());
+                state.borrow_mut().command_queue.push_back((target, cmd));
+                handler.process_commands(win_ctx);
+                advance_time(&mut handler, &clock, win_ctx, Duration::from_millis(10));
+            }
+            assert!(queries.borrow().is_empty());
+
+            // the timers of the first two queries are stale...
+            advance_time(&mut handler, &clock, win_ctx, Duration::from_millis(80));
+            assert!(queries.borrow().is_empty());
+            // ...and the last query is delivered once its interval has passed.
+            advance_time(&mut handler, &clock, win_ctx, Duration::from_millis(10));
+            assert!(win_ctx.timers().is_empty());
+        });
+        assert_eq!(*queries.borrow(), vec!["dru".to_string()]);
+    }
+
+    #[test]
+    fn debounced_commands_move_when_their_window_closes() {
+        const QUERY: Selector<String> = Selector::new("druid-tests.query");
+        let state = test_state(false, None);
+        let interval = Duration::from_millis(10);
+        state.borrow_mut().debouncer.add(QUERY.symbol(), interval);
+        let selectors = Rc::new(RefCell::new(Vec::new()));
+        let mut first = open_handler(&state, spinner());
+        let mut second = open_handler(&state, command_log(selectors.clone()));
+
+        // the query is held on a timer of the window that dispatched it.
+        with_win_ctx(|win_ctx| {
+            let query = Command::new(QUERY, "dru".to_string());
+            first.dispatch_cmd(Target::Global, query, win_ctx);
+        });
+        assert!(selectors.borrow().is_empty());
+        let timers = state.borrow().debouncer.timers();
+        assert_eq!(timers.len(), 1);
+        assert_eq!(timers[0].1, first.window_id);
+
+        with_win_ctx(|win_ctx| {
+            first.destroy(win_ctx);
+            assert!(state.borrow().debouncer.timers().is_empty());
+            second.idle(REARM_IDLE_TOKEN, win_ctx);
+        });
+        let timers = state.borrow().debouncer.timers();
+        assert_eq!(timers.len(), 1);
+        assert_eq!(timers[0].1, second.window_id);
+
+        // the new timer releases the query.
+        with_win_ctx(|win_ctx| second.timer(timers[0].0, win_ctx));
+        assert_eq!(*selectors.borrow(), vec![QUERY.symbol()]);
+        assert!(state.borrow().debouncer.timers().is_empty());
+    }
+
+    #[test]
+    fn cancelled_dialogs_are_reported() {
+        let state = test_state(false, None);
+        let selectors = Rc::new(RefCell::new(Vec::new()));
+        let mut handler = open_handler(&state, command_log(selectors.clone()));
+        let id = handler.window_id;
+
+        with_win_ctx(|win_ctx| {
+            handler.open_file(FileDialogToken::next(), None, win_ctx);
+            handler.save_as(FileDialogToken::next(), None, win_ctx);
+            // this context can only show dialogs while we wait, and they are
+            // always cancelled; the result is delivered all the same.
+            let options = FileDialogOptions::default();
+            let open = Command::new(sys_cmd::SHOW_OPEN_PANEL, options.clone());
+            handler.dispatch_cmd(id.into(), open, win_ctx);
+            let save = Command::new(sys_cmd::SHOW_SAVE_PANEL, options);
+            handler.dispatch_cmd(id.into(), save, win_ctx);
+        });
+        let cancelled = [
+            sys_cmd::OPEN_PANEL_CANCELLED.symbol(),
+            sys_cmd::SAVE_PANEL_CANCELLED.symbol(),
+        ];
+        assert_eq!(
+            *selectors.borrow(),
+            [&cancelled[..], &cancelled[..]].concat()
+        );
+    }
+
+    #[test]
+    fn focus_moves_between_windows() {
+        let state = test_state(false, None);
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut handlers = Vec::new();
+        for &name in &["a", "b"] {
+            // records the focus events it receives, with the name of its window.
+            let widget = filler(log.clone()).event_fn(move |log, _, event, _, _| match event {
+                Event::WindowGotFocus => log.borrow_mut().push((name, true)),
+                Event::WindowLostFocus => log.borrow_mut().push((name, false)),
+                _ => (),
+            });
+            handlers.push(open_handler(&state, widget));
+        }
+
+        with_win_ctx(|win_ctx| {
+            handlers[0].got_focus(win_ctx);
+            // the platform reports the new window first; "a" still hears
+            // about it first, and only once.
+            handlers[1].got_focus(win_ctx);
+            handlers[0].lost_focus(win_ctx);
+            handlers[1].lost_focus(win_ctx);
+        });
+        assert_eq!(
+            *log.borrow(),
+            vec![("a", true), ("a", false), ("b", true), ("b", false)]
+        );
+    }
+
+    #[test]
+    fn blur_goes_through_the_blurred_windows_handler() {
+        const SAVE: Selector = Selector::new("druid-test.save");
+        let state = test_state(false, None);
+        let log = Rc::new(RefCell::new(Vec::new()));
+        // logs what it hears, and saves when it loses focus.
+        let widget = filler(log.clone()).event_fn(|log, ctx, event, _, _| match event {
+            Event::MouseMoved(_) => log.borrow_mut().push("move"),
+            Event::WindowLostFocus => {
+                log.borrow_mut().push("blur");
+                ctx.submit_command(SAVE, None);
+            }
+            Event::Command(cmd) if cmd.is(SAVE) => log.borrow_mut().push("save"),
+            _ => (),
+        });
+        let mut first = open_handler(&state, widget);
+        let mut second = open_handler(&state, spinner());
+        let mouse = left_shell_mouse(10., 10., 0);
+
+        with_piet(|piet| {
+            with_win_ctx(|win_ctx| {
+                first.size(100, 100, win_ctx);
+                first.paint(piet, WHOLE_WINDOW, win_ctx);
+            })
+        });
+
+        with_win_ctx(|win_ctx| {
+            first.got_focus(win_ctx);
+            first.mouse_move(&mouse, win_ctx);
+            // the move is still pending when the other window takes focus.
+            second.got_focus(win_ctx);
+        });
+        assert_eq!(*log.borrow(), vec!["move", "blur", "save"]);
+    }
+
+    #[test]
+    fn delegate_sees_focused_window() {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        // records the focused window as of each key press.
+        let delegate = ModularDelegate::new(seen.clone()).event_fn(|seen, event, _, _, ctx| {
+            if let Event::KeyDown(_) = event {
+                seen.borrow_mut().push(ctx.focused_window());
+            }
+            Some(event)
+        });
+        let state = test_state(false, Some(Box::new(delegate)));
+        let mut handlers = [
+            open_handler(&state, spinner()),
+            open_handler(&state, spinner()),
+        ];
+        let second = handlers[1].window_id;
+        let key = || KeyEvent::for_test(KeyModifiers::default(), "a", KeyCode::KeyA);
+
+        with_win_ctx(|win_ctx| {
+            handlers[0].key_down(key(), win_ctx);
+            handlers[1].got_focus(win_ctx);
+            // the event comes from one window, while another has focus.
+            handlers[0].key_down(key(), win_ctx);
+            handlers[1].lost_focus(win_ctx);
+            handlers[0].key_down(key(), win_ctx);
+            handlers[1].got_focus(win_ctx);
+            state.borrow_mut().remove_window(second, win_ctx);
+            handlers[0].key_down(key(), win_ctx);
+        });
+        assert_eq!(*seen.borrow(), vec![None, Some(second), None, None]);
+    }
+
+    const SAVE_ALL: Selector = Selector::new("druid-test.save-all");
+
+    /// Handles `SAVE_ALL` itself, without involving any widget.
+    fn saver(saves: Rc<Cell<usize>>) -> ModularDelegate<Rc<Cell<usize>>, bool> {
+        ModularDelegate::new(saves).command_fn(|saves, _, cmd, _, _, _| {
+            if cmd.is(SAVE_ALL) {
+                saves.set(saves.get() + 1);
+                return Handled::Yes;
+            }
+            Handled::No
+        })
+    }
+
+    const OPEN_DOCUMENT: Selector = Selector::new("druid-test.open-document");
+
+    #[test]
+    fn delegate_opens_window() {
+        // opens a new window for each `OPEN_DOCUMENT`.
+        let opener = ModularDelegate::new(()).command_fn(|_, _, cmd, _, _, ctx| {
+            if cmd.is(OPEN_DOCUMENT) {
+                ctx.new_window(WindowDesc::new(spinner)).unwrap();
+                return Handled::Yes;
+            }
+            Handled::No
+        });
+        let state = test_state(false, Some(Box::new(opener)));
+        let id = open_window(&state, spinner());
+        let mut state = state.borrow_mut();
+
+        with_win_ctx(|win_ctx| {
+            let open = Event::TargetedCommand(id.into(), Command::from(OPEN_DOCUMENT));
+            assert!(state.do_event(id, open, win_ctx));
+        });
+        let (target, cmd) = state.command_queue.pop_front().unwrap();
+        assert_eq!(target, Target::Window(id));
+        let desc = cmd.take(sys_cmd::NEW_WINDOW).unwrap();
+        assert!(desc.into_desc::<bool>().is_some());
+    }
+
+    #[test]
+    fn delegate_intercepts_commands() {
+        let saves = Rc::new(Cell::new(0));
+        let state = test_state(false, Some(Box::new(saver(saves.clone()))));
+        let selectors = Rc::new(RefCell::new(Vec::new()));
+        let id = open_window(&state, command_log(selectors.clone()));
+        let mut state = state.borrow_mut();
+
+        with_win_ctx(|win_ctx| {
+            let save = Event::TargetedCommand(id.into(), Command::from(SAVE_ALL));
+            assert!(state.do_event(id, save, win_ctx));
+            let other = Event::TargetedCommand(id.into(), Command::from(POINT));
+            state.do_event(id, other, win_ctx);
+        });
+        assert_eq!(saves.get(), 1);
+        assert_eq!(*selectors.borrow(), vec![POINT.symbol()]);
+    }
+
+    /// Counts key events.
+    fn count_keys(keys: &Cell<usize>, event: &Event) {
+        if let Event::KeyDown(_) | Event::KeyUp(_) = event {
+            keys.set(keys.get() + 1);
+        }
+    }
+
+    /// Counts the key events it receives.
+    fn key_counter(keys: Rc<Cell<usize>>) -> ModularWidget<Rc<Cell<usize>>, bool> {
+        filler(keys).event_fn(|keys, _, event, _, _| count_keys(keys, event))
+    }
+
+    #[test]
+    fn event_filter_drops_key_events() {
+        let delegate_keys = Rc::new(Cell::new(0));
+        let widget_keys = Rc::new(Cell::new(0));
+        let delegate =
+            ModularDelegate::new(delegate_keys.clone()).event_fn(|keys, event, _, _, _| {
+                count_keys(keys, &event);
+                Some(event)
+            });
+        let state = test_state(false, Some(Box::new(delegate)));
+        let filtered = Rc::new(Cell::new(0));
+        let filtered2 = filtered.clone();
+        state.borrow_mut().event_filter = Some(Box::new(move |event: &Event, _| match event {
+            Event::KeyDown(_) | Event::KeyUp(_) => {
+                filtered2.set(filtered2.get() + 1);
+                EventFilterResult::Drop
+            }
+            _ => EventFilterResult::Pass,
+        }));
+        let mut handler = open_handler(&state, key_counter(widget_keys.clone()));
+
+        with_win_ctx(|win_ctx| {
+            let key = KeyEvent::for_test(KeyModifiers::default(), "a", KeyCode::KeyA);
+            handler.key_down(key, win_ctx);
+            handler.key_up(key, win_ctx);
+        });
+        assert_eq!(filtered.get(), 2);
+        assert_eq!(delegate_keys.get(), 0);
+        assert_eq!(widget_keys.get(), 0);
+    }
+
+    #[test]
+    fn hotkeys_win_over_widgets() {
+        let saves = Rc::new(Cell::new(0));
+        let state = test_state(false, Some(Box::new(saver(saves.clone()))));
+        state.borrow_mut().hotkeys = vec![
+            (HotKey::new(RawMods::Ctrl, "s"), SAVE_ALL.into()),
+            (HotKey::new(None, "x"), SAVE_ALL.into()),
+        ];
+        let widget_keys = Rc::new(Cell::new(0));
+        let widget_id = WidgetId::next();
+        let root = key_counter(widget_keys.clone()).with_id(widget_id);
+        let mut handler = open_handler(&state, root);
+        let id = handler.window_id;
+
+        with_win_ctx(|win_ctx| {
+            let save = KeyEvent::for_test(RawMods::Ctrl, "s", KeyCode::KeyS);
+            assert!(handler.key_down(save, win_ctx));
+            // with nothing focused, plain keys can be bound too.
+            let x = KeyEvent::for_test(KeyModifiers::default(), "x", KeyCode::KeyX);
+            assert!(handler.key_down(x, win_ctx));
+        });
+        assert_eq!(saves.get(), 2);
+        assert_eq!(widget_keys.get(), 0);
+
+        state.borrow_mut().windows.get_mut(id).unwrap().focus = Some(widget_id);
+        with_win_ctx(|win_ctx| {
+            let x = KeyEvent::for_test(KeyModifiers::default(), "x", KeyCode::KeyX);
+            handler.key_down(x, win_ctx);
+            let save = KeyEvent::for_test(RawMods::Ctrl, "s", KeyCode::KeyS);
+            handler.key_down(save, win_ctx);
+        });
+        assert_eq!(saves.get(), 3);
+        assert_eq!(widget_keys.get(), 1);
+    }
+
+    #[test]
+    fn closing_hides_window() {
+        let state = test_state(false, None);
+        let updates = Rc::new(Cell::new(0));
+        let paints = Rc::new(Cell::new(0));
+        let paints2 = paints.clone();
+        let counter = update_counter(updates.clone())
+            .paint_fn(move |_, _, _, _| paints2.set(paints2.get() + 1));
+        let mut handler = open_handler(&state, counter);
+        let id = handler.window_id;
+        state
+            .borrow_mut()
+            .windows
+            .get_mut(id)
+            .unwrap()
+            .hide_on_close = true;
+
+        let paint = |handler: &mut DruidHandler<bool>| {
+            with_piet(|piet| {
+                with_win_ctx(|win_ctx| {
+                    handler.paint(piet, WHOLE_WINDOW, win_ctx);
+                })
+            });
+        };
+
+        with_win_ctx(|win_ctx| {
+            assert!(!handler.request_close(win_ctx));
+            state.borrow_mut().data = true;
+            state.borrow_mut().do_update(win_ctx);
+        });
+        paint(&mut handler);
+        assert!(state.borrow_mut().windows.get(id).unwrap().hidden);
+        assert_eq!(updates.get(), 1);
+        assert_eq!(paints.get(), 0);
+
+        state.borrow_mut().show_window(id);
+        paint(&mut handler);
+        assert!(!state.borrow_mut().windows.get(id).unwrap().hidden);
+        assert_eq!(paints.get(), 1);
+    }
+
+    #[test]
+    fn delegate_notified_when_windows_gone() {
+        let closed = Rc::new(Cell::new(0));
+        // counts how often the app was left without windows.
+        let delegate =
+            ModularDelegate::new(closed.clone()).all_windows_closed_fn(|closed, data, _, _| {
+                closed.set(closed.get() + 1);
+                *data = true;
+            });
+        let state = test_state(false, Some(Box::new(delegate)));
+        let ids = [
+            open_window(&state, spinner()),
+            open_window(&state, spinner()),
+        ];
+        let mut state = state.borrow_mut();
+
+        with_win_ctx(|win_ctx| {
+            state.remove_window(ids[0], win_ctx);
+            assert_eq!(closed.get(), 0);
+            state.remove_window(ids[1], win_ctx);
+        });
+        assert_eq!(closed.get(), 1);
+        assert!(state.data);
+    }
+
+    #[test]
+    fn hidden_windows_close_with_the_last_visible_one() {
+        let closed = Rc::new(Cell::new(0));
+        let delegate =
+            ModularDelegate::new(closed.clone()).all_windows_closed_fn(|closed, _, _, _| {
+                closed.set(closed.get() + 1);
+            });
+        let state = test_state(false, Some(Box::new(delegate)));
+        state.borrow_mut().quit_on_last_window_close = true;
+        let mut hidden = open_handler(&state, spinner());
+        let hidden_id = hidden.window_id;
+        state
+            .borrow_mut()
+            .windows
+            .get_mut(hidden_id)
+            .unwrap()
+            .hide_on_close = true;
+        let visible = open_window(&state, spinner());
+
+        with_win_ctx(|win_ctx| {
+            assert!(!hidden.request_close(win_ctx));
+            assert!(!state.borrow().should_quit_after_close());
+            assert_eq!(closed.get(), 0);
+
+            let handles = state.borrow_mut().remove_window(visible, win_ctx);
+            assert_eq!(handles.len(), 1);
+            assert!(state.borrow().windows.get(hidden_id).is_none());
+            assert!(state.borrow().should_quit_after_close());
+            assert_eq!(closed.get(), 1);
+
+            // the platform destroying the hidden window later is a no-op.
+            hidden.destroy(win_ctx);
+            assert_eq!(closed.get(), 1);
+        });
+    }
+
+    #[test]
+    fn closing_last_window_quits() {
+        let removed = Rc::new(RefCell::new(Vec::new()));
+        let delegate = ModularDelegate::new(removed.clone())
+            .window_removed_fn(|removed, id, _, _, _| removed.borrow_mut().push(id));
+        let state = test_state(false, Some(Box::new(delegate)));
+        let ids = [
+            open_window(&state, spinner()),
+            open_window(&state, spinner()),
+        ];
+        let mut state = state.borrow_mut();
+        state.quit_on_last_window_close = true;
+
+        with_win_ctx(|win_ctx| {
+            state.remove_window(ids[0], win_ctx);
+            assert_eq!(*removed.borrow(), vec![ids[0]]);
+            assert!(!state.should_quit_after_close());
+
+            // a window that has not connected yet keeps the app alive.
+            let replacement = WindowId::next();
+            let window = PendingWindow::new(spinner(), LocalizedString::new(""), None);
+            state.add_window(replacement, window);
+            state.remove_window(ids[1], win_ctx);
+            assert!(!state.should_quit_after_close());
+
+            // the delegate hears about the last window before the check, so
+            // it has the chance to replace it.
+            state.connect(replacement, WindowHandle::default());
+            state.remove_window(replacement, win_ctx);
+            assert_eq!(*removed.borrow(), vec![ids[0], ids[1], replacement]);
+            assert!(state.should_quit_after_close());
+
+            state.quit_on_last_window_close = false;
+            assert!(!state.should_quit_after_close());
+        });
+    }
+
+    #[test]
+    fn delegate_vetoes_close() {
+        let confirmed = Rc::new(Cell::new(false));
+        let asked = Rc::new(Cell::new(0));
+        // keeps windows open until the user has confirmed closing them.
+        let delegate = ModularDelegate::new((confirmed.clone(), asked.clone()))
+            .window_should_close_fn(|(confirmed, asked), _, _, _, _| {
+                asked.set(asked.get() + 1);
+                confirmed.get()
+            });
+        let state = test_state(false, Some(Box::new(delegate)));
+        let mut handler = open_handler(&state, spinner());
+
+        with_win_ctx(|win_ctx| {
+            assert!(!handler.request_close(win_ctx));
+            confirmed.set(true);
+            assert!(handler.request_close(win_ctx));
+        });
+        assert_eq!(asked.get(), 2);
+    }
+
+    #[test]
+    fn os_close_can_be_vetoed() {
+        // the widget keeps the window open while it has unsaved changes.
+        let unsaved = Rc::new(Cell::new(true));
+        let widget = filler(unsaved.clone()).event_fn(|unsaved, ctx, event, _, _| {
+            if let Event::WindowCloseRequested = event {
+                if unsaved.get() {
+                    ctx.set_handled();
+                }
+            }
+        });
+        // the delegate swallows the request while the app is busy.
+        let busy = Rc::new(Cell::new(false));
+        let delegate =
+            ModularDelegate::new(busy.clone()).event_fn(|busy, event, _, _, _| match event {
+                Event::WindowCloseRequested if busy.get() => None,
+                event => Some(event),
+            });
+        let state = test_state(false, Some(Box::new(delegate)));
+        let mut handler = open_handler(&state, widget);
+        let id = handler.window_id;
+
+        with_win_ctx(|win_ctx| {
+            assert!(!handler.request_close(win_ctx));
+            assert!(state.borrow().windows.get(id).is_some());
+
+            unsaved.set(false);
+            busy.set(true);
+            assert!(!handler.request_close(win_ctx));
+            assert!(state.borrow().windows.get(id).is_some());
+
+            busy.set(false);
+            assert!(handler.request_close(win_ctx));
+        });
+    }
+
+    #[test]
+    fn delegate_vetoes_quit() {
+        let asked = Rc::new(Cell::new(0));
+        // never lets the application quit; counts how often it was asked.
+        let delegate =
+            ModularDelegate::new(asked.clone()).application_should_quit_fn(|asked, data, _, _| {
+                asked.set(asked.get() + 1);
+                *data = true;
+                false
+            });
+        let state = test_state(false, Some(Box::new(delegate)));
+        let mut handler = open_handler(&state, spinner());
+        let id = handler.window_id;
+
+        with_win_ctx(|win_ctx| {
+            assert!(handler.handle_cmd(id.into(), sys_cmd::QUIT_APP.into(), win_ctx));
+        });
+        assert_eq!(asked.get(), 1);
+        // the delegate saw, and could change, the app state.
+        assert!(state.borrow().data);
+        assert!(state.borrow().windows.get(id).is_some());
+    }
+
+    const BUMP: Selector<u32> = Selector::new("druid-test.bump");
+    const ECHO: Selector<u32> = Selector::new("druid-test.echo");
+
+    #[test]
+    fn ext_events_are_coalesced() {
+        let state = test_state(0u32, None);
+        let sink = state.borrow().ext_event_host.make_sink();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let updates = Rc::new(Cell::new(0));
+        // answers each `BUMP` with an `ECHO`, logging both, and counts updates.
+        let bumper = update_counter(updates.clone()).event_fn({
+            let log = log.clone();
+            move |_, ctx, event, data, _| {
+                if let Event::Command(cmd) = event {
+                    if let Some(n) = cmd.get(BUMP) {
+                        log.borrow_mut().push(("bump", *n));
+                        *data += 1;
+                        ctx.submit_command(ECHO.with(*n), None);
+                    } else if let Some(n) = cmd.get(ECHO) {
+                        log.borrow_mut().push(("echo", *n));
+                    }
+                }
+            }
+        });
+        let mut handler = open_handler(&state, bumper);
+        let id = handler.window_id;
+
+        for n in 0..10_000 {
+            sink.submit_command(BUMP, n, Target::Window(id)).unwrap();
+        }
+        with_win_ctx(|win_ctx| handler.idle(EXT_EVENT_IDLE_TOKEN, win_ctx));
+
+        assert_eq!(state.borrow().data, 10_000);
+        assert_eq!(updates.get(), 1);
+        let log = log.borrow();
+        assert_eq!(log.len(), 20_000);
+        // each echo is handled before the next bump.
+        assert_eq!(
+            &log[..4],
+            &[("bump", 0), ("echo", 0), ("bump", 1), ("echo", 1)]
+        );
+    }
+
+    #[test]
+    fn ext_events_are_refused_without_windows() {
+        let state = test_state(0u32, None);
+        let sink = state.borrow().ext_event_host.make_sink();
+        // before the first window opens, commands wait for it.
+        assert_eq!(sink.submit_command(BUMP, 0, None), Ok(()));
+        let id = open_window(&state, filler(()));
+        assert_eq!(sink.submit_command(BUMP, 1, None), Ok(()));
+
+        with_win_ctx(|win_ctx| {
+            state.borrow_mut().remove_window(id, win_ctx);
+        });
+        assert_eq!(
+            sink.submit_command(BUMP, 2, None),
+            Err(ExtEventError::WindowClosed)
+        );
+
+        open_window(&state, filler(()));
+        assert_eq!(sink.submit_command(BUMP, 3, None), Ok(()));
+    }
+
+    #[test]
+    fn set_window_title_outlives_updates() {
+        let state = test_state(false, None);
+        let id = WindowId::next();
+        let title = LocalizedString::new("druid-test.untitled").with_placeholder("Untitled");
+        let window = PendingWindow::new(spinner(), title, None);
+        state.borrow_mut().add_window(id, window);
+        state.borrow_mut().connect(id, WindowHandle::default());
+        let mut handler = DruidHandler::new_shared(state.clone(), id);
+
+        with_win_ctx(|win_ctx| {
+            let cmd = sys_cmd::SET_WINDOW_TITLE.with("notes.txt*".to_string());
+            state.borrow_mut().command_queue.push_back((id.into(), cmd));
+            handler.idle(RUN_COMMANDS_TOKEN, win_ctx);
+            state.borrow_mut().data = true;
+            state.borrow_mut().do_update(win_ctx);
+        });
+        let mut state = state.borrow_mut();
+        let win = state.windows.get_mut(id).unwrap();
+        assert_eq!(win.fixed_title.as_deref(), Some("notes.txt*"));
+    }
+
+    #[test]
+    fn window_state_commands_are_consumed() {
+        let state = test_state(false, None);
+        let selectors = Rc::new(RefCell::new(Vec::new()));
+        let mut handler = open_handler(&state, command_log(selectors.clone()));
+        let id = handler.window_id;
+
+        let commands = vec![
+            sys_cmd::SET_WINDOW_SIZE.with(Size::new(640., 480.)),
+            sys_cmd::SET_WINDOW_POSITION.with(Point::new(20., 20.)),
+            sys_cmd::MINIMIZE_WINDOW.into(),
+            sys_cmd::MAXIMIZE_WINDOW.into(),
+            sys_cmd::RESTORE_WINDOW.into(),
+        ];
+        with_win_ctx(|win_ctx| {
+            for cmd in commands {
+                // the unknown window is ignored, with a warning.
+                let mut state = state.borrow_mut();
+                state
+                    .command_queue
+                    .push_back((WindowId::next().into(), cmd.clone()));
+                state.command_queue.push_back((id.into(), cmd));
+            }
+            handler.idle(RUN_COMMANDS_TOKEN, win_ctx);
+        });
+        assert!(selectors.borrow().is_empty());
+    }
+
+    #[test]
+    fn window_moves_are_reported_once() {
+        let state = test_state(false, None);
+        let moves = Rc::new(RefCell::new(Vec::new()));
+        // records the positions of the window, from `WindowMoved` events.
+        let widget = filler(moves.clone()).event_fn(|moves, _, event, _, _| {
+            if let Event::WindowMoved(pos) = event {
+                moves.borrow_mut().push(*pos);
+            }
+        });
+        let mut handler = open_handler(&state, widget);
+
+        with_win_ctx(|win_ctx| {
+            handler.position(Point::new(10., 20.), win_ctx);
+            // a resize that doesn't move the window.
+            handler.size(800, 600, win_ctx);
+            handler.position(Point::new(10., 20.), win_ctx);
+            handler.position(Point::new(1930., 20.), win_ctx);
+        });
+        assert_eq!(
+            *moves.borrow(),
+            vec![Point::new(10., 20.), Point::new(1930., 20.)]
+        );
+    }
+
+    #[test]
+    fn scale_change_relayouts_at_new_scale() {
+        let state = test_state(false, None);
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let layouts = Rc::new(RefCell::new(Vec::new()));
+        // records scale changes, and the scale and size of each layout.
+        let widget = filler((changes.clone(), layouts.clone()))
+            .event_fn(|(changes, _), _, event, _, _| {
+                if let Event::ScaleChanged(scale) = event {
+                    changes.borrow_mut().push(*scale);
+                }
+            })
+            .layout_fn(|(_, layouts), ctx, bc, _, _| {
+                layouts.borrow_mut().push((ctx.scale(), bc.max()));
+                bc.max()
+            });
+        let mut handler = open_handler(&state, widget);
+
+        with_win_ctx(|win_ctx| {
+            handler.size(800, 600, win_ctx);
+            handler.scale(2.0, win_ctx);
+            handler.scale(2.0, win_ctx);
+        });
+        with_piet(|piet| {
+            with_win_ctx(|win_ctx| {
+                handler.paint(piet, WHOLE_WINDOW, win_ctx);
+            })
+        });
+        assert_eq!(*changes.borrow(), vec![2.0]);
+        assert_eq!(*layouts.borrow(), vec![(2.0, Size::new(400., 300.))]);
+    }
+
+    #[test]
+    fn composition_reaches_focused_widget() {
+        let state = test_state(false, None);
+        let log = Rc::new(RefCell::new(Vec::new()));
+        // takes focus when the window connects, and records what it composes.
+        let composer = filler(log.clone()).event_fn(|log, ctx, event, _, _| match event {
+            Event::WindowConnected => ctx.request_focus(),
+            Event::ImeComposition { text, cursor } => {
+                log.borrow_mut().push(text.clone());
+                let caret = *cursor as f64;
+                ctx.set_ime_cursor_area(Rect::new(caret, 0., caret + 1., 10.));
+            }
+            Event::ImeCommit(text) => log.borrow_mut().push(format!("commit {}", text)),
+            _ => (),
+        });
+        let mut handler = open_handler(&state, composer.padding(10.));
+
+        with_piet(|piet| {
+            with_win_ctx(|win_ctx| {
+                handler.connected(win_ctx);
+                handler.size(100, 100, win_ctx);
+                handler.paint(piet, WHOLE_WINDOW, win_ctx);
+            })
+        });
+        with_mock_win_ctx(|win_ctx| {
+            handler.ime_composition("ka", 2, win_ctx);
+            handler.ime_commit("か", win_ctx);
+            // the area is reported in window coordinates.
+            assert_eq!(win_ctx.calls().ime_areas.len(), 1);
+            assert_eq!(win_ctx.calls().ime_areas[0].origin(), Point::new(12., 10.));
+            assert_eq!(win_ctx.calls().ime_areas[0].size(), Size::new(1., 10.));
+        });
+        assert_eq!(
+            *log.borrow(),
+            vec!["ka".to_string(), "commit か".to_string()]
+        );
+    }
+
+    /// A `MouseMoved` event at this position in the window.
+    fn mouse_at(x: f64, y: f64) -> Event {
+        Event::MouseMoved(left_mouse(x, y, 0))
+    }
+
+    #[test]
+    fn cursor_reverts_when_pointer_leaves_widget() {
+        let state = test_state(false, None);
+        // shows an I-beam while it is hot.
+        let hover = filler(()).event_fn(|_, ctx, event, _, _| {
+            if let Event::MouseMoved(_) = event {
+                if ctx.is_hot() {
+                    ctx.set_cursor(&Cursor::IBeam);
+                }
+            }
+        });
+        let mut handler = open_handler(&state, hover.padding(20.));
+
+        with_piet(|piet| {
+            with_win_ctx(|win_ctx| {
+                handler.size(100, 100, win_ctx);
+                handler.paint(piet, WHOLE_WINDOW, win_ctx);
+            })
+        });
+
+        with_mock_win_ctx(|win_ctx| {
+            handler.do_event(mouse_at(50., 50.), win_ctx);
+            handler.do_event(mouse_at(55., 50.), win_ctx);
+            handler.do_event(mouse_at(5., 50.), win_ctx);
+            handler.do_event(mouse_at(6., 50.), win_ctx);
+            // the platform may change the cursor while the pointer is away.
+            handler.do_event(Event::MouseLeave, win_ctx);
+            handler.do_event(mouse_at(6., 50.), win_ctx);
+            assert_eq!(
+                win_ctx.calls().cursors,
+                vec![Cursor::IBeam, Cursor::Arrow, Cursor::Arrow]
+            );
+        });
+    }
+
+    #[test]
+    fn inspector_toggles_per_window() {
+        let state = test_state(false, None);
+        let mut handler = open_handler(&state, spinner().padding(20.));
+        let id = handler.window_id;
+        let inspecting = |state: &Rc<RefCell<AppState<bool>>>| {
+            let state = state.borrow();
+            state.windows.get(id).unwrap().inspecting(&state.env)
+        };
+        let toggle = Event::TargetedCommand(id.into(), Command::from(sys_cmd::TOGGLE_INSPECTOR));
+
+        assert!(!inspecting(&state));
+        with_piet(|piet| {
+            with_win_ctx(|win_ctx| {
+                handler.size(100, 100, win_ctx);
+                handler.do_event(toggle.clone(), win_ctx);
+                handler.do_event(mouse_at(50., 50.), win_ctx);
+                // the overlay paints over the window, labelling the spinner.
+                handler.paint(piet, WHOLE_WINDOW, win_ctx);
+            })
+        });
+        assert!(inspecting(&state));
+
+        with_win_ctx(|win_ctx| assert!(handler.do_event(toggle, win_ctx)));
+        assert!(!inspecting(&state));
+    }
+
+    #[test]
+    fn set_theme_reaches_every_window() {
+        let state = test_state(false, None);
+        let mut logs = Vec::new();
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let changes = Rc::new(Cell::new(0));
+            let colors = Rc::new(RefCell::new(Vec::new()));
+            // records theme changes, and the label color of each update.
+            let widget = filler((changes.clone(), colors.clone()))
+                .lifecycle_fn(|(changes, _), _, event, _, _| {
+                    if let LifeCycle::ThemeChanged = event {
+                        changes.set(changes.get() + 1);
+                    }
+                })
+                .update_fn(|(_, colors), _, _, _, env| {
+                    let color = env.get(theme::LABEL_COLOR).as_rgba_u32();
+                    colors.borrow_mut().push(color);
+                });
+            ids.push(open_window(&state, widget));
+            logs.push((changes, colors));
         }
+        let mut handler = DruidHandler::new_shared(state.clone(), ids[0]);
+
+        let dark = theme::init().adding(theme::LABEL_COLOR, Color::rgb8(1, 2, 3));
+        with_win_ctx(|win_ctx| {
+            for id in ids.iter() {
+                state
+                    .borrow_mut()
+                    .do_event(*id, Event::WindowConnected, win_ctx);
+            }
+            state
+                .borrow_mut()
+                .command_queue
+                .push_back((ids[0].into(), sys_cmd::SET_THEME.with(dark)));
+            handler.idle(RUN_COMMANDS_TOKEN, win_ctx);
+        });
+        // every window is recolored in the same cycle.
+        for (changes, colors) in logs {
+            assert_eq!(changes.get(), 1);
+            assert_eq!(*colors.borrow(), vec![Color::rgb8(1, 2, 3).as_rgba_u32()]);
+        }
+    }
+
+    #[test]
+    fn set_locale_resolves_strings_again() {
+        let state = test_state(false, None);
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let text = LocalizedString::new("hello-counter").with_arg("count", |_, _| 3.into());
+        // records the text of a localized string at each update.
+        let widget = filler((text, seen.clone())).update_fn(|(text, seen), _, _, data, env| {
+            text.resolve(data, env);
+            seen.borrow_mut().push(text.localized_str().to_string());
+        });
+        let mut handler = open_handler(&state, widget);
+        let id = handler.window_id;
+
+        with_win_ctx(|win_ctx| {
+            state
+                .borrow_mut()
+                .do_event(id, Event::WindowConnected, win_ctx);
+            state
+                .borrow_mut()
+                .command_queue
+                .push_back((id.into(), sys_cmd::SET_LOCALE.with("fr-CA".into())));
+            handler.idle(RUN_COMMANDS_TOKEN, win_ctx);
+        });
+        assert_eq!(
+            seen.borrow().last().map(String::as_str),
+            Some("La valeur actuelle est 3")
+        );
+    }
+
+    #[test]
+    fn mouse_moves_are_coalesced() {
+        let state = test_state(false, None);
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let updates = Rc::new(Cell::new(0));
+        // logs the mouse events it gets, and changes the data on each move.
+        let widget = update_counter::<bool>(updates.clone()).event_fn({
+            let events = events.clone();
+            move |_, _, event, data, _| {
+                let entry = match event {
+                    Event::MouseMoved(e) => {
+                        *data = !*data;
+                        format!("move {}", e.pos.x)
+                    }
+                    Event::MouseDown(e) => format!("down {}", e.pos.x),
+                    Event::MouseUp(e) => format!("up {}", e.pos.x),
+                    Event::MouseLeave => "leave".to_string(),
+                    _ => return,
+                };
+                events.borrow_mut().push(entry);
+            }
+        });
+        let mut handler = open_handler(&state, widget);
+
+        let mouse = |x| left_shell_mouse(x, 10., 0);
+        let take_events = || events.borrow_mut().drain(..).collect::<Vec<_>>();
+        with_piet(|piet| {
+            with_win_ctx(|win_ctx| {
+                handler.size(100, 100, win_ctx);
+                handler.paint(piet, WHOLE_WINDOW, win_ctx);
+            })
+        });
+        updates.set(0);
+
+        with_win_ctx(|win_ctx| {
+            // a fast drag: only the last move is handled, on idle.
+            handler.mouse_down(&mouse(0.), win_ctx);
+            for x in 1..=50 {
+                handler.mouse_move(&mouse(x as f64), win_ctx);
+            }
+            assert_eq!(take_events(), vec!["down 0"]);
+            handler.idle(MOUSE_MOVE_TOKEN, win_ctx);
+            assert_eq!(take_events(), vec!["move 50"]);
+            assert_eq!(updates.get(), 1);
+            // nothing is pending any more.
+            handler.idle(MOUSE_MOVE_TOKEN, win_ctx);
+            assert!(take_events().is_empty());
+
+            // other events are never reordered with the moves before them.
+            handler.mouse_move(&mouse(60.), win_ctx);
+            handler.mouse_move(&mouse(61.), win_ctx);
+            handler.mouse_up(&mouse(61.), win_ctx);
+            handler.mouse_move(&mouse(70.), win_ctx);
+            handler.mouse_leave(win_ctx);
+            assert_eq!(take_events(), vec!["move 61", "up 61", "move 70", "leave"]);
+            assert_eq!(updates.get(), 3);
+        });
+    }
+
+    #[test]
+    fn pending_move_is_handled_before_commands() {
+        const PING: Selector = Selector::new("druid-test.ping");
+        const QUERY: Selector = Selector::new("druid-test.query");
+        let delegate =
+            ModularDelegate::new(()).system_will_sleep_fn(|_, data: &mut bool, _, _| *data = true);
+        let state = test_state(false, Some(Box::new(delegate)));
+        let interval = Duration::from_millis(10);
+        state.borrow_mut().debouncer.add(QUERY.symbol(), interval);
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let widget = filler(events.clone())
+            .event_fn(|events, _, event, _, _| match event {
+                Event::MouseMoved(e) => events.borrow_mut().push(format!("move {}", e.pos.x)),
+                Event::Command(cmd) => events.borrow_mut().push(cmd.symbol().to_string()),
+                _ => (),
+            })
+            .update_fn(|events, _, _, data, _| {
+                if *data {
+                    events.borrow_mut().push("sleep".to_string());
+                }
+            });
+        let mut handler = open_handler(&state, widget);
+        let id = handler.window_id;
+        let mouse = |x| left_shell_mouse(x, 10., 0);
+        let take_events = || events.borrow_mut().drain(..).collect::<Vec<_>>();
+        with_piet(|piet| {
+            with_win_ctx(|win_ctx| {
+                handler.size(100, 100, win_ctx);
+                handler.paint(piet, WHOLE_WINDOW, win_ctx);
+            })
+        });
+
+        with_mock_win_ctx(|win_ctx| {
+            state
+                .borrow_mut()
+                .command_queue
+                .push_back((id.into(), Command::from(QUERY)));
+            handler.idle(RUN_COMMANDS_TOKEN, win_ctx);
+            take_events();
+
+            // a command released by a timer...
+            handler.mouse_move(&mouse(5.), win_ctx);
+            let (_, token) = win_ctx.timers().pop().unwrap();
+            handler.timer(token, win_ctx);
+            assert_eq!(take_events(), vec!["move 5", QUERY.symbol()]);
+
+            // ...one run on idle...
+            handler.mouse_move(&mouse(6.), win_ctx);
+            state
+                .borrow_mut()
+                .command_queue
+                .push_back((id.into(), Command::from(PING)));
+            handler.idle(RUN_COMMANDS_TOKEN, win_ctx);
+            assert_eq!(take_events(), vec!["move 6", PING.symbol()]);
+
+            // ...and the update after a power event all see the latest move.
+            handler.mouse_move(&mouse(7.), win_ctx);
+            handler.power_event(PowerEvent::WillSleep, win_ctx);
+            assert_eq!(take_events(), vec!["move 7", "sleep"]);
+        });
     }
 }
@@ -15,19 +15,40 @@
 //! Management of multiple windows.
 
 use std::mem;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use crate::kurbo::{Insets, Point, Rect, Size};
-use crate::piet::{Piet, RenderContext};
-use crate::shell::{Counter, Cursor, WinCtx, WindowHandle};
+use crate::piet::{FontBuilder, Piet, RenderContext, Text, TextLayout, TextLayoutBuilder};
+use crate::shell::{Counter, Cursor, WheelPhase, WinCtx, WindowHandle};
 
+use crate::command::{sys as sys_cmd, DataType};
 use crate::core::{BaseState, CommandQueue, FocusChange};
+use crate::event::{BoundsCollector, InternalLifeCycle, WidgetBounds};
+use crate::gesture::{GestureRecognizer, GestureSet};
+use crate::interval::IntervalTimers;
+use crate::overlay::OverlayLayer;
+use crate::theme;
 use crate::win_handler::RUN_COMMANDS_TOKEN;
 use crate::{
-    BoxConstraints, Command, Data, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
-    LocalizedString, MenuDesc, PaintCtx, UpdateCtx, Widget, WidgetId, WidgetPod,
+    AccessibilityCollector, BoxConstraints, Color, Command, Data, Env, Event, EventCtx, KeyCode,
+    KeyEvent, LayoutCtx, LifeCycle, LifeCycleCtx, LocalizedString, MenuDesc, MenuItem, MouseEvent,
+    MouseRoute, PaintCtx, Target, TimerToken, UpdateCtx, Widget, WidgetId, WidgetPod,
 };
 
+/// A per-window strategy for routing mouse events.
+///
+/// This is called with each mouse event, in window coordinates, and the
+/// current size of the window.
+pub(crate) type MouseRouter = dyn FnMut(&MouseEvent, Size) -> MouseRoute;
+
+/// How long to wait for momentum after the fingers lift at the end of a
+/// scroll gesture, before the pointer is hit-tested again.
+const MOMENTUM_WAIT: Duration = Duration::from_millis(50);
+
+/// Sent through the tree after widgets were added, so that they receive
+/// `WidgetAdded`.
+const ROUTE_WIDGET_ADDED: LifeCycle = LifeCycle::Internal(InternalLifeCycle::RouteWidgetAdded);
+
 /// A unique identifier for a window.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct WindowId(u64);
@@ -37,6 +58,11 @@ pub(crate) struct PendingWindow<T: Data> {
     root: WidgetPod<T, Box<dyn Widget<T>>>,
     title: LocalizedString<T>,
     menu: Option<MenuDesc<T>>,
+    pub(crate) mouse_router: Option<Box<MouseRouter>>,
+    pub(crate) gestures: GestureSet,
+    pub(crate) hide_on_close: bool,
+    pub(crate) transparent: bool,
+    pub(crate) background: Option<Color>,
 }
 
 /// Per-window state not owned by user code.
@@ -44,12 +70,51 @@ pub struct Window<T: Data> {
     pub(crate) id: WindowId,
     pub(crate) root: WidgetPod<T, Box<dyn Widget<T>>>,
     pub(crate) title: LocalizedString<T>,
+    /// A title set with `SET_WINDOW_TITLE`, which replaces `title`.
+    pub(crate) fixed_title: Option<String>,
     size: Size,
+    /// The scale factor, where `1.0` is 96 dpi.
+    scale: f64,
+    /// The last position reported by the platform.
+    position: Option<Point>,
+    /// The cursor we last set, or `None` if the pointer has left the window
+    /// since, and the platform may have changed it.
+    cursor: Option<Cursor>,
     pub(crate) menu: Option<MenuDesc<T>>,
     pub(crate) context_menu: Option<MenuDesc<T>>,
     pub(crate) last_anim: Option<Instant>,
     pub(crate) focus: Option<WidgetId>,
     pub(crate) handle: WindowHandle,
+    pub(crate) mouse_router: Option<Box<MouseRouter>>,
+    pub(crate) interval_timers: IntervalTimers,
+    pub(crate) gestures: GestureRecognizer,
+    /// Whether closing the window hides it, instead of destroying it.
+    pub(crate) hide_on_close: bool,
+    /// Whether the window is hidden; it is still updated, but not painted.
+    pub(crate) hidden: bool,
+    /// The latest mouse move, if it has not been handled yet.
+    ///
+    /// Moves can come much faster than we can handle them, so only the
+    /// latest is kept, and handled on idle; any other event handles it
+    /// first, so that it is never handled out of order. It lives here,
+    /// rather than with the window's handler, so that the handler of
+    /// another window can flush it too.
+    pub(crate) pending_move: Option<MouseEvent>,
+    /// Whether the window has no background of its own.
+    transparent: bool,
+    /// The background color, if not the one in the `Env`.
+    background: Option<Color>,
+    /// Whether the inspector overlay has been toggled away from what
+    /// `Env::DEBUG_INSPECT` asks for.
+    inspector_toggled: bool,
+    /// The last mouse move; `None` if the pointer has left the window.
+    last_mouse: Option<MouseEvent>,
+    /// The timer started when the fingers lifted at the end of a scroll
+    /// gesture, and whether to hit-test when it fires; that is called off
+    /// if momentum, or another gesture, begins first.
+    lift_timer: Option<(TimerToken, bool)>,
+    /// The overlays shown above the root, bottom first.
+    overlays: Vec<OverlayLayer>,
     // delegate?
 }
 
@@ -63,21 +128,52 @@ impl<T: Data> PendingWindow<T> {
             root: WidgetPod::new(Box::new(root)),
             title,
             menu,
+            mouse_router: None,
+            gestures: GestureSet::default(),
+            hide_on_close: false,
+            transparent: false,
+            background: None,
         }
     }
 
     pub(crate) fn into_window(self, id: WindowId, handle: WindowHandle) -> Window<T> {
-        let PendingWindow { root, title, menu } = self;
+        let PendingWindow {
+            root,
+            title,
+            menu,
+            mouse_router,
+            gestures,
+            hide_on_close,
+            transparent,
+            background,
+        } = self;
+        let scale = f64::from(handle.get_dpi()) / 96.0;
         Window {
             id,
             root,
             size: Size::ZERO,
+            scale,
+            position: None,
+            cursor: None,
             title,
+            fixed_title: None,
             menu,
             context_menu: None,
             last_anim: None,
             focus: None,
             handle,
+            mouse_router,
+            interval_timers: IntervalTimers::default(),
+            gestures: GestureRecognizer::new(gestures),
+            hide_on_close,
+            hidden: false,
+            pending_move: None,
+            transparent,
+            background,
+            inspector_toggled: false,
+            last_mouse: None,
+            lift_timer: None,
+            overlays: Vec::new(),
         }
     }
 }
@@ -88,6 +184,25 @@ impl<T: Data> Window<T> {
         self.last_anim.is_some()
     }
 
+    /// The widgets that have requested an animation frame since the last one.
+    pub(crate) fn animating_widgets(&self) -> &[WidgetId] {
+        &self.root.state().anim_requests
+    }
+
+    /// Cancel the animation frame requests of this widget, or of every widget.
+    pub(crate) fn cancel_animation(
+        &mut self,
+        widget: Option<WidgetId>,
+        queue: &mut CommandQueue,
+        data: &T,
+        env: &Env,
+    ) {
+        self.lifecycle(queue, &LifeCycle::CancelAnimation(widget), data, env);
+        if self.animating_widgets().is_empty() {
+            self.last_anim = None;
+        }
+    }
+
     pub(crate) fn focus_chain(&self) -> &[WidgetId] {
         &self.root.state().focus_chain
     }
@@ -119,6 +234,7 @@ impl<T: Data> Window<T> {
         }
     }
 
+    /// Handle an event, at `now` on the app's clock.
     pub(crate) fn event(
         &mut self,
         win_ctx: &mut dyn WinCtx,
@@ -126,6 +242,312 @@ impl<T: Data> Window<T> {
         event: Event,
         data: &mut T,
         env: &Env,
+        now: Instant,
+    ) -> bool {
+        let gesture = self.gestures.event(&event, now, win_ctx);
+
+        match &event {
+            Event::MouseMoved(mouse) => self.set_last_mouse(Some(mouse.clone()), env),
+            Event::MouseLeave => self.set_last_mouse(None, env),
+            _ => (),
+        }
+        if self.overlay_event(win_ctx, queue, &event, data, env, now) {
+            self.interval_timers.arm(win_ctx);
+            return true;
+        }
+        // timers and commands may be for the widgets of an overlay.
+        let layer_event = match &event {
+            Event::Timer(_) => Some(event.clone()),
+            Event::TargetedCommand(Target::Window(_), _) => None,
+            Event::TargetedCommand(..) => Some(event.clone()),
+            _ => None,
+        };
+        // Wheel and zoom events go to the hot widgets, those under the pointer
+        // as of the last mouse move. Once one has scrolled or zoomed, other
+        // content may be under the pointer, so hit-test it again, as if the
+        // mouse had moved, for the next event to go to the right widget. The
+        // events of a trackpad gesture, and its momentum, all go to the widget
+        // that was under the pointer when the gesture began. A gesture without
+        // momentum ends when the fingers lift; as we can't know yet whether
+        // momentum will follow, the hit-test waits for a moment.
+        if let Event::Wheel(wheel) = &event {
+            if wheel.phase == WheelPhase::Ended {
+                let token = win_ctx.request_timer(now + MOMENTUM_WAIT);
+                self.lift_timer = Some((token, true));
+            } else if let Some((_, rehit)) = self.lift_timer.as_mut() {
+                *rehit = false;
+            }
+        }
+        let (starts_gesture, ends_gesture, moves_content) = match &event {
+            Event::Wheel(wheel) => (
+                wheel.phase == WheelPhase::Began,
+                wheel.phase == WheelPhase::MomentumEnded,
+                wheel.phase == WheelPhase::None,
+            ),
+            Event::Zoom(_) => (false, false, true),
+            _ => (false, false, false),
+        };
+
+        // The routing strategy gets the first look at mouse events, before
+        // the tree walk does any hit-testing; but while a widget is active it
+        // has captured the mouse, and the tree walk delivers to it.
+        let captured = self.root.has_active();
+        if starts_gesture && !captured {
+            self.rehit(win_ctx, queue, data, env, now);
+        }
+        let route = match (self.mouse_router.as_mut(), &event) {
+            _ if captured => MouseRoute::Tree,
+            (Some(router), Event::MouseDown(mouse))
+            | (Some(router), Event::MouseUp(mouse))
+            | (Some(router), Event::MouseMoved(mouse)) => router(mouse, self.size),
+            _ => MouseRoute::Tree,
+        };
+
+        let is_handled = match route {
+            MouseRoute::Tree => self.dispatch_event(win_ctx, queue, event, data, env, now),
+            MouseRoute::Drop => false,
+            MouseRoute::Widgets(ids) => ids.into_iter().any(|id| {
+                let routed = Event::Routed(id, Box::new(event.clone()));
+                self.dispatch_event(win_ctx, queue, routed, data, env, now)
+            }),
+        };
+
+        let is_handled = match gesture {
+            Some(gesture) => {
+                let event = Event::Gesture(gesture);
+                self.dispatch_event(win_ctx, queue, event, data, env, now) || is_handled
+            }
+            None => is_handled,
+        };
+
+        if (ends_gesture || moves_content && is_handled) && !self.root.has_active() {
+            self.rehit(win_ctx, queue, data, env, now);
+        }
+
+        let mut is_handled = is_handled;
+        if let Some(event) = layer_event {
+            for i in 0..self.overlays.len() {
+                is_handled |= self.layer_event(i, win_ctx, queue, &event, env, now);
+            }
+        }
+
+        // schedule any interval timers requested while handling this event,
+        // as well as the next tick of one that just fired.
+        self.interval_timers.arm(win_ctx);
+        is_handled
+    }
+
+    /// Hit-test the pointer again, by dispatching its last move.
+    fn rehit(
+        &mut self,
+        win_ctx: &mut dyn WinCtx,
+        queue: &mut CommandQueue,
+        data: &mut T,
+        env: &Env,
+        now: Instant,
+    ) {
+        if let Some(mouse) = self.last_mouse.clone() {
+            self.dispatch_event(win_ctx, queue, Event::MouseMoved(mouse), data, env, now);
+        }
+    }
+
+    /// Give the overlays the first look at an event.
+    ///
+    /// Returns `true` if the event was for the overlays, and should not be
+    /// dispatched to the root.
+    fn overlay_event(
+        &mut self,
+        win_ctx: &mut dyn WinCtx,
+        queue: &mut CommandQueue,
+        event: &Event,
+        data: &mut T,
+        env: &Env,
+        now: Instant,
+    ) -> bool {
+        // while a widget of the root has captured the mouse, it keeps it.
+        if self.overlays.is_empty() || self.root.has_active() {
+            return false;
+        }
+        let top = self.overlays.len() - 1;
+        match event {
+            Event::MouseMoved(mouse) => {
+                let captured = self.overlays.iter().any(|layer| layer.pod.has_active());
+                let over = captured || self.overlays.iter().any(|layer| layer.contains(mouse.pos));
+                if over && self.root.state().is_hot {
+                    // the content under the overlays is no longer hot.
+                    self.dispatch_event(win_ctx, queue, Event::MouseLeave, data, env, now);
+                }
+                // every layer gets moves, to keep track of what is hot.
+                for i in 0..self.overlays.len() {
+                    self.layer_event(i, win_ctx, queue, event, env, now);
+                }
+                over
+            }
+            Event::MouseLeave => {
+                for i in 0..self.overlays.len() {
+                    self.layer_event(i, win_ctx, queue, event, env, now);
+                }
+                false
+            }
+            Event::MouseDown(mouse) | Event::MouseUp(mouse) => {
+                let target = self
+                    .overlays
+                    .iter()
+                    .rposition(|layer| layer.pod.has_active() || layer.contains(mouse.pos));
+                let is_down = matches!(event, Event::MouseDown(_));
+                match target {
+                    Some(i) => {
+                        // a click in a lower layer dismisses those above it.
+                        if is_down {
+                            self.dismiss_overlays(i + 1, queue);
+                        }
+                        self.layer_event(i, win_ctx, queue, event, env, now);
+                        true
+                    }
+                    // a click outside dismisses every overlay, and goes no
+                    // further.
+                    None if is_down => {
+                        self.dismiss_overlays(0, queue);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            Event::Wheel(_) => {
+                let pos = self.last_mouse.as_ref().map(|mouse| mouse.pos);
+                let target =
+                    pos.and_then(|pos| self.overlays.iter().rposition(|layer| layer.contains(pos)));
+                match target {
+                    Some(i) => {
+                        self.layer_event(i, win_ctx, queue, event, env, now);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            // keys go to the top overlay; those it doesn't handle go on to
+            // the focused widget, except for the `Escape` that dismisses it.
+            Event::KeyDown(key) => {
+                let is_handled = self.layer_event(top, win_ctx, queue, event, env, now);
+                if !is_handled && key.key_code == KeyCode::Escape {
+                    self.dismiss_overlays(top, queue);
+                    return true;
+                }
+                is_handled
+            }
+            Event::KeyUp(_) => self.layer_event(top, win_ctx, queue, event, env, now),
+            _ => false,
+        }
+    }
+
+    /// Dispatch an event to the overlay at `index`.
+    fn layer_event(
+        &mut self,
+        index: usize,
+        win_ctx: &mut dyn WinCtx,
+        queue: &mut CommandQueue,
+        event: &Event,
+        env: &Env,
+        now: Instant,
+    ) -> bool {
+        let layer = &mut self.overlays[index];
+        let mut cursor = match event {
+            Event::MouseMoved(mouse) if layer.contains(mouse.pos) => Some(Cursor::Arrow),
+            _ => None,
+        };
+        let mut base_state = BaseState::new(layer.pod.id());
+        let is_handled = {
+            let mut ctx = EventCtx {
+                win_ctx,
+                data_type: DataType::of::<T>(),
+                cursor: &mut cursor,
+                command_queue: queue,
+                interval_timers: &mut self.interval_timers,
+                base_state: &mut base_state,
+                is_handled: false,
+                is_root: true,
+                had_active: layer.pod.has_active(),
+                window: &self.handle,
+                window_id: self.id,
+                focus_widget: Some(layer.pod.id()),
+                window_origin: Point::ORIGIN,
+                now,
+            };
+            layer.pod.event(&mut ctx, event, &mut (), env);
+            ctx.is_handled
+        };
+        if base_state.children_changed {
+            layer.lifecycle(queue, self.id, &ROUTE_WIDGET_ADDED, env);
+        }
+        self.set_cursor(win_ctx, cursor);
+        is_handled
+    }
+
+    fn show_overlay(&mut self, layer: OverlayLayer, queue: &mut CommandQueue, env: &Env) {
+        // a widget has at most one overlay.
+        self.overlays.retain(|other| other.owner != layer.owner);
+        self.overlays.push(layer);
+        let layer = self.overlays.last_mut().unwrap();
+        layer.lifecycle(queue, self.id, &ROUTE_WIDGET_ADDED, env);
+        self.handle.invalidate();
+    }
+
+    /// Remove the overlays from `index` up, and tell their owners.
+    fn dismiss_overlays(&mut self, index: usize, queue: &mut CommandQueue) {
+        if index >= self.overlays.len() {
+            return;
+        }
+        for layer in self.overlays.drain(index..) {
+            queue.push_back((layer.owner.into(), sys_cmd::OVERLAY_DISMISSED.into()));
+        }
+        self.handle.invalidate();
+    }
+
+    /// The cursor last set through this window's `WinCtx`.
+    #[cfg(test)]
+    pub(crate) fn cursor(&self) -> Option<&Cursor> {
+        self.cursor.as_ref()
+    }
+
+    fn set_cursor(&mut self, win_ctx: &mut dyn WinCtx, cursor: Option<Cursor>) {
+        if let Some(cursor) = cursor {
+            if self.cursor.as_ref() != Some(&cursor) {
+                win_ctx.set_cursor(&cursor);
+                self.cursor = Some(cursor);
+            }
+        }
+    }
+
+    /// The event to dispatch when a timer from the platform fires.
+    ///
+    /// Ticks of interval timers are translated to the token of their interval;
+    /// this returns `None` for a cancelled timer, or the stale tick of a
+    /// cancelled interval. The timers of the gesture recognizer become
+    /// `Gesture` events, if they complete a gesture, and the end of a scroll
+    /// gesture without momentum becomes a move of the mouse where it is.
+    pub(crate) fn timer_event(&mut self, token: TimerToken, now: Instant) -> Option<Event> {
+        if self.lift_timer.map(|(t, _)| t) == Some(token) {
+            match self.lift_timer.take() {
+                Some((_, true)) => self.last_mouse.clone().map(Event::MouseMoved),
+                _ => None,
+            }
+        } else if self.gestures.owns(token) {
+            self.gestures.timer(token).map(Event::Gesture)
+        } else if self.interval_timers.owns(token) {
+            self.interval_timers.fire(token, now).map(Event::Timer)
+        } else {
+            Some(Event::Timer(token))
+        }
+    }
+
+    fn dispatch_event(
+        &mut self,
+        win_ctx: &mut dyn WinCtx,
+        queue: &mut CommandQueue,
+        event: Event,
+        data: &mut T,
+        env: &Env,
+        now: Instant,
     ) -> bool {
         let mut cursor = match event {
             Event::MouseMoved(..) => Some(Cursor::Arrow),
@@ -134,24 +556,82 @@ impl<T: Data> Window<T> {
 
         let event = match event {
             Event::Size(size) => {
-                let dpi = f64::from(self.handle.get_dpi());
-                let scale = 96.0 / dpi;
-                self.size = Size::new(size.width * scale, size.height * scale);
+                self.size = Size::new(size.width / self.scale, size.height / self.scale);
                 Event::Size(self.size)
             }
+            Event::ScaleChanged(scale) if scale == self.scale => return false,
+            Event::ScaleChanged(scale) => {
+                // the physical size is unchanged until the platform says so.
+                self.size *= self.scale / scale;
+                self.scale = scale;
+                self.handle.invalidate();
+                Event::ScaleChanged(scale)
+            }
+            // some platforms report the position on every resize.
+            Event::WindowMoved(pos) if self.position == Some(pos) => return false,
+            Event::WindowMoved(pos) => {
+                self.position = Some(pos);
+                Event::WindowMoved(pos)
+            }
+            Event::MouseLeave => {
+                self.cursor = None;
+                Event::MouseLeave
+            }
             other => other,
         };
 
         if let Event::WindowConnected = event {
-            self.lifecycle(queue, &LifeCycle::WidgetAdded, data, env);
+            self.lifecycle(queue, &ROUTE_WIDGET_ADDED, data, env);
+        }
+
+        if let Event::TargetedCommand(Target::Window(_), cmd) = &event {
+            if cmd.is(sys_cmd::COLLECT_WIDGET_BOUNDS) {
+                self.collect_widget_bounds(cmd, queue, data, env);
+                return true;
+            }
+            if cmd.is(sys_cmd::TOGGLE_INSPECTOR) {
+                self.inspector_toggled = !self.inspector_toggled;
+                self.handle.invalidate();
+                return true;
+            }
+            if cmd.is(sys_cmd::COLLECT_ACCESSIBILITY_TREE) {
+                self.collect_accessibility_tree(cmd, queue, data, env);
+                return true;
+            }
+            if let Some(overlay) = cmd.take(sys_cmd::SHOW_OVERLAY) {
+                self.show_overlay(OverlayLayer::new(overlay), queue, env);
+                return true;
+            }
+            if let Some(owner) = cmd.get(sys_cmd::DISMISS_OVERLAY) {
+                let index = self.overlays.iter().position(|layer| layer.owner == *owner);
+                if let Some(index) = index {
+                    self.dismiss_overlays(index, queue);
+                }
+                return true;
+            }
+            if let Some(&id) = cmd.get(sys_cmd::FOCUS_WIDGET) {
+                if id == self.root.id() || self.root.state().children.contains(&id) {
+                    self.set_focus(Some(id), queue, data, env);
+                } else {
+                    log::warn!("FOCUS_WIDGET: {:?} is not in window {:?}", id, self.id);
+                }
+                return true;
+            }
+            if cmd.is(sys_cmd::CANCEL_ANIMATION) {
+                let widget = cmd.get(sys_cmd::CANCEL_ANIMATION).copied().flatten();
+                self.cancel_animation(widget, queue, data, env);
+                return true;
+            }
         }
 
         let mut base_state = BaseState::new(self.root.id());
         let is_handled = {
             let mut ctx = EventCtx {
                 win_ctx,
+                data_type: DataType::of::<T>(),
                 cursor: &mut cursor,
                 command_queue: queue,
+                interval_timers: &mut self.interval_timers,
                 base_state: &mut base_state,
                 is_handled: false,
                 is_root: true,
@@ -159,33 +639,111 @@ impl<T: Data> Window<T> {
                 window: &self.handle,
                 window_id: self.id,
                 focus_widget: self.focus,
+                window_origin: Point::ORIGIN,
+                now,
             };
 
             self.root.event(&mut ctx, &event, data, env);
             ctx.is_handled
         };
 
+        // Tab and shift-tab move focus, unless a widget used them, or there
+        // is nothing to move it to.
+        let is_handled = match &event {
+            Event::KeyDown(key) if !is_handled && base_state.request_focus.is_none() => {
+                match focus_traversal(key) {
+                    Some(change) if !self.focus_chain().is_empty() => {
+                        base_state.request_focus = Some(change);
+                        true
+                    }
+                    _ => false,
+                }
+            }
+            _ => is_handled,
+        };
+
         if let Some(focus_req) = base_state.request_focus.take() {
-            let old = self.focus;
             let new = self.widget_for_focus_request(focus_req);
-            let event = LifeCycle::RouteFocusChanged { old, new };
-            self.lifecycle(queue, &event, data, env);
-            self.focus = new;
+            self.set_focus(new, queue, data, env);
         }
 
-        if let Some(cursor) = cursor {
-            win_ctx.set_cursor(&cursor);
-        }
+        self.set_cursor(win_ctx, cursor);
 
         // If children are changed during the handling of an event,
         // we need to send WidgetAdded now, so that they are ready for update/layout.
         if base_state.children_changed {
-            self.lifecycle(queue, &LifeCycle::WidgetAdded, data, env);
+            self.lifecycle(queue, &ROUTE_WIDGET_ADDED, data, env);
         }
 
         is_handled
     }
 
+    /// Move focus to `new`, telling the widgets that gain and lose it.
+    fn set_focus(
+        &mut self,
+        new: Option<WidgetId>,
+        queue: &mut CommandQueue,
+        data: &mut T,
+        env: &Env,
+    ) {
+        let old = self.focus;
+        let event = LifeCycle::RouteFocusChanged { old, new };
+        self.lifecycle(queue, &event, data, env);
+        self.focus = new;
+    }
+
+    /// Walk the tree, gathering the bounds of every widget, and reply with a
+    /// `WIDGET_BOUNDS` command.
+    fn collect_widget_bounds(
+        &mut self,
+        cmd: &Command,
+        queue: &mut CommandQueue,
+        data: &T,
+        env: &Env,
+    ) {
+        let collector = BoundsCollector::default();
+        self.lifecycle(
+            queue,
+            &LifeCycle::CollectBounds(collector.clone()),
+            data,
+            env,
+        );
+        let reply_to = cmd
+            .get(sys_cmd::COLLECT_WIDGET_BOUNDS)
+            .copied()
+            .flatten()
+            .unwrap_or_else(|| self.id.into());
+        let reply = Command::new(sys_cmd::WIDGET_BOUNDS, collector.bounds());
+        queue.push_back((reply_to, reply));
+    }
+
+    /// Walk the tree, describing every widget, and reply with an
+    /// `ACCESSIBILITY_TREE` command.
+    fn collect_accessibility_tree(
+        &mut self,
+        cmd: &Command,
+        queue: &mut CommandQueue,
+        data: &T,
+        env: &Env,
+    ) {
+        let collector = AccessibilityCollector::new(self.focus);
+        self.lifecycle(
+            queue,
+            &LifeCycle::CollectAccessibility(collector.clone()),
+            data,
+            env,
+        );
+        let reply_to = cmd
+            .get(sys_cmd::COLLECT_ACCESSIBILITY_TREE)
+            .copied()
+            .flatten()
+            .unwrap_or_else(|| self.id.into());
+        if let Some(tree) = collector.tree() {
+            let reply = Command::new(sys_cmd::ACCESSIBILITY_TREE, tree);
+            queue.push_back((reply_to, reply));
+        }
+    }
+
     pub(crate) fn lifecycle(
         &mut self,
         queue: &mut CommandQueue,
@@ -198,6 +756,7 @@ impl<T: Data> Window<T> {
             command_queue: queue,
             window_id: self.id,
             base_state: &mut base_state,
+            window_origin: Point::ORIGIN,
         };
 
         if let LifeCycle::AnimFrame(_) = event {
@@ -205,6 +764,9 @@ impl<T: Data> Window<T> {
         }
 
         self.root.lifecycle(&mut ctx, event, data, env);
+        for layer in &mut self.overlays {
+            layer.pod.lifecycle(&mut ctx, event, &(), env);
+        }
     }
 
     /// AnimFrame has special logic, so we implement it separately.
@@ -219,13 +781,28 @@ impl<T: Data> Window<T> {
 
         let event = LifeCycle::AnimFrame(elapsed_ns);
         self.root.lifecycle(ctx, &event, data, env);
+        for layer in &mut self.overlays {
+            layer.pod.lifecycle(ctx, &event, &(), env);
+        }
         if ctx.base_state.request_anim {
             self.last_anim = Some(now);
         }
     }
 
+    /// The last position reported by the platform, if any.
+    pub(crate) fn position(&self) -> Option<Point> {
+        self.position
+    }
+
+    /// Returns `true` if the window must be updated even if the data has
+    /// not changed, such as after a change to its local state.
+    pub(crate) fn needs_update(&self) -> bool {
+        self.root.state().needs_update
+    }
+
     pub(crate) fn update(&mut self, win_ctx: &mut dyn WinCtx, data: &T, env: &Env) {
         self.update_title(data, env);
+        self.update_menu(data, env);
 
         let mut base_state = BaseState::new(self.root.id());
         let mut update_ctx = UpdateCtx {
@@ -233,6 +810,7 @@ impl<T: Data> Window<T> {
             base_state: &mut base_state,
             window: &self.handle,
             window_id: self.id,
+            window_origin: Point::ORIGIN,
         };
 
         self.root.update(&mut update_ctx, data, env);
@@ -244,27 +822,54 @@ impl<T: Data> Window<T> {
         data: &T,
         env: &Env,
     ) {
-        if self.root.state().children_changed {
-            self.lifecycle(queue, &LifeCycle::WidgetAdded, data, env);
+        if self.children_changed() {
+            self.lifecycle(queue, &ROUTE_WIDGET_ADDED, data, env);
         }
-        if self.root.state().needs_inval {
+        // overlays are small, and short-lived; any change repaints them whole.
+        let overlays_changed = self.overlays.iter().any(|layer| {
+            let state = layer.pod.state();
+            state.needs_inval || state.request_anim || !state.invalid.is_empty()
+        });
+        let state = self.root.state();
+        // a frame requested outside of an animation frame also needs a paint
+        // to get things going.
+        if state.needs_inval || state.request_anim || overlays_changed {
             self.handle.invalidate();
+        } else if !state.invalid.is_empty() {
+            self.handle.invalidate_rect(state.invalid.to_rect());
         }
     }
 
     /// Do all the stuff we do in response to a paint call from the system:
     /// layout, send an `AnimFrame` event, and then actually paint.
+    ///
+    /// Only the widgets that intersect `invalid_rect` are painted.
     pub(crate) fn do_paint(
         &mut self,
         piet: &mut Piet,
+        invalid_rect: Rect,
         queue: &mut CommandQueue,
         data: &T,
         env: &Env,
     ) {
         self.lifecycle(queue, &LifeCycle::AnimFrame(0), data, env);
         self.layout(piet, data, env);
-        piet.clear(env.get(crate::theme::WINDOW_BACKGROUND_COLOR));
-        self.paint(piet, data, env);
+        if self.children_changed() {
+            // children that were added during layout must be registered,
+            // and laid out, before they are painted.
+            self.lifecycle(queue, &ROUTE_WIDGET_ADDED, data, env);
+            self.layout(piet, data, env);
+        }
+        // what is behind a transparent window shows through wherever the
+        // widgets do not paint.
+        if !self.transparent {
+            let background = self.background.clone();
+            piet.clear(background.unwrap_or_else(|| env.get(theme::WINDOW_BACKGROUND_COLOR)));
+        }
+        self.paint(piet, invalid_rect, data, env);
+        if self.inspecting(env) {
+            self.paint_inspector(piet, queue, data, env);
+        }
 
         // If commands were submitted during anim frame, ask the handler
         // to call us back on idle so we can process them in a new event/update pass.
@@ -282,11 +887,25 @@ impl<T: Data> Window<T> {
             text_factory: piet.text(),
             window_id: self.id,
             paint_insets: Insets::ZERO,
+            scale: self.scale,
+            children_changed: false,
         };
         let bc = BoxConstraints::tight(self.size);
         let size = self.root.layout(&mut layout_ctx, &bc, data, env);
         self.root
             .set_layout_rect(Rect::from_origin_size(Point::ORIGIN, size));
+        for layer in &mut self.overlays {
+            layer.layout(&mut layout_ctx, self.size, env);
+        }
+    }
+
+    /// Whether widgets were added to the root, or to an overlay.
+    fn children_changed(&self) -> bool {
+        self.root.state().children_changed
+            || self
+                .overlays
+                .iter()
+                .any(|layer| layer.pod.state().children_changed)
     }
 
     /// only expose `layout` for testing; normally it is called as part of `do_paint`
@@ -295,7 +914,7 @@ impl<T: Data> Window<T> {
         self.layout(piet, data, env)
     }
 
-    fn paint(&mut self, piet: &mut Piet, data: &T, env: &Env) {
+    fn paint(&mut self, piet: &mut Piet, invalid_rect: Rect, data: &T, env: &Env) {
         let base_state = BaseState::new(self.root.id());
         let mut paint_ctx = PaintCtx {
             render_ctx: piet,
@@ -304,8 +923,9 @@ impl<T: Data> Window<T> {
             z_ops: Vec::new(),
             focus_widget: self.focus,
             region: Rect::ZERO.into(),
+            window_size: self.size,
         };
-        let visible = Rect::from_origin_size(Point::ZERO, self.size);
+        let visible = Rect::from_origin_size(Point::ZERO, self.size).intersect(invalid_rect);
         paint_ctx.with_child_ctx(visible, |ctx| self.root.paint(ctx, data, env));
 
         let mut z_ops = mem::take(&mut paint_ctx.z_ops);
@@ -326,44 +946,185 @@ impl<T: Data> Window<T> {
                 }
             });
         }
+
+        for layer in &mut self.overlays {
+            paint_ctx.with_child_ctx(visible, |ctx| layer.pod.paint_with_offset(ctx, &(), env));
+        }
+    }
+
+    /// Whether the inspector overlay is shown.
+    pub(crate) fn inspecting(&self, env: &Env) -> bool {
+        env.get(Env::DEBUG_INSPECT) != self.inspector_toggled
+    }
+
+    fn set_last_mouse(&mut self, mouse: Option<MouseEvent>, env: &Env) {
+        let pos = mouse.as_ref().map(|mouse| mouse.pos);
+        if self.last_mouse.as_ref().map(|mouse| mouse.pos) != pos && self.inspecting(env) {
+            // the label follows the mouse.
+            self.handle.invalidate();
+        }
+        self.last_mouse = mouse;
+    }
+
+    /// Outline every widget, and label the one under the mouse.
+    ///
+    /// This paints over the whole window, after the widgets have painted.
+    fn paint_inspector(&mut self, piet: &mut Piet, queue: &mut CommandQueue, data: &T, env: &Env) {
+        let collector = BoundsCollector::default();
+        self.lifecycle(
+            queue,
+            &LifeCycle::CollectBounds(collector.clone()),
+            data,
+            env,
+        );
+        let bounds = collector.bounds();
+        for widget in &bounds {
+            let color = env.get_debug_color(widget.id.to_raw());
+            piet.stroke(widget.rect, &color, 1.0);
+        }
+
+        let pos = match &self.last_mouse {
+            Some(mouse) => mouse.pos,
+            None => return,
+        };
+        let widget = match inspected_widget(&bounds, pos) {
+            Some(widget) => widget,
+            None => return,
+        };
+        let text = format!(
+            "{} #{} ({:.1} × {:.1})",
+            widget.type_name,
+            widget.id.to_raw(),
+            widget.rect.width(),
+            widget.rect.height()
+        );
+        let font_size = env.get(theme::TEXT_SIZE_NORMAL);
+        let font = match piet
+            .text()
+            .new_font_by_name(env.get(theme::FONT_NAME), font_size)
+            .build()
+        {
+            Ok(font) => font,
+            Err(e) => {
+                log::error!("inspector font failed: {:?}", e);
+                return;
+            }
+        };
+        let layout = match piet.text().new_text_layout(&font, &text).build() {
+            Ok(layout) => layout,
+            Err(e) => {
+                log::error!("inspector text layout failed: {:?}", e);
+                return;
+            }
+        };
+
+        let color = env.get_debug_color(widget.id.to_raw());
+        let label_size = Size::new(layout.width() + 8.0, font_size + 6.0);
+        // keep the label inside the window, below and to the right of the mouse
+        // if there is room.
+        let x = (pos.x + 12.0)
+            .min(self.size.width - label_size.width)
+            .max(0.0);
+        let y = (pos.y + 16.0)
+            .min(self.size.height - label_size.height)
+            .max(0.0);
+        let label = Rect::from_origin_size((x, y), label_size);
+        piet.fill(widget.rect, &color.clone().with_alpha(0.2));
+        piet.stroke(widget.rect, &color, 2.0);
+        piet.fill(label, &env.get(theme::BACKGROUND_DARK));
+        piet.stroke(label, &color, 1.0);
+        piet.draw_text(
+            &layout,
+            (x + 4.0, y + 3.0 + font_size * 0.8),
+            &env.get(theme::LABEL_COLOR),
+        );
+    }
+
+    /// Rebuild the window's menu if the state of any of its items depends
+    /// on the data, and has changed.
+    fn update_menu(&mut self, data: &T, env: &Env) {
+        if let Some(menu) = self.menu.as_mut() {
+            if menu.needs_rebuild(data, env) {
+                let platform_menu = menu.build_window_menu(data, env);
+                self.handle.set_menu(platform_menu);
+            }
+        }
     }
 
     pub(crate) fn update_title(&mut self, data: &T, env: &Env) {
+        if self.fixed_title.is_some() {
+            return;
+        }
         if self.title.resolve(data, env) {
             self.handle.set_title(self.title.localized_str());
         }
     }
 
-    pub(crate) fn get_menu_cmd(&self, cmd_id: u32) -> Option<Command> {
+    /// Replace the window's title.
+    ///
+    /// From now on the title is no longer resolved from the `WindowDesc`.
+    pub(crate) fn set_title(&mut self, title: &str) {
+        self.handle.set_title(title);
+        self.fixed_title = Some(title.to_owned());
+    }
+
+    pub(crate) fn get_menu_item(&self, cmd_id: u32) -> Option<&MenuItem<T>> {
         self.context_menu
             .as_ref()
-            .and_then(|m| m.command_for_id(cmd_id))
-            .or_else(|| self.menu.as_ref().and_then(|m| m.command_for_id(cmd_id)))
+            .and_then(|m| m.item_for_id(cmd_id))
+            .or_else(|| self.menu.as_ref().and_then(|m| m.item_for_id(cmd_id)))
     }
 
     fn widget_for_focus_request(&self, focus: FocusChange) -> Option<WidgetId> {
         match focus {
             FocusChange::Resign => None,
             FocusChange::Focus(id) => Some(id),
-            FocusChange::Next => self
-                .focus
-                .and_then(|id| self.focus_chain().iter().position(|i| i == &id))
-                .map(|idx| {
-                    let next_idx = (idx + 1) % self.focus_chain().len();
-                    self.focus_chain()[next_idx]
-                }),
-            FocusChange::Previous => self
-                .focus
-                .and_then(|id| self.focus_chain().iter().position(|i| i == &id))
-                .map(|idx| {
-                    let len = self.focus_chain().len();
-                    let prev_idx = (idx + len - 1) % len;
-                    self.focus_chain()[prev_idx]
-                }),
+            // with nothing focused, traversal starts at either end.
+            FocusChange::Next => {
+                let chain = self.focus_chain();
+                match self
+                    .focus
+                    .and_then(|id| chain.iter().position(|i| i == &id))
+                {
+                    Some(idx) => Some(chain[(idx + 1) % chain.len()]),
+                    None => chain.first().copied(),
+                }
+            }
+            FocusChange::Previous => {
+                let chain = self.focus_chain();
+                let len = chain.len();
+                match self
+                    .focus
+                    .and_then(|id| chain.iter().position(|i| i == &id))
+                {
+                    Some(idx) => Some(chain[(idx + len - 1) % len]),
+                    None => chain.last().copied(),
+                }
+            }
         }
     }
 }
 
+/// The focus change for a key that moves focus, if this is one.
+fn focus_traversal(key: &KeyEvent) -> Option<FocusChange> {
+    if key.key_code != KeyCode::Tab || key.mods.ctrl || key.mods.alt || key.mods.meta {
+        None
+    } else if key.mods.shift {
+        Some(FocusChange::Previous)
+    } else {
+        Some(FocusChange::Next)
+    }
+}
+
+/// The widget the inspector describes at `pos`: the deepest widget whose
+/// bounds contain it, and of those, the last painted.
+fn inspected_widget(bounds: &[WidgetBounds], pos: Point) -> Option<&WidgetBounds> {
+    bounds
+        .iter()
+        .filter(|widget| widget.rect.contains(pos))
+        .max_by_key(|widget| widget.depth)
+}
+
 impl WindowId {
     /// Allocate a new, unique window id.
     pub fn next() -> WindowId {